@@ -0,0 +1,118 @@
+// Synthetic maps for the benchmarks, the same size always gives the same map so numbers from
+// different runs can be compared. Each benchmark only uses some of them
+#![allow(dead_code)]
+
+use bevy::math::{Vec2, Vec3};
+use mm_modchart_maker::{
+    maps::{
+        Map,
+        objects::{Hitsound, Note, NoteId, SpeedChange, TimingPoint},
+    },
+    modchart::{Easing, Keyframe, ModChannel, ModEvent, Modchart},
+};
+
+pub const NOTE_COUNTS: [usize; 3] = [1_000, 100_000, 1_000_000];
+
+// About 12 notes a second, a dense stream for a regular map
+const NOTE_SPACING_MS: u32 = 83;
+
+// Small xorshift generator, the benchmarks shouldn't depend on a random crate
+pub struct Fixture(u64);
+
+impl Fixture {
+    pub fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn range(&mut self, max: u64) -> u64 {
+        self.next() % max
+    }
+
+    // Mostly grid positions, with some quantum ones since they are written differently
+    fn position(&mut self) -> Vec2 {
+        match self.range(4) {
+            0 => Vec2::new(
+                self.range(2000) as f32 / 1000.0,
+                self.range(2000) as f32 / 1000.0,
+            ),
+            _ => Vec2::new(self.range(3) as f32, self.range(3) as f32),
+        }
+    }
+
+    pub fn map(&mut self, notes: usize) -> Map {
+        let notes: Vec<Note> = (0..notes)
+            .map(|index| Note {
+                id: NoteId(index as u64),
+                millisecond: index as u32 * NOTE_SPACING_MS,
+                position: self.position(),
+                hitsound: (self.range(8) == 0).then_some(Hitsound {
+                    sample: self.range(4) as u8,
+                    volume: 0.8,
+                }),
+                color: None,
+            })
+            .collect();
+        let length = notes.last().map_or(0, |note| note.millisecond + 1000);
+
+        // A timing or speed change every 30 seconds, like a map with a few tempo changes
+        let timing_points = (0..length)
+            .step_by(30_000)
+            .map(|millisecond| TimingPoint {
+                millisecond,
+                bpm: 120.0 + self.range(120) as f32,
+                beats_per_measure: 4,
+            })
+            .collect();
+        let speed_changes = (15_000..length)
+            .step_by(30_000)
+            .map(|millisecond| SpeedChange {
+                millisecond,
+                multiplier: 0.5 + self.range(150) as f32 / 100.0,
+            })
+            .collect();
+
+        Map {
+            id: format!("bench_{}", notes.len()),
+            length,
+            title: "Benchmark".to_string(),
+            artists: vec!["Fixture".to_string()],
+            difficulty: 3,
+            mappers: vec!["bench".to_string()],
+            notes,
+            timing_points,
+            speed_changes,
+            ..Map::default()
+        }
+    }
+
+    // Every channel keyed `keyframes` times over `length_ms`, with all the easings mixed in
+    pub fn modchart(&mut self, keyframes: usize, length_ms: u32) -> Modchart {
+        let spacing = (length_ms / keyframes.max(1) as u32).max(1);
+        let events = ModChannel::ALL
+            .iter()
+            .map(|channel| {
+                let keyframes = (0..keyframes)
+                    .map(|index| {
+                        let value = Vec3::new(
+                            self.range(100) as f32 / 10.0,
+                            self.range(100) as f32 / 10.0,
+                            self.range(100) as f32 / 10.0,
+                        );
+                        let easing = Easing::ALL[self.range(Easing::ALL.len() as u64) as usize];
+                        Keyframe::new(index as u32 * spacing, value).with_easing(easing)
+                    })
+                    .collect();
+                ModEvent::new(*channel, keyframes)
+            })
+            .collect();
+
+        Modchart::new(events)
+    }
+}
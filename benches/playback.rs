@@ -0,0 +1,88 @@
+mod fixtures;
+
+use std::hint::black_box;
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use mm_modchart_maker::{
+    modchart::{ScreenFrame, random::ModRandom},
+    player::notes::visible_range,
+};
+
+use fixtures::{Fixture, NOTE_COUNTS};
+
+// Playheads spread over the whole map, so lookups don't all hit the same cache lines
+const PLAYHEADS: usize = 256;
+
+// The approach window of the playfield, about a second
+const WINDOW_MS: f64 = 1000.0;
+
+fn playheads(length_ms: u32) -> Vec<f64> {
+    (0..PLAYHEADS)
+        .map(|index| index as f64 * length_ms as f64 / PLAYHEADS as f64)
+        .collect()
+}
+
+fn note_queries(c: &mut Criterion) {
+    let mut group = c.benchmark_group("notes/visible_range");
+
+    for notes in NOTE_COUNTS {
+        let map = Fixture::new(notes as u64).map(notes);
+        let scroll = map.scroll_timeline();
+        let playheads = playheads(map.length);
+
+        group.bench_with_input(BenchmarkId::from_parameter(notes), &map, |b, map| {
+            b.iter(|| {
+                for millisecond in playheads.iter() {
+                    black_box(visible_range(&map.notes, &scroll, *millisecond, WINDOW_MS));
+                }
+            })
+        });
+    }
+
+    group.finish();
+
+    let mut group = c.benchmark_group("notes/timing_point_at");
+    for notes in NOTE_COUNTS {
+        let map = Fixture::new(notes as u64).map(notes);
+        let playheads = playheads(map.length);
+
+        group.bench_with_input(BenchmarkId::from_parameter(notes), &map, |b, map| {
+            b.iter(|| {
+                for millisecond in playheads.iter() {
+                    black_box(map.timing_point_at(*millisecond as u32));
+                }
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn mod_evaluation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("modchart/evaluate");
+    let frame = ScreenFrame::default();
+    let random = ModRandom::default();
+
+    // Keyframes per channel, from a few moves per section up to something generated
+    for keyframes in [100, 10_000, 100_000] {
+        let modchart = Fixture::new(keyframes as u64).modchart(keyframes, 300_000);
+        let playheads = playheads(300_000);
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(keyframes),
+            &modchart,
+            |b, modchart| {
+                b.iter(|| {
+                    for millisecond in playheads.iter() {
+                        black_box(modchart.evaluate(*millisecond, &frame, &random));
+                    }
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, note_queries, mod_evaluation);
+criterion_main!(benches);
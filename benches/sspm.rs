@@ -0,0 +1,49 @@
+mod fixtures;
+
+use std::{hint::black_box, io::Cursor};
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use mm_modchart_maker::maps::parser::{MapSerializer, SSPMSerializer};
+
+use fixtures::{Fixture, NOTE_COUNTS};
+
+fn serialize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sspm/serialize");
+    group.sample_size(10);
+
+    for notes in NOTE_COUNTS {
+        let map = Fixture::new(notes as u64).map(notes);
+        group.throughput(Throughput::Elements(notes as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(notes), &map, |b, map| {
+            b.iter(|| {
+                let mut buffer = Cursor::new(Vec::new());
+                SSPMSerializer::serialize(black_box(map), &mut buffer).unwrap();
+                buffer
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn deserialize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sspm/deserialize");
+    group.sample_size(10);
+
+    for notes in NOTE_COUNTS {
+        let map = Fixture::new(notes as u64).map(notes);
+        let mut buffer = Cursor::new(Vec::new());
+        SSPMSerializer::serialize(&map, &mut buffer).unwrap();
+        let bytes = buffer.into_inner();
+
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(notes), &bytes, |b, bytes| {
+            b.iter(|| SSPMSerializer::deserialize(Cursor::new(black_box(&bytes[..]))).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, serialize, deserialize);
+criterion_main!(benches);
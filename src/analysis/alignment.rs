@@ -0,0 +1,172 @@
+// Checks whether notes line up with the audio by cross-correlating note times against the
+// onset envelope: the lag where notes land on the most onset energy is how far off they are
+
+use crate::maps::objects::Note;
+
+// About 3ms per frame at 44.1kHz, fine enough to tell a noticeable offset apart
+const HOP: usize = 128;
+
+// Offsets further than this are a different problem than a misaligned chart
+const MAX_OFFSET_MS: f64 = 250.0;
+
+const SECTION_MS: u32 = 15_000;
+
+// Energy is measured over windows this long, shorter ones are too noisy to find onsets in
+const WINDOW: usize = 1024;
+
+// Log energy of a window around every hop, rising by more than the window before it is an onset
+fn smooth_onsets(samples: &[f32]) -> Vec<f64> {
+    let mut prefix = Vec::with_capacity(samples.len() + 1);
+    prefix.push(0.0);
+    for sample in samples {
+        prefix.push(prefix.last().unwrap_or(&0.0) + (*sample as f64).powi(2));
+    }
+
+    let energy = |start: usize| {
+        let end = (start + WINDOW).min(samples.len());
+        let start = start.min(end);
+        let length = (end - start).max(1) as f64;
+        (1.0 + 1000.0 * (prefix[end] - prefix[start]) / length).ln()
+    };
+
+    (0..samples.len().div_ceil(HOP))
+        .map(|frame| {
+            let center = frame * HOP;
+            let after = energy(center);
+            let before = energy(center.saturating_sub(WINDOW));
+            (after - before).max(0.0)
+        })
+        .collect()
+}
+
+// Sections are only searched this far around the whole chart's offset, wider ranges let them
+// lock onto the beat before or after
+const SECTION_RANGE_MS: f64 = 60.0;
+
+// Sections with fewer notes don't give a reliable offset
+const MIN_SECTION_NOTES: usize = 16;
+
+// Sections off from the whole chart by more than this are reported
+const SECTION_THRESHOLD_MS: f64 = 12.0;
+
+// Below this the correlation peak barely stands out and the offset is a guess
+pub const MIN_CONFIDENCE: f64 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SectionAlignment {
+    pub start: u32,
+    pub end: u32,
+    pub notes: usize,
+    // Same meaning as the report's offset, for this section alone
+    pub offset_ms: f64,
+    pub confidence: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlignmentReport {
+    // Milliseconds to add to every note to line it up with the audio, positive when the notes
+    // come before the sounds they are placed on
+    pub offset_ms: f64,
+    // How much the best offset stands out from the others, 0..1
+    pub confidence: f64,
+    pub sections: Vec<SectionAlignment>,
+}
+
+impl AlignmentReport {
+    // Sections that are early or late compared to the rest of the chart
+    pub fn misaligned(&self) -> impl Iterator<Item = &SectionAlignment> {
+        self.sections.iter().filter(|section| {
+            section.confidence >= MIN_CONFIDENCE
+                && (section.offset_ms - self.offset_ms).abs() > SECTION_THRESHOLD_MS
+        })
+    }
+}
+
+// Onset energy summed over every note for each lag in frames from `center - range` to
+// `center + range`, the best lag with its confidence
+fn best_lag(envelope: &[f64], frames: &[usize], center: isize, range: isize) -> Option<(f64, f64)> {
+    let score = |lag: isize| -> f64 {
+        frames
+            .iter()
+            .filter_map(|&frame| envelope.get(frame.checked_add_signed(lag)?))
+            .sum()
+    };
+
+    let scores: Vec<f64> = (center - range..=center + range).map(score).collect();
+    let (index, best) = scores
+        .iter()
+        .copied()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+
+    if best <= 0.0 {
+        return None;
+    }
+
+    // Parabolic interpolation around the peak, frames are a few milliseconds long
+    let refined = if index > 0 && index + 1 < scores.len() {
+        let (left, right) = (scores[index - 1], scores[index + 1]);
+        let denominator = left - 2.0 * best + right;
+        match denominator.abs() > f64::EPSILON {
+            true => index as f64 + 0.5 * (left - right) / denominator,
+            false => index as f64,
+        }
+    } else {
+        index as f64
+    };
+
+    let average = scores.iter().sum::<f64>() / scores.len() as f64;
+    Some((
+        refined + (center - range) as f64,
+        (1.0 - average / best).clamp(0.0, 1.0),
+    ))
+}
+
+pub fn check_alignment(
+    samples: &[f32],
+    sample_rate: u32,
+    notes: &[Note],
+) -> Option<AlignmentReport> {
+    let envelope = smooth_onsets(samples);
+    let frames_per_ms = sample_rate as f64 / HOP as f64 / 1000.0;
+    let max_lag = (MAX_OFFSET_MS * frames_per_ms).ceil() as isize;
+
+    // Chords are one sound, counting every note in them would weigh them more
+    let mut times: Vec<u32> = notes.iter().map(|note| note.millisecond).collect();
+    times.dedup();
+
+    let frame = |millisecond: u32| (millisecond as f64 * frames_per_ms).round() as usize;
+    let frames: Vec<usize> = times.iter().map(|&ms| frame(ms)).collect();
+
+    let (lag, confidence) = best_lag(&envelope, &frames, 0, max_lag)?;
+    let section_range = (SECTION_RANGE_MS * frames_per_ms).ceil() as isize;
+
+    let mut sections = Vec::new();
+    let mut start = 0;
+    while start < times.len() {
+        let section_start = times[start];
+        let count = times[start..].partition_point(|&ms| ms < section_start + SECTION_MS);
+        let section = &frames[start..start + count];
+
+        if count >= MIN_SECTION_NOTES
+            && let Some((lag, confidence)) =
+                best_lag(&envelope, section, lag.round() as isize, section_range)
+        {
+            sections.push(SectionAlignment {
+                start: section_start,
+                end: times[start + count - 1],
+                notes: count,
+                offset_ms: lag / frames_per_ms,
+                confidence,
+            });
+        }
+
+        start += count;
+    }
+
+    Some(AlignmentReport {
+        offset_ms: lag / frames_per_ms,
+        confidence,
+        sections,
+    })
+}
@@ -0,0 +1,93 @@
+// Tempo estimation from an onset strength envelope: energy increases between short frames
+// are autocorrelated and the strongest period within the BPM range wins
+
+const HOP: usize = 512;
+const MIN_BPM: f64 = 70.0;
+const MAX_BPM: f64 = 200.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BpmEstimate {
+    pub bpm: f64,
+    // Millisecond of the first beat
+    pub offset_ms: f64,
+    // How much the winning period stands out from the average one, 0..1
+    pub confidence: f64,
+}
+
+pub fn onset_envelope(samples: &[f32]) -> Vec<f64> {
+    let energies: Vec<f64> = samples
+        .chunks(HOP)
+        .map(|frame| frame.iter().map(|s| (*s as f64).powi(2)).sum::<f64>() / frame.len() as f64)
+        .map(|energy| (1.0 + 1000.0 * energy).ln())
+        .collect();
+
+    let mut envelope = Vec::with_capacity(energies.len());
+    envelope.push(0.0);
+
+    for pair in energies.windows(2) {
+        envelope.push((pair[1] - pair[0]).max(0.0));
+    }
+
+    envelope
+}
+
+pub fn detect_bpm(samples: &[f32], sample_rate: u32) -> Option<BpmEstimate> {
+    let envelope = onset_envelope(samples);
+    let frames_per_second = sample_rate as f64 / HOP as f64;
+
+    let min_lag = (frames_per_second * 60.0 / MAX_BPM).floor() as usize;
+    let max_lag = (frames_per_second * 60.0 / MIN_BPM).ceil() as usize;
+
+    if min_lag == 0 || envelope.len() <= max_lag * 4 {
+        return None;
+    }
+
+    let correlation = |lag: usize| -> f64 {
+        envelope
+            .iter()
+            .zip(envelope[lag..].iter())
+            .map(|(a, b)| a * b)
+            .sum::<f64>()
+            / (envelope.len() - lag) as f64
+    };
+
+    let scores: Vec<(usize, f64)> = (min_lag..=max_lag)
+        .map(|lag| (lag, correlation(lag)))
+        .collect();
+    let average = scores.iter().map(|(_, s)| s).sum::<f64>() / scores.len() as f64;
+    let (best_lag, best_score) = scores.iter().copied().max_by(|a, b| a.1.total_cmp(&b.1))?;
+
+    if best_score <= 0.0 {
+        return None;
+    }
+
+    // Parabolic interpolation around the peak for sub-frame period precision
+    let index = best_lag - min_lag;
+    let period = if index > 0 && index + 1 < scores.len() {
+        let (left, right) = (scores[index - 1].1, scores[index + 1].1);
+        let denominator = left - 2.0 * best_score + right;
+        match denominator.abs() > f64::EPSILON {
+            true => best_lag as f64 + 0.5 * (left - right) / denominator,
+            false => best_lag as f64,
+        }
+    } else {
+        best_lag as f64
+    };
+
+    let bpm = frames_per_second * 60.0 / period;
+
+    // Phase is the offset inside one period where onsets line up best
+    let phase = (0..best_lag)
+        .max_by(|a, b| {
+            let strength =
+                |offset: usize| envelope.iter().skip(offset).step_by(best_lag).sum::<f64>();
+            strength(*a).total_cmp(&strength(*b))
+        })
+        .unwrap_or(0);
+
+    Some(BpmEstimate {
+        bpm,
+        offset_ms: phase as f64 / frames_per_second * 1000.0,
+        confidence: (1.0 - average / best_score).clamp(0.0, 1.0),
+    })
+}
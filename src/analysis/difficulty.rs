@@ -0,0 +1,91 @@
+use crate::maps::objects::Note;
+
+// Resolution of the curve, each value covers this much of the map
+pub const SAMPLE_MS: u32 = 250;
+
+// Notes closer together than this are treated as stacked rather than infinitely fast
+const MIN_GAP_MS: f32 = 20.0;
+
+// Time for built up strain to halve once notes stop
+const HALF_LIFE_MS: f32 = 500.0;
+
+// Even notes on the same cell take some effort to hit
+const STACK_DISTANCE: f32 = 0.5;
+
+// Rolling strain over the map, built from how far and how fast the cursor has to move between
+// consecutive notes. Values are only meaningful relative to each other, not across rulesets.
+#[derive(Debug, Clone, Default)]
+pub struct DifficultyCurve {
+    values: Vec<f32>,
+    peak: f32,
+}
+
+// Most notes within any one second, expects the notes sorted by time
+pub fn peak_notes_per_second(notes: &[Note]) -> usize {
+    let mut peak = 0;
+    let mut start = 0;
+    for (end, note) in notes.iter().enumerate() {
+        while notes[start].millisecond + 1000 <= note.millisecond {
+            start += 1;
+        }
+        peak = peak.max(end + 1 - start);
+    }
+    peak
+}
+
+impl DifficultyCurve {
+    // Expects the notes sorted by time, the curve covers at least `length_ms`
+    pub fn from_notes(notes: &[Note], length_ms: u32) -> Self {
+        let end = notes.last().map_or(0, |n| n.millisecond).max(length_ms);
+        let mut values = vec![0.0f32; (end / SAMPLE_MS + 1) as usize];
+
+        let decayed =
+            |strain: f32, elapsed_ms: f32| strain * 0.5f32.powf(elapsed_ms / HALF_LIFE_MS);
+
+        let mut strain = 0.0f32;
+        for pair in notes.windows(2) {
+            let (previous, note) = (&pair[0], &pair[1]);
+            let gap = ((note.millisecond - previous.millisecond) as f32).max(MIN_GAP_MS);
+            let distance = previous.position.distance(note.position) + STACK_DISTANCE;
+
+            // Samples without notes of their own still show the strain wearing off
+            for index in previous.millisecond / SAMPLE_MS + 1..note.millisecond / SAMPLE_MS {
+                let elapsed = (index * SAMPLE_MS - previous.millisecond) as f32;
+                values[index as usize] = decayed(strain, elapsed);
+            }
+
+            strain = decayed(strain, gap) + distance / gap * 1000.0;
+
+            let sample = &mut values[(note.millisecond / SAMPLE_MS) as usize];
+            *sample = sample.max(strain);
+        }
+
+        if let Some(last) = notes.last() {
+            for index in last.millisecond / SAMPLE_MS + 1..values.len() as u32 {
+                values[index as usize] =
+                    decayed(strain, (index * SAMPLE_MS - last.millisecond) as f32);
+            }
+        }
+
+        let peak = values.iter().copied().fold(0.0, f32::max);
+        Self { values, peak }
+    }
+
+    pub fn values(&self) -> &[f32] {
+        &self.values
+    }
+
+    pub fn peak(&self) -> f32 {
+        self.peak
+    }
+
+    // From 0 to 1 relative to the hardest part of the map
+    pub fn normalized_at(&self, millisecond: f64) -> f32 {
+        let index = (millisecond.max(0.0) as u32 / SAMPLE_MS) as usize;
+
+        match self.peak > 0.0 {
+            true => self.values.get(index).copied().unwrap_or(0.0) / self.peak,
+            false => 0.0,
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::ops::Range;
+
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+use crate::maps::objects::Note;
+
+// Grid positions range from cell center 0 to 2, the outer cell edges add half a cell
+const GRID_MIN: f32 = -0.5;
+const GRID_MAX: f32 = 2.5;
+
+// Standard deviation of the splat around each sample, in grid cells
+const SPREAD: f32 = 0.2;
+
+#[derive(Debug, Clone)]
+pub struct Heatmap {
+    pub resolution: u32,
+    cells: Vec<f32>,
+    peak: f32,
+}
+
+impl Heatmap {
+    pub fn new(resolution: u32) -> Self {
+        Self {
+            resolution,
+            cells: vec![0.0; (resolution * resolution) as usize],
+            peak: 0.0,
+        }
+    }
+
+    // Builds a heatmap of the notes, limited to `range` when given
+    pub fn from_notes(notes: &[Note], range: Option<Range<u32>>, resolution: u32) -> Self {
+        let mut heatmap = Self::new(resolution);
+
+        for note in notes.iter() {
+            if range.as_ref().is_none_or(|r| r.contains(&note.millisecond)) {
+                heatmap.add(note.position, 1.0);
+            }
+        }
+
+        heatmap
+    }
+
+    // Any positional samples can be accumulated, e.g. the cursor path of a replay
+    pub fn from_positions(positions: impl IntoIterator<Item = Vec2>, resolution: u32) -> Self {
+        let mut heatmap = Self::new(resolution);
+
+        for position in positions {
+            heatmap.add(position, 1.0);
+        }
+
+        heatmap
+    }
+
+    pub fn add(&mut self, position: Vec2, weight: f32) {
+        let cell_size = (GRID_MAX - GRID_MIN) / self.resolution as f32;
+        let radius = (SPREAD * 3.0 / cell_size).ceil() as i32;
+
+        let center = (position - Vec2::splat(GRID_MIN)) / cell_size;
+        let (cx, cy) = (center.x as i32, center.y as i32);
+
+        for y in (cy - radius)..=(cy + radius) {
+            for x in (cx - radius)..=(cx + radius) {
+                if x < 0 || y < 0 || x >= self.resolution as i32 || y >= self.resolution as i32 {
+                    continue;
+                }
+
+                let cell =
+                    Vec2::new(x as f32 + 0.5, y as f32 + 0.5) * cell_size + Vec2::splat(GRID_MIN);
+                let distance = cell.distance_squared(position);
+                let value = weight * (-distance / (2.0 * SPREAD * SPREAD)).exp();
+
+                let index = (y as u32 * self.resolution + x as u32) as usize;
+                self.cells[index] += value;
+                self.peak = self.peak.max(self.cells[index]);
+            }
+        }
+    }
+
+    // Density of a heatmap cell normalized against the hottest cell
+    pub fn value(&self, x: u32, y: u32) -> f32 {
+        if self.peak <= 0.0 {
+            return 0.0;
+        }
+
+        self.cells[(y * self.resolution + x) as usize] / self.peak
+    }
+
+    pub fn to_image(&self) -> Image {
+        let mut data = Vec::with_capacity((self.resolution * self.resolution * 4) as usize);
+
+        for y in 0..self.resolution {
+            for x in 0..self.resolution {
+                let value = self.value(x, y);
+                let color = heat_color(value).to_srgba();
+
+                data.extend_from_slice(&[
+                    (color.red * 255.0) as u8,
+                    (color.green * 255.0) as u8,
+                    (color.blue * 255.0) as u8,
+                    (value.sqrt() * 200.0) as u8,
+                ]);
+            }
+        }
+
+        Image::new(
+            Extent3d {
+                width: self.resolution,
+                height: self.resolution,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        )
+    }
+}
+
+// Blue for cold areas, through green and yellow to red for the hottest
+fn heat_color(value: f32) -> Color {
+    Color::hsl((1.0 - value.clamp(0.0, 1.0)) * 240.0, 0.9, 0.5)
+}
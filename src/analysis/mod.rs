@@ -0,0 +1,6 @@
+pub mod alignment;
+pub mod bpm;
+pub mod difficulty;
+pub mod heatmap;
+pub mod readability;
+pub mod tags;
@@ -0,0 +1,138 @@
+use bevy::math::Vec2;
+use serde::Serialize;
+
+use crate::{
+    analysis::difficulty::SAMPLE_MS,
+    maps::objects::Note,
+    player::playfield::{APPROACH_DISTANCE, APPROACH_SPEED, NOTE_SIZE},
+    theme::approach::ApproachProfile,
+};
+
+// How long a note takes from appearing to being hit without speed changes
+const APPROACH_MS: f32 = APPROACH_DISTANCE / APPROACH_SPEED * 1000.0;
+
+// A note has to be in plain sight for about this long to be read in time
+const REACTION_MS: f32 = 350.0;
+
+// Notes this hard to read or worse make a section unreadable
+const UNREADABLE: f32 = 0.5;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UnreadableSection {
+    pub start_ms: u32,
+    pub end_ms: u32,
+    // Worst note in the section, from 0 for readable to 1 for impossible to see in time
+    pub penalty: f32,
+}
+
+// How well notes can be seen before they have to be hit. A note drawn behind one that is hit
+// just before it is hidden along the same sightline until then, and partly hidden when the two
+// only overlap. Approach profiles change how big and where notes are on the way in, and fading
+// them in late cuts into the time they are visible. Notes are treated as seen straight on and
+// speed changes are left out, so this is an estimate at the default approach rate.
+#[derive(Debug, Clone, Default)]
+pub struct Readability {
+    // Worst penalty per `SAMPLE_MS`, like the difficulty curve
+    values: Vec<f32>,
+    // From 0 to 100, 100 when every note is in plain sight long enough
+    score: f32,
+    unreadable: Vec<UnreadableSection>,
+}
+
+// Portion of `b` covered by `a`, both squares given by their center and side
+fn covered(a: Vec2, a_size: f32, b: Vec2, b_size: f32) -> f32 {
+    if b_size <= 0.0 {
+        return 0.0;
+    }
+
+    let overlap = |a: f32, b: f32| {
+        let low = (a - a_size / 2.0).max(b - b_size / 2.0);
+        let high = (a + a_size / 2.0).min(b + b_size / 2.0);
+        (high - low).max(0.0)
+    };
+
+    overlap(a.x, b.x) * overlap(a.y, b.y) / (b_size * b_size)
+}
+
+impl Readability {
+    // Expects the notes sorted by time
+    pub fn from_notes(notes: &[Note], length_ms: u32, approach: &ApproachProfile) -> Self {
+        let end = notes.last().map_or(0, |n| n.millisecond).max(length_ms);
+        let mut values = vec![0.0f32; (end / SAMPLE_MS + 1) as usize];
+
+        // Fading in takes a share of the approach, the rest is all the time a note is fully shown
+        let shown_ms = APPROACH_MS * (1.0 - approach.fade_in.clamp(0.0, 1.0));
+        let faded = (1.0 - shown_ms / REACTION_MS).clamp(0.0, 1.0);
+
+        let mut penalties = Vec::with_capacity(notes.len());
+        for (index, note) in notes.iter().enumerate() {
+            let mut penalty = faded;
+
+            // Earlier notes are in front until they are hit, what is left after that is all
+            // the time this one is in plain sight
+            for previous in notes[..index].iter().rev() {
+                let gap = (note.millisecond - previous.millisecond) as f32;
+                if gap >= REACTION_MS {
+                    break;
+                }
+
+                let pose = approach.pose(note.position, 1.0 - gap / APPROACH_MS);
+                let hidden = covered(
+                    previous.position,
+                    NOTE_SIZE,
+                    note.position + pose.offset,
+                    NOTE_SIZE * pose.scale,
+                );
+                penalty = penalty.max(hidden * (1.0 - gap / REACTION_MS));
+            }
+
+            let sample = &mut values[(note.millisecond / SAMPLE_MS) as usize];
+            *sample = sample.max(penalty);
+            penalties.push(penalty);
+        }
+
+        let score = match penalties.is_empty() {
+            true => 100.0,
+            false => 100.0 * (1.0 - penalties.iter().sum::<f32>() / penalties.len() as f32),
+        };
+
+        // Neighbouring unreadable samples make one section
+        let mut unreadable: Vec<UnreadableSection> = Vec::new();
+        for (index, penalty) in values.iter().enumerate() {
+            if *penalty < UNREADABLE {
+                continue;
+            }
+
+            let start_ms = index as u32 * SAMPLE_MS;
+            match unreadable.last_mut() {
+                Some(section) if section.end_ms == start_ms => {
+                    section.end_ms = start_ms + SAMPLE_MS;
+                    section.penalty = section.penalty.max(*penalty);
+                }
+                _ => unreadable.push(UnreadableSection {
+                    start_ms,
+                    end_ms: start_ms + SAMPLE_MS,
+                    penalty: *penalty,
+                }),
+            }
+        }
+
+        Self {
+            values,
+            score,
+            unreadable,
+        }
+    }
+
+    pub fn values(&self) -> &[f32] {
+        &self.values
+    }
+
+    pub fn score(&self) -> f32 {
+        self.score
+    }
+
+    pub fn unreadable(&self) -> &[UnreadableSection] {
+        &self.unreadable
+    }
+}
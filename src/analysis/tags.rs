@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+
+use crate::maps::{objects::Note, parser::is_quantum};
+
+// Notes closer together than this are stacked, not part of a stream or jump
+const MIN_GAP_MS: u32 = 20;
+
+// Consecutive notes at most this far apart in time and on the grid make up streams, about 1/4
+// beats at 100 BPM
+const STREAM_GAP_MS: u32 = 150;
+const STREAM_DISTANCE: f32 = 1.0;
+
+// Crossing most of the grid this quickly is a jump
+const JUMP_GAP_MS: u32 = 300;
+const JUMP_DISTANCE: f32 = 1.9;
+
+// Share of the note pairs, or notes for quantum, a map needs to get a tag
+const STREAM_SHARE: f32 = 0.4;
+const JUMP_SHARE: f32 = 0.25;
+const QUANTUM_SHARE: f32 = 0.2;
+
+// Notes per second below which a map is low density, over the part with notes
+const LOW_DENSITY: f32 = 2.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ChartTag {
+    StreamHeavy,
+    JumpHeavy,
+    Quantum,
+    LowDensity,
+}
+
+impl ChartTag {
+    pub const ALL: [ChartTag; 4] = [
+        ChartTag::StreamHeavy,
+        ChartTag::JumpHeavy,
+        ChartTag::Quantum,
+        ChartTag::LowDensity,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ChartTag::StreamHeavy => "Streams",
+            ChartTag::JumpHeavy => "Jumps",
+            ChartTag::Quantum => "Quantum",
+            ChartTag::LowDensity => "Low density",
+        }
+    }
+}
+
+// What the tags are decided from, shares are from 0 to 1
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ChartStats {
+    pub notes: usize,
+    pub notes_per_second: f32,
+    // Of the consecutive note pairs that aren't stacked
+    pub stream_share: f32,
+    pub jump_share: f32,
+    // Of the notes
+    pub quantum_share: f32,
+}
+
+impl ChartStats {
+    // Expects the notes sorted by time
+    pub fn from_notes(notes: &[Note]) -> Self {
+        let (Some(first), Some(last)) = (notes.first(), notes.last()) else {
+            return Self::default();
+        };
+
+        let mut pairs = 0;
+        let mut streams = 0;
+        let mut jumps = 0;
+        for pair in notes.windows(2) {
+            let gap = pair[1].millisecond - pair[0].millisecond;
+            if gap < MIN_GAP_MS {
+                continue;
+            }
+
+            pairs += 1;
+            let distance = pair[0].position.distance(pair[1].position);
+            if gap <= STREAM_GAP_MS && distance <= STREAM_DISTANCE {
+                streams += 1;
+            }
+            if gap <= JUMP_GAP_MS && distance >= JUMP_DISTANCE {
+                jumps += 1;
+            }
+        }
+
+        let share = |count: usize, of: usize| match of {
+            0 => 0.0,
+            of => count as f32 / of as f32,
+        };
+        let quantum = notes.iter().filter(|n| is_quantum(n.position)).count();
+        // A single note or a chord still counts as a second of notes
+        let seconds = ((last.millisecond - first.millisecond) as f32 / 1000.0).max(1.0);
+
+        Self {
+            notes: notes.len(),
+            notes_per_second: notes.len() as f32 / seconds,
+            stream_share: share(streams, pairs),
+            jump_share: share(jumps, pairs),
+            quantum_share: share(quantum, notes.len()),
+        }
+    }
+
+    pub fn tags(&self) -> Vec<ChartTag> {
+        if self.notes == 0 {
+            return Vec::new();
+        }
+
+        ChartTag::ALL
+            .into_iter()
+            .filter(|tag| match tag {
+                ChartTag::StreamHeavy => self.stream_share >= STREAM_SHARE,
+                ChartTag::JumpHeavy => self.jump_share >= JUMP_SHARE,
+                ChartTag::Quantum => self.quantum_share >= QUANTUM_SHARE,
+                ChartTag::LowDensity => self.notes_per_second < LOW_DENSITY,
+            })
+            .collect()
+    }
+}
+
+// Expects the notes sorted by time
+pub fn chart_tags(notes: &[Note]) -> Vec<ChartTag> {
+    ChartStats::from_notes(notes).tags()
+}
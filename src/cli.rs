@@ -0,0 +1,632 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    analysis::alignment::{MIN_CONFIDENCE, check_alignment},
+    jukebox::pcm::Pcm,
+    maps::{
+        beat_saber::export_beat_saber,
+        card::{card_path, save_card},
+        export::export_all,
+        format_time,
+        hooks::run_hooks,
+        mashup::{MashupMode, mashup as mashup_maps},
+        read_map,
+        rhythm::{RhythmFormat, export_rhythm},
+        server::{self, DEFAULT_PORT, ServedLibrary},
+        size::{SizeReport, format_size},
+        trim::trim,
+    },
+    modchart::{
+        pack::{self, ModPack, PackManifest, write_pack},
+        script::{self, read_script, write_script},
+        sidecar::{self, read_modchart, save_modchart},
+    },
+    settings::Settings,
+};
+
+const USAGE: &str = "Usage:
+  mm-modchart-maker [--safe-mode] [<project.mmm>]
+  mm-modchart-maker export [--out <folder>] [--template <template>] [--sizes] [--range <start ms>-<end ms>] [--no-hooks] [--max-size <MB>] [--shrink] <map>...
+  mm-modchart-maker align <map>...
+  mm-modchart-maker card [--out <folder>] [--template <template>] <map>...
+  mm-modchart-maker beatsaber [--out <folder>] [--template <template>] <map>...
+  mm-modchart-maker rhythm [--out <folder>] [--template <template>] [--format taiko|chart] <map>...
+  mm-modchart-maker serve [--bind <address>] [--port <port>] [<folder>...]
+  mm-modchart-maker mashup [--out <folder>] [--template <template>] [--gap <ms> | --overlay] <first> <second>
+  mm-modchart-maker script <modchart.mmmod | script.ron>...
+  mm-modchart-maker pack --name <name> [--author <author>] [--description <text>] [--out <pack.mmpack>] [--skin <file>]... <modchart.mmmod | script.ron>...";
+
+// Runs a command line subcommand instead of the editor, returns None when no subcommand was given
+pub fn run(args: &[String]) -> Option<io::Result<()>> {
+    match args.first().map(String::as_str) {
+        Some("export") => Some(export(&args[1..])),
+        Some("align") => Some(align(&args[1..])),
+        Some("card") => Some(card(&args[1..])),
+        Some("beatsaber") => Some(beat_saber(&args[1..])),
+        Some("rhythm") => Some(rhythm(&args[1..])),
+        Some("mashup") => Some(mashup(&args[1..])),
+        Some("serve") => Some(serve(&args[1..])),
+        Some("script") => Some(convert_scripts(&args[1..])),
+        Some("pack") => Some(build_pack(&args[1..])),
+        Some("help" | "--help" | "-h") => {
+            println!("{USAGE}");
+            Some(Ok(()))
+        }
+        _ => None,
+    }
+}
+
+fn export(args: &[String]) -> io::Result<()> {
+    let settings = Settings::load();
+    let mut folder = PathBuf::from(&settings.export.folder);
+    let mut template = settings.export.filename_template.clone();
+    let mut inputs = Vec::new();
+    let mut sizes = false;
+    let mut range = None;
+    let mut hooks = settings.export.hooks.clone();
+    let mut limit = settings.export.size_limit.clone();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" | "-o" => folder = PathBuf::from(required(args.next(), arg)?),
+            "--template" | "-t" => template = required(args.next(), arg)?.clone(),
+            "--sizes" | "-s" => sizes = true,
+            "--range" | "-r" => range = Some(parse_range(required(args.next(), arg)?)?),
+            "--no-hooks" => hooks.clear(),
+            "--max-size" => {
+                limit.max_mb = required(args.next(), arg)?
+                    .parse()
+                    .map_err(|_| invalid_input(&format!("{arg} needs a size in MB\n{USAGE}")))?;
+            }
+            "--shrink" => limit.shrink = true,
+            _ => inputs.push(PathBuf::from(arg)),
+        }
+    }
+
+    if inputs.is_empty() {
+        return Err(invalid_input(USAGE));
+    }
+
+    let mut failed = 0;
+
+    for input in inputs.iter() {
+        let map = read_map(input, settings.read_options()).and_then(|map| match range {
+            Some((start, end)) => trim(&map, start, end),
+            None => Ok(map),
+        });
+
+        let map = match map {
+            Ok(map) => map,
+            Err(e) => {
+                eprintln!("{}: {e}", input.display());
+                failed += 1;
+                continue;
+            }
+        };
+
+        for warning in map.load_warnings.iter() {
+            eprintln!("  warning: {warning}");
+        }
+
+        for export in export_all(
+            &map,
+            &folder,
+            &template,
+            &limit,
+            settings.export.write_options(),
+        ) {
+            match &export.result {
+                Ok(()) => println!("{} -> {}", input.display(), export.path.display()),
+                Err(e) => {
+                    eprintln!("{} -> {:?} failed: {e}", input.display(), export.format);
+                    failed += 1;
+                }
+            }
+
+            for warning in export.warnings.iter() {
+                eprintln!("  warning ({:?}): {warning}", export.format);
+            }
+
+            if sizes && let Some(size) = &export.size {
+                print_size(size);
+            }
+
+            if export.result.is_ok() {
+                for run in run_hooks(&hooks, &export.path) {
+                    match run.result {
+                        Ok(_) => println!("  {} ran", run.hook),
+                        Err(e) => {
+                            eprintln!("  {} failed: {e}", run.hook);
+                            failed += 1;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    match failed {
+        0 => Ok(()),
+        _ => Err(io::Error::other(format!("{failed} exports failed"))),
+    }
+}
+
+// Reports how far each map's notes are from the onsets in its audio
+fn align(inputs: &[String]) -> io::Result<()> {
+    if inputs.is_empty() {
+        return Err(invalid_input(USAGE));
+    }
+
+    let options = Settings::load().read_options();
+
+    let mut failed = 0;
+
+    for input in inputs.iter() {
+        let report = read_map(&PathBuf::from(input), options).and_then(|map| {
+            let audio = map
+                .audio
+                .as_ref()
+                .ok_or_else(|| io::Error::other("Map has no audio"))?;
+            let pcm = Pcm::decode(&audio.bytes)?;
+            check_alignment(&pcm.mono(), pcm.sample_rate, &map.notes)
+                .ok_or_else(|| io::Error::other("No onsets line up with the notes"))
+        });
+
+        let report = match report {
+            Ok(report) => report,
+            Err(e) => {
+                eprintln!("{input}: {e}");
+                failed += 1;
+                continue;
+            }
+        };
+
+        println!(
+            "{input}: suggested offset {:+.1}ms (confidence {:.0}%)",
+            report.offset_ms,
+            report.confidence * 100.0
+        );
+        if report.confidence < MIN_CONFIDENCE {
+            println!("  the audio has no clear onsets, the offset is a guess");
+        }
+
+        for section in report.misaligned() {
+            println!(
+                "  {} to {}: {:+.1}ms over {} notes",
+                format_time(section.start),
+                format_time(section.end),
+                section.offset_ms,
+                section.notes
+            );
+        }
+    }
+
+    match failed {
+        0 => Ok(()),
+        _ => Err(io::Error::other(format!(
+            "{failed} maps could not be checked"
+        ))),
+    }
+}
+
+// Renders a chart card for each map, named and placed like the exports
+fn card(args: &[String]) -> io::Result<()> {
+    let settings = Settings::load();
+    let mut folder = PathBuf::from(&settings.export.folder);
+    let mut template = settings.export.filename_template.clone();
+    let mut inputs = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" | "-o" => folder = PathBuf::from(required(args.next(), arg)?),
+            "--template" | "-t" => template = required(args.next(), arg)?.clone(),
+            _ => inputs.push(PathBuf::from(arg)),
+        }
+    }
+
+    if inputs.is_empty() {
+        return Err(invalid_input(USAGE));
+    }
+
+    let mut failed = 0;
+
+    for input in inputs.iter() {
+        let result = read_map(input, settings.read_options()).and_then(|map| {
+            let path = card_path(&map, &folder, &template);
+            save_card(&map, &path).map(|_| path)
+        });
+
+        match result {
+            Ok(path) => println!("{} -> {}", input.display(), path.display()),
+            Err(e) => {
+                eprintln!("{}: {e}", input.display());
+                failed += 1;
+            }
+        }
+    }
+
+    match failed {
+        0 => Ok(()),
+        _ => Err(io::Error::other(format!("{failed} cards failed"))),
+    }
+}
+
+// Experimental, writes each map as a Beat Saber custom level folder
+fn beat_saber(args: &[String]) -> io::Result<()> {
+    let settings = Settings::load();
+    let mut folder = PathBuf::from(&settings.export.folder);
+    let mut template = settings.export.filename_template.clone();
+    let mut inputs = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" | "-o" => folder = PathBuf::from(required(args.next(), arg)?),
+            "--template" | "-t" => template = required(args.next(), arg)?.clone(),
+            _ => inputs.push(PathBuf::from(arg)),
+        }
+    }
+
+    if inputs.is_empty() {
+        return Err(invalid_input(USAGE));
+    }
+
+    let mut failed = 0;
+
+    for input in inputs.iter() {
+        match read_map(input, settings.read_options())
+            .and_then(|map| export_beat_saber(&map, &folder, &template))
+        {
+            Ok(export) => {
+                println!("{} -> {}", input.display(), export.folder.display());
+                for warning in export.warnings.iter() {
+                    eprintln!("  warning: {warning}");
+                }
+            }
+            Err(e) => {
+                eprintln!("{}: {e}", input.display());
+                failed += 1;
+            }
+        }
+    }
+
+    match failed {
+        0 => Ok(()),
+        _ => Err(io::Error::other(format!("{failed} levels failed"))),
+    }
+}
+
+// Rhythm only charts for osu!taiko or Clone Hero, both when no format is given
+fn rhythm(args: &[String]) -> io::Result<()> {
+    let settings = Settings::load();
+    let mut folder = PathBuf::from(&settings.export.folder);
+    let mut template = settings.export.filename_template.clone();
+    let mut formats = RhythmFormat::ALL.to_vec();
+    let mut inputs = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" | "-o" => folder = PathBuf::from(required(args.next(), arg)?),
+            "--template" | "-t" => template = required(args.next(), arg)?.clone(),
+            "--format" | "-f" => {
+                formats = match required(args.next(), arg)?.as_str() {
+                    "taiko" => vec![RhythmFormat::OsuTaiko],
+                    "chart" => vec![RhythmFormat::CloneHero],
+                    other => {
+                        return Err(invalid_input(&format!(
+                            "Unknown format \"{other}\"\n{USAGE}"
+                        )));
+                    }
+                }
+            }
+            _ => inputs.push(PathBuf::from(arg)),
+        }
+    }
+
+    if inputs.is_empty() {
+        return Err(invalid_input(USAGE));
+    }
+
+    let mut failed = 0;
+
+    for input in inputs.iter() {
+        let map = match read_map(input, settings.read_options()) {
+            Ok(map) => map,
+            Err(e) => {
+                eprintln!("{}: {e}", input.display());
+                failed += 1;
+                continue;
+            }
+        };
+
+        for format in formats.iter() {
+            match export_rhythm(&map, *format, &folder, &template) {
+                Ok(export) => {
+                    println!("{} -> {}", input.display(), export.folder.display());
+                    for warning in export.warnings.iter() {
+                        eprintln!("  warning ({}): {warning}", format.label());
+                    }
+                }
+                Err(e) => {
+                    eprintln!("{} -> {} failed: {e}", input.display(), format.label());
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    match failed {
+        0 => Ok(()),
+        _ => Err(io::Error::other(format!("{failed} exports failed"))),
+    }
+}
+
+// Serves the library over HTTP until stopped, see maps::server. Only this machine can connect
+// unless another address is given, like 0.0.0.0 for the whole network
+fn serve(args: &[String]) -> io::Result<()> {
+    let settings = Settings::load();
+    let mut bind = "127.0.0.1".to_string();
+    let mut port = DEFAULT_PORT;
+    let mut folders = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bind" | "-b" => bind = required(args.next(), arg)?.clone(),
+            "--port" | "-p" => {
+                port = required(args.next(), arg)?
+                    .parse()
+                    .map_err(|_| invalid_input(&format!("{arg} needs a port number\n{USAGE}")))?;
+            }
+            _ => folders.push(PathBuf::from(arg)),
+        }
+    }
+
+    // Without folders of its own it serves the editor's library
+    if folders.is_empty() {
+        folders = settings.map_folders.iter().map(PathBuf::from).collect();
+    }
+    if folders.is_empty() {
+        return Err(invalid_input(&format!(
+            "No map folders, pass some or add them to the library\n{USAGE}"
+        )));
+    }
+
+    let mut library = ServedLibrary::new(folders).with_options(settings.read_options());
+    library.rescan();
+    server::serve(library, (bind.as_str(), port))
+}
+
+// Exports the second map appended to the first one, or both overlaid on the first one's audio
+fn mashup(args: &[String]) -> io::Result<()> {
+    let settings = Settings::load();
+    let mut folder = PathBuf::from(&settings.export.folder);
+    let mut template = settings.export.filename_template.clone();
+    let mut mode = MashupMode::Append { gap_ms: 0 };
+    let mut inputs = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" | "-o" => folder = PathBuf::from(required(args.next(), arg)?),
+            "--template" | "-t" => template = required(args.next(), arg)?.clone(),
+            "--gap" | "-g" => {
+                let gap_ms = required(args.next(), arg)?
+                    .parse()
+                    .map_err(|_| invalid_input(&format!("{arg} needs a number of ms\n{USAGE}")))?;
+                mode = MashupMode::Append { gap_ms };
+            }
+            "--overlay" => mode = MashupMode::Overlay,
+            _ => inputs.push(PathBuf::from(arg)),
+        }
+    }
+
+    let [first, second] = inputs.as_slice() else {
+        return Err(invalid_input(USAGE));
+    };
+
+    let map = mashup_maps(
+        &read_map(first, settings.read_options())?,
+        &read_map(second, settings.read_options())?,
+        mode,
+    )?;
+    let mut failed = 0;
+
+    for export in export_all(
+        &map,
+        &folder,
+        &template,
+        &settings.export.size_limit,
+        settings.export.write_options(),
+    ) {
+        match &export.result {
+            Ok(()) => println!("{:?} -> {}", export.format, export.path.display()),
+            Err(e) => {
+                eprintln!("{:?} failed: {e}", export.format);
+                failed += 1;
+            }
+        }
+
+        for warning in export.warnings.iter() {
+            eprintln!("  warning ({:?}): {warning}", export.format);
+        }
+    }
+
+    match failed {
+        0 => Ok(()),
+        _ => Err(io::Error::other(format!("{failed} exports failed"))),
+    }
+}
+
+// Turns modchart sidecars into text scripts next to them, and scripts back into sidecars
+fn convert_scripts(args: &[String]) -> io::Result<()> {
+    if args.is_empty() {
+        return Err(invalid_input(USAGE));
+    }
+
+    let mut failed = 0;
+
+    for input in args.iter().map(PathBuf::from) {
+        match convert_script(&input) {
+            Ok(output) => println!("{} -> {}", input.display(), output.display()),
+            Err(e) => {
+                eprintln!("{}: {e}", input.display());
+                failed += 1;
+            }
+        }
+    }
+
+    match failed {
+        0 => Ok(()),
+        _ => Err(io::Error::other(format!("{failed} conversions failed"))),
+    }
+}
+
+fn convert_script(input: &Path) -> io::Result<PathBuf> {
+    let extension = input.extension().and_then(|e| e.to_str());
+
+    if extension == Some(sidecar::EXTENSION) {
+        let modchart = read_modchart(BufReader::new(File::open(input)?))?;
+        let output = input.with_extension(script::EXTENSION);
+        fs::write(&output, write_script(&modchart)?)?;
+        Ok(output)
+    } else if extension == Some(script::EXTENSION) {
+        let modchart = read_script(&fs::read_to_string(input)?)?;
+        let output = input.with_extension(sidecar::EXTENSION);
+        save_modchart(&modchart, &output)?;
+        Ok(output)
+    } else {
+        Err(invalid_input(&format!(
+            "Expected a .{} or .{} file",
+            sidecar::EXTENSION,
+            script::EXTENSION
+        )))
+    }
+}
+
+// Bundles modcharts and the skin files they need into a pack to share, see modchart::pack
+fn build_pack(args: &[String]) -> io::Result<()> {
+    let mut manifest = PackManifest::default();
+    let mut output = None;
+    let mut skins = Vec::new();
+    let mut inputs = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--name" | "-n" => manifest.name = required(args.next(), arg)?.clone(),
+            "--author" | "-a" => manifest.author = required(args.next(), arg)?.clone(),
+            "--description" | "-d" => manifest.description = required(args.next(), arg)?.clone(),
+            "--out" | "-o" => output = Some(PathBuf::from(required(args.next(), arg)?)),
+            "--skin" | "-s" => skins.push(PathBuf::from(required(args.next(), arg)?)),
+            _ => inputs.push(PathBuf::from(arg)),
+        }
+    }
+
+    if manifest.name.trim().is_empty() || (inputs.is_empty() && skins.is_empty()) {
+        return Err(invalid_input(USAGE));
+    }
+
+    let mut built = ModPack {
+        manifest,
+        ..Default::default()
+    };
+
+    for input in inputs.iter() {
+        let extension = input.extension().and_then(|e| e.to_str());
+        let modchart = if extension == Some(sidecar::EXTENSION) {
+            read_modchart(BufReader::new(File::open(input)?))?
+        } else if extension == Some(script::EXTENSION) {
+            read_script(&fs::read_to_string(input)?)?
+        } else {
+            return Err(invalid_input(&format!(
+                "{}: expected a .{} or .{} file",
+                input.display(),
+                sidecar::EXTENSION,
+                script::EXTENSION
+            )));
+        };
+
+        let name = file_name(input, true);
+        if built.modcharts.insert(name.clone(), modchart).is_some() {
+            return Err(invalid_input(&format!("Two modcharts are named {name}")));
+        }
+    }
+
+    for skin in skins.iter() {
+        built.skins.insert(file_name(skin, false), fs::read(skin)?);
+    }
+
+    let output = output.unwrap_or_else(|| {
+        PathBuf::from(format!(
+            "{}.{}",
+            pack::pack_id(&built.manifest),
+            pack::EXTENSION
+        ))
+    });
+    let mut writer = BufWriter::new(File::create(&output)?);
+    write_pack(&built, &mut writer)?;
+    writer.flush()?;
+
+    println!(
+        "{} modcharts and {} skin files -> {}",
+        built.modcharts.len(),
+        built.skins.len(),
+        output.display()
+    );
+    Ok(())
+}
+
+fn file_name(path: &Path, stem: bool) -> String {
+    let name = match stem {
+        true => path.file_stem(),
+        false => path.file_name(),
+    };
+    name.map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn print_size(size: &SizeReport) {
+    println!("  {} in total", format_size(size.total));
+
+    for (name, bytes) in size.sections.iter() {
+        println!(
+            "    {name:<20} {:>10} {:>6.1}%",
+            format_size(*bytes),
+            size.share(*bytes) * 100.0
+        );
+    }
+
+    println!(
+        "  {} grid notes, {} quantum notes",
+        size.grid_notes, size.quantum_notes
+    );
+
+    for hint in size.hints() {
+        println!("  hint: {hint}");
+    }
+}
+
+// "30000-45000", in milliseconds
+fn parse_range(value: &str) -> io::Result<(u32, u32)> {
+    let parsed = value
+        .split_once('-')
+        .and_then(|(start, end)| Some((start.trim().parse().ok()?, end.trim().parse().ok()?)));
+
+    parsed.ok_or_else(|| invalid_input(&format!("Invalid range {value}\n{USAGE}")))
+}
+
+fn required<'a>(value: Option<&'a String>, flag: &str) -> io::Result<&'a String> {
+    value.ok_or_else(|| invalid_input(&format!("{flag} needs a value\n{USAGE}")))
+}
+
+fn invalid_input(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.to_string())
+}
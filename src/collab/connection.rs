@@ -0,0 +1,144 @@
+use std::{
+    io::{self, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{Receiver, Sender, channel},
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::collab::protocol::{Message, read_message};
+
+// How often a waiting host checks whether it was cancelled
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+pub enum ConnectionEvent {
+    Connected(SocketAddr),
+    Received(Message),
+    Closed(String),
+}
+
+// One peer on the other end of a TCP stream, messages are sent and received on background
+// threads so the editor never waits on the network
+pub struct Connection {
+    outgoing: Sender<Message>,
+    pub incoming: Mutex<Receiver<ConnectionEvent>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Connection {
+    // Waits for a single guest on `port`. Only other instances on this computer can connect
+    // unless `lan` is set
+    pub fn host(port: u16, lan: bool) -> io::Result<Self> {
+        let interface = if lan { "0.0.0.0" } else { "127.0.0.1" };
+        let listener = TcpListener::bind((interface, port))?;
+        listener.set_nonblocking(true)?;
+
+        Ok(Self::spawn(move |cancelled| {
+            loop {
+                if cancelled.load(Ordering::Relaxed) {
+                    return Err(io::Error::other("Stopped hosting"));
+                }
+
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        stream.set_nonblocking(false)?;
+                        return Ok(stream);
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                        thread::sleep(ACCEPT_INTERVAL)
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }))
+    }
+
+    pub fn connect(address: String) -> Self {
+        Self::spawn(move |_| TcpStream::connect(address.as_str()))
+    }
+
+    fn spawn(open: impl FnOnce(&AtomicBool) -> io::Result<TcpStream> + Send + 'static) -> Self {
+        let (outgoing, outgoing_rx) = channel::<Message>();
+        let (incoming_tx, incoming) = channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+
+        let flag = cancelled.clone();
+        thread::spawn(move || {
+            let stream = match open(&flag) {
+                Ok(stream) => stream,
+                Err(e) => {
+                    let _ = incoming_tx.send(ConnectionEvent::Closed(e.to_string()));
+                    return;
+                }
+            };
+
+            if let Ok(address) = stream.peer_addr() {
+                let _ = incoming_tx.send(ConnectionEvent::Connected(address));
+            }
+
+            let reader = match stream.try_clone() {
+                Ok(reader) => reader,
+                Err(e) => {
+                    let _ = incoming_tx.send(ConnectionEvent::Closed(e.to_string()));
+                    return;
+                }
+            };
+
+            thread::spawn(move || receive(reader, incoming_tx));
+            send(stream, outgoing_rx);
+        });
+
+        Self {
+            outgoing,
+            incoming: Mutex::new(incoming),
+            cancelled,
+        }
+    }
+
+    pub fn send(&self, message: Message) {
+        // A closed connection is reported by the receiving side
+        let _ = self.outgoing.send(message);
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+fn receive(stream: TcpStream, events: Sender<ConnectionEvent>) {
+    let mut reader = BufReader::new(stream);
+    let reason = loop {
+        match read_message(&mut reader) {
+            Ok(Some(message)) => {
+                if events.send(ConnectionEvent::Received(message)).is_err() {
+                    break "Connection was closed".to_string();
+                }
+            }
+            Ok(None) => break "The other side disconnected".to_string(),
+            Err(e) => break e.to_string(),
+        }
+    };
+
+    let _ = events.send(ConnectionEvent::Closed(reason));
+}
+
+// Runs until the connection is dropped, which also closes the stream for the other side
+fn send(mut stream: TcpStream, messages: Receiver<Message>) {
+    for message in messages {
+        let written = serde_json::to_string(&message)
+            .map_err(io::Error::other)
+            .and_then(|line| writeln!(stream, "{line}"));
+        if written.is_err() {
+            break;
+        }
+    }
+
+    let _ = stream.shutdown(std::net::Shutdown::Both);
+}
@@ -0,0 +1,309 @@
+pub mod connection;
+pub mod panel;
+pub mod protocol;
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use bevy_egui::EguiPrimaryContextPass;
+
+use crate::{
+    collab::{
+        connection::{Connection, ConnectionEvent},
+        panel::CollabPanel,
+        protocol::{Message, OperationLog},
+    },
+    editor::{
+        commands::{EditHistory, Selection},
+        editing::{NotesEdited, process_edit_requests},
+    },
+    maps::{CurrentMap, Map, objects::NoteId},
+    modchart::random::Rng,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CollabStatus {
+    Idle,
+    Hosting { port: u16 },
+    Connecting { address: String },
+    // Connected, but the other side hasn't shown it has the session token yet
+    Verifying { address: SocketAddr },
+    Connected { address: SocketAddr },
+    Disconnected { reason: String },
+}
+
+// Experimental live editing with one other instance. Both sides exchange what their edits did
+// to the notes, and when both change the same note the later edit wins.
+#[derive(Resource)]
+pub struct Collaboration {
+    pub status: CollabStatus,
+    connection: Option<Connection>,
+    pub log: OperationLog,
+    // Shown by the host and typed in by the guest, so others that can reach the port can't join
+    pub token: String,
+    // The host's notes are the starting point, the guest replaces its own with them
+    hosting: bool,
+}
+
+impl Default for Collaboration {
+    fn default() -> Self {
+        Self {
+            status: CollabStatus::Idle,
+            connection: None,
+            log: OperationLog::new(new_peer_id()),
+            token: String::new(),
+            hosting: false,
+        }
+    }
+}
+
+impl Collaboration {
+    pub fn is_active(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    pub fn host(&mut self, port: u16, lan: bool) {
+        match Connection::host(port, lan) {
+            Ok(connection) => {
+                self.start(connection, true);
+                self.token = new_token();
+                self.status = CollabStatus::Hosting { port };
+            }
+            Err(e) => {
+                self.status = CollabStatus::Disconnected {
+                    reason: format!("Could not host on port {port}: {e}"),
+                }
+            }
+        }
+    }
+
+    pub fn connect(&mut self, address: String, token: String) {
+        self.start(Connection::connect(address.clone()), false);
+        self.token = token;
+        self.status = CollabStatus::Connecting { address };
+    }
+
+    fn start(&mut self, connection: Connection, hosting: bool) {
+        self.connection = Some(connection);
+        self.log = OperationLog::new(self.log.peer);
+        self.hosting = hosting;
+    }
+
+    pub fn disconnect(&mut self) {
+        self.connection = None;
+        self.status = CollabStatus::Idle;
+    }
+
+    fn end(&mut self, reason: String) {
+        info!("Collaboration ended: {reason}");
+        self.status = CollabStatus::Disconnected { reason };
+        self.connection = None;
+    }
+}
+
+// Only has to differ between the two instances
+fn new_peer_id() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_nanos() as u64);
+    nanos ^ ((std::process::id() as u64) << 32)
+}
+
+// From the system's entropy, long enough that it can't be guessed by trying. Falls back to the
+// time seeded generator on systems without one
+fn new_token() -> String {
+    let mut bytes = [0u8; 16];
+    if getrandom::fill(&mut bytes).is_err() {
+        warn!("No system randomness for the session token, falling back to the clock");
+        let mut rng = Rng(new_peer_id());
+        for chunk in bytes.chunks_exact_mut(8) {
+            chunk.copy_from_slice(&rng.next_u64().to_le_bytes());
+        }
+    }
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+// Looks at every byte whatever the first difference, so timing doesn't give the token away
+fn same_token(received: &str, token: &str) -> bool {
+    received.len() == token.len()
+        && received
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+pub struct CollabPlugin;
+
+impl Plugin for CollabPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Collaboration>()
+            .init_resource::<CollabPanel>()
+            .add_systems(
+                Update,
+                (
+                    panel::toggle_collab_panel,
+                    (send_local_edits, receive_messages)
+                        .chain()
+                        .after(process_edit_requests),
+                ),
+            )
+            .add_systems(EguiPrimaryContextPass, panel::collab_panel_ui);
+    }
+}
+
+pub fn send_local_edits(mut edits: EventReader<NotesEdited>, mut collab: ResMut<Collaboration>) {
+    let collab = collab.as_mut();
+    let Some(connection) = collab.connection.as_ref() else {
+        edits.clear();
+        return;
+    };
+
+    // Nothing is sent until the guest has the host's notes, edits before that are in the snapshot
+    if !matches!(collab.status, CollabStatus::Connected { .. }) {
+        edits.clear();
+        return;
+    }
+
+    for NotesEdited(edit) in edits.read() {
+        if edit.is_empty() {
+            continue;
+        }
+        connection.send(Message::Operation(collab.log.local(edit.clone())));
+    }
+}
+
+pub fn receive_messages(
+    mut collab: ResMut<Collaboration>,
+    mut maps: ResMut<Assets<Map>>,
+    mut selection: ResMut<Selection>,
+    mut history: ResMut<EditHistory>,
+    current_map: Option<Res<CurrentMap>>,
+) {
+    let collab = collab.as_mut();
+    let Some(connection) = collab.connection.as_ref() else {
+        return;
+    };
+
+    let events: Vec<ConnectionEvent> = match connection.incoming.lock() {
+        Ok(incoming) => incoming.try_iter().collect(),
+        Err(_) => return,
+    };
+    if events.is_empty() {
+        return;
+    }
+
+    let Some(handle) = current_map.map(|m| m.0.clone()) else {
+        return;
+    };
+
+    for event in events {
+        let verified = matches!(collab.status, CollabStatus::Connected { .. });
+        let Some(connection) = collab.connection.as_ref() else {
+            return;
+        };
+
+        match event {
+            // Only the guest sends the token. The host stays quiet until it has checked it, so
+            // there is nothing a guest without the token could send back
+            ConnectionEvent::Connected(address) => {
+                collab.status = CollabStatus::Verifying { address };
+                if !collab.hosting {
+                    let title = maps.get(&handle).map(|map| map.title.clone());
+                    connection.send(Message::Hello {
+                        peer: collab.log.peer,
+                        token: collab.token.clone(),
+                        map: title.unwrap_or_default(),
+                    });
+                }
+            }
+            ConnectionEvent::Received(Message::Hello {
+                token, map: title, ..
+            }) => {
+                let CollabStatus::Verifying { address } = collab.status else {
+                    continue;
+                };
+                if collab.hosting && !same_token(&token, &collab.token) {
+                    collab.end("The other side has a different session token".to_string());
+                    return;
+                }
+
+                info!("Collaborating with {address}");
+                collab.status = CollabStatus::Connected { address };
+
+                let Some(map) = maps.get(&handle) else {
+                    continue;
+                };
+                if map.title != title {
+                    warn!("The other side has \"{title}\" open, edits will be applied anyway");
+                }
+                if collab.hosting {
+                    connection.send(Message::Hello {
+                        peer: collab.log.peer,
+                        token: String::new(),
+                        map: map.title.clone(),
+                    });
+                    connection.send(Message::Snapshot {
+                        notes: map.notes.clone(),
+                        clock: collab.log.clock(),
+                    });
+                }
+            }
+            ConnectionEvent::Received(_) if !verified => {
+                collab.end("The other side sent edits before its session token".to_string());
+                return;
+            }
+            ConnectionEvent::Received(Message::Snapshot { notes, clock }) => {
+                if collab.hosting {
+                    continue;
+                }
+                let Some(map) = maps.get_mut(&handle) else {
+                    continue;
+                };
+
+                info!("Received {} notes from the host", notes.len());
+                map.notes = notes;
+                selection.0.clear();
+                history.clear();
+                collab.log.observe(clock);
+            }
+            ConnectionEvent::Received(Message::Operation(operation)) => {
+                let Some(map) = maps.get_mut(&handle) else {
+                    continue;
+                };
+
+                // Indices move around, the same notes are found again by id afterwards
+                let selected: Vec<NoteId> = selection
+                    .0
+                    .iter()
+                    .filter_map(|&i| map.notes.get(i))
+                    .map(|note| note.id)
+                    .collect();
+
+                if collab.log.remote(operation, &mut map.notes) {
+                    let indices: HashMap<NoteId, usize> = map
+                        .notes
+                        .iter()
+                        .enumerate()
+                        .map(|(index, note)| (note.id, index))
+                        .collect();
+                    selection.0 = selected
+                        .iter()
+                        .filter_map(|id| indices.get(id))
+                        .copied()
+                        .collect();
+                    // Undo entries point at note indices the remote edit may have shifted
+                    history.clear();
+                }
+            }
+            ConnectionEvent::Closed(reason) => {
+                collab.end(reason);
+                return;
+            }
+        }
+    }
+}
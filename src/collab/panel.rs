@@ -0,0 +1,127 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    collab::{CollabStatus, Collaboration},
+    editor::shortcuts::{EditorAction, EditorActionEvent},
+};
+
+const DEFAULT_PORT: u16 = 7878;
+
+#[derive(Resource)]
+pub struct CollabPanel {
+    pub open: bool,
+    port: u16,
+    // Listens on every network interface instead of only this computer
+    lan: bool,
+    address: String,
+    token: String,
+}
+
+impl Default for CollabPanel {
+    fn default() -> Self {
+        Self {
+            open: false,
+            port: DEFAULT_PORT,
+            lan: false,
+            address: format!("127.0.0.1:{DEFAULT_PORT}"),
+            token: String::new(),
+        }
+    }
+}
+
+pub fn toggle_collab_panel(
+    mut actions: EventReader<EditorActionEvent>,
+    mut panel: ResMut<CollabPanel>,
+) {
+    for EditorActionEvent(action) in actions.read() {
+        if *action == EditorAction::ToggleCollaboration {
+            panel.open = !panel.open;
+        }
+    }
+}
+
+pub fn collab_panel_ui(
+    mut contexts: EguiContexts,
+    mut panel: ResMut<CollabPanel>,
+    mut collab: ResMut<Collaboration>,
+) -> Result {
+    if !panel.open {
+        return Ok(());
+    }
+
+    let mut open = panel.open;
+
+    egui::Window::new("Collaborate")
+        .open(&mut open)
+        .resizable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.label("Experimental: edits are exchanged live with one other instance");
+            ui.separator();
+
+            let status = match &collab.status {
+                CollabStatus::Idle => "Not connected".to_string(),
+                CollabStatus::Hosting { port } => format!("Waiting for a guest on port {port}"),
+                CollabStatus::Connecting { address } => format!("Connecting to {address}"),
+                CollabStatus::Verifying { address } => format!("Checking the token of {address}"),
+                CollabStatus::Connected { address } => format!("Connected to {address}"),
+                CollabStatus::Disconnected { reason } => format!("Disconnected: {reason}"),
+            };
+            ui.label(status);
+
+            if collab.is_active() {
+                if collab.hosting {
+                    ui.horizontal(|ui| {
+                        ui.label("Session token");
+                        ui.code(&collab.token);
+                        if ui.small_button("Copy").clicked() {
+                            ui.ctx().copy_text(collab.token.clone());
+                        }
+                    });
+                }
+                ui.label(format!(
+                    "{} operations exchanged",
+                    collab.log.operations.len()
+                ));
+                ui.weak("Undo history is cleared whenever the other side edits");
+                if ui.button("Disconnect").clicked() {
+                    collab.disconnect();
+                }
+                return;
+            }
+
+            let panel = panel.as_mut();
+            egui::Grid::new("collab_panel")
+                .num_columns(3)
+                .show(ui, |ui| {
+                    ui.label("Port");
+                    ui.add(egui::DragValue::new(&mut panel.port).range(1024..=u16::MAX));
+                    if ui.button("Host").clicked() {
+                        collab.host(panel.port, panel.lan);
+                    }
+                    ui.end_row();
+
+                    ui.label("");
+                    ui.checkbox(&mut panel.lan, "Allow other computers to join");
+                    ui.end_row();
+
+                    // The guest's notes are replaced with the host's once connected
+                    ui.label("Address");
+                    ui.text_edit_singleline(&mut panel.address);
+                    ui.end_row();
+
+                    ui.label("Token");
+                    ui.text_edit_singleline(&mut panel.token);
+                    if ui.button("Join").clicked() {
+                        collab.connect(
+                            panel.address.trim().to_string(),
+                            panel.token.trim().to_string(),
+                        );
+                    }
+                    ui.end_row();
+                });
+        });
+
+    panel.open = open;
+    Ok(())
+}
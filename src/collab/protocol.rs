@@ -0,0 +1,160 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, BufRead, Read},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    editor::commands::NoteEdit,
+    maps::objects::{Note, NoteId},
+};
+
+// Lamport timestamp, ties between peers are broken by their id so every instance orders
+// concurrent operations the same way
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Stamp {
+    pub clock: u64,
+    pub peer: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Operation {
+    pub stamp: Stamp,
+    pub edit: NoteEdit,
+}
+
+// Longest line read from the other side, a snapshot of a very large map still fits
+pub const MAX_MESSAGE: u64 = 32 * 1024 * 1024;
+
+// Sent as one JSON object per line
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Message {
+    // First thing the guest sends, with the token it was given. The host accepts nothing else
+    // until the token matches its own, then answers with a Hello of its own without one. The map
+    // title is only used to warn about mismatches
+    Hello {
+        peer: u64,
+        token: String,
+        map: String,
+    },
+    // The host's notes, which the guest starts from. Their ids come along, so both sides
+    // refer to the same notes by the same ids
+    Snapshot {
+        notes: Vec<Note>,
+        clock: u64,
+    },
+    Operation(Operation),
+}
+
+// The next message, or None once the other side closed the connection
+pub fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Message>> {
+    let mut line = String::new();
+    let read = reader.take(MAX_MESSAGE).read_line(&mut line)?;
+    if read == 0 {
+        return Ok(None);
+    }
+    if !line.ends_with('\n') && read as u64 == MAX_MESSAGE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Message from the other side is too large",
+        ));
+    }
+
+    serde_json::from_str(&line)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+// Every operation seen on this instance, with the stamp of the last write to each note
+pub struct OperationLog {
+    pub peer: u64,
+    clock: u64,
+    last_write: HashMap<NoteId, Stamp>,
+    pub operations: Vec<Operation>,
+}
+
+impl OperationLog {
+    pub fn new(peer: u64) -> Self {
+        Self {
+            peer,
+            clock: 0,
+            last_write: HashMap::new(),
+            operations: Vec::new(),
+        }
+    }
+
+    pub fn clock(&self) -> u64 {
+        self.clock
+    }
+
+    // Operations from a snapshot happened before anything this instance will do
+    pub fn observe(&mut self, clock: u64) {
+        self.clock = self.clock.max(clock);
+    }
+
+    fn touch(&mut self, edit: &NoteEdit, stamp: Stamp) {
+        for note in edit.removed.iter().chain(edit.added.iter()) {
+            self.last_write.insert(note.id, stamp);
+        }
+    }
+
+    // Stamps an edit made here, it always wins over what came before
+    pub fn local(&mut self, edit: NoteEdit) -> Operation {
+        self.clock += 1;
+        let stamp = Stamp {
+            clock: self.clock,
+            peer: self.peer,
+        };
+
+        self.touch(&edit, stamp);
+        let operation = Operation { stamp, edit };
+        self.operations.push(operation.clone());
+        operation
+    }
+
+    // Applies the parts of a remote operation that are newer than the last write to each note,
+    // returns whether any note changed. A note edited on both sides ends up as the later version
+    pub fn remote(&mut self, operation: Operation, notes: &mut Vec<Note>) -> bool {
+        self.observe(operation.stamp.clock);
+        let stamp = operation.stamp;
+        let edit = &operation.edit;
+
+        let newer: HashSet<NoteId> = edit
+            .removed
+            .iter()
+            .chain(edit.added.iter())
+            .map(|note| note.id)
+            .filter(|id| self.last_write.get(id).is_none_or(|last| *last < stamp))
+            .collect();
+
+        // Whatever version of the note is here goes, not only the one the other side removed
+        let removed: HashSet<NoteId> = edit
+            .removed
+            .iter()
+            .map(|note| note.id)
+            .filter(|id| newer.contains(id))
+            .collect();
+        let count = notes.len();
+        notes.retain(|note| !removed.contains(&note.id));
+        let mut changed = notes.len() != count;
+
+        let present: HashSet<NoteId> = notes.iter().map(|note| note.id).collect();
+        for note in edit.added.iter() {
+            if !newer.contains(&note.id) || present.contains(&note.id) {
+                continue;
+            }
+            // Same place Change::replace puts it, after notes with an equal time
+            let index = notes.partition_point(|n| n.millisecond <= note.millisecond);
+            notes.insert(index, *note);
+            changed = true;
+        }
+
+        for id in newer {
+            self.last_write.insert(id, stamp);
+        }
+
+        self.operations.push(operation);
+        changed
+    }
+}
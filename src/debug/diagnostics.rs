@@ -0,0 +1,196 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    log::{BoxedLayer, tracing_subscriber::fmt},
+    prelude::*,
+};
+use zip::{ZipWriter, write::SimpleFileOptions};
+
+use crate::{
+    editor::shortcuts::{EditorAction, EditorActionEvent},
+    maps::{CurrentMap, Map, library::MapLibrary, map_file},
+    settings::{Settings, config_dir, recovery::SafeMode},
+};
+
+const LOG_FOLDER: &str = "logs";
+
+// Sessions whose logs are kept, older ones are removed when a new session starts
+const KEPT_LOGS: usize = 5;
+
+// Enough to see the format, version and metadata of a map without sharing its audio
+const HEADER_BYTES: u64 = 512;
+
+pub fn log_folder() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(LOG_FOLDER))
+}
+
+fn seconds_since_epoch() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+}
+
+// Newest first, named by when their session started
+fn recent_logs(folder: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(folder) else {
+        return Vec::new();
+    };
+
+    let mut logs: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == "log"))
+        .collect();
+    logs.sort_by(|a, b| b.cmp(a));
+    logs
+}
+
+// Every session also logs into a file of its own, so there is something to attach to bug
+// reports after the window is gone. Passed to the log plugin, which calls it before logging works.
+pub fn file_log_layer(_app: &mut App) -> Option<BoxedLayer> {
+    let folder = log_folder()?;
+    fs::create_dir_all(&folder).ok()?;
+
+    for old in recent_logs(&folder).into_iter().skip(KEPT_LOGS - 1) {
+        let _ = fs::remove_file(old);
+    }
+
+    let path = folder.join(format!("session-{}.log", seconds_since_epoch()));
+    let file = File::create(path).ok()?;
+    Some(Box::new(
+        fmt::layer().with_ansi(false).with_writer(Mutex::new(file)),
+    ))
+}
+
+// What a bug report needs to start with, written as text at the root of the bundle
+fn summary(safe_mode: &SafeMode, current: Option<&Map>, failing: &[(PathBuf, String)]) -> String {
+    let mut lines = vec![
+        format!("Version: {}", env!("CARGO_PKG_VERSION")),
+        format!(
+            "Platform: {} {}",
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        ),
+        format!(
+            "Safe mode: {} (crashed last session: {})",
+            safe_mode.active, safe_mode.crashed
+        ),
+    ];
+
+    if let Some(map) = current {
+        lines.push(format!(
+            "Current map: {} ({} notes, {:?})",
+            map.title,
+            map.notes.len(),
+            map.format
+        ));
+    }
+
+    for (path, error) in failing {
+        lines.push(format!("Failed to load {}: {error}", path.display()));
+    }
+
+    lines.join("\n") + "\n"
+}
+
+fn read_header(path: &Path) -> io::Result<Vec<u8>> {
+    let mut header = Vec::new();
+    File::open(path)?
+        .take(HEADER_BYTES)
+        .read_to_end(&mut header)?;
+    Ok(header)
+}
+
+pub fn write_diagnostics(
+    path: &Path,
+    settings: &Settings,
+    summary: &str,
+    maps: &[PathBuf],
+) -> io::Result<()> {
+    if let Some(folder) = path.parent() {
+        fs::create_dir_all(folder)?;
+    }
+
+    let mut zip = ZipWriter::new(File::create(path)?);
+    let options = SimpleFileOptions::default();
+
+    zip.start_file("summary.txt", options)?;
+    zip.write_all(summary.as_bytes())?;
+
+    zip.start_file("settings.json", options)?;
+    zip.write_all(serde_json::to_string_pretty(settings)?.as_bytes())?;
+
+    let logs = log_folder().map_or(Vec::new(), |folder| recent_logs(&folder));
+    for log in logs.iter().take(KEPT_LOGS) {
+        let name = log.file_name().unwrap_or_default().to_string_lossy();
+        zip.start_file(format!("logs/{name}"), options)?;
+        zip.write_all(&fs::read(log)?)?;
+    }
+
+    // A map that can't be read shouldn't keep the rest of the bundle from being written
+    for map in maps {
+        let name = map.file_name().unwrap_or_default().to_string_lossy();
+        match read_header(map) {
+            Ok(header) => {
+                zip.start_file(format!("maps/{name}.header"), options)?;
+                zip.write_all(&header)?;
+            }
+            Err(e) => warn!("Could not read the header of {}: {e}", map.display()),
+        }
+    }
+
+    zip.finish()?;
+    Ok(())
+}
+
+// Bundles recent logs, the settings and the start of every map that failed to load, plus the
+// current one, into the export folder
+pub fn export_diagnostics(
+    mut actions: EventReader<EditorActionEvent>,
+    settings: Res<Settings>,
+    safe_mode: Res<SafeMode>,
+    library: Res<MapLibrary>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+    asset_server: Res<AssetServer>,
+) {
+    if !actions
+        .read()
+        .any(|EditorActionEvent(action)| *action == EditorAction::ExportDiagnostics)
+    {
+        return;
+    }
+
+    let _span = info_span!("export_diagnostics").entered();
+
+    let failing: Vec<(PathBuf, String)> = library
+        .entries
+        .iter()
+        .filter_map(|(path, entry)| Some((path.clone(), entry.error.clone()?)))
+        .collect();
+
+    let current = current_map.as_ref().and_then(|m| maps.get(&m.0));
+    let mut headers: Vec<PathBuf> = failing.iter().map(|(path, _)| path.clone()).collect();
+    if let Some(path) = current_map
+        .as_ref()
+        .and_then(|m| map_file(&m.0, &library, &asset_server))
+        .filter(|path| path.is_file())
+    {
+        headers.push(path);
+    }
+
+    let folder = PathBuf::from(settings.export.folder.trim());
+    let path = folder.join(format!("diagnostics-{}.zip", seconds_since_epoch()));
+    let summary = summary(&safe_mode, current, &failing);
+
+    match write_diagnostics(&path, &settings, &summary, &headers) {
+        Ok(()) => info!("Saved diagnostics to {}", path.display()),
+        Err(e) => warn!("Could not save diagnostics to {}: {e}", path.display()),
+    }
+}
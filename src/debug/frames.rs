@@ -0,0 +1,19 @@
+use bevy::prelude::*;
+
+use crate::debug::stats::RollingStats;
+
+const SAMPLE_WINDOW: usize = 600;
+
+// Frame to frame variance is what shows up as hitches, the mean alone hides them
+#[derive(Resource, Debug)]
+pub struct FrameTimes(pub RollingStats);
+
+impl Default for FrameTimes {
+    fn default() -> Self {
+        Self(RollingStats::new(SAMPLE_WINDOW))
+    }
+}
+
+pub fn record_frame_time(time: Res<Time<Real>>, mut frames: ResMut<FrameTimes>) {
+    frames.0.push(time.delta_secs_f64() * 1000.0);
+}
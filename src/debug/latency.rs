@@ -0,0 +1,62 @@
+use std::time::Instant;
+
+use bevy::{
+    input::{keyboard::KeyboardInput, mouse::MouseButtonInput},
+    prelude::*,
+};
+
+use crate::debug::stats::RollingStats;
+
+const SAMPLE_WINDOW: usize = 240;
+
+// Bevy doesn't timestamp input events, so latency is split into what can be measured:
+// - queueing: how long an event may have waited for the frame to start (the last frame time)
+// - processing: time from the frame picking the events up until the end of that frame
+#[derive(Resource, Debug)]
+pub struct InputLatency {
+    pub queueing: RollingStats,
+    pub processing: RollingStats,
+    received: Option<Instant>,
+}
+
+impl Default for InputLatency {
+    fn default() -> Self {
+        Self {
+            queueing: RollingStats::new(SAMPLE_WINDOW),
+            processing: RollingStats::new(SAMPLE_WINDOW),
+            received: None,
+        }
+    }
+}
+
+impl InputLatency {
+    pub fn reset(&mut self) {
+        self.queueing.clear();
+        self.processing.clear();
+        self.received = None;
+    }
+}
+
+pub fn stamp_input_events(
+    time: Res<Time<Real>>,
+    mut keyboard: EventReader<KeyboardInput>,
+    mut mouse: EventReader<MouseButtonInput>,
+    mut latency: ResMut<InputLatency>,
+) {
+    // Only presses matter for sync complaints, releases and repeats would skew the numbers
+    let pressed = keyboard.read().any(|e| e.state.is_pressed() && !e.repeat)
+        | mouse.read().any(|e| e.state.is_pressed());
+
+    if pressed {
+        latency.received = Some(Instant::now());
+        latency.queueing.push(time.delta_secs_f64() * 1000.0);
+    }
+}
+
+pub fn record_input_processed(mut latency: ResMut<InputLatency>) {
+    if let Some(received) = latency.received.take() {
+        latency
+            .processing
+            .push(received.elapsed().as_secs_f64() * 1000.0);
+    }
+}
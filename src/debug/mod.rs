@@ -0,0 +1,167 @@
+pub mod diagnostics;
+pub mod frames;
+pub mod latency;
+pub mod profiler;
+pub mod stats;
+
+use bevy::prelude::*;
+
+use frames::FrameTimes;
+use latency::InputLatency;
+use profiler::{Profiler, ProfilerOverlayText};
+
+use crate::{
+    editor::shortcuts::{EditorAction, EditorActionEvent},
+    player::notes::NotePool,
+    settings::Settings,
+    theme::Theme,
+};
+
+#[derive(Component)]
+pub struct DebugOverlayText;
+
+// Both overlays follow the theme the same way
+type AnyOverlay = Or<(With<DebugOverlayText>, With<ProfilerOverlayText>)>;
+
+#[derive(Resource, Default)]
+pub struct DebugOverlay {
+    pub visible: bool,
+}
+
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        profiler::register_diagnostics(app);
+
+        app.init_resource::<DebugOverlay>()
+            .init_resource::<Profiler>()
+            .init_resource::<InputLatency>()
+            .init_resource::<FrameTimes>()
+            .add_systems(Startup, (spawn_overlay, profiler::spawn_profiler_overlay))
+            .add_systems(First, latency::stamp_input_events)
+            .add_systems(
+                Last,
+                (
+                    latency::record_input_processed,
+                    frames::record_frame_time,
+                    profiler::record_system_timings,
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    (toggle_overlay, update_overlay).chain(),
+                    (profiler::toggle_profiler, profiler::update_profiler_overlay).chain(),
+                    diagnostics::export_diagnostics,
+                ),
+            )
+            .add_systems(
+                Update,
+                apply_overlay_theme.run_if(resource_changed::<Theme>),
+            );
+    }
+}
+
+pub fn spawn_overlay(mut commands: Commands) {
+    commands.spawn((
+        DebugOverlayText,
+        Text::new(""),
+        TextFont {
+            font_size: 14.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            left: Val::Px(8.0),
+            padding: UiRect::all(Val::Px(6.0)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+        Visibility::Hidden,
+    ));
+}
+
+pub fn apply_overlay_theme(
+    theme: Res<Theme>,
+    mut overlay: Query<(&mut BackgroundColor, &mut TextColor), AnyOverlay>,
+) {
+    for (mut background, mut text) in overlay.iter_mut() {
+        background.0 = theme.panel.color().with_alpha(0.8);
+        text.0 = theme.text.color();
+    }
+}
+
+pub fn toggle_overlay(
+    mut actions: EventReader<EditorActionEvent>,
+    mut overlay: ResMut<DebugOverlay>,
+    mut latency: ResMut<InputLatency>,
+    mut frames: ResMut<FrameTimes>,
+    mut pool: ResMut<NotePool>,
+) {
+    for EditorActionEvent(action) in actions.read() {
+        match action {
+            EditorAction::ToggleDebugOverlay => overlay.visible = !overlay.visible,
+            EditorAction::ResetDebugOverlay if overlay.visible => {
+                latency.reset();
+                frames.0.clear();
+            }
+            // Switching renderers restarts the frame samples so both modes can be compared side
+            // by side
+            EditorAction::SwitchNoteRenderer if overlay.visible => {
+                pool.pooled = !pool.pooled;
+                frames.0.clear();
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn update_overlay(
+    overlay: Res<DebugOverlay>,
+    latency: Res<InputLatency>,
+    frames: Res<FrameTimes>,
+    pool: Res<NotePool>,
+    settings: Res<Settings>,
+    mut text: Query<(&mut Text, &mut Visibility), With<DebugOverlayText>>,
+) {
+    let Ok((mut text, mut visibility)) = text.single_mut() else {
+        return;
+    };
+
+    *visibility = match overlay.visible {
+        true => Visibility::Visible,
+        false => Visibility::Hidden,
+    };
+
+    if !overlay.visible {
+        return;
+    }
+
+    let renderer = match pool.pooled {
+        true => format!(
+            "pooled, {}/{} in use, grown {} times",
+            pool.active(),
+            pool.capacity(),
+            pool.grown
+        ),
+        false => format!("spawn per note, {} spawned", pool.active()),
+    };
+
+    let key = |action| match settings.keybinds.get(action) {
+        Some(chord) => chord.to_string(),
+        None => "unbound".to_string(),
+    };
+
+    text.0 = format!(
+        "Input latency ({} to reset)\n  queueing    {}\n  processing  {}\n\
+         Frame time\n  {}\n\
+         Notes ({} to switch)\n  {renderer}",
+        key(EditorAction::ResetDebugOverlay),
+        latency.queueing.summary(),
+        latency.processing.summary(),
+        frames.0.summary(),
+        key(EditorAction::SwitchNoteRenderer),
+    );
+}
@@ -0,0 +1,222 @@
+use std::{
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Instant,
+};
+
+use bevy::{
+    diagnostic::{
+        Diagnostic, DiagnosticPath, Diagnostics, DiagnosticsStore, FrameTimeDiagnosticsPlugin,
+        RegisterDiagnostic,
+    },
+    ecs::system::{Adapt, IntoAdapterSystem, SystemIn},
+    prelude::*,
+};
+
+use crate::{
+    debug::stats::RollingStats,
+    editor::shortcuts::{EditorAction, EditorActionEvent},
+    settings::Settings,
+};
+
+// Frames kept for the percentiles, about ten seconds at 60fps
+pub const HISTORY: usize = 600;
+
+pub static NOTE_SPAWNER: DiagnosticPath = DiagnosticPath::const_new("systems/note_spawner");
+pub static MOD_EVALUATOR: DiagnosticPath = DiagnosticPath::const_new("systems/mod_evaluator");
+pub static RENDERER: DiagnosticPath = DiagnosticPath::const_new("systems/renderer");
+
+const PROFILED: [(&DiagnosticPath, &str); 3] = [
+    (&NOTE_SPAWNER, "note spawner"),
+    (&MOD_EVALUATOR, "mod evaluator"),
+    (&RENDERER, "renderer"),
+];
+
+// Timed systems are built by other plugins before any resource exists, and run on whichever
+// thread the executor picks, so what they measure is collected here until the end of the frame
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static TIMINGS: Mutex<Vec<(&DiagnosticPath, f64)>> = Mutex::new(Vec::new());
+
+#[derive(Component)]
+pub struct ProfilerOverlayText;
+
+// Opt in, systems are only timed while it is shown
+#[derive(Resource, Default)]
+pub struct Profiler {
+    pub visible: bool,
+}
+
+pub struct Timed(&'static DiagnosticPath);
+
+impl<S: System<In = ()>> Adapt<S> for Timed {
+    type In = ();
+    type Out = S::Out;
+
+    fn adapt(&mut self, input: (), run_system: impl FnOnce(SystemIn<'_, S>) -> S::Out) -> S::Out {
+        if !ENABLED.load(Ordering::Relaxed) {
+            return run_system(input);
+        }
+
+        let started = Instant::now();
+        let out = run_system(input);
+        let elapsed = started.elapsed().as_secs_f64() * 1000.0;
+        if let Ok(mut timings) = TIMINGS.lock() {
+            timings.push((self.0, elapsed));
+        }
+        out
+    }
+}
+
+// Adds the system's run time to `path`, several systems can share one path and are summed
+pub fn timed<S, O, M>(path: &'static DiagnosticPath, system: S) -> IntoAdapterSystem<Timed, S>
+where
+    S: IntoSystem<(), O, M>,
+{
+    IntoAdapterSystem::new(Timed(path), system)
+}
+
+pub fn register_diagnostics(app: &mut App) {
+    if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+        app.add_plugins(FrameTimeDiagnosticsPlugin::new(HISTORY));
+    }
+
+    for (path, _) in PROFILED {
+        app.register_diagnostic(
+            Diagnostic::new(path.clone())
+                .with_suffix("ms")
+                .with_max_history_length(HISTORY),
+        );
+    }
+}
+
+pub fn record_system_timings(mut diagnostics: Diagnostics) {
+    let Ok(mut timings) = TIMINGS.lock() else {
+        return;
+    };
+    if timings.is_empty() {
+        return;
+    }
+
+    for (path, _) in PROFILED {
+        let total: f64 = timings
+            .iter()
+            .filter(|(timed, _)| *timed == path)
+            .map(|(_, elapsed)| elapsed)
+            .sum();
+        diagnostics.add_measurement(path, || total);
+    }
+    timings.clear();
+}
+
+pub fn spawn_profiler_overlay(mut commands: Commands) {
+    commands.spawn((
+        ProfilerOverlayText,
+        Text::new(""),
+        TextFont {
+            font_size: 14.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            right: Val::Px(8.0),
+            padding: UiRect::all(Val::Px(6.0)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+        Visibility::Hidden,
+    ));
+}
+
+pub fn toggle_profiler(
+    mut actions: EventReader<EditorActionEvent>,
+    mut profiler: ResMut<Profiler>,
+    mut store: ResMut<DiagnosticsStore>,
+) {
+    if !actions
+        .read()
+        .any(|EditorActionEvent(action)| *action == EditorAction::ToggleProfiler)
+    {
+        return;
+    }
+
+    profiler.visible = !profiler.visible;
+    ENABLED.store(profiler.visible, Ordering::Relaxed);
+
+    // Whatever was measured last time it was shown says nothing about now
+    if profiler.visible {
+        for (path, _) in PROFILED {
+            if let Some(diagnostic) = store.get_mut(path) {
+                diagnostic.clear_history();
+            }
+        }
+        if let Some(frames) = store.get_mut(&FrameTimeDiagnosticsPlugin::FRAME_TIME) {
+            frames.clear_history();
+        }
+    }
+}
+
+fn history(store: &DiagnosticsStore, path: &DiagnosticPath) -> RollingStats {
+    let values: Vec<f64> = store.get(path).map_or(Vec::new(), |diagnostic| {
+        diagnostic.values().copied().collect()
+    });
+    let mut stats = RollingStats::new(values.len().max(1));
+    for value in values {
+        stats.push(value);
+    }
+    stats
+}
+
+fn format_ms(value: Option<f64>) -> String {
+    value.map_or("-".to_string(), |ms| format!("{ms:.2}ms"))
+}
+
+pub fn update_profiler_overlay(
+    profiler: Res<Profiler>,
+    store: Res<DiagnosticsStore>,
+    settings: Res<Settings>,
+    mut text: Query<(&mut Text, &mut Visibility), With<ProfilerOverlayText>>,
+) {
+    let Ok((mut text, mut visibility)) = text.single_mut() else {
+        return;
+    };
+
+    *visibility = match profiler.visible {
+        true => Visibility::Visible,
+        false => Visibility::Hidden,
+    };
+
+    if !profiler.visible {
+        return;
+    }
+
+    let frames = history(&store, &FrameTimeDiagnosticsPlugin::FRAME_TIME);
+    let mut lines = vec![
+        match settings.keybinds.get(EditorAction::ToggleProfiler) {
+            Some(chord) => format!("Frame time ({chord} to hide)"),
+            None => "Frame time".to_string(),
+        },
+        format!(
+            "  p50 {}  p95 {}  p99 {}  max {}",
+            format_ms(frames.percentile(0.5)),
+            format_ms(frames.percentile(0.95)),
+            format_ms(frames.percentile(0.99)),
+            format_ms(frames.max()),
+        ),
+        "Systems per frame".to_string(),
+    ];
+
+    for (path, name) in PROFILED {
+        let stats = history(&store, path);
+        lines.push(format!(
+            "  {name:<14} mean {}  p95 {}  max {}",
+            format_ms(stats.mean()),
+            format_ms(stats.percentile(0.95)),
+            format_ms(stats.max()),
+        ));
+    }
+
+    text.0 = lines.join("\n");
+}
@@ -0,0 +1,90 @@
+use std::collections::VecDeque;
+
+// Fixed size window of samples, oldest ones are dropped once capacity is reached
+#[derive(Debug, Clone)]
+pub struct RollingStats {
+    capacity: usize,
+    samples: VecDeque<f64>,
+}
+
+impl RollingStats {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            samples: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn push(&mut self, sample: f64) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+
+        self.samples.push_back(sample);
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        if self.samples.is_empty() {
+            return None;
+        }
+
+        Some(self.samples.iter().sum::<f64>() / self.samples.len() as f64)
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        self.samples.iter().copied().reduce(f64::min)
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.samples.iter().copied().reduce(f64::max)
+    }
+
+    pub fn std_dev(&self) -> Option<f64> {
+        let mean = self.mean()?;
+        let variance = self.samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>()
+            / self.samples.len() as f64;
+
+        Some(variance.sqrt())
+    }
+
+    // Nearest-rank percentile, `p` in the 0..=1 range
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        if self.samples.is_empty() {
+            return None;
+        }
+
+        let mut sorted: Vec<f64> = self.samples.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+
+        let rank = ((p.clamp(0.0, 1.0) * sorted.len() as f64).ceil() as usize).max(1);
+        Some(sorted[rank - 1])
+    }
+
+    // One line summary in milliseconds, used by the debug overlay
+    pub fn summary(&self) -> String {
+        match (
+            self.mean(),
+            self.percentile(0.95),
+            self.max(),
+            self.std_dev(),
+        ) {
+            (Some(mean), Some(p95), Some(max), Some(std_dev)) => format!(
+                "mean {mean:.2}ms  p95 {p95:.2}ms  max {max:.2}ms  sd {std_dev:.2}ms (n={})",
+                self.len()
+            ),
+            _ => "no samples".to_string(),
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use bevy::{
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, Task, block_on, futures_lite::future},
+};
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    analysis::alignment::{AlignmentReport, MIN_CONFIDENCE, check_alignment},
+    editor::{
+        commands::EditCommand,
+        editing::EditRequest,
+        shortcuts::{EditorAction, EditorActionEvent},
+    },
+    jukebox::DecodedAudio,
+    maps::{CurrentMap, Map, format_time},
+    player::playback::PlaybackClock,
+};
+
+#[derive(Resource, Default)]
+pub struct AlignmentPanel {
+    pub open: bool,
+    task: Option<Task<Option<AlignmentReport>>>,
+    // None inside when no offset could be found
+    report: Option<Option<AlignmentReport>>,
+}
+
+pub fn toggle_alignment_panel(
+    mut actions: EventReader<EditorActionEvent>,
+    mut panel: ResMut<AlignmentPanel>,
+) {
+    for EditorActionEvent(action) in actions.read() {
+        if *action == EditorAction::ToggleAlignmentPanel {
+            panel.open = !panel.open;
+        }
+    }
+}
+
+pub fn poll_alignment(mut panel: ResMut<AlignmentPanel>) {
+    let Some(report) = panel
+        .task
+        .as_mut()
+        .and_then(|task| block_on(future::poll_once(task)))
+    else {
+        return;
+    };
+
+    panel.task = None;
+    panel.report = Some(report);
+}
+
+pub fn alignment_ui(
+    mut contexts: EguiContexts,
+    mut panel: ResMut<AlignmentPanel>,
+    mut clock: ResMut<PlaybackClock>,
+    mut requests: EventWriter<EditRequest>,
+    mut decoded: ResMut<DecodedAudio>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+) -> Result {
+    if !panel.open {
+        return Ok(());
+    }
+
+    // Onsets are found in the samples, streamed audio is decoded once the panel is opened
+    if decoded.pcm.is_none() {
+        decoded.request_pcm();
+    }
+
+    let map = current_map.and_then(|m| maps.get(&m.0));
+    let mut open = panel.open;
+    let mut check = false;
+    let mut shift = None;
+
+    egui::Window::new("Audio alignment")
+        .open(&mut open)
+        .resizable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.label("Compares note times with the onsets in the audio");
+
+            let ready = decoded.pcm.is_some() && map.is_some_and(|map| !map.notes.is_empty());
+            ui.horizontal(|ui| {
+                let button =
+                    ui.add_enabled(ready && panel.task.is_none(), egui::Button::new("Check"));
+                if button.clicked() {
+                    check = true;
+                }
+
+                if panel.task.is_some() {
+                    ui.spinner();
+                } else if decoded.is_decoding() {
+                    ui.label("Decoding audio...");
+                }
+            });
+
+            let Some(report) = &panel.report else {
+                return;
+            };
+            ui.separator();
+
+            let Some(report) = report else {
+                ui.label("No onsets in the audio line up with the notes");
+                return;
+            };
+
+            ui.label(format!(
+                "Suggested offset {:+.1}ms ({:.0}% confidence)",
+                report.offset_ms,
+                report.confidence * 100.0
+            ));
+            if report.confidence < MIN_CONFIDENCE {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    "The audio has no clear onsets, the offset is a guess",
+                );
+            }
+
+            let milliseconds = report.offset_ms.round() as i64;
+            if milliseconds != 0
+                && ui
+                    .button(format!("Shift every note by {milliseconds:+}ms"))
+                    .clicked()
+            {
+                shift = Some(milliseconds);
+            }
+
+            let misaligned: Vec<_> = report.misaligned().collect();
+            if misaligned.is_empty() {
+                ui.label("Every section agrees with the whole chart");
+                return;
+            }
+
+            ui.label("Sections that are early or late compared to the rest:");
+            egui::Grid::new("misaligned_sections")
+                .num_columns(3)
+                .show(ui, |ui| {
+                    for section in misaligned {
+                        ui.label(format!(
+                            "{} to {}",
+                            format_time(section.start),
+                            format_time(section.end)
+                        ));
+                        ui.label(format!("{:+.1}ms", section.offset_ms));
+                        if ui.small_button("Go").clicked() {
+                            clock.seek(section.start as f64);
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+
+    panel.open = open;
+
+    if check && let (Some(pcm), Some(map)) = (decoded.pcm.clone(), map) {
+        let notes = map.notes.clone();
+        panel.report = None;
+        panel.task = Some(
+            AsyncComputeTaskPool::get()
+                .spawn(async move { check_alignment(&pcm.mono(), pcm.sample_rate, &notes) }),
+        );
+    }
+
+    if let Some(milliseconds) = shift {
+        requests.write(EditRequest::Execute(EditCommand::SelectAll));
+        requests.write(EditRequest::Execute(EditCommand::Offset { milliseconds }));
+        // The notes moved, the old report no longer applies
+        panel.report = None;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,203 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    editor::{
+        commands::Selection,
+        shortcuts::{EditorAction, EditorActionEvent},
+        timeline::SnapSettings,
+    },
+    maps::{Annotation, CurrentMap, Map},
+    player::playback::PlaybackClock,
+};
+
+// Review comments on notes and timestamps, stored in the map so they travel with it between mappers
+#[derive(Resource, Default)]
+pub struct AnnotationPanel {
+    pub open: bool,
+    draft: String,
+    show_resolved: bool,
+}
+
+pub fn toggle_annotations(
+    mut actions: EventReader<EditorActionEvent>,
+    mut panel: ResMut<AnnotationPanel>,
+) {
+    for EditorActionEvent(action) in actions.read() {
+        if *action == EditorAction::ToggleAnnotations {
+            panel.open = !panel.open;
+        }
+    }
+}
+
+fn format_position(annotation: &Annotation) -> String {
+    match annotation.position {
+        Some([x, y]) => format!("note at {x:.2}, {y:.2}"),
+        None => "timestamp".to_string(),
+    }
+}
+
+pub fn annotations_ui(
+    mut contexts: EguiContexts,
+    mut panel: ResMut<AnnotationPanel>,
+    mut clock: ResMut<PlaybackClock>,
+    mut selection: ResMut<Selection>,
+    snap: Res<SnapSettings>,
+    current_map: Option<Res<CurrentMap>>,
+    mut maps: ResMut<Assets<Map>>,
+) -> Result {
+    if !panel.open {
+        return Ok(());
+    }
+
+    let Some(current_map) = current_map else {
+        return Ok(());
+    };
+
+    // Only borrow the map mutably when something was edited, like the bookmarks
+    let Some(map) = maps.get(&current_map.0) else {
+        return Ok(());
+    };
+    let annotations = map.annotations.clone();
+    let playhead = map.snap(clock.millisecond, snap.divisor).max(0.0) as u32;
+    // Comments go on the selected note when exactly one is selected
+    let selected_note = match selection.0.len() {
+        1 => selection.0.first().and_then(|&i| map.notes.get(i)).copied(),
+        _ => None,
+    };
+
+    let mut edited = annotations.clone();
+    let mut removed = None;
+    let mut added = None;
+    let mut go_to = None;
+    let mut open = panel.open;
+    let panel = panel.as_mut();
+
+    egui::Window::new("Review comments")
+        .open(&mut open)
+        .default_width(380.0)
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.add(
+                egui::TextEdit::multiline(&mut panel.draft)
+                    .desired_rows(2)
+                    .hint_text("Feedback for the other mappers"),
+            );
+
+            ui.horizontal(|ui| {
+                let has_text = !panel.draft.trim().is_empty();
+
+                if ui
+                    .add_enabled(has_text, egui::Button::new("Add at playhead"))
+                    .clicked()
+                {
+                    added = Some(Annotation {
+                        millisecond: playhead,
+                        position: None,
+                        text: panel.draft.trim().to_string(),
+                        resolved: false,
+                    });
+                }
+
+                if ui
+                    .add_enabled(
+                        has_text && selected_note.is_some(),
+                        egui::Button::new("Add on selected note"),
+                    )
+                    .on_disabled_hover_text("Select a single note")
+                    .clicked()
+                    && let Some(note) = selected_note
+                {
+                    added = Some(Annotation {
+                        millisecond: note.millisecond,
+                        position: Some(note.position.to_array()),
+                        text: panel.draft.trim().to_string(),
+                        resolved: false,
+                    });
+                }
+            });
+
+            ui.separator();
+
+            let open_count = edited.iter().filter(|a| !a.resolved).count();
+            ui.horizontal(|ui| {
+                ui.label(format!("{open_count} open, {} total", edited.len()));
+                ui.checkbox(&mut panel.show_resolved, "Show resolved");
+            });
+
+            if edited.is_empty() {
+                ui.label("No comments yet");
+            }
+
+            egui::ScrollArea::vertical()
+                .max_height(320.0)
+                .show(ui, |ui| {
+                    for (index, annotation) in edited.iter_mut().enumerate() {
+                        if annotation.resolved && !panel.show_resolved {
+                            continue;
+                        }
+
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut annotation.resolved, "")
+                                .on_hover_text("Resolved");
+                            ui.label(format!(
+                                "{}ms, {}",
+                                annotation.millisecond,
+                                format_position(annotation)
+                            ));
+
+                            if ui.button("Go").clicked() {
+                                go_to = Some(index);
+                            }
+                            if ui.button("Delete").clicked() {
+                                removed = Some(index);
+                            }
+                        });
+
+                        ui.add(
+                            egui::TextEdit::multiline(&mut annotation.text)
+                                .desired_rows(1)
+                                .desired_width(f32::INFINITY),
+                        );
+                        ui.add_space(4.0);
+                    }
+                });
+        });
+
+    panel.open = open;
+
+    if let Some(index) = go_to
+        && let Some(annotation) = edited.get(index)
+    {
+        clock.seek(annotation.millisecond as f64);
+
+        // Notes on the comment are picked so they're easy to find and fix
+        if annotation.position.is_some() {
+            selection.0 = map
+                .notes
+                .iter()
+                .enumerate()
+                .filter(|(_, note)| annotation.is_on(note))
+                .map(|(index, _)| index)
+                .collect();
+        }
+    }
+
+    if let Some(index) = removed {
+        edited.remove(index);
+    }
+
+    if let Some(annotation) = added {
+        panel.draft.clear();
+        let index = edited.partition_point(|a| a.millisecond <= annotation.millisecond);
+        edited.insert(index, annotation);
+    }
+
+    if edited != annotations
+        && let Some(map) = maps.get_mut(&current_map.0)
+    {
+        edited.sort_by_key(|annotation| annotation.millisecond);
+        map.annotations = edited;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    maps::{
+        CurrentMap, Map,
+        incremental::{IncrementalSave, SaveKind},
+    },
+    settings::config_dir,
+};
+
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(60);
+const AUTOSAVE_FOLDER: &str = "autosave";
+
+#[derive(Resource)]
+pub struct Autosave {
+    timer: Timer,
+    dirty: bool,
+    saver: Option<IncrementalSave>,
+}
+
+impl Default for Autosave {
+    fn default() -> Self {
+        Self {
+            timer: Timer::new(AUTOSAVE_INTERVAL, TimerMode::Repeating),
+            dirty: false,
+            saver: None,
+        }
+    }
+}
+
+pub fn autosave_folder() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(AUTOSAVE_FOLDER))
+}
+
+// Newest first
+pub fn recent_autosaves(count: usize) -> Vec<(PathBuf, SystemTime)> {
+    let Some(entries) = autosave_folder().and_then(|folder| fs::read_dir(folder).ok()) else {
+        return Vec::new();
+    };
+
+    let mut autosaves: Vec<(PathBuf, SystemTime)> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().extension().is_some_and(|e| e == "sspm"))
+        .filter_map(|entry| Some((entry.path(), entry.metadata().ok()?.modified().ok()?)))
+        .collect();
+
+    autosaves.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
+    autosaves.truncate(count);
+    autosaves
+}
+
+pub fn track_map_changes(
+    mut events: EventReader<AssetEvent<Map>>,
+    current_map: Option<Res<CurrentMap>>,
+    mut autosave: ResMut<Autosave>,
+) {
+    let Some(current_map) = current_map else {
+        events.clear();
+        return;
+    };
+
+    if events
+        .read()
+        .any(|event| event.is_modified(current_map.0.id()))
+    {
+        autosave.dirty = true;
+    }
+}
+
+pub fn run_autosave(
+    time: Res<Time<Real>>,
+    mut autosave: ResMut<Autosave>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+) {
+    if !autosave.timer.tick(time.delta()).just_finished() || !autosave.dirty {
+        return;
+    }
+
+    let (Some(current_map), Some(folder)) = (current_map, autosave_folder()) else {
+        return;
+    };
+
+    let Some(map) = maps.get(&current_map.0) else {
+        return;
+    };
+
+    let name = if map.id.is_empty() {
+        "untitled"
+    } else {
+        &map.id
+    };
+    let path = folder.join(format!("{name}.sspm"));
+
+    // A different map starts over with a full save
+    let saver = match &mut autosave.saver {
+        Some(saver) if saver.path() == path => saver,
+        saver => saver.insert(IncrementalSave::new(path)),
+    };
+
+    let started = Instant::now();
+
+    match saver.save(map) {
+        Ok(kind) => {
+            let kind = match kind {
+                SaveKind::Full => "full",
+                SaveKind::Incremental => "incremental",
+            };
+            info!(
+                "Autosaved to {} ({kind}, {:.1}ms)",
+                saver.path().display(),
+                started.elapsed().as_secs_f64() * 1000.0
+            );
+            autosave.dirty = false;
+        }
+        Err(e) => warn!("Autosave to {} failed: {e}", saver.path().display()),
+    }
+}
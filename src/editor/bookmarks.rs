@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    editor::{
+        shortcuts::{EditorAction, EditorActionEvent},
+        timeline::SnapSettings,
+    },
+    maps::{Bookmark, CurrentMap, Map},
+    player::playback::PlaybackClock,
+};
+
+// New bookmarks cycle through these so neighbouring sections are easy to tell apart
+const PALETTE: [[u8; 3]; 6] = [
+    [230, 90, 80],
+    [240, 170, 60],
+    [230, 220, 90],
+    [110, 200, 110],
+    [80, 160, 230],
+    [180, 120, 220],
+];
+
+#[derive(Resource, Default)]
+pub struct BookmarkPanel {
+    pub open: bool,
+}
+
+pub fn bookmark_actions(
+    mut actions: EventReader<EditorActionEvent>,
+    mut clock: ResMut<PlaybackClock>,
+    snap: Res<SnapSettings>,
+    current_map: Option<Res<CurrentMap>>,
+    mut maps: ResMut<Assets<Map>>,
+) {
+    let Some(current_map) = current_map else {
+        return;
+    };
+
+    for EditorActionEvent(action) in actions.read() {
+        match action {
+            EditorAction::AddBookmark => {
+                let Some(map) = maps.get_mut(&current_map.0) else {
+                    continue;
+                };
+
+                let millisecond = map.snap(clock.millisecond, snap.divisor).max(0.0) as u32;
+                let number = map.bookmarks.len() + 1;
+
+                map.add_bookmark(Bookmark {
+                    millisecond,
+                    label: format!("Bookmark {number}"),
+                    color: PALETTE[(number - 1) % PALETTE.len()],
+                });
+            }
+            EditorAction::NextBookmark | EditorAction::PreviousBookmark => {
+                let Some(map) = maps.get(&current_map.0) else {
+                    continue;
+                };
+
+                let target = match action {
+                    EditorAction::NextBookmark => map.next_bookmark(clock.millisecond),
+                    _ => map.previous_bookmark(clock.millisecond),
+                };
+
+                if let Some(bookmark) = target {
+                    clock.seek(bookmark.millisecond as f64);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn bookmarks_ui(
+    mut contexts: EguiContexts,
+    mut panel: ResMut<BookmarkPanel>,
+    mut clock: ResMut<PlaybackClock>,
+    current_map: Option<Res<CurrentMap>>,
+    mut maps: ResMut<Assets<Map>>,
+) -> Result {
+    if !panel.open {
+        return Ok(());
+    }
+
+    let Some(current_map) = current_map else {
+        return Ok(());
+    };
+
+    // Only borrow the map mutably when something was edited, so looking doesn't mark it as modified
+    let Some(bookmarks) = maps.get(&current_map.0).map(|map| map.bookmarks.clone()) else {
+        return Ok(());
+    };
+
+    let mut edited = bookmarks.clone();
+    let mut removed = None;
+    let mut open = panel.open;
+
+    egui::Window::new("Bookmarks")
+        .open(&mut open)
+        .show(contexts.ctx_mut()?, |ui| {
+            if edited.is_empty() {
+                ui.label("No bookmarks yet");
+            }
+
+            egui::Grid::new("bookmark_list")
+                .num_columns(5)
+                .show(ui, |ui| {
+                    for (index, bookmark) in edited.iter_mut().enumerate() {
+                        ui.color_edit_button_srgb(&mut bookmark.color);
+                        ui.text_edit_singleline(&mut bookmark.label);
+                        ui.add(egui::DragValue::new(&mut bookmark.millisecond).suffix("ms"));
+
+                        if ui.button("Go").clicked() {
+                            clock.seek(bookmark.millisecond as f64);
+                        }
+                        if ui.button("Delete").clicked() {
+                            removed = Some(index);
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+
+    panel.open = open;
+
+    if let Some(index) = removed {
+        edited.remove(index);
+    }
+
+    if edited != bookmarks
+        && let Some(map) = maps.get_mut(&current_map.0)
+    {
+        edited.sort_by_key(|bookmark| bookmark.millisecond);
+        map.bookmarks = edited;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,227 @@
+use std::collections::BTreeSet;
+
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    editor::{
+        commands::EditCommand,
+        editing::EditRequest,
+        shortcuts::{EditorAction, EditorActionEvent},
+    },
+    maps::{CurrentMap, Map, merge::plan_merge, objects::Note, parser::is_quantum},
+    player::playback::PlaybackClock,
+    settings::Settings,
+};
+
+// Which notes a bulk delete removes, every criterion that is set has to match
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NoteFilter {
+    // Inclusive on both ends
+    pub time: Option<(u32, u32)>,
+    // Corners of a rectangle on the grid, inclusive
+    pub region: Option<(Vec2, Vec2)>,
+    pub quantum_only: bool,
+    // Only every nth of the notes matching the rest, counting from the first
+    pub every_nth: Option<usize>,
+}
+
+impl NoteFilter {
+    pub fn matches(&self, notes: &[Note]) -> BTreeSet<usize> {
+        let candidates = notes.iter().enumerate().filter(|(_, note)| {
+            let in_time = self
+                .time
+                .is_none_or(|(start, end)| (start..=end).contains(&note.millisecond));
+            let in_region = self.region.is_none_or(|(a, b)| {
+                let (min, max) = (a.min(b), a.max(b));
+                note.position.cmpge(min).all() && note.position.cmple(max).all()
+            });
+
+            in_time && in_region && (!self.quantum_only || is_quantum(note.position))
+        });
+
+        match self.every_nth.filter(|&n| n > 1) {
+            Some(n) => candidates
+                .enumerate()
+                .filter(|(count, _)| (count + 1) % n == 0)
+                .map(|(_, (index, _))| index)
+                .collect(),
+            None => candidates.map(|(index, _)| index).collect(),
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct BulkDeleteTool {
+    pub open: bool,
+    filter: NoteFilter,
+    // Kept while a criterion is switched off so turning it back on doesn't lose the values
+    time: (u32, u32),
+    region: (Vec2, Vec2),
+    nth: usize,
+    // Starts at the one from the preferences
+    merge_epsilon_ms: u32,
+}
+
+pub fn toggle_bulk_delete(
+    mut actions: EventReader<EditorActionEvent>,
+    mut tool: ResMut<BulkDeleteTool>,
+) {
+    for EditorActionEvent(action) in actions.read() {
+        if *action == EditorAction::ToggleBulkDelete {
+            tool.open = !tool.open;
+        }
+    }
+}
+
+pub fn bulk_delete_ui(
+    mut contexts: EguiContexts,
+    mut tool: ResMut<BulkDeleteTool>,
+    mut requests: EventWriter<EditRequest>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+    clock: Res<PlaybackClock>,
+    settings: Res<Settings>,
+) -> Result {
+    if !tool.open {
+        return Ok(());
+    }
+
+    let Some(map) = current_map.and_then(|m| maps.get(&m.0)) else {
+        return Ok(());
+    };
+
+    let mut open = tool.open;
+    let mut delete = false;
+    let mut merge = false;
+    let tool = tool.as_mut();
+
+    if tool.nth == 0 {
+        tool.nth = 2;
+        tool.region = (Vec2::ZERO, Vec2::splat(2.0));
+        tool.time = (0, map.length);
+        tool.merge_epsilon_ms = settings.merge_epsilon_ms;
+    }
+
+    egui::Window::new("Bulk delete")
+        .open(&mut open)
+        .resizable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            let playhead = clock.millisecond as u32;
+
+            egui::Grid::new("bulk_delete")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    let mut use_time = tool.filter.time.is_some();
+                    ui.checkbox(&mut use_time, "Between");
+                    ui.horizontal(|ui| {
+                        ui.add_enabled_ui(use_time, |ui| {
+                            ui.add(egui::DragValue::new(&mut tool.time.0).suffix("ms"));
+                            if ui.small_button("Playhead").clicked() {
+                                tool.time.0 = playhead;
+                            }
+                            ui.label("and");
+                            ui.add(egui::DragValue::new(&mut tool.time.1).suffix("ms"));
+                            if ui.small_button("Playhead").clicked() {
+                                tool.time.1 = playhead;
+                            }
+                        });
+                    });
+                    tool.filter.time = use_time.then_some(tool.time);
+                    ui.end_row();
+
+                    let mut use_region = tool.filter.region.is_some();
+                    ui.checkbox(&mut use_region, "Inside");
+                    ui.horizontal(|ui| {
+                        ui.add_enabled_ui(use_region, |ui| {
+                            let (from, to) = &mut tool.region;
+                            for value in [&mut from.x, &mut from.y] {
+                                ui.add(egui::DragValue::new(value).range(0.0..=2.0).speed(0.05));
+                            }
+                            ui.label("to");
+                            for value in [&mut to.x, &mut to.y] {
+                                ui.add(egui::DragValue::new(value).range(0.0..=2.0).speed(0.05));
+                            }
+                        });
+                    });
+                    tool.filter.region = use_region.then_some(tool.region);
+                    ui.end_row();
+
+                    ui.checkbox(&mut tool.filter.quantum_only, "Quantum only")
+                        .on_hover_text("Notes between grid cells");
+                    ui.end_row();
+
+                    let mut use_nth = tool.filter.every_nth.is_some();
+                    ui.checkbox(&mut use_nth, "Every");
+                    ui.add_enabled(
+                        use_nth,
+                        egui::DragValue::new(&mut tool.nth)
+                            .range(2..=64)
+                            .suffix(" notes"),
+                    );
+                    tool.filter.every_nth = use_nth.then_some(tool.nth);
+                    ui.end_row();
+                });
+
+            ui.separator();
+
+            let matching = tool.filter.matches(&map.notes).len();
+            let everything = tool.filter == NoteFilter::default();
+            match everything {
+                true => ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!("No criteria set, all {matching} notes match"),
+                ),
+                false => ui.label(format!("{matching} of {} notes match", map.notes.len())),
+            };
+
+            let button = egui::Button::new(
+                egui::RichText::new(format!("Delete {matching} notes"))
+                    .color(ui.visuals().error_fg_color),
+            );
+            delete = ui
+                .add_enabled(matching > 0, button)
+                .on_hover_text("Undone in one step")
+                .clicked();
+
+            ui.separator();
+
+            // Notes a few ms apart from tools that round times, merged across the whole map
+            ui.horizontal(|ui| {
+                ui.label("Merge notes within");
+                ui.add(
+                    egui::DragValue::new(&mut tool.merge_epsilon_ms)
+                        .range(1..=20)
+                        .suffix("ms"),
+                );
+            });
+
+            let (_, _, report) = plan_merge(&map.notes, tool.merge_epsilon_ms);
+            ui.label(format!(
+                "{} duplicates to remove, {} notes to align",
+                report.merged, report.aligned
+            ));
+            merge = ui
+                .add_enabled(!report.is_empty(), egui::Button::new("Merge"))
+                .on_hover_text("Duplicates on the same spot keep the first note")
+                .clicked();
+        });
+
+    tool.open = open;
+
+    if delete {
+        requests.write(EditRequest::Execute(EditCommand::DeleteMatching {
+            filter: tool.filter.clone(),
+        }));
+    }
+
+    if merge {
+        requests.write(EditRequest::Execute(EditCommand::MergeClose {
+            epsilon_ms: tool.merge_epsilon_ms,
+        }));
+    }
+
+    Ok(())
+}
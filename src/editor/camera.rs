@@ -0,0 +1,335 @@
+use std::collections::HashMap;
+
+use bevy::{
+    input::mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll},
+    prelude::*,
+    render::{camera::ScalingMode, view::RenderLayers},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    editor::shortcuts::{EditorAction, EditorActionEvent},
+    gameplay::feedback::Feedback,
+    modchart::{ModChannel, ModState, ScreenFrame},
+    player::playfield::APPROACH_DISTANCE,
+    settings::Settings,
+};
+
+const FLY_SPEED: f32 = 8.0;
+const FLY_BOOST: f32 = 4.0;
+const LOOK_SENSITIVITY: f32 = 0.003;
+const TOP_DOWN_HEIGHT: f32 = 20.0;
+const TOP_DOWN_PAN_SPEED: f32 = 2.0;
+
+// Editor only visuals like drag ghosts and the heatmap, hidden from the preview window
+pub const EDITOR_LAYER: usize = 1;
+
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct EditorGizmos;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum CameraMode {
+    #[default]
+    Gameplay,
+    FreeFly,
+    TopDown,
+}
+
+impl CameraMode {
+    fn from_action(action: EditorAction) -> Option<CameraMode> {
+        match action {
+            EditorAction::CameraGameplay => Some(CameraMode::Gameplay),
+            EditorAction::CameraFreeFly => Some(CameraMode::FreeFly),
+            EditorAction::CameraTopDown => Some(CameraMode::TopDown),
+            _ => None,
+        }
+    }
+
+    fn default_transform(&self) -> Transform {
+        match self {
+            CameraMode::Gameplay => {
+                Transform::from_xyz(0.0, 0.0, 4.0).looking_at(Vec3::ZERO, Vec3::Y)
+            }
+            CameraMode::FreeFly => {
+                Transform::from_xyz(6.0, 4.0, 6.0).looking_at(Vec3::new(0.0, 0.0, -5.0), Vec3::Y)
+            }
+            CameraMode::TopDown => {
+                let center = Vec3::new(0.0, 0.0, -APPROACH_DISTANCE / 2.0);
+                Transform::from_translation(center + Vec3::Y * TOP_DOWN_HEIGHT)
+                    .looking_at(center, Vec3::NEG_Z)
+            }
+        }
+    }
+
+    fn projection(&self) -> Projection {
+        match self {
+            CameraMode::TopDown => Projection::Orthographic(OrthographicProjection {
+                scaling_mode: ScalingMode::FixedVertical {
+                    viewport_height: APPROACH_DISTANCE,
+                },
+                ..OrthographicProjection::default_3d()
+            }),
+            _ => Projection::Perspective(PerspectiveProjection::default()),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct EditorCamera;
+
+// Every mode keeps its own transform so switching back lands where the user left it
+#[derive(Resource, Default)]
+pub struct EditorCameraState {
+    pub mode: CameraMode,
+    saved: HashMap<CameraMode, Transform>,
+}
+
+impl EditorCameraState {
+    pub fn switch(
+        &mut self,
+        mode: CameraMode,
+        transform: &mut Transform,
+        projection: &mut Projection,
+    ) {
+        let previous = self.mode;
+        self.saved.insert(previous, *transform);
+        self.mode = mode;
+
+        *transform = self.transform_for(mode);
+        *projection = mode.projection();
+    }
+
+    fn transform_for(&self, mode: CameraMode) -> Transform {
+        self.saved
+            .get(&mode)
+            .copied()
+            .unwrap_or_else(|| mode.default_transform())
+    }
+
+    pub fn view(&self, transform: &Transform, projection: &Projection) -> CameraView {
+        let mut transforms: Vec<(CameraMode, Transform)> = self
+            .saved
+            .iter()
+            .filter(|(mode, _)| **mode != self.mode)
+            .map(|(mode, transform)| (*mode, *transform))
+            .collect();
+        transforms.push((self.mode, *transform));
+
+        CameraView {
+            mode: self.mode,
+            transforms,
+            top_down_scale: match projection {
+                Projection::Orthographic(ortho) => ortho.scale,
+                _ => 1.0,
+            },
+        }
+    }
+
+    pub fn restore(
+        &mut self,
+        view: &CameraView,
+        transform: &mut Transform,
+        projection: &mut Projection,
+    ) {
+        self.saved = view.transforms.iter().copied().collect();
+        self.mode = view.mode;
+
+        *transform = self.transform_for(view.mode);
+        *projection = view.mode.projection();
+        if let Projection::Orthographic(ortho) = projection {
+            ortho.scale = view.top_down_scale;
+        }
+    }
+}
+
+// What a project remembers about the cameras, the top down zoom only lasts while in that view
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraView {
+    pub mode: CameraMode,
+    pub transforms: Vec<(CameraMode, Transform)>,
+    pub top_down_scale: f32,
+}
+
+impl Default for CameraView {
+    fn default() -> Self {
+        Self {
+            mode: CameraMode::Gameplay,
+            transforms: Vec::new(),
+            top_down_scale: 1.0,
+        }
+    }
+}
+
+pub fn spawn_camera(
+    mut commands: Commands,
+    state: Res<EditorCameraState>,
+    mut gizmos: ResMut<GizmoConfigStore>,
+) {
+    commands.spawn((
+        EditorCamera,
+        Camera3d::default(),
+        state.mode.projection(),
+        state.transform_for(state.mode),
+        RenderLayers::from_layers(&[0, EDITOR_LAYER]),
+    ));
+
+    let (config, _) = gizmos.config_mut::<EditorGizmos>();
+    config.render_layers = RenderLayers::layer(EDITOR_LAYER);
+}
+
+// Where camera mods put the gameplay view, shared with the preview window
+pub fn gameplay_transform(mods: &ModState) -> Transform {
+    let base = CameraMode::Gameplay.default_transform();
+    let rotation = mods.get(ModChannel::CameraRotation);
+
+    Transform {
+        translation: base.translation + mods.get(ModChannel::CameraOffset),
+        rotation: base.rotation
+            * Quat::from_euler(EulerRot::YXZ, rotation.y, rotation.x, rotation.z),
+        ..base
+    }
+}
+
+pub fn switch_camera_mode(
+    mut actions: EventReader<EditorActionEvent>,
+    mut state: ResMut<EditorCameraState>,
+    mut camera: Query<(&mut Transform, &mut Projection), With<EditorCamera>>,
+) {
+    let Some(mode) = actions
+        .read()
+        .filter_map(|action| CameraMode::from_action(action.0))
+        .last()
+    else {
+        return;
+    };
+
+    if mode == state.mode {
+        return;
+    }
+
+    if let Ok((mut transform, mut projection)) = camera.single_mut() {
+        state.switch(mode, &mut transform, &mut projection);
+    }
+}
+
+pub fn free_fly(
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    motion: Res<AccumulatedMouseMotion>,
+    state: Res<EditorCameraState>,
+    mut camera: Query<&mut Transform, With<EditorCamera>>,
+) {
+    if state.mode != CameraMode::FreeFly {
+        return;
+    }
+
+    let Ok(mut transform) = camera.single_mut() else {
+        return;
+    };
+
+    // Only look around while the right mouse button is held so the cursor stays usable
+    if mouse.pressed(MouseButton::Right) && motion.delta != Vec2::ZERO {
+        let (mut yaw, mut pitch, _) = transform.rotation.to_euler(EulerRot::YXZ);
+        yaw -= motion.delta.x * LOOK_SENSITIVITY;
+        pitch = (pitch - motion.delta.y * LOOK_SENSITIVITY).clamp(-1.54, 1.54);
+        transform.rotation = Quat::from_euler(EulerRot::YXZ, yaw, pitch, 0.0);
+    }
+
+    let mut direction = Vec3::ZERO;
+
+    for (key, axis) in [
+        (KeyCode::KeyW, transform.forward().as_vec3()),
+        (KeyCode::KeyS, transform.back().as_vec3()),
+        (KeyCode::KeyA, transform.left().as_vec3()),
+        (KeyCode::KeyD, transform.right().as_vec3()),
+        (KeyCode::KeyE, Vec3::Y),
+        (KeyCode::KeyQ, Vec3::NEG_Y),
+    ] {
+        if keys.pressed(key) {
+            direction += axis;
+        }
+    }
+
+    let mut speed = FLY_SPEED;
+    if keys.pressed(KeyCode::ShiftLeft) {
+        speed *= FLY_BOOST;
+    }
+
+    transform.translation += direction.normalize_or_zero() * speed * time.delta_secs();
+}
+
+pub fn top_down_pan(
+    scroll: Res<AccumulatedMouseScroll>,
+    keys: Res<ButtonInput<KeyCode>>,
+    state: Res<EditorCameraState>,
+    mut camera: Query<(&mut Transform, &mut Projection), With<EditorCamera>>,
+) {
+    if state.mode != CameraMode::TopDown || scroll.delta.y == 0.0 {
+        return;
+    }
+
+    let Ok((mut transform, mut projection)) = camera.single_mut() else {
+        return;
+    };
+
+    // Ctrl + scroll zooms, plain scroll moves along the time axis
+    if keys.pressed(KeyCode::ControlLeft) {
+        if let Projection::Orthographic(ortho) = projection.as_mut() {
+            ortho.scale = (ortho.scale * (1.0 - scroll.delta.y * 0.1)).clamp(0.05, 4.0);
+        }
+    } else {
+        transform.translation.z -= scroll.delta.y * TOP_DOWN_PAN_SPEED;
+    }
+}
+
+// The gameplay view is driven by camera mods, the other views are left alone for inspection
+// Hit feedback punches in on top of the camera mods, the preview window follows the mods only
+pub fn apply_camera_mods(
+    state: Res<EditorCameraState>,
+    mods: Res<ModState>,
+    feedback: Res<Feedback>,
+    settings: Res<Settings>,
+    mut camera: Query<&mut Transform, With<EditorCamera>>,
+) {
+    if state.mode != CameraMode::Gameplay
+        || !(mods.is_changed() || state.is_changed() || feedback.is_changed())
+    {
+        return;
+    }
+
+    let Ok(mut transform) = camera.single_mut() else {
+        return;
+    };
+
+    *transform = gameplay_transform(&mods);
+    let punch = feedback.camera_offset(&settings.feedback, &settings.comfort);
+    let forward = transform.forward();
+    transform.translation += forward * punch;
+}
+
+// Screen space mods are measured against the gameplay view, so the other views don't stretch them
+pub fn update_screen_frame(
+    state: Res<EditorCameraState>,
+    mut frame: ResMut<ScreenFrame>,
+    camera: Query<&Projection, (With<EditorCamera>, Changed<Projection>)>,
+) {
+    if state.mode != CameraMode::Gameplay {
+        return;
+    }
+
+    let Ok(Projection::Perspective(perspective)) = camera.single() else {
+        return;
+    };
+
+    let distance = CameraMode::Gameplay
+        .default_transform()
+        .translation
+        .length();
+    frame.set_if_neq(ScreenFrame::from_perspective(
+        perspective.fov,
+        perspective.aspect_ratio,
+        distance,
+    ));
+}
@@ -0,0 +1,570 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{Receiver, Sender, channel},
+    },
+    thread::{self, JoinHandle},
+};
+
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages},
+        view::screenshot::{Screenshot, ScreenshotCaptured},
+    },
+};
+use bevy_egui::egui;
+use image::{
+    Delay, Frame, RgbaImage,
+    codecs::gif::{GifEncoder, Repeat},
+};
+
+use crate::{
+    editor::camera::gameplay_transform,
+    maps::{CurrentMap, Map, export::template_filename},
+    modchart::ModState,
+    player::{SimulationState, playback::PlaybackClock},
+    settings::Settings,
+};
+
+// The render target needs a frame or two before what it shows can be captured
+const WARMUP_FRAMES: u32 = 2;
+
+// Pixels in a whole clip, about five seconds of 720p at 30fps. Longer or larger clips take
+// minutes to encode and are too big to share anyway
+pub const MAX_CLIP_PIXELS: u64 = 150_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClipFormat {
+    Gif,
+    // Far smaller than a gif, needs ffmpeg on the path
+    Webm,
+}
+
+impl ClipFormat {
+    pub const ALL: [ClipFormat; 2] = [ClipFormat::Gif, ClipFormat::Webm];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ClipFormat::Gif => "GIF",
+            ClipFormat::Webm => "WebM",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ClipFormat::Gif => "gif",
+            ClipFormat::Webm => "webm",
+        }
+    }
+}
+
+// A few seconds of the gameplay view for sharing a pattern or a mod, rendered frame by frame
+// with the clock stepped in between so slow frames never drop out of the clip
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClipSettings {
+    pub format: ClipFormat,
+    pub center_ms: u32,
+    pub seconds: f32,
+    pub fps: u32,
+    // The height follows from a 16:9 frame
+    pub width: u32,
+}
+
+impl Default for ClipSettings {
+    fn default() -> Self {
+        Self {
+            format: ClipFormat::Gif,
+            center_ms: 0,
+            seconds: 4.0,
+            fps: 20,
+            width: 480,
+        }
+    }
+}
+
+impl ClipSettings {
+    pub fn height(&self) -> u32 {
+        // Encoders want even sizes
+        (self.width * 9 / 16).max(2) & !1
+    }
+
+    pub fn frame_count(&self) -> usize {
+        (self.seconds * self.fps as f32).round().max(1.0) as usize
+    }
+
+    // Longest clip at this size and frame rate that stays under MAX_CLIP_PIXELS
+    pub fn max_seconds(&self) -> f32 {
+        let per_second = self.width as u64 * self.height() as u64 * self.fps.max(1) as u64;
+        (MAX_CLIP_PIXELS as f64 / per_second.max(1) as f64) as f32
+    }
+
+    pub fn limited(self) -> Self {
+        Self {
+            seconds: self.seconds.min(self.max_seconds()),
+            ..self
+        }
+    }
+
+    pub fn frame_ms(&self, frame: usize) -> f64 {
+        let start = self.center_ms as f64 - self.seconds as f64 * 500.0;
+        start.max(0.0) + frame as f64 * 1000.0 / self.fps as f64
+    }
+}
+
+// Where the frames of a clip are written as they come in, so the clip never has to fit in memory
+pub enum ClipWriter {
+    Gif {
+        encoder: GifEncoder<BufWriter<File>>,
+        delay: Delay,
+    },
+    // Frames are piped into ffmpeg as raw pixels
+    Webm {
+        ffmpeg: Child,
+        stdin: BufWriter<ChildStdin>,
+    },
+}
+
+impl ClipWriter {
+    pub fn create(clip: &ClipSettings, path: &Path) -> io::Result<Self> {
+        if let Some(folder) = path.parent() {
+            fs::create_dir_all(folder)?;
+        }
+
+        match clip.format {
+            ClipFormat::Gif => {
+                let file = BufWriter::new(File::create(path)?);
+                let mut encoder = GifEncoder::new_with_speed(file, 10);
+                encoder
+                    .set_repeat(Repeat::Infinite)
+                    .map_err(io::Error::other)?;
+                Ok(Self::Gif {
+                    encoder,
+                    delay: Delay::from_numer_denom_ms(1000, clip.fps.max(1)),
+                })
+            }
+            ClipFormat::Webm => {
+                let mut ffmpeg = ffmpeg(clip, path)?;
+                let Some(stdin) = ffmpeg.stdin.take() else {
+                    return Err(io::Error::other("Could not write to ffmpeg"));
+                };
+                Ok(Self::Webm {
+                    ffmpeg,
+                    stdin: BufWriter::new(stdin),
+                })
+            }
+        }
+    }
+
+    pub fn write(&mut self, frame: RgbaImage) -> io::Result<()> {
+        match self {
+            Self::Gif { encoder, delay } => encoder
+                .encode_frame(Frame::from_parts(frame, 0, 0, *delay))
+                .map_err(io::Error::other),
+            Self::Webm { stdin, .. } => stdin.write_all(frame.as_raw()),
+        }
+    }
+
+    pub fn finish(self) -> io::Result<()> {
+        match self {
+            // The trailer is written and the file flushed when the encoder is dropped
+            Self::Gif { encoder, .. } => {
+                drop(encoder);
+                Ok(())
+            }
+            Self::Webm { ffmpeg, mut stdin } => {
+                // Closing stdin tells ffmpeg the clip is over
+                stdin.flush()?;
+                drop(stdin);
+
+                let output = ffmpeg.wait_with_output()?;
+                if !output.status.success() {
+                    let error = String::from_utf8_lossy(&output.stderr);
+                    return Err(io::Error::other(format!("ffmpeg failed: {}", error.trim())));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn ffmpeg(clip: &ClipSettings, path: &Path) -> io::Result<Child> {
+    Command::new("ffmpeg")
+        .args([
+            "-y",
+            "-loglevel",
+            "error",
+            "-f",
+            "rawvideo",
+            "-pix_fmt",
+            "rgba",
+        ])
+        .args(["-s", &format!("{}x{}", clip.width, clip.height())])
+        .args(["-r", &clip.fps.to_string(), "-i", "-"])
+        .args([
+            "-c:v",
+            "libvpx-vp9",
+            "-b:v",
+            "0",
+            "-crf",
+            "34",
+            "-pix_fmt",
+            "yuv420p",
+        ])
+        .arg(path)
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("Could not start ffmpeg: {e}")))
+}
+
+// Writes frames on a thread of its own while the rest are still being captured. Screenshots can
+// arrive out of order, the few that are early wait for the ones before them
+pub struct ClipEncoder {
+    pub frames: Sender<(usize, RgbaImage)>,
+    thread: JoinHandle<io::Result<PathBuf>>,
+}
+
+impl ClipEncoder {
+    pub fn spawn(clip: ClipSettings, path: PathBuf) -> Self {
+        let (frames, received) = channel::<(usize, RgbaImage)>();
+        let total = clip.frame_count();
+
+        let thread = thread::spawn(move || {
+            let _span = info_span!("encode_clip", frames = total).entered();
+            match write_frames(&clip, &path, received) {
+                Ok(()) => Ok(path),
+                Err(e) => {
+                    // Half a clip is no use to anyone
+                    let _ = fs::remove_file(&path);
+                    Err(e)
+                }
+            }
+        });
+
+        Self { frames, thread }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    pub fn join(self) -> io::Result<PathBuf> {
+        // Dropping the sender ends a recording that is still waiting for frames
+        drop(self.frames);
+        self.thread
+            .join()
+            .unwrap_or_else(|_| Err(io::Error::other("The clip encoder crashed")))
+    }
+}
+
+fn write_frames(
+    clip: &ClipSettings,
+    path: &Path,
+    received: Receiver<(usize, RgbaImage)>,
+) -> io::Result<()> {
+    let total = clip.frame_count();
+    let mut writer = ClipWriter::create(clip, path)?;
+    let mut early = BTreeMap::new();
+    let mut next = 0;
+
+    for (index, frame) in received {
+        early.insert(index, frame);
+        while let Some(frame) = early.remove(&next) {
+            writer.write(frame)?;
+            next += 1;
+        }
+        if next == total {
+            return writer.finish();
+        }
+    }
+
+    Err(io::Error::other(format!(
+        "Recording stopped after {next} of {total} frames"
+    )))
+}
+
+struct Recording {
+    camera: Entity,
+    target: Handle<Image>,
+    encoder: ClipEncoder,
+    next_frame: usize,
+    captured: Arc<AtomicUsize>,
+    warmup: u32,
+    // Put back once every frame is captured
+    playhead_ms: f64,
+}
+
+#[derive(Resource, Default)]
+pub struct ClipRecorder {
+    pub settings: ClipSettings,
+    requested: bool,
+    recording: Option<Recording>,
+    encoding: Option<ClipEncoder>,
+    // Outcome of the last clip, or how far the current one is
+    status: Option<Result<String, String>>,
+}
+
+impl ClipRecorder {
+    pub fn is_busy(&self) -> bool {
+        self.recording.is_some() || self.encoding.is_some()
+    }
+}
+
+fn clip_path(map: &Map, settings: &Settings, clip: &ClipSettings) -> PathBuf {
+    // Named like the map exports, with the time in it so several clips of a map don't collide
+    let template = format!("{} {}ms", settings.export.filename_template, clip.center_ms);
+    PathBuf::from(settings.export.folder.trim()).join(template_filename(
+        map,
+        &template,
+        clip.format.extension(),
+    ))
+}
+
+fn render_target(images: &mut Assets<Image>, width: u32, height: u32) -> Handle<Image> {
+    let size = Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+    };
+    let mut image = Image::new_fill(
+        size,
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.texture_descriptor.usage = TextureUsages::TEXTURE_BINDING
+        | TextureUsages::COPY_SRC
+        | TextureUsages::COPY_DST
+        | TextureUsages::RENDER_ATTACHMENT;
+    images.add(image)
+}
+
+// Runs before the mods are evaluated so every frame shows the time it was captured for
+#[allow(clippy::too_many_arguments)]
+pub fn record_clip(
+    mut commands: Commands,
+    mut recorder: ResMut<ClipRecorder>,
+    mut images: ResMut<Assets<Image>>,
+    mut clock: ResMut<PlaybackClock>,
+    mut next_simulation: ResMut<NextState<SimulationState>>,
+    settings: Res<Settings>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+) {
+    let recorder = recorder.as_mut();
+
+    if recorder.requested {
+        recorder.requested = false;
+
+        let Some(map) = current_map.and_then(|current| maps.get(&current.0)) else {
+            recorder.status = Some(Err("There is no map to record".to_string()));
+            return;
+        };
+
+        let clip = recorder.settings.limited();
+        recorder.settings = clip;
+        let target = render_target(&mut images, clip.width, clip.height());
+        let camera = commands
+            .spawn((
+                Camera3d::default(),
+                Camera {
+                    target: RenderTarget::Image(target.clone().into()),
+                    ..default()
+                },
+                Projection::Perspective(PerspectiveProjection::default()),
+                Transform::default(),
+            ))
+            .id();
+
+        next_simulation.set(SimulationState::Paused);
+        recorder.recording = Some(Recording {
+            camera,
+            target,
+            encoder: ClipEncoder::spawn(clip, clip_path(map, &settings, &clip)),
+            next_frame: 0,
+            captured: Arc::new(AtomicUsize::new(0)),
+            warmup: WARMUP_FRAMES,
+            playhead_ms: clock.millisecond,
+        });
+    }
+
+    let Some(recording) = recorder.recording.as_mut() else {
+        return;
+    };
+
+    let clip = recorder.settings;
+    let total = clip.frame_count();
+
+    if recording.next_frame < total {
+        clock.seek(clip.frame_ms(recording.next_frame));
+
+        if recording.warmup > 0 {
+            recording.warmup -= 1;
+            return;
+        }
+
+        let frames = recording.encoder.frames.clone();
+        let captured = recording.captured.clone();
+        let index = recording.next_frame;
+        let (width, height) = (clip.width, clip.height());
+        commands
+            .spawn(Screenshot::image(recording.target.clone()))
+            .observe(move |trigger: Trigger<ScreenshotCaptured>| {
+                // A frame that can't be read stays black instead of holding up the clip
+                let image = match trigger.event().0.clone().try_into_dynamic() {
+                    Ok(image) => image.to_rgba8(),
+                    Err(e) => {
+                        warn!("Could not read clip frame {index}: {e}");
+                        RgbaImage::new(width, height)
+                    }
+                };
+                // The encoder stopped on an error, which it reports itself
+                let _ = frames.send((index, image));
+                captured.fetch_add(1, Ordering::Relaxed);
+            });
+
+        recording.next_frame += 1;
+        recorder.status = Some(Ok(format!("Recording frame {} of {total}", index + 1)));
+        return;
+    }
+
+    // Screenshots arrive a few frames after they are taken
+    if recording.captured.load(Ordering::Relaxed) < total {
+        return;
+    }
+
+    let Some(recording) = recorder.recording.take() else {
+        return;
+    };
+    commands.entity(recording.camera).despawn();
+    images.remove(&recording.target);
+    clock.seek(recording.playhead_ms);
+
+    recorder.status = Some(Ok("Encoding the clip".to_string()));
+    recorder.encoding = Some(recording.encoder);
+}
+
+// The clip camera sees what the gameplay camera would, mods included
+pub fn follow_clip_view(
+    recorder: Res<ClipRecorder>,
+    mods: Res<ModState>,
+    mut cameras: Query<&mut Transform>,
+) {
+    if let Some(recording) = &recorder.recording
+        && let Ok(mut transform) = cameras.get_mut(recording.camera)
+    {
+        *transform = gameplay_transform(&mods);
+    }
+}
+
+pub fn poll_clip_encoding(mut recorder: ResMut<ClipRecorder>) {
+    if !recorder
+        .encoding
+        .as_ref()
+        .is_some_and(|encoder| encoder.is_finished())
+    {
+        return;
+    }
+    let Some(encoder) = recorder.encoding.take() else {
+        return;
+    };
+
+    recorder.status = Some(match encoder.join() {
+        Ok(path) => {
+            info!("Saved clip to {}", path.display());
+            Ok(format!("Saved to {}", path.display()))
+        }
+        Err(e) => {
+            warn!("Could not save the clip: {e}");
+            Err(format!("Could not save the clip: {e}"))
+        }
+    });
+}
+
+pub fn clip_ui(ui: &mut egui::Ui, recorder: &mut ClipRecorder, length: u32, playhead: u32) {
+    ui.label("Renders the gameplay view around a point of the map, without sound");
+
+    let clip = &mut recorder.settings;
+    egui::Grid::new("export_clip")
+        .num_columns(3)
+        .show(ui, |ui| {
+            ui.label("Format");
+            egui::ComboBox::from_id_salt("clip_format")
+                .selected_text(clip.format.label())
+                .show_ui(ui, |ui| {
+                    for format in ClipFormat::ALL {
+                        ui.selectable_value(&mut clip.format, format, format.label());
+                    }
+                });
+            ui.end_row();
+
+            ui.label("Around");
+            ui.add(
+                egui::DragValue::new(&mut clip.center_ms)
+                    .range(0..=length)
+                    .suffix("ms"),
+            );
+            if ui.button("Playhead").clicked() {
+                clip.center_ms = playhead.min(length);
+            }
+            ui.end_row();
+
+            ui.label("Length");
+            let max_seconds = clip.max_seconds().clamp(0.5, 15.0);
+            ui.add(
+                egui::DragValue::new(&mut clip.seconds)
+                    .range(0.5..=max_seconds)
+                    .speed(0.1)
+                    .suffix("s"),
+            )
+            .on_hover_text("Larger sizes and frame rates allow shorter clips");
+            ui.end_row();
+
+            ui.label("Frame rate");
+            ui.add(
+                egui::DragValue::new(&mut clip.fps)
+                    .range(5..=60)
+                    .suffix("fps"),
+            );
+            ui.end_row();
+
+            ui.label("Width");
+            ui.add(
+                egui::DragValue::new(&mut clip.width)
+                    .range(160..=1280)
+                    .suffix("px"),
+            )
+            .on_hover_text(format!("{}px high", clip.height()));
+            ui.end_row();
+        });
+    *clip = clip.limited();
+
+    let busy = recorder.is_busy();
+    if ui
+        .add_enabled(!busy, egui::Button::new("Export clip"))
+        .on_hover_text("Playback pauses while the clip is recorded")
+        .clicked()
+    {
+        recorder.requested = true;
+    }
+
+    match &recorder.status {
+        Some(Ok(message)) => {
+            ui.label(message);
+        }
+        Some(Err(error)) => {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+        None => {}
+    }
+}
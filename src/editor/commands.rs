@@ -0,0 +1,606 @@
+use std::collections::{BTreeSet, HashSet};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    editor::{bulk_delete::NoteFilter, placement::PathShape},
+    maps::{
+        merge::plan_merge,
+        objects::{Hitsound, Note, NoteId, TimingPoint},
+    },
+    modchart::Easing,
+};
+
+// Indices into Map::notes of the currently selected notes
+#[derive(Resource, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Selection(pub BTreeSet<usize>);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MirrorAxis {
+    Horizontal,
+    Vertical,
+}
+
+// Edits are described relative to the selection and playhead so that recorded macros
+// can be replayed anywhere, the concrete effect is only resolved when they are planned
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum EditCommand {
+    PlaceNote {
+        position: Vec2,
+    },
+    DeleteSelection,
+    SelectNext {
+        count: usize,
+    },
+    SelectAll,
+    ClearSelection,
+    Mirror {
+        axis: MirrorAxis,
+    },
+    Offset {
+        milliseconds: i64,
+    },
+    SetHitsound {
+        hitsound: Option<Hitsound>,
+    },
+    // Axes left as None keep their value
+    SetPosition {
+        x: Option<f32>,
+        y: Option<f32>,
+    },
+    // Moves the selection across the grid, notes are kept inside it
+    Translate {
+        offset: Vec2,
+    },
+    // Rounds quantum positions to the nearest cell
+    SnapToGrid,
+    // Scales the time between notes, the first selected note stays where it is
+    Stretch {
+        ratio: f64,
+    },
+    // Spreads the selection along a path between two grid positions, in time order
+    Distribute {
+        shape: PathShape,
+        start: Vec2,
+        end: Vec2,
+        easing: Easing,
+    },
+    SetColor {
+        color: Option<u8>,
+    },
+    // Note times are relative to the playhead
+    Paste {
+        notes: Vec<Note>,
+    },
+    // Generated by the random fill tool at fixed times, `replace` first removes the notes
+    // between the first and last of them
+    Fill {
+        notes: Vec<Note>,
+        replace: bool,
+    },
+    // Removes every note the filter matches, regardless of the selection
+    DeleteMatching {
+        filter: NoteFilter,
+    },
+    // Picked by id, so the same notes are found again after edits moved them around
+    Select {
+        ids: Vec<NoteId>,
+    },
+    // Placed at the given time instead of the playhead
+    PlaceNoteAt {
+        millisecond: u32,
+        position: Vec2,
+    },
+    // Removes the notes on the cell at the playhead, regardless of the selection
+    DeleteAt {
+        position: Vec2,
+    },
+    // Collapses notes that drifted a few ms apart across the whole map, see maps::merge
+    MergeClose {
+        epsilon_ms: u32,
+    },
+}
+
+impl EditCommand {
+    pub fn label(&self) -> String {
+        match self {
+            EditCommand::PlaceNote { .. } => "Place note".to_string(),
+            EditCommand::DeleteSelection => "Delete notes".to_string(),
+            EditCommand::SelectNext { count } => format!("Select next {count} notes"),
+            EditCommand::SelectAll => "Select all".to_string(),
+            EditCommand::ClearSelection => "Clear selection".to_string(),
+            EditCommand::Mirror { axis } => format!("Mirror {axis:?}"),
+            EditCommand::Offset { milliseconds } => format!("Offset {milliseconds:+}ms"),
+            EditCommand::SetHitsound { hitsound: Some(_) } => "Set hitsound".to_string(),
+            EditCommand::SetHitsound { hitsound: None } => "Clear hitsound".to_string(),
+            EditCommand::SetPosition { .. } => "Set position".to_string(),
+            EditCommand::Translate { .. } => "Move notes".to_string(),
+            EditCommand::SnapToGrid => "Snap to grid".to_string(),
+            EditCommand::Stretch { ratio } => format!("Stretch x{ratio:.3}"),
+            EditCommand::Distribute { shape, .. } => format!("Place along {}", shape.label()),
+            EditCommand::SetColor { color: Some(_) } => "Set color".to_string(),
+            EditCommand::SetColor { color: None } => "Clear color".to_string(),
+            EditCommand::Paste { notes } => format!("Paste {} notes", notes.len()),
+            EditCommand::Fill { notes, .. } => format!("Fill {} notes", notes.len()),
+            EditCommand::DeleteMatching { .. } => "Bulk delete".to_string(),
+            EditCommand::Select { ids } => format!("Select {} notes", ids.len()),
+            EditCommand::PlaceNoteAt { .. } => "Place note".to_string(),
+            EditCommand::DeleteAt { .. } => "Delete note".to_string(),
+            EditCommand::MergeClose { epsilon_ms } => {
+                format!("Merge notes within {epsilon_ms}ms")
+            }
+        }
+    }
+
+    pub fn plan(&self, notes: &[Note], selection: &Selection, playhead: u32) -> Change {
+        let selected = || selection.0.iter().map(|&i| notes[i]);
+
+        match self {
+            EditCommand::PlaceNote { position } => Change::replace(
+                notes,
+                selection,
+                &BTreeSet::new(),
+                vec![Note {
+                    id: NoteId::next(),
+                    millisecond: playhead,
+                    position: *position,
+                    hitsound: None,
+                    color: None,
+                }],
+            ),
+            EditCommand::DeleteSelection => {
+                Change::replace(notes, selection, &selection.0, Vec::new())
+            }
+            EditCommand::SelectNext { count } => {
+                let start = notes.partition_point(|n| n.millisecond < playhead);
+                let end = (start + count).min(notes.len());
+                Change::select(selection, Selection((start..end).collect()))
+            }
+            EditCommand::SelectAll => {
+                Change::select(selection, Selection((0..notes.len()).collect()))
+            }
+            EditCommand::ClearSelection => Change::select(selection, Selection::default()),
+            EditCommand::Mirror { axis } => {
+                let mirrored = selected()
+                    .map(|mut note| {
+                        match axis {
+                            MirrorAxis::Horizontal => note.position.x = 2.0 - note.position.x,
+                            MirrorAxis::Vertical => note.position.y = 2.0 - note.position.y,
+                        }
+                        note
+                    })
+                    .collect();
+
+                Change::replace(notes, selection, &selection.0, mirrored)
+            }
+            EditCommand::Offset { milliseconds } => {
+                let offset = selected()
+                    .map(|mut note| {
+                        note.millisecond = (note.millisecond as i64 + *milliseconds)
+                            .clamp(0, u32::MAX as i64)
+                            as u32;
+                        note
+                    })
+                    .collect();
+
+                Change::replace(notes, selection, &selection.0, offset)
+            }
+            EditCommand::SetHitsound { hitsound } => {
+                let changed = selected()
+                    .map(|note| Note {
+                        hitsound: *hitsound,
+                        ..note
+                    })
+                    .collect();
+
+                Change::replace(notes, selection, &selection.0, changed)
+            }
+            EditCommand::SetPosition { x, y } => {
+                let moved = selected()
+                    .map(|mut note| {
+                        note.position.x = x.unwrap_or(note.position.x);
+                        note.position.y = y.unwrap_or(note.position.y);
+                        note
+                    })
+                    .collect();
+
+                Change::replace(notes, selection, &selection.0, moved)
+            }
+            EditCommand::Translate { offset } => {
+                let moved = selected()
+                    .map(|mut note| {
+                        note.position =
+                            (note.position + *offset).clamp(Vec2::ZERO, Vec2::splat(2.0));
+                        note
+                    })
+                    .collect();
+
+                Change::replace(notes, selection, &selection.0, moved)
+            }
+            EditCommand::SnapToGrid => {
+                let snapped = selected()
+                    .map(|mut note| {
+                        note.position = note.position.round().clamp(Vec2::ZERO, Vec2::splat(2.0));
+                        note
+                    })
+                    .collect();
+
+                Change::replace(notes, selection, &selection.0, snapped)
+            }
+            EditCommand::Stretch { ratio } => {
+                let anchor = selected().map(|n| n.millisecond).min().unwrap_or(0) as f64;
+                let stretched = selected()
+                    .map(|mut note| {
+                        let millisecond = anchor + (note.millisecond as f64 - anchor) * ratio;
+                        note.millisecond = millisecond.round().clamp(0.0, u32::MAX as f64) as u32;
+                        note
+                    })
+                    .collect();
+
+                Change::replace(notes, selection, &selection.0, stretched)
+            }
+            EditCommand::Distribute {
+                shape,
+                start,
+                end,
+                easing,
+            } => {
+                let last = selection.0.len().saturating_sub(1).max(1) as f32;
+                let placed = selected()
+                    .enumerate()
+                    .map(|(i, mut note)| {
+                        let t = easing.apply(i as f32 / last);
+                        note.position = shape
+                            .point(*start, *end, t)
+                            .clamp(Vec2::ZERO, Vec2::splat(2.0));
+                        note
+                    })
+                    .collect();
+
+                Change::replace(notes, selection, &selection.0, placed)
+            }
+            EditCommand::SetColor { color } => {
+                let changed = selected()
+                    .map(|note| Note {
+                        color: *color,
+                        ..note
+                    })
+                    .collect();
+
+                Change::replace(notes, selection, &selection.0, changed)
+            }
+            EditCommand::Paste { notes: pasted } => {
+                // Notes that would land past the last representable time are left out
+                let pasted = pasted
+                    .iter()
+                    .filter_map(|note| {
+                        Some(Note {
+                            id: NoteId::next(),
+                            millisecond: note.millisecond.checked_add(playhead)?,
+                            ..*note
+                        })
+                    })
+                    .collect();
+
+                Change::replace(notes, selection, &BTreeSet::new(), pasted)
+            }
+            EditCommand::Fill {
+                notes: filled,
+                replace,
+            } => {
+                let range = filled
+                    .iter()
+                    .map(|n| n.millisecond)
+                    .min()
+                    .zip(filled.iter().map(|n| n.millisecond).max());
+
+                let removed = match (replace, range) {
+                    (true, Some((start, end))) => notes
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, n)| (start..=end).contains(&n.millisecond))
+                        .map(|(i, _)| i)
+                        .collect(),
+                    _ => BTreeSet::new(),
+                };
+
+                // Replaying the same fill makes new notes, not copies of the first ones
+                let filled = filled
+                    .iter()
+                    .map(|note| Note {
+                        id: NoteId::next(),
+                        ..*note
+                    })
+                    .collect();
+
+                Change::replace(notes, selection, &removed, filled)
+            }
+            EditCommand::DeleteMatching { filter } => {
+                Change::replace(notes, selection, &filter.matches(notes), Vec::new())
+            }
+            EditCommand::Select { ids } => {
+                let ids: HashSet<NoteId> = ids.iter().copied().collect();
+                let selected = notes
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, note)| ids.contains(&note.id))
+                    .map(|(index, _)| index)
+                    .collect();
+
+                Change::select(selection, Selection(selected))
+            }
+            EditCommand::PlaceNoteAt {
+                millisecond,
+                position,
+            } => Change::replace(
+                notes,
+                selection,
+                &BTreeSet::new(),
+                vec![Note {
+                    id: NoteId::next(),
+                    millisecond: *millisecond,
+                    position: *position,
+                    hitsound: None,
+                    color: None,
+                }],
+            ),
+            EditCommand::DeleteAt { position } => {
+                let under = notes
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, note)| {
+                        note.millisecond == playhead && note.position.distance(*position) < 0.5
+                    })
+                    .map(|(index, _)| index)
+                    .collect();
+
+                Change::replace(notes, selection, &under, Vec::new())
+            }
+            EditCommand::MergeClose { epsilon_ms } => {
+                let (removed, moved, _) = plan_merge(notes, *epsilon_ms);
+                Change::replace(notes, selection, &removed, moved)
+            }
+        }
+    }
+}
+
+// Exact description of what an edit did to the note list, so it can be reverted and
+// reapplied without having to re-run the command that produced it
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Change {
+    // Indices the notes had before the change, ascending
+    removed: Vec<(usize, Note)>,
+    // Indices the notes have after the change, ascending
+    added: Vec<(usize, Note)>,
+    selection_before: Selection,
+    selection_after: Selection,
+}
+
+impl Change {
+    pub fn select(before: &Selection, after: Selection) -> Self {
+        Self {
+            selection_before: before.clone(),
+            selection_after: after,
+            ..default()
+        }
+    }
+
+    // Removes the given notes and inserts new ones in time order, the inserted notes become
+    // the selection. Inserted notes are placed after existing notes with the same millisecond
+    pub fn replace(
+        notes: &[Note],
+        selection: &Selection,
+        remove: &BTreeSet<usize>,
+        mut insert: Vec<Note>,
+    ) -> Self {
+        insert.sort_by_key(|n| n.millisecond);
+
+        let removed = remove.iter().map(|&i| (i, notes[i])).collect();
+
+        let mut kept = notes
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !remove.contains(i))
+            .map(|(_, n)| n)
+            .peekable();
+
+        let mut index = 0;
+        let mut added = Vec::with_capacity(insert.len());
+
+        for note in insert {
+            while kept
+                .next_if(|k| k.millisecond <= note.millisecond)
+                .is_some()
+            {
+                index += 1;
+            }
+
+            added.push((index, note));
+            index += 1;
+        }
+
+        let selection_after = Selection(added.iter().map(|(i, _)| *i).collect());
+
+        Self {
+            removed,
+            added,
+            selection_before: selection.clone(),
+            selection_after,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty()
+            && self.added.is_empty()
+            && self.selection_before == self.selection_after
+    }
+
+    pub fn touches_notes(&self) -> bool {
+        !(self.removed.is_empty() && self.added.is_empty())
+    }
+
+    pub fn apply(&self, notes: &mut Vec<Note>, selection: &mut Selection) {
+        for (index, _) in self.removed.iter().rev() {
+            notes.remove(*index);
+        }
+
+        for (index, note) in self.added.iter() {
+            notes.insert(*index, *note);
+        }
+
+        *selection = self.selection_after.clone();
+    }
+
+    pub fn revert(&self, notes: &mut Vec<Note>, selection: &mut Selection) {
+        for (index, _) in self.added.iter().rev() {
+            notes.remove(*index);
+        }
+
+        for (index, note) in self.removed.iter() {
+            notes.insert(*index, *note);
+        }
+
+        *selection = self.selection_before.clone();
+    }
+}
+
+// Timing points have no ids to follow them by, so they are swapped as a whole
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimingChange {
+    pub before: Vec<TimingPoint>,
+    pub after: Vec<TimingPoint>,
+}
+
+#[derive(Clone, Debug)]
+pub struct HistoryEntry {
+    pub label: String,
+    pub changes: Vec<Change>,
+    pub timing: Option<TimingChange>,
+}
+
+// What an edit did to the notes regardless of where they were, for when indices don't carry
+// over, like on another instance editing the same map
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct NoteEdit {
+    pub removed: Vec<Note>,
+    pub added: Vec<Note>,
+}
+
+impl NoteEdit {
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.added.is_empty()
+    }
+
+    pub fn inverted(self) -> Self {
+        Self {
+            removed: self.added,
+            added: self.removed,
+        }
+    }
+}
+
+impl HistoryEntry {
+    // Notes that are added and removed again within the entry cancel out
+    pub fn note_edit(&self) -> NoteEdit {
+        let mut edit = NoteEdit::default();
+
+        for change in self.changes.iter() {
+            for (_, note) in change.removed.iter() {
+                match edit.added.iter().position(|n| n == note) {
+                    Some(index) => {
+                        edit.added.remove(index);
+                    }
+                    None => edit.removed.push(*note),
+                }
+            }
+            edit.added
+                .extend(change.added.iter().map(|(_, note)| *note));
+        }
+
+        edit
+    }
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct EditHistory {
+    undo: Vec<HistoryEntry>,
+    redo: Vec<HistoryEntry>,
+}
+
+impl EditHistory {
+    pub fn push(&mut self, entry: HistoryEntry) {
+        if entry.changes.iter().all(Change::is_empty) && entry.timing.is_none() {
+            return;
+        }
+
+        self.undo.push(entry);
+        self.redo.clear();
+    }
+
+    pub fn undo(
+        &mut self,
+        notes: &mut Vec<Note>,
+        timing_points: &mut Vec<TimingPoint>,
+        selection: &mut Selection,
+    ) -> Option<&HistoryEntry> {
+        let entry = self.undo.pop()?;
+
+        for change in entry.changes.iter().rev() {
+            change.revert(notes, selection);
+        }
+        if let Some(timing) = &entry.timing {
+            timing_points.clone_from(&timing.before);
+        }
+
+        self.redo.push(entry);
+        self.redo.last()
+    }
+
+    pub fn redo(
+        &mut self,
+        notes: &mut Vec<Note>,
+        timing_points: &mut Vec<TimingPoint>,
+        selection: &mut Selection,
+    ) -> Option<&HistoryEntry> {
+        let entry = self.redo.pop()?;
+
+        for change in entry.changes.iter() {
+            change.apply(notes, selection);
+        }
+        if let Some(timing) = &entry.timing {
+            timing_points.clone_from(&timing.after);
+        }
+
+        self.undo.push(entry);
+        self.undo.last()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+// Runs a sequence of commands, each planned against the state the previous one left behind
+pub fn execute(
+    commands: &[EditCommand],
+    label: String,
+    notes: &mut Vec<Note>,
+    selection: &mut Selection,
+    playhead: u32,
+) -> HistoryEntry {
+    let mut changes = Vec::with_capacity(commands.len());
+
+    for command in commands {
+        let change = command.plan(notes, selection, playhead);
+        change.apply(notes, selection);
+        changes.push(change);
+    }
+
+    HistoryEntry {
+        label,
+        changes,
+        timing: None,
+    }
+}
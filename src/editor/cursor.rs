@@ -0,0 +1,55 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    editor::camera::EditorCamera,
+    player::playfield::{GRID_CELLS, Playfield, world_to_grid},
+};
+
+// Where the mouse points at on the grid plane, in grid coordinates
+#[derive(Resource, Default, Debug)]
+pub struct GridCursor {
+    pub position: Option<Vec2>,
+}
+
+impl GridCursor {
+    // Nearest cell center inside the grid
+    pub fn snapped(&self) -> Option<Vec2> {
+        let max = (GRID_CELLS - 1) as f32;
+        self.position
+            .map(|p| p.round().clamp(Vec2::ZERO, Vec2::splat(max)))
+    }
+}
+
+pub fn update_grid_cursor(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera: Query<(&Camera, &GlobalTransform), With<EditorCamera>>,
+    playfield: Query<&GlobalTransform, With<Playfield>>,
+    mut cursor: ResMut<GridCursor>,
+) {
+    cursor.position = None;
+
+    let (Ok(window), Ok((camera, camera_transform)), Ok(playfield)) =
+        (windows.single(), camera.single(), playfield.single())
+    else {
+        return;
+    };
+
+    let Some(ray) = window
+        .cursor_position()
+        .and_then(|p| camera.viewport_to_world(camera_transform, p).ok())
+    else {
+        return;
+    };
+
+    let plane = InfinitePlane3d::new(playfield.back());
+    let Some(distance) = ray.intersect_plane(playfield.translation(), plane) else {
+        return;
+    };
+
+    let local = playfield
+        .affine()
+        .inverse()
+        .transform_point3(ray.get_point(distance));
+
+    cursor.position = Some(world_to_grid(local));
+}
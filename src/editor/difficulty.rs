@@ -0,0 +1,93 @@
+use bevy::prelude::*;
+use bevy_egui::egui;
+
+use crate::{
+    analysis::{
+        difficulty::{DifficultyCurve, SAMPLE_MS},
+        readability::Readability,
+    },
+    maps::{CurrentMap, Map},
+    theme::Theme,
+};
+
+// Difficulty and readability of the current map, rebuilt when its notes change. Readability also
+// depends on the theme's approach profile
+#[derive(Resource, Debug, Default)]
+pub struct DifficultyGraph {
+    pub curve: Option<DifficultyCurve>,
+    pub readability: Option<Readability>,
+}
+
+pub fn update_difficulty_graph(
+    mut graph: ResMut<DifficultyGraph>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+    mut map_events: EventReader<AssetEvent<Map>>,
+    theme: Res<Theme>,
+) {
+    let map_changed = map_events.read().count() > 0;
+    let map_switched = current_map.as_ref().is_some_and(|m| m.is_changed());
+
+    if !(map_changed || map_switched || theme.is_changed()) {
+        return;
+    }
+
+    let map = current_map.and_then(|m| maps.get(&m.0));
+    graph.curve = map.map(|map| DifficultyCurve::from_notes(&map.notes, map.length));
+    graph.readability =
+        map.map(|map| Readability::from_notes(&map.notes, map.length, &theme.approach));
+}
+
+// Strain along `rect` from the start of the map to `length_ms`, peaks reach the top
+pub fn paint_difficulty(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    curve: &DifficultyCurve,
+    length_ms: f64,
+    color: egui::Color32,
+) {
+    let x_at = |ms: f64| rect.left() + (ms / length_ms).clamp(0.0, 1.0) as f32 * rect.width();
+
+    let points: Vec<egui::Pos2> = (0..curve.values().len())
+        .map(|index| {
+            let millisecond = (index as u32 * SAMPLE_MS) as f64;
+            let height = curve.normalized_at(millisecond) * rect.height();
+            egui::pos2(x_at(millisecond), rect.bottom() - height)
+        })
+        .collect();
+
+    // Filled with vertical strokes since the area under the curve is rarely convex
+    let fill = color.gamma_multiply(0.3);
+    for point in points.iter() {
+        painter.line_segment(
+            [*point, egui::pos2(point.x, rect.bottom())],
+            egui::Stroke::new(1.0, fill),
+        );
+    }
+
+    painter.add(egui::Shape::line(points, egui::Stroke::new(1.0, color)));
+}
+
+// Sections that can't be read in time, as bars along the top of `rect`
+pub fn paint_unreadable(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    readability: &Readability,
+    length_ms: f64,
+    color: egui::Color32,
+) {
+    let x_at = |ms: f64| rect.left() + (ms / length_ms).clamp(0.0, 1.0) as f32 * rect.width();
+
+    for section in readability.unreadable() {
+        let left = x_at(section.start_ms as f64);
+        let right = x_at(section.end_ms as f64).max(left + 2.0);
+        painter.rect_filled(
+            egui::Rect::from_min_max(
+                egui::pos2(left, rect.top()),
+                egui::pos2(right, rect.top() + 3.0),
+            ),
+            0.0,
+            color,
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use bevy::prelude::*;
+use bevy_egui::EguiContexts;
+
+use crate::{
+    editor::{
+        camera::EditorGizmos,
+        commands::{EditCommand, Selection},
+        cursor::GridCursor,
+        editing::EditRequest,
+    },
+    maps::{CurrentMap, Map},
+    player::{
+        playback::PlaybackClock,
+        playfield::{
+            APPROACH_DISTANCE, APPROACH_SPEED, CELL_SIZE, GRID_CELLS, Playfield, grid_to_world,
+            time_to_depth,
+        },
+    },
+    theme::Theme,
+};
+
+// How close to a selected note, in cells, a press has to be to pick the selection up
+const GRAB_RADIUS: f32 = 0.5;
+
+// Without snapping, positions are kept to this many cells
+const FREE_STEP: f32 = 0.01;
+
+// Ghosts are outlines of every dragged note, only a limited number are drawn
+const MAX_GHOSTS: usize = 256;
+
+// The selection being dragged. Notes are only changed once the drag is released, so the whole
+// drag is a single edit and undoes in one step.
+#[derive(Resource, Default, Debug)]
+pub struct NoteDrag {
+    // Grid position the selection was picked up at, dragged in the playfield
+    grab: Option<Vec2>,
+    offset: Vec2,
+    // Dragged along the timeline
+    pub milliseconds: Option<i64>,
+}
+
+impl NoteDrag {
+    pub fn is_active(&self) -> bool {
+        self.grab.is_some() || self.milliseconds.is_some()
+    }
+}
+
+// Offset that keeps every selected note inside the grid
+fn clamp_offset(map: &Map, selection: &Selection, offset: Vec2) -> Vec2 {
+    let max = (GRID_CELLS - 1) as f32;
+    let (low, high) = selection
+        .0
+        .iter()
+        .filter_map(|&i| map.notes.get(i))
+        .fold((Vec2::MAX, Vec2::MIN), |(low, high), note| {
+            (low.min(note.position), high.max(note.position))
+        });
+
+    match low.cmple(high).all() {
+        true => offset.clamp(-low, Vec2::splat(max) - high),
+        false => Vec2::ZERO,
+    }
+}
+
+// Dragging with the left mouse button moves the selection across the grid by whole cells,
+// holding Alt moves it freely
+#[allow(clippy::too_many_arguments)]
+pub fn drag_selection(
+    mut contexts: EguiContexts,
+    mouse: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    cursor: Res<GridCursor>,
+    selection: Res<Selection>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+    mut drag: ResMut<NoteDrag>,
+    mut requests: EventWriter<EditRequest>,
+) {
+    let Some(map) = current_map.and_then(|m| maps.get(&m.0)) else {
+        return;
+    };
+
+    if mouse.just_pressed(MouseButton::Left) && drag.grab.is_none() {
+        let over_ui = contexts
+            .ctx_mut()
+            .is_ok_and(|ctx| ctx.is_pointer_over_area() || ctx.wants_pointer_input());
+
+        let grabbed = cursor.position.filter(|position| {
+            !over_ui
+                && selection.0.iter().any(|&i| {
+                    map.notes
+                        .get(i)
+                        .is_some_and(|n| n.position.distance(*position) <= GRAB_RADIUS)
+                })
+        });
+
+        drag.grab = grabbed;
+        drag.offset = Vec2::ZERO;
+    }
+
+    let Some(grab) = drag.grab else {
+        return;
+    };
+
+    if let Some(position) = cursor.position {
+        let free = keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+        let offset = match free {
+            true => ((position - grab) / FREE_STEP).round() * FREE_STEP,
+            false => (position - grab).round(),
+        };
+        drag.offset = clamp_offset(map, &selection, offset);
+    }
+
+    if mouse.just_released(MouseButton::Left) {
+        if drag.offset != Vec2::ZERO {
+            requests.write(EditRequest::Execute(EditCommand::Translate {
+                offset: drag.offset,
+            }));
+        }
+        drag.grab = None;
+        drag.offset = Vec2::ZERO;
+    }
+}
+
+// Outlines where the dragged notes will end up, at the depth they are drawn at
+#[allow(clippy::too_many_arguments)]
+pub fn draw_drag_ghosts(
+    mut gizmos: Gizmos<EditorGizmos>,
+    drag: Res<NoteDrag>,
+    selection: Res<Selection>,
+    clock: Res<PlaybackClock>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+    playfield: Query<&GlobalTransform, With<Playfield>>,
+    theme: Res<Theme>,
+) {
+    if !drag.is_active() {
+        return;
+    }
+
+    let (Some(map), Ok(playfield)) = (current_map.and_then(|m| maps.get(&m.0)), playfield.single())
+    else {
+        return;
+    };
+
+    let scroll = map.scroll_timeline();
+    let now = scroll.position(clock.millisecond);
+    let window = (APPROACH_DISTANCE / APPROACH_SPEED * 1000.0) as f64;
+    let milliseconds = drag.milliseconds.unwrap_or(0);
+    let half = CELL_SIZE * 0.4;
+
+    let ghosts = selection
+        .0
+        .iter()
+        .filter_map(|&i| map.notes.get(i))
+        .filter_map(|note| {
+            let millisecond = (note.millisecond as i64 + milliseconds).max(0) as f64;
+            let ahead = scroll.position(millisecond) - now;
+            (0.0..=window)
+                .contains(&ahead)
+                .then(|| (note.position + drag.offset, ahead))
+        })
+        .take(MAX_GHOSTS);
+
+    for (position, ahead) in ghosts {
+        let center = grid_to_world(position).with_z(time_to_depth(ahead as f32));
+        let corners = [
+            Vec3::new(-half, -half, 0.0),
+            Vec3::new(half, -half, 0.0),
+            Vec3::new(half, half, 0.0),
+            Vec3::new(-half, half, 0.0),
+        ];
+
+        for (i, corner) in corners.iter().enumerate() {
+            let next = corners[(i + 1) % corners.len()];
+            gizmos.line(
+                playfield.transform_point(center + *corner),
+                playfield.transform_point(center + next),
+                theme.accent.color(),
+            );
+        }
+    }
+}
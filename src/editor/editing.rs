@@ -0,0 +1,206 @@
+use bevy::prelude::*;
+
+use crate::{
+    editor::{
+        commands::{
+            EditCommand, EditHistory, HistoryEntry, NoteEdit, Selection, TimingChange, execute,
+        },
+        cursor::GridCursor,
+        macros::MacroRecorder,
+        shortcuts::{EditorAction, EditorActionEvent},
+    },
+    maps::{
+        CurrentMap, Map,
+        objects::{Note, TimingPoint},
+    },
+    player::playback::PlaybackClock,
+    settings::Settings,
+};
+
+#[derive(Event, Clone, Debug)]
+pub enum EditRequest {
+    Execute(EditCommand),
+    // Several commands that undo as a single step, with the label shown in the history
+    ExecuteGroup(Vec<EditCommand>, String),
+    // Replaces every timing point of the map, with the label shown in the history
+    SetTimingPoints(Vec<TimingPoint>, String),
+    Undo,
+    Redo,
+    StartMacro,
+    StopMacro(String),
+    ReplayMacro(String),
+}
+
+// Sent after every local edit, undo and redo that changed notes
+#[derive(Event, Clone, Debug)]
+pub struct NotesEdited(pub NoteEdit);
+
+// Copied notes with times relative to the first one
+#[derive(Resource, Default, Debug)]
+pub struct Clipboard(pub Vec<Note>);
+
+#[allow(clippy::too_many_arguments)]
+pub fn translate_actions(
+    mut actions: EventReader<EditorActionEvent>,
+    mut requests: EventWriter<EditRequest>,
+    cursor: Res<GridCursor>,
+    recorder: Res<MacroRecorder>,
+    settings: Res<Settings>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+    selection: Res<Selection>,
+    mut clipboard: ResMut<Clipboard>,
+) {
+    for EditorActionEvent(action) in actions.read() {
+        let request = match action {
+            EditorAction::PlaceNote => match cursor.snapped() {
+                Some(position) => EditRequest::Execute(EditCommand::PlaceNote { position }),
+                None => continue,
+            },
+            EditorAction::DeleteNote => EditRequest::Execute(EditCommand::DeleteSelection),
+            EditorAction::Undo => EditRequest::Undo,
+            EditorAction::Redo => EditRequest::Redo,
+            EditorAction::Paste => EditRequest::Execute(EditCommand::Paste {
+                notes: clipboard.0.clone(),
+            }),
+            EditorAction::ToggleMacroRecording => match recorder.is_recording() {
+                true => EditRequest::StopMacro(format!("Macro {}", settings.macros.len() + 1)),
+                false => EditRequest::StartMacro,
+            },
+            EditorAction::ReplayLastMacro => match recorder.last.clone() {
+                Some(name) => EditRequest::ReplayMacro(name),
+                None => continue,
+            },
+            EditorAction::Copy => {
+                let Some(map) = current_map.as_ref().and_then(|m| maps.get(&m.0)) else {
+                    continue;
+                };
+
+                let copied: Vec<Note> = selection.0.iter().map(|&i| map.notes[i]).collect();
+                let start = copied.first().map_or(0, |n| n.millisecond);
+
+                clipboard.0 = copied
+                    .into_iter()
+                    .map(|note| Note {
+                        millisecond: note.millisecond - start,
+                        ..note
+                    })
+                    .collect();
+                continue;
+            }
+            _ => continue,
+        };
+
+        requests.write(request);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn process_edit_requests(
+    mut requests: EventReader<EditRequest>,
+    current_map: Option<Res<CurrentMap>>,
+    mut maps: ResMut<Assets<Map>>,
+    mut selection: ResMut<Selection>,
+    mut history: ResMut<EditHistory>,
+    mut recorder: ResMut<MacroRecorder>,
+    mut settings: ResMut<Settings>,
+    mut edited: EventWriter<NotesEdited>,
+    clock: Res<PlaybackClock>,
+) {
+    if requests.is_empty() {
+        return;
+    }
+
+    let Some(map) = current_map.and_then(|m| maps.get_mut(&m.0)) else {
+        requests.clear();
+        return;
+    };
+
+    let playhead = clock.millisecond.round() as u32;
+
+    for request in requests.read() {
+        match request {
+            EditRequest::Execute(command) => {
+                recorder.record(command);
+
+                let entry = execute(
+                    std::slice::from_ref(command),
+                    command.label(),
+                    &mut map.notes,
+                    &mut selection,
+                    playhead,
+                );
+                edited.write(NotesEdited(entry.note_edit()));
+                history.push(entry);
+            }
+            EditRequest::ExecuteGroup(commands, label) => {
+                for command in commands {
+                    recorder.record(command);
+                }
+
+                let entry = execute(
+                    commands,
+                    label.clone(),
+                    &mut map.notes,
+                    &mut selection,
+                    playhead,
+                );
+                edited.write(NotesEdited(entry.note_edit()));
+                history.push(entry);
+            }
+            EditRequest::SetTimingPoints(points, label) => {
+                let timing = TimingChange {
+                    before: std::mem::replace(&mut map.timing_points, points.clone()),
+                    after: points.clone(),
+                };
+                history.push(HistoryEntry {
+                    label: label.clone(),
+                    changes: Vec::new(),
+                    timing: Some(timing),
+                });
+            }
+            EditRequest::Undo => {
+                if let Some(entry) =
+                    history.undo(&mut map.notes, &mut map.timing_points, &mut selection)
+                {
+                    info!("Undo: {}", entry.label);
+                    edited.write(NotesEdited(entry.note_edit().inverted()));
+                }
+            }
+            EditRequest::Redo => {
+                if let Some(entry) =
+                    history.redo(&mut map.notes, &mut map.timing_points, &mut selection)
+                {
+                    info!("Redo: {}", entry.label);
+                    edited.write(NotesEdited(entry.note_edit()));
+                }
+            }
+            EditRequest::StartMacro => recorder.start(),
+            EditRequest::StopMacro(name) => {
+                if let Some(commands) = recorder.stop().filter(|c| !c.is_empty()) {
+                    info!("Recorded macro \"{name}\" with {} commands", commands.len());
+                    settings.macros.insert(name.clone(), commands);
+                    recorder.last = Some(name.clone());
+                }
+            }
+            EditRequest::ReplayMacro(name) => {
+                let Some(commands) = settings.macros.get(name) else {
+                    warn!("No macro named \"{name}\"");
+                    continue;
+                };
+
+                // The whole replay undoes as a single step
+                let entry: HistoryEntry = execute(
+                    commands,
+                    format!("Macro \"{name}\""),
+                    &mut map.notes,
+                    &mut selection,
+                    playhead,
+                );
+                edited.write(NotesEdited(entry.note_edit()));
+                history.push(entry);
+                recorder.last = Some(name.clone());
+            }
+        }
+    }
+}
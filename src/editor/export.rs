@@ -0,0 +1,885 @@
+use std::{
+    fs, io,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    prelude::*,
+    render::view::screenshot::{Screenshot, save_to_disk},
+    tasks::{AsyncComputeTaskPool, Task, block_on, futures_lite::future},
+};
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    editor::{
+        clip::{ClipRecorder, clip_ui},
+        shortcuts::{EditorAction, EditorActionEvent},
+    },
+    jukebox::{
+        DecodedAudio,
+        click_track::{
+            ClickTrackFormat, ClickTrackMode, ClickTrackOptions, click_track_path,
+            render_click_track, write_click_track,
+        },
+    },
+    maps::{
+        CurrentMap, Map,
+        beat_saber::{BeatSaberExport, export_beat_saber},
+        card::{card_path, save_card},
+        cover::{self, CoverEncoding, CoverOptions},
+        export::{ExportResult, export_all},
+        hooks::{ExportHook, HookRun, run_hooks},
+        limit::SizeLimit,
+        rhythm::{RhythmExport, RhythmFormat, export_rhythm},
+        size::{SizeReport, format_size},
+        trim::trim,
+    },
+    player::playback::PlaybackClock,
+    settings::Settings,
+};
+
+#[derive(Resource, Default)]
+pub struct ExportPanel {
+    pub open: bool,
+    pub results: Vec<ExportResult>,
+    // What the results were exported from, trimmed when a range was exported, so oversized
+    // exports can be shrunk and written again
+    exported: Option<Map>,
+    cover: CoverOptions,
+    cover_path: String,
+    // Outcome of the last cover operation
+    cover_status: Option<Result<String, String>>,
+    // Where the last chart card was saved to
+    card_status: Option<Result<String, String>>,
+    // Where the last Beat Saber level went, with what it couldn't carry over
+    beat_saber_status: Option<Result<BeatSaberExport, String>>,
+    rhythm_status: Option<Result<RhythmExport, String>>,
+    click_track: ClickTrackOptions,
+    // Rendered and written in the background, long songs take a moment
+    click_track_task: Option<Task<io::Result<PathBuf>>>,
+    click_track_status: Option<Result<String, String>>,
+    range_start_ms: u32,
+    // Zero until a range is picked, the whole map is used then
+    range_end_ms: u32,
+    range_error: Option<String>,
+    // One task per exported path, running its hooks in order
+    hook_tasks: Vec<Task<Vec<HookRun>>>,
+    hook_runs: Vec<HookRun>,
+}
+
+impl ExportPanel {
+    // Hooks run in the background, uploads and validators can take a while
+    fn run_hooks(&mut self, hooks: &[ExportHook], paths: impl IntoIterator<Item = PathBuf>) {
+        self.hook_runs.clear();
+        if !hooks.iter().any(|hook| hook.enabled) {
+            return;
+        }
+
+        for path in paths {
+            let hooks = hooks.to_vec();
+            self.hook_tasks
+                .push(AsyncComputeTaskPool::get().spawn(async move { run_hooks(&hooks, &path) }));
+        }
+    }
+
+    fn exported_paths(&self) -> Vec<PathBuf> {
+        self.results
+            .iter()
+            .filter(|export| export.result.is_ok())
+            .map(|export| export.path.clone())
+            .collect()
+    }
+}
+
+pub fn export_current_map(
+    mut actions: EventReader<EditorActionEvent>,
+    mut panel: ResMut<ExportPanel>,
+    settings: Res<Settings>,
+    current: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+) {
+    if !actions
+        .read()
+        .any(|EditorActionEvent(action)| *action == EditorAction::ExportAll)
+    {
+        return;
+    }
+
+    let Some(map) = current.and_then(|current| maps.get(&current.0)) else {
+        warn!("No map is loaded, nothing to export");
+        return;
+    };
+
+    panel.results = export_all(
+        map,
+        &PathBuf::from(settings.export.folder.trim()),
+        &settings.export.filename_template,
+        &settings.export.size_limit,
+        settings.export.write_options(),
+    );
+    panel.exported = Some(map.clone());
+    panel.open = true;
+
+    for export in panel.results.iter() {
+        match &export.result {
+            Ok(()) => info!("Exported {:?} to {}", export.format, export.path.display()),
+            Err(e) => warn!("Could not export {:?}: {e}", export.format),
+        }
+
+        for warning in export.warnings.iter() {
+            warn!("{:?}: {warning}", export.format);
+        }
+    }
+
+    let paths = panel.exported_paths();
+    panel.run_hooks(&settings.export.hooks, paths);
+}
+
+pub fn poll_export_hooks(mut panel: ResMut<ExportPanel>) {
+    if panel.hook_tasks.is_empty() {
+        return;
+    }
+
+    let panel = panel.as_mut();
+    let mut finished = Vec::new();
+    panel
+        .hook_tasks
+        .retain_mut(|task| match block_on(future::poll_once(task)) {
+            Some(runs) => {
+                finished.extend(runs);
+                false
+            }
+            None => true,
+        });
+
+    for run in finished.iter() {
+        match &run.result {
+            Ok(_) => info!("{} ran on {}", run.hook, run.path.display()),
+            Err(e) => warn!("{} failed on {}: {e}", run.hook, run.path.display()),
+        }
+    }
+    panel.hook_runs.extend(finished);
+}
+
+pub fn poll_click_track(mut panel: ResMut<ExportPanel>) {
+    let Some(result) = panel
+        .click_track_task
+        .as_mut()
+        .and_then(|task| block_on(future::poll_once(task)))
+    else {
+        return;
+    };
+
+    panel.click_track_task = None;
+    panel.click_track_status = Some(match result {
+        Ok(path) => {
+            info!("Exported a click track to {}", path.display());
+            Ok(path.display().to_string())
+        }
+        Err(e) => {
+            warn!("Could not export the click track: {e}");
+            Err(format!("Could not export the click track: {e}"))
+        }
+    });
+}
+
+// Saves what the window shows into the export folder
+pub fn take_screenshot(
+    mut commands: Commands,
+    mut actions: EventReader<EditorActionEvent>,
+    settings: Res<Settings>,
+) {
+    if !actions
+        .read()
+        .any(|EditorActionEvent(action)| *action == EditorAction::Screenshot)
+    {
+        return;
+    }
+
+    let folder = PathBuf::from(settings.export.folder.trim());
+    if let Err(e) = fs::create_dir_all(&folder) {
+        warn!("Could not create {}: {e}", folder.display());
+        return;
+    }
+
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let path = folder.join(format!("screenshot-{seconds}.png"));
+    info!("Saving screenshot to {}", path.display());
+
+    commands
+        .spawn(Screenshot::primary_window())
+        .observe(save_to_disk(path));
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn export_ui(
+    mut contexts: EguiContexts,
+    mut panel: ResMut<ExportPanel>,
+    mut settings: ResMut<Settings>,
+    current_map: Option<Res<CurrentMap>>,
+    mut maps: ResMut<Assets<Map>>,
+    clock: Res<PlaybackClock>,
+    mut recorder: ResMut<ClipRecorder>,
+    mut decoded: ResMut<DecodedAudio>,
+) -> Result {
+    if !panel.open {
+        return Ok(());
+    }
+
+    let mut open = panel.open;
+    let mut export_settings = settings.export.clone();
+    let current_cover = current_map
+        .as_ref()
+        .and_then(|m| maps.get(&m.0))
+        .map(|map| map.cover.as_slice());
+    let save_note_ids = current_map
+        .as_ref()
+        .and_then(|m| maps.get(&m.0))
+        .map(|map| map.save_note_ids);
+    let mut new_save_note_ids = save_note_ids;
+    let mut cover_action = None;
+    let mut save_chart_card = false;
+    let mut export_for_beat_saber = false;
+    let mut rhythm_format = None;
+    let mut export_range = false;
+    let mut export_click_track = false;
+    let mut shrink_again = false;
+    let has_audio = current_map
+        .as_ref()
+        .and_then(|m| maps.get(&m.0))
+        .is_some_and(|map| map.audio.is_some());
+    let map_length = current_map
+        .as_ref()
+        .and_then(|m| maps.get(&m.0))
+        .map(|map| map.length);
+    if panel.range_end_ms == 0 {
+        panel.range_end_ms = map_length.unwrap_or(0);
+    }
+
+    egui::Window::new("Export")
+        .open(&mut open)
+        .resizable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            egui::Grid::new("export_settings")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Folder");
+                    ui.text_edit_singleline(&mut export_settings.folder);
+                    ui.end_row();
+
+                    ui.label("Filename");
+                    ui.text_edit_singleline(&mut export_settings.filename_template)
+                        .on_hover_text(
+                            "Placeholders: {id} {title} {artist} {mappers} {diff_name} {difficulty} {ext}",
+                        );
+                    ui.end_row();
+
+                    ui.label("Grid snap");
+                    ui.add(
+                        egui::DragValue::new(&mut export_settings.grid_epsilon)
+                            .range(0.0..=0.5)
+                            .speed(0.001)
+                            .fixed_decimals(3),
+                    )
+                    .on_hover_text(
+                        "Notes closer than this to a whole cell are exported as grid positions",
+                    );
+                    ui.end_row();
+
+                    if let Some(save) = new_save_note_ids.as_mut() {
+                        ui.label("Note ids");
+                        ui.checkbox(save, "Keep in SSPM")
+                            .on_hover_text("Lets tools find the same notes again after reopening the map");
+                        ui.end_row();
+                    }
+                });
+
+            egui::CollapsingHeader::new("Size limit")
+                .show(ui, |ui| size_limit_ui(ui, &mut export_settings.size_limit));
+
+            egui::CollapsingHeader::new("After export")
+                .show(ui, |ui| hooks_ui(ui, &mut export_settings.hooks));
+
+            if let Some(length) = map_length {
+                egui::CollapsingHeader::new("Range").show(ui, |ui| {
+                    export_range = range_ui(ui, &mut panel, length, clock.millisecond as u32);
+                });
+                egui::CollapsingHeader::new("Clip").show(ui, |ui| {
+                    clip_ui(ui, &mut recorder, length, clock.millisecond as u32);
+                });
+                egui::CollapsingHeader::new("Click track").show(ui, |ui| {
+                    export_click_track = click_track_ui(ui, &mut panel, &mut decoded, has_audio);
+                });
+            }
+
+            if let Some(current_cover) = current_cover {
+                egui::CollapsingHeader::new("Cover")
+                    .show(ui, |ui| cover_action = cover_ui(ui, &mut panel, current_cover));
+
+                ui.horizontal(|ui| {
+                    save_chart_card = ui
+                        .button("Save chart card")
+                        .on_hover_text("An image with the cover, title and stats for sharing")
+                        .clicked();
+
+                    match &panel.card_status {
+                        Some(Ok(path)) => ui.label(path.as_str()),
+                        Some(Err(e)) => ui.colored_label(ui.visuals().error_fg_color, e.as_str()),
+                        None => ui.label(""),
+                    };
+                });
+
+                ui.horizontal(|ui| {
+                    export_for_beat_saber = ui
+                        .button("Beat Saber (experimental)")
+                        .on_hover_text("A custom level folder to try the chart in VR")
+                        .clicked();
+
+                    match &panel.beat_saber_status {
+                        Some(Ok(export)) => ui.label(export.folder.display().to_string()),
+                        Some(Err(e)) => ui.colored_label(ui.visuals().error_fg_color, e.as_str()),
+                        None => ui.label(""),
+                    };
+                });
+
+                if let Some(Ok(export)) = &panel.beat_saber_status {
+                    for warning in export.warnings.iter() {
+                        ui.colored_label(ui.visuals().warn_fg_color, warning);
+                    }
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Rhythm only");
+                    for format in RhythmFormat::ALL {
+                        if ui.button(format.label()).clicked() {
+                            rhythm_format = Some(format);
+                        }
+                    }
+
+                    match &panel.rhythm_status {
+                        Some(Ok(export)) => ui.label(export.folder.display().to_string()),
+                        Some(Err(e)) => ui.colored_label(ui.visuals().error_fg_color, e.as_str()),
+                        None => ui.label(""),
+                    };
+                });
+
+                if let Some(Ok(export)) = &panel.rhythm_status {
+                    for warning in export.warnings.iter() {
+                        ui.colored_label(ui.visuals().warn_fg_color, warning);
+                    }
+                }
+            }
+
+            for export in panel.results.iter() {
+                ui.separator();
+
+                match &export.result {
+                    Ok(()) => ui.label(format!("{:?}: {}", export.format, export.path.display())),
+                    Err(e) => ui.colored_label(
+                        ui.visuals().error_fg_color,
+                        format!("{:?} failed: {e}", export.format),
+                    ),
+                };
+
+                for warning in export.warnings.iter() {
+                    ui.colored_label(ui.visuals().warn_fg_color, warning);
+                }
+
+                if export.limit.as_ref().is_some_and(|check| check.over())
+                    && !export_settings.size_limit.shrink
+                    && panel.exported.is_some()
+                {
+                    shrink_again |= ui
+                        .button("Shrink and export again")
+                        .on_hover_text("Shrinks the cover, then the audio, until the files fit")
+                        .clicked();
+                }
+
+                if let Some(size) = &export.size {
+                    let title = format!("Size: {}", format_size(size.total));
+                    egui::CollapsingHeader::new(title)
+                        .id_salt(("export_size", export.format.extension()))
+                        .show(ui, |ui| size_ui(ui, size));
+                }
+            }
+
+            if !panel.hook_tasks.is_empty() || !panel.hook_runs.is_empty() {
+                ui.separator();
+            }
+            if !panel.hook_tasks.is_empty() {
+                ui.label(format!("Running hooks on {} exports...", panel.hook_tasks.len()));
+            }
+            for run in panel.hook_runs.iter() {
+                let name = run.path.file_name().unwrap_or_default().to_string_lossy();
+                match &run.result {
+                    Ok(output) => ui
+                        .label(format!("{} on {name}", run.hook))
+                        .on_hover_text(output.as_str()),
+                    Err(e) => ui.colored_label(
+                        ui.visuals().error_fg_color,
+                        format!("{} on {name} failed: {e}", run.hook),
+                    ),
+                };
+            }
+        });
+
+    panel.open = open;
+
+    if new_save_note_ids != save_note_ids
+        && let Some(save) = new_save_note_ids
+        && let Some(map) = current_map.as_ref().and_then(|m| maps.get_mut(&m.0))
+    {
+        map.save_note_ids = save;
+    }
+
+    if save_chart_card && let Some(map) = current_map.as_ref().and_then(|m| maps.get(&m.0)) {
+        let folder = PathBuf::from(export_settings.folder.trim());
+        let path = card_path(map, &folder, &export_settings.filename_template);
+        panel.card_status = Some(
+            save_card(map, &path)
+                .map(|_| path.display().to_string())
+                .map_err(|e| format!("Could not save the chart card: {e}")),
+        );
+    }
+
+    if export_for_beat_saber && let Some(map) = current_map.as_ref().and_then(|m| maps.get(&m.0)) {
+        let folder = PathBuf::from(export_settings.folder.trim());
+        panel.beat_saber_status = Some(
+            export_beat_saber(map, &folder, &export_settings.filename_template)
+                .map_err(|e| format!("Could not export for Beat Saber: {e}")),
+        );
+        if let Some(Ok(export)) = &panel.beat_saber_status {
+            let level = export.folder.clone();
+            panel.run_hooks(&export_settings.hooks, [level]);
+        }
+    }
+
+    if let Some(format) = rhythm_format
+        && let Some(map) = current_map.as_ref().and_then(|m| maps.get(&m.0))
+    {
+        let folder = PathBuf::from(export_settings.folder.trim());
+        panel.rhythm_status = Some(
+            export_rhythm(map, format, &folder, &export_settings.filename_template)
+                .map_err(|e| format!("Could not export for {}: {e}", format.label())),
+        );
+        if let Some(Ok(export)) = &panel.rhythm_status {
+            let chart = export.folder.clone();
+            panel.run_hooks(&export_settings.hooks, [chart]);
+        }
+    }
+
+    if export_click_track && let Some(map) = current_map.as_ref().and_then(|m| maps.get(&m.0)) {
+        let folder = PathBuf::from(export_settings.folder.trim());
+        let options = panel.click_track;
+        let path = click_track_path(
+            map,
+            &folder,
+            &export_settings.filename_template,
+            options.format,
+        );
+        let map = map.clone();
+        let song = decoded.pcm.clone();
+
+        panel.click_track_status = None;
+        panel.click_track_task = Some(AsyncComputeTaskPool::get().spawn(async move {
+            let track = render_click_track(&map, song.as_deref(), &options);
+            write_click_track(&track, options.format, &path).map(|_| path)
+        }));
+    }
+
+    if export_range && let Some(map) = current_map.as_ref().and_then(|m| maps.get(&m.0)) {
+        let folder = PathBuf::from(export_settings.folder.trim());
+        match trim(map, panel.range_start_ms, panel.range_end_ms) {
+            Ok(trimmed) => {
+                panel.results = export_all(
+                    &trimmed,
+                    &folder,
+                    &export_settings.filename_template,
+                    &export_settings.size_limit,
+                    export_settings.write_options(),
+                );
+                panel.exported = Some(trimmed);
+                panel.range_error = None;
+
+                let paths = panel.exported_paths();
+                panel.run_hooks(&export_settings.hooks, paths);
+            }
+            Err(e) => panel.range_error = Some(format!("Could not trim the map: {e}")),
+        }
+    }
+
+    if shrink_again && let Some(map) = panel.exported.take() {
+        let limit = SizeLimit {
+            shrink: true,
+            ..export_settings.size_limit.clone()
+        };
+        let folder = PathBuf::from(export_settings.folder.trim());
+        panel.results = export_all(
+            &map,
+            &folder,
+            &export_settings.filename_template,
+            &limit,
+            export_settings.write_options(),
+        );
+        panel.exported = Some(map);
+
+        let paths = panel.exported_paths();
+        panel.run_hooks(&export_settings.hooks, paths);
+    }
+
+    if let Some(action) = cover_action
+        && let Some(map) = current_map.and_then(|m| maps.get_mut(&m.0))
+    {
+        let options = panel.cover;
+        let source = match action {
+            CoverAction::Load => fs::read(panel.cover_path.trim())
+                .map_err(|e| format!("Could not read {}: {e}", panel.cover_path.trim())),
+            CoverAction::Reencode => Ok(map.cover.clone()),
+        };
+
+        panel.cover_status = Some(source.and_then(|bytes| {
+            let processed = cover::process(&bytes, &options).map_err(|e| e.to_string())?;
+            let message = format!(
+                "{} to {}",
+                format_size(bytes.len() as u64),
+                format_size(processed.len() as u64)
+            );
+            map.cover = processed;
+            Ok(message)
+        }));
+    }
+
+    // Only written back on edits so settings aren't saved every frame the window is open
+    if export_settings != settings.export {
+        settings.export = export_settings;
+    }
+
+    Ok(())
+}
+
+// True when the click track should be exported
+fn click_track_ui(
+    ui: &mut egui::Ui,
+    panel: &mut ExportPanel,
+    decoded: &mut DecodedAudio,
+    has_audio: bool,
+) -> bool {
+    ui.label("The rhythm of the notes as audio, for proofing timing away from the editor");
+
+    let options = &mut panel.click_track;
+    if !has_audio {
+        options.mode = ClickTrackMode::Isolated;
+    }
+
+    egui::Grid::new("export_click_track")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Clicks");
+            ui.add_enabled_ui(has_audio, |ui| {
+                for mode in ClickTrackMode::ALL {
+                    ui.radio_value(&mut options.mode, mode, mode.label());
+                }
+            });
+            ui.end_row();
+
+            if options.mode == ClickTrackMode::OverSong {
+                ui.label("Song volume");
+                ui.add(egui::Slider::new(&mut options.song_volume, 0.0..=1.0));
+                ui.end_row();
+            }
+
+            ui.label("Format");
+            egui::ComboBox::from_id_salt("click_track_format")
+                .selected_text(options.format.label())
+                .show_ui(ui, |ui| {
+                    for format in ClickTrackFormat::ALL {
+                        ui.selectable_value(&mut options.format, format, format.label());
+                    }
+                });
+            ui.end_row();
+
+            ui.label("Metronome");
+            ui.checkbox(&mut options.metronome, "Tick every beat");
+            ui.end_row();
+        });
+
+    // The track is built from the samples, streamed audio is decoded once the section is opened
+    if has_audio && decoded.pcm.is_none() {
+        decoded.request_pcm();
+    }
+    let ready = !has_audio || decoded.pcm.is_some();
+    let busy = panel.click_track_task.is_some();
+
+    let mut export = false;
+    ui.horizontal(|ui| {
+        export = ui
+            .add_enabled(ready && !busy, egui::Button::new("Export click track"))
+            .clicked();
+
+        if busy {
+            ui.spinner();
+        } else if has_audio && decoded.is_decoding() {
+            ui.label("Decoding audio...");
+        }
+    });
+
+    match &panel.click_track_status {
+        Some(Ok(path)) => {
+            ui.label(path.as_str());
+        }
+        Some(Err(e)) => {
+            ui.colored_label(ui.visuals().error_fg_color, e.as_str());
+        }
+        None => {}
+    }
+
+    export
+}
+
+fn size_limit_ui(ui: &mut egui::Ui, limit: &mut SizeLimit) {
+    ui.label("Some games and sites refuse maps above a size, exports over it are warned about");
+
+    egui::Grid::new("export_size_limit")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Largest file");
+            ui.add(
+                egui::DragValue::new(&mut limit.max_mb)
+                    .range(0.0..=1024.0)
+                    .speed(0.5)
+                    .suffix(" MB"),
+            )
+            .on_hover_text("Zero turns the check off");
+            ui.end_row();
+
+            ui.label("Shrink");
+            ui.checkbox(&mut limit.shrink, "Before writing oversized files")
+                .on_hover_text("The cover first, then the audio if that wasn't enough");
+            ui.end_row();
+
+            ui.label("Cover");
+            ui.add(
+                egui::DragValue::new(&mut limit.cover_size)
+                    .range(16..=4096)
+                    .suffix("px"),
+            );
+            ui.end_row();
+
+            ui.label("Audio");
+            ui.add(
+                egui::DragValue::new(&mut limit.audio_kbps)
+                    .range(32..=320)
+                    .suffix(" kbps"),
+            )
+            .on_hover_text("Re-encoded as MP3, needs ffmpeg on the path");
+            ui.end_row();
+        });
+}
+
+fn hooks_ui(ui: &mut egui::Ui, hooks: &mut Vec<ExportHook>) {
+    ui.label("Commands run on every exported file, in order, stopping at the first that fails");
+    ui.label("{path}, {folder} and {name} are replaced, the path is added last otherwise");
+
+    let mut removed = None;
+    egui::Grid::new("export_hooks")
+        .num_columns(4)
+        .show(ui, |ui| {
+            for (index, hook) in hooks.iter_mut().enumerate() {
+                ui.checkbox(&mut hook.enabled, "").on_hover_text("Enabled");
+                ui.add(
+                    egui::TextEdit::singleline(&mut hook.name)
+                        .hint_text("Name")
+                        .desired_width(100.0),
+                );
+                ui.add(
+                    egui::TextEdit::singleline(&mut hook.command)
+                        .hint_text("cp {path} ~/game/maps")
+                        .desired_width(240.0),
+                );
+                if ui.small_button("Remove").clicked() {
+                    removed = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+
+    if let Some(index) = removed {
+        hooks.remove(index);
+    }
+    if ui.button("Add command").clicked() {
+        hooks.push(ExportHook::default());
+    }
+}
+
+enum CoverAction {
+    // Replaces the cover with an image file
+    Load,
+    // Runs the current cover through the options again
+    Reencode,
+}
+
+fn cover_ui(ui: &mut egui::Ui, panel: &mut ExportPanel, current: &[u8]) -> Option<CoverAction> {
+    let mut action = None;
+
+    match cover::dimensions(current) {
+        _ if current.is_empty() => ui.label("This map has no cover"),
+        Some((width, height)) => ui.label(format!(
+            "{width}x{height}, {}",
+            format_size(current.len() as u64)
+        )),
+        None => ui.label(format!(
+            "Unknown format, {}",
+            format_size(current.len() as u64)
+        )),
+    };
+
+    egui::Grid::new("cover_options")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Crop to square");
+            ui.checkbox(&mut panel.cover.crop, "");
+            ui.end_row();
+
+            ui.label("Largest side");
+            ui.add(
+                egui::DragValue::new(&mut panel.cover.max_size)
+                    .range(16..=4096)
+                    .suffix("px"),
+            );
+            ui.end_row();
+
+            ui.label("Format");
+            egui::ComboBox::from_id_salt("cover_encoding")
+                .selected_text(panel.cover.encoding.label())
+                .show_ui(ui, |ui| {
+                    let quality = match panel.cover.encoding {
+                        CoverEncoding::Jpeg { quality } => quality,
+                        CoverEncoding::Png => 85,
+                    };
+                    for encoding in [CoverEncoding::Png, CoverEncoding::Jpeg { quality }] {
+                        let selected = panel.cover.encoding.label() == encoding.label();
+                        if ui.selectable_label(selected, encoding.label()).clicked() {
+                            panel.cover.encoding = encoding;
+                        }
+                    }
+                });
+            ui.end_row();
+
+            if let CoverEncoding::Jpeg { quality } = &mut panel.cover.encoding {
+                ui.label("Quality");
+                ui.add(egui::Slider::new(quality, 1..=100));
+                ui.end_row();
+            }
+
+            ui.label("Image");
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut panel.cover_path);
+                if ui
+                    .add_enabled(
+                        !panel.cover_path.trim().is_empty(),
+                        egui::Button::new("Load"),
+                    )
+                    .clicked()
+                {
+                    action = Some(CoverAction::Load);
+                }
+            });
+            ui.end_row();
+        });
+
+    if ui
+        .add_enabled(
+            !current.is_empty(),
+            egui::Button::new("Apply to current cover"),
+        )
+        .clicked()
+    {
+        action = Some(CoverAction::Reencode);
+    }
+
+    match &panel.cover_status {
+        Some(Ok(message)) => {
+            ui.label(message);
+        }
+        Some(Err(error)) => {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+        None => {}
+    }
+
+    action
+}
+
+// Returns whether the range should be exported
+fn range_ui(ui: &mut egui::Ui, panel: &mut ExportPanel, length: u32, playhead: u32) -> bool {
+    ui.label("Exports only this part of the map, starting at zero with the audio cut to match");
+
+    egui::Grid::new("export_range")
+        .num_columns(3)
+        .show(ui, |ui| {
+            ui.label("Start");
+            ui.add(
+                egui::DragValue::new(&mut panel.range_start_ms)
+                    .range(0..=length)
+                    .suffix("ms"),
+            );
+            if ui.button("Playhead").clicked() {
+                panel.range_start_ms = playhead.min(length);
+            }
+            ui.end_row();
+
+            ui.label("End");
+            ui.add(
+                egui::DragValue::new(&mut panel.range_end_ms)
+                    .range(0..=length)
+                    .suffix("ms"),
+            );
+            if ui.button("Playhead").clicked() {
+                panel.range_end_ms = playhead.min(length);
+            }
+            ui.end_row();
+        });
+
+    let export = ui
+        .add_enabled(
+            panel.range_end_ms > panel.range_start_ms,
+            egui::Button::new("Export range"),
+        )
+        .clicked();
+
+    if let Some(error) = &panel.range_error {
+        ui.colored_label(ui.visuals().error_fg_color, error);
+    }
+
+    export
+}
+
+fn size_ui(ui: &mut egui::Ui, size: &SizeReport) {
+    egui::Grid::new(ui.next_auto_id())
+        .num_columns(3)
+        .striped(true)
+        .show(ui, |ui| {
+            for (name, bytes) in size.sections.iter() {
+                ui.label(name);
+                ui.label(format_size(*bytes));
+                ui.label(format!("{:.1}%", size.share(*bytes) * 100.0));
+                ui.end_row();
+            }
+        });
+
+    ui.label(format!(
+        "{} grid notes at {} bytes, {} quantum notes at {} bytes, {} in total",
+        size.grid_notes,
+        size.grid_note_bytes,
+        size.quantum_notes,
+        size.quantum_note_bytes,
+        format_size(size.note_bytes())
+    ));
+
+    for hint in size.hints() {
+        ui.weak(hint);
+    }
+}
@@ -0,0 +1,291 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    editor::{
+        commands::{EditCommand, Selection},
+        editing::EditRequest,
+        shortcuts::{EditorAction, EditorActionEvent},
+        timeline::SnapSettings,
+    },
+    maps::{
+        CurrentMap, Map,
+        objects::{Note, NoteId},
+    },
+    modchart::random::Rng,
+    player::playback::PlaybackClock,
+};
+
+// Quantum positions are rolled again this many times before giving up on the constraints
+const QUANTUM_ATTEMPTS: usize = 32;
+
+// Fills larger than this are most likely a typo in the range
+const MAX_NOTES: usize = 10_000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FillSettings {
+    pub seed: u64,
+    pub start: u32,
+    pub end: u32,
+    pub divisor: u32,
+    // Furthest a note may be from the one before it, in cells
+    pub max_jump: f32,
+    pub allow_repeats: bool,
+    // Places notes anywhere on the grid instead of only on cells
+    pub quantum: bool,
+}
+
+impl Default for FillSettings {
+    fn default() -> Self {
+        Self {
+            seed: 1,
+            start: 0,
+            end: 2000,
+            divisor: 4,
+            max_jump: 2.0,
+            allow_repeats: false,
+            quantum: false,
+        }
+    }
+}
+
+// Every 1/divisor beat from start to end, following the map's timing points. Nothing is placed
+// past the end of the song
+fn snap_times(map: &Map, start: u32, end: u32, divisor: u32) -> Vec<u32> {
+    let divisor = divisor.max(1);
+    let end = end.min(map.length);
+    let mut times = Vec::new();
+    if start > end {
+        return times;
+    }
+
+    let mut time = map.snap(start as f64, divisor);
+    if time < start as f64 - 0.5 {
+        time = map.snap(time + step(map, time, divisor), divisor);
+    }
+
+    while time <= end as f64 + 0.5 && times.len() < MAX_NOTES {
+        times.push(time.round().max(0.0) as u32);
+        let step = step(map, time, divisor);
+        // Snapping right after a timing point can land back on the same time
+        time = map.snap(time + step, divisor).max(time + step / 2.0);
+    }
+
+    times.dedup();
+    times
+}
+
+fn step(map: &Map, millisecond: f64, divisor: u32) -> f64 {
+    let beat = map
+        .timing_point_at(millisecond.max(0.0) as u32)
+        .map_or(500.0, |timing| timing.beat_length());
+    beat / divisor as f64
+}
+
+fn next_position(rng: &mut Rng, previous: Option<Vec2>, settings: &FillSettings) -> Vec2 {
+    let allowed = |position: Vec2| match previous {
+        None => true,
+        Some(previous) => {
+            let distance = position.distance(previous);
+            distance <= settings.max_jump + f32::EPSILON
+                && (settings.allow_repeats || distance > 0.01)
+        }
+    };
+
+    if settings.quantum {
+        for _ in 0..QUANTUM_ATTEMPTS {
+            let position = (Vec2::new(rng.unit(), rng.unit()) * 2.0 * 100.0).round() / 100.0;
+            if allowed(position) {
+                return position;
+            }
+        }
+        // Out of luck, keep the jump limit by stepping towards a random point instead
+        let target = Vec2::new(rng.unit(), rng.unit()) * 2.0;
+        return match previous {
+            Some(previous) => previous + (target - previous).clamp_length_max(settings.max_jump),
+            None => target,
+        };
+    }
+
+    let cells: Vec<Vec2> = (0..9)
+        .map(|i| Vec2::new((i % 3) as f32, (i / 3) as f32))
+        .collect();
+    let candidates: Vec<Vec2> = cells.iter().copied().filter(|c| allowed(*c)).collect();
+
+    // A jump limit under one cell can't be kept without repeats, moving anywhere beats stalling
+    let candidates = match candidates.is_empty() {
+        true => cells.into_iter().filter(|c| Some(*c) != previous).collect(),
+        false => candidates,
+    };
+    candidates[rng.below(candidates.len())]
+}
+
+// Always the same notes for the same map timing and settings
+pub fn random_fill(map: &Map, settings: &FillSettings) -> Vec<Note> {
+    let mut rng = Rng(settings.seed);
+    let mut previous = None;
+
+    snap_times(map, settings.start, settings.end, settings.divisor)
+        .into_iter()
+        .map(|millisecond| {
+            let position = next_position(&mut rng, previous, settings);
+            previous = Some(position);
+            Note {
+                id: NoteId::next(),
+                millisecond,
+                position,
+                hitsound: None,
+                color: None,
+            }
+        })
+        .collect()
+}
+
+#[derive(Resource, Default)]
+pub struct FillTool {
+    pub open: bool,
+    settings: FillSettings,
+    // Removes the notes already in the range before filling it
+    replace: bool,
+}
+
+pub fn toggle_fill_tool(mut actions: EventReader<EditorActionEvent>, mut tool: ResMut<FillTool>) {
+    for EditorActionEvent(action) in actions.read() {
+        if *action == EditorAction::ToggleFillTool {
+            tool.open = !tool.open;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn fill_tool_ui(
+    mut contexts: EguiContexts,
+    mut tool: ResMut<FillTool>,
+    mut requests: EventWriter<EditRequest>,
+    selection: Res<Selection>,
+    snap: Res<SnapSettings>,
+    clock: Res<PlaybackClock>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+) -> Result {
+    if !tool.open {
+        return Ok(());
+    }
+
+    let Some(map) = current_map.and_then(|m| maps.get(&m.0)) else {
+        return Ok(());
+    };
+
+    let mut open = tool.open;
+    let mut command = None;
+
+    egui::Window::new("Random fill")
+        .open(&mut open)
+        .resizable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            let tool = tool.as_mut();
+            let settings = &mut tool.settings;
+
+            egui::Grid::new("fill_tool").num_columns(2).show(ui, |ui| {
+                ui.label("From");
+                ui.add(
+                    egui::DragValue::new(&mut settings.start)
+                        .range(0..=map.length)
+                        .suffix("ms"),
+                );
+                ui.end_row();
+
+                ui.label("To");
+                ui.add(
+                    egui::DragValue::new(&mut settings.end)
+                        .range(settings.start..=map.length.max(settings.start))
+                        .suffix("ms"),
+                );
+                ui.end_row();
+
+                ui.label("Snap");
+                ui.add(
+                    egui::DragValue::new(&mut settings.divisor)
+                        .range(1..=64)
+                        .prefix("1/"),
+                );
+                ui.end_row();
+
+                ui.label("Max jump");
+                ui.add(
+                    egui::DragValue::new(&mut settings.max_jump)
+                        .range(0.0..=3.0)
+                        .speed(0.01)
+                        .suffix(" cells"),
+                );
+                ui.end_row();
+
+                ui.label("Repeats");
+                ui.checkbox(&mut settings.allow_repeats, "Allow the same position twice");
+                ui.end_row();
+
+                ui.label("Quantum");
+                ui.checkbox(&mut settings.quantum, "Place between cells");
+                ui.end_row();
+
+                ui.label("Seed");
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut settings.seed));
+                    if ui.small_button("New").clicked() {
+                        settings.seed = Rng(settings.seed).next_u64();
+                    }
+                });
+                ui.end_row();
+            });
+
+            ui.horizontal(|ui| {
+                if ui.button("Playhead").clicked() {
+                    let length = settings.end.saturating_sub(settings.start);
+                    settings.start = (clock.millisecond.max(0.0) as u32).min(map.length);
+                    settings.end = settings.start.saturating_add(length).min(map.length);
+                }
+
+                let times = || selection.0.iter().filter_map(|&i| map.notes.get(i));
+                let range = times()
+                    .map(|n| n.millisecond)
+                    .min()
+                    .zip(times().map(|n| n.millisecond).max());
+                if ui
+                    .add_enabled(range.is_some(), egui::Button::new("Selection"))
+                    .clicked()
+                    && let Some((start, end)) = range
+                {
+                    settings.start = start.min(map.length);
+                    settings.end = end.min(map.length);
+                }
+
+                if ui.button("Editor snap").clicked() {
+                    settings.divisor = snap.divisor;
+                }
+            });
+
+            ui.checkbox(&mut tool.replace, "Replace notes in the range");
+
+            let settings = &tool.settings;
+            let count = snap_times(map, settings.start, settings.end, settings.divisor).len();
+            ui.label(format!("{count} notes"));
+
+            if ui
+                .add_enabled(count > 0, egui::Button::new("Fill"))
+                .clicked()
+            {
+                command = Some(EditCommand::Fill {
+                    notes: random_fill(map, settings),
+                    replace: tool.replace,
+                });
+            }
+        });
+
+    tool.open = open;
+
+    if let Some(command) = command {
+        requests.write(EditRequest::Execute(command));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use std::ops::Range;
+
+use bevy::{prelude::*, render::view::RenderLayers};
+
+use crate::{
+    analysis::heatmap::Heatmap,
+    editor::{
+        camera::EDITOR_LAYER,
+        shortcuts::{EditorAction, EditorActionEvent},
+    },
+    maps::{CurrentMap, Map},
+    player::playfield::{CELL_SIZE, GRID_CELLS, Playfield},
+};
+
+#[derive(Resource, Debug)]
+pub struct HeatmapSettings {
+    pub enabled: bool,
+    pub resolution: u32,
+    // Only notes inside this range are counted, the whole map otherwise
+    pub range: Option<Range<u32>>,
+}
+
+impl Default for HeatmapSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            resolution: 64,
+            range: None,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct HeatmapOverlay;
+
+pub fn toggle_heatmap(
+    mut actions: EventReader<EditorActionEvent>,
+    mut settings: ResMut<HeatmapSettings>,
+) {
+    for EditorActionEvent(action) in actions.read() {
+        if *action == EditorAction::ToggleHeatmap {
+            settings.enabled = !settings.enabled;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_heatmap_overlay(
+    mut commands: Commands,
+    settings: Res<HeatmapSettings>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+    mut map_events: EventReader<AssetEvent<Map>>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    overlays: Query<Entity, With<HeatmapOverlay>>,
+    playfield: Query<Entity, With<Playfield>>,
+) {
+    let map_changed = map_events.read().count() > 0;
+    let map_switched = current_map.as_ref().is_some_and(|m| m.is_changed());
+
+    if !(settings.is_changed() || map_changed || map_switched) {
+        return;
+    }
+
+    for overlay in overlays.iter() {
+        commands.entity(overlay).despawn();
+    }
+
+    if !settings.enabled {
+        return;
+    }
+
+    let (Some(current_map), Ok(playfield)) = (current_map, playfield.single()) else {
+        return;
+    };
+
+    let Some(map) = maps.get(&current_map.0) else {
+        return;
+    };
+
+    let heatmap = Heatmap::from_notes(&map.notes, settings.range.clone(), settings.resolution);
+    let size = GRID_CELLS as f32 * CELL_SIZE;
+
+    let material = materials.add(StandardMaterial {
+        base_color_texture: Some(images.add(heatmap.to_image())),
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    });
+
+    commands.entity(playfield).with_child((
+        HeatmapOverlay,
+        Mesh3d(meshes.add(Rectangle::new(size, size))),
+        MeshMaterial3d(material),
+        // Slightly in front of the grid so it doesn't z-fight with anything drawn on it
+        Transform::from_xyz(0.0, 0.0, 0.01),
+        RenderLayers::layer(EDITOR_LAYER),
+    ));
+}
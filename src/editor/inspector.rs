@@ -0,0 +1,287 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    editor::{
+        commands::{EditCommand, Selection},
+        editing::EditRequest,
+        shortcuts::{EditorAction, EditorActionEvent},
+    },
+    jukebox::hitsounds::{SAMPLE_NAMES, sample_name},
+    maps::{
+        CurrentMap, Map,
+        objects::{Hitsound, Note},
+        parser::is_quantum,
+    },
+    theme::Theme,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field {
+    Millisecond,
+    X,
+    Y,
+    Volume,
+}
+
+#[derive(Resource, Default)]
+pub struct NoteInspector {
+    pub open: bool,
+    // Value of the field being dragged, committed on release so a drag is a single undo step
+    dragged: Option<(Field, f64)>,
+    // Allows positions between cells, turned on by itself for notes that already are
+    quantum: bool,
+}
+
+impl NoteInspector {
+    // Shows a drag value for `field` and returns the value to commit once the edit is done
+    fn drag(
+        &mut self,
+        ui: &mut egui::Ui,
+        field: Field,
+        value: f64,
+        edit: impl FnOnce(egui::DragValue) -> egui::DragValue,
+    ) -> Option<f64> {
+        let mut value = match self.dragged {
+            Some((dragged, value)) if dragged == field => value,
+            _ => value,
+        };
+
+        let response = ui.add(edit(egui::DragValue::new(&mut value)));
+
+        if response.dragged() {
+            self.dragged = Some((field, value));
+            None
+        } else if response.drag_stopped() || response.changed() {
+            self.dragged = None;
+            Some(value)
+        } else {
+            None
+        }
+    }
+}
+
+pub fn toggle_inspector(
+    mut actions: EventReader<EditorActionEvent>,
+    mut inspector: ResMut<NoteInspector>,
+) {
+    for EditorActionEvent(action) in actions.read() {
+        if *action == EditorAction::ToggleInspector {
+            inspector.open = !inspector.open;
+        }
+    }
+}
+
+// The first selected note's value, None when the selected notes disagree
+fn shared<T: PartialEq + Copy>(notes: &[Note], value: impl Fn(&Note) -> T) -> Option<T> {
+    let first = value(notes.first()?);
+    notes.iter().all(|n| value(n) == first).then_some(first)
+}
+
+pub fn inspector_ui(
+    mut contexts: EguiContexts,
+    mut inspector: ResMut<NoteInspector>,
+    mut requests: EventWriter<EditRequest>,
+    selection: Res<Selection>,
+    theme: Res<Theme>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+) -> Result {
+    if !inspector.open {
+        return Ok(());
+    }
+
+    let Some(map) = current_map.and_then(|m| maps.get(&m.0)) else {
+        return Ok(());
+    };
+
+    let notes: Vec<Note> = selection
+        .0
+        .iter()
+        .filter_map(|&i| map.notes.get(i))
+        .copied()
+        .collect();
+
+    let mut open = inspector.open;
+    let mut command = None;
+
+    egui::Window::new("Inspector")
+        .open(&mut open)
+        .resizable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            let Some(first) = notes.first().copied() else {
+                ui.label("No notes selected");
+                return;
+            };
+
+            ui.label(format!("{} notes selected", notes.len()));
+            ui.separator();
+
+            let inspector = inspector.as_mut();
+            let quantum = inspector.quantum || notes.iter().any(|n| is_quantum(n.position));
+            let hitsound = shared(&notes, |n| n.hitsound);
+            let color = shared(&notes, |n| n.color);
+
+            egui::Grid::new("note_inspector")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    // Several notes are moved together, keeping the spacing between them
+                    ui.label("Time");
+                    let time =
+                        inspector.drag(ui, Field::Millisecond, first.millisecond as f64, |d| {
+                            d.range(0.0..=u32::MAX as f64)
+                                .suffix("ms")
+                                .fixed_decimals(0)
+                        });
+                    if let Some(time) = time {
+                        let milliseconds = time.round() as i64 - first.millisecond as i64;
+                        command = Some(EditCommand::Offset { milliseconds });
+                    }
+                    ui.end_row();
+
+                    for (field, label, value) in [
+                        (Field::X, "X", shared(&notes, |n| n.position.x)),
+                        (Field::Y, "Y", shared(&notes, |n| n.position.y)),
+                    ] {
+                        ui.label(label);
+                        let shown = value.unwrap_or(match field {
+                            Field::X => first.position.x,
+                            _ => first.position.y,
+                        });
+
+                        let edited = ui
+                            .horizontal(|ui| {
+                                let edited =
+                                    inspector.drag(ui, field, shown as f64, |d| match quantum {
+                                        true => d.range(0.0..=2.0).speed(0.01).fixed_decimals(2),
+                                        false => d.range(0.0..=2.0).speed(0.05).fixed_decimals(0),
+                                    });
+
+                                if value.is_none() {
+                                    ui.label("Mixed");
+                                }
+                                edited
+                            })
+                            .inner;
+
+                        if let Some(edited) = edited {
+                            let edited = match quantum {
+                                true => edited as f32,
+                                false => (edited as f32).round(),
+                            };
+                            command = Some(match field {
+                                Field::X => EditCommand::SetPosition {
+                                    x: Some(edited),
+                                    y: None,
+                                },
+                                _ => EditCommand::SetPosition {
+                                    x: None,
+                                    y: Some(edited),
+                                },
+                            });
+                        }
+                        ui.end_row();
+                    }
+
+                    // Turning quantum off moves every selected note onto the nearest cell
+                    ui.label("Quantum");
+                    let mut checked = quantum;
+                    if ui.checkbox(&mut checked, "").changed() {
+                        inspector.quantum = checked;
+                        if !checked {
+                            command = Some(EditCommand::SnapToGrid);
+                        }
+                    }
+                    ui.end_row();
+
+                    ui.label("Color");
+                    let selected_text = match color {
+                        None => "Mixed".to_string(),
+                        Some(None) => "Automatic".to_string(),
+                        Some(Some(index)) => format!("Color {}", index + 1),
+                    };
+                    egui::ComboBox::from_id_salt("note_color")
+                        .selected_text(selected_text)
+                        .show_ui(ui, |ui| {
+                            if ui
+                                .selectable_label(color == Some(None), "Automatic")
+                                .clicked()
+                            {
+                                command = Some(EditCommand::SetColor { color: None });
+                            }
+
+                            for (index, rgb) in theme.notes.iter().enumerate() {
+                                let index = index as u8;
+                                let label = egui::RichText::new(format!("Color {}", index + 1))
+                                    .color(rgb.egui());
+
+                                if ui
+                                    .selectable_label(color == Some(Some(index)), label)
+                                    .clicked()
+                                {
+                                    command = Some(EditCommand::SetColor { color: Some(index) });
+                                }
+                            }
+                        });
+                    ui.end_row();
+
+                    ui.label("Hitsound");
+                    let selected_text = match hitsound {
+                        None => "Mixed".to_string(),
+                        Some(None) => "None".to_string(),
+                        Some(Some(hitsound)) => sample_name(hitsound.sample),
+                    };
+
+                    egui::ComboBox::from_id_salt("hitsound_sample")
+                        .selected_text(selected_text)
+                        .show_ui(ui, |ui| {
+                            if ui
+                                .selectable_label(hitsound == Some(None), "None")
+                                .clicked()
+                            {
+                                command = Some(EditCommand::SetHitsound { hitsound: None });
+                            }
+
+                            for (sample, name) in SAMPLE_NAMES.iter().enumerate() {
+                                let sample = sample as u8;
+                                let current = hitsound.flatten().filter(|h| h.sample == sample);
+
+                                if ui.selectable_label(current.is_some(), *name).clicked() {
+                                    let volume = hitsound.flatten().map_or(1.0, |h| h.volume);
+                                    command = Some(EditCommand::SetHitsound {
+                                        hitsound: Some(Hitsound { sample, volume }),
+                                    });
+                                }
+                            }
+                        });
+                    ui.end_row();
+
+                    // Volume is only editable when every selected note plays the same hitsound
+                    if let Some(Some(hitsound)) = hitsound {
+                        ui.label("Volume");
+
+                        let volume =
+                            inspector.drag(ui, Field::Volume, hitsound.volume as f64, |d| {
+                                d.range(0.0..=1.0).speed(0.01).fixed_decimals(2)
+                            });
+                        if let Some(volume) = volume {
+                            command = Some(EditCommand::SetHitsound {
+                                hitsound: Some(Hitsound {
+                                    volume: volume as f32,
+                                    ..hitsound
+                                }),
+                            });
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+
+    inspector.open = open;
+
+    if let Some(command) = command {
+        requests.write(EditRequest::Execute(command));
+    }
+
+    Ok(())
+}
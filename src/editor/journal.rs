@@ -0,0 +1,261 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    editor::{
+        editing::NotesEdited,
+        shortcuts::{EditorAction, EditorActionEvent},
+    },
+    gameplay::{Session, SessionEnd, pause::PauseMenu, playing},
+    maps::{CurrentMap, Map},
+    player::playback::PlaybackClock,
+    settings::{
+        journal::{Journal, JournalEntry, format_duration, section_at},
+        recovery::SafeMode,
+    },
+};
+
+// Time tracked since the last save is lost if the app crashes, at most this much of it
+const SAVE_INTERVAL_SECS: f32 = 60.0;
+
+// Sections listed for the open map in the journal window
+const LISTED_SECTIONS: usize = 5;
+
+// Follows what is being done with the open map. The last entry of the journal is the one for it
+#[derive(Resource, Default)]
+pub struct JournalTracker {
+    map: Option<AssetId<Map>>,
+    // Attempt of the session seen last frame, None outside of sessions
+    attempt: Option<u32>,
+    // Where the current attempt is, so a restart knows where it was made
+    last_ms: f64,
+    counted_clear: bool,
+    unsaved: bool,
+    since_save: f32,
+}
+
+#[derive(Resource, Default)]
+pub struct JournalPanel {
+    pub open: bool,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |age| age.as_secs())
+}
+
+pub fn toggle_journal(
+    mut actions: EventReader<EditorActionEvent>,
+    mut panel: ResMut<JournalPanel>,
+) {
+    for EditorActionEvent(action) in actions.read() {
+        if *action == EditorAction::ToggleJournal {
+            panel.open = !panel.open;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn track_journal(
+    time: Res<Time>,
+    mut journal: ResMut<Journal>,
+    mut tracker: ResMut<JournalTracker>,
+    mut edits: EventReader<NotesEdited>,
+    session: Option<Res<Session>>,
+    pause: Res<PauseMenu>,
+    clock: Res<PlaybackClock>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+) {
+    let Some((id, map)) = current_map.and_then(|m| Some((m.0.id(), maps.get(&m.0)?))) else {
+        edits.clear();
+        return;
+    };
+
+    let tracker = tracker.as_mut();
+    if tracker.map != Some(id) {
+        journal.push(JournalEntry::new(map, now()));
+        tracker.map = Some(id);
+        tracker.unsaved = true;
+    }
+    let Some(entry) = journal.entries.last_mut() else {
+        return;
+    };
+
+    for NotesEdited(edit) in edits.read() {
+        let (added, removed) = (edit.added.len() as u32, edit.removed.len() as u32);
+        entry.notes_placed += added.saturating_sub(removed);
+        entry.notes_removed += removed.saturating_sub(added);
+    }
+
+    // Time only counts while the app is in front, not while it is left open in the background
+    let delta = time.delta_secs_f64();
+    let focused = windows.single().is_ok_and(|window| window.focused);
+    match session.as_deref() {
+        Some(session) if playing(Some(session), &pause) => entry.playing_secs += delta,
+        None if focused => entry.editing_secs += delta,
+        _ => {}
+    }
+
+    match session.as_deref() {
+        Some(session) => {
+            if tracker.attempt != Some(session.attempt) {
+                if tracker.attempt.is_some_and(|last| session.attempt > last) {
+                    *entry
+                        .retries
+                        .entry(section_at(map, tracker.last_ms))
+                        .or_default() += 1;
+                }
+                entry.plays += 1;
+                tracker.attempt = Some(session.attempt);
+                tracker.counted_clear = false;
+            }
+
+            if session.end == Some(SessionEnd::Cleared) && !tracker.counted_clear {
+                entry.clears += 1;
+                tracker.counted_clear = true;
+            }
+            tracker.last_ms = session.failed_ms.unwrap_or(clock.millisecond);
+        }
+        None => tracker.attempt = None,
+    }
+
+    tracker.unsaved = true;
+    tracker.since_save += time.delta_secs();
+}
+
+// Saved every minute and when the app quits, not every frame the time goes up
+pub fn save_journal(
+    mut exits: EventReader<AppExit>,
+    mut tracker: ResMut<JournalTracker>,
+    journal: Res<Journal>,
+    safe_mode: Res<SafeMode>,
+) {
+    let exiting = exits.read().count() > 0;
+    if !tracker.unsaved
+        || safe_mode.active
+        || !(exiting || tracker.since_save >= SAVE_INTERVAL_SECS)
+    {
+        return;
+    }
+
+    tracker.unsaved = false;
+    tracker.since_save = 0.0;
+    if let Err(e) = journal.save() {
+        warn!("Could not save the journal: {e}");
+    }
+}
+
+fn week_label(ago: u64) -> String {
+    match ago {
+        0 => "This week".to_string(),
+        1 => "Last week".to_string(),
+        weeks => format!("{weeks} weeks ago"),
+    }
+}
+
+pub fn journal_ui(
+    mut contexts: EguiContexts,
+    mut panel: ResMut<JournalPanel>,
+    tracker: Res<JournalTracker>,
+    journal: Res<Journal>,
+) -> Result {
+    if !panel.open {
+        return Ok(());
+    }
+
+    let mut open = panel.open;
+    let current = journal.entries.last().filter(|_| tracker.map.is_some());
+
+    egui::Window::new("Journal")
+        .open(&mut open)
+        .resizable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            if let Some(entry) = current {
+                ui.strong(format!("This session on {}", entry.title));
+                egui::Grid::new("journal_session")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Editing");
+                        ui.label(format_duration(entry.editing_secs));
+                        ui.end_row();
+
+                        ui.label("Playing");
+                        ui.label(format_duration(entry.playing_secs));
+                        ui.end_row();
+
+                        ui.label("Notes");
+                        ui.label(format!(
+                            "{} placed, {} removed",
+                            entry.notes_placed, entry.notes_removed
+                        ));
+                        ui.end_row();
+
+                        ui.label("Plays");
+                        ui.label(format!(
+                            "{} plays, {} cleared, {} retries",
+                            entry.plays,
+                            entry.clears,
+                            entry.total_retries()
+                        ));
+                        ui.end_row();
+                    });
+
+                // Over every time the map was played, not just this session
+                let retries = journal.retries_for(&entry.map_id);
+                if !retries.is_empty() {
+                    ui.separator();
+                    ui.label("Most retried sections");
+                    egui::Grid::new("journal_sections")
+                        .num_columns(2)
+                        .show(ui, |ui| {
+                            for (section, count) in retries.iter().take(LISTED_SECTIONS) {
+                                ui.label(section);
+                                ui.label(count.to_string());
+                                ui.end_row();
+                            }
+                        });
+                }
+                ui.separator();
+            }
+
+            let weeks = journal.weeks(now());
+            if weeks.is_empty() {
+                ui.label("Nothing in the journal yet, it fills up as maps are edited and played");
+                return;
+            }
+
+            egui::ScrollArea::vertical()
+                .max_height(240.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("journal_weeks")
+                        .num_columns(6)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for heading in ["", "Editing", "Playing", "Placed", "Plays", "Retries"]
+                            {
+                                ui.strong(heading);
+                            }
+                            ui.end_row();
+
+                            for (ago, week) in weeks.iter() {
+                                ui.label(week_label(*ago));
+                                ui.label(format_duration(week.editing_secs));
+                                ui.label(format_duration(week.playing_secs));
+                                ui.label(week.notes_placed.to_string());
+                                ui.label(format!("{} ({} cleared)", week.plays, week.clears));
+                                ui.label(week.total_retries().to_string());
+                                ui.end_row();
+                            }
+                        });
+                });
+        });
+
+    panel.open = open;
+
+    Ok(())
+}
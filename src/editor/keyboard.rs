@@ -0,0 +1,150 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui, input::EguiWantsInput};
+
+use crate::{
+    editor::{
+        commands::EditCommand,
+        editing::EditRequest,
+        shortcuts::{EditorAction, EditorActionEvent, KeyChord, Keybinds},
+        timeline::SnapSettings,
+    },
+    settings::Settings,
+};
+
+// The grid cell each action places on. Cells are read by the key of their binding, so Ctrl and
+// Shift can be held with it to delete and move instead
+const CELL_ACTIONS: [(EditorAction, Vec2); 9] = [
+    (EditorAction::ChartCellTopLeft, Vec2::new(0.0, 0.0)),
+    (EditorAction::ChartCellTop, Vec2::new(1.0, 0.0)),
+    (EditorAction::ChartCellTopRight, Vec2::new(2.0, 0.0)),
+    (EditorAction::ChartCellLeft, Vec2::new(0.0, 1.0)),
+    (EditorAction::ChartCellCenter, Vec2::new(1.0, 1.0)),
+    (EditorAction::ChartCellRight, Vec2::new(2.0, 1.0)),
+    (EditorAction::ChartCellBottomLeft, Vec2::new(0.0, 2.0)),
+    (EditorAction::ChartCellBottom, Vec2::new(1.0, 2.0)),
+    (EditorAction::ChartCellBottomRight, Vec2::new(2.0, 2.0)),
+];
+
+// Charting without the mouse. The keys only translate into the same actions and edit requests
+// the shortcuts and tools send, so snapping, undo and macro recording behave as usual
+#[derive(Resource, Default, Debug)]
+pub struct KeyboardCharting {
+    pub enabled: bool,
+}
+
+impl KeyboardCharting {
+    // Shortcuts on a cell's key wait until the mode is turned off again
+    pub fn claims(&self, keybinds: &Keybinds, chord: &KeyChord) -> bool {
+        self.enabled && cell_for_key(keybinds, chord.key).is_some()
+    }
+}
+
+pub fn cell_for_key(keybinds: &Keybinds, key: KeyCode) -> Option<Vec2> {
+    CELL_ACTIONS
+        .iter()
+        .find(|(action, _)| keybinds.get(*action).is_some_and(|chord| chord.key == key))
+        .map(|(_, cell)| *cell)
+}
+
+pub fn toggle_keyboard_charting(
+    mut actions: EventReader<EditorActionEvent>,
+    mut charting: ResMut<KeyboardCharting>,
+) {
+    for EditorActionEvent(action) in actions.read() {
+        if *action == EditorAction::ToggleKeyboardCharting {
+            charting.enabled = !charting.enabled;
+            info!(
+                "Keyboard charting {}",
+                if charting.enabled { "on" } else { "off" }
+            );
+        }
+    }
+}
+
+// A cell's key places on it at the playhead, with Ctrl it deletes there and with Shift the
+// selection is moved onto the cell. The other charting actions stand in for editor ones
+pub fn keyboard_charting(
+    keys: Res<ButtonInput<KeyCode>>,
+    charting: Res<KeyboardCharting>,
+    settings: Res<Settings>,
+    egui_input: Res<EguiWantsInput>,
+    mut actions: ParamSet<(
+        EventReader<EditorActionEvent>,
+        EventWriter<EditorActionEvent>,
+    )>,
+    mut requests: EventWriter<EditRequest>,
+) {
+    let translated: Vec<EditorAction> = actions
+        .p0()
+        .read()
+        .filter_map(|EditorActionEvent(action)| match action {
+            EditorAction::ChartStepForward => Some(EditorAction::SeekForward),
+            EditorAction::ChartStepBackward => Some(EditorAction::SeekBackward),
+            EditorAction::ChartSnapIncrease => Some(EditorAction::SnapIncrease),
+            EditorAction::ChartSnapDecrease => Some(EditorAction::SnapDecrease),
+            EditorAction::ChartUndo => Some(EditorAction::Undo),
+            EditorAction::ChartRedo => Some(EditorAction::Redo),
+            _ => None,
+        })
+        .collect();
+
+    if !charting.enabled || egui_input.wants_any_keyboard_input() {
+        return;
+    }
+
+    for action in translated {
+        actions.p1().write(EditorActionEvent(action));
+    }
+
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    for key in keys.get_just_pressed() {
+        let Some(position) = cell_for_key(&settings.keybinds, *key) else {
+            continue;
+        };
+
+        let command = match (ctrl, shift) {
+            (true, _) => EditCommand::DeleteAt { position },
+            (false, true) => EditCommand::SetPosition {
+                x: Some(position.x),
+                y: Some(position.y),
+            },
+            (false, false) => EditCommand::PlaceNote { position },
+        };
+        requests.write(EditRequest::Execute(command));
+    }
+}
+
+pub fn keyboard_charting_ui(
+    mut contexts: EguiContexts,
+    charting: Res<KeyboardCharting>,
+    snap: Res<SnapSettings>,
+    settings: Res<Settings>,
+) -> Result {
+    if !charting.enabled {
+        return Ok(());
+    }
+
+    let key = |action| match settings.keybinds.get(action) {
+        Some(chord) => chord.to_string(),
+        None => "unbound".to_string(),
+    };
+
+    egui::Area::new(egui::Id::new("keyboard_charting"))
+        .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 12.0))
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.label(format!("Keyboard charting, 1/{} beats", snap.divisor));
+            ui.small("Cell keys place, Ctrl deletes, Shift moves the selection");
+            ui.small(format!(
+                "{}/{} step, {}/{} change the snap, {} undoes",
+                key(EditorAction::ChartStepForward),
+                key(EditorAction::ChartStepBackward),
+                key(EditorAction::ChartSnapIncrease),
+                key(EditorAction::ChartSnapDecrease),
+                key(EditorAction::ChartUndo),
+            ));
+        });
+
+    Ok(())
+}
@@ -0,0 +1,260 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    editor::shortcuts::{EditorAction, EditorActionEvent},
+    jukebox::hitsounds::HitsoundBank,
+    settings::Settings,
+};
+
+// Calibration beats come at 120 BPM
+const BEAT_SECONDS: f64 = 0.5;
+const CALIBRATION_BEATS: usize = 16;
+
+// Taps before these beats are the player finding the rhythm
+const WARMUP_BEATS: usize = 3;
+const MIN_TAPS: usize = 4;
+
+// How long the visual beat stays lit
+const FLASH_SECONDS: f64 = 0.1;
+
+// Taps along to a beat, how late they land on average is the latency
+#[derive(Default)]
+pub struct Calibration {
+    // When the first beat of the current run was played
+    pub started: Option<f64>,
+    pub beats: Vec<f64>,
+    // Seconds from the nearest beat to each tap
+    pub taps: Vec<f64>,
+}
+
+impl Calibration {
+    pub fn start(now: f64) -> Self {
+        Self {
+            started: Some(now),
+            ..default()
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started.is_some()
+    }
+
+    // True on the frame the next beat is due, which is recorded at that frame's time since frames
+    // don't line up with the beat. Stops the run a beat after the last one.
+    pub fn advance(&mut self, now: f64) -> bool {
+        let Some(started) = self.started else {
+            return false;
+        };
+
+        if self.beats.len() >= CALIBRATION_BEATS {
+            // Room for a late tap on the last beat
+            if now - started > CALIBRATION_BEATS as f64 * BEAT_SECONDS {
+                self.started = None;
+            }
+            return false;
+        }
+
+        let beat = ((now - started) / BEAT_SECONDS) as usize;
+        if beat < self.beats.len() {
+            return false;
+        }
+
+        self.beats.push(now);
+        true
+    }
+
+    pub fn tap(&mut self, now: f64) {
+        let Some(nearest) = self
+            .beats
+            .iter()
+            .skip(WARMUP_BEATS)
+            .min_by(|a, b| (*a - now).abs().total_cmp(&(*b - now).abs()))
+        else {
+            return;
+        };
+
+        let offset = now - nearest;
+        if offset.abs() < BEAT_SECONDS / 2.0 {
+            self.taps.push(offset);
+        }
+    }
+
+    // The median, so a few stray taps don't pull it off
+    pub fn offset_ms(&self) -> Option<f32> {
+        if self.taps.len() < MIN_TAPS {
+            return None;
+        }
+
+        let mut taps = self.taps.clone();
+        taps.sort_by(f64::total_cmp);
+        Some((taps[taps.len() / 2] * 1000.0) as f32)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LatencyKind {
+    // A flashing beat without sound, measures display and input latency for the visual offset
+    #[default]
+    Visual,
+    // Clicks without a flash, measures output latency for the audio offset
+    Audio,
+}
+
+impl LatencyKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            LatencyKind::Visual => "Visual",
+            LatencyKind::Audio => "Audio",
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct LatencyTest {
+    pub open: bool,
+    kind: LatencyKind,
+    calibration: Calibration,
+}
+
+pub fn toggle_latency_test(
+    mut actions: EventReader<EditorActionEvent>,
+    mut test: ResMut<LatencyTest>,
+) {
+    for EditorActionEvent(action) in actions.read() {
+        if *action == EditorAction::TestLatency {
+            test.open = !test.open;
+            test.calibration = Calibration::default();
+        }
+    }
+}
+
+// Key taps are read here rather than in the window so they are timed to the frame they came in
+pub fn run_latency_test(
+    mut commands: Commands,
+    mut actions: EventReader<EditorActionEvent>,
+    mut test: ResMut<LatencyTest>,
+    bank: Res<HitsoundBank>,
+    time: Res<Time>,
+) {
+    let tapped = actions.read().any(|EditorActionEvent(action)| {
+        matches!(
+            action,
+            EditorAction::LatencyTap | EditorAction::LatencyTapAlt
+        )
+    });
+
+    if !test.open || !test.calibration.is_running() {
+        return;
+    }
+
+    let now = time.elapsed_secs_f64();
+    let test = test.as_mut();
+
+    if test.calibration.advance(now)
+        && test.kind == LatencyKind::Audio
+        && let Some(click) = bank.0.first()
+    {
+        commands.spawn((AudioPlayer(click.clone()), PlaybackSettings::DESPAWN));
+    }
+
+    if tapped {
+        test.calibration.tap(now);
+    }
+}
+
+pub fn latency_ui(
+    mut contexts: EguiContexts,
+    mut test: ResMut<LatencyTest>,
+    mut settings: ResMut<Settings>,
+    time: Res<Time>,
+) -> Result {
+    if !test.open {
+        return Ok(());
+    }
+
+    let now = time.elapsed_secs_f64();
+    let tap_keys = [EditorAction::LatencyTap, EditorAction::LatencyTapAlt]
+        .into_iter()
+        .filter_map(|action| settings.keybinds.get(action))
+        .map(|chord| chord.to_string())
+        .collect::<Vec<_>>()
+        .join(" or ");
+    let mut open = test.open;
+    let mut measured_offset = None;
+    let test = test.as_mut();
+
+    egui::Window::new("Latency test")
+        .open(&mut open)
+        .resizable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.horizontal(|ui| {
+                for kind in [LatencyKind::Visual, LatencyKind::Audio] {
+                    let running = test.calibration.is_running();
+                    ui.add_enabled_ui(!running, |ui| {
+                        if ui.radio_value(&mut test.kind, kind, kind.label()).changed() {
+                            test.calibration = Calibration::default();
+                        }
+                    });
+                }
+            });
+
+            ui.label(match test.kind {
+                LatencyKind::Visual => format!("Press {tap_keys} each time the circle lights up"),
+                LatencyKind::Audio => format!("Press {tap_keys} in time with the clicks you hear"),
+            });
+
+            if test.kind == LatencyKind::Visual {
+                let lit = test.calibration.beats.last().is_some_and(|beat| {
+                    test.calibration.is_running() && now - beat < FLASH_SECONDS
+                });
+                let (rect, _) =
+                    ui.allocate_exact_size(egui::vec2(80.0, 80.0), egui::Sense::hover());
+                let color = match lit {
+                    true => ui.visuals().strong_text_color(),
+                    false => ui.visuals().widgets.inactive.bg_fill,
+                };
+                ui.painter().circle_filled(rect.center(), 36.0, color);
+            }
+
+            if ui
+                .add_enabled(!test.calibration.is_running(), egui::Button::new("Start"))
+                .clicked()
+            {
+                test.calibration = Calibration::start(now);
+            }
+
+            match test.calibration.offset_ms() {
+                Some(measured) => {
+                    ui.label(format!(
+                        "Measured {measured:+.0}ms over {} taps",
+                        test.calibration.taps.len()
+                    ));
+                    if !test.calibration.is_running() && ui.button("Use this offset").clicked() {
+                        measured_offset = Some(measured.round());
+                    }
+                }
+                None if test.calibration.is_running() => {
+                    ui.label(format!("{} taps so far", test.calibration.taps.len()));
+                }
+                None => {}
+            }
+
+            ui.separator();
+            ui.label(format!("Visual offset {:+.0}ms", settings.visual_offset_ms));
+            ui.label(format!("Audio offset {:+.0}ms", settings.audio_offset_ms));
+        });
+
+    match (measured_offset, test.kind) {
+        (Some(offset), LatencyKind::Visual) => settings.visual_offset_ms = offset,
+        (Some(offset), LatencyKind::Audio) => settings.audio_offset_ms = offset,
+        (None, _) => {}
+    }
+
+    if !open {
+        test.calibration = Calibration::default();
+    }
+    test.open = open;
+
+    Ok(())
+}
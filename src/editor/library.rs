@@ -0,0 +1,206 @@
+use std::{collections::BTreeSet, path::PathBuf};
+
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    analysis::tags::ChartTag,
+    editor::{
+        commands::{EditHistory, Selection},
+        metadata::MetadataTool,
+        shortcuts::{EditorAction, EditorActionEvent},
+    },
+    maps::{CurrentMap, Map, library::MapLibrary, merge::merge_on_open},
+    player::playback::PlaybackClock,
+    settings::Settings,
+};
+
+#[derive(Resource, Default)]
+pub struct LibraryPanel {
+    pub open: bool,
+    pub new_folder: String,
+    // Maps picked for the bulk metadata editor
+    pub selected: BTreeSet<PathBuf>,
+    // Only maps with every one of these tags are listed
+    pub tags: BTreeSet<ChartTag>,
+}
+
+pub fn open_library(mut actions: EventReader<EditorActionEvent>, mut panel: ResMut<LibraryPanel>) {
+    for EditorActionEvent(action) in actions.read() {
+        if *action == EditorAction::OpenLibrary {
+            panel.open = !panel.open;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn library_ui(
+    mut contexts: EguiContexts,
+    mut commands: Commands,
+    mut panel: ResMut<LibraryPanel>,
+    mut settings: ResMut<Settings>,
+    library: Res<MapLibrary>,
+    mut maps: ResMut<Assets<Map>>,
+    mut clock: ResMut<PlaybackClock>,
+    mut selection: ResMut<Selection>,
+    mut history: ResMut<EditHistory>,
+    mut metadata_tool: ResMut<MetadataTool>,
+) -> Result {
+    if !panel.open {
+        return Ok(());
+    }
+
+    let mut open = panel.open;
+    let mut opened = None;
+    let mut folders = settings.map_folders.clone();
+
+    egui::Window::new("Library")
+        .open(&mut open)
+        .default_width(420.0)
+        .show(contexts.ctx_mut()?, |ui| {
+            let panel = panel.as_mut();
+
+            ui.label("Map folders");
+            folders.retain(|folder| {
+                ui.horizontal(|ui| {
+                    ui.label(folder.as_str());
+                    !ui.small_button("Remove").clicked()
+                })
+                .inner
+            });
+
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut panel.new_folder);
+                let folder = panel.new_folder.trim().to_string();
+                if ui
+                    .add_enabled(!folder.is_empty(), egui::Button::new("Add folder"))
+                    .clicked()
+                {
+                    if !folders.contains(&folder) {
+                        folders.push(folder);
+                    }
+                    panel.new_folder.clear();
+                }
+            });
+
+            ui.separator();
+
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Tags");
+                for tag in ChartTag::ALL {
+                    let on = panel.tags.contains(&tag);
+                    if ui.selectable_label(on, tag.label()).clicked() {
+                        match on {
+                            true => panel.tags.remove(&tag),
+                            false => panel.tags.insert(tag),
+                        };
+                    }
+                }
+            });
+
+            let shown: Vec<_> = library
+                .entries
+                .iter()
+                .filter(|(_, entry)| panel.tags.iter().all(|tag| entry.tags.contains(tag)))
+                .collect();
+
+            // Maps that are gone from the folders can't stay picked
+            panel
+                .selected
+                .retain(|path| library.entries.get(path).is_some_and(|e| e.map.is_some()));
+
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(
+                        !panel.selected.is_empty(),
+                        egui::Button::new(format!("Edit metadata of {}", panel.selected.len())),
+                    )
+                    .clicked()
+                {
+                    metadata_tool.open = true;
+                }
+                if ui.button("Pick all").clicked() {
+                    panel.selected = shown
+                        .iter()
+                        .filter(|(_, entry)| entry.map.is_some())
+                        .map(|(path, _)| path.to_path_buf())
+                        .collect();
+                }
+                if ui
+                    .add_enabled(!panel.selected.is_empty(), egui::Button::new("Clear"))
+                    .clicked()
+                {
+                    panel.selected.clear();
+                }
+            });
+
+            if library.is_loading() {
+                ui.label("Loading maps...");
+            } else if library.entries.is_empty() {
+                ui.label("No maps found");
+            } else if shown.is_empty() {
+                ui.label("No maps with these tags");
+            }
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (path, entry) in shown {
+                    ui.horizontal(|ui| {
+                        let enabled = entry.map.is_some();
+
+                        let mut picked = panel.selected.contains(path);
+                        if ui
+                            .add_enabled(enabled, egui::Checkbox::without_text(&mut picked))
+                            .changed()
+                        {
+                            match picked {
+                                true => panel.selected.insert(path.clone()),
+                                false => panel.selected.remove(path),
+                            };
+                        }
+
+                        if ui.add_enabled(enabled, egui::Button::new("Open")).clicked() {
+                            opened = entry.map.clone();
+                        }
+
+                        ui.vertical(|ui| {
+                            ui.strong(&entry.title);
+                            if !entry.artists.is_empty() || !entry.mappers.is_empty() {
+                                ui.label(format!(
+                                    "{}  mapped by {}",
+                                    entry.artists.join(", "),
+                                    entry.mappers.join(", ")
+                                ));
+                            }
+                            if !entry.tags.is_empty() {
+                                let labels: Vec<_> = entry.tags.iter().map(|t| t.label()).collect();
+                                ui.weak(labels.join(", "));
+                            }
+                            if let Some(error) = &entry.error {
+                                ui.colored_label(ui.visuals().error_fg_color, error);
+                            }
+                        })
+                        .response
+                        .on_hover_text(path.display().to_string());
+                    });
+                }
+            });
+        });
+
+    panel.open = open;
+
+    if folders != settings.map_folders {
+        settings.map_folders = folders;
+    }
+
+    if let Some(handle) = opened {
+        if let Some(map) = maps.get_mut(&handle) {
+            merge_on_open(map, settings.open_merge_epsilon_ms());
+        }
+        commands.insert_resource(CurrentMap(handle));
+        clock.seek(0.0);
+        selection.0.clear();
+        history.clear();
+    }
+
+    Ok(())
+}
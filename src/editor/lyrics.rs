@@ -0,0 +1,178 @@
+use std::{fs, io, path::Path};
+
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    editor::timeline::SnapSettings,
+    maps::{
+        CurrentMap, Map,
+        lrc::parse_lrc,
+        objects::lyric::{Lyric, LyricStyle},
+    },
+    player::playback::PlaybackClock,
+};
+
+// Lyrics added by hand start out this long
+const NEW_LYRIC_MS: u32 = 2000;
+
+#[derive(Resource, Default)]
+pub struct LyricPanel {
+    pub open: bool,
+    lrc_path: String,
+    status: Option<Result<String, String>>,
+}
+
+fn import_lrc(path: &Path) -> io::Result<Vec<Lyric>> {
+    parse_lrc(&fs::read_to_string(path)?)
+}
+
+pub fn lyrics_ui(
+    mut contexts: EguiContexts,
+    mut panel: ResMut<LyricPanel>,
+    mut clock: ResMut<PlaybackClock>,
+    snap: Res<SnapSettings>,
+    current_map: Option<Res<CurrentMap>>,
+    mut maps: ResMut<Assets<Map>>,
+) -> Result {
+    if !panel.open {
+        return Ok(());
+    }
+
+    let Some(current_map) = current_map else {
+        return Ok(());
+    };
+
+    // Only borrow the map mutably when something was edited, so looking doesn't mark it as modified
+    let Some(lyrics) = maps.get(&current_map.0).map(|map| map.lyrics.clone()) else {
+        return Ok(());
+    };
+    let playhead = maps
+        .get(&current_map.0)
+        .map_or(0.0, |map| map.snap(clock.millisecond, snap.divisor))
+        .max(0.0) as u32;
+
+    let mut edited = lyrics.clone();
+    let mut removed = None;
+    let mut open = panel.open;
+    let panel = panel.as_mut();
+
+    egui::Window::new("Lyrics")
+        .open(&mut open)
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut panel.lrc_path)
+                        .hint_text("Path to a .lrc")
+                        .desired_width(220.0),
+                );
+
+                let path = panel.lrc_path.trim().to_string();
+                if ui
+                    .add_enabled(!path.is_empty(), egui::Button::new("Import"))
+                    .clicked()
+                {
+                    // Importing replaces what is there, so a corrected file can be brought in again
+                    panel.status = Some(match import_lrc(Path::new(&path)) {
+                        Ok(imported) => {
+                            let message = format!("Imported {} lines", imported.len());
+                            edited = imported;
+                            panel.lrc_path.clear();
+                            Ok(message)
+                        }
+                        Err(e) => {
+                            warn!("Could not import lyrics from {path}: {e}");
+                            Err(format!("Could not import {path}: {e}"))
+                        }
+                    });
+                }
+            });
+
+            match &panel.status {
+                Some(Ok(message)) => {
+                    ui.label(message.as_str());
+                }
+                Some(Err(e)) => {
+                    ui.colored_label(ui.visuals().error_fg_color, e.as_str());
+                }
+                None => {}
+            }
+
+            if ui.button("Add at playhead").clicked() {
+                edited.push(Lyric {
+                    millisecond: playhead,
+                    duration_ms: NEW_LYRIC_MS,
+                    text: String::new(),
+                    style: LyricStyle::Subtitle,
+                });
+            }
+            ui.separator();
+
+            if edited.is_empty() {
+                ui.label("No lyrics yet");
+            }
+
+            egui::ScrollArea::vertical()
+                .max_height(320.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("lyric_list").num_columns(6).show(ui, |ui| {
+                        for (index, lyric) in edited.iter_mut().enumerate() {
+                            ui.add(
+                                egui::TextEdit::singleline(&mut lyric.text)
+                                    .hint_text("Text")
+                                    .desired_width(200.0),
+                            );
+
+                            egui::ComboBox::from_id_salt(("lyric_style", index))
+                                .selected_text(lyric.style.label())
+                                .show_ui(ui, |ui| {
+                                    for style in LyricStyle::ALL {
+                                        ui.selectable_value(&mut lyric.style, style, style.label());
+                                    }
+                                });
+
+                            ui.add(egui::DragValue::new(&mut lyric.millisecond).suffix("ms"))
+                                .on_hover_text("Start");
+                            ui.add(egui::DragValue::new(&mut lyric.duration_ms).suffix("ms"))
+                                .on_hover_text("Duration");
+
+                            ui.horizontal(|ui| {
+                                if ui.button("Go").clicked() {
+                                    clock.seek(lyric.millisecond as f64);
+                                }
+                                // Ends the line at the playhead, for timing lines while listening
+                                if ui
+                                    .add_enabled(
+                                        playhead > lyric.millisecond,
+                                        egui::Button::new("End here"),
+                                    )
+                                    .clicked()
+                                {
+                                    lyric.duration_ms = playhead - lyric.millisecond;
+                                }
+                            });
+
+                            if ui.button("Delete").clicked() {
+                                removed = Some(index);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+        });
+
+    panel.open = open;
+
+    if let Some(index) = removed {
+        edited.remove(index);
+    }
+
+    if edited != lyrics
+        && let Some(map) = maps.get_mut(&current_map.0)
+    {
+        edited.sort_by_key(|lyric| lyric.millisecond);
+        map.lyrics = edited;
+    }
+
+    Ok(())
+}
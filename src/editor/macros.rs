@@ -0,0 +1,31 @@
+use bevy::prelude::*;
+
+use crate::editor::commands::EditCommand;
+
+#[derive(Resource, Default, Debug)]
+pub struct MacroRecorder {
+    recording: Option<Vec<EditCommand>>,
+    // Name of the most recently recorded or replayed macro
+    pub last: Option<String>,
+}
+
+impl MacroRecorder {
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    pub fn start(&mut self) {
+        self.recording = Some(Vec::new());
+    }
+
+    // Returns the recorded commands, or None if nothing was being recorded
+    pub fn stop(&mut self) -> Option<Vec<EditCommand>> {
+        self.recording.take()
+    }
+
+    pub fn record(&mut self, command: &EditCommand) {
+        if let Some(recording) = self.recording.as_mut() {
+            recording.push(command.clone());
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    editor::library::LibraryPanel,
+    maps::{
+        DIFFICULTY_NAMES, Map,
+        library::MapLibrary,
+        metadata::{BulkEdit, Casing, PlannedChange, Renumber, plan_bulk_edit, write_map},
+    },
+};
+
+// Edits the metadata of the maps picked in the library, previewed as a dry run before anything
+// is written back
+#[derive(Resource, Default)]
+pub struct MetadataTool {
+    pub open: bool,
+    edit: BulkEdit,
+    // Planned for the edit it was made with, stale once the options change
+    preview: Option<(BulkEdit, Vec<PlannedChange>)>,
+    // Files that could not be written by the last run
+    failures: Vec<(PathBuf, String)>,
+    written: usize,
+}
+
+fn renumber_label(renumber: Renumber) -> &'static str {
+    match renumber {
+        Renumber::Keep => "Keep",
+        Renumber::Set(_) => "Set to",
+        Renumber::Shift(_) => "Shift by",
+        Renumber::FromName => "From difficulty name",
+    }
+}
+
+pub fn metadata_tool_ui(
+    mut contexts: EguiContexts,
+    mut tool: ResMut<MetadataTool>,
+    library: Res<MapLibrary>,
+    library_panel: Res<LibraryPanel>,
+    mut maps: ResMut<Assets<Map>>,
+) -> Result {
+    if !tool.open {
+        return Ok(());
+    }
+
+    let mut open = tool.open;
+    let mut preview = false;
+    let mut write = false;
+    let tool = tool.as_mut();
+
+    // Maps that failed to load can't be picked in the library, they are never part of an edit
+    let selected: Vec<(PathBuf, Handle<Map>)> = library_panel
+        .selected
+        .iter()
+        .filter_map(|path| {
+            let handle = library.entries.get(path)?.map.clone()?;
+            Some((path.clone(), handle))
+        })
+        .collect();
+
+    egui::Window::new("Bulk metadata")
+        .open(&mut open)
+        .default_width(420.0)
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.label(format!("{} maps picked in the library", selected.len()));
+
+            egui::Grid::new("bulk_metadata_options")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Artists");
+                    egui::ComboBox::from_id_salt("bulk_artist_casing")
+                        .selected_text(tool.edit.artist_casing.label())
+                        .show_ui(ui, |ui| {
+                            for casing in Casing::ALL {
+                                ui.selectable_value(
+                                    &mut tool.edit.artist_casing,
+                                    casing,
+                                    casing.label(),
+                                );
+                            }
+                        });
+                    ui.end_row();
+
+                    ui.label("Mappers");
+                    ui.checkbox(&mut tool.edit.normalize_mappers, "Normalize names")
+                        .on_hover_text(
+                            "Trims the names and spells each one the way most of the maps do",
+                        );
+                    ui.end_row();
+
+                    ui.label("Difficulty");
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_salt("bulk_difficulty")
+                            .selected_text(renumber_label(tool.edit.difficulty))
+                            .show_ui(ui, |ui| {
+                                for renumber in [
+                                    Renumber::Keep,
+                                    Renumber::Set(1),
+                                    Renumber::Shift(1),
+                                    Renumber::FromName,
+                                ] {
+                                    let selected = renumber_label(renumber)
+                                        == renumber_label(tool.edit.difficulty);
+                                    if ui
+                                        .selectable_label(selected, renumber_label(renumber))
+                                        .clicked()
+                                        && !selected
+                                    {
+                                        tool.edit.difficulty = renumber;
+                                    }
+                                }
+                            });
+
+                        match &mut tool.edit.difficulty {
+                            Renumber::Set(value) => {
+                                egui::ComboBox::from_id_salt("bulk_difficulty_value")
+                                    .selected_text(DIFFICULTY_NAMES[*value as usize])
+                                    .show_ui(ui, |ui| {
+                                        for (index, name) in DIFFICULTY_NAMES.iter().enumerate() {
+                                            ui.selectable_value(value, index as u8, *name);
+                                        }
+                                    });
+                            }
+                            Renumber::Shift(by) => {
+                                ui.add(egui::DragValue::new(by).range(-5..=5));
+                            }
+                            _ => {}
+                        }
+                    });
+                    ui.end_row();
+                });
+
+            ui.horizontal(|ui| {
+                preview = ui
+                    .add_enabled(!selected.is_empty(), egui::Button::new("Preview"))
+                    .clicked();
+
+                let current = tool
+                    .preview
+                    .as_ref()
+                    .filter(|(edit, changes)| *edit == tool.edit && !changes.is_empty());
+                write = ui
+                    .add_enabled(
+                        current.is_some(),
+                        egui::Button::new(format!(
+                            "Write {} maps",
+                            current.map_or(0, |(_, changes)| changes.len())
+                        )),
+                    )
+                    .on_hover_text("Only what the preview shows is written")
+                    .clicked();
+            });
+
+            if tool.written > 0 {
+                ui.label(format!("Wrote {} maps", tool.written));
+            }
+            for (path, error) in tool.failures.iter() {
+                ui.colored_label(
+                    ui.visuals().error_fg_color,
+                    format!("{}: {error}", path.display()),
+                );
+            }
+
+            let Some((edit, changes)) = &tool.preview else {
+                return;
+            };
+
+            ui.separator();
+            if *edit != tool.edit {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    "The options changed, preview again before writing",
+                );
+            }
+
+            if changes.is_empty() {
+                ui.label("Nothing would change");
+            }
+
+            egui::ScrollArea::vertical()
+                .max_height(240.0)
+                .show(ui, |ui| {
+                    for change in changes.iter() {
+                        let name = change
+                            .path
+                            .file_name()
+                            .map(|name| name.to_string_lossy().into_owned())
+                            .unwrap_or_default();
+                        ui.strong(name)
+                            .on_hover_text(change.path.display().to_string());
+                        for line in change.describe() {
+                            ui.label(format!("  {line}"));
+                        }
+                    }
+                });
+        });
+
+    tool.open = open;
+
+    if preview {
+        let loaded: Vec<(PathBuf, &Map)> = selected
+            .iter()
+            .filter_map(|(path, handle)| Some((path.clone(), maps.get(handle)?)))
+            .collect();
+        tool.preview = Some((tool.edit.clone(), plan_bulk_edit(&loaded, &tool.edit)));
+        tool.written = 0;
+        tool.failures.clear();
+    }
+
+    if write && let Some((_, changes)) = tool.preview.take() {
+        tool.written = 0;
+        tool.failures.clear();
+
+        for change in changes {
+            let Some(handle) = library
+                .entries
+                .get(&change.path)
+                .and_then(|e| e.map.as_ref())
+            else {
+                continue;
+            };
+            let Some(map) = maps.get_mut(handle) else {
+                continue;
+            };
+
+            // Kept in memory only once the file has it, so the library never shows unsaved data
+            let mut edited = map.clone();
+            change.after.apply_to(&mut edited);
+
+            match write_map(&change.path, &edited) {
+                Ok(()) => {
+                    change.after.apply_to(map);
+                    tool.written += 1;
+                }
+                Err(e) => {
+                    warn!("Could not write {}: {e}", change.path.display());
+                    tool.failures.push((change.path, e.to_string()));
+                }
+            }
+        }
+
+        info!("Bulk metadata edit wrote {} maps", tool.written);
+    }
+
+    Ok(())
+}
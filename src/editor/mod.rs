@@ -0,0 +1,313 @@
+use bevy::{input::InputSystem, prelude::*};
+use bevy_egui::EguiPrimaryContextPass;
+
+pub mod alignment;
+pub mod annotations;
+pub mod autosave;
+pub mod bookmarks;
+pub mod bulk_delete;
+pub mod camera;
+pub mod clip;
+pub mod commands;
+pub mod cursor;
+pub mod difficulty;
+pub mod drag;
+pub mod editing;
+pub mod export;
+pub mod fill;
+pub mod heatmap;
+pub mod inspector;
+pub mod journal;
+pub mod keyboard;
+pub mod latency;
+pub mod library;
+pub mod lyrics;
+pub mod macros;
+pub mod metadata;
+pub mod note_list;
+pub mod objects;
+pub mod onboarding;
+pub mod packs;
+pub mod piano_roll;
+pub mod placement;
+pub mod preferences;
+pub mod preview;
+pub mod project;
+pub mod recovery;
+pub mod shortcuts;
+pub mod simplify;
+pub mod snapshots;
+pub mod start;
+pub mod stretch;
+pub mod tempo;
+pub mod timeline;
+pub mod updates;
+pub mod windowing;
+pub mod wizard;
+
+use alignment::AlignmentPanel;
+use annotations::AnnotationPanel;
+use autosave::Autosave;
+use bookmarks::BookmarkPanel;
+use bulk_delete::BulkDeleteTool;
+use camera::{EditorCameraState, EditorGizmos};
+use clip::ClipRecorder;
+use commands::{EditHistory, Selection};
+use cursor::GridCursor;
+use difficulty::DifficultyGraph;
+use drag::NoteDrag;
+use editing::{Clipboard, EditRequest, NotesEdited};
+use export::ExportPanel;
+use fill::FillTool;
+use heatmap::HeatmapSettings;
+use inspector::NoteInspector;
+use journal::{JournalPanel, JournalTracker};
+use keyboard::KeyboardCharting;
+use latency::LatencyTest;
+use library::LibraryPanel;
+use lyrics::LyricPanel;
+use macros::MacroRecorder;
+use metadata::MetadataTool;
+use note_list::NoteList;
+use objects::ObjectInspector;
+use onboarding::Onboarding;
+use packs::ModPacks;
+use piano_roll::PianoRoll;
+use placement::PlacementTool;
+use preferences::PreferencesPanel;
+use preview::PreviewWindow;
+use project::{OpenProject, ProjectFile, ProjectPanel, SaveProject};
+use recovery::RecoveryPanel;
+use shortcuts::{EditorAction, EditorActionEvent};
+use simplify::SimplifyTool;
+use snapshots::SnapshotPanel;
+use start::{OpenRecent, StartScreen};
+use stretch::StretchTool;
+use tempo::TempoImport;
+use timeline::SnapSettings;
+use updates::UpdatePanel;
+use wizard::NewMapWizard;
+
+use crate::{
+    gameplay::{Session, feedback::update_feedback},
+    loading::AppState,
+    modchart::update_mod_state,
+    player::note_path::NotePathSettings,
+    settings::{journal::Journal, save_settings},
+};
+
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EditorCameraState>()
+            .init_resource::<HeatmapSettings>()
+            .init_resource::<GridCursor>()
+            .init_resource::<Selection>()
+            .init_resource::<EditHistory>()
+            .init_resource::<MacroRecorder>()
+            .init_resource::<Clipboard>()
+            .init_resource::<SnapSettings>()
+            .init_resource::<NewMapWizard>()
+            .init_resource::<ExportPanel>()
+            .init_resource::<Autosave>()
+            .init_resource::<BookmarkPanel>()
+            .init_resource::<AnnotationPanel>()
+            .init_resource::<LyricPanel>()
+            .init_resource::<TempoImport>()
+            .init_resource::<LibraryPanel>()
+            .init_resource::<MetadataTool>()
+            .init_resource::<NoteInspector>()
+            .init_resource::<ObjectInspector>()
+            .init_resource::<PreferencesPanel>()
+            .init_resource::<ModPacks>()
+            .init_resource::<StretchTool>()
+            .init_resource::<PlacementTool>()
+            .init_resource::<FillTool>()
+            .init_resource::<BulkDeleteTool>()
+            .init_resource::<PianoRoll>()
+            .init_resource::<NoteList>()
+            .init_resource::<SimplifyTool>()
+            .init_resource::<KeyboardCharting>()
+            .init_resource::<AlignmentPanel>()
+            .init_resource::<DifficultyGraph>()
+            .init_resource::<NoteDrag>()
+            .init_resource::<Onboarding>()
+            .init_resource::<RecoveryPanel>()
+            .init_resource::<UpdatePanel>()
+            .init_resource::<LatencyTest>()
+            .init_resource::<PreviewWindow>()
+            .init_resource::<SnapshotPanel>()
+            .init_resource::<ProjectFile>()
+            .init_resource::<ProjectPanel>()
+            .init_resource::<StartScreen>()
+            .init_resource::<ClipRecorder>()
+            .init_resource::<JournalTracker>()
+            .init_resource::<JournalPanel>()
+            .insert_resource(Journal::load())
+            .init_gizmo_group::<EditorGizmos>()
+            .add_event::<EditorActionEvent>()
+            .add_event::<EditRequest>()
+            .add_event::<NotesEdited>()
+            .add_event::<OpenProject>()
+            .add_event::<SaveProject>()
+            .add_event::<OpenRecent>()
+            .add_systems(
+                Startup,
+                (
+                    camera::spawn_camera,
+                    onboarding::start_onboarding,
+                    recovery::open_recovery,
+                    start::open_start_screen,
+                    updates::check_on_startup,
+                ),
+            )
+            .add_systems(
+                PreUpdate,
+                (
+                    shortcuts::dispatch_shortcuts,
+                    keyboard::keyboard_charting.run_if(not(resource_exists::<Session>)),
+                )
+                    .chain()
+                    .after(InputSystem),
+            )
+            .add_systems(
+                Update,
+                (
+                    // Gameplay owns the clock and the keys while a session is running
+                    (timeline::scrub_playhead, bookmarks::bookmark_actions)
+                        .before(update_mod_state)
+                        .run_if(not(resource_exists::<Session>)),
+                    (
+                        camera::switch_camera_mode.run_if(not(resource_exists::<Session>)),
+                        camera::free_fly,
+                        camera::top_down_pan,
+                        camera::update_screen_frame.before(update_mod_state),
+                        camera::apply_camera_mods
+                            .after(update_mod_state)
+                            .after(update_feedback),
+                    )
+                        .chain(),
+                    (
+                        preview::toggle_preview_window,
+                        preview::close_preview_window,
+                        preview::follow_gameplay_view.after(update_mod_state),
+                    )
+                        .chain(),
+                    (heatmap::toggle_heatmap, heatmap::update_heatmap_overlay).chain(),
+                    toggle_note_path,
+                    (
+                        cursor::update_grid_cursor,
+                        (editing::translate_actions, drag::drag_selection)
+                            .run_if(not(resource_exists::<Session>)),
+                        editing::process_edit_requests,
+                        drag::draw_drag_ghosts,
+                    )
+                        .chain(),
+                    (wizard::open_wizard, wizard::poll_analysis),
+                    (
+                        export::export_current_map,
+                        export::poll_export_hooks,
+                        export::poll_click_track,
+                        export::take_screenshot,
+                    ),
+                    (
+                        clip::record_clip.before(update_mod_state),
+                        clip::follow_clip_view.after(update_mod_state),
+                        clip::poll_clip_encoding,
+                    ),
+                    // Panels opened and closed from their shortcuts
+                    (
+                        library::open_library,
+                        inspector::toggle_inspector,
+                        objects::toggle_object_inspector,
+                        stretch::toggle_stretch_tool,
+                        placement::toggle_placement_tool,
+                        fill::toggle_fill_tool,
+                        bulk_delete::toggle_bulk_delete,
+                        piano_roll::toggle_piano_roll,
+                        note_list::toggle_note_list,
+                        simplify::toggle_simplify_tool,
+                        keyboard::toggle_keyboard_charting,
+                        preferences::open_preferences,
+                        snapshots::toggle_snapshot_panel,
+                        annotations::toggle_annotations,
+                        tempo::toggle_tempo_import,
+                        journal::toggle_journal,
+                    ),
+                    (alignment::toggle_alignment_panel, alignment::poll_alignment),
+                    updates::poll_updates,
+                    difficulty::update_difficulty_graph,
+                    (
+                        project::project_actions,
+                        start::start_actions,
+                        start::open_recent,
+                        project::open_project,
+                        start::track_recent,
+                    )
+                        .chain(),
+                    onboarding::play_calibration_clicks,
+                    (latency::toggle_latency_test, latency::run_latency_test).chain(),
+                    (autosave::track_map_changes, autosave::run_autosave).chain(),
+                    journal::track_journal.after(editing::process_edit_requests),
+                ),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                (
+                    wizard::wizard_ui,
+                    export::export_ui,
+                    timeline::timeline_ui,
+                    bookmarks::bookmarks_ui,
+                    annotations::annotations_ui,
+                    lyrics::lyrics_ui,
+                    library::library_ui,
+                    metadata::metadata_tool_ui,
+                    inspector::inspector_ui,
+                    objects::object_inspector_ui,
+                    // Tools working on the notes
+                    (
+                        stretch::stretch_tool_ui,
+                        placement::placement_tool_ui,
+                        fill::fill_tool_ui,
+                        bulk_delete::bulk_delete_ui,
+                        piano_roll::piano_roll_ui,
+                        note_list::note_list_ui,
+                        simplify::simplify_tool_ui,
+                        keyboard::keyboard_charting_ui,
+                    ),
+                    // Timing tools
+                    (alignment::alignment_ui, tempo::tempo_import_ui),
+                    preferences::preferences_ui,
+                    onboarding::onboarding_ui,
+                    recovery::recovery_ui,
+                    updates::updates_ui,
+                    latency::latency_ui,
+                    // Looking back at earlier work
+                    (snapshots::snapshots_ui, journal::journal_ui),
+                    project::project_ui,
+                    start::start_screen_ui.run_if(in_state(AppState::Menu)),
+                ),
+            )
+            // The app quits at the end of the frame the window closed in
+            .add_systems(
+                Last,
+                (
+                    (project::save_on_exit, project::save_project).chain(),
+                    start::remember_playhead.before(save_settings),
+                    journal::save_journal,
+                ),
+            );
+    }
+}
+
+pub fn toggle_note_path(
+    mut actions: EventReader<EditorActionEvent>,
+    mut settings: ResMut<NotePathSettings>,
+) {
+    for EditorActionEvent(action) in actions.read() {
+        if *action == EditorAction::ToggleNotePath {
+            settings.enabled = !settings.enabled;
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    editor::{
+        commands::{EditCommand, Selection},
+        editing::EditRequest,
+        shortcuts::{EditorAction, EditorActionEvent},
+    },
+    gameplay::Session,
+    maps::{CurrentMap, Map, format_precise_time, parser::is_quantum},
+    player::playback::PlaybackClock,
+};
+
+const LIST_HEIGHT: f32 = 320.0;
+
+// Every note of the map as a row. Only the rows in view are laid out, so it opens just as fast
+// on maps with hundreds of thousands of notes
+#[derive(Resource)]
+pub struct NoteList {
+    pub open: bool,
+    // Keeps the note at the playhead in view
+    follow: bool,
+}
+
+impl Default for NoteList {
+    fn default() -> Self {
+        Self {
+            open: false,
+            follow: true,
+        }
+    }
+}
+
+pub fn toggle_note_list(mut actions: EventReader<EditorActionEvent>, mut list: ResMut<NoteList>) {
+    for EditorActionEvent(action) in actions.read() {
+        if *action == EditorAction::ToggleNoteList {
+            list.open = !list.open;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn note_list_ui(
+    mut contexts: EguiContexts,
+    mut list: ResMut<NoteList>,
+    mut requests: EventWriter<EditRequest>,
+    mut clock: ResMut<PlaybackClock>,
+    selection: Res<Selection>,
+    session: Option<Res<Session>>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+) -> Result {
+    if !list.open {
+        return Ok(());
+    }
+
+    let Some(map) = current_map.and_then(|m| maps.get(&m.0)) else {
+        return Ok(());
+    };
+
+    let mut open = list.open;
+    let list = list.as_mut();
+    let mut clicked = None;
+
+    egui::Window::new("Notes")
+        .open(&mut open)
+        .resizable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "{} notes, {} selected",
+                    map.notes.len(),
+                    selection.0.len()
+                ));
+                ui.checkbox(&mut list.follow, "Follow playhead");
+            });
+            ui.separator();
+
+            if map.notes.is_empty() {
+                ui.label("This map has no notes yet");
+                return;
+            }
+
+            let row_height = ui.spacing().interact_size.y;
+            let mut scroll = egui::ScrollArea::vertical()
+                .max_height(LIST_HEIGHT)
+                .auto_shrink([false, true]);
+            if list.follow {
+                let at = map
+                    .notes
+                    .partition_point(|n| (n.millisecond as f64) < clock.millisecond);
+                let spacing = row_height + ui.spacing().item_spacing.y;
+                scroll = scroll.vertical_scroll_offset(at as f32 * spacing);
+            }
+
+            scroll.show_rows(ui, row_height, map.notes.len(), |ui, rows| {
+                for index in rows {
+                    let note = &map.notes[index];
+                    let position = match is_quantum(note.position) {
+                        true => format!("({:.2}, {:.2})", note.position.x, note.position.y),
+                        false => format!("({}, {})", note.position.x, note.position.y),
+                    };
+                    let label = format!(
+                        "{index:>7}  {}  {position}",
+                        format_precise_time(note.millisecond)
+                    );
+
+                    let row = ui.selectable_label(
+                        selection.0.contains(&index),
+                        egui::RichText::new(label).monospace(),
+                    );
+                    if row.clicked() {
+                        clicked = Some((index, ui.input(|i| i.modifiers.shift)));
+                    }
+                }
+            });
+        });
+
+    list.open = open;
+
+    // Clicking a row jumps to the note and selects it, with Shift it is toggled in the selection
+    if let Some((index, shift)) = clicked {
+        let note = &map.notes[index];
+        clock.seek(note.millisecond as f64);
+
+        if session.is_none() {
+            let mut selected = match shift {
+                true => selection.0.clone(),
+                false => Default::default(),
+            };
+            if !selected.remove(&index) || !shift {
+                selected.insert(index);
+            }
+            let ids = selected
+                .into_iter()
+                .filter_map(|i| map.notes.get(i))
+                .map(|note| note.id)
+                .collect();
+            requests.write(EditRequest::Execute(EditCommand::Select { ids }));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,228 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    editor::shortcuts::{EditorAction, EditorActionEvent},
+    maps::{
+        CurrentMap, Map,
+        parser::{ObjectDefinition, ObjectType},
+    },
+    player::playback::PlaybackClock,
+};
+
+const LIST_HEIGHT: f32 = 200.0;
+
+// Objects the app doesn't know, e.g. markers of other editors, kept as they were read
+#[derive(Resource, Default)]
+pub struct ObjectInspector {
+    pub open: bool,
+    selected: Option<usize>,
+}
+
+pub fn toggle_object_inspector(
+    mut actions: EventReader<EditorActionEvent>,
+    mut inspector: ResMut<ObjectInspector>,
+) {
+    for EditorActionEvent(action) in actions.read() {
+        if *action == EditorAction::ToggleObjectInspector {
+            inspector.open = !inspector.open;
+        }
+    }
+}
+
+pub fn object_inspector_ui(
+    mut contexts: EguiContexts,
+    mut inspector: ResMut<ObjectInspector>,
+    mut clock: ResMut<PlaybackClock>,
+    current_map: Option<Res<CurrentMap>>,
+    mut maps: ResMut<Assets<Map>>,
+) -> Result {
+    if !inspector.open {
+        return Ok(());
+    }
+
+    let Some(current_map) = current_map else {
+        return Ok(());
+    };
+    let Some(map) = maps.get(&current_map.0) else {
+        return Ok(());
+    };
+
+    let labels: Vec<String> = map
+        .objects
+        .iter()
+        .map(|object| format!("{} @ {}ms", object.name, object.millisecond))
+        .collect();
+
+    let selected = inspector.selected.filter(|&index| index < labels.len());
+    let original = selected.map(|index| map.objects[index].clone());
+
+    // Only the selected object is copied and compared, maps can have thousands of objects
+    let mut edited = original.clone();
+    let mut open = inspector.open;
+    let mut clicked = None;
+
+    egui::Window::new("Objects")
+        .open(&mut open)
+        .show(contexts.ctx_mut()?, |ui| {
+            if labels.is_empty() {
+                ui.label("This map has no custom objects");
+                return;
+            }
+
+            egui::ScrollArea::vertical()
+                .max_height(LIST_HEIGHT)
+                .show_rows(
+                    ui,
+                    ui.spacing().interact_size.y,
+                    labels.len(),
+                    |ui, rows| {
+                        for index in rows {
+                            if ui
+                                .selectable_label(selected == Some(index), &labels[index])
+                                .clicked()
+                            {
+                                clicked = Some(index);
+                            }
+                        }
+                    },
+                );
+
+            let Some(object) = edited.as_mut() else {
+                return;
+            };
+
+            ui.separator();
+            object_ui(ui, object, egui::Id::new(("object", selected)));
+
+            if ui.button("Go").clicked() {
+                clock.seek(object.millisecond as f64);
+            }
+        });
+
+    inspector.open = open;
+    if clicked.is_some() {
+        inspector.selected = clicked;
+    }
+
+    if let (Some(index), Some(edited)) = (selected, edited)
+        && original.as_ref() != Some(&edited)
+        && let Some(map) = maps.get_mut(&current_map.0)
+    {
+        map.objects[index] = edited;
+    }
+
+    Ok(())
+}
+
+fn object_ui(ui: &mut egui::Ui, object: &mut ObjectDefinition, id: egui::Id) {
+    egui::Grid::new("object_fields")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Name");
+            ui.text_edit_singleline(&mut object.name);
+            ui.end_row();
+
+            ui.label("Time");
+            ui.add(egui::DragValue::new(&mut object.millisecond).suffix("ms"));
+            ui.end_row();
+
+            for (index, field) in object.definitions.iter_mut().enumerate() {
+                ui.label(format!("{} ({})", index, field.type_name()));
+                field_ui(ui, field, id.with(index));
+                ui.end_row();
+            }
+        });
+}
+
+// An editor matching the field's type, fields without a value are shown as such
+fn field_ui(ui: &mut egui::Ui, field: &mut ObjectType, id: egui::Id) {
+    match field {
+        ObjectType::U8(Some(value)) => drag(ui, value),
+        ObjectType::U16(Some(value)) => drag(ui, value),
+        ObjectType::U32(Some(value)) => drag(ui, value),
+        ObjectType::U64(Some(value)) => drag(ui, value),
+        ObjectType::I64(Some(value)) => drag(ui, value),
+        ObjectType::F32(Some(value)) => {
+            ui.add(egui::DragValue::new(value).speed(0.01));
+        }
+        ObjectType::F64(Some(value)) => {
+            ui.add(egui::DragValue::new(value).speed(0.01));
+        }
+        ObjectType::Vec2(Some(value)) => {
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut value.x).speed(0.01));
+                ui.add(egui::DragValue::new(&mut value.y).speed(0.01));
+            });
+        }
+        ObjectType::Vec3(Some(value)) => {
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut value.x).speed(0.01));
+                ui.add(egui::DragValue::new(&mut value.y).speed(0.01));
+                ui.add(egui::DragValue::new(&mut value.z).speed(0.01));
+            });
+        }
+        ObjectType::String(Some(value)) => {
+            ui.text_edit_singleline(value);
+        }
+        ObjectType::LongString(Some(value)) => {
+            ui.text_edit_multiline(value);
+        }
+        ObjectType::Buf(Some(bytes)) | ObjectType::LongBuf(Some(bytes)) => hex_ui(ui, bytes, id),
+        ObjectType::Vec(Some(items)) => {
+            ui.vertical(|ui| {
+                for (index, item) in items.iter_mut().enumerate() {
+                    field_ui(ui, item, id.with(index));
+                }
+            });
+        }
+        _ => {
+            ui.weak("No value");
+        }
+    }
+}
+
+fn drag<T: egui::emath::Numeric>(ui: &mut egui::Ui, value: &mut T) {
+    ui.add(egui::DragValue::new(value));
+}
+
+// Bytes as editable hex. The text is kept while it's being typed in, so half written bytes
+// don't reset it, and is only applied while it is valid hex.
+fn hex_ui(ui: &mut egui::Ui, bytes: &mut Vec<u8>, id: egui::Id) {
+    let mut text = ui
+        .data_mut(|data| data.get_temp::<String>(id))
+        .unwrap_or_else(|| bytes.iter().map(|byte| format!("{byte:02x}")).collect());
+
+    let response = ui
+        .vertical(|ui| {
+            let response = ui.add(egui::TextEdit::multiline(&mut text).code_editor());
+            ui.weak(format!("{} bytes", bytes.len()));
+            response
+        })
+        .inner;
+
+    if response.changed() {
+        if let Some(parsed) = parse_hex(&text) {
+            *bytes = parsed;
+        }
+        ui.data_mut(|data| data.insert_temp(id, text));
+    }
+    if response.lost_focus() {
+        ui.data_mut(|data| data.remove::<String>(id));
+    }
+}
+
+fn parse_hex(text: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u8> = text
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_digit(16).map(|digit| digit as u8))
+        .collect::<Option<_>>()?;
+
+    digits.len().is_multiple_of(2).then(|| {
+        digits
+            .chunks(2)
+            .map(|pair| pair[0] << 4 | pair[1])
+            .collect()
+    })
+}
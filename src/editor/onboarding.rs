@@ -0,0 +1,224 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    editor::latency::Calibration, gameplay::InputDevice, jukebox::hitsounds::HitsoundBank,
+    maps::library::detect_map_folders, settings::Settings,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Step {
+    #[default]
+    Folders,
+    Calibration,
+    Input,
+}
+
+// Shown when the app starts without a settings file, everything in it can be changed later in
+// the preferences and library
+#[derive(Resource, Default)]
+pub struct Onboarding {
+    pub open: bool,
+    step: Step,
+    // Found on startup, checked ones are added to the library
+    folders: Vec<(String, bool)>,
+    new_folder: String,
+    calibration: Calibration,
+    offset_ms: f32,
+    input: InputDevice,
+}
+
+pub fn start_onboarding(mut onboarding: ResMut<Onboarding>, settings: Res<Settings>) {
+    if !settings.first_run {
+        return;
+    }
+
+    onboarding.open = true;
+    onboarding.folders = detect_map_folders()
+        .into_iter()
+        .map(|folder| (folder.display().to_string(), true))
+        .collect();
+    onboarding.offset_ms = settings.audio_offset_ms;
+    onboarding.input = settings.input;
+}
+
+pub fn play_calibration_clicks(
+    mut commands: Commands,
+    mut onboarding: ResMut<Onboarding>,
+    bank: Res<HitsoundBank>,
+    time: Res<Time>,
+) {
+    if onboarding.calibration.advance(time.elapsed_secs_f64())
+        && let Some(click) = bank.0.first()
+    {
+        commands.spawn((AudioPlayer(click.clone()), PlaybackSettings::DESPAWN));
+    }
+}
+
+pub fn onboarding_ui(
+    mut contexts: EguiContexts,
+    mut onboarding: ResMut<Onboarding>,
+    mut settings: ResMut<Settings>,
+    time: Res<Time>,
+) -> Result {
+    if !onboarding.open {
+        return Ok(());
+    }
+
+    let mut open = onboarding.open;
+    let mut finish = false;
+    let onboarding = onboarding.as_mut();
+
+    egui::Window::new("Welcome")
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(contexts.ctx_mut()?, |ui| {
+            match onboarding.step {
+                Step::Folders => folders_ui(ui, onboarding),
+                Step::Calibration => calibration_ui(ui, onboarding, time.elapsed_secs_f64()),
+                Step::Input => input_ui(ui, onboarding),
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                let previous = match onboarding.step {
+                    Step::Folders => None,
+                    Step::Calibration => Some(Step::Folders),
+                    Step::Input => Some(Step::Calibration),
+                };
+                let next = match onboarding.step {
+                    Step::Folders => Some(Step::Calibration),
+                    Step::Calibration => Some(Step::Input),
+                    Step::Input => None,
+                };
+
+                if let Some(previous) = previous
+                    && ui.button("Back").clicked()
+                {
+                    onboarding.step = previous;
+                }
+
+                match next {
+                    Some(next) => {
+                        if ui.button("Next").clicked() {
+                            onboarding.step = next;
+                        }
+                    }
+                    None => finish = ui.button("Finish").clicked(),
+                }
+
+                if ui.button("Skip setup").clicked() {
+                    onboarding.open = false;
+                }
+            });
+        });
+
+    // Ending the calibration when leaving its step stops the clicks
+    if onboarding.step != Step::Calibration {
+        onboarding.calibration.started = None;
+    }
+
+    if finish {
+        for (folder, _) in onboarding.folders.iter().filter(|(_, checked)| *checked) {
+            if !settings.map_folders.contains(folder) {
+                settings.map_folders.push(folder.clone());
+            }
+        }
+        settings.audio_offset_ms = onboarding.offset_ms;
+        settings.input = onboarding.input;
+        onboarding.open = false;
+    }
+
+    // However it was closed it isn't shown again, changing the settings writes the file
+    if !open || !onboarding.open {
+        onboarding.open = false;
+        onboarding.calibration.started = None;
+        settings.first_run = false;
+    }
+
+    Ok(())
+}
+
+fn folders_ui(ui: &mut egui::Ui, onboarding: &mut Onboarding) {
+    ui.heading("Map folders");
+    ui.label("Maps in these folders show up in the library and are kept up to date");
+
+    if onboarding.folders.is_empty() {
+        ui.weak("No Sound Space or Phoenix installs were found in their usual places");
+    }
+    for (folder, checked) in onboarding.folders.iter_mut() {
+        ui.checkbox(checked, folder.as_str());
+    }
+
+    ui.horizontal(|ui| {
+        ui.text_edit_singleline(&mut onboarding.new_folder);
+        let folder = onboarding.new_folder.trim().to_string();
+        if ui
+            .add_enabled(!folder.is_empty(), egui::Button::new("Add"))
+            .clicked()
+        {
+            onboarding.folders.push((folder, true));
+            onboarding.new_folder.clear();
+        }
+    });
+}
+
+fn calibration_ui(ui: &mut egui::Ui, onboarding: &mut Onboarding, now: f64) {
+    ui.heading("Audio offset");
+    ui.label("Press Start, then press the Tap button in time with the clicks you hear");
+
+    let calibration = &mut onboarding.calibration;
+    ui.horizontal(|ui| {
+        if ui
+            .add_enabled(!calibration.is_running(), egui::Button::new("Start"))
+            .clicked()
+        {
+            *calibration = Calibration::start(now);
+        }
+
+        // Counted on press rather than release, which would add the time the button is held
+        let tap = ui.add_enabled(
+            calibration.is_running(),
+            egui::Button::new("Tap").min_size(egui::vec2(120.0, 40.0)),
+        );
+        if tap.hovered() && ui.input(|i| i.pointer.primary_pressed()) {
+            calibration.tap(now);
+        }
+    });
+
+    match calibration.offset_ms() {
+        Some(measured) => {
+            ui.label(format!(
+                "Measured {measured:+.0}ms over {} taps",
+                calibration.taps.len()
+            ));
+            if !calibration.is_running() && ui.button("Use this offset").clicked() {
+                onboarding.offset_ms = measured.round();
+            }
+        }
+        None if calibration.is_running() => {
+            ui.label(format!("{} taps so far", calibration.taps.len()));
+        }
+        None => {}
+    }
+
+    ui.horizontal(|ui| {
+        ui.label("Offset");
+        ui.add(
+            egui::DragValue::new(&mut onboarding.offset_ms)
+                .range(-300.0..=300.0)
+                .suffix("ms"),
+        );
+    });
+}
+
+fn input_ui(ui: &mut egui::Ui, onboarding: &mut Onboarding) {
+    ui.heading("Input");
+    ui.label("What you play with, the cursor is kept inside the window for mice");
+
+    for device in InputDevice::ALL {
+        ui.radio_value(&mut onboarding.input, device, device.label());
+    }
+}
@@ -0,0 +1,167 @@
+use std::{
+    fs::File,
+    io::{self, BufReader},
+    path::Path,
+};
+
+use bevy::prelude::*;
+use bevy_egui::egui;
+
+use crate::{
+    modchart::{
+        Modchart,
+        pack::{
+            InstalledPack, install_pack, installed_packs, packs_folder, read_pack, uninstall_pack,
+        },
+        sidecar::read_modchart,
+    },
+    theme::Theme,
+};
+
+// Installed mod packs, listed with the mods in the preferences
+#[derive(Resource, Default)]
+pub struct ModPacks {
+    packs: Vec<InstalledPack>,
+    path: String,
+    status: Option<Result<String, String>>,
+}
+
+fn no_config_dir() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        "No configuration directory available",
+    )
+}
+
+impl ModPacks {
+    // Read when the preferences open rather than every frame
+    pub fn refresh(&mut self) {
+        self.packs = packs_folder().map_or_else(Vec::new, |folder| installed_packs(&folder));
+    }
+
+    fn import(&mut self, path: &Path) -> io::Result<InstalledPack> {
+        let (Some(folder), Some(themes)) = (packs_folder(), Theme::folder()) else {
+            return Err(no_config_dir());
+        };
+
+        let pack = read_pack(BufReader::new(File::open(path)?))?;
+        let installed = install_pack(&pack, &folder, &themes)?;
+        self.refresh();
+        Ok(installed)
+    }
+
+    fn remove(&mut self, index: usize) -> io::Result<()> {
+        let themes = Theme::folder().ok_or_else(no_config_dir)?;
+        uninstall_pack(&self.packs[index], &themes)?;
+        self.refresh();
+        Ok(())
+    }
+}
+
+// Returns true when packs were installed or removed, their themes change with them. The modchart
+// is only marked changed once events are added to it
+pub fn packs_ui(ui: &mut egui::Ui, packs: &mut ModPacks, mut modchart: Mut<Modchart>) -> bool {
+    let mut changed = false;
+    let mut remove = None;
+
+    if packs.packs.is_empty() {
+        ui.weak("No mod packs installed");
+    }
+
+    for (index, pack) in packs.packs.iter().enumerate() {
+        let mut title = pack.manifest.name.clone();
+        if !pack.manifest.author.is_empty() {
+            title.push_str(&format!(" by {}", pack.manifest.author));
+        }
+
+        egui::CollapsingHeader::new(title)
+            .id_salt(("mod_pack", &pack.path))
+            .show(ui, |ui| {
+                if !pack.manifest.description.is_empty() {
+                    ui.label(&pack.manifest.description);
+                }
+
+                // Added on top of the current modchart, the pack's events stack after its own
+                for name in pack.modcharts.iter() {
+                    ui.horizontal(|ui| {
+                        ui.label(name);
+                        if ui.button("Add to modchart").clicked() {
+                            let path = pack.modchart_path(name);
+                            packs.status = Some(
+                                match File::open(&path)
+                                    .and_then(|f| read_modchart(BufReader::new(f)))
+                                {
+                                    Ok(loaded) => {
+                                        let count = loaded.events().len();
+                                        modchart.events_mut().extend_from_slice(loaded.events());
+                                        Ok(format!("Added {count} events from {name}"))
+                                    }
+                                    Err(e) => Err(format!("Could not read {name}: {e}")),
+                                },
+                            );
+                        }
+                    });
+                }
+
+                if !pack.themes.is_empty() {
+                    ui.weak(format!("Themes: {}", pack.themes.join(", ")));
+                }
+
+                if ui.button("Remove").clicked() {
+                    remove = Some(index);
+                }
+            });
+    }
+
+    if let Some(index) = remove {
+        let name = packs.packs[index].manifest.name.clone();
+        packs.status = Some(match packs.remove(index) {
+            Ok(()) => Ok(format!("Removed {name}")),
+            Err(e) => Err(format!("Could not remove {name}: {e}")),
+        });
+        changed = true;
+    }
+
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(&mut packs.path)
+                .hint_text("Path to a .mmpack")
+                .desired_width(220.0),
+        );
+
+        let path = packs.path.trim().to_string();
+        if ui
+            .add_enabled(!path.is_empty(), egui::Button::new("Import"))
+            .clicked()
+        {
+            packs.status = Some(match packs.import(Path::new(&path)) {
+                Ok(installed) => {
+                    info!("Installed mod pack {}", installed.path.display());
+                    packs.path.clear();
+                    Ok(format!(
+                        "Installed {} with {} modcharts",
+                        installed.manifest.name,
+                        installed.modcharts.len()
+                    ))
+                }
+                Err(e) => {
+                    warn!("Could not install mod pack {path}: {e}");
+                    Err(format!("Could not install {path}: {e}"))
+                }
+            });
+            changed = true;
+        }
+    });
+
+    match &packs.status {
+        Some(Ok(message)) => {
+            ui.label(message.as_str());
+        }
+        Some(Err(e)) => {
+            ui.colored_label(ui.visuals().error_fg_color, e.as_str());
+        }
+        None => {}
+    }
+
+    changed
+}
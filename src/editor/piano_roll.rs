@@ -0,0 +1,459 @@
+use std::collections::BTreeSet;
+
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    editor::{
+        commands::{EditCommand, Selection},
+        editing::EditRequest,
+        shortcuts::{EditorAction, EditorActionEvent},
+        timeline::SnapSettings,
+        windowing::{notes_between, one_per_column},
+    },
+    gameplay::Session,
+    maps::{CurrentMap, Map, objects::Note, parser::is_quantum},
+    player::{playback::PlaybackClock, playfield::GRID_CELLS},
+    theme::Theme,
+};
+
+const ROWS: usize = (GRID_CELLS * GRID_CELLS) as usize;
+const ROW_HEIGHT: f32 = 20.0;
+const RULER_HEIGHT: f32 = 14.0;
+const LABEL_WIDTH: f32 = 28.0;
+const NOTE_WIDTH: f32 = 6.0;
+
+// Pixels per millisecond
+const MIN_ZOOM: f32 = 0.02;
+const MAX_ZOOM: f32 = 2.0;
+
+// Subdivision lines closer together than this are left out, only beats are drawn then
+const MIN_LINE_SPACING: f32 = 6.0;
+
+// The playhead sits this far into the view, so more of what comes next is visible
+const PLAYHEAD_AT: f32 = 0.25;
+
+// Notes laid out by time against the grid cell they are in, one row per cell. It edits the
+// same notes and selection as the playfield, so both always show the same thing.
+#[derive(Resource)]
+pub struct PianoRoll {
+    pub open: bool,
+    zoom: f32,
+    drag: Option<RollDrag>,
+}
+
+impl Default for PianoRoll {
+    fn default() -> Self {
+        Self {
+            open: false,
+            zoom: 0.2,
+            drag: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum RollDrag {
+    // Selects every note inside the box once released
+    Box { from: egui::Pos2 },
+    // Moves the selection, following the note it was picked up by
+    Move { grabbed: usize },
+}
+
+// Quantum notes go into the row of the cell they are closest to
+fn row_of(note: &Note) -> usize {
+    let cell = note.position.round().clamp(Vec2::ZERO, Vec2::splat(2.0));
+    cell.y as usize * GRID_CELLS as usize + cell.x as usize
+}
+
+fn cell_of(row: usize) -> Vec2 {
+    Vec2::new(
+        (row % GRID_CELLS as usize) as f32,
+        (row / GRID_CELLS as usize) as f32,
+    )
+}
+
+pub fn toggle_piano_roll(mut actions: EventReader<EditorActionEvent>, mut roll: ResMut<PianoRoll>) {
+    for EditorActionEvent(action) in actions.read() {
+        if *action == EditorAction::TogglePianoRoll {
+            roll.open = !roll.open;
+        }
+    }
+}
+
+// Time offset and grid offset the selection would be moved by if the drag ended here
+fn move_offset(
+    map: &Map,
+    grabbed: &Note,
+    origin: egui::Pos2,
+    position: egui::Pos2,
+    zoom: f32,
+    divisor: u32,
+    row_at: impl Fn(f32) -> usize,
+) -> (i64, Vec2) {
+    let moved = ((position.x - origin.x) / zoom) as f64;
+    let target = map
+        .snap(grabbed.millisecond as f64 + moved, divisor)
+        .max(0.0);
+    let milliseconds = (target - grabbed.millisecond as f64).round() as i64;
+
+    let from = cell_of(row_at(origin.y));
+    let to = cell_of(row_at(position.y));
+    (milliseconds, to - from)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn piano_roll_ui(
+    mut contexts: EguiContexts,
+    mut roll: ResMut<PianoRoll>,
+    mut requests: EventWriter<EditRequest>,
+    mut clock: ResMut<PlaybackClock>,
+    selection: Res<Selection>,
+    snap: Res<SnapSettings>,
+    session: Option<Res<Session>>,
+    theme: Res<Theme>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+) -> Result {
+    if !roll.open {
+        return Ok(());
+    }
+
+    let Some(map) = current_map.and_then(|m| maps.get(&m.0)) else {
+        return Ok(());
+    };
+
+    let mut open = roll.open;
+    let roll = roll.as_mut();
+    let editable = session.is_none();
+
+    egui::Window::new("Piano roll")
+        .open(&mut open)
+        .default_width(720.0)
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Zoom");
+                ui.add(
+                    egui::Slider::new(&mut roll.zoom, MIN_ZOOM..=MAX_ZOOM)
+                        .logarithmic(true)
+                        .show_value(false),
+                );
+                ui.weak("Click to select, drag to move or box select, double click to place");
+            });
+
+            let size = egui::vec2(
+                ui.available_width(),
+                RULER_HEIGHT + ROWS as f32 * ROW_HEIGHT,
+            );
+            let (response, painter) = ui.allocate_painter(size, egui::Sense::click_and_drag());
+            let rect = response.rect;
+            let lanes = egui::Rect::from_min_max(
+                egui::pos2(rect.left() + LABEL_WIDTH, rect.top() + RULER_HEIGHT),
+                rect.max,
+            );
+
+            let zoom = roll.zoom;
+            let start = clock.millisecond - (lanes.width() * PLAYHEAD_AT / zoom) as f64;
+            let end = start + (lanes.width() / zoom) as f64;
+            let x_at = |ms: f64| lanes.left() + ((ms - start) as f32 * zoom);
+            let ms_at = |x: f32| start + ((x - lanes.left()) / zoom) as f64;
+            let row_y = |row: usize| lanes.top() + (row as f32 + 0.5) * ROW_HEIGHT;
+            let row_at =
+                |y: f32| (((y - lanes.top()) / ROW_HEIGHT).floor().max(0.0) as usize).min(ROWS - 1);
+
+            let visuals = ui.visuals().clone();
+            painter.rect_filled(rect, 2.0, visuals.extreme_bg_color);
+
+            for row in 0..ROWS {
+                let y = lanes.top() + row as f32 * ROW_HEIGHT;
+                if row % 2 == 1 {
+                    painter.rect_filled(
+                        egui::Rect::from_min_size(
+                            egui::pos2(rect.left(), y),
+                            egui::vec2(rect.width(), ROW_HEIGHT),
+                        ),
+                        0.0,
+                        visuals.faint_bg_color,
+                    );
+                }
+                let cell = cell_of(row);
+                painter.text(
+                    egui::pos2(rect.left() + 4.0, row_y(row)),
+                    egui::Align2::LEFT_CENTER,
+                    format!("{},{}", cell.x, cell.y),
+                    egui::FontId::monospace(10.0),
+                    visuals.weak_text_color(),
+                );
+            }
+
+            // Beat and subdivision lines, each timing point keeps its own spacing
+            let mut time = map.snap(start.max(0.0), snap.divisor);
+            while time < end
+                && let Some(timing) = map.timing_point_at(time.max(0.0) as u32)
+            {
+                let beat = timing.beat_length();
+                let divisor = match (beat / snap.divisor.max(1) as f64) as f32 * zoom {
+                    spacing if spacing < MIN_LINE_SPACING => 1,
+                    _ => snap.divisor,
+                };
+                let step = beat / divisor.max(1) as f64;
+                if beat <= 0.0 || (beat as f32) * zoom < 1.0 {
+                    break;
+                }
+
+                let beats = (time - timing.millisecond as f64) / beat;
+                let on_beat = (beats - beats.round()).abs() < 1e-3;
+                let measure = on_beat
+                    && (beats.round() as i64).rem_euclid(timing.beats_per_measure.max(1) as i64)
+                        == 0;
+                let stroke = match (measure, on_beat) {
+                    (true, _) => egui::Stroke::new(1.5, visuals.widgets.active.bg_fill),
+                    (false, true) => egui::Stroke::new(1.0, visuals.widgets.inactive.bg_fill),
+                    _ => egui::Stroke::new(1.0, visuals.widgets.noninteractive.bg_stroke.color),
+                };
+                let x = x_at(time);
+                painter.line_segment(
+                    [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+                    stroke,
+                );
+
+                let next = map.snap(time + step, divisor);
+                time = next.max(time + 1.0);
+            }
+
+            let pointer = response.interact_pointer_pos();
+            // Still known on the frame a drag is released
+            let drag_pointer = pointer.or(ui.input(|i| i.pointer.latest_pos()));
+            let origin = ui.input(|i| i.pointer.press_origin());
+            let shift = ui.input(|i| i.modifiers.shift);
+
+            let visible = notes_between(&map.notes, start - NOTE_WIDTH as f64 / zoom as f64, end);
+
+            // Note under a point, the closest in time when notes overlap
+            let hit = |position: egui::Pos2| -> Option<usize> {
+                if !lanes.contains(position) {
+                    return None;
+                }
+                let row = row_at(position.y);
+                visible
+                    .clone()
+                    .filter(|&i| row_of(&map.notes[i]) == row)
+                    .map(|i| {
+                        (
+                            i,
+                            (x_at(map.notes[i].millisecond as f64) - position.x).abs(),
+                        )
+                    })
+                    .filter(|(_, distance)| *distance <= NOTE_WIDTH)
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(i, _)| i)
+            };
+
+            let moving = match (roll.drag, origin, drag_pointer) {
+                (Some(RollDrag::Move { grabbed }), Some(origin), Some(position)) => {
+                    map.notes.get(grabbed).map(|note| {
+                        move_offset(map, note, origin, position, zoom, snap.divisor, row_at)
+                    })
+                }
+                _ => None,
+            };
+
+            // Zoomed out on dense maps many notes share a pixel, only one of each is drawn
+            let drawn = one_per_column(
+                visible.clone(),
+                ROWS,
+                |i| x_at(map.notes[i].millisecond as f64).floor() as i32,
+                |i| row_of(&map.notes[i]),
+                |i| selection.0.contains(&i),
+            );
+
+            for index in drawn {
+                let note = &map.notes[index];
+                let selected = selection.0.contains(&index);
+                let (ms, row) = match (moving, selected) {
+                    (Some((milliseconds, offset)), true) => {
+                        let ms = (note.millisecond as i64 + milliseconds).max(0) as f64;
+                        let cell =
+                            (note.position.round() + offset).clamp(Vec2::ZERO, Vec2::splat(2.0));
+                        (
+                            ms,
+                            row_of(&Note {
+                                position: cell,
+                                ..*note
+                            }),
+                        )
+                    }
+                    _ => (note.millisecond as f64, row_of(note)),
+                };
+
+                let [r, g, b, _] = theme
+                    .note_color(note.color.map_or(index, usize::from))
+                    .to_srgba()
+                    .to_u8_array();
+                let color = egui::Color32::from_rgb(r, g, b);
+                let marker = egui::Rect::from_center_size(
+                    egui::pos2(x_at(ms), row_y(row)),
+                    egui::vec2(NOTE_WIDTH, ROW_HEIGHT - 6.0),
+                );
+
+                // Notes between cells are drawn hollow, they only sit in the closest row
+                match is_quantum(note.position) {
+                    true => painter.rect_stroke(
+                        marker,
+                        1.0,
+                        egui::Stroke::new(1.5, color),
+                        egui::StrokeKind::Inside,
+                    ),
+                    false => painter.rect_filled(marker, 1.0, color),
+                };
+                if selected {
+                    painter.rect_stroke(
+                        marker.expand(2.0),
+                        2.0,
+                        egui::Stroke::new(1.5, visuals.selection.stroke.color),
+                        egui::StrokeKind::Outside,
+                    );
+                }
+            }
+
+            if let (Some(RollDrag::Box { from }), Some(position)) = (roll.drag, drag_pointer) {
+                painter.rect(
+                    egui::Rect::from_two_pos(from, position),
+                    0.0,
+                    visuals.selection.bg_fill.gamma_multiply(0.25),
+                    egui::Stroke::new(1.0, visuals.selection.stroke.color),
+                    egui::StrokeKind::Inside,
+                );
+            }
+
+            let playhead = x_at(clock.millisecond);
+            painter.line_segment(
+                [
+                    egui::pos2(playhead, rect.top()),
+                    egui::pos2(playhead, rect.bottom()),
+                ],
+                egui::Stroke::new(2.0, visuals.strong_text_color()),
+            );
+
+            // Scrolling moves the playhead like the timeline does, holding Ctrl zooms instead
+            if response.hovered() {
+                let (scroll, zoom_delta) = ui.input(|i| (i.smooth_scroll_delta, i.zoom_delta()));
+                if zoom_delta != 1.0 {
+                    roll.zoom = (roll.zoom * zoom_delta).clamp(MIN_ZOOM, MAX_ZOOM);
+                } else if scroll != egui::Vec2::ZERO {
+                    let delta = if scroll.x != 0.0 { scroll.x } else { scroll.y };
+                    let target = clock.millisecond - (delta / zoom) as f64;
+                    clock.seek(target);
+                }
+            }
+
+            let ids = |indices: &mut dyn Iterator<Item = usize>| {
+                indices
+                    .filter_map(|i| map.notes.get(i))
+                    .map(|note| note.id)
+                    .collect::<Vec<_>>()
+            };
+
+            if let Some(position) = pointer
+                && position.y < lanes.top()
+                && roll.drag.is_none()
+            {
+                // The ruler seeks
+                clock.seek(ms_at(position.x).max(0.0));
+            } else if !editable {
+                roll.drag = None;
+            } else if response.double_clicked()
+                && let Some(position) = pointer
+                && hit(position).is_none()
+            {
+                let millisecond = map.snap(ms_at(position.x).max(0.0), snap.divisor).max(0.0);
+                requests.write(EditRequest::Execute(EditCommand::PlaceNoteAt {
+                    millisecond: millisecond.round() as u32,
+                    position: cell_of(row_at(position.y)),
+                }));
+            } else if response.clicked()
+                && let Some(position) = pointer
+            {
+                let command = match (hit(position), shift) {
+                    (Some(index), true) => {
+                        let mut selected = selection.0.clone();
+                        if !selected.remove(&index) {
+                            selected.insert(index);
+                        }
+                        EditCommand::Select {
+                            ids: ids(&mut selected.into_iter()),
+                        }
+                    }
+                    (Some(index), false) => EditCommand::Select {
+                        ids: ids(&mut std::iter::once(index)),
+                    },
+                    (None, true) => return,
+                    (None, false) => EditCommand::ClearSelection,
+                };
+                if command != EditCommand::ClearSelection || !selection.0.is_empty() {
+                    requests.write(EditRequest::Execute(command));
+                }
+            } else if response.drag_started()
+                && let Some(origin) = origin
+                && lanes.contains(origin)
+            {
+                roll.drag = Some(match hit(origin) {
+                    Some(grabbed) => {
+                        // Picking up a note that isn't selected moves it on its own
+                        if !selection.0.contains(&grabbed) {
+                            requests.write(EditRequest::Execute(EditCommand::Select {
+                                ids: ids(&mut std::iter::once(grabbed)),
+                            }));
+                        }
+                        RollDrag::Move { grabbed }
+                    }
+                    None => RollDrag::Box { from: origin },
+                });
+            } else if response.drag_stopped()
+                && let Some(drag) = roll.drag.take()
+            {
+                match drag {
+                    RollDrag::Move { .. } => {
+                        let Some((milliseconds, offset)) = moving else {
+                            return;
+                        };
+                        let mut commands = Vec::new();
+                        if milliseconds != 0 {
+                            commands.push(EditCommand::Offset { milliseconds });
+                        }
+                        if offset != Vec2::ZERO {
+                            commands.push(EditCommand::Translate { offset });
+                        }
+                        if !commands.is_empty() {
+                            requests.write(EditRequest::ExecuteGroup(
+                                commands,
+                                "Move notes".to_string(),
+                            ));
+                        }
+                    }
+                    RollDrag::Box { from } => {
+                        let Some(to) = drag_pointer else {
+                            return;
+                        };
+                        let area = egui::Rect::from_two_pos(from, to);
+                        let (low, high) = (ms_at(area.left()), ms_at(area.right()));
+                        let (top, bottom) = (row_at(area.top()), row_at(area.bottom()));
+
+                        let mut boxed: BTreeSet<usize> = notes_between(&map.notes, low, high)
+                            .filter(|&i| (top..=bottom).contains(&row_of(&map.notes[i])))
+                            .collect();
+                        if shift {
+                            boxed.extend(selection.0.iter().copied());
+                        }
+                        requests.write(EditRequest::Execute(EditCommand::Select {
+                            ids: ids(&mut boxed.into_iter()),
+                        }));
+                    }
+                }
+            }
+        });
+
+    roll.open = open;
+
+    Ok(())
+}
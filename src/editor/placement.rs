@@ -0,0 +1,278 @@
+use std::f32::consts::{PI, TAU};
+
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    editor::{
+        commands::{EditCommand, Selection},
+        editing::EditRequest,
+        shortcuts::{EditorAction, EditorActionEvent},
+    },
+    maps::{CurrentMap, Map},
+    modchart::Easing,
+    theme::Theme,
+};
+
+// Size of the grid preview in the tool window
+const PREVIEW_SIZE: f32 = 120.0;
+
+// Shapes a stream of notes can be laid out along, from one grid position to another
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PathShape {
+    Line,
+    // Bulges out from the line by `bend` times half its length, negative bends bulge the other way
+    Arc { bend: f32 },
+    // Goes once around the circle with the two positions on opposite sides
+    Circle { clockwise: bool },
+    // Winds in from the start towards the end, which is its center
+    Spiral { turns: f32 },
+}
+
+impl PathShape {
+    const ALL: [PathShape; 4] = [
+        PathShape::Line,
+        PathShape::Arc { bend: 1.0 },
+        PathShape::Circle { clockwise: true },
+        PathShape::Spiral { turns: 1.5 },
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PathShape::Line => "line",
+            PathShape::Arc { .. } => "arc",
+            PathShape::Circle { .. } => "circle",
+            PathShape::Spiral { .. } => "spiral",
+        }
+    }
+
+    // Position along the path, `t` goes from 0 at the start to 1 at the end
+    pub fn point(&self, start: Vec2, end: Vec2, t: f32) -> Vec2 {
+        match *self {
+            PathShape::Line => start.lerp(end, t),
+            PathShape::Arc { bend } => {
+                let along = end - start;
+                start.lerp(end, t) + along.perp() * 0.5 * bend * (PI * t).sin()
+            }
+            PathShape::Circle { clockwise } => {
+                let center = (start + end) / 2.0;
+                let radius = start - center;
+                // Grid rows go downwards, so positive angles turn clockwise
+                let angle = match clockwise {
+                    true => TAU * t,
+                    false => -TAU * t,
+                };
+                center + Vec2::from_angle(angle).rotate(radius)
+            }
+            PathShape::Spiral { turns } => {
+                let radius = start - end;
+                end + Vec2::from_angle(TAU * turns * t).rotate(radius) * (1.0 - t)
+            }
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct PlacementTool {
+    pub open: bool,
+    shape: PathShape,
+    start: Vec2,
+    end: Vec2,
+    easing: Easing,
+}
+
+impl Default for PlacementTool {
+    fn default() -> Self {
+        Self {
+            open: false,
+            shape: PathShape::Line,
+            start: Vec2::ZERO,
+            end: Vec2::splat(2.0),
+            easing: Easing::Linear,
+        }
+    }
+}
+
+pub fn toggle_placement_tool(
+    mut actions: EventReader<EditorActionEvent>,
+    mut tool: ResMut<PlacementTool>,
+) {
+    for EditorActionEvent(action) in actions.read() {
+        if *action == EditorAction::TogglePlacementTool {
+            tool.open = !tool.open;
+        }
+    }
+}
+
+fn position_ui(ui: &mut egui::Ui, label: &str, position: &mut Vec2) {
+    ui.label(label);
+    ui.horizontal(|ui| {
+        for axis in [&mut position.x, &mut position.y] {
+            ui.add(
+                egui::DragValue::new(axis)
+                    .range(0.0..=2.0)
+                    .speed(0.01)
+                    .fixed_decimals(2),
+            );
+        }
+    });
+    ui.end_row();
+}
+
+// The 3x3 grid with the notes where they would be placed, the first one highlighted
+fn preview_ui(ui: &mut egui::Ui, points: &[Vec2], theme: &Theme) {
+    let (rect, _) = ui.allocate_exact_size(egui::Vec2::splat(PREVIEW_SIZE), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let cell = PREVIEW_SIZE / 3.0;
+    // Row 0 is the top of the grid
+    let to_screen = |p: Vec2| rect.left_top() + egui::vec2(p.x + 0.5, p.y + 0.5) * cell;
+
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+    for x in 0..3 {
+        for y in 0..3 {
+            let center = to_screen(Vec2::new(x as f32, y as f32));
+            painter.circle_stroke(
+                center,
+                cell * 0.35,
+                egui::Stroke::new(1.0, ui.visuals().weak_text_color()),
+            );
+        }
+    }
+
+    let line: Vec<egui::Pos2> = points.iter().map(|p| to_screen(*p)).collect();
+    painter.add(egui::Shape::line(
+        line.clone(),
+        egui::Stroke::new(1.0, theme.accent.egui()),
+    ));
+    for (i, point) in line.iter().enumerate() {
+        let radius = if i == 0 { 4.0 } else { 2.5 };
+        painter.circle_filled(*point, radius, theme.accent.egui());
+    }
+}
+
+pub fn placement_tool_ui(
+    mut contexts: EguiContexts,
+    mut tool: ResMut<PlacementTool>,
+    mut requests: EventWriter<EditRequest>,
+    selection: Res<Selection>,
+    theme: Res<Theme>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+) -> Result {
+    if !tool.open {
+        return Ok(());
+    }
+
+    let Some(map) = current_map.and_then(|m| maps.get(&m.0)) else {
+        return Ok(());
+    };
+
+    let positions: Vec<Vec2> = selection
+        .0
+        .iter()
+        .filter_map(|&i| map.notes.get(i))
+        .map(|note| note.position)
+        .collect();
+
+    let mut open = tool.open;
+    let mut command = None;
+
+    egui::Window::new("Place along path")
+        .open(&mut open)
+        .resizable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            if positions.len() < 2 {
+                ui.label("Select at least two notes");
+                return;
+            }
+
+            ui.label(format!("{} notes selected", positions.len()));
+            ui.separator();
+
+            let tool = tool.as_mut();
+            ui.horizontal(|ui| {
+                for shape in PathShape::ALL {
+                    let selected = tool.shape.label() == shape.label();
+                    if ui.selectable_label(selected, shape.label()).clicked() && !selected {
+                        tool.shape = shape;
+                    }
+                }
+            });
+
+            egui::Grid::new("placement_tool")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    position_ui(ui, "Start", &mut tool.start);
+                    position_ui(ui, "End", &mut tool.end);
+
+                    match &mut tool.shape {
+                        PathShape::Line => {}
+                        PathShape::Arc { bend } => {
+                            ui.label("Bend");
+                            ui.add(egui::DragValue::new(bend).range(-2.0..=2.0).speed(0.01));
+                            ui.end_row();
+                        }
+                        PathShape::Circle { clockwise } => {
+                            ui.label("Clockwise");
+                            ui.checkbox(clockwise, "");
+                            ui.end_row();
+                        }
+                        PathShape::Spiral { turns } => {
+                            ui.label("Turns");
+                            ui.add(egui::DragValue::new(turns).range(-8.0..=8.0).speed(0.05));
+                            ui.end_row();
+                        }
+                    }
+
+                    // Linear keeps the notes evenly spaced along the path
+                    ui.label("Spacing");
+                    egui::ComboBox::from_id_salt("placement_easing")
+                        .selected_text(format!("{:?}", tool.easing))
+                        .show_ui(ui, |ui| {
+                            for easing in Easing::ALL {
+                                ui.selectable_value(
+                                    &mut tool.easing,
+                                    easing,
+                                    format!("{easing:?}"),
+                                );
+                            }
+                        });
+                    ui.end_row();
+                });
+
+            if ui.button("Use the ends of the selection").clicked()
+                && let (Some(first), Some(last)) = (positions.first(), positions.last())
+            {
+                tool.start = *first;
+                tool.end = *last;
+            }
+
+            let last = (positions.len() - 1) as f32;
+            let points: Vec<Vec2> = (0..positions.len())
+                .map(|i| {
+                    tool.shape
+                        .point(tool.start, tool.end, tool.easing.apply(i as f32 / last))
+                        .clamp(Vec2::ZERO, Vec2::splat(2.0))
+                })
+                .collect();
+            preview_ui(ui, &points, &theme);
+
+            if ui.button("Place").clicked() {
+                command = Some(EditCommand::Distribute {
+                    shape: tool.shape,
+                    start: tool.start,
+                    end: tool.end,
+                    easing: tool.easing,
+                });
+            }
+        });
+
+    tool.open = open;
+
+    if let Some(command) = command {
+        requests.write(EditRequest::Execute(command));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,504 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    editor::{
+        latency::LatencyTest,
+        packs::{ModPacks, packs_ui},
+        shortcuts::{EditorAction, EditorActionEvent},
+        updates::UpdatePanel,
+    },
+    gameplay::{InputDevice, cursor::CursorSettings, ruleset::RulesetPreset},
+    jukebox::{output::AudioOutput, stretch::PitchMode},
+    modchart::{Modchart, comfort::RotationComfort},
+    player::mods::{MOD_DEFINITIONS, Mods},
+    settings::{
+        Settings,
+        display::{DisplayMode, DisplaySettings},
+    },
+    theme::{Theme, palette::NotePalette},
+};
+
+#[derive(Resource, Default)]
+pub struct PreferencesPanel {
+    pub open: bool,
+    // Read when the window opens rather than every frame
+    themes: Vec<String>,
+}
+
+pub fn open_preferences(
+    mut actions: EventReader<EditorActionEvent>,
+    mut panel: ResMut<PreferencesPanel>,
+    mut packs: ResMut<ModPacks>,
+) {
+    for EditorActionEvent(action) in actions.read() {
+        if *action == EditorAction::OpenPreferences {
+            panel.open = !panel.open;
+            panel.themes = Theme::available();
+            packs.refresh();
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn preferences_ui(
+    mut contexts: EguiContexts,
+    mut panel: ResMut<PreferencesPanel>,
+    mut settings: ResMut<Settings>,
+    mut latency: ResMut<LatencyTest>,
+    output: Res<AudioOutput>,
+    mut updates: ResMut<UpdatePanel>,
+    mut packs: ResMut<ModPacks>,
+    mut modchart: ResMut<Modchart>,
+) -> Result {
+    if !panel.open {
+        return Ok(());
+    }
+
+    let mut open = panel.open;
+    let mut theme = settings.theme.clone();
+    let mut accessibility = settings.accessibility.clone();
+    let mut beat_lines = settings.beat_lines.clone();
+    let mut effects = settings.effects.clone();
+    let mut feedback = settings.feedback.clone();
+    let mut comfort = settings.comfort.clone();
+    let mut preset = settings.ruleset.preset();
+    let mut mods = settings.mods.clone();
+    let mut reroll_random = settings.reroll_random;
+    let mut pitch_mode = settings.pitch_mode;
+    let mut audio_device = settings.audio_device.clone();
+    let mut audio_offset_ms = settings.audio_offset_ms;
+    let mut visual_offset_ms = settings.visual_offset_ms;
+    let mut input = settings.input;
+    let mut cursor = settings.cursor.clone();
+    let mut lossy_strings = settings.lossy_strings;
+    let mut merge_epsilon_ms = settings.merge_epsilon_ms;
+    let mut merge_on_open = settings.merge_on_open;
+    let mut display = settings.display.clone();
+    let mut check_updates = settings.updates.check_on_startup;
+    let mut check_now = false;
+    let mut packs_changed = false;
+    let ruleset_name = match preset {
+        Some(_) => settings.ruleset.name.clone(),
+        None => format!("{} (custom)", settings.ruleset.name),
+    };
+
+    egui::Window::new("Preferences")
+        .open(&mut open)
+        .resizable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            egui::Grid::new("preferences")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Theme");
+                    egui::ComboBox::from_id_salt("theme")
+                        .selected_text(theme.as_str())
+                        .show_ui(ui, |ui| {
+                            for name in panel.themes.iter() {
+                                ui.selectable_value(&mut theme, name.clone(), name.as_str());
+                            }
+                        });
+                    ui.end_row();
+
+                    ui.label("Note colors");
+                    egui::ComboBox::from_id_salt("note_palette")
+                        .selected_text(accessibility.palette.label())
+                        .show_ui(ui, |ui| {
+                            for palette in NotePalette::ALL {
+                                ui.selectable_value(
+                                    &mut accessibility.palette,
+                                    palette,
+                                    palette.label(),
+                                );
+                            }
+                        });
+                    ui.end_row();
+
+                    ui.label("High contrast");
+                    ui.checkbox(&mut accessibility.high_contrast, "");
+                    ui.end_row();
+
+                    ui.label("Beat lines");
+                    ui.checkbox(&mut beat_lines.enabled, "");
+                    ui.end_row();
+
+                    ui.label("Beat line opacity");
+                    ui.add(egui::Slider::new(&mut beat_lines.opacity, 0.0..=1.0));
+                    ui.end_row();
+
+                    ui.label("Beat subdivisions");
+                    ui.add(egui::Slider::new(&mut beat_lines.subdivisions, 1..=8));
+                    ui.end_row();
+
+                    ui.label("Hit effects");
+                    ui.add(egui::Slider::new(&mut effects.intensity, 0.0..=2.0));
+                    ui.end_row();
+
+                    ui.label("Cursor trail");
+                    ui.checkbox(&mut effects.cursor_trail, "");
+                    ui.end_row();
+
+                    ui.label("Hit feedback");
+                    ui.add(egui::Slider::new(&mut feedback.intensity, 0.0..=2.0));
+                    ui.end_row();
+
+                    ui.label("Feedback effects");
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut feedback.camera_punch, "Camera punch")
+                            .on_hover_text(
+                                "On perfect hits, softened with the rotation setting below",
+                            );
+                        ui.checkbox(&mut feedback.miss_flash, "Miss flash");
+                    });
+                    ui.end_row();
+
+                    ui.label("Combo callouts");
+                    ui.add(
+                        egui::DragValue::new(&mut feedback.milestone_every)
+                            .range(0..=1000)
+                            .prefix("every "),
+                    )
+                    .on_hover_text("Zero turns them off");
+                    ui.end_row();
+
+                    ui.label("Rotations");
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_salt("rotation_comfort")
+                            .selected_text(comfort.rotation.label())
+                            .show_ui(ui, |ui| {
+                                for option in RotationComfort::ALL {
+                                    ui.selectable_value(
+                                        &mut comfort.rotation,
+                                        option,
+                                        option.label(),
+                                    );
+                                }
+                            })
+                            .response
+                            .on_hover_text("For when spinning charts make you feel sick");
+                        if comfort.rotation == RotationComfort::Capped {
+                            ui.add(
+                                egui::DragValue::new(&mut comfort.max_turn_speed)
+                                    .range(5.0..=720.0)
+                                    .suffix("°/s"),
+                            );
+                        }
+                    });
+                    ui.end_row();
+
+                    // A chosen device that isn't connected is still listed, it's used again once
+                    // it is back
+                    ui.label("Audio device");
+                    egui::ComboBox::from_id_salt("audio_device")
+                        .selected_text(audio_device.as_deref().unwrap_or("System default"))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut audio_device, None, "System default");
+                            let missing = audio_device
+                                .clone()
+                                .filter(|name| !output.devices.contains(name));
+                            for name in output.devices.iter().chain(missing.iter()) {
+                                ui.selectable_value(
+                                    &mut audio_device,
+                                    Some(name.clone()),
+                                    name.as_str(),
+                                );
+                            }
+                        })
+                        .response
+                        .on_hover_text(match &output.device {
+                            Some(device) => format!("Playing on {device}"),
+                            None => "No device is connected".to_string(),
+                        });
+                    ui.end_row();
+
+                    ui.label("Audio offset");
+                    ui.add(
+                        egui::DragValue::new(&mut audio_offset_ms)
+                            .range(-300.0..=300.0)
+                            .suffix("ms"),
+                    )
+                    .on_hover_text("Positive when sounds come out late");
+                    ui.end_row();
+
+                    ui.label("Visual offset");
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::DragValue::new(&mut visual_offset_ms)
+                                .range(-300.0..=300.0)
+                                .suffix("ms"),
+                        )
+                        .on_hover_text("Positive when you hit late on what you see");
+                        if ui.button("Test latency").clicked() {
+                            latency.open = true;
+                        }
+                    });
+                    ui.end_row();
+
+                    ui.label("Input");
+                    egui::ComboBox::from_id_salt("input_device")
+                        .selected_text(input.label())
+                        .show_ui(ui, |ui| {
+                            for device in InputDevice::ALL {
+                                ui.selectable_value(&mut input, device, device.label());
+                            }
+                        });
+                    ui.end_row();
+
+                    ui.label("Updates");
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut check_updates, "Check on startup");
+                        check_now = ui
+                            .add_enabled(!updates.is_checking(), egui::Button::new("Check now"))
+                            .clicked();
+                    });
+                    ui.end_row();
+
+                    ui.label("Repair map text");
+                    ui.checkbox(&mut lossy_strings, "")
+                        .on_hover_text("Opens old maps with text that isn't UTF-8");
+                    ui.end_row();
+
+                    ui.label("Merge close notes");
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::DragValue::new(&mut merge_epsilon_ms)
+                                .range(1..=20)
+                                .suffix("ms"),
+                        )
+                        .on_hover_text("Notes this close in time are meant to be at one time");
+                        ui.checkbox(&mut merge_on_open, "When opening maps")
+                            .on_hover_text("Cleans up maps converted by tools that round times");
+                    });
+                    ui.end_row();
+
+                    // Rulesets edited by hand in the settings file show up as custom
+                    ui.label("Ruleset");
+                    egui::ComboBox::from_id_salt("ruleset")
+                        .selected_text(ruleset_name)
+                        .show_ui(ui, |ui| {
+                            for option in RulesetPreset::ALL {
+                                ui.selectable_value(
+                                    &mut preset,
+                                    Some(option),
+                                    option.ruleset().name,
+                                );
+                            }
+                        });
+                    ui.end_row();
+                });
+
+            ui.separator();
+            ui.label("Display");
+            display_ui(ui, &mut display);
+
+            ui.separator();
+            ui.label("Cursor");
+            cursor_ui(ui, &mut cursor, input);
+
+            ui.separator();
+            ui.label("Mods");
+            mods_ui(ui, &mut mods);
+            ui.checkbox(&mut reroll_random, "Roll random effects again every run")
+                .on_hover_text("Otherwise they follow the map, and look the same every time");
+
+            ui.collapsing("Mod packs", |ui| {
+                packs_changed = packs_ui(ui, &mut packs, modchart.reborrow());
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Pitch at other rates");
+                egui::ComboBox::from_id_salt("pitch_mode")
+                    .selected_text(pitch_mode.label())
+                    .show_ui(ui, |ui| {
+                        for mode in PitchMode::ALL {
+                            ui.selectable_value(&mut pitch_mode, mode, mode.label());
+                        }
+                    });
+            });
+        });
+
+    panel.open = open;
+    if packs_changed {
+        panel.themes = Theme::available();
+    }
+
+    // Only written back on edits so settings aren't saved every frame the window is open
+    if theme != settings.theme {
+        settings.theme = theme;
+    }
+    if accessibility != settings.accessibility {
+        settings.accessibility = accessibility;
+    }
+    if beat_lines != settings.beat_lines {
+        settings.beat_lines = beat_lines;
+    }
+    if effects != settings.effects {
+        settings.effects = effects;
+    }
+    if feedback != settings.feedback {
+        settings.feedback = feedback;
+    }
+    if comfort != settings.comfort {
+        settings.comfort = comfort;
+    }
+    if mods != settings.mods {
+        settings.mods = mods;
+    }
+    if reroll_random != settings.reroll_random {
+        settings.reroll_random = reroll_random;
+    }
+    if audio_device != settings.audio_device {
+        settings.audio_device = audio_device;
+    }
+    if audio_offset_ms != settings.audio_offset_ms {
+        settings.audio_offset_ms = audio_offset_ms;
+    }
+    if visual_offset_ms != settings.visual_offset_ms {
+        settings.visual_offset_ms = visual_offset_ms;
+    }
+    if input != settings.input {
+        settings.input = input;
+    }
+    if cursor != settings.cursor {
+        settings.cursor = cursor;
+    }
+    if lossy_strings != settings.lossy_strings {
+        settings.lossy_strings = lossy_strings;
+    }
+    if merge_epsilon_ms != settings.merge_epsilon_ms {
+        settings.merge_epsilon_ms = merge_epsilon_ms;
+    }
+    if merge_on_open != settings.merge_on_open {
+        settings.merge_on_open = merge_on_open;
+    }
+    if display != settings.display {
+        settings.display = display;
+    }
+    if check_updates != settings.updates.check_on_startup {
+        settings.updates.check_on_startup = check_updates;
+    }
+    if check_now {
+        updates.check(&settings.updates.endpoint, true);
+    }
+    if pitch_mode != settings.pitch_mode {
+        settings.pitch_mode = pitch_mode;
+    }
+    if let Some(preset) = preset.filter(|p| settings.ruleset.preset() != Some(*p)) {
+        settings.ruleset = preset.ruleset();
+    }
+
+    Ok(())
+}
+
+fn cursor_ui(ui: &mut egui::Ui, cursor: &mut CursorSettings, input: InputDevice) {
+    egui::Grid::new("cursor").num_columns(2).show(ui, |ui| {
+        ui.label("Raw input");
+        ui.add_enabled(
+            input == InputDevice::Mouse,
+            egui::Checkbox::new(&mut cursor.raw_input, ""),
+        )
+        .on_hover_text("Moves by the mouse itself, without system acceleration");
+        ui.end_row();
+
+        ui.add_enabled_ui(cursor.raw(input), |ui| ui.label("Mouse DPI"));
+        ui.add_enabled(
+            cursor.raw(input),
+            egui::DragValue::new(&mut cursor.dpi)
+                .range(100.0..=32000.0)
+                .speed(10.0),
+        );
+        ui.end_row();
+
+        ui.add_enabled_ui(cursor.raw(input), |ui| ui.label("Sensitivity"));
+        ui.add_enabled(
+            cursor.raw(input),
+            egui::DragValue::new(&mut cursor.sensitivity)
+                .range(0.1..=20.0)
+                .speed(0.01)
+                .suffix(" cells/inch"),
+        );
+        ui.end_row();
+
+        ui.label("Smoothing");
+        ui.add(
+            egui::DragValue::new(&mut cursor.smoothing_ms)
+                .range(0.0..=100.0)
+                .suffix("ms"),
+        )
+        .on_hover_text("Only smooths the cursor you see, hits use where it really is");
+        ui.end_row();
+    });
+}
+
+fn display_ui(ui: &mut egui::Ui, display: &mut DisplaySettings) {
+    egui::Grid::new("display").num_columns(2).show(ui, |ui| {
+        ui.label("Window");
+        egui::ComboBox::from_id_salt("display_mode")
+            .selected_text(display.mode.label())
+            .show_ui(ui, |ui| {
+                for mode in DisplayMode::ALL {
+                    ui.selectable_value(&mut display.mode, mode, mode.label());
+                }
+            });
+        ui.end_row();
+
+        ui.label("Resolution");
+        ui.add_enabled_ui(display.mode == DisplayMode::Windowed, |ui| {
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut display.width).range(640..=7680));
+                ui.label("x");
+                ui.add(egui::DragValue::new(&mut display.height).range(360..=4320));
+            });
+        });
+        ui.end_row();
+
+        ui.label("Vsync");
+        ui.checkbox(&mut display.vsync, "")
+            .on_hover_text("Off lowers input latency, but the picture can tear");
+        ui.end_row();
+
+        ui.label("Frame cap");
+        ui.horizontal(|ui| {
+            let mut capped = display.frame_cap.is_some();
+            if ui.checkbox(&mut capped, "").changed() {
+                display.frame_cap = capped.then_some(240);
+            }
+            if let Some(cap) = display.frame_cap.as_mut() {
+                ui.add(egui::DragValue::new(cap).range(30..=1000).suffix(" fps"));
+            }
+        });
+        ui.end_row();
+    });
+}
+
+// Built from the mod definitions, so new mods and parameters show up without changes here
+fn mods_ui(ui: &mut egui::Ui, mods: &mut Mods) {
+    egui::Grid::new("mods").num_columns(2).show(ui, |ui| {
+        for definition in MOD_DEFINITIONS {
+            let mut enabled = mods.is_enabled(definition.id);
+            let toggle = ui
+                .checkbox(&mut enabled, definition.name)
+                .on_hover_text(definition.description);
+            if toggle.changed() {
+                mods.set_enabled(definition, enabled);
+            }
+            ui.end_row();
+
+            // Parameters are only shown for enabled mods
+            for parameter in definition.parameters {
+                let Some(mut value) = mods.parameter(definition.id, parameter.id) else {
+                    continue;
+                };
+
+                ui.label(parameter.name);
+                let slider = egui::Slider::new(&mut value, parameter.min..=parameter.max)
+                    .step_by(parameter.step as f64)
+                    .suffix(parameter.suffix);
+                if ui.add(slider).changed() {
+                    mods.set_parameter(definition.id, parameter.id, value);
+                }
+                ui.end_row();
+            }
+        }
+    });
+}
@@ -0,0 +1,102 @@
+use bevy::{
+    prelude::*,
+    render::camera::RenderTarget,
+    window::{WindowRef, WindowResolution},
+};
+
+use crate::{
+    editor::{
+        camera::gameplay_transform,
+        shortcuts::{EditorAction, EditorActionEvent},
+    },
+    modchart::ModState,
+};
+
+// A second window showing only the gameplay view, for capturing or a second monitor. Its camera
+// only sees the default render layer and has no egui context, so none of the editor shows up.
+#[derive(Resource, Default)]
+pub struct PreviewWindow {
+    window: Option<Entity>,
+    camera: Option<Entity>,
+}
+
+#[derive(Component)]
+pub struct PreviewCamera;
+
+pub fn toggle_preview_window(
+    mut commands: Commands,
+    mut actions: EventReader<EditorActionEvent>,
+    mut preview: ResMut<PreviewWindow>,
+    mods: Res<ModState>,
+) {
+    for EditorActionEvent(action) in actions.read() {
+        if *action != EditorAction::TogglePreviewWindow {
+            continue;
+        }
+
+        if let Some(window) = preview.window.take() {
+            commands.entity(window).despawn();
+            if let Some(camera) = preview.camera.take() {
+                commands.entity(camera).despawn();
+            }
+            continue;
+        }
+
+        let window = commands
+            .spawn(Window {
+                title: "Preview".to_string(),
+                resolution: WindowResolution::new(1280.0, 720.0),
+                ..default()
+            })
+            .id();
+        let camera = commands
+            .spawn((
+                PreviewCamera,
+                Camera3d::default(),
+                Camera {
+                    target: RenderTarget::Window(WindowRef::Entity(window)),
+                    ..default()
+                },
+                Projection::Perspective(PerspectiveProjection::default()),
+                gameplay_transform(&mods),
+            ))
+            .id();
+
+        preview.window = Some(window);
+        preview.camera = Some(camera);
+    }
+}
+
+// The preview follows camera mods whichever view the editor is in
+pub fn follow_gameplay_view(
+    mods: Res<ModState>,
+    mut camera: Query<&mut Transform, With<PreviewCamera>>,
+) {
+    if !mods.is_changed() {
+        return;
+    }
+
+    for mut transform in camera.iter_mut() {
+        *transform = gameplay_transform(&mods);
+    }
+}
+
+// Closing the window from its title bar despawns it, the camera goes with it
+pub fn close_preview_window(
+    mut commands: Commands,
+    mut preview: ResMut<PreviewWindow>,
+    windows: Query<(), With<Window>>,
+) {
+    let Some(window) = preview.window else {
+        return;
+    };
+
+    if windows.contains(window) {
+        return;
+    }
+
+    preview.window = None;
+    if let Some(camera) = preview.camera.take() {
+        commands.entity(camera).despawn();
+    }
+}
@@ -0,0 +1,355 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Cursor, ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+use serde::{Deserialize, Serialize};
+use zip::{ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+use crate::{
+    editor::{
+        camera::{CameraView, EditorCamera, EditorCameraState},
+        commands::{EditHistory, Selection},
+        shortcuts::{EditorAction, EditorActionEvent},
+        timeline::SnapSettings,
+    },
+    maps::{
+        CurrentMap, Map,
+        archive::{read_map_entries, write_map_entries},
+    },
+    modchart::{
+        Modchart,
+        sidecar::{ModchartFile, read_modchart, write_modchart},
+    },
+    player::playback::PlaybackClock,
+    settings::{ExportSettings, Settings},
+};
+
+pub const EXTENSION: &str = "mmm";
+
+const VERSION: u32 = 1;
+
+// Everything needed to pick up where the editor was left: the working map with its bookmarks and
+// review comments, the modchart, and the view and export settings, in one zip
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectState {
+    pub version: u32,
+    pub playhead_ms: f64,
+    pub snap_divisor: u32,
+    // Indices into the map's notes, which are stored alongside so they still line up
+    pub selection: Vec<usize>,
+    pub camera: CameraView,
+    pub export: ExportSettings,
+}
+
+impl Default for ProjectState {
+    fn default() -> Self {
+        Self {
+            version: VERSION,
+            playhead_ms: 0.0,
+            snap_divisor: SnapSettings::default().divisor,
+            selection: Vec::new(),
+            camera: CameraView::default(),
+            export: ExportSettings::default(),
+        }
+    }
+}
+
+pub struct Project {
+    pub map: Map,
+    pub modchart: Modchart,
+    pub state: ProjectState,
+}
+
+// Written next to the target first so a failed save never leaves a broken project behind
+pub fn write_project(
+    path: &Path,
+    map: &Map,
+    modchart: &Modchart,
+    state: &ProjectState,
+) -> io::Result<()> {
+    let temporary = path.with_extension(format!("{EXTENSION}.tmp"));
+    let mut zip = ZipWriter::new(BufWriter::new(File::create(&temporary)?));
+    let options = SimpleFileOptions::default();
+
+    zip.start_file("project.json", options)?;
+    zip.write_all(serde_json::to_string_pretty(state)?.as_bytes())?;
+
+    write_map_entries(&mut zip, map)?;
+
+    let mut mods = Cursor::new(Vec::new());
+    write_modchart(modchart, &mut mods)?;
+    zip.start_file("modchart.mmmod", options)?;
+    zip.write_all(&mods.into_inner())?;
+
+    zip.finish()?.flush()?;
+
+    fs::rename(&temporary, path)
+}
+
+pub fn read_project(path: &Path) -> io::Result<Project> {
+    let mut zip = ZipArchive::new(BufReader::new(File::open(path)?))?;
+
+    let mut contents = String::new();
+    zip.by_name("project.json")?.read_to_string(&mut contents)?;
+    let state: ProjectState = serde_json::from_str(&contents)?;
+
+    if state.version > VERSION {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Project version {} is newer than this app supports",
+                state.version
+            ),
+        ));
+    }
+
+    let map = read_map_entries(&mut zip)?;
+
+    let mut mods = Vec::new();
+    zip.by_name("modchart.mmmod")?.read_to_end(&mut mods)?;
+    let modchart = read_modchart(Cursor::new(mods))?;
+
+    Ok(Project {
+        map,
+        modchart,
+        state,
+    })
+}
+
+// Project the editor saves to, set once one is opened or saved
+#[derive(Resource, Default)]
+pub struct ProjectFile {
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Event, Clone, Debug)]
+pub struct OpenProject(pub PathBuf);
+
+// None saves to the project that is open
+#[derive(Event, Clone, Debug)]
+pub struct SaveProject(pub Option<PathBuf>);
+
+#[derive(Resource, Default)]
+pub struct ProjectPanel {
+    pub open: bool,
+    path: String,
+    // Outcome of the last open or save
+    status: Option<Result<String, String>>,
+}
+
+pub fn project_actions(
+    mut actions: EventReader<EditorActionEvent>,
+    mut panel: ResMut<ProjectPanel>,
+    mut saves: EventWriter<SaveProject>,
+    file: Res<ProjectFile>,
+) {
+    for EditorActionEvent(action) in actions.read() {
+        match action {
+            // Without a project yet, saving asks where to put it
+            EditorAction::SaveProject if file.path.is_some() => {
+                saves.write(SaveProject(None));
+            }
+            EditorAction::SaveProject => panel.open = true,
+            EditorAction::ToggleProjectPanel => panel.open = !panel.open,
+            _ => {}
+        }
+    }
+}
+
+pub fn save_on_exit(
+    mut exits: EventReader<AppExit>,
+    mut saves: EventWriter<SaveProject>,
+    file: Res<ProjectFile>,
+) {
+    if exits.read().count() > 0 && file.path.is_some() {
+        saves.write(SaveProject(None));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn save_project(
+    mut requests: EventReader<SaveProject>,
+    mut file: ResMut<ProjectFile>,
+    mut panel: ResMut<ProjectPanel>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+    modchart: Res<Modchart>,
+    clock: Res<PlaybackClock>,
+    snap: Res<SnapSettings>,
+    selection: Res<Selection>,
+    camera_state: Res<EditorCameraState>,
+    camera: Query<(&Transform, &Projection), With<EditorCamera>>,
+    settings: Res<Settings>,
+) {
+    // Several requests in one frame all save the same state
+    let Some(SaveProject(target)) = requests.read().last() else {
+        return;
+    };
+
+    let Some(path) = target.clone().or_else(|| file.path.clone()) else {
+        return;
+    };
+
+    let Some(map) = current_map.and_then(|current| maps.get(&current.0)) else {
+        panel.status = Some(Err("There is no map to save".to_string()));
+        return;
+    };
+
+    let camera = camera
+        .single()
+        .map(|(transform, projection)| camera_state.view(transform, projection))
+        .unwrap_or_default();
+    let state = ProjectState {
+        playhead_ms: clock.millisecond,
+        snap_divisor: snap.divisor,
+        selection: selection.0.iter().copied().collect(),
+        camera,
+        // Hooks run commands, they stay on this machine instead of travelling with the project
+        export: ExportSettings {
+            hooks: Vec::new(),
+            ..settings.export.clone()
+        },
+        ..default()
+    };
+
+    match write_project(&path, map, &modchart, &state) {
+        Ok(()) => {
+            info!("Saved project to {}", path.display());
+            panel.status = Some(Ok(format!("Saved to {}", path.display())));
+            file.path = Some(path);
+        }
+        Err(e) => {
+            warn!("Saving project to {} failed: {e}", path.display());
+            panel.status = Some(Err(format!("Saving failed: {e}")));
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn open_project(
+    mut commands: Commands,
+    mut requests: EventReader<OpenProject>,
+    mut file: ResMut<ProjectFile>,
+    mut panel: ResMut<ProjectPanel>,
+    mut maps: ResMut<Assets<Map>>,
+    mut modchart_file: ResMut<ModchartFile>,
+    mut clock: ResMut<PlaybackClock>,
+    mut snap: ResMut<SnapSettings>,
+    mut selection: ResMut<Selection>,
+    mut history: ResMut<EditHistory>,
+    mut camera_state: ResMut<EditorCameraState>,
+    mut camera: Query<(&mut Transform, &mut Projection), With<EditorCamera>>,
+    mut settings: ResMut<Settings>,
+) {
+    let Some(OpenProject(path)) = requests.read().last() else {
+        return;
+    };
+
+    let project = match read_project(path) {
+        Ok(project) => project,
+        Err(e) => {
+            warn!("Could not open project {}: {e}", path.display());
+            panel.status = Some(Err(format!("Could not open {}: {e}", path.display())));
+            return;
+        }
+    };
+
+    info!("Opened project {}", path.display());
+    let state = project.state;
+
+    // Picked up by the sidecar loader once the map becomes the current one
+    modchart_file.preloaded = Some(project.modchart);
+    commands.insert_resource(CurrentMap(maps.add(project.map)));
+
+    clock.seek(state.playhead_ms);
+    snap.divisor = state.snap_divisor.max(1);
+    selection.0 = state.selection.into_iter().collect();
+    history.clear();
+
+    if let Ok((mut transform, mut projection)) = camera.single_mut() {
+        camera_state.restore(&state.camera, &mut transform, &mut projection);
+    }
+
+    if settings.export.folder != state.export.folder
+        || settings.export.filename_template != state.export.filename_template
+    {
+        settings.export = ExportSettings {
+            hooks: settings.export.hooks.clone(),
+            ..state.export
+        };
+    }
+
+    panel.status = Some(Ok(format!("Opened {}", path.display())));
+    file.path = Some(path.clone());
+}
+
+pub fn project_ui(
+    mut contexts: EguiContexts,
+    mut panel: ResMut<ProjectPanel>,
+    file: Res<ProjectFile>,
+    mut opens: EventWriter<OpenProject>,
+    mut saves: EventWriter<SaveProject>,
+) -> Result {
+    if !panel.open {
+        return Ok(());
+    }
+
+    let mut open = panel.open;
+    let panel = panel.as_mut();
+
+    if panel.path.is_empty()
+        && let Some(path) = &file.path
+    {
+        panel.path = path.display().to_string();
+    }
+
+    egui::Window::new("Project")
+        .open(&mut open)
+        .resizable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("File");
+                ui.add(
+                    egui::TextEdit::singleline(&mut panel.path)
+                        .hint_text(format!("song.{EXTENSION}")),
+                );
+            });
+
+            let path = panel.path.trim();
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(!path.is_empty(), egui::Button::new("Open"))
+                    .clicked()
+                {
+                    opens.write(OpenProject(PathBuf::from(path)));
+                }
+                if ui
+                    .add_enabled(!path.is_empty(), egui::Button::new("Save"))
+                    .on_hover_text("Also saved when the editor closes")
+                    .clicked()
+                {
+                    let path = Path::new(path).with_extension(EXTENSION);
+                    saves.write(SaveProject(Some(path)));
+                }
+            });
+
+            match &panel.status {
+                Some(Ok(message)) => {
+                    ui.label(message);
+                }
+                Some(Err(error)) => {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                None => {}
+            }
+        });
+
+    panel.open &= open;
+
+    Ok(())
+}
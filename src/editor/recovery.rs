@@ -0,0 +1,142 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    editor::{
+        autosave::recent_autosaves,
+        commands::{EditHistory, Selection},
+    },
+    maps::{CurrentMap, Map, MapFolder, merge::merge_on_open, read_map},
+    player::playback::PlaybackClock,
+    settings::{Settings, recovery::SafeMode},
+};
+
+const RECENT_AUTOSAVES: usize = 8;
+
+#[derive(Resource, Default)]
+pub struct RecoveryPanel {
+    pub open: bool,
+    autosaves: Vec<(PathBuf, SystemTime)>,
+    // Why the last autosave couldn't be opened
+    error: Option<String>,
+}
+
+pub fn open_recovery(mut panel: ResMut<RecoveryPanel>, safe_mode: Res<SafeMode>) {
+    if safe_mode.active {
+        panel.open = true;
+        panel.autosaves = recent_autosaves(RECENT_AUTOSAVES);
+    }
+}
+
+pub fn format_age(age: Duration) -> String {
+    match age.as_secs() {
+        0..60 => "just now".to_string(),
+        seconds @ 60..3600 => format!("{} minutes ago", seconds / 60),
+        seconds @ 3600..86400 => format!("{} hours ago", seconds / 3600),
+        seconds => format!("{} days ago", seconds / 86400),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn recovery_ui(
+    mut contexts: EguiContexts,
+    mut commands: Commands,
+    mut panel: ResMut<RecoveryPanel>,
+    mut safe_mode: ResMut<SafeMode>,
+    mut settings: ResMut<Settings>,
+    mut maps: ResMut<Assets<Map>>,
+    mut clock: ResMut<PlaybackClock>,
+    mut selection: ResMut<Selection>,
+    mut history: ResMut<EditHistory>,
+    asset_server: Res<AssetServer>,
+) -> Result {
+    if !panel.open {
+        return Ok(());
+    }
+
+    let mut open = panel.open;
+    let mut opened = None;
+    let mut leave_safe_mode = false;
+
+    egui::Window::new("Safe mode")
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(contexts.ctx_mut()?, |ui| {
+            if safe_mode.crashed {
+                ui.label("The editor didn't close properly last time.");
+            }
+            ui.label("Started with default settings and without scanning map folders.");
+            ui.weak("Nothing changed in the settings is saved until safe mode is left");
+            ui.separator();
+
+            match panel.autosaves.is_empty() {
+                true => {
+                    ui.label("There are no autosaves to recover");
+                }
+                false => {
+                    ui.label("Recent autosaves:");
+                    let now = SystemTime::now();
+                    egui::Grid::new("autosaves").num_columns(3).show(ui, |ui| {
+                        for (path, modified) in panel.autosaves.iter() {
+                            let name = path.file_stem().unwrap_or_default().to_string_lossy();
+                            let age = now.duration_since(*modified).unwrap_or_default();
+                            ui.label(name).on_hover_text(path.display().to_string());
+                            ui.weak(format_age(age));
+                            if ui.small_button("Open").clicked() {
+                                opened = Some(path.clone());
+                            }
+                            ui.end_row();
+                        }
+                    });
+                }
+            }
+
+            if let Some(error) = &panel.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                leave_safe_mode = ui
+                    .button("Continue normally")
+                    .on_hover_text("Loads your settings and map folders")
+                    .clicked();
+                if ui.button("Stay in safe mode").clicked() {
+                    panel.open = false;
+                }
+            });
+        });
+
+    panel.open &= open;
+
+    if let Some(path) = opened {
+        match read_map(&path, settings.read_options()) {
+            Ok(mut map) => {
+                merge_on_open(&mut map, settings.open_merge_epsilon_ms());
+                info!("Recovered {}", path.display());
+                commands.insert_resource(CurrentMap(maps.add(map)));
+                clock.seek(0.0);
+                selection.0.clear();
+                history.clear();
+                panel.error = None;
+            }
+            Err(e) => panel.error = Some(format!("Could not open {}: {e}", path.display())),
+        }
+    }
+
+    if leave_safe_mode {
+        *settings = Settings::load();
+        safe_mode.active = false;
+        commands.insert_resource(MapFolder(asset_server.load_folder("maps")));
+        panel.open = false;
+    }
+
+    Ok(())
+}
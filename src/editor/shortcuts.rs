@@ -0,0 +1,455 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use bevy::prelude::*;
+use bevy_egui::input::EguiWantsInput;
+use serde::{Deserialize, Serialize};
+
+use crate::{editor::keyboard::KeyboardCharting, settings::Settings};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum EditorAction {
+    PlaceNote,
+    DeleteNote,
+    TogglePlayback,
+    SeekBackward,
+    SeekForward,
+    SeekBackwardFast,
+    SeekForwardFast,
+    SnapIncrease,
+    SnapDecrease,
+    Copy,
+    Paste,
+    Undo,
+    Redo,
+    ToggleMacroRecording,
+    ReplayLastMacro,
+    CameraGameplay,
+    CameraFreeFly,
+    CameraTopDown,
+    TogglePreviewWindow,
+    ToggleNotePath,
+    ToggleHeatmap,
+    NewMap,
+    ExportAll,
+    AddBookmark,
+    NextBookmark,
+    PreviousBookmark,
+    OpenLibrary,
+    ToggleInspector,
+    ToggleObjectInspector,
+    OpenPreferences,
+    Pause,
+    SkipIntro,
+    PlaytestFromHere,
+    SaveModchart,
+    ToggleStretchTool,
+    TogglePlacementTool,
+    ToggleFillTool,
+    ToggleCollaboration,
+    ToggleAlignmentPanel,
+    Screenshot,
+    ExportDiagnostics,
+    TestLatency,
+    ToggleSnapshots,
+    SaveProject,
+    ToggleProjectPanel,
+    ToggleStartScreen,
+    ResumeLastSession,
+    ToggleBulkDelete,
+    TogglePianoRoll,
+    ToggleSimplifyTool,
+    ToggleKeyboardCharting,
+    ToggleAnnotations,
+    ImportTempoMap,
+    ToggleNoteList,
+    ToggleJournal,
+    LatencyTap,
+    LatencyTapAlt,
+    ToggleDebugOverlay,
+    ResetDebugOverlay,
+    SwitchNoteRenderer,
+    ToggleProfiler,
+    ChartCellTopLeft,
+    ChartCellTop,
+    ChartCellTopRight,
+    ChartCellLeft,
+    ChartCellCenter,
+    ChartCellRight,
+    ChartCellBottomLeft,
+    ChartCellBottom,
+    ChartCellBottomRight,
+    ChartStepForward,
+    ChartStepBackward,
+    ChartSnapIncrease,
+    ChartSnapDecrease,
+    ChartUndo,
+    ChartRedo,
+}
+
+impl EditorAction {
+    pub const ALL: [EditorAction; 76] = [
+        EditorAction::PlaceNote,
+        EditorAction::DeleteNote,
+        EditorAction::TogglePlayback,
+        EditorAction::SeekBackward,
+        EditorAction::SeekForward,
+        EditorAction::SeekBackwardFast,
+        EditorAction::SeekForwardFast,
+        EditorAction::SnapIncrease,
+        EditorAction::SnapDecrease,
+        EditorAction::Copy,
+        EditorAction::Paste,
+        EditorAction::Undo,
+        EditorAction::Redo,
+        EditorAction::ToggleMacroRecording,
+        EditorAction::ReplayLastMacro,
+        EditorAction::CameraGameplay,
+        EditorAction::CameraFreeFly,
+        EditorAction::CameraTopDown,
+        EditorAction::ToggleNotePath,
+        EditorAction::ToggleHeatmap,
+        EditorAction::NewMap,
+        EditorAction::ExportAll,
+        EditorAction::AddBookmark,
+        EditorAction::NextBookmark,
+        EditorAction::PreviousBookmark,
+        EditorAction::OpenLibrary,
+        EditorAction::ToggleInspector,
+        EditorAction::ToggleObjectInspector,
+        EditorAction::OpenPreferences,
+        EditorAction::Pause,
+        EditorAction::SkipIntro,
+        EditorAction::PlaytestFromHere,
+        EditorAction::SaveModchart,
+        EditorAction::ToggleStretchTool,
+        EditorAction::TogglePlacementTool,
+        EditorAction::ToggleFillTool,
+        EditorAction::ToggleCollaboration,
+        EditorAction::ToggleAlignmentPanel,
+        EditorAction::Screenshot,
+        EditorAction::ExportDiagnostics,
+        EditorAction::TestLatency,
+        EditorAction::TogglePreviewWindow,
+        EditorAction::ToggleSnapshots,
+        EditorAction::SaveProject,
+        EditorAction::ToggleProjectPanel,
+        EditorAction::ToggleStartScreen,
+        EditorAction::ResumeLastSession,
+        EditorAction::ToggleBulkDelete,
+        EditorAction::TogglePianoRoll,
+        EditorAction::ToggleSimplifyTool,
+        EditorAction::ToggleKeyboardCharting,
+        EditorAction::ToggleAnnotations,
+        EditorAction::ImportTempoMap,
+        EditorAction::ToggleNoteList,
+        EditorAction::ToggleJournal,
+        EditorAction::LatencyTap,
+        EditorAction::LatencyTapAlt,
+        EditorAction::ToggleDebugOverlay,
+        EditorAction::ResetDebugOverlay,
+        EditorAction::SwitchNoteRenderer,
+        EditorAction::ToggleProfiler,
+        EditorAction::ChartCellTopLeft,
+        EditorAction::ChartCellTop,
+        EditorAction::ChartCellTopRight,
+        EditorAction::ChartCellLeft,
+        EditorAction::ChartCellCenter,
+        EditorAction::ChartCellRight,
+        EditorAction::ChartCellBottomLeft,
+        EditorAction::ChartCellBottom,
+        EditorAction::ChartCellBottomRight,
+        EditorAction::ChartStepForward,
+        EditorAction::ChartStepBackward,
+        EditorAction::ChartSnapIncrease,
+        EditorAction::ChartSnapDecrease,
+        EditorAction::ChartUndo,
+        EditorAction::ChartRedo,
+    ];
+
+    pub fn default_chord(&self) -> KeyChord {
+        let key = KeyChord::new;
+
+        match self {
+            EditorAction::PlaceNote => key(KeyCode::KeyN),
+            EditorAction::DeleteNote => key(KeyCode::Delete),
+            EditorAction::TogglePlayback => key(KeyCode::Space),
+            EditorAction::SeekBackward => key(KeyCode::ArrowLeft),
+            EditorAction::SeekForward => key(KeyCode::ArrowRight),
+            EditorAction::SeekBackwardFast => key(KeyCode::ArrowLeft).shift(),
+            EditorAction::SeekForwardFast => key(KeyCode::ArrowRight).shift(),
+            EditorAction::SnapIncrease => key(KeyCode::ArrowUp).ctrl(),
+            EditorAction::SnapDecrease => key(KeyCode::ArrowDown).ctrl(),
+            EditorAction::Copy => key(KeyCode::KeyC).ctrl(),
+            EditorAction::Paste => key(KeyCode::KeyV).ctrl(),
+            EditorAction::Undo => key(KeyCode::KeyZ).ctrl(),
+            EditorAction::Redo => key(KeyCode::KeyZ).ctrl().shift(),
+            EditorAction::ToggleMacroRecording => key(KeyCode::KeyM).ctrl(),
+            EditorAction::ReplayLastMacro => key(KeyCode::KeyM).ctrl().shift(),
+            EditorAction::CameraGameplay => key(KeyCode::F1),
+            EditorAction::CameraFreeFly => key(KeyCode::F2),
+            EditorAction::CameraTopDown => key(KeyCode::F3),
+            EditorAction::ToggleNotePath => key(KeyCode::KeyP),
+            EditorAction::ToggleHeatmap => key(KeyCode::KeyH),
+            EditorAction::NewMap => key(KeyCode::KeyN).ctrl(),
+            EditorAction::ExportAll => key(KeyCode::KeyE).ctrl().shift(),
+            EditorAction::AddBookmark => key(KeyCode::KeyB).ctrl(),
+            EditorAction::NextBookmark => key(KeyCode::ArrowRight).ctrl(),
+            EditorAction::PreviousBookmark => key(KeyCode::ArrowLeft).ctrl(),
+            EditorAction::OpenLibrary => key(KeyCode::KeyO).ctrl(),
+            EditorAction::ToggleInspector => key(KeyCode::KeyI),
+            EditorAction::ToggleObjectInspector => key(KeyCode::KeyI).shift(),
+            EditorAction::OpenPreferences => key(KeyCode::Comma).ctrl(),
+            EditorAction::Pause => key(KeyCode::Escape),
+            EditorAction::SkipIntro => key(KeyCode::Enter),
+            EditorAction::PlaytestFromHere => key(KeyCode::F5),
+            EditorAction::SaveModchart => key(KeyCode::KeyS).ctrl(),
+            EditorAction::ToggleStretchTool => key(KeyCode::KeyT).ctrl(),
+            EditorAction::TogglePlacementTool => key(KeyCode::KeyL).ctrl(),
+            EditorAction::ToggleFillTool => key(KeyCode::KeyR).ctrl(),
+            EditorAction::ToggleCollaboration => key(KeyCode::KeyK).ctrl(),
+            EditorAction::ToggleAlignmentPanel => key(KeyCode::KeyJ).ctrl(),
+            EditorAction::Screenshot => key(KeyCode::F9),
+            EditorAction::ExportDiagnostics => key(KeyCode::KeyD).ctrl().shift(),
+            EditorAction::TestLatency => key(KeyCode::KeyL).ctrl().shift(),
+            EditorAction::TogglePreviewWindow => key(KeyCode::F4),
+            EditorAction::ToggleSnapshots => key(KeyCode::KeyV).ctrl().shift(),
+            EditorAction::SaveProject => key(KeyCode::KeyS).ctrl().alt(),
+            EditorAction::ToggleProjectPanel => key(KeyCode::KeyO).ctrl().alt(),
+            EditorAction::ToggleStartScreen => key(KeyCode::KeyH).ctrl().shift(),
+            EditorAction::ResumeLastSession => key(KeyCode::KeyR).ctrl().shift(),
+            EditorAction::ToggleBulkDelete => key(KeyCode::Delete).ctrl().shift(),
+            EditorAction::TogglePianoRoll => key(KeyCode::KeyG).ctrl(),
+            EditorAction::ToggleSimplifyTool => key(KeyCode::KeyD).ctrl().alt(),
+            EditorAction::ToggleKeyboardCharting => key(KeyCode::KeyK).ctrl().shift(),
+            EditorAction::ToggleAnnotations => key(KeyCode::KeyC).ctrl().shift(),
+            EditorAction::ImportTempoMap => key(KeyCode::KeyT).ctrl().shift(),
+            EditorAction::ToggleNoteList => key(KeyCode::KeyN).ctrl().shift(),
+            EditorAction::ToggleJournal => key(KeyCode::KeyJ).ctrl().alt(),
+            // Two keys so taps can alternate between fingers
+            EditorAction::LatencyTap => key(KeyCode::KeyZ),
+            EditorAction::LatencyTapAlt => key(KeyCode::KeyX),
+            EditorAction::ToggleDebugOverlay => key(KeyCode::F12),
+            EditorAction::ResetDebugOverlay => key(KeyCode::F11),
+            EditorAction::SwitchNoteRenderer => key(KeyCode::F10),
+            EditorAction::ToggleProfiler => key(KeyCode::F8),
+            // Keyboard charting lays the numpad out like the grid, 7 is the top left cell
+            EditorAction::ChartCellTopLeft => key(KeyCode::Numpad7),
+            EditorAction::ChartCellTop => key(KeyCode::Numpad8),
+            EditorAction::ChartCellTopRight => key(KeyCode::Numpad9),
+            EditorAction::ChartCellLeft => key(KeyCode::Numpad4),
+            EditorAction::ChartCellCenter => key(KeyCode::Numpad5),
+            EditorAction::ChartCellRight => key(KeyCode::Numpad6),
+            EditorAction::ChartCellBottomLeft => key(KeyCode::Numpad1),
+            EditorAction::ChartCellBottom => key(KeyCode::Numpad2),
+            EditorAction::ChartCellBottomRight => key(KeyCode::Numpad3),
+            EditorAction::ChartStepForward => key(KeyCode::NumpadEnter),
+            EditorAction::ChartStepBackward => key(KeyCode::NumpadEnter).shift(),
+            EditorAction::ChartSnapIncrease => key(KeyCode::NumpadAdd),
+            EditorAction::ChartSnapDecrease => key(KeyCode::NumpadSubtract),
+            EditorAction::ChartUndo => key(KeyCode::Numpad0),
+            EditorAction::ChartRedo => key(KeyCode::Numpad0).ctrl(),
+        }
+    }
+}
+
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EditorActionEvent(pub EditorAction);
+
+// A key plus the exact set of modifiers that has to be held, so Ctrl+S and Ctrl+Shift+S never overlap
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct KeyChord {
+    pub key: KeyCode,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl KeyChord {
+    pub fn new(key: KeyCode) -> Self {
+        Self {
+            key,
+            ctrl: false,
+            shift: false,
+            alt: false,
+        }
+    }
+
+    pub fn ctrl(mut self) -> Self {
+        self.ctrl = true;
+        self
+    }
+
+    pub fn shift(mut self) -> Self {
+        self.shift = true;
+        self
+    }
+
+    pub fn alt(mut self) -> Self {
+        self.alt = true;
+        self
+    }
+
+    pub fn just_pressed(&self, keys: &ButtonInput<KeyCode>) -> bool {
+        let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+        let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        let alt = keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+
+        keys.just_pressed(self.key) && ctrl == self.ctrl && shift == self.shift && alt == self.alt
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            f.write_str("Ctrl+")?;
+        }
+        if self.shift {
+            f.write_str("Shift+")?;
+        }
+        if self.alt {
+            f.write_str("Alt+")?;
+        }
+
+        write!(f, "{:?}", self.key)
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts: Vec<&str> = s.split('+').map(str::trim).collect();
+        let key = parts
+            .pop()
+            .filter(|k| !k.is_empty())
+            .ok_or("Empty shortcut")?;
+
+        // Key names use the same spelling as bevy's KeyCode variants, e.g. "KeyS" or "F1"
+        let key: KeyCode = serde_json::from_value(serde_json::Value::String(key.to_string()))
+            .map_err(|_| format!("Unknown key \"{key}\""))?;
+
+        let mut chord = KeyChord::new(key);
+
+        for modifier in parts {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => chord.ctrl = true,
+                "shift" => chord.shift = true,
+                "alt" => chord.alt = true,
+                _ => return Err(format!("Unknown modifier \"{modifier}\"")),
+            }
+        }
+
+        Ok(chord)
+    }
+}
+
+impl TryFrom<String> for KeyChord {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<KeyChord> for String {
+    fn from(value: KeyChord) -> Self {
+        value.to_string()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Keybinds {
+    bindings: BTreeMap<EditorAction, KeyChord>,
+}
+
+impl Default for Keybinds {
+    fn default() -> Self {
+        Self {
+            bindings: EditorAction::ALL
+                .iter()
+                .map(|action| (*action, action.default_chord()))
+                .collect(),
+        }
+    }
+}
+
+impl Keybinds {
+    pub fn get(&self, action: EditorAction) -> Option<KeyChord> {
+        self.bindings.get(&action).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (EditorAction, KeyChord)> + '_ {
+        self.bindings
+            .iter()
+            .map(|(action, chord)| (*action, *chord))
+    }
+
+    // Refuses to bind a chord that is already in use, returning the action that owns it
+    pub fn bind(&mut self, action: EditorAction, chord: KeyChord) -> Result<(), EditorAction> {
+        if let Some((owner, _)) = self.iter().find(|(a, c)| *a != action && *c == chord) {
+            return Err(owner);
+        }
+
+        self.bindings.insert(action, chord);
+        Ok(())
+    }
+
+    pub fn unbind(&mut self, action: EditorAction) {
+        self.bindings.remove(&action);
+    }
+
+    pub fn reset(&mut self, action: EditorAction) -> Result<(), EditorAction> {
+        self.bind(action, action.default_chord())
+    }
+
+    pub fn reset_to_default(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn fill_missing(&mut self) {
+        for action in EditorAction::ALL {
+            self.bindings
+                .entry(action)
+                .or_insert_with(|| action.default_chord());
+        }
+    }
+
+    // Hand-edited setting files can still bind one chord to several actions
+    pub fn conflicts(&self) -> Vec<(KeyChord, Vec<EditorAction>)> {
+        let mut by_chord = BTreeMap::<String, (KeyChord, Vec<EditorAction>)>::new();
+
+        for (action, chord) in self.iter() {
+            by_chord
+                .entry(chord.to_string())
+                .or_insert_with(|| (chord, Vec::new()))
+                .1
+                .push(action);
+        }
+
+        by_chord
+            .into_values()
+            .filter(|(_, actions)| actions.len() > 1)
+            .collect()
+    }
+}
+
+pub fn dispatch_shortcuts(
+    keys: Res<ButtonInput<KeyCode>>,
+    settings: Res<Settings>,
+    egui_input: Res<EguiWantsInput>,
+    charting: Res<KeyboardCharting>,
+    mut actions: EventWriter<EditorActionEvent>,
+) {
+    // Typing into a text field must not trigger editor shortcuts
+    if keys.get_just_pressed().len() == 0 || egui_input.wants_any_keyboard_input() {
+        return;
+    }
+
+    for (action, chord) in settings.keybinds.iter() {
+        if chord.just_pressed(&keys) && !charting.claims(&settings.keybinds, &chord) {
+            actions.write(EditorActionEvent(action));
+        }
+    }
+}
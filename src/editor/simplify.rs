@@ -0,0 +1,187 @@
+use std::{fs::File, io::BufWriter, path::PathBuf};
+
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    editor::{
+        commands::{EditHistory, Selection},
+        shortcuts::{EditorAction, EditorActionEvent},
+    },
+    maps::{
+        CurrentMap, Map,
+        library::MapLibrary,
+        map_file,
+        parser::{MapSerializer, SSPMSerializer},
+        simplify::{SimplifyOptions, SimplifyReport, simplify},
+    },
+    settings::Settings,
+};
+
+// Derives an easier difficulty from the current map, written next to it and opened for touching up
+#[derive(Resource, Default)]
+pub struct SimplifyTool {
+    pub open: bool,
+    options: SimplifyOptions,
+    // What the current options would do to the current map, worked out again when either changes
+    preview: Option<(SimplifyOptions, SimplifyReport)>,
+    status: Option<Result<String, String>>,
+}
+
+pub fn toggle_simplify_tool(
+    mut actions: EventReader<EditorActionEvent>,
+    mut tool: ResMut<SimplifyTool>,
+) {
+    for EditorActionEvent(action) in actions.read() {
+        if *action == EditorAction::ToggleSimplifyTool {
+            tool.open = !tool.open;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn simplify_tool_ui(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut tool: ResMut<SimplifyTool>,
+    current_map: Option<Res<CurrentMap>>,
+    mut maps: ResMut<Assets<Map>>,
+    library: Res<MapLibrary>,
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+    mut selection: ResMut<Selection>,
+    mut history: ResMut<EditHistory>,
+) -> Result {
+    if !tool.open {
+        return Ok(());
+    }
+
+    let Some(current_map) = current_map else {
+        return Ok(());
+    };
+    let Some(map) = maps.get(&current_map.0) else {
+        return Ok(());
+    };
+
+    let mut open = tool.open;
+    let mut create = false;
+    let tool = tool.as_mut();
+
+    if current_map.is_changed()
+        || tool
+            .preview
+            .as_ref()
+            .is_none_or(|(options, _)| *options != tool.options)
+    {
+        let (_, report) = simplify(map, &tool.options);
+        tool.preview = Some((tool.options.clone(), report));
+    }
+
+    egui::Window::new("Simplify")
+        .open(&mut open)
+        .resizable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            egui::Grid::new("simplify_options")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Keep notes on");
+                    egui::ComboBox::from_id_salt("simplify_divisor")
+                        .selected_text(format!("1/{} beats", tool.options.divisor))
+                        .show_ui(ui, |ui| {
+                            for divisor in [1, 2, 3, 4, 6, 8] {
+                                ui.selectable_value(
+                                    &mut tool.options.divisor,
+                                    divisor,
+                                    format!("1/{divisor} beats"),
+                                );
+                            }
+                        });
+                    ui.end_row();
+
+                    ui.label("Longest jump");
+                    ui.add(
+                        egui::DragValue::new(&mut tool.options.max_jump)
+                            .range(0.5..=3.0)
+                            .speed(0.05)
+                            .suffix(" cells"),
+                    );
+                    ui.end_row();
+
+                    ui.label("Quantum notes");
+                    ui.checkbox(&mut tool.options.remove_quantum, "Move onto the grid");
+                    ui.end_row();
+
+                    ui.label("Difficulty name");
+                    ui.text_edit_singleline(&mut tool.options.difficulty_name);
+                    ui.end_row();
+                });
+
+            if let Some((_, report)) = &tool.preview {
+                ui.separator();
+                ui.label(format!(
+                    "Keeps {} of {} notes",
+                    report.kept,
+                    report.kept + report.removed
+                ));
+                ui.label(format!(
+                    "{} jumps shortened, {} quantum notes moved",
+                    report.shortened_jumps, report.snapped
+                ));
+            }
+
+            create = ui
+                .button("Create difficulty")
+                .on_hover_text("Written next to this map as a new file and opened")
+                .clicked();
+
+            match &tool.status {
+                Some(Ok(path)) => {
+                    ui.label(format!("Created {path}"));
+                }
+                Some(Err(e)) => {
+                    ui.colored_label(ui.visuals().error_fg_color, e);
+                }
+                None => {}
+            }
+        });
+
+    tool.open = open;
+
+    if !create {
+        return Ok(());
+    }
+
+    let (simplified, _) = simplify(map, &tool.options);
+
+    // Maps made in the editor have no folder of their own yet, the export folder takes them
+    let folder = map_file(&current_map.0, &library, &asset_server)
+        .and_then(|path| path.parent().map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from(settings.export.folder.trim()));
+    let path = folder.join(format!("{}.sspm", simplified.id));
+
+    if path.exists() {
+        tool.status = Some(Err(format!(
+            "{} already exists, pick another difficulty name",
+            path.display()
+        )));
+        return Ok(());
+    }
+
+    let written = std::fs::create_dir_all(&folder)
+        .and_then(|_| File::create(&path))
+        .and_then(|file| SSPMSerializer::serialize(&simplified, BufWriter::new(file)));
+
+    if let Err(e) = written {
+        tool.status = Some(Err(format!("Could not write {}: {e}", path.display())));
+        return Ok(());
+    }
+
+    info!("Created {} from {}", path.display(), map.id);
+    tool.status = Some(Ok(path.display().to_string()));
+
+    commands.insert_resource(CurrentMap(maps.add(simplified)));
+    selection.0.clear();
+    history.clear();
+
+    Ok(())
+}
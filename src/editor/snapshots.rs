@@ -0,0 +1,224 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    editor::{
+        commands::{EditHistory, Selection},
+        recovery::format_age,
+        shortcuts::{EditorAction, EditorActionEvent},
+    },
+    maps::{
+        CurrentMap, Map,
+        library::MapLibrary,
+        map_file,
+        snapshot::{
+            MapDiff, Snapshot, create_snapshot, delete_snapshot, diff_maps, list_snapshots,
+            read_snapshot, snapshot_folder,
+        },
+    },
+};
+
+#[derive(Resource, Default)]
+pub struct SnapshotPanel {
+    pub open: bool,
+    name: String,
+    // Found when the panel opens, the current map can't change while it is open
+    folder: Option<PathBuf>,
+    snapshots: Vec<Snapshot>,
+    // Name of the snapshot the working map was compared against, and what changed since
+    diff: Option<(String, MapDiff)>,
+    error: Option<String>,
+}
+
+enum SnapshotAction {
+    Create,
+    Diff(usize),
+    Restore(usize),
+    Delete(usize),
+}
+
+pub fn toggle_snapshot_panel(
+    mut actions: EventReader<EditorActionEvent>,
+    mut panel: ResMut<SnapshotPanel>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+    library: Res<MapLibrary>,
+    asset_server: Res<AssetServer>,
+) {
+    for EditorActionEvent(action) in actions.read() {
+        if *action != EditorAction::ToggleSnapshots {
+            continue;
+        }
+
+        panel.open = !panel.open;
+        if !panel.open {
+            continue;
+        }
+
+        let Some(current_map) = &current_map else {
+            continue;
+        };
+
+        let path = map_file(&current_map.0, &library, &asset_server);
+        panel.folder = maps
+            .get(&current_map.0)
+            .and_then(|map| snapshot_folder(map, path.as_deref()));
+        panel.snapshots = panel
+            .folder
+            .as_deref()
+            .map_or_else(Vec::new, list_snapshots);
+        panel.diff = None;
+        panel.error = None;
+    }
+}
+
+pub fn snapshots_ui(
+    mut contexts: EguiContexts,
+    mut panel: ResMut<SnapshotPanel>,
+    current_map: Option<Res<CurrentMap>>,
+    mut maps: ResMut<Assets<Map>>,
+    mut selection: ResMut<Selection>,
+    mut history: ResMut<EditHistory>,
+) -> Result {
+    if !panel.open {
+        return Ok(());
+    }
+
+    let mut open = panel.open;
+    let mut action = None;
+    let panel = panel.as_mut();
+
+    egui::Window::new("Snapshots")
+        .open(&mut open)
+        .show(contexts.ctx_mut()?, |ui| {
+            if panel.folder.is_none() {
+                ui.label("Open a map to take snapshots of it");
+                return;
+            }
+
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut panel.name).hint_text("v1 ranked submission"),
+                );
+                let name = panel.name.trim();
+                if ui
+                    .add_enabled(!name.is_empty(), egui::Button::new("Take snapshot"))
+                    .clicked()
+                {
+                    action = Some(SnapshotAction::Create);
+                }
+            });
+
+            ui.separator();
+
+            if panel.snapshots.is_empty() {
+                ui.label("No snapshots yet");
+            }
+
+            let now = SystemTime::now();
+            egui::Grid::new("snapshot_list")
+                .num_columns(6)
+                .show(ui, |ui| {
+                    for (index, snapshot) in panel.snapshots.iter().enumerate() {
+                        let created = UNIX_EPOCH + Duration::from_secs(snapshot.created);
+                        ui.label(&snapshot.name)
+                            .on_hover_text(snapshot.path.display().to_string());
+                        ui.weak(format_age(now.duration_since(created).unwrap_or_default()));
+                        ui.weak(format!("{} notes", snapshot.notes));
+
+                        if ui.button("Diff").clicked() {
+                            action = Some(SnapshotAction::Diff(index));
+                        }
+                        if ui
+                            .button("Restore")
+                            .on_hover_text("Takes a snapshot of the working map first")
+                            .clicked()
+                        {
+                            action = Some(SnapshotAction::Restore(index));
+                        }
+                        if ui.button("Delete").clicked() {
+                            action = Some(SnapshotAction::Delete(index));
+                        }
+                        ui.end_row();
+                    }
+                });
+
+            if let Some((name, diff)) = &panel.diff {
+                ui.separator();
+                ui.label(format!("Changes since {name}:"));
+                match diff.is_empty() {
+                    true => {
+                        ui.weak("Nothing changed");
+                    }
+                    false => {
+                        ui.label(format!(
+                            "{} notes added, {} removed, {} moved",
+                            diff.added, diff.removed, diff.moved
+                        ));
+                        for change in diff.changes.iter() {
+                            ui.label(change);
+                        }
+                    }
+                }
+            }
+
+            if let Some(error) = &panel.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+        });
+
+    panel.open = open;
+
+    let (Some(action), Some(current_map), Some(folder)) =
+        (action, current_map, panel.folder.clone())
+    else {
+        return Ok(());
+    };
+
+    let Some(map) = maps.get(&current_map.0) else {
+        return Ok(());
+    };
+
+    let result = match action {
+        SnapshotAction::Create => create_snapshot(map, &folder, panel.name.trim()).map(|_| {
+            panel.name.clear();
+        }),
+        SnapshotAction::Diff(index) => {
+            let snapshot = &panel.snapshots[index];
+            read_snapshot(snapshot).map(|old| {
+                panel.diff = Some((snapshot.name.clone(), diff_maps(&old, map)));
+            })
+        }
+        SnapshotAction::Restore(index) => {
+            let snapshot = panel.snapshots[index].clone();
+            let backup = format!("Before restoring {}", snapshot.name);
+            let restored = read_snapshot(&snapshot).and_then(|restored| {
+                create_snapshot(map, &folder, &backup)?;
+                Ok(restored)
+            });
+
+            restored.map(|restored| {
+                info!("Restored snapshot {}", snapshot.path.display());
+                if let Some(map) = maps.get_mut(&current_map.0) {
+                    *map = restored;
+                }
+                selection.0.clear();
+                history.clear();
+                panel.diff = None;
+            })
+        }
+        SnapshotAction::Delete(index) => delete_snapshot(&panel.snapshots[index]).map(|()| {
+            panel.diff = None;
+        }),
+    };
+
+    panel.error = result.err().map(|e| format!("Snapshot failed: {e}"));
+    panel.snapshots = list_snapshots(&folder);
+
+    Ok(())
+}
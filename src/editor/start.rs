@@ -0,0 +1,323 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    editor::{
+        commands::{EditHistory, Selection},
+        project::{OpenProject, ProjectFile},
+        recovery::format_age,
+        shortcuts::{EditorAction, EditorActionEvent},
+    },
+    maps::{CurrentMap, Map, cover, library::MapLibrary, map_file, merge::merge_on_open, read_map},
+    player::playback::PlaybackClock,
+    settings::{
+        Settings,
+        recent::{RecentFile, RecentKind, cover_path, modified, remember, save_cover},
+    },
+};
+
+const THUMBNAIL_SIZE: f32 = 64.0;
+
+#[derive(Event, Clone, Debug)]
+pub struct OpenRecent(pub RecentFile);
+
+// Recently opened projects and maps, shown when the app starts
+#[derive(Resource, Default)]
+pub struct StartScreen {
+    pub open: bool,
+    // Loaded the first time an entry is shown, None when it has no cover
+    covers: HashMap<PathBuf, Option<egui::TextureHandle>>,
+    error: Option<String>,
+    // Front of the list when the app started, before this session added anything to it
+    last_session: Option<RecentFile>,
+    // File the editor is working on, its recent entry gets the playhead when the app closes
+    session: Option<PathBuf>,
+    // Set until the session's map has loaded and its cover was saved
+    cover_pending: bool,
+}
+
+pub fn open_start_screen(
+    mut screen: ResMut<StartScreen>,
+    settings: Res<Settings>,
+    opens: EventReader<OpenProject>,
+) {
+    screen.last_session = settings.recent.first().cloned();
+    // A project passed on the command line is already being opened
+    screen.open = !settings.first_run && !settings.recent.is_empty() && opens.is_empty();
+}
+
+pub fn start_actions(
+    mut actions: EventReader<EditorActionEvent>,
+    mut screen: ResMut<StartScreen>,
+    mut opens: EventWriter<OpenRecent>,
+) {
+    for EditorActionEvent(action) in actions.read() {
+        match action {
+            EditorAction::ToggleStartScreen => {
+                screen.open = !screen.open;
+                screen.covers.clear();
+            }
+            EditorAction::ResumeLastSession => {
+                if let Some(last) = &screen.last_session {
+                    opens.write(OpenRecent(last.clone()));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn open_recent(
+    mut commands: Commands,
+    mut requests: EventReader<OpenRecent>,
+    mut screen: ResMut<StartScreen>,
+    mut projects: EventWriter<OpenProject>,
+    mut maps: ResMut<Assets<Map>>,
+    library: Res<MapLibrary>,
+    settings: Res<Settings>,
+    mut clock: ResMut<PlaybackClock>,
+    mut selection: ResMut<Selection>,
+    mut history: ResMut<EditHistory>,
+) {
+    let Some(OpenRecent(recent)) = requests.read().last() else {
+        return;
+    };
+
+    // Projects bring their own playhead along
+    if recent.kind == RecentKind::Project {
+        projects.write(OpenProject(recent.path.clone()));
+        screen.open = false;
+        return;
+    }
+
+    // Library maps are opened through their handle so everything else still finds their file
+    let handle = match library
+        .entries
+        .get(&recent.path)
+        .and_then(|entry| entry.map.clone())
+    {
+        Some(handle) => handle,
+        None => match read_map(&recent.path, settings.read_options()) {
+            Ok(map) => maps.add(map),
+            Err(e) => {
+                warn!("Could not open {}: {e}", recent.path.display());
+                screen.error = Some(format!("Could not open {}: {e}", recent.path.display()));
+                return;
+            }
+        },
+    };
+
+    if let Some(map) = maps.get_mut(&handle) {
+        merge_on_open(map, settings.open_merge_epsilon_ms());
+    }
+
+    info!("Resumed {}", recent.path.display());
+    commands.insert_resource(CurrentMap(handle));
+    clock.seek(recent.playhead_ms);
+    selection.0.clear();
+    history.clear();
+    screen.open = false;
+    screen.error = None;
+}
+
+pub fn track_recent(
+    mut screen: ResMut<StartScreen>,
+    mut settings: ResMut<Settings>,
+    current_map: Option<Res<CurrentMap>>,
+    file: Res<ProjectFile>,
+    maps: Res<Assets<Map>>,
+    library: Res<MapLibrary>,
+    asset_server: Res<AssetServer>,
+) {
+    // Saving a project sets its file again, which moves it back to the front
+    let opened = match (&file.path, &current_map) {
+        (Some(path), _) if file.is_changed() => Some((path.clone(), RecentKind::Project)),
+        // Maps without a file, like the one inside a project, aren't remembered on their own
+        (_, Some(current)) if current.is_changed() => {
+            map_file(&current.0, &library, &asset_server).map(|path| (path, RecentKind::Map))
+        }
+        _ => None,
+    };
+
+    if let Some((path, kind)) = opened {
+        let title = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        remember(
+            &mut settings.recent,
+            RecentFile::new(path.clone(), kind, title),
+        );
+        screen.covers.remove(&path);
+        screen.session = Some(path);
+        screen.cover_pending = true;
+    }
+
+    // Maps from the asset folder load a few frames after they become the current one
+    if !screen.cover_pending {
+        return;
+    }
+
+    let (Some(session), Some(map)) = (
+        screen.session.clone(),
+        current_map.and_then(|current| maps.get(&current.0)),
+    ) else {
+        return;
+    };
+
+    if let Err(e) = save_cover(&session, &map.cover) {
+        warn!("Could not save the cover of {}: {e}", session.display());
+    }
+    if let Some(recent) = settings.recent.iter_mut().find(|r| r.path == session)
+        && !map.title.is_empty()
+    {
+        recent.title = map.title.clone();
+    }
+    screen.cover_pending = false;
+}
+
+pub fn remember_playhead(
+    mut exits: EventReader<AppExit>,
+    screen: Res<StartScreen>,
+    mut settings: ResMut<Settings>,
+    clock: Res<PlaybackClock>,
+) {
+    if exits.read().count() == 0 {
+        return;
+    }
+
+    if let Some(session) = &screen.session
+        && let Some(recent) = settings.recent.iter_mut().find(|r| &r.path == session)
+    {
+        recent.playhead_ms = clock.millisecond;
+        recent.last_edited = modified(session).unwrap_or(recent.last_edited);
+    }
+}
+
+fn load_thumbnail(ctx: &egui::Context, path: &Path) -> Option<egui::TextureHandle> {
+    let bytes = fs::read(cover_path(path)?).ok()?;
+    let image = cover::decode(&bytes).ok()?;
+    let size = [image.width() as usize, image.height() as usize];
+    let image = egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw());
+    Some(ctx.load_texture(path.display().to_string(), image, default()))
+}
+
+pub fn start_screen_ui(
+    mut contexts: EguiContexts,
+    mut screen: ResMut<StartScreen>,
+    mut settings: ResMut<Settings>,
+    mut opens: EventWriter<OpenRecent>,
+) -> Result {
+    if !screen.open {
+        return Ok(());
+    }
+
+    let ctx = contexts.ctx_mut()?.clone();
+    let mut open = screen.open;
+    let mut forget = None;
+    let screen = screen.as_mut();
+
+    egui::Window::new("Recent")
+        .open(&mut open)
+        .collapsible(false)
+        .default_width(420.0)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(&ctx, |ui| {
+            if settings.recent.is_empty() {
+                ui.label("Projects and maps you open show up here");
+                return;
+            }
+
+            if let Some(last) = &screen.last_session {
+                if ui
+                    .button("Resume last session")
+                    .on_hover_text(last.path.display().to_string())
+                    .clicked()
+                {
+                    opens.write(OpenRecent(last.clone()));
+                }
+                ui.separator();
+            }
+
+            let now = SystemTime::now();
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (index, recent) in settings.recent.iter().enumerate() {
+                    let exists = recent.path.exists();
+                    ui.horizontal(|ui| {
+                        let thumbnail = screen
+                            .covers
+                            .entry(recent.path.clone())
+                            .or_insert_with(|| load_thumbnail(&ctx, &recent.path));
+                        let size = egui::Vec2::splat(THUMBNAIL_SIZE);
+                        match thumbnail {
+                            Some(texture) => {
+                                ui.add(egui::Image::new((texture.id(), size)));
+                            }
+                            None => {
+                                let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+                                ui.painter()
+                                    .rect_filled(rect, 4.0, ui.visuals().extreme_bg_color);
+                            }
+                        }
+
+                        ui.vertical(|ui| {
+                            ui.strong(&recent.title);
+                            let kind = match recent.kind {
+                                RecentKind::Project => "Project",
+                                RecentKind::Map => "Map",
+                            };
+                            let edited = UNIX_EPOCH + Duration::from_secs(recent.last_edited);
+                            ui.weak(format!(
+                                "{kind}, edited {}",
+                                format_age(now.duration_since(edited).unwrap_or_default())
+                            ));
+
+                            ui.horizontal(|ui| {
+                                if ui.add_enabled(exists, egui::Button::new("Open")).clicked() {
+                                    opens.write(OpenRecent(recent.clone()));
+                                }
+                                if ui.small_button("Forget").clicked() {
+                                    forget = Some(index);
+                                }
+                                if !exists {
+                                    ui.colored_label(
+                                        ui.visuals().error_fg_color,
+                                        "File is missing",
+                                    );
+                                }
+                            });
+                        })
+                        .response
+                        .on_hover_text(recent.path.display().to_string());
+                    });
+                }
+            });
+
+            if let Some(error) = &screen.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+        });
+
+    screen.open = open;
+
+    if let Some(index) = forget {
+        let recent = settings.recent.remove(index);
+        screen.covers.remove(&recent.path);
+        if let Err(e) = save_cover(&recent.path, &[]) {
+            warn!(
+                "Could not remove the cover of {}: {e}",
+                recent.path.display()
+            );
+        }
+    }
+
+    Ok(())
+}
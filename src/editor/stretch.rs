@@ -0,0 +1,184 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    editor::{
+        commands::{EditCommand, Selection},
+        editing::EditRequest,
+        shortcuts::{EditorAction, EditorActionEvent},
+    },
+    maps::{CurrentMap, Map},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+enum StretchMode {
+    #[default]
+    Ratio,
+    // Rescales a section charted against the wrong BPM onto the right one
+    Bpm,
+}
+
+#[derive(Resource)]
+pub struct StretchTool {
+    pub open: bool,
+    mode: StretchMode,
+    ratio: f64,
+    // Follows the BPM at the first selected note until edited
+    from_bpm: Option<f64>,
+    to_bpm: f64,
+}
+
+impl Default for StretchTool {
+    fn default() -> Self {
+        Self {
+            open: false,
+            mode: StretchMode::default(),
+            ratio: 1.0,
+            from_bpm: None,
+            to_bpm: 120.0,
+        }
+    }
+}
+
+pub fn toggle_stretch_tool(
+    mut actions: EventReader<EditorActionEvent>,
+    mut tool: ResMut<StretchTool>,
+) {
+    for EditorActionEvent(action) in actions.read() {
+        if *action == EditorAction::ToggleStretchTool {
+            tool.open = !tool.open;
+        }
+    }
+}
+
+fn format_ms(millisecond: f64) -> String {
+    let seconds = millisecond / 1000.0;
+    format!("{}:{:06.3}", (seconds / 60.0) as u32, seconds % 60.0)
+}
+
+pub fn stretch_tool_ui(
+    mut contexts: EguiContexts,
+    mut tool: ResMut<StretchTool>,
+    mut requests: EventWriter<EditRequest>,
+    selection: Res<Selection>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+) -> Result {
+    if !tool.open {
+        return Ok(());
+    }
+
+    let Some(map) = current_map.and_then(|m| maps.get(&m.0)) else {
+        return Ok(());
+    };
+
+    let times: Vec<u32> = selection
+        .0
+        .iter()
+        .filter_map(|&i| map.notes.get(i))
+        .map(|note| note.millisecond)
+        .collect();
+
+    let mut open = tool.open;
+    let mut command = None;
+
+    egui::Window::new("Time stretch")
+        .open(&mut open)
+        .resizable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            let (Some(&first), Some(&last)) = (times.iter().min(), times.iter().max()) else {
+                ui.label("No notes selected");
+                return;
+            };
+
+            ui.label(format!("{} notes selected", times.len()));
+            ui.separator();
+
+            let tool = tool.as_mut();
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut tool.mode, StretchMode::Ratio, "Ratio");
+                ui.selectable_value(&mut tool.mode, StretchMode::Bpm, "BPM");
+            });
+
+            let ratio = match tool.mode {
+                StretchMode::Ratio => {
+                    ui.horizontal(|ui| {
+                        ui.label("Ratio");
+                        ui.add(
+                            egui::DragValue::new(&mut tool.ratio)
+                                .range(0.01..=100.0)
+                                .speed(0.01)
+                                .fixed_decimals(3),
+                        );
+                        for (label, ratio) in
+                            [("1/2", 0.5), ("3/4", 0.75), ("4/3", 4.0 / 3.0), ("2", 2.0)]
+                        {
+                            if ui.small_button(label).clicked() {
+                                tool.ratio = ratio;
+                            }
+                        }
+                    });
+                    tool.ratio
+                }
+                StretchMode::Bpm => {
+                    let charted = map
+                        .timing_point_at(first)
+                        .map_or(120.0, |timing| timing.bpm as f64);
+                    let mut from = tool.from_bpm.unwrap_or(charted);
+
+                    egui::Grid::new("stretch_bpm")
+                        .num_columns(2)
+                        .show(ui, |ui| {
+                            ui.label("Charted at");
+                            if ui
+                                .add(
+                                    egui::DragValue::new(&mut from)
+                                        .range(1.0..=10000.0)
+                                        .suffix(" BPM"),
+                                )
+                                .changed()
+                            {
+                                tool.from_bpm = Some(from);
+                            }
+                            ui.end_row();
+
+                            ui.label("Should be");
+                            ui.add(
+                                egui::DragValue::new(&mut tool.to_bpm)
+                                    .range(1.0..=10000.0)
+                                    .suffix(" BPM"),
+                            );
+                            ui.end_row();
+                        });
+
+                    // A faster BPM puts the beats closer together
+                    from / tool.to_bpm
+                }
+            };
+
+            let end = first as f64 + (last - first) as f64 * ratio;
+            ui.label(format!(
+                "{} to {} becomes {} to {}",
+                format_ms(first as f64),
+                format_ms(last as f64),
+                format_ms(first as f64),
+                format_ms(end)
+            ));
+
+            let unchanged = (ratio - 1.0).abs() < f64::EPSILON;
+            if ui
+                .add_enabled(!unchanged && times.len() > 1, egui::Button::new("Stretch"))
+                .clicked()
+            {
+                command = Some(EditCommand::Stretch { ratio });
+            }
+        });
+
+    tool.open = open;
+
+    if let Some(command) = command {
+        requests.write(EditRequest::Execute(command));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,149 @@
+use std::{fs::File, io::BufReader, path::Path};
+
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    editor::{
+        editing::EditRequest,
+        shortcuts::{EditorAction, EditorActionEvent},
+    },
+    maps::{
+        CurrentMap, Map, format_precise_time,
+        objects::TimingPoint,
+        tempo::{offset_timing_points, read_midi_tempo_map},
+    },
+};
+
+// Replaces the map's timing with the tempo map of a MIDI file exported from a DAW, so songs with
+// tempo changes don't need every timing point placed by hand
+#[derive(Resource, Default)]
+pub struct TempoImport {
+    pub open: bool,
+    path: String,
+    // Milliseconds the audio starts after the DAW project does, negative when it starts earlier
+    offset_ms: i64,
+    read: Option<Result<Vec<TimingPoint>, String>>,
+}
+
+pub fn toggle_tempo_import(
+    mut actions: EventReader<EditorActionEvent>,
+    mut import: ResMut<TempoImport>,
+) {
+    for EditorActionEvent(action) in actions.read() {
+        if *action == EditorAction::ImportTempoMap {
+            import.open = !import.open;
+        }
+    }
+}
+
+pub fn tempo_import_ui(
+    mut contexts: EguiContexts,
+    mut import: ResMut<TempoImport>,
+    mut requests: EventWriter<EditRequest>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+) -> Result {
+    if !import.open {
+        return Ok(());
+    }
+
+    let current = current_map.as_ref().and_then(|m| maps.get(&m.0));
+    let existing = current.map_or(0, |map| map.timing_points.len());
+    let mut open = import.open;
+    let mut apply = None;
+    let import = import.as_mut();
+
+    egui::Window::new("Import tempo map")
+        .open(&mut open)
+        .resizable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut import.path)
+                        .hint_text("Path to a .mid")
+                        .desired_width(220.0),
+                );
+
+                let path = import.path.trim().to_string();
+                if ui
+                    .add_enabled(!path.is_empty(), egui::Button::new("Read"))
+                    .clicked()
+                {
+                    let points = File::open(Path::new(&path))
+                        .and_then(|file| read_midi_tempo_map(BufReader::new(file)));
+                    import.read = Some(points.map_err(|e| {
+                        warn!("Could not read a tempo map from {path}: {e}");
+                        format!("Could not read {path}: {e}")
+                    }));
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Audio starts at");
+                ui.add(egui::DragValue::new(&mut import.offset_ms).suffix("ms"))
+                    .on_hover_text(
+                        "Where the audio begins in the DAW project, when it was exported from \
+                         somewhere other than the start",
+                    );
+            });
+
+            let points = match &import.read {
+                Some(Ok(points)) => offset_timing_points(points, -import.offset_ms),
+                Some(Err(e)) => {
+                    ui.colored_label(ui.visuals().error_fg_color, e.as_str());
+                    return;
+                }
+                None => return,
+            };
+            ui.separator();
+
+            let slowest = points.iter().map(|p| p.bpm).fold(f32::INFINITY, f32::min);
+            let fastest = points.iter().map(|p| p.bpm).fold(0.0, f32::max);
+            ui.label(match points.len() {
+                1 => format!("Steady {fastest:.2} BPM"),
+                count => format!("{count} timing points from {slowest:.2} to {fastest:.2} BPM"),
+            });
+
+            egui::ScrollArea::vertical()
+                .max_height(240.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("tempo_map_points")
+                        .num_columns(3)
+                        .show(ui, |ui| {
+                            for point in points.iter() {
+                                ui.label(format_precise_time(point.millisecond));
+                                ui.label(format!("{:.2} BPM", point.bpm));
+                                ui.label(format!("{}/4", point.beats_per_measure));
+                                ui.end_row();
+                            }
+                        });
+                });
+
+            if existing > 0 {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!("Replaces the {existing} timing points in the map"),
+                );
+            }
+            if ui
+                .add_enabled(current.is_some(), egui::Button::new("Use as timing"))
+                .clicked()
+            {
+                apply = Some(points);
+            }
+        });
+
+    import.open = open;
+
+    // Goes through the history so the import can be undone
+    if let Some(points) = apply {
+        info!("Imported {} timing points from a tempo map", points.len());
+        requests.write(EditRequest::SetTimingPoints(
+            points,
+            "Import tempo map".to_string(),
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,343 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    editor::{
+        annotations::AnnotationPanel,
+        bookmarks::BookmarkPanel,
+        commands::{EditCommand, Selection},
+        difficulty::{DifficultyGraph, paint_difficulty, paint_unreadable},
+        drag::NoteDrag,
+        editing::EditRequest,
+        lyrics::LyricPanel,
+        shortcuts::{EditorAction, EditorActionEvent},
+        windowing::marked_columns,
+    },
+    gameplay::Session,
+    jukebox::scrub::ScrubAt,
+    maps::{CurrentMap, Map, format_precise_time, objects::Lyric},
+    player::{SimulationState, playback::PlaybackClock},
+};
+
+// Used when the map has no timing to snap to
+const SCRUB_STEP_MS: f64 = 100.0;
+const SCRUB_STEP_FAST_MS: f64 = 1000.0;
+
+const SNAP_DIVISORS: [u32; 8] = [1, 2, 3, 4, 6, 8, 12, 16];
+
+#[derive(Resource, Debug)]
+pub struct SnapSettings {
+    // Beat subdivision the playhead and placed notes snap to, 4 means 1/4 beats
+    pub divisor: u32,
+}
+
+impl Default for SnapSettings {
+    fn default() -> Self {
+        Self { divisor: 4 }
+    }
+}
+
+impl SnapSettings {
+    fn step(&mut self, direction: i32) {
+        let index = SNAP_DIVISORS
+            .iter()
+            .position(|d| *d == self.divisor)
+            .unwrap_or(3) as i32;
+        let index = (index + direction).clamp(0, SNAP_DIVISORS.len() as i32 - 1);
+        self.divisor = SNAP_DIVISORS[index as usize];
+    }
+}
+
+pub fn scrub_playhead(
+    mut actions: EventReader<EditorActionEvent>,
+    mut clock: ResMut<PlaybackClock>,
+    mut snap: ResMut<SnapSettings>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+    simulation: Res<State<SimulationState>>,
+    mut next_simulation: ResMut<NextState<SimulationState>>,
+) {
+    let map = current_map.and_then(|m| maps.get(&m.0));
+
+    for EditorActionEvent(action) in actions.read() {
+        let timing = map.and_then(|m| m.timing_point_at(clock.millisecond.max(0.0) as u32));
+
+        let (snap_step, measure) = match timing {
+            Some(timing) => (
+                timing.beat_length() / snap.divisor as f64,
+                timing.beat_length() * timing.beats_per_measure as f64,
+            ),
+            None => (SCRUB_STEP_MS, SCRUB_STEP_FAST_MS),
+        };
+
+        let step = match action {
+            EditorAction::SeekBackward => -snap_step,
+            EditorAction::SeekForward => snap_step,
+            EditorAction::SeekBackwardFast => -measure,
+            EditorAction::SeekForwardFast => measure,
+            EditorAction::SnapIncrease => {
+                snap.step(1);
+                continue;
+            }
+            EditorAction::SnapDecrease => {
+                snap.step(-1);
+                continue;
+            }
+            EditorAction::TogglePlayback => {
+                next_simulation.set(match simulation.get() {
+                    SimulationState::Paused => SimulationState::Running,
+                    SimulationState::Running => SimulationState::Paused,
+                });
+                continue;
+            }
+            _ => continue,
+        };
+
+        let mut target = clock.millisecond + step;
+
+        if let Some(map) = map {
+            target = map.snap(target, snap.divisor);
+        }
+
+        clock.seek(target);
+    }
+}
+
+const TIMELINE_HEIGHT: f32 = 36.0;
+
+// Pixels from a selected note's tick that a drag picks up the selection instead of seeking
+const GRAB_DISTANCE: f32 = 4.0;
+
+// Overview of the whole song along the bottom of the window, click or drag to seek.
+// Dragging a selected note's tick moves the selection in time instead, snapped to the beat.
+#[allow(clippy::too_many_arguments)]
+pub fn timeline_ui(
+    mut contexts: EguiContexts,
+    mut clock: ResMut<PlaybackClock>,
+    mut bookmark_panel: ResMut<BookmarkPanel>,
+    mut annotation_panel: ResMut<AnnotationPanel>,
+    mut lyric_panel: ResMut<LyricPanel>,
+    mut scrub: EventWriter<ScrubAt>,
+    mut drag: ResMut<NoteDrag>,
+    mut requests: EventWriter<EditRequest>,
+    selection: Res<Selection>,
+    snap: Res<SnapSettings>,
+    session: Option<Res<Session>>,
+    graph: Res<DifficultyGraph>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+) -> Result {
+    let Some(map) = current_map.and_then(|m| maps.get(&m.0)) else {
+        return Ok(());
+    };
+
+    let length = map
+        .notes
+        .last()
+        .map_or(map.length, |note| note.millisecond.max(map.length))
+        .max(1) as f64;
+
+    egui::TopBottomPanel::bottom("timeline").show(contexts.ctx_mut()?, |ui| {
+        ui.horizontal(|ui| {
+            ui.label(format_precise_time(clock.millisecond.max(0.0) as u32));
+
+            let bookmarks = ui.button("Bookmarks");
+            if bookmarks.clicked() {
+                bookmark_panel.open = !bookmark_panel.open;
+            }
+
+            let open_comments = map.annotations.iter().filter(|a| !a.resolved).count();
+            let comments = match open_comments {
+                0 => ui.button("Comments"),
+                count => ui.button(format!("Comments ({count})")),
+            };
+            if comments.clicked() {
+                annotation_panel.open = !annotation_panel.open;
+            }
+
+            if ui.button("Lyrics").clicked() {
+                lyric_panel.open = !lyric_panel.open;
+            }
+
+            if let Some(readability) = graph.readability.as_ref() {
+                let unreadable = readability.unreadable();
+                let label = ui.label(format!("Readability {:.0}", readability.score()));
+                if unreadable.is_empty() {
+                    label.on_hover_text("Every note can be seen in time");
+                } else {
+                    label.on_hover_text(format!(
+                        "{} sections can't be read in time at the default approach rate, marked \
+                         along the top. Notes there are hidden behind others or fade in too late",
+                        unreadable.len()
+                    ));
+                }
+            }
+
+            let width = ui.available_width();
+            let (response, painter) = ui.allocate_painter(
+                egui::vec2(width, TIMELINE_HEIGHT),
+                egui::Sense::click_and_drag(),
+            );
+            let rect = response.rect;
+            let x_at = |ms: f64| rect.left() + (ms / length).clamp(0.0, 1.0) as f32 * rect.width();
+
+            painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+
+            if let Some(curve) = graph.curve.as_ref() {
+                paint_difficulty(
+                    &painter,
+                    rect,
+                    curve,
+                    length,
+                    ui.visuals().selection.bg_fill,
+                );
+            }
+
+            if let Some(readability) = graph.readability.as_ref() {
+                paint_unreadable(
+                    &painter,
+                    rect,
+                    readability,
+                    length,
+                    ui.visuals().warn_fg_color,
+                );
+            }
+
+            for bookmark in map.bookmarks.iter() {
+                let [r, g, b] = bookmark.color;
+                let color = egui::Color32::from_rgb(r, g, b);
+                let x = x_at(bookmark.millisecond as f64);
+
+                painter.line_segment(
+                    [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+                    egui::Stroke::new(2.0, color),
+                );
+                painter.text(
+                    egui::pos2(x + 3.0, rect.top() + 2.0),
+                    egui::Align2::LEFT_TOP,
+                    &bookmark.label,
+                    egui::FontId::proportional(11.0),
+                    color,
+                );
+            }
+
+            // Lyrics are spans through the middle, clicking one opens the lyrics panel to edit it
+            let lyric_band = |lyric: &Lyric| {
+                egui::Rect::from_x_y_ranges(
+                    x_at(lyric.millisecond as f64)
+                        ..=x_at(lyric.end() as f64).max(x_at(lyric.millisecond as f64) + 2.0),
+                    rect.center().y - 3.0..=rect.center().y + 3.0,
+                )
+            };
+            for lyric in map.lyrics.iter() {
+                painter.rect_filled(lyric_band(lyric), 1.0, ui.visuals().widgets.active.bg_fill);
+            }
+            let hovered_lyric = response.hover_pos().and_then(|at| {
+                map.lyrics
+                    .iter()
+                    .find(|lyric| lyric_band(lyric).contains(at))
+            });
+            if let Some(lyric) = hovered_lyric {
+                if response.clicked() {
+                    lyric_panel.open = true;
+                }
+                response.clone().on_hover_text_at_pointer(&lyric.text);
+            }
+
+            // Comments sit along the bottom edge, open ones stand out from the resolved ones
+            for annotation in map.annotations.iter() {
+                let color = match annotation.resolved {
+                    true => ui.visuals().weak_text_color(),
+                    false => ui.visuals().warn_fg_color,
+                };
+                let x = x_at(annotation.millisecond as f64);
+                let y = rect.bottom() - 4.0;
+
+                painter.add(egui::Shape::convex_polygon(
+                    vec![
+                        egui::pos2(x, y - 4.0),
+                        egui::pos2(x + 4.0, y),
+                        egui::pos2(x, y + 4.0),
+                        egui::pos2(x - 4.0, y),
+                    ],
+                    color,
+                    egui::Stroke::NONE,
+                ));
+            }
+
+            // One tick per pixel is enough, selections can span thousands of notes
+            let offset = drag.milliseconds.unwrap_or(0);
+            let columns = rect.width().max(1.0) as usize;
+            let ticks: Vec<f32> = marked_columns(&map.notes, &selection.0, length, columns, offset)
+                .into_iter()
+                .map(|column| {
+                    (rect.left() + (column as f32 + 0.5) * rect.width() / columns as f32).round()
+                })
+                .collect();
+
+            let accent = ui.visuals().selection.stroke.color;
+            for x in ticks.iter() {
+                painter.line_segment(
+                    [
+                        egui::pos2(*x, rect.bottom() - TIMELINE_HEIGHT / 3.0),
+                        egui::pos2(*x, rect.bottom()),
+                    ],
+                    egui::Stroke::new(1.0, accent),
+                );
+            }
+
+            let first = selection
+                .0
+                .first()
+                .and_then(|&i| map.notes.get(i))
+                .map(|note| note.millisecond as f64);
+
+            if response.drag_started()
+                && session.is_none()
+                && let Some(origin) = ui.input(|i| i.pointer.press_origin())
+                && ticks.iter().any(|x| (x - origin.x).abs() <= GRAB_DISTANCE)
+            {
+                drag.milliseconds = Some(0);
+            }
+
+            if let (Some(_), Some(first)) = (drag.milliseconds, first) {
+                let origin = ui.input(|i| i.pointer.press_origin());
+                if let (Some(origin), Some(position)) = (origin, response.interact_pointer_pos()) {
+                    let moved = (position.x - origin.x) / rect.width() * length as f32;
+                    let target = map.snap(first + moved as f64, snap.divisor).max(0.0);
+                    drag.milliseconds = Some((target - first).round() as i64);
+                }
+
+                if response.drag_stopped() {
+                    if let Some(milliseconds) = drag.milliseconds.filter(|ms| *ms != 0) {
+                        requests.write(EditRequest::Execute(EditCommand::Offset { milliseconds }));
+                    }
+                    drag.milliseconds = None;
+                }
+            }
+
+            let playhead = x_at(clock.millisecond);
+            painter.line_segment(
+                [
+                    egui::pos2(playhead, rect.top()),
+                    egui::pos2(playhead, rect.bottom()),
+                ],
+                egui::Stroke::new(2.0, ui.visuals().strong_text_color()),
+            );
+
+            if let Some(position) = response.interact_pointer_pos()
+                && drag.milliseconds.is_none()
+                && !response.drag_stopped()
+            {
+                let fraction = ((position.x - rect.left()) / rect.width()).clamp(0.0, 1.0);
+                clock.seek(fraction as f64 * length);
+
+                if response.dragged() {
+                    scrub.write(ScrubAt(clock.millisecond));
+                }
+            }
+        });
+    });
+
+    Ok(())
+}
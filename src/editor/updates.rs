@@ -0,0 +1,206 @@
+use std::{io, path::PathBuf};
+
+use bevy::{
+    prelude::*,
+    tasks::{IoTaskPool, Task, block_on, futures_lite::future},
+};
+use bevy_egui::{EguiContexts, egui};
+
+use crate::settings::{
+    Settings,
+    recovery::SafeMode,
+    updates::{CURRENT_VERSION, Release, download, fetch_latest},
+};
+
+#[derive(Resource, Default)]
+pub struct UpdatePanel {
+    pub open: bool,
+    checking: Option<Task<io::Result<Release>>>,
+    // Asked for from the preferences, the answer is shown even when there's nothing new
+    manual: bool,
+    // Only set once it is newer than this build
+    latest: Option<Release>,
+    downloading: Option<Task<io::Result<PathBuf>>>,
+    status: Option<Result<String, String>>,
+}
+
+impl UpdatePanel {
+    pub fn is_checking(&self) -> bool {
+        self.checking.is_some()
+    }
+
+    pub fn check(&mut self, endpoint: &str, manual: bool) {
+        if self.checking.is_some() {
+            return;
+        }
+
+        let endpoint = endpoint.to_string();
+        self.manual = manual;
+        self.checking = Some(IoTaskPool::get().spawn(async move { fetch_latest(&endpoint) }));
+    }
+}
+
+// Never in safe mode, the network shouldn't be in the way of recovering from a crash
+pub fn check_on_startup(
+    mut panel: ResMut<UpdatePanel>,
+    settings: Res<Settings>,
+    safe_mode: Res<SafeMode>,
+) {
+    if settings.updates.check_on_startup && !safe_mode.active {
+        panel.check(&settings.updates.endpoint, false);
+    }
+}
+
+pub fn poll_updates(mut panel: ResMut<UpdatePanel>, settings: Res<Settings>) {
+    if let Some(result) = panel
+        .checking
+        .as_mut()
+        .and_then(|task| block_on(future::poll_once(task)))
+    {
+        panel.checking = None;
+        let manual = panel.manual;
+
+        match result {
+            Ok(release) if release.is_newer_than(CURRENT_VERSION) => {
+                info!("Version {} is available", release.tag_name);
+                let skipped = settings.updates.skipped.as_ref() == Some(&release.tag_name);
+                if manual || !skipped {
+                    panel.open = true;
+                    panel.status = None;
+                }
+                panel.latest = Some(release);
+            }
+            Ok(_) => {
+                if manual {
+                    panel.open = true;
+                    panel.latest = None;
+                    panel.status = Some(Ok(format!("{CURRENT_VERSION} is the newest version")));
+                }
+            }
+            Err(e) => {
+                warn!("Could not check for updates: {e}");
+                if manual {
+                    panel.open = true;
+                    panel.status = Some(Err(format!("Could not check for updates: {e}")));
+                }
+            }
+        }
+    }
+
+    if let Some(result) = panel
+        .downloading
+        .as_mut()
+        .and_then(|task| block_on(future::poll_once(task)))
+    {
+        panel.downloading = None;
+        panel.status = Some(match result {
+            Ok(path) => {
+                info!("Downloaded the update to {}", path.display());
+                Ok(format!(
+                    "Saved and verified to {}, install it once the editor is closed",
+                    path.display()
+                ))
+            }
+            Err(e) => {
+                warn!("Could not download the update: {e}");
+                Err(format!("Could not download the update: {e}"))
+            }
+        });
+    }
+}
+
+pub fn updates_ui(
+    mut contexts: EguiContexts,
+    mut panel: ResMut<UpdatePanel>,
+    mut settings: ResMut<Settings>,
+) -> Result {
+    if !panel.open {
+        return Ok(());
+    }
+
+    let mut open = panel.open;
+    let mut start_download = false;
+    let mut skip = false;
+    let panel = panel.as_mut();
+
+    egui::Window::new("Updates")
+        .open(&mut open)
+        .collapsible(false)
+        .default_width(380.0)
+        .show(contexts.ctx_mut()?, |ui| {
+            if let Some(release) = &panel.latest {
+                ui.strong(format!(
+                    "Version {} is out, this is {CURRENT_VERSION}",
+                    release.tag_name
+                ));
+                if !release.name.is_empty() && release.name != release.tag_name {
+                    ui.label(&release.name);
+                }
+                ui.weak("Modcharts often depend on fixes to the map formats, updating is worth it");
+
+                if !release.body.trim().is_empty() {
+                    egui::ScrollArea::vertical()
+                        .max_height(200.0)
+                        .show(ui, |ui| ui.label(release.body.trim()));
+                }
+
+                ui.horizontal(|ui| {
+                    let asset = release.asset_for_platform();
+                    let checksum = asset.and_then(|asset| release.checksum_for(asset));
+                    start_download = ui
+                        .add_enabled(
+                            checksum.is_some() && panel.downloading.is_none(),
+                            egui::Button::new("Download"),
+                        )
+                        .on_hover_text(asset.map_or(String::new(), |asset| asset.name.clone()))
+                        .on_disabled_hover_text(match asset {
+                            None => "There's no build for this system in the release",
+                            Some(_) => "The build has no checksum to verify it with",
+                        })
+                        .clicked();
+
+                    if !release.html_url.is_empty() {
+                        ui.hyperlink_to("Release page", &release.html_url);
+                    }
+
+                    skip = ui
+                        .button("Skip this version")
+                        .on_hover_text("It isn't brought up on startup again")
+                        .clicked();
+                });
+            }
+
+            if panel.downloading.is_some() {
+                ui.label("Downloading...");
+            }
+            match &panel.status {
+                Some(Ok(message)) => {
+                    ui.label(message.as_str());
+                }
+                Some(Err(e)) => {
+                    ui.colored_label(ui.visuals().error_fg_color, e.as_str());
+                }
+                None => {}
+            }
+        });
+
+    if start_download
+        && let Some(release) = panel.latest.clone()
+        && let Some(asset) = release.asset_for_platform().cloned()
+    {
+        panel.status = None;
+        // curl blocks its thread for the whole download, which must not hold up audio decoding
+        // on the compute pool
+        panel.downloading =
+            Some(IoTaskPool::get().spawn(async move { download(&release, &asset) }));
+    }
+
+    if skip && let Some(release) = &panel.latest {
+        settings.updates.skipped = Some(release.tag_name.clone());
+        open = false;
+    }
+
+    panel.open = open;
+
+    Ok(())
+}
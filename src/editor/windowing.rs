@@ -0,0 +1,92 @@
+use std::{collections::BTreeSet, ops::Range};
+
+use crate::maps::objects::Note;
+
+// Views of the notes only ever touch what is on screen, so maps with hundreds of thousands of
+// notes stay as responsive as small ones. Notes are sorted by time, ranges are binary searches
+
+// Notes from `start_ms` up to and including `end_ms`
+pub fn notes_between(notes: &[Note], start_ms: f64, end_ms: f64) -> Range<usize> {
+    let from = notes.partition_point(|n| (n.millisecond as f64) < start_ms);
+    let to = notes.partition_point(|n| (n.millisecond as f64) <= end_ms);
+    from..to.max(from)
+}
+
+// The notes of `range` worth drawing when many land on the same pixel column: one per column and
+// lane, a `marked` one when there is any so selections stay visible. Columns can't decrease
+// along the range, which holds for anything laid out by time
+pub fn one_per_column(
+    range: Range<usize>,
+    lanes: usize,
+    column: impl Fn(usize) -> i32,
+    lane: impl Fn(usize) -> usize,
+    marked: impl Fn(usize) -> bool,
+) -> Vec<usize> {
+    let mut drawn = Vec::new();
+    let mut slots: Vec<Option<usize>> = vec![None; lanes.max(1)];
+    let mut current = None;
+
+    for index in range {
+        let at = column(index);
+        if current != Some(at) {
+            drawn.extend(slots.iter_mut().filter_map(Option::take));
+            current = Some(at);
+        }
+
+        let slot = &mut slots[lane(index).min(lanes.max(1) - 1)];
+        match slot {
+            Some(kept) if marked(*kept) || !marked(index) => {}
+            _ => *slot = Some(index),
+        }
+    }
+    drawn.extend(slots.into_iter().flatten());
+
+    drawn
+}
+
+fn column_of(millisecond: f64, length_ms: f64, columns: usize) -> usize {
+    let fraction = (millisecond / length_ms.max(1.0)).clamp(0.0, 1.0);
+    ((fraction * columns as f64) as usize).min(columns.saturating_sub(1))
+}
+
+// Pixel columns out of `columns` spread over `length_ms` that hold a marked note, after moving
+// the notes by `offset_ms`. Large selections are looked up per column instead of per note
+pub fn marked_columns(
+    notes: &[Note],
+    marked: &BTreeSet<usize>,
+    length_ms: f64,
+    columns: usize,
+    offset_ms: i64,
+) -> Vec<usize> {
+    if columns == 0 {
+        return Vec::new();
+    }
+    let moved = |note: &Note| (note.millisecond as i64 + offset_ms).max(0) as f64;
+
+    if marked.len() <= columns {
+        let mut found: Vec<usize> = marked
+            .iter()
+            .filter_map(|&i| notes.get(i))
+            .map(|note| column_of(moved(note), length_ms, columns))
+            .collect();
+        found.dedup();
+        return found;
+    }
+
+    // Notes before the start and past the end are drawn in the first and last column
+    let first_index = |column: usize| match column {
+        0 => 0,
+        _ if column >= columns => notes.len(),
+        _ => {
+            let from = column as f64 * length_ms.max(1.0) / columns as f64;
+            notes.partition_point(|note| moved(note) < from)
+        }
+    };
+
+    (0..columns)
+        .filter(|&column| {
+            let (from, to) = (first_index(column), first_index(column + 1));
+            from < to && marked.range(from..to).next().is_some()
+        })
+        .collect()
+}
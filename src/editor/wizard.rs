@@ -0,0 +1,315 @@
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    path::PathBuf,
+};
+
+use bevy::{
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, Task, block_on, futures_lite::future},
+};
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    analysis::bpm::{BpmEstimate, detect_bpm},
+    editor::{
+        commands::{EditHistory, Selection},
+        shortcuts::{EditorAction, EditorActionEvent},
+        timeline::SnapSettings,
+    },
+    jukebox::pcm::Pcm,
+    maps::{
+        CurrentMap, DIFFICULTY_NAMES, Map,
+        objects::TimingPoint,
+        parser::{MapSerializer, SSPMSerializer},
+    },
+};
+
+const DEFAULT_OUTPUT: &str = "assets/maps";
+
+#[derive(Debug)]
+pub struct AudioAnalysis {
+    pub bytes: Vec<u8>,
+    pub duration_ms: u32,
+    pub estimate: Option<BpmEstimate>,
+}
+
+#[derive(Resource)]
+pub struct NewMapWizard {
+    pub open: bool,
+    pub audio_path: String,
+    pub title: String,
+    pub artist: String,
+    pub mappers: String,
+    pub difficulty: u8,
+    pub difficulty_name: String,
+    pub bpm: f32,
+    pub offset_ms: u32,
+    pub beats_per_measure: u8,
+    pub output_folder: String,
+    pub analysis: Option<AudioAnalysis>,
+    pub status: String,
+    task: Option<Task<Result<AudioAnalysis, String>>>,
+}
+
+impl Default for NewMapWizard {
+    fn default() -> Self {
+        Self {
+            open: false,
+            audio_path: String::new(),
+            title: String::new(),
+            artist: String::new(),
+            mappers: String::new(),
+            difficulty: 0,
+            difficulty_name: String::new(),
+            bpm: 120.0,
+            offset_ms: 0,
+            beats_per_measure: 4,
+            output_folder: DEFAULT_OUTPUT.to_string(),
+            analysis: None,
+            status: String::new(),
+            task: None,
+        }
+    }
+}
+
+impl NewMapWizard {
+    pub fn map_id(&self) -> String {
+        let mut id = String::new();
+
+        for c in format!("{} {}", self.artist, self.title).trim().chars() {
+            if c.is_ascii_alphanumeric() {
+                id.push(c.to_ascii_lowercase());
+            } else if !id.ends_with('_') {
+                id.push('_');
+            }
+        }
+
+        id.trim_matches('_').to_string()
+    }
+
+    pub fn build_map(&self) -> Result<Map, String> {
+        let analysis = self
+            .analysis
+            .as_ref()
+            .ok_or("Analyze the audio file first")?;
+
+        if self.title.trim().is_empty() {
+            return Err("A title is required".to_string());
+        }
+
+        if self.bpm <= 0.0 {
+            return Err("BPM must be positive".to_string());
+        }
+
+        Ok(Map {
+            id: self.map_id(),
+            length: analysis.duration_ms,
+            title: self.title.trim().to_string(),
+            artists: vec![self.artist.trim().to_string()],
+            difficulty: self.difficulty,
+            difficulty_name: self.difficulty_name.trim().to_string(),
+            mappers: self
+                .mappers
+                .split(',')
+                .map(str::trim)
+                .filter(|m| !m.is_empty())
+                .map(String::from)
+                .collect(),
+            audio: Some(AudioSource {
+                bytes: analysis.bytes.clone().into(),
+            }),
+            timing_points: vec![TimingPoint {
+                millisecond: self.offset_ms,
+                bpm: self.bpm,
+                beats_per_measure: self.beats_per_measure,
+            }],
+            ..Map::default()
+        })
+    }
+
+    fn start_analysis(&mut self) {
+        let path = PathBuf::from(self.audio_path.trim());
+        self.status = format!("Analyzing {}...", path.display());
+
+        self.task = Some(AsyncComputeTaskPool::get().spawn(async move {
+            let bytes = fs::read(&path).map_err(|e| format!("Could not read audio: {e}"))?;
+            let pcm = Pcm::decode(&bytes).map_err(|e| format!("Could not decode audio: {e}"))?;
+
+            Ok(AudioAnalysis {
+                duration_ms: pcm.duration_ms(),
+                estimate: detect_bpm(&pcm.mono(), pcm.sample_rate),
+                bytes,
+            })
+        }));
+    }
+}
+
+pub fn open_wizard(mut actions: EventReader<EditorActionEvent>, mut wizard: ResMut<NewMapWizard>) {
+    for EditorActionEvent(action) in actions.read() {
+        if *action == EditorAction::NewMap {
+            wizard.open = true;
+        }
+    }
+}
+
+pub fn poll_analysis(mut wizard: ResMut<NewMapWizard>) {
+    let Some(result) = wizard
+        .task
+        .as_mut()
+        .and_then(|task| block_on(future::poll_once(task)))
+    else {
+        return;
+    };
+
+    wizard.task = None;
+
+    match result {
+        Ok(analysis) => {
+            wizard.status = match analysis.estimate {
+                Some(estimate) => {
+                    wizard.bpm = (estimate.bpm * 100.0).round() as f32 / 100.0;
+                    wizard.offset_ms = estimate.offset_ms.round() as u32;
+                    format!(
+                        "Detected {:.2} BPM ({:.0}% confidence)",
+                        estimate.bpm,
+                        estimate.confidence * 100.0
+                    )
+                }
+                None => "Could not detect a tempo, enter the BPM manually".to_string(),
+            };
+            wizard.analysis = Some(analysis);
+        }
+        Err(e) => wizard.status = e,
+    }
+}
+
+pub fn wizard_ui(
+    mut contexts: EguiContexts,
+    mut commands: Commands,
+    mut wizard: ResMut<NewMapWizard>,
+    mut maps: ResMut<Assets<Map>>,
+    mut selection: ResMut<Selection>,
+    mut history: ResMut<EditHistory>,
+    mut snap: ResMut<SnapSettings>,
+) -> Result {
+    if !wizard.open {
+        return Ok(());
+    }
+
+    let mut open = wizard.open;
+    let mut create = false;
+
+    egui::Window::new("New map")
+        .open(&mut open)
+        .resizable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            let wizard = wizard.as_mut();
+
+            egui::Grid::new("new_map_fields")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Audio file");
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut wizard.audio_path);
+                        let can_analyze = wizard.task.is_none() && !wizard.audio_path.is_empty();
+                        if ui
+                            .add_enabled(can_analyze, egui::Button::new("Analyze"))
+                            .clicked()
+                        {
+                            wizard.start_analysis();
+                        }
+                    });
+                    ui.end_row();
+
+                    ui.label("Title");
+                    ui.text_edit_singleline(&mut wizard.title);
+                    ui.end_row();
+
+                    ui.label("Artist");
+                    ui.text_edit_singleline(&mut wizard.artist);
+                    ui.end_row();
+
+                    ui.label("Mappers");
+                    ui.text_edit_singleline(&mut wizard.mappers)
+                        .on_hover_text("Comma separated");
+                    ui.end_row();
+
+                    ui.label("Difficulty");
+                    egui::ComboBox::from_id_salt("new_map_difficulty")
+                        .selected_text(DIFFICULTY_NAMES[wizard.difficulty as usize])
+                        .show_ui(ui, |ui| {
+                            for (i, name) in DIFFICULTY_NAMES.iter().enumerate() {
+                                ui.selectable_value(&mut wizard.difficulty, i as u8, *name);
+                            }
+                        });
+                    ui.end_row();
+
+                    ui.label("Difficulty name");
+                    ui.text_edit_singleline(&mut wizard.difficulty_name);
+                    ui.end_row();
+
+                    ui.label("BPM");
+                    ui.add(
+                        egui::DragValue::new(&mut wizard.bpm)
+                            .speed(0.1)
+                            .range(1.0..=1000.0),
+                    );
+                    ui.end_row();
+
+                    ui.label("Offset (ms)");
+                    ui.add(egui::DragValue::new(&mut wizard.offset_ms));
+                    ui.end_row();
+
+                    ui.label("Beats per measure");
+                    ui.add(egui::DragValue::new(&mut wizard.beats_per_measure).range(1..=16));
+                    ui.end_row();
+
+                    ui.label("Output folder");
+                    ui.text_edit_singleline(&mut wizard.output_folder);
+                    ui.end_row();
+                });
+
+            if !wizard.status.is_empty() {
+                ui.label(&wizard.status);
+            }
+
+            create = ui
+                .add_enabled(wizard.analysis.is_some(), egui::Button::new("Create map"))
+                .clicked();
+        });
+
+    wizard.open = open;
+
+    if !create {
+        return Ok(());
+    }
+
+    let map = match wizard.build_map() {
+        Ok(map) => map,
+        Err(e) => {
+            wizard.status = e;
+            return Ok(());
+        }
+    };
+
+    let path = PathBuf::from(wizard.output_folder.trim()).join(format!("{}.sspm", map.id));
+    let written = fs::create_dir_all(wizard.output_folder.trim())
+        .and_then(|_| File::create(&path))
+        .and_then(|file| SSPMSerializer::serialize(&map, BufWriter::new(file)));
+
+    if let Err(e) = written {
+        wizard.status = format!("Could not write {}: {e}", path.display());
+        return Ok(());
+    }
+
+    info!("Created new map at {}", path.display());
+
+    commands.insert_resource(CurrentMap(maps.add(map)));
+    selection.0.clear();
+    history.clear();
+    *snap = SnapSettings::default();
+    *wizard = NewMapWizard::default();
+
+    Ok(())
+}
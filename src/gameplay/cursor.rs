@@ -0,0 +1,191 @@
+use bevy::{input::mouse::AccumulatedMouseMotion, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    editor::cursor::GridCursor,
+    gameplay::{InputDevice, Session, pause::PauseMenu, playing},
+    player::playfield::{CELL_SIZE, GRID_CELLS, Playfield, grid_to_world},
+    settings::Settings,
+    theme::Theme,
+};
+
+// How far past the outer cells a raw cursor can go, in cells
+const EDGE: f32 = 1.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct CursorSettings {
+    // Moves by the mouse's own deltas instead of following the system cursor, which is locked
+    // in place while playing. Only for mice, tablets already map to the screen
+    pub raw_input: bool,
+    // Counts the mouse reports per inch, so the sensitivity feels the same on every mouse
+    pub dpi: f32,
+    // Cells the cursor moves per inch of mouse movement with raw input
+    pub sensitivity: f32,
+    // Half-life of the drawn cursor catching up with the judged one, 0 draws it as it is.
+    // Only changes what is seen, hits are always judged on the unsmoothed position
+    pub smoothing_ms: f32,
+}
+
+impl Default for CursorSettings {
+    fn default() -> Self {
+        Self {
+            raw_input: false,
+            dpi: 800.0,
+            sensitivity: 1.5,
+            smoothing_ms: 0.0,
+        }
+    }
+}
+
+impl CursorSettings {
+    // Grid cells moved for a mouse delta in counts, y points down on the grid and the screen
+    pub fn scale(&self, counts: Vec2) -> Vec2 {
+        counts / self.dpi.max(1.0) * self.sensitivity
+    }
+
+    // The system cursor can't be smoothed or moved by raw input, so it is hidden for one drawn
+    // on the playfield
+    pub fn draws_own(&self, input: InputDevice) -> bool {
+        self.raw(input) || self.smoothing_ms > 0.0
+    }
+
+    pub fn raw(&self, input: InputDevice) -> bool {
+        self.raw_input && input == InputDevice::Mouse
+    }
+}
+
+// Raw cursors stop a little past the grid so they can't be lost off screen
+pub fn clamp_to_playfield(position: Vec2) -> Vec2 {
+    let max = (GRID_CELLS - 1) as f32 + EDGE;
+    position.clamp(Vec2::splat(-EDGE), Vec2::splat(max))
+}
+
+// Frame rate independent easing towards `target`, halfway there every `smoothing_ms`
+pub fn smooth(shown: Vec2, target: Vec2, delta_ms: f32, smoothing_ms: f32) -> Vec2 {
+    if smoothing_ms <= 0.0 {
+        return target;
+    }
+    shown.lerp(target, 1.0 - 0.5f32.powf(delta_ms / smoothing_ms))
+}
+
+// The cursor while playing, in grid coordinates like `GridCursor`. Outside of sessions both
+// positions follow the system cursor
+#[derive(Resource, Default, Debug)]
+pub struct PlayCursor {
+    // What notes are judged against, straight from the input
+    pub judged: Option<Vec2>,
+    // What is drawn and leaves the trail
+    pub shown: Option<Vec2>,
+}
+
+#[derive(Component)]
+pub struct PlayCursorVisual;
+
+#[derive(Resource)]
+pub struct PlayCursorMaterial(Handle<StandardMaterial>);
+
+fn cursor_material(color: Color) -> StandardMaterial {
+    StandardMaterial {
+        base_color: color,
+        unlit: true,
+        ..default()
+    }
+}
+
+pub fn spawn_play_cursor(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    playfield: Query<Entity, With<Playfield>>,
+    theme: Res<Theme>,
+) {
+    let Ok(playfield) = playfield.single() else {
+        return;
+    };
+
+    let material = materials.add(cursor_material(theme.accent.color()));
+    commands.spawn((
+        PlayCursorVisual,
+        ChildOf(playfield),
+        Mesh3d(meshes.add(Circle::new(0.08 * CELL_SIZE))),
+        MeshMaterial3d(material.clone()),
+        Transform::default(),
+        Visibility::Hidden,
+    ));
+    commands.insert_resource(PlayCursorMaterial(material));
+}
+
+pub fn update_play_cursor(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    session: Option<Res<Session>>,
+    pause: Res<PauseMenu>,
+    motion: Res<AccumulatedMouseMotion>,
+    grid: Res<GridCursor>,
+    mut cursor: ResMut<PlayCursor>,
+) {
+    // Stays where it was while paused, so raw cursors don't jump when playing resumes
+    if !playing(session.as_deref(), &pause) {
+        if session.is_none() {
+            cursor.judged = grid.position;
+            cursor.shown = grid.position;
+        }
+        return;
+    }
+
+    let judged = match settings.cursor.raw(settings.input) {
+        true => {
+            let start = cursor.judged.or(grid.position).unwrap_or(Vec2::ONE);
+            Some(clamp_to_playfield(
+                start + settings.cursor.scale(motion.delta),
+            ))
+        }
+        false => grid.position,
+    };
+
+    cursor.shown = match (cursor.shown, judged) {
+        (Some(shown), Some(judged)) => Some(smooth(
+            shown,
+            judged,
+            time.delta_secs() * 1000.0,
+            settings.cursor.smoothing_ms,
+        )),
+        _ => judged,
+    };
+    cursor.judged = judged;
+}
+
+pub fn draw_play_cursor(
+    settings: Res<Settings>,
+    session: Option<Res<Session>>,
+    cursor: Res<PlayCursor>,
+    mut visual: Query<(&mut Transform, &mut Visibility), With<PlayCursorVisual>>,
+) {
+    let Ok((mut transform, mut visibility)) = visual.single_mut() else {
+        return;
+    };
+
+    let shown = cursor
+        .shown
+        .filter(|_| session.is_some() && settings.cursor.draws_own(settings.input));
+
+    let Some(shown) = shown else {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    };
+
+    // Just in front of the grid so it is never hidden behind it
+    transform.translation = grid_to_world(shown) + Vec3::Z * 0.01;
+    visibility.set_if_neq(Visibility::Visible);
+}
+
+pub fn apply_cursor_theme(
+    material: Option<Res<PlayCursorMaterial>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    theme: Res<Theme>,
+) {
+    if let Some(material) = material.and_then(|m| materials.get_mut(&m.0)) {
+        material.base_color = theme.accent.color();
+    }
+}
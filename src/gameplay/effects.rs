@@ -0,0 +1,323 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    gameplay::{
+        Session,
+        cursor::PlayCursor,
+        judgement::{Judgement, JudgementEvent},
+    },
+    maps::{CurrentMap, Map},
+    player::playfield::{CELL_SIZE, Playfield, grid_to_world},
+    settings::Settings,
+    theme::Theme,
+};
+
+// Oldest particles are reused once every slot is taken, so dense sections can't grow this
+const MAX_PARTICLES: usize = 2048;
+
+// At an intensity of 1
+const BURST_PARTICLES: f32 = 12.0;
+const RING_PARTICLES: f32 = 20.0;
+
+const BURST_LIFETIME: f32 = 0.4;
+const RING_LIFETIME: f32 = 0.3;
+const TRAIL_LIFETIME: f32 = 0.15;
+
+// World units per second, the ring spreads evenly while the burst slows down
+const RING_SPEED: f32 = 3.0;
+const BURST_SPEED: f32 = 4.0;
+const BURST_DRAG: f32 = 6.0;
+
+// Distance between trail particles, fast flicks are filled in up to the per frame limit
+const TRAIL_SPACING: f32 = 0.04 * CELL_SIZE;
+const MAX_TRAIL_PER_FRAME: usize = 32;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct EffectSettings {
+    // Scales how many particles a hit bursts into, 0 turns hit effects off
+    pub intensity: f32,
+    pub cursor_trail: bool,
+}
+
+impl Default for EffectSettings {
+    fn default() -> Self {
+        Self {
+            intensity: 1.0,
+            cursor_trail: true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Particle {
+    // Relative to the playfield, so effects follow it around when mods move it
+    position: Vec3,
+    velocity: Vec3,
+    drag: f32,
+    size: f32,
+    age: f32,
+    lifetime: f32,
+    trail: bool,
+}
+
+impl Particle {
+    fn alive(&self) -> bool {
+        self.age < self.lifetime
+    }
+}
+
+#[derive(Component)]
+pub struct ParticleVisual;
+
+#[derive(Resource)]
+pub struct EffectAssets {
+    hit: Handle<StandardMaterial>,
+    trail: Handle<StandardMaterial>,
+}
+
+// Every particle is a pooled entity with the same mesh and one of two materials, which Bevy
+// batches into instanced draws no matter how many are on screen
+#[derive(Resource, Default)]
+pub struct ParticlePool {
+    entities: Vec<Entity>,
+    particles: Vec<Particle>,
+    // Slot the next particle is written to, wrapping around to the oldest
+    next: usize,
+    last_cursor: Option<Vec3>,
+}
+
+impl ParticlePool {
+    fn emit(&mut self, particle: Particle) {
+        if self.particles.is_empty() {
+            return;
+        }
+        self.particles[self.next] = particle;
+        self.next = (self.next + 1) % self.particles.len();
+    }
+}
+
+// Cheap deterministic scatter in 0..1, hits don't need a real random source
+fn scatter(seed: u64) -> f32 {
+    let mut z = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    ((z ^ (z >> 31)) >> 40) as f32 / (1u64 << 24) as f32
+}
+
+fn effect_material(color: Color) -> StandardMaterial {
+    StandardMaterial {
+        base_color: color,
+        unlit: true,
+        alpha_mode: AlphaMode::Add,
+        ..default()
+    }
+}
+
+pub fn spawn_particle_pool(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut pool: ResMut<ParticlePool>,
+    playfield: Query<Entity, With<Playfield>>,
+    theme: Res<Theme>,
+) {
+    let Ok(playfield) = playfield.single() else {
+        return;
+    };
+
+    // A unit sphere scaled per particle, it looks the same from every camera angle
+    let mesh = meshes.add(Sphere::new(0.5).mesh().uv(8, 6));
+    let assets = EffectAssets {
+        hit: materials.add(effect_material(theme.hit.color())),
+        trail: materials.add(effect_material(theme.accent.color())),
+    };
+
+    for _ in 0..MAX_PARTICLES {
+        let entity = commands
+            .spawn((
+                ParticleVisual,
+                ChildOf(playfield),
+                Mesh3d(mesh.clone()),
+                MeshMaterial3d(assets.hit.clone()),
+                Transform::default(),
+                Visibility::Hidden,
+            ))
+            .id();
+        pool.entities.push(entity);
+    }
+    pool.particles = vec![Particle::default(); MAX_PARTICLES];
+
+    commands.insert_resource(assets);
+}
+
+pub fn apply_effect_theme(
+    assets: Option<Res<EffectAssets>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    theme: Res<Theme>,
+) {
+    let Some(assets) = assets else {
+        return;
+    };
+
+    for (handle, color) in [(&assets.hit, theme.hit), (&assets.trail, theme.accent)] {
+        if let Some(material) = materials.get_mut(handle) {
+            material.base_color = color.color();
+        }
+    }
+}
+
+// A burst of scattered sparks and an even ring spreading out from every hit note
+pub fn emit_hit_effects(
+    mut judgements: EventReader<JudgementEvent>,
+    mut pool: ResMut<ParticlePool>,
+    settings: Res<Settings>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+) {
+    let intensity = settings.effects.intensity;
+    let Some(map) = current_map.and_then(|m| maps.get(&m.0)) else {
+        judgements.clear();
+        return;
+    };
+    if intensity <= 0.0 {
+        judgements.clear();
+        return;
+    }
+
+    for event in judgements.read() {
+        let (Judgement::Hit { .. }, Some(note)) = (event.judgement, map.notes.get(event.note))
+        else {
+            continue;
+        };
+
+        let origin = grid_to_world(note.position);
+        let seed = (event.note as u64) << 16;
+
+        let burst = (BURST_PARTICLES * intensity).round() as usize;
+        for i in 0..burst {
+            let seed = seed + i as u64 * 3;
+            let angle = scatter(seed) * std::f32::consts::TAU;
+            let speed = BURST_SPEED * (0.4 + 0.6 * scatter(seed + 1));
+            let lift = scatter(seed + 2) * 0.5;
+            pool.emit(Particle {
+                position: origin,
+                velocity: Vec3::new(angle.cos(), angle.sin(), lift) * speed,
+                drag: BURST_DRAG,
+                size: 0.08 * CELL_SIZE,
+                age: 0.0,
+                lifetime: BURST_LIFETIME,
+                trail: false,
+            });
+        }
+
+        let ring = (RING_PARTICLES * intensity.min(1.0)).round().max(6.0) as usize;
+        for i in 0..ring {
+            let angle = i as f32 / ring as f32 * std::f32::consts::TAU;
+            pool.emit(Particle {
+                position: origin,
+                velocity: Vec3::new(angle.cos(), angle.sin(), 0.0) * RING_SPEED,
+                drag: 0.0,
+                size: 0.05 * CELL_SIZE,
+                age: 0.0,
+                lifetime: RING_LIFETIME,
+                trail: false,
+            });
+        }
+    }
+}
+
+// Particles are laid along the path the cursor moved since the last frame, so the trail stays
+// smooth at any frame rate instead of showing gaps on fast movements
+pub fn emit_cursor_trail(
+    mut pool: ResMut<ParticlePool>,
+    settings: Res<Settings>,
+    cursor: Res<PlayCursor>,
+    session: Option<Res<Session>>,
+) {
+    // Only while playing, the editor cursor has no use for one
+    let position = cursor
+        .shown
+        .filter(|_| settings.effects.cursor_trail && session.is_some())
+        .map(grid_to_world);
+
+    let Some(position) = position else {
+        pool.last_cursor = None;
+        return;
+    };
+    let last = pool.last_cursor.unwrap_or(position);
+
+    let distance = last.distance(position);
+    let steps = ((distance / TRAIL_SPACING) as usize).min(MAX_TRAIL_PER_FRAME);
+    for step in 1..=steps {
+        let t = step as f32 / steps as f32;
+        // Earlier points along the path have already been fading for part of the frame
+        pool.emit(Particle {
+            position: last.lerp(position, t),
+            velocity: Vec3::ZERO,
+            drag: 0.0,
+            size: 0.06 * CELL_SIZE,
+            age: (1.0 - t) * TRAIL_LIFETIME * 0.25,
+            lifetime: TRAIL_LIFETIME,
+            trail: true,
+        });
+    }
+
+    if steps > 0 || pool.last_cursor.is_none() {
+        pool.last_cursor = Some(position);
+    }
+}
+
+// Particles shrink instead of fading, so they can keep sharing materials
+pub fn update_particles(
+    time: Res<Time>,
+    assets: Option<Res<EffectAssets>>,
+    mut pool: ResMut<ParticlePool>,
+    mut visuals: Query<
+        (
+            &mut Transform,
+            &mut Visibility,
+            &mut MeshMaterial3d<StandardMaterial>,
+        ),
+        With<ParticleVisual>,
+    >,
+) {
+    let Some(assets) = assets else {
+        return;
+    };
+    let delta = time.delta_secs();
+    let pool = pool.as_mut();
+
+    for (entity, particle) in pool.entities.iter().zip(pool.particles.iter_mut()) {
+        if !particle.alive() {
+            continue;
+        }
+
+        let Ok((mut transform, mut visibility, mut material)) = visuals.get_mut(*entity) else {
+            continue;
+        };
+
+        particle.age += delta;
+        if !particle.alive() {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+
+        particle.position += particle.velocity * delta;
+        particle.velocity *= (1.0 - particle.drag * delta).max(0.0);
+
+        let remaining = 1.0 - particle.age / particle.lifetime;
+        *transform = Transform::from_translation(particle.position)
+            .with_scale(Vec3::splat(particle.size * remaining));
+        visibility.set_if_neq(Visibility::Inherited);
+
+        let handle = match particle.trail {
+            true => &assets.trail,
+            false => &assets.hit,
+        };
+        if material.0 != *handle {
+            material.0 = handle.clone();
+        }
+    }
+}
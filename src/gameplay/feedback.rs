@@ -0,0 +1,200 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    gameplay::{
+        Session,
+        judgement::{Judgement, JudgementEvent},
+    },
+    modchart::comfort::ComfortSettings,
+    settings::Settings,
+    theme::Theme,
+};
+
+// Seconds each effect takes to fade out
+const PUNCH_LIFETIME: f32 = 0.12;
+const FLASH_LIFETIME: f32 = 0.35;
+const MILESTONE_LIFETIME: f32 = 1.2;
+
+// World units the camera moves toward the playfield on a full punch
+const PUNCH_DISTANCE: f32 = 0.15;
+
+// Points the miss flash reaches in from the screen edges at full intensity
+const FLASH_WIDTH: f32 = 48.0;
+
+// The flash is drawn as bands that get fainter toward the middle of the screen
+const FLASH_BANDS: usize = 6;
+
+const MILESTONE_SIZE: f32 = 48.0;
+// How much bigger a milestone starts before settling, with full motion
+const MILESTONE_POP: f32 = 0.4;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct FeedbackSettings {
+    // Scales every effect, 0 turns them all off
+    pub intensity: f32,
+    // The camera punches in on hits in the tightest window
+    pub camera_punch: bool,
+    // The screen edges flash on misses
+    pub miss_flash: bool,
+    // Every this many combo is called out, 0 turns it off
+    pub milestone_every: u32,
+}
+
+impl Default for FeedbackSettings {
+    fn default() -> Self {
+        Self {
+            intensity: 1.0,
+            camera_punch: true,
+            miss_flash: true,
+            milestone_every: 100,
+        }
+    }
+}
+
+// How strongly each effect shows right now, from 1 when it's triggered down to 0
+#[derive(Resource, Default, Debug, Clone, PartialEq)]
+pub struct Feedback {
+    pub punch: f32,
+    pub flash: f32,
+    // Combo that was reached, with how much of its callout is left
+    pub milestone: Option<(u32, f32)>,
+    last_combo: u32,
+}
+
+impl Feedback {
+    pub fn judge(&mut self, judgement: Judgement, settings: &FeedbackSettings) {
+        if settings.intensity <= 0.0 {
+            return;
+        }
+
+        match judgement {
+            Judgement::Hit { window: 0, .. } if settings.camera_punch => self.punch = 1.0,
+            Judgement::Miss if settings.miss_flash => self.flash = 1.0,
+            _ => {}
+        }
+    }
+
+    // Called with the combo once a frame's judgements are in, so several hits in one frame
+    // can't skip past a milestone
+    pub fn track_combo(&mut self, combo: u32, settings: &FeedbackSettings) {
+        let every = settings.milestone_every;
+        if every > 0
+            && settings.intensity > 0.0
+            && combo > self.last_combo
+            && combo / every > self.last_combo / every
+        {
+            self.milestone = Some((combo / every * every, 1.0));
+        }
+        self.last_combo = combo;
+    }
+
+    pub fn tick(&mut self, delta: f32) {
+        self.punch = (self.punch - delta / PUNCH_LIFETIME).max(0.0);
+        self.flash = (self.flash - delta / FLASH_LIFETIME).max(0.0);
+        self.milestone = self
+            .milestone
+            .map(|(combo, left)| (combo, left - delta / MILESTONE_LIFETIME))
+            .filter(|(_, left)| *left > 0.0);
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.punch == 0.0 && self.flash == 0.0 && self.milestone.is_none()
+    }
+
+    // Moving the camera is what bothers motion sensitive players, so it follows the rotation
+    // comfort setting while the flash and callouts don't
+    pub fn camera_offset(&self, settings: &FeedbackSettings, comfort: &ComfortSettings) -> f32 {
+        // Eased out, the punch snaps in and settles back slowly
+        let eased = 1.0 - (1.0 - self.punch).powi(3);
+        eased * settings.intensity * comfort.rotation.motion_scale() * PUNCH_DISTANCE
+    }
+}
+
+pub fn update_feedback(
+    time: Res<Time>,
+    mut judgements: EventReader<JudgementEvent>,
+    session: Option<Res<Session>>,
+    settings: Res<Settings>,
+    mut feedback: ResMut<Feedback>,
+) {
+    let Some(session) = session else {
+        judgements.clear();
+        if *feedback != Feedback::default() {
+            *feedback = Feedback::default();
+        }
+        return;
+    };
+
+    // Only touched when something shows, so the camera isn't rebuilt every frame
+    if !feedback.is_idle() {
+        feedback.tick(time.delta_secs());
+    }
+
+    for event in judgements.read() {
+        feedback.judge(event.judgement, &settings.feedback);
+    }
+
+    if session.score.combo != feedback.last_combo {
+        feedback.track_combo(session.score.combo, &settings.feedback);
+    }
+}
+
+// Drawn behind every window, over the playfield
+pub fn feedback_ui(
+    mut contexts: EguiContexts,
+    feedback: Res<Feedback>,
+    settings: Res<Settings>,
+    theme: Res<Theme>,
+) -> Result {
+    if feedback.is_idle() {
+        return Ok(());
+    }
+
+    let intensity = settings.feedback.intensity.min(1.0);
+    let ctx = contexts.ctx_mut()?;
+
+    if feedback.flash > 0.0 {
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Background,
+            egui::Id::new("miss_flash"),
+        ));
+        let screen = ctx.screen_rect();
+        let color = theme.miss.egui();
+
+        for band in 0..FLASH_BANDS {
+            let t = band as f32 / FLASH_BANDS as f32;
+            let inset = FLASH_WIDTH * intensity * t;
+            let width = FLASH_WIDTH * intensity / FLASH_BANDS as f32;
+            let alpha = feedback.flash * intensity * (1.0 - t) * 0.5;
+            painter.rect_stroke(
+                screen.shrink(inset),
+                0.0,
+                egui::Stroke::new(width, color.gamma_multiply(alpha)),
+                egui::StrokeKind::Inside,
+            );
+        }
+    }
+
+    if let Some((combo, left)) = feedback.milestone {
+        let pop = 1.0 + left.powi(4) * MILESTONE_POP * settings.comfort.rotation.motion_scale();
+        let color = theme.accent.egui().gamma_multiply(left.min(1.0));
+
+        egui::Area::new(egui::Id::new("combo_milestone"))
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, -96.0))
+            .interactable(false)
+            .order(egui::Order::Background)
+            .show(ctx, |ui| {
+                ui.label(
+                    egui::RichText::new(format!("{combo} combo"))
+                        .size(MILESTONE_SIZE * pop)
+                        .strong()
+                        .color(color),
+                );
+            });
+    }
+
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    gameplay::{Session, cursor::PlayCursor},
+    maps::{CurrentMap, Map},
+    player::playback::PlaybackClock,
+    settings::Settings,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Judgement {
+    // Index of the ruleset window and how late the hit was, negative when early
+    Hit { window: usize, offset_ms: f32 },
+    Miss,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct JudgementEvent {
+    pub note: usize,
+    pub judgement: Judgement,
+}
+
+// Per note progress of the current session
+#[derive(Default, Debug)]
+pub struct NoteJudge {
+    // Every note before this one has been judged
+    next: usize,
+    judged: HashSet<usize>,
+    // Latest offset the cursor was over a note before its time
+    early: HashMap<usize, f32>,
+}
+
+impl NoteJudge {
+    pub fn starting_at(next: usize) -> Self {
+        Self { next, ..default() }
+    }
+
+    pub fn next(&self) -> usize {
+        self.next
+    }
+}
+
+// A note that is reached early is only judged once the cursor leaves it or its time comes, so
+// resting on a note isn't punished with the earliest possible timing
+pub fn judge_notes(
+    mut session: ResMut<Session>,
+    mut events: EventWriter<JudgementEvent>,
+    clock: Res<PlaybackClock>,
+    settings: Res<Settings>,
+    cursor: Res<PlayCursor>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+) {
+    let Some(map) = current_map.and_then(|m| maps.get(&m.0)) else {
+        return;
+    };
+
+    if session.end.is_some() {
+        return;
+    }
+
+    let session = session.as_mut();
+    let ruleset = &session.score.ruleset;
+    let judge = &mut session.judge;
+    let widest = ruleset.widest_window();
+    let now = clock.judged_ms(&settings);
+
+    let mut judgements = Vec::new();
+
+    for (index, note) in map.notes.iter().enumerate().skip(judge.next) {
+        let offset = (now - note.millisecond as f64) as f32;
+
+        if offset < -widest {
+            break;
+        }
+
+        if judge.judged.contains(&index) {
+            continue;
+        }
+
+        let over = cursor
+            .judged
+            .is_some_and(|cursor| ruleset.is_over_note(cursor, note.position));
+
+        let judgement = if over && offset >= 0.0 {
+            ruleset
+                .window_for(offset)
+                .map(|window| Judgement::Hit {
+                    window,
+                    offset_ms: offset,
+                })
+                .or((offset > widest).then_some(Judgement::Miss))
+        } else if over {
+            judge.early.insert(index, offset);
+            None
+        } else if let Some(early) = judge.early.remove(&index) {
+            ruleset.window_for(early).map(|window| Judgement::Hit {
+                window,
+                offset_ms: early,
+            })
+        } else {
+            (offset > widest).then_some(Judgement::Miss)
+        };
+
+        if let Some(judgement) = judgement {
+            judge.judged.insert(index);
+            judge.early.remove(&index);
+            judgements.push((index, judgement));
+        }
+    }
+
+    while judge.judged.remove(&judge.next) {
+        judge.next += 1;
+    }
+
+    for (note, judgement) in judgements {
+        session.record(judgement, now);
+        events.write(JudgementEvent { note, judgement });
+    }
+}
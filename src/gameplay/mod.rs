@@ -0,0 +1,258 @@
+use bevy::{
+    prelude::*,
+    window::{CursorGrabMode, PrimaryWindow},
+};
+use bevy_egui::EguiPrimaryContextPass;
+use serde::{Deserialize, Serialize};
+
+pub mod cursor;
+pub mod effects;
+pub mod feedback;
+pub mod judgement;
+pub mod pause;
+pub mod playtest;
+pub mod results;
+pub mod ruleset;
+pub mod score;
+
+use cursor::PlayCursor;
+use effects::ParticlePool;
+use feedback::Feedback;
+use judgement::{Judgement, JudgementEvent, NoteJudge};
+use pause::PauseMenu;
+use playtest::SessionExited;
+use ruleset::Ruleset;
+use score::Score;
+
+use crate::{
+    editor::cursor::update_grid_cursor,
+    maps::Map,
+    modchart::{random::ModRandom, update_mod_state},
+    player::{
+        SimulationState,
+        mods::Mods,
+        playback::{PlaybackClock, advance_clock, detect_map_end},
+        playfield::spawn_playfield,
+    },
+    settings::Settings,
+    theme::Theme,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InputDevice {
+    // Kept inside the window while playing, so fast flicks can't leave it
+    #[default]
+    Mouse,
+    // Absolute positioning already maps to the screen, the cursor is left free
+    Tablet,
+}
+
+impl InputDevice {
+    pub const ALL: [InputDevice; 2] = [InputDevice::Mouse, InputDevice::Tablet];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            InputDevice::Mouse => "Mouse",
+            InputDevice::Tablet => "Tablet",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SessionEnd {
+    Failed,
+    Cleared,
+}
+
+// A playthrough of the current map, notes are only judged while one exists
+#[derive(Resource, Debug)]
+pub struct Session {
+    pub score: Score,
+    // Where the session started, restarting seeks back here
+    pub start_ms: f64,
+    // From 0 to 1, failing at 0 unless playing with no fail
+    pub health: f32,
+    // Set once the session is over and its results are shown
+    pub end: Option<SessionEnd>,
+    // Map time the health ran out at
+    pub failed_ms: Option<f64>,
+    // Counts up with every restart, the first run is 0
+    pub attempt: u32,
+    judge: NoteJudge,
+}
+
+impl Session {
+    // Notes before `start_ms` are skipped, so sessions can start anywhere in the map
+    pub fn new(map: &Map, ruleset: Ruleset, mods: Mods, start_ms: f64) -> Self {
+        let first_note = map
+            .notes
+            .partition_point(|n| (n.millisecond as f64) < start_ms);
+
+        Self {
+            score: Score::new(map.id.clone(), ruleset, mods),
+            start_ms,
+            health: 1.0,
+            end: None,
+            failed_ms: None,
+            attempt: 0,
+            judge: NoteJudge::starting_at(first_note),
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.score.seed = seed;
+        self
+    }
+
+    // Restarts play out the same randomness as the run they restart
+    pub fn restarted(&self, map: &Map) -> Self {
+        Self {
+            attempt: self.attempt + 1,
+            ..Self::new(
+                map,
+                self.score.ruleset.clone(),
+                self.score.mods.clone(),
+                self.start_ms,
+            )
+            .with_seed(self.score.seed)
+        }
+    }
+
+    // Index of the first note that hasn't been judged yet
+    pub fn next_note(&self) -> usize {
+        self.judge.next()
+    }
+
+    pub fn record(&mut self, judgement: Judgement, millisecond: f64) {
+        self.score.add(judgement);
+
+        let ruleset = &self.score.ruleset;
+        self.health = match judgement {
+            Judgement::Hit { .. } => self.health + ruleset.hit_health,
+            Judgement::Miss => self.health - ruleset.miss_health,
+        }
+        .clamp(0.0, 1.0);
+
+        if self.health <= 0.0 && !self.score.mods.no_fail() && self.end.is_none() {
+            self.score.failed = true;
+            self.end = Some(SessionEnd::Failed);
+            self.failed_ms = Some(millisecond);
+        }
+    }
+}
+
+pub struct GameplayPlugin;
+
+impl Plugin for GameplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Ruleset>()
+            .init_resource::<PauseMenu>()
+            .init_resource::<ParticlePool>()
+            .init_resource::<Feedback>()
+            .init_resource::<PlayCursor>()
+            .add_event::<JudgementEvent>()
+            .add_event::<SessionExited>()
+            .add_systems(
+                Startup,
+                (
+                    effects::spawn_particle_pool.after(spawn_playfield),
+                    cursor::spawn_play_cursor.after(spawn_playfield),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    sync_ruleset,
+                    sync_playback_rate.before(advance_clock),
+                    sync_mod_seed.before(update_mod_state),
+                    (cursor::update_play_cursor, cursor::draw_play_cursor)
+                        .chain()
+                        .after(update_grid_cursor),
+                    judgement::judge_notes
+                        .after(advance_clock)
+                        .after(cursor::update_play_cursor)
+                        .run_if(resource_exists::<Session>)
+                        .run_if(in_state(SimulationState::Running)),
+                    (pause::pause_actions, pause::tick_countdown).chain(),
+                    results::stop_ended_session,
+                    results::finish_session.after(detect_map_end),
+                    (playtest::start_playtest, playtest::exit_session).chain(),
+                    (
+                        effects::emit_hit_effects.after(judgement::judge_notes),
+                        effects::emit_cursor_trail.after(cursor::update_play_cursor),
+                        effects::update_particles,
+                    )
+                        .chain(),
+                    feedback::update_feedback.after(judgement::judge_notes),
+                    (effects::apply_effect_theme, cursor::apply_cursor_theme)
+                        .run_if(resource_changed::<Theme>),
+                    confine_cursor,
+                ),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                (
+                    feedback::feedback_ui,
+                    pause::pause_menu_ui,
+                    results::health_bar_ui,
+                    results::results_ui,
+                ),
+            );
+    }
+}
+
+// Sessions play at the rate of their speed mod, editing always happens at normal speed
+pub fn sync_playback_rate(session: Option<Res<Session>>, mut clock: ResMut<PlaybackClock>) {
+    let rate = session.map_or(1.0, |session| session.score.mods.rate());
+    if clock.rate != rate {
+        clock.rate = rate;
+    }
+}
+
+// Sessions draw mod randomness from the seed in their score, so the run can be played back exactly
+pub fn sync_mod_seed(session: Option<Res<Session>>, mut random: ResMut<ModRandom>) {
+    let run = session.map(|session| session.score.seed);
+    if random.run != run {
+        random.run = run;
+    }
+}
+
+// The ruleset is chosen in the settings, sessions keep the one they were started with
+pub fn sync_ruleset(settings: Res<Settings>, mut ruleset: ResMut<Ruleset>) {
+    if settings.is_changed() && settings.ruleset != *ruleset {
+        *ruleset = settings.ruleset.clone();
+    }
+}
+
+pub fn confine_cursor(
+    session: Option<Res<Session>>,
+    pause: Res<PauseMenu>,
+    settings: Res<Settings>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let Ok(mut window) = windows.single_mut() else {
+        return;
+    };
+
+    // Released on the pause menu and results screen so they can be left with the mouse. Raw
+    // input only reads how the mouse moves, so the system cursor is held still instead
+    let active = playing(session.as_deref(), &pause);
+    let grab_mode = match active && settings.input == InputDevice::Mouse {
+        true if settings.cursor.raw(settings.input) => CursorGrabMode::Locked,
+        true => CursorGrabMode::Confined,
+        false => CursorGrabMode::None,
+    };
+    let visible = !(active && settings.cursor.draws_own(settings.input));
+
+    if window.cursor_options.grab_mode != grab_mode {
+        window.cursor_options.grab_mode = grab_mode;
+    }
+    if window.cursor_options.visible != visible {
+        window.cursor_options.visible = visible;
+    }
+}
+
+// Notes are judged and the cursor is held, not on the pause menu or once the session is over
+pub fn playing(session: Option<&Session>, pause: &PauseMenu) -> bool {
+    session.is_some_and(|session| session.end.is_none()) && !pause.open
+}
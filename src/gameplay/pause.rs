@@ -0,0 +1,188 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    editor::shortcuts::{EditorAction, EditorActionEvent},
+    gameplay::{
+        Session,
+        playtest::{Playtest, SessionExited},
+    },
+    maps::{CurrentMap, Map},
+    player::{SimulationState, playback::PlaybackClock},
+};
+
+const RESUME_COUNTDOWN_SECONDS: f32 = 3.0;
+
+// Skipping is offered when the next note is further away than this, and lands this long before it
+const SKIP_INTRO_MIN_MS: f64 = 5000.0;
+const SKIP_INTRO_LEAD_MS: f64 = 2000.0;
+
+#[derive(Resource, Default)]
+pub struct PauseMenu {
+    pub open: bool,
+    // Counts down before playback resumes so the player can get their cursor back in place
+    countdown: Option<Timer>,
+}
+
+impl PauseMenu {
+    fn resume(&mut self) {
+        self.countdown = Some(Timer::from_seconds(
+            RESUME_COUNTDOWN_SECONDS,
+            TimerMode::Once,
+        ));
+    }
+}
+
+enum MenuChoice {
+    Resume,
+    Restart,
+    Quit,
+}
+
+// Where skipping the intro would seek to, if the upcoming note is far enough away
+pub fn skip_intro_target(session: &Session, map: &Map, now: f64) -> Option<f64> {
+    let next = map.notes.get(session.next_note())?.millisecond as f64;
+    (next - now > SKIP_INTRO_MIN_MS).then_some(next - SKIP_INTRO_LEAD_MS)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn pause_actions(
+    mut actions: EventReader<EditorActionEvent>,
+    mut menu: ResMut<PauseMenu>,
+    session: Option<Res<Session>>,
+    simulation: Res<State<SimulationState>>,
+    mut next_simulation: ResMut<NextState<SimulationState>>,
+    mut clock: ResMut<PlaybackClock>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+) {
+    // The results screen takes over once the session has ended
+    let Some(session) = session.filter(|session| session.end.is_none()) else {
+        actions.clear();
+        return;
+    };
+
+    for EditorActionEvent(action) in actions.read() {
+        match action {
+            EditorAction::Pause if menu.open && menu.countdown.is_none() => menu.resume(),
+            EditorAction::Pause if menu.open => {}
+            EditorAction::Pause if *simulation.get() == SimulationState::Running => {
+                next_simulation.set(SimulationState::Paused);
+                menu.open = true;
+            }
+            EditorAction::SkipIntro if !menu.open => {
+                let target = current_map
+                    .as_ref()
+                    .and_then(|m| maps.get(&m.0))
+                    .and_then(|map| skip_intro_target(&session, map, clock.millisecond));
+
+                if let Some(target) = target {
+                    clock.seek(target);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn tick_countdown(
+    time: Res<Time>,
+    mut menu: ResMut<PauseMenu>,
+    mut next_simulation: ResMut<NextState<SimulationState>>,
+) {
+    let Some(countdown) = menu.countdown.as_mut() else {
+        return;
+    };
+
+    if countdown.tick(time.delta()).finished() {
+        menu.countdown = None;
+        menu.open = false;
+        next_simulation.set(SimulationState::Running);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn pause_menu_ui(
+    mut contexts: EguiContexts,
+    mut exited: EventWriter<SessionExited>,
+    mut menu: ResMut<PauseMenu>,
+    session: Option<ResMut<Session>>,
+    simulation: Res<State<SimulationState>>,
+    mut next_simulation: ResMut<NextState<SimulationState>>,
+    mut clock: ResMut<PlaybackClock>,
+    playtest: Option<Res<Playtest>>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+) -> Result {
+    let Some(mut session) = session.filter(|session| session.end.is_none()) else {
+        menu.open = false;
+        menu.countdown = None;
+        return Ok(());
+    };
+
+    let ctx = contexts.ctx_mut()?;
+    let map = current_map.and_then(|m| maps.get(&m.0));
+
+    if !menu.open {
+        if *simulation.get() == SimulationState::Running
+            && map.is_some_and(|map| skip_intro_target(&session, map, clock.millisecond).is_some())
+        {
+            egui::Area::new(egui::Id::new("skip_intro"))
+                .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -60.0))
+                .show(ctx, |ui| {
+                    ui.label("Press Enter to skip the intro");
+                });
+        }
+
+        return Ok(());
+    }
+
+    let mut choice = None;
+    let quit_label = match playtest {
+        Some(_) => "Back to editor",
+        None => "Quit to song select",
+    };
+
+    egui::Window::new("Paused")
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            if let Some(countdown) = menu.countdown.as_ref() {
+                let remaining = countdown.remaining_secs().ceil() as u32;
+                ui.heading(format!("Resuming in {remaining}"));
+                return;
+            }
+
+            ui.vertical_centered_justified(|ui| {
+                if ui.button("Resume").clicked() {
+                    choice = Some(MenuChoice::Resume);
+                }
+                if ui.button("Restart").clicked() {
+                    choice = Some(MenuChoice::Restart);
+                }
+                if ui.button(quit_label).clicked() {
+                    choice = Some(MenuChoice::Quit);
+                }
+            });
+        });
+
+    match choice {
+        Some(MenuChoice::Resume) => menu.resume(),
+        Some(MenuChoice::Restart) => {
+            if let Some(map) = map {
+                *session = session.restarted(map);
+                clock.seek(session.start_ms);
+            }
+            menu.open = false;
+            next_simulation.set(SimulationState::Running);
+        }
+        Some(MenuChoice::Quit) => {
+            exited.write(SessionExited);
+            menu.open = false;
+        }
+        None => {}
+    }
+
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+
+use crate::{
+    editor::{
+        camera::{CameraMode, EditorCamera, EditorCameraState},
+        library::LibraryPanel,
+        shortcuts::{EditorAction, EditorActionEvent},
+    },
+    gameplay::{Session, ruleset::Ruleset},
+    maps::{CurrentMap, Map},
+    modchart::random::fresh_seed,
+    player::{SimulationState, playback::PlaybackClock},
+    settings::Settings,
+};
+
+// Sent by the pause menu and results screen when the player leaves a session
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SessionExited;
+
+// A session started from the editor, leaving it returns to where editing stopped. Selection and
+// edit history are never touched by gameplay so they carry over as they were.
+#[derive(Resource, Debug)]
+pub struct Playtest {
+    pub return_ms: f64,
+    camera: CameraMode,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn start_playtest(
+    mut commands: Commands,
+    mut actions: EventReader<EditorActionEvent>,
+    session: Option<Res<Session>>,
+    clock: Res<PlaybackClock>,
+    ruleset: Res<Ruleset>,
+    settings: Res<Settings>,
+    mut next_simulation: ResMut<NextState<SimulationState>>,
+    mut camera_state: ResMut<EditorCameraState>,
+    mut camera: Query<(&mut Transform, &mut Projection), With<EditorCamera>>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+) {
+    let requested = actions
+        .read()
+        .any(|EditorActionEvent(action)| *action == EditorAction::PlaytestFromHere);
+
+    if !requested || session.is_some() {
+        return;
+    }
+
+    let Some(map) = current_map.and_then(|m| maps.get(&m.0)) else {
+        return;
+    };
+
+    let start_ms = clock.millisecond.max(0.0);
+    let mut session = Session::new(map, ruleset.clone(), settings.mods.clone(), start_ms);
+    if settings.reroll_random {
+        session = session.with_seed(fresh_seed());
+    }
+    commands.insert_resource(session);
+    commands.insert_resource(Playtest {
+        return_ms: clock.millisecond,
+        camera: camera_state.mode,
+    });
+
+    if camera_state.mode != CameraMode::Gameplay
+        && let Ok((mut transform, mut projection)) = camera.single_mut()
+    {
+        camera_state.switch(CameraMode::Gameplay, &mut transform, &mut projection);
+    }
+
+    next_simulation.set(SimulationState::Running);
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn exit_session(
+    mut commands: Commands,
+    mut exited: EventReader<SessionExited>,
+    playtest: Option<Res<Playtest>>,
+    mut clock: ResMut<PlaybackClock>,
+    mut next_simulation: ResMut<NextState<SimulationState>>,
+    mut library: ResMut<LibraryPanel>,
+    mut camera_state: ResMut<EditorCameraState>,
+    mut camera: Query<(&mut Transform, &mut Projection), With<EditorCamera>>,
+) {
+    if exited.read().count() == 0 {
+        return;
+    }
+
+    commands.remove_resource::<Session>();
+    next_simulation.set(SimulationState::Paused);
+
+    let Some(playtest) = playtest else {
+        library.open = true;
+        return;
+    };
+
+    commands.remove_resource::<Playtest>();
+    clock.seek(playtest.return_ms);
+
+    if camera_state.mode != playtest.camera
+        && let Ok((mut transform, mut projection)) = camera.single_mut()
+    {
+        camera_state.switch(playtest.camera, &mut transform, &mut projection);
+    }
+}
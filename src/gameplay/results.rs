@@ -0,0 +1,216 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    analysis::difficulty::DifficultyCurve,
+    editor::difficulty::{DifficultyGraph, paint_difficulty},
+    gameplay::{
+        Session, SessionEnd,
+        playtest::{Playtest, SessionExited},
+    },
+    maps::{CurrentMap, Map},
+    player::{
+        SimulationState,
+        playback::{MapFinished, PlaybackClock},
+    },
+};
+
+const HEALTH_BAR_WIDTH: f32 = 300.0;
+const GRAPH_WIDTH: f32 = 300.0;
+const GRAPH_HEIGHT: f32 = 60.0;
+
+pub fn stop_ended_session(
+    session: Option<Res<Session>>,
+    simulation: Res<State<SimulationState>>,
+    mut next_simulation: ResMut<NextState<SimulationState>>,
+) {
+    if session.is_some_and(|session| session.end.is_some())
+        && *simulation.get() == SimulationState::Running
+    {
+        next_simulation.set(SimulationState::Paused);
+    }
+}
+
+// Reaching the end of the map with health left clears it
+pub fn finish_session(mut finished: EventReader<MapFinished>, session: Option<ResMut<Session>>) {
+    if finished.read().count() == 0 {
+        return;
+    }
+
+    if let Some(mut session) = session.filter(|session| session.end.is_none()) {
+        session.end = Some(SessionEnd::Cleared);
+    }
+}
+
+pub fn health_bar_ui(mut contexts: EguiContexts, session: Option<Res<Session>>) -> Result {
+    let Some(session) = session.filter(|session| session.end.is_none()) else {
+        return Ok(());
+    };
+
+    egui::Area::new(egui::Id::new("health_bar"))
+        .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 12.0))
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.add(egui::ProgressBar::new(session.health).desired_width(HEALTH_BAR_WIDTH));
+
+            let mods = session.score.mods.summary();
+            if !mods.is_empty() {
+                ui.label(mods);
+            }
+        });
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn results_ui(
+    mut contexts: EguiContexts,
+    mut exited: EventWriter<SessionExited>,
+    session: Option<ResMut<Session>>,
+    mut clock: ResMut<PlaybackClock>,
+    mut next_simulation: ResMut<NextState<SimulationState>>,
+    playtest: Option<Res<Playtest>>,
+    graph: Res<DifficultyGraph>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+) -> Result {
+    let Some(mut session) = session else {
+        return Ok(());
+    };
+    let Some(end) = session.end else {
+        return Ok(());
+    };
+
+    let map = current_map.and_then(|m| maps.get(&m.0));
+    let score = &session.score;
+    let mut retry = false;
+    let mut quit = false;
+    let quit_label = match playtest {
+        Some(_) => "Back to editor",
+        None => "Song select",
+    };
+
+    egui::Window::new("Results")
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .collapsible(false)
+        .resizable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            match end {
+                SessionEnd::Failed => {
+                    ui.heading(egui::RichText::new("Failed").color(ui.visuals().error_fg_color))
+                }
+                SessionEnd::Cleared => ui.heading("Cleared"),
+            };
+
+            egui::Grid::new("results").num_columns(2).show(ui, |ui| {
+                ui.label("Points");
+                ui.label(score.points().to_string());
+                ui.end_row();
+
+                ui.label("Accuracy");
+                ui.label(format!("{:.2}%", score.accuracy() * 100.0));
+                ui.end_row();
+
+                ui.label("Max combo");
+                ui.label(score.max_combo.to_string());
+                ui.end_row();
+
+                for (window, hits) in score.ruleset.windows.iter().zip(score.hits.iter()) {
+                    ui.label(&window.name);
+                    ui.label(hits.to_string());
+                    ui.end_row();
+                }
+
+                ui.label("Misses");
+                ui.label(score.misses.to_string());
+                ui.end_row();
+
+                ui.label("Ruleset");
+                ui.label(&score.ruleset.name);
+                ui.end_row();
+
+                ui.label("Seed");
+                ui.label(format!("{:016x}", score.seed))
+                    .on_hover_text("Random mod effects are drawn from it");
+                ui.end_row();
+
+                let mods = score.mods.summary();
+                if !mods.is_empty() {
+                    ui.label("Mods");
+                    let multiplier = score.multiplier();
+                    if multiplier == 1.0 {
+                        ui.label(mods);
+                    } else {
+                        ui.label(format!("{mods} (x{multiplier})"));
+                    }
+                    ui.end_row();
+                }
+            });
+
+            if let (Some(curve), Some(map)) = (graph.curve.as_ref(), map) {
+                ui.separator();
+                difficulty_ui(ui, curve, session.start_ms, session.failed_ms, map);
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                retry = ui.button("Retry").clicked();
+                quit = ui.button(quit_label).clicked();
+            });
+        });
+
+    if retry && let Some(map) = map {
+        *session = session.restarted(map);
+        clock.seek(session.start_ms);
+        next_simulation.set(SimulationState::Running);
+    } else if quit {
+        exited.write(SessionExited);
+    }
+
+    Ok(())
+}
+
+// Where the map gets hard, with the part that was played highlighted and the point of failure marked
+fn difficulty_ui(
+    ui: &mut egui::Ui,
+    curve: &DifficultyCurve,
+    start_ms: f64,
+    failed_ms: Option<f64>,
+    map: &Map,
+) {
+    let length = map.length.max(map.last_object_ms()).max(1) as f64;
+    let (rect, _) =
+        ui.allocate_exact_size(egui::vec2(GRAPH_WIDTH, GRAPH_HEIGHT), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let x_at = |ms: f64| rect.left() + (ms / length).clamp(0.0, 1.0) as f32 * rect.width();
+
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+    paint_difficulty(
+        &painter,
+        rect,
+        curve,
+        length,
+        ui.visuals().selection.bg_fill,
+    );
+
+    // Sessions started from the editor may not cover the whole map
+    let dim = ui.visuals().extreme_bg_color.gamma_multiply(0.7);
+    let played = x_at(start_ms)..=x_at(failed_ms.unwrap_or(length));
+    painter.rect_filled(
+        egui::Rect::from_x_y_ranges(rect.left()..=*played.start(), rect.y_range()),
+        0.0,
+        dim,
+    );
+    painter.rect_filled(
+        egui::Rect::from_x_y_ranges(*played.end()..=rect.right(), rect.y_range()),
+        0.0,
+        dim,
+    );
+
+    if let Some(failed) = failed_ms {
+        let x = x_at(failed);
+        painter.line_segment(
+            [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+            egui::Stroke::new(2.0, ui.visuals().error_fg_color),
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HitWindow {
+    pub name: String,
+    // Largest distance from the note's time, early or late
+    pub milliseconds: f32,
+    // Contribution of a hit in this window to the accuracy, from 0 to 1
+    pub weight: f32,
+}
+
+impl HitWindow {
+    fn new(name: &str, milliseconds: f32, weight: f32) -> Self {
+        Self {
+            name: name.to_string(),
+            milliseconds,
+            weight,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RulesetPreset {
+    SoundSpace,
+    Strict,
+    Lenient,
+}
+
+impl RulesetPreset {
+    pub const ALL: [RulesetPreset; 3] = [
+        RulesetPreset::SoundSpace,
+        RulesetPreset::Strict,
+        RulesetPreset::Lenient,
+    ];
+
+    pub fn ruleset(&self) -> Ruleset {
+        match self {
+            // A single window, notes are either hit or missed
+            RulesetPreset::SoundSpace => Ruleset {
+                name: "Sound Space".to_string(),
+                windows: vec![HitWindow::new("Hit", 55.0, 1.0)],
+                hitbox: 1.14,
+                miss_weight: 0.0,
+                hit_health: 0.025,
+                miss_health: 0.2,
+                no_fail_multiplier: 0.5,
+            },
+            RulesetPreset::Strict => Ruleset {
+                name: "Strict".to_string(),
+                windows: vec![
+                    HitWindow::new("Perfect", 25.0, 1.0),
+                    HitWindow::new("Great", 45.0, 0.7),
+                    HitWindow::new("Good", 65.0, 0.4),
+                ],
+                hitbox: 1.0,
+                miss_weight: 0.0,
+                hit_health: 0.02,
+                miss_health: 0.25,
+                no_fail_multiplier: 0.5,
+            },
+            RulesetPreset::Lenient => Ruleset {
+                name: "Lenient".to_string(),
+                windows: vec![
+                    HitWindow::new("Perfect", 50.0, 1.0),
+                    HitWindow::new("Good", 100.0, 0.75),
+                ],
+                hitbox: 1.3,
+                miss_weight: 0.0,
+                hit_health: 0.04,
+                miss_health: 0.1,
+                no_fail_multiplier: 0.5,
+            },
+        }
+    }
+}
+
+// How notes are judged. Results keep a copy of the ruleset they were played with, and two results
+// can only be compared when those are identical.
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Ruleset {
+    pub name: String,
+    // Sorted from the tightest to the widest, a note is missed once the widest has passed
+    pub windows: Vec<HitWindow>,
+    // Side of the square around a note the cursor has to be inside of, in cells
+    pub hitbox: f32,
+    // Contribution of a miss to the accuracy, below zero to punish misses harder
+    pub miss_weight: f32,
+    // Fraction of the health bar gained per hit and lost per miss
+    pub hit_health: f32,
+    pub miss_health: f32,
+    // Points are multiplied by this when playing with no fail
+    pub no_fail_multiplier: f32,
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        RulesetPreset::SoundSpace.ruleset()
+    }
+}
+
+impl Ruleset {
+    // Which preset this is, None once any value was changed by hand
+    pub fn preset(&self) -> Option<RulesetPreset> {
+        RulesetPreset::ALL
+            .into_iter()
+            .find(|preset| preset.ruleset() == *self)
+    }
+
+    pub fn widest_window(&self) -> f32 {
+        self.windows
+            .iter()
+            .map(|window| window.milliseconds)
+            .fold(0.0, f32::max)
+    }
+
+    // Index of the tightest window containing a hit this far from the note's time
+    pub fn window_for(&self, offset_ms: f32) -> Option<usize> {
+        self.windows
+            .iter()
+            .position(|window| offset_ms.abs() <= window.milliseconds)
+    }
+
+    pub fn is_over_note(&self, cursor: Vec2, note: Vec2) -> bool {
+        (cursor - note).abs().max_element() <= self.hitbox / 2.0
+    }
+}
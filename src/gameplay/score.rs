@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    gameplay::{judgement::Judgement, ruleset::Ruleset},
+    modchart::random::map_seed,
+    player::mods::Mods,
+};
+
+// Points for a note hit in a window with a weight of 1
+const POINTS_PER_NOTE: f32 = 100.0;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Score {
+    pub map_id: String,
+    pub ruleset: Ruleset,
+    pub mods: Mods,
+    // Hits per window of the ruleset
+    pub hits: Vec<u32>,
+    pub misses: u32,
+    pub combo: u32,
+    pub max_combo: u32,
+    pub failed: bool,
+    // Mods with randomness draw from this, see modchart::random. Scores from before there was a
+    // seed read as 0
+    #[serde(default)]
+    pub seed: u64,
+}
+
+impl Score {
+    // Starts with the seed of the map, runs that roll again replace it
+    pub fn new(map_id: String, ruleset: Ruleset, mods: Mods) -> Self {
+        Self {
+            seed: map_seed(&map_id),
+            map_id,
+            hits: vec![0; ruleset.windows.len()],
+            ruleset,
+            mods,
+            misses: 0,
+            combo: 0,
+            max_combo: 0,
+            failed: false,
+        }
+    }
+
+    pub fn add(&mut self, judgement: Judgement) {
+        match judgement {
+            Judgement::Hit { window, .. } => {
+                if let Some(hits) = self.hits.get_mut(window) {
+                    *hits += 1;
+                }
+                self.combo += 1;
+                self.max_combo = self.max_combo.max(self.combo);
+            }
+            Judgement::Miss => {
+                self.misses += 1;
+                self.combo = 0;
+            }
+        }
+    }
+
+    pub fn judged(&self) -> u32 {
+        self.hits.iter().sum::<u32>() + self.misses
+    }
+
+    // Weighted by the ruleset, 1 when every note was hit in the tightest window
+    pub fn accuracy(&self) -> f32 {
+        let judged = self.judged();
+        if judged == 0 {
+            return 1.0;
+        }
+
+        let weighted: f32 = self
+            .hits
+            .iter()
+            .zip(self.ruleset.windows.iter())
+            .map(|(hits, window)| *hits as f32 * window.weight)
+            .sum::<f32>()
+            + self.misses as f32 * self.ruleset.miss_weight;
+
+        (weighted / judged as f32).clamp(0.0, 1.0)
+    }
+
+    pub fn multiplier(&self) -> f32 {
+        match self.mods.no_fail() {
+            true => self.ruleset.no_fail_multiplier,
+            false => 1.0,
+        }
+    }
+
+    pub fn points(&self) -> u32 {
+        let weighted: f32 = self
+            .hits
+            .iter()
+            .zip(self.ruleset.windows.iter())
+            .map(|(hits, window)| *hits as f32 * window.weight)
+            .sum();
+
+        (weighted * POINTS_PER_NOTE * self.multiplier()).round() as u32
+    }
+
+    // Scores played under different rules measure different things, so they are never ranked together
+    pub fn is_comparable(&self, other: &Score) -> bool {
+        self.map_id == other.map_id && self.ruleset == other.ruleset
+    }
+}
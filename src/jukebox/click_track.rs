@@ -0,0 +1,215 @@
+use std::{
+    f32::consts::TAU,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use crate::{
+    jukebox::pcm::Pcm,
+    maps::{Map, export::template_filename},
+};
+
+// Rate isolated tracks are rendered at when the map has no audio to match
+const SAMPLE_RATE: u32 = 44100;
+const CLICK_MS: f64 = 40.0;
+// Note clicks are high and metronome ticks low, so both can be told apart
+const NOTE_PITCH: f32 = 2000.0;
+const BEAT_PITCH: f32 = 1000.0;
+// Metronome ticks sit under the note clicks
+const BEAT_GAIN: f32 = 0.5;
+// Isolated tracks without audio run this long past the last note
+const TAIL_MS: f64 = 1000.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClickTrackMode {
+    // Clicks mixed over the song, turned down so they cut through
+    OverSong,
+    // Clicks alone, as long as the song so it lines up when layered in a DAW
+    Isolated,
+}
+
+impl ClickTrackMode {
+    pub const ALL: [ClickTrackMode; 2] = [ClickTrackMode::OverSong, ClickTrackMode::Isolated];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ClickTrackMode::OverSong => "Over the song",
+            ClickTrackMode::Isolated => "Clicks only",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClickTrackFormat {
+    Wav,
+    // Needs ffmpeg on the path, like webm clips
+    Ogg,
+}
+
+impl ClickTrackFormat {
+    pub const ALL: [ClickTrackFormat; 2] = [ClickTrackFormat::Wav, ClickTrackFormat::Ogg];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ClickTrackFormat::Wav => "WAV",
+            ClickTrackFormat::Ogg => "Ogg Vorbis",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ClickTrackFormat::Wav => "wav",
+            ClickTrackFormat::Ogg => "ogg",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClickTrackOptions {
+    pub mode: ClickTrackMode,
+    pub format: ClickTrackFormat,
+    // Also ticks every beat of the timing points
+    pub metronome: bool,
+    pub song_volume: f32,
+}
+
+impl Default for ClickTrackOptions {
+    fn default() -> Self {
+        Self {
+            mode: ClickTrackMode::OverSong,
+            format: ClickTrackFormat::Wav,
+            metronome: false,
+            song_volume: 0.6,
+        }
+    }
+}
+
+fn click(t: f32, pitch: f32) -> f32 {
+    (TAU * pitch * t).sin() * (-t * 90.0).exp()
+}
+
+// Every beat of the timing points up to `end_ms`, each point counts from its own start
+pub fn beat_times(map: &Map, end_ms: f64) -> Vec<f64> {
+    let mut beats = Vec::new();
+
+    for (index, timing) in map.timing_points.iter().enumerate() {
+        let until = map
+            .timing_points
+            .get(index + 1)
+            .map_or(end_ms, |next| (next.millisecond as f64).min(end_ms));
+        let step = timing.beat_length();
+        if !step.is_finite() || step <= 0.0 {
+            continue;
+        }
+
+        let mut beat = 0;
+        loop {
+            let millisecond = timing.millisecond as f64 + beat as f64 * step;
+            if millisecond >= until {
+                break;
+            }
+            beats.push(millisecond);
+            beat += 1;
+        }
+    }
+
+    beats
+}
+
+fn add_click(track: &mut Pcm, millisecond: f64, pitch: f32, gain: f32) {
+    let channels = track.channels.max(1) as usize;
+    let rate = track.sample_rate as f64;
+    let start = (millisecond.max(0.0) / 1000.0 * rate) as usize;
+    let length = (CLICK_MS / 1000.0 * rate) as usize;
+
+    for frame in 0..length.min(track.frames().saturating_sub(start)) {
+        let value = click(frame as f32 / rate as f32, pitch) * gain;
+        let at = (start + frame) * channels;
+        for sample in track.samples[at..at + channels].iter_mut() {
+            *sample += value;
+        }
+    }
+}
+
+// The map's rhythm as audio, a click on every note time. `song` is the decoded map audio, the
+// track matches its rate and length when there is one
+pub fn render_click_track(map: &Map, song: Option<&Pcm>, options: &ClickTrackOptions) -> Pcm {
+    let last_note = map.notes.iter().map(|n| n.millisecond).max().unwrap_or(0) as f64;
+
+    let mut track = match (options.mode, song) {
+        (ClickTrackMode::OverSong, Some(song)) => {
+            let mut track = song.clone();
+            let volume = options.song_volume.clamp(0.0, 1.0);
+            track
+                .samples
+                .iter_mut()
+                .for_each(|sample| *sample *= volume);
+            track
+        }
+        (_, Some(song)) => Pcm::silence(song.sample_rate, song.channels, song.duration_ms() as f64),
+        (_, None) => Pcm::silence(SAMPLE_RATE, 1, last_note + TAIL_MS),
+    };
+
+    if options.metronome {
+        for beat in beat_times(map, track.duration_ms() as f64) {
+            add_click(&mut track, beat, BEAT_PITCH, BEAT_GAIN);
+        }
+    }
+
+    // Chords are a single click, stacking them would only clip
+    let mut times: Vec<u32> = map.notes.iter().map(|note| note.millisecond).collect();
+    times.sort_unstable();
+    times.dedup();
+    for millisecond in times {
+        add_click(&mut track, millisecond as f64, NOTE_PITCH, 1.0);
+    }
+
+    track
+}
+
+// Next to the other exports, named after the map like they are
+pub fn click_track_path(
+    map: &Map,
+    folder: &Path,
+    template: &str,
+    format: ClickTrackFormat,
+) -> PathBuf {
+    let extension = format.extension();
+    let name = template_filename(map, template, extension);
+    let name = name.strip_suffix(&format!(".{extension}")).unwrap_or(&name);
+    folder.join(format!("{name} (clicks).{extension}"))
+}
+
+pub fn write_click_track(track: &Pcm, format: ClickTrackFormat, path: &Path) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    match format {
+        ClickTrackFormat::Wav => fs::write(path, track.to_wav()),
+        // The WAV is piped into ffmpeg rather than bundling a Vorbis encoder
+        ClickTrackFormat::Ogg => {
+            let mut ffmpeg = Command::new("ffmpeg")
+                .args(["-y", "-loglevel", "error", "-f", "wav", "-i", "-"])
+                .args(["-c:a", "libvorbis", "-q:a", "5"])
+                .arg(path)
+                .stdin(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .map_err(|e| io::Error::new(e.kind(), format!("Could not start ffmpeg: {e}")))?;
+
+            if let Some(mut stdin) = ffmpeg.stdin.take() {
+                stdin.write_all(&track.to_wav())?;
+            }
+
+            let output = ffmpeg.wait_with_output()?;
+            if !output.status.success() {
+                let error = String::from_utf8_lossy(&output.stderr);
+                return Err(io::Error::other(format!("ffmpeg failed: {}", error.trim())));
+            }
+            Ok(())
+        }
+    }
+}
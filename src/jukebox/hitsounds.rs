@@ -0,0 +1,138 @@
+use std::f32::consts::TAU;
+
+use bevy::{audio::Volume, prelude::*};
+
+use crate::{
+    jukebox::Clip,
+    maps::{CurrentMap, Map},
+    player::{
+        SimulationState,
+        playback::{PlaybackClock, advance_clock},
+    },
+    settings::Settings,
+};
+
+const SAMPLE_RATE: u32 = 44100;
+
+// Names of the built in samples, a hitsound's sample index points into this list
+pub const SAMPLE_NAMES: [&str; 4] = ["Click", "Tick", "Kick", "Snare"];
+
+// Jumps larger than this are seeks rather than playback, the notes skipped over stay silent
+const MAX_STEP_MS: f64 = 250.0;
+
+#[derive(Resource, Default)]
+pub struct HitsoundBank(pub Vec<Handle<Clip>>);
+
+#[derive(Resource, Default)]
+pub struct HitsoundPlayer {
+    last_position: Option<f64>,
+}
+
+pub struct HitsoundPlugin;
+
+impl Plugin for HitsoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HitsoundBank>()
+            .init_resource::<HitsoundPlayer>()
+            .add_systems(Startup, generate_samples)
+            .add_systems(Update, play_hitsounds.after(advance_clock));
+    }
+}
+
+pub fn sample_name(sample: u8) -> String {
+    match SAMPLE_NAMES.get(sample as usize) {
+        Some(name) => name.to_string(),
+        None => format!("Sample {sample}"),
+    }
+}
+
+// Mono clip of the given length, filled by a function of the time in seconds
+fn synthesize(length_ms: u32, sample: impl Fn(f32) -> f32) -> Clip {
+    let count = (SAMPLE_RATE * length_ms / 1000) as usize;
+
+    Clip {
+        sample_rate: SAMPLE_RATE,
+        channels: 1,
+        samples: (0..count)
+            .map(|i| sample(i as f32 / SAMPLE_RATE as f32).clamp(-1.0, 1.0))
+            .collect(),
+    }
+}
+
+// Deterministic white noise so the samples sound the same every run
+fn noise(t: f32) -> f32 {
+    let mut x = (t * SAMPLE_RATE as f32) as u32 ^ 0x9E37_79B9;
+    x ^= x << 13;
+    x ^= x >> 17;
+    x ^= x << 5;
+    x as f32 / u32::MAX as f32 * 2.0 - 1.0
+}
+
+// The samples are generated rather than shipped so hitsounds work without any asset files
+pub fn generate_samples(mut bank: ResMut<HitsoundBank>, mut clips: ResMut<Assets<Clip>>) {
+    let samples = [
+        synthesize(25, |t| noise(t) * (-t * 300.0).exp() * 0.8),
+        synthesize(50, |t| (TAU * 2000.0 * t).sin() * (-t * 90.0).exp() * 0.7),
+        synthesize(200, |t| {
+            // Pitch falls from 160Hz to 50Hz, the phase is the integral of that sweep
+            let phase = 50.0 * t + 110.0 / 30.0 * (1.0 - (-t * 30.0).exp());
+            (TAU * phase).sin() * (-t * 15.0).exp()
+        }),
+        synthesize(150, |t| {
+            ((TAU * 180.0 * t).sin() * 0.4 + noise(t) * 0.6) * (-t * 30.0).exp() * 0.8
+        }),
+    ];
+
+    bank.0 = samples.into_iter().map(|clip| clips.add(clip)).collect();
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn play_hitsounds(
+    mut commands: Commands,
+    bank: Res<HitsoundBank>,
+    mut player: ResMut<HitsoundPlayer>,
+    clock: Res<PlaybackClock>,
+    simulation: Res<State<SimulationState>>,
+    settings: Res<Settings>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+) {
+    // Played early by the audio offset like the song, so both stay in step
+    let now = clock.millisecond + settings.audio_offset_ms as f64;
+    let previous = player.last_position.replace(now);
+
+    if *simulation.get() != SimulationState::Running {
+        return;
+    }
+
+    let Some(previous) = previous.filter(|p| now >= *p && now - *p <= MAX_STEP_MS) else {
+        return;
+    };
+
+    let Some(map) = current_map.and_then(|m| maps.get(&m.0)) else {
+        return;
+    };
+
+    let start = map
+        .notes
+        .partition_point(|n| n.millisecond as f64 <= previous);
+    let end = map.notes.partition_point(|n| n.millisecond as f64 <= now);
+
+    // Chords would stack into clipping, each sample plays once per frame at the loudest volume
+    let mut volumes = vec![0.0f32; bank.0.len()];
+
+    for hitsound in map.notes[start..end].iter().filter_map(|n| n.hitsound) {
+        if let Some(volume) = volumes.get_mut(hitsound.sample as usize) {
+            *volume = volume.max(hitsound.volume);
+        }
+    }
+
+    for (clip, volume) in bank.0.iter().zip(volumes) {
+        if volume > 0.0 {
+            commands.spawn((
+                AudioPlayer(clip.clone()),
+                PlaybackSettings::DESPAWN.with_volume(Volume::Linear(volume)),
+            ));
+        }
+    }
+}
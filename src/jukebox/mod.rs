@@ -0,0 +1,232 @@
+use std::{io, sync::Arc};
+
+use bevy::{
+    audio::Decodable,
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, Task, block_on, futures_lite::future},
+};
+use rodio::buffer::SamplesBuffer;
+
+pub mod click_track;
+pub mod hitsounds;
+pub mod output;
+pub mod pcm;
+pub mod scrub;
+pub mod song;
+pub mod stream;
+pub mod stretch;
+
+use output::{AudioOutput, OutputStreams};
+use pcm::Pcm;
+use song::SongAudio;
+
+use crate::maps::{CurrentMap, Map};
+
+pub struct Jukebox;
+
+// A ready to play snippet of audio generated or decoded by the app itself
+#[derive(Asset, TypePath)]
+pub struct Clip {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub samples: Vec<f32>,
+}
+
+impl Decodable for Clip {
+    type DecoderItem = f32;
+    type Decoder = SamplesBuffer<f32>;
+
+    fn decoder(&self) -> Self::Decoder {
+        SamplesBuffer::new(self.channels, self.sample_rate, self.samples.clone())
+    }
+}
+
+// Compressed audio larger than this is streamed while it plays, and only decoded in full once
+// something needs random access to its samples
+pub const STREAM_THRESHOLD: usize = 16 * 1024 * 1024;
+
+// The current map's audio decoded once in the background, scrubbing and seeking need random
+// access to samples which the compressed audio doesn't give
+#[derive(Resource, Default)]
+pub struct DecodedAudio {
+    pub pcm: Option<Arc<Pcm>>,
+    // Played straight from the map's bytes until something asks for the samples
+    pub streamed: bool,
+    bytes: Option<Arc<[u8]>>,
+    // Measured without keeping the samples when the audio is streamed
+    duration_ms: Option<u32>,
+    wanted: bool,
+    // Not retried, scrubbing would otherwise start a new decode on every event
+    failed: bool,
+    source: Option<AssetId<Map>>,
+    task: Option<Task<io::Result<Pcm>>>,
+    measure: Option<Task<io::Result<u32>>>,
+}
+
+impl DecodedAudio {
+    // Also true while the length of streamed audio is still being measured
+    pub fn is_decoding(&self) -> bool {
+        self.task.is_some() || self.measure.is_some()
+    }
+
+    // Decodes streamed audio in full, does nothing once it is decoded or decoding
+    pub fn request_pcm(&mut self) {
+        if self.pcm.is_none() && self.task.is_none() && !self.failed {
+            self.wanted = true;
+        }
+    }
+
+    pub fn duration_ms(&self) -> Option<u32> {
+        self.pcm
+            .as_ref()
+            .map(|pcm| pcm.duration_ms())
+            .or(self.duration_ms)
+    }
+
+    // The decoded samples when there are some, the compressed bytes for streamed audio before that
+    pub fn song_audio(&self) -> Option<SongAudio> {
+        match (&self.pcm, &self.bytes) {
+            (Some(pcm), _) => Some(SongAudio::Decoded(pcm.clone())),
+            (None, Some(bytes)) if self.streamed => Some(SongAudio::Streamed(bytes.clone())),
+            _ => None,
+        }
+    }
+}
+
+pub struct JukeboxPlugin;
+
+impl Plugin for JukeboxPlugin {
+    fn build(&self, app: &mut App) {
+        // Registered once here, every audio source type gets its own playback system
+        app.init_asset::<Clip>()
+            .init_asset::<song::Song>()
+            .init_resource::<DecodedAudio>()
+            .init_resource::<AudioOutput>()
+            .insert_non_send_resource(OutputStreams::default())
+            .add_systems(Startup, output::open_default_output)
+            .add_systems(Update, (decode_current_audio, poll_decode).chain())
+            .add_systems(
+                PostUpdate,
+                (
+                    output::watch_output_devices,
+                    (
+                        output::play_queued::<Clip>,
+                        output::play_queued::<song::Song>,
+                    ),
+                    output::finish_sinks,
+                )
+                    .chain()
+                    .after(song::follow_clock),
+            )
+            .add_plugins((
+                scrub::ScrubPlugin,
+                hitsounds::HitsoundPlugin,
+                song::SongPlugin,
+            ));
+    }
+}
+
+pub fn decode_current_audio(
+    mut decoded: ResMut<DecodedAudio>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+) {
+    let Some(current_map) = current_map else {
+        return;
+    };
+
+    let id = current_map.0.id();
+    if decoded.source != Some(id) {
+        let Some(map) = maps.get(id) else {
+            return;
+        };
+
+        let bytes = map.audio.as_ref().map(|audio| audio.bytes.clone());
+        let streamed = bytes.as_ref().is_some_and(|b| b.len() > STREAM_THRESHOLD);
+        let measure = bytes.clone().filter(|_| streamed).map(|bytes| {
+            AsyncComputeTaskPool::get().spawn(async move {
+                info_span!("measure_audio", bytes = bytes.len())
+                    .in_scope(|| stream::duration_ms(bytes))
+            })
+        });
+
+        *decoded = DecodedAudio {
+            streamed,
+            wanted: !streamed,
+            bytes,
+            source: Some(id),
+            measure,
+            ..default()
+        };
+    }
+
+    if !decoded.wanted {
+        return;
+    }
+    decoded.wanted = false;
+
+    decoded.task = decoded.bytes.clone().map(|bytes| {
+        AsyncComputeTaskPool::get().spawn(async move {
+            info_span!("decode_audio", bytes = bytes.len()).in_scope(|| Pcm::decode_shared(bytes))
+        })
+    });
+}
+
+pub fn poll_decode(mut decoded: ResMut<DecodedAudio>) {
+    if let Some(result) = decoded
+        .measure
+        .as_mut()
+        .and_then(|task| block_on(future::poll_once(task)))
+    {
+        decoded.measure = None;
+
+        match result {
+            Ok(duration) => decoded.duration_ms = Some(duration),
+            Err(e) => warn!("Could not measure the map audio: {e}"),
+        }
+    }
+
+    let Some(result) = decoded
+        .task
+        .as_mut()
+        .and_then(|task| block_on(future::poll_once(task)))
+    else {
+        return;
+    };
+
+    decoded.task = None;
+
+    match result {
+        Ok(pcm) => decoded.pcm = Some(Arc::new(pcm)),
+        Err(e) => {
+            warn!("Could not decode the map audio: {e}");
+            decoded.failed = true;
+        }
+    }
+}
+
+// Guesses the container of raw audio bytes from their magic number
+pub fn audio_extension(bytes: &[u8]) -> Option<&'static str> {
+    match bytes {
+        [b'I', b'D', b'3', ..] => Some("mp3"),
+        [0xFF, second, ..] if second & 0xE0 == 0xE0 => Some("mp3"),
+        [b'O', b'g', b'g', b'S', ..] => Some("ogg"),
+        [
+            b'R',
+            b'I',
+            b'F',
+            b'F',
+            _,
+            _,
+            _,
+            _,
+            b'W',
+            b'A',
+            b'V',
+            b'E',
+            ..,
+        ] => Some("wav"),
+        [b'f', b'L', b'a', b'C', ..] => Some("flac"),
+        _ => None,
+    }
+}
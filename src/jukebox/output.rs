@@ -0,0 +1,253 @@
+use std::time::Duration;
+
+use bevy::{
+    audio::{Decodable, PlaybackMode},
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, Task, block_on, futures_lite::future},
+};
+use rodio::{
+    OutputStream, OutputStreamHandle, Sink, Source,
+    cpal::{
+        self,
+        traits::{DeviceTrait, HostTrait},
+    },
+};
+
+use crate::{player::SimulationState, settings::Settings};
+
+// Listing devices can take a while on some backends, so it happens in the background this often
+const SCAN_INTERVAL: Duration = Duration::from_millis(500);
+
+// The app plays its own sounds instead of going through bevy's output, which opens the default
+// device once and goes quiet for good when that device is unplugged
+#[derive(Default)]
+pub struct OutputStreams {
+    // Dropping the stream closes the device
+    stream: Option<(OutputStream, OutputStreamHandle)>,
+}
+
+#[derive(Resource, Default)]
+pub struct AudioOutput {
+    // Names of the output devices found by the last scan
+    pub devices: Vec<String>,
+    // Device that is playing, None while there isn't one
+    pub device: Option<String>,
+    // Bumped whenever the output is opened again, sinks from before are silent
+    pub generation: u32,
+    // Playback was paused because the device went away and continues once there is one again
+    resume: bool,
+    scan: Option<Task<DeviceScan>>,
+    next_scan: Duration,
+}
+
+struct DeviceScan {
+    default: Option<String>,
+    devices: Vec<String>,
+}
+
+// A sound playing on the app's output, made for the AudioPlayer it is on
+#[derive(Component)]
+pub struct OutputSink {
+    sink: Sink,
+    generation: u32,
+}
+
+fn scan_devices() -> DeviceScan {
+    let host = cpal::default_host();
+    let devices = host
+        .output_devices()
+        .map(|devices| devices.filter_map(|device| device.name().ok()).collect())
+        .unwrap_or_default();
+
+    DeviceScan {
+        default: host
+            .default_output_device()
+            .and_then(|device| device.name().ok()),
+        devices,
+    }
+}
+
+fn open_device(name: &str) -> Option<(OutputStream, OutputStreamHandle)> {
+    let device = cpal::default_host()
+        .output_devices()
+        .ok()?
+        .find(|device| device.name().is_ok_and(|n| n == name))?;
+
+    match OutputStream::try_from_device(&device) {
+        Ok(stream) => Some(stream),
+        Err(e) => {
+            warn!("Could not open audio device {name}: {e}");
+            None
+        }
+    }
+}
+
+// The chosen device when it is connected, the system default otherwise
+fn wanted_device(scan: &DeviceScan, settings: &Settings) -> Option<String> {
+    settings
+        .audio_device
+        .clone()
+        .filter(|name| scan.devices.contains(name))
+        .or_else(|| scan.default.clone())
+}
+
+// Opened right away so sounds at startup don't wait for the first scan
+pub fn open_default_output(
+    mut streams: NonSendMut<OutputStreams>,
+    mut output: ResMut<AudioOutput>,
+    settings: Res<Settings>,
+) {
+    let scan = scan_devices();
+    let Some(name) = wanted_device(&scan, &settings) else {
+        warn!("No audio output device found");
+        return;
+    };
+
+    streams.stream = open_device(&name);
+    if streams.stream.is_some() {
+        info!("Playing audio on {name}");
+        output.device = Some(name);
+    }
+    output.devices = scan.devices;
+}
+
+pub fn watch_output_devices(
+    mut streams: NonSendMut<OutputStreams>,
+    mut output: ResMut<AudioOutput>,
+    settings: Res<Settings>,
+    time: Res<Time<Real>>,
+    simulation: Res<State<SimulationState>>,
+    mut next_simulation: ResMut<NextState<SimulationState>>,
+) {
+    if output.scan.is_none() && time.elapsed() >= output.next_scan {
+        output.next_scan = time.elapsed() + SCAN_INTERVAL;
+        output.scan = Some(AsyncComputeTaskPool::get().spawn(async { scan_devices() }));
+    }
+
+    let Some(scan) = output
+        .scan
+        .as_mut()
+        .and_then(|task| block_on(future::poll_once(task)))
+    else {
+        return;
+    };
+    output.scan = None;
+
+    let wanted = wanted_device(&scan, &settings);
+    output.devices = scan.devices;
+
+    if wanted == output.device && streams.stream.is_some() {
+        return;
+    }
+
+    // Sounds can't move to another device, playback stops here and starts over on the new one
+    if *simulation.get() == SimulationState::Running {
+        next_simulation.set(SimulationState::Paused);
+        output.resume = true;
+    }
+
+    streams.stream = None;
+    output.device = None;
+    output.generation += 1;
+
+    let Some(name) = wanted else {
+        warn!("Audio output device was disconnected, playback is paused until one is connected");
+        return;
+    };
+
+    streams.stream = open_device(&name);
+    if streams.stream.is_none() {
+        return;
+    }
+
+    info!("Playing audio on {name}");
+    output.device = Some(name);
+
+    if output.resume {
+        output.resume = false;
+        next_simulation.set(SimulationState::Running);
+    }
+}
+
+// Takes the place of bevy's own playback for the app's audio sources
+pub fn play_queued<T: Asset + Decodable<DecoderItem = f32>>(
+    mut commands: Commands,
+    queued: Query<(Entity, &AudioPlayer<T>, &PlaybackSettings), Without<OutputSink>>,
+    sources: Res<Assets<T>>,
+    streams: NonSend<OutputStreams>,
+    output: Res<AudioOutput>,
+) where
+    T::Decoder: 'static,
+{
+    for (entity, player, settings) in queued.iter() {
+        // Sounds that would have played without a device are dropped, not saved up for later
+        let Some((_, handle)) = &streams.stream else {
+            if matches!(settings.mode, PlaybackMode::Despawn) {
+                commands.entity(entity).try_despawn();
+            }
+            continue;
+        };
+
+        let Some(source) = sources.get(&player.0) else {
+            continue;
+        };
+
+        let sink = match Sink::try_new(handle) {
+            Ok(sink) => sink,
+            Err(e) => {
+                warn!("Could not play a sound: {e}");
+                commands.entity(entity).try_despawn();
+                continue;
+            }
+        };
+
+        sink.set_speed(settings.speed);
+        sink.set_volume(match settings.muted {
+            true => 0.0,
+            false => settings.volume.to_linear(),
+        });
+        if settings.paused {
+            sink.pause();
+        }
+        match settings.mode {
+            PlaybackMode::Loop => sink.append(source.decoder().repeat_infinite()),
+            _ => sink.append(source.decoder()),
+        }
+
+        commands.entity(entity).insert(OutputSink {
+            sink,
+            generation: output.generation,
+        });
+    }
+}
+
+// Finished sounds are cleaned up like bevy does, ones made for a closed output never finish
+pub fn finish_sinks(
+    mut commands: Commands,
+    sinks: Query<(Entity, &OutputSink, &PlaybackSettings)>,
+    output: Res<AudioOutput>,
+) {
+    for (entity, sink, settings) in sinks.iter() {
+        if sink.generation != output.generation {
+            commands.entity(entity).try_despawn();
+            continue;
+        }
+
+        if !sink.sink.empty() {
+            continue;
+        }
+
+        match settings.mode {
+            PlaybackMode::Despawn => {
+                commands.entity(entity).try_despawn();
+            }
+            // Without its settings the player isn't picked up again
+            PlaybackMode::Remove => {
+                commands
+                    .entity(entity)
+                    .remove::<(OutputSink, PlaybackSettings)>();
+            }
+            PlaybackMode::Once | PlaybackMode::Loop => {}
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::{
+    io::{self, Cursor},
+    sync::Arc,
+};
+
+use rodio::{Decoder, Source};
+
+use crate::jukebox::stream;
+
+// Fully decoded audio, interleaved samples in the -1..1 range
+#[derive(Debug, Clone)]
+pub struct Pcm {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub samples: Vec<f32>,
+}
+
+impl Pcm {
+    pub fn decode(bytes: &[u8]) -> io::Result<Pcm> {
+        let decoder = Decoder::new(Cursor::new(bytes.to_vec()))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        Ok(Self::from_decoder(decoder))
+    }
+
+    // Decodes the map's own bytes without copying them first
+    pub fn decode_shared(bytes: Arc<[u8]>) -> io::Result<Pcm> {
+        Ok(Self::from_decoder(stream::open(bytes)?))
+    }
+
+    fn from_decoder<R>(decoder: Decoder<R>) -> Pcm
+    where
+        R: io::Read + io::Seek + Send + Sync + 'static,
+    {
+        let sample_rate = decoder.sample_rate();
+        let channels = decoder.channels();
+        let samples = decoder.map(|s| s as f32 / i16::MAX as f32).collect();
+
+        Pcm {
+            sample_rate,
+            channels,
+            samples,
+        }
+    }
+
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels.max(1) as usize
+    }
+
+    pub fn duration_ms(&self) -> u32 {
+        (self.frames() as f64 / self.sample_rate as f64 * 1000.0) as u32
+    }
+
+    // Channels averaged down to a single one
+    pub fn mono(&self) -> Vec<f32> {
+        let channels = self.channels.max(1) as usize;
+
+        self.samples
+            .chunks_exact(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect()
+    }
+}
+
+impl Pcm {
+    // A short slice starting at `millisecond` with faded edges so it doesn't click when played
+    pub fn grain(&self, millisecond: f64, length_ms: f64, fade_ms: f64) -> Vec<f32> {
+        let channels = self.channels.max(1) as usize;
+        let to_frames = |ms: f64| (ms.max(0.0) / 1000.0 * self.sample_rate as f64) as usize;
+
+        let start = to_frames(millisecond).min(self.frames());
+        let end = (start + to_frames(length_ms)).min(self.frames());
+        let fade = to_frames(fade_ms).max(1);
+        let length = end - start;
+
+        self.samples[start * channels..end * channels]
+            .chunks_exact(channels)
+            .enumerate()
+            .flat_map(|(frame, samples)| {
+                let edge = frame.min(length - 1 - frame);
+                let gain = (edge as f32 / fade as f32).min(1.0);
+                samples.iter().map(move |s| s * gain)
+            })
+            .collect()
+    }
+}
+
+impl Pcm {
+    // The audio between two times, clamped to its length
+    pub fn slice(&self, start_ms: f64, end_ms: f64) -> Pcm {
+        let channels = self.channels.max(1) as usize;
+        let to_frame = |ms: f64| {
+            ((ms.max(0.0) / 1000.0 * self.sample_rate as f64) as usize).min(self.frames())
+        };
+        let start = to_frame(start_ms);
+        let end = to_frame(end_ms).max(start);
+
+        Pcm {
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            samples: self.samples[start * channels..end * channels].to_vec(),
+        }
+    }
+
+    // 16 bit WAV, cut audio can't be stored in its original compressed format without an encoder
+    pub fn to_wav(&self) -> Vec<u8> {
+        let channels = self.channels.max(1);
+        let data_length = self.samples.len() as u32 * 2;
+        let mut wav = Vec::with_capacity(44 + data_length as usize);
+
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_length).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes()); // Integer PCM
+        wav.extend_from_slice(&channels.to_le_bytes());
+        wav.extend_from_slice(&self.sample_rate.to_le_bytes());
+        wav.extend_from_slice(&(self.sample_rate * channels as u32 * 2).to_le_bytes());
+        wav.extend_from_slice(&(channels * 2).to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_length.to_le_bytes());
+
+        for sample in self.samples.iter() {
+            let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            wav.extend_from_slice(&value.to_le_bytes());
+        }
+
+        wav
+    }
+}
+
+impl Pcm {
+    pub fn silence(sample_rate: u32, channels: u16, length_ms: f64) -> Pcm {
+        let frames = (length_ms.max(0.0) / 1000.0 * sample_rate as f64) as usize;
+
+        Pcm {
+            sample_rate,
+            channels,
+            samples: vec![0.0; frames * channels.max(1) as usize],
+        }
+    }
+
+    // Linearly interpolated to another rate, mono is spread over every channel and anything else
+    // is averaged down first
+    pub fn converted(&self, sample_rate: u32, channels: u16) -> Pcm {
+        if self.sample_rate == sample_rate && self.channels == channels {
+            return self.clone();
+        }
+
+        let mono = match self.channels == channels {
+            true => None,
+            false => Some(self.mono()),
+        };
+        let source_channels = match mono {
+            Some(_) => 1,
+            None => channels.max(1) as usize,
+        };
+        let source = mono.as_deref().unwrap_or(self.samples.as_slice());
+        let source_frames = source.len() / source_channels;
+
+        let ratio = self.sample_rate as f64 / sample_rate as f64;
+        let frames = (source_frames as f64 / ratio) as usize;
+        let mut samples = Vec::with_capacity(frames * channels.max(1) as usize);
+
+        for frame in 0..frames {
+            let position = frame as f64 * ratio;
+            let before = (position as usize).min(source_frames.saturating_sub(1));
+            let after = (before + 1).min(source_frames.saturating_sub(1));
+            let t = (position - before as f64) as f32;
+
+            for channel in 0..channels.max(1) as usize {
+                let channel = channel.min(source_channels - 1);
+                let a = source[before * source_channels + channel];
+                let b = source[after * source_channels + channel];
+                samples.push(a + (b - a) * t);
+            }
+        }
+
+        Pcm {
+            sample_rate,
+            channels,
+            samples,
+        }
+    }
+
+    // Plays `other` right after this one, converted to this one's rate and channels
+    pub fn append(&mut self, other: &Pcm) {
+        let other = other.converted(self.sample_rate, self.channels);
+        self.samples.extend_from_slice(&other.samples);
+    }
+}
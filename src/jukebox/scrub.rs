@@ -0,0 +1,69 @@
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+
+use crate::jukebox::{Clip, DecodedAudio, poll_decode};
+
+// Grains slightly overlap so scrubbing sounds continuous instead of like a series of clicks
+const GRAIN_LENGTH_MS: f64 = 90.0;
+const GRAIN_FADE_MS: f64 = 15.0;
+const GRAIN_INTERVAL: Duration = Duration::from_millis(70);
+
+// Sent while the playhead is dragged, with the position under the pointer
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ScrubAt(pub f64);
+
+#[derive(Resource, Default)]
+pub struct ScrubPlayer {
+    last_grain: Option<Instant>,
+    last_position: Option<f64>,
+}
+
+pub struct ScrubPlugin;
+
+impl Plugin for ScrubPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScrubPlayer>()
+            .add_event::<ScrubAt>()
+            .add_systems(Update, play_scrub_grains.after(poll_decode));
+    }
+}
+
+pub fn play_scrub_grains(
+    mut commands: Commands,
+    mut events: EventReader<ScrubAt>,
+    mut scrub: ResMut<ScrubPlayer>,
+    mut decoded: ResMut<DecodedAudio>,
+    mut clips: ResMut<Assets<Clip>>,
+) {
+    let Some(ScrubAt(position)) = events.read().last().copied() else {
+        return;
+    };
+
+    // Grains need the samples, streamed audio is decoded the first time it's scrubbed
+    decoded.request_pcm();
+
+    let due = scrub
+        .last_grain
+        .is_none_or(|last| last.elapsed() >= GRAIN_INTERVAL);
+
+    // Holding the pointer still stays silent, like it does in a DAW
+    let moved = scrub
+        .last_position
+        .is_none_or(|last| (last - position).abs() >= 1.0);
+
+    let Some(pcm) = decoded.pcm.as_ref().filter(|_| due && moved) else {
+        return;
+    };
+
+    let grain = Clip {
+        sample_rate: pcm.sample_rate,
+        channels: pcm.channels,
+        samples: pcm.grain(position, GRAIN_LENGTH_MS, GRAIN_FADE_MS),
+    };
+
+    commands.spawn((AudioPlayer(clips.add(grain)), PlaybackSettings::DESPAWN));
+
+    scrub.last_grain = Some(Instant::now());
+    scrub.last_position = Some(position);
+}
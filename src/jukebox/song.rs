@@ -0,0 +1,253 @@
+use std::{sync::Arc, time::Duration};
+
+use bevy::{audio::Decodable, prelude::*};
+use rodio::Source;
+
+use crate::{
+    jukebox::{
+        DecodedAudio,
+        output::AudioOutput,
+        pcm::Pcm,
+        stream::StreamSource,
+        stretch::{PitchMode, TimeStretch},
+    },
+    player::{SimulationState, playback::PlaybackClock},
+    settings::Settings,
+};
+
+// What a song plays from, see DecodedAudio::song_audio
+#[derive(Clone)]
+pub enum SongAudio {
+    Decoded(Arc<Pcm>),
+    Streamed(Arc<[u8]>),
+}
+
+impl SongAudio {
+    pub fn same(&self, other: &SongAudio) -> bool {
+        match (self, other) {
+            (SongAudio::Decoded(a), SongAudio::Decoded(b)) => Arc::ptr_eq(a, b),
+            (SongAudio::Streamed(a), SongAudio::Streamed(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+// The map audio from a given point onwards. Sinks can't seek, so seeking starts a new one of
+// these, which only shares the decoded samples or compressed bytes instead of copying them.
+#[derive(Asset, TypePath)]
+pub struct Song {
+    audio: SongAudio,
+    start_ms: f64,
+    // Rate to time stretch to, the audio is otherwise played at its own rate. Needs decoded audio
+    stretch: Option<f64>,
+}
+
+impl Song {
+    pub fn audio(&self) -> &SongAudio {
+        &self.audio
+    }
+
+    pub fn start_ms(&self) -> f64 {
+        self.start_ms
+    }
+}
+
+impl Decodable for Song {
+    type DecoderItem = f32;
+    type Decoder = SongSource;
+
+    fn decoder(&self) -> Self::Decoder {
+        match &self.audio {
+            SongAudio::Decoded(pcm) => {
+                let start_frame = (self.start_ms / 1000.0 * pcm.sample_rate as f64) as usize;
+
+                SongSource::Decoded {
+                    position: start_frame * pcm.channels.max(1) as usize,
+                    pcm: pcm.clone(),
+                    stretch: self
+                        .stretch
+                        .map(|rate| TimeStretch::new(pcm.clone(), start_frame, rate)),
+                }
+            }
+            SongAudio::Streamed(bytes) => match StreamSource::new(bytes.clone(), self.start_ms) {
+                Ok(stream) => SongSource::Streamed(Box::new(stream)),
+                Err(e) => {
+                    warn!("Could not stream the map audio: {e}");
+                    SongSource::Silent
+                }
+            },
+        }
+    }
+}
+
+pub enum SongSource {
+    Decoded {
+        pcm: Arc<Pcm>,
+        position: usize,
+        stretch: Option<TimeStretch>,
+    },
+    Streamed(Box<StreamSource>),
+    // Streamed audio that couldn't be opened, ends right away
+    Silent,
+}
+
+impl Iterator for SongSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        match self {
+            SongSource::Decoded {
+                stretch: Some(stretch),
+                ..
+            } => stretch.next(),
+            SongSource::Decoded { pcm, position, .. } => {
+                let sample = pcm.samples.get(*position).copied();
+                *position += 1;
+                sample
+            }
+            SongSource::Streamed(stream) => stream.next(),
+            SongSource::Silent => None,
+        }
+    }
+}
+
+impl Source for SongSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        match self {
+            // Stretched audio is produced as it plays, its length isn't known up front
+            SongSource::Decoded {
+                stretch: Some(_), ..
+            } => None,
+            SongSource::Decoded { pcm, position, .. } => {
+                Some(pcm.samples.len().saturating_sub(*position))
+            }
+            SongSource::Streamed(stream) => stream.current_frame_len(),
+            SongSource::Silent => Some(0),
+        }
+    }
+
+    fn channels(&self) -> u16 {
+        match self {
+            SongSource::Decoded { pcm, .. } => pcm.channels,
+            SongSource::Streamed(stream) => stream.channels(),
+            SongSource::Silent => 1,
+        }
+    }
+
+    fn sample_rate(&self) -> u32 {
+        match self {
+            SongSource::Decoded { pcm, .. } => pcm.sample_rate,
+            SongSource::Streamed(stream) => stream.sample_rate(),
+            SongSource::Silent => 44_100,
+        }
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+// Keeps the map audio in step with the playback clock
+#[derive(Resource, Default)]
+pub struct SongPlayer {
+    entity: Option<Entity>,
+    seeks: u32,
+    rate: f64,
+    pitch: PitchMode,
+    offset_ms: f32,
+    audio: Option<SongAudio>,
+    // Output the song was started on, it starts over when the device changes
+    generation: u32,
+}
+
+pub struct SongPlugin;
+
+impl Plugin for SongPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SongPlayer>()
+            .add_systems(PostUpdate, follow_clock);
+    }
+}
+
+// Runs after everything that might seek or pause this frame has done so
+#[allow(clippy::too_many_arguments)]
+pub fn follow_clock(
+    mut commands: Commands,
+    mut player: ResMut<SongPlayer>,
+    mut songs: ResMut<Assets<Song>>,
+    clock: Res<PlaybackClock>,
+    simulation: Res<State<SimulationState>>,
+    mut decoded: ResMut<DecodedAudio>,
+    settings: Res<Settings>,
+    output: Res<AudioOutput>,
+) {
+    let running = *simulation.get() == SimulationState::Running;
+    let current = decoded.song_audio();
+
+    // Streamed audio can't be stretched, it follows the rate until the samples are decoded
+    let stretch = settings.pitch_mode == PitchMode::Preserved && clock.rate != 1.0;
+    if stretch && running && decoded.pcm.is_none() {
+        decoded.request_pcm();
+    }
+
+    // Decoding finishing swaps the streamed audio for the samples mid song. That starts over from
+    // the clock like a seek does, the clock itself is left where it is
+    let same_audio = match (&player.audio, &current) {
+        (Some(playing), Some(current)) => playing.same(current),
+        _ => false,
+    };
+
+    let in_step = player.entity.is_some()
+        && same_audio
+        && player.seeks == clock.seeks
+        && player.rate == clock.rate
+        && player.pitch == settings.pitch_mode
+        && player.offset_ms == settings.audio_offset_ms
+        && player.generation == output.generation;
+
+    if running && in_step {
+        return;
+    }
+
+    if let Some(entity) = player.entity.take() {
+        // The entity is already gone if the song played to its end
+        commands.entity(entity).try_despawn();
+    }
+    player.audio = None;
+
+    let Some(audio) = current.filter(|_| running) else {
+        return;
+    };
+
+    // Starting ahead makes up for the time the sound takes to come out of the speakers
+    let start_ms = (clock.millisecond + settings.audio_offset_ms as f64).max(0.0);
+    let _span = debug_span!("start_song", start_ms, rate = clock.rate).entered();
+
+    // Stretching at 1x would only add artifacts
+    let stretch = stretch && matches!(audio, SongAudio::Decoded(_));
+    let speed = match stretch {
+        true => 1.0,
+        false => clock.rate as f32,
+    };
+
+    let song = songs.add(Song {
+        audio: audio.clone(),
+        start_ms,
+        stretch: stretch.then_some(clock.rate),
+    });
+
+    player.entity = Some(
+        commands
+            .spawn((
+                AudioPlayer(song),
+                PlaybackSettings::DESPAWN.with_speed(speed),
+            ))
+            .id(),
+    );
+    player.seeks = clock.seeks;
+    player.rate = clock.rate;
+    player.pitch = settings.pitch_mode;
+    player.offset_ms = settings.audio_offset_ms;
+    player.generation = output.generation;
+    player.audio = Some(audio);
+}
@@ -0,0 +1,73 @@
+use std::{
+    io::{self, Cursor},
+    sync::Arc,
+    time::Duration,
+};
+
+use rodio::{Decoder, Source};
+
+// Compressed map audio decoded as it plays, so large files aren't kept in memory a second time
+// as samples. Shares the bytes with the map instead of copying them.
+pub struct StreamSource {
+    decoder: Decoder<Cursor<Arc<[u8]>>>,
+}
+
+impl StreamSource {
+    pub fn new(bytes: Arc<[u8]>, start_ms: f64) -> io::Result<Self> {
+        let mut decoder = open(bytes)?;
+        let start = Duration::from_secs_f64(start_ms.max(0.0) / 1000.0);
+
+        // Not every format can seek, those are decoded up to the start instead
+        if decoder.try_seek(start).is_err() {
+            let frames = (start.as_secs_f64() * decoder.sample_rate() as f64) as usize;
+            let samples = frames * decoder.channels().max(1) as usize;
+            decoder.by_ref().take(samples).for_each(drop);
+        }
+
+        Ok(Self { decoder })
+    }
+}
+
+impl Iterator for StreamSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        self.decoder.next().map(|s| s as f32 / i16::MAX as f32)
+    }
+}
+
+impl Source for StreamSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.decoder.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.decoder.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.decoder.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+// Read from the header when the format has it, otherwise every sample is decoded and dropped
+pub fn duration_ms(bytes: Arc<[u8]>) -> io::Result<u32> {
+    let decoder = open(bytes)?;
+
+    if let Some(duration) = decoder.total_duration() {
+        return Ok(duration.as_millis() as u32);
+    }
+
+    let sample_rate = decoder.sample_rate() as f64;
+    let channels = decoder.channels().max(1) as f64;
+    let samples = decoder.count() as f64;
+    Ok((samples / channels / sample_rate * 1000.0) as u32)
+}
+
+pub fn open(bytes: Arc<[u8]>) -> io::Result<Decoder<Cursor<Arc<[u8]>>>> {
+    Decoder::new(Cursor::new(bytes)).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
@@ -0,0 +1,174 @@
+use std::{f32::consts::TAU, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use crate::jukebox::pcm::Pcm;
+
+// Long enough to hold a couple of periods of low notes, short enough that transients don't smear
+const GRAIN_MS: f64 = 40.0;
+
+// How far a grain may be moved to line up with the previous one
+const SEARCH_MS: f64 = 10.0;
+
+// How the map audio sounds when it is played faster or slower
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PitchMode {
+    // Resampled, higher when faster like a record spun faster
+    #[default]
+    FollowsRate,
+    // Time stretched, keeps the original pitch at the cost of some artifacts
+    Preserved,
+}
+
+impl PitchMode {
+    pub const ALL: [PitchMode; 2] = [PitchMode::FollowsRate, PitchMode::Preserved];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PitchMode::FollowsRate => "Follows rate",
+            PitchMode::Preserved => "Preserved",
+        }
+    }
+}
+
+// Plays audio at a different rate without changing its pitch, using waveform similarity overlap
+// add: windowed grains are read `rate` times faster than they are written, and every grain is
+// shifted slightly so it continues the waveform of the previous one instead of cancelling it out.
+pub struct TimeStretch {
+    pcm: Arc<Pcm>,
+    rate: f64,
+    window: Vec<f32>,
+    search: usize,
+    // Frame the next grain would ideally start at
+    position: f64,
+    // Where the previous grain started, the next one is matched against what followed it
+    previous: Option<usize>,
+    // Second half of the previous grain, added to the first half of the next
+    overlap: Vec<f32>,
+    output: Vec<f32>,
+    cursor: usize,
+    finished: bool,
+}
+
+impl TimeStretch {
+    pub fn new(pcm: Arc<Pcm>, start_frame: usize, rate: f64) -> Self {
+        let sample_rate = pcm.sample_rate as f64;
+        let channels = pcm.channels.max(1) as usize;
+        let grain = ((GRAIN_MS / 1000.0 * sample_rate) as usize).max(4) & !1;
+
+        // A periodic Hann window sums to exactly one when overlapped by half
+        let window = (0..grain)
+            .map(|i| 0.5 - 0.5 * (TAU * i as f32 / grain as f32).cos())
+            .collect();
+
+        Self {
+            rate: rate.max(0.01),
+            window,
+            search: (SEARCH_MS / 1000.0 * sample_rate) as usize,
+            position: start_frame as f64,
+            previous: None,
+            overlap: vec![0.0; grain / 2 * channels],
+            output: Vec::new(),
+            cursor: 0,
+            finished: false,
+            pcm,
+        }
+    }
+
+    fn hop(&self) -> usize {
+        self.window.len() / 2
+    }
+
+    fn sample(&self, frame: usize, channel: usize) -> f32 {
+        let channels = self.pcm.channels.max(1) as usize;
+        self.pcm
+            .samples
+            .get(frame * channels + channel)
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    fn mono(&self, frame: usize) -> f32 {
+        let channels = self.pcm.channels.max(1) as usize;
+        (0..channels).map(|c| self.sample(frame, c)).sum::<f32>() / channels as f32
+    }
+
+    // Start near `ideal` whose first half correlates best with the audio following `target`.
+    // Only every other frame and offset is compared, which is plenty at audio sample rates.
+    fn best_match(&self, target: usize, ideal: usize) -> usize {
+        let hop = self.hop();
+        let first = ideal.saturating_sub(self.search);
+        let last = (ideal + self.search).min(self.pcm.frames().saturating_sub(hop));
+
+        (first..=last.max(first))
+            .step_by(2)
+            .map(|candidate| {
+                let correlation: f32 = (0..hop)
+                    .step_by(2)
+                    .map(|i| self.mono(target + i) * self.mono(candidate + i))
+                    .sum();
+                (candidate, correlation)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map_or(ideal, |(candidate, _)| candidate)
+    }
+
+    // Writes the next hop of output, false once the audio has run out
+    fn next_block(&mut self) -> bool {
+        if self.finished {
+            return false;
+        }
+
+        let hop = self.hop();
+        let channels = self.pcm.channels.max(1) as usize;
+        let ideal = self.position.round() as usize;
+
+        // The tail of the last grain still has to be played out
+        if ideal >= self.pcm.frames() {
+            self.finished = true;
+            self.output = std::mem::take(&mut self.overlap);
+            return true;
+        }
+
+        let start = match self.previous {
+            Some(previous) => self.best_match(previous + hop, ideal),
+            None => ideal,
+        };
+
+        let mut output = Vec::with_capacity(hop * channels);
+        let mut overlap = Vec::with_capacity(hop * channels);
+
+        for i in 0..hop {
+            for c in 0..channels {
+                output.push(
+                    self.overlap[i * channels + c] + self.sample(start + i, c) * self.window[i],
+                );
+                overlap.push(self.sample(start + hop + i, c) * self.window[hop + i]);
+            }
+        }
+
+        self.output = output;
+        self.overlap = overlap;
+        self.previous = Some(start);
+        self.position += hop as f64 * self.rate;
+
+        true
+    }
+}
+
+impl Iterator for TimeStretch {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        while self.cursor >= self.output.len() {
+            if !self.next_block() {
+                return None;
+            }
+            self.cursor = 0;
+        }
+
+        let sample = self.output[self.cursor];
+        self.cursor += 1;
+        Some(sample)
+    }
+}
@@ -0,0 +1,14 @@
+// Everything but startup lives here, so the benchmarks can use the same code as the editor
+pub mod analysis;
+pub mod cli;
+pub mod collab;
+pub mod debug;
+pub mod editor;
+pub mod gameplay;
+pub mod jukebox;
+pub mod loading;
+pub mod maps;
+pub mod modchart;
+pub mod player;
+pub mod settings;
+pub mod theme;
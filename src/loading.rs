@@ -0,0 +1,191 @@
+use bevy::{
+    asset::{AssetLoadFailedEvent, LoadedFolder, RecursiveDependencyLoadState},
+    prelude::*,
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+
+use crate::{
+    maps::{Map, MapFolder, library},
+    settings::Settings,
+};
+
+#[derive(States, PartialEq, Eq, Debug, Hash, Clone, Copy, Default)]
+pub enum AppState {
+    // Settings, the maps folder and the library are still being read
+    #[default]
+    Loading,
+    // The editor with its start screen
+    Menu,
+}
+
+#[derive(Resource, Default)]
+pub struct LoadingScreen {
+    // Maps in the asset folder that could not be read, with the reason
+    failures: Vec<(String, String)>,
+    // Everything is loaded but there were failures, they stay up until they were seen
+    waiting: bool,
+}
+
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<AppState>()
+            .init_resource::<LoadingScreen>()
+            .add_systems(
+                Update,
+                (collect_failures, finish_loading)
+                    .chain()
+                    .after(library::poll_library_loads)
+                    .run_if(in_state(AppState::Loading)),
+            )
+            .add_systems(OnEnter(AppState::Menu), log_loaded)
+            .add_systems(
+                EguiPrimaryContextPass,
+                loading_screen_ui.run_if(in_state(AppState::Loading)),
+            );
+    }
+}
+
+// Safe mode doesn't scan the maps folder, there is nothing to wait for then
+fn folder_loaded(folder: Option<&MapFolder>, asset_server: &AssetServer) -> bool {
+    folder.is_none_or(|folder| {
+        matches!(
+            asset_server.recursive_dependency_load_state(&folder.0),
+            RecursiveDependencyLoadState::Loaded | RecursiveDependencyLoadState::Failed(_)
+        )
+    })
+}
+
+fn library_failures(library: &library::MapLibrary) -> impl Iterator<Item = (String, &str)> {
+    library.entries.iter().filter_map(|(path, entry)| {
+        let error = entry.error.as_deref()?;
+        Some((path.display().to_string(), error))
+    })
+}
+
+pub fn collect_failures(
+    mut failed: EventReader<AssetLoadFailedEvent<Map>>,
+    mut screen: ResMut<LoadingScreen>,
+) {
+    for event in failed.read() {
+        screen
+            .failures
+            .push((event.path.to_string(), event.error.to_string()));
+    }
+}
+
+pub fn finish_loading(
+    mut screen: ResMut<LoadingScreen>,
+    mut next_state: ResMut<NextState<AppState>>,
+    folder: Option<Res<MapFolder>>,
+    asset_server: Res<AssetServer>,
+    library: Res<library::MapLibrary>,
+) {
+    if screen.waiting || !folder_loaded(folder.as_deref(), &asset_server) || library.is_loading() {
+        return;
+    }
+
+    match screen.failures.is_empty() && library_failures(&library).next().is_none() {
+        true => next_state.set(AppState::Menu),
+        false => screen.waiting = true,
+    }
+}
+
+pub fn log_loaded(
+    maps: Res<Assets<Map>>,
+    library: Res<library::MapLibrary>,
+    screen: Res<LoadingScreen>,
+) {
+    let failures = screen.failures.len() + library_failures(&library).count();
+    info!("Loaded {} maps, {failures} could not be read", maps.len());
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn loading_screen_ui(
+    mut contexts: EguiContexts,
+    screen: Res<LoadingScreen>,
+    mut next_state: ResMut<NextState<AppState>>,
+    folder: Option<Res<MapFolder>>,
+    folders: Res<Assets<LoadedFolder>>,
+    asset_server: Res<AssetServer>,
+    maps: Res<Assets<Map>>,
+    library: Res<library::MapLibrary>,
+    settings: Res<Settings>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+
+    egui::Modal::new(egui::Id::new("loading")).show(ctx, |ui| {
+        ui.set_width(420.0);
+        ui.heading("Loading");
+
+        // Settings are read before the window opens, they're listed so every step shows up here
+        ui.label(match settings.first_run {
+            true => "Settings: first start, using defaults",
+            false => "Settings: loaded",
+        });
+
+        match &folder {
+            Some(handle) => {
+                // Assets with a path came from the folder, library maps are added without one
+                let read = match folders.get(&handle.0) {
+                    Some(folder) => folder.handles.len(),
+                    None => maps
+                        .ids()
+                        .filter(|id| asset_server.get_path(*id).is_some())
+                        .count(),
+                };
+                ui.horizontal(|ui| {
+                    if !folder_loaded(folder.as_deref(), &asset_server) {
+                        ui.spinner();
+                    }
+                    ui.label(format!("Maps folder: {read} read"));
+                });
+            }
+            None => {
+                ui.label("Maps folder: skipped in safe mode");
+            }
+        }
+
+        let (done, total) = library.progress();
+        let fraction = match total {
+            0 => 1.0,
+            total => done as f32 / total as f32,
+        };
+        ui.label("Library");
+        ui.add(
+            egui::ProgressBar::new(fraction)
+                .text(format!("{done} of {total} maps"))
+                .animate(library.is_loading()),
+        );
+
+        let failures: Vec<(String, &str)> = screen
+            .failures
+            .iter()
+            .map(|(path, error)| (path.clone(), error.as_str()))
+            .chain(library_failures(&library))
+            .collect();
+
+        if !failures.is_empty() {
+            ui.separator();
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!("{} maps could not be read", failures.len()),
+            );
+            egui::ScrollArea::vertical()
+                .max_height(200.0)
+                .show(ui, |ui| {
+                    for (path, error) in failures.iter() {
+                        ui.label(egui::RichText::new(path).strong());
+                        ui.weak(*error);
+                    }
+                });
+        }
+
+        if screen.waiting && ui.button("Continue").clicked() {
+            next_state.set(AppState::Menu);
+        }
+    });
+
+    Ok(())
+}
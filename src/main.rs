@@ -1,28 +1,80 @@
-use bevy::prelude::*;
-use std;
-
-mod jukebox;
-mod maps;
-mod player;
+use bevy::{log::LogPlugin, prelude::*, window::ExitCondition};
+use bevy_egui::EguiPlugin;
+use mm_modchart_maker::{
+    cli, collab, debug, editor, gameplay, jukebox, loading, maps, modchart, player, settings, theme,
+};
 
 const _UPDATE_FREQUENCY: f32 = 1.0 / 60.0; // 60 updates per second
 
 fn main() -> std::io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(result) = cli::run(&args) {
+        return result;
+    }
+
+    let crashed = settings::recovery::acquire_lock().unwrap_or_else(|e| {
+        // Logging only starts with the app
+        eprintln!("Could not write the lock file, crashes won't be detected: {e}");
+        false
+    });
+    let safe_mode = settings::recovery::SafeMode {
+        active: crashed || args.iter().any(|arg| arg == "--safe-mode"),
+        crashed,
+    };
+
     let mut app = App::new();
 
-    app.add_plugins(DefaultPlugins)
+    // Also writes every session's log to a file, for the diagnostics bundle
+    let log = LogPlugin {
+        custom_layer: debug::diagnostics::file_log_layer,
+        ..default()
+    };
+
+    // The preview window can be closed on its own, closing the editor quits
+    let window = WindowPlugin {
+        exit_condition: ExitCondition::OnPrimaryClosed,
+        ..default()
+    };
+
+    app.add_plugins(DefaultPlugins.set(log).set(window))
+        .add_plugins(EguiPlugin::default())
+        .add_plugins(settings::SettingsPlugin { safe_mode })
+        .add_plugins(loading::LoadingPlugin)
+        .add_plugins(theme::ThemePlugin)
         .add_plugins(maps::MapPlugin)
         .add_plugins(player::PlayerPlugin)
-        .add_systems(Startup, load_assets)
-        .run();
+        .add_plugins(gameplay::GameplayPlugin)
+        .add_plugins(jukebox::JukeboxPlugin)
+        .add_plugins(modchart::ModchartPlugin)
+        .add_plugins(editor::EditorPlugin)
+        .add_plugins(collab::CollabPlugin)
+        .add_plugins(debug::DebugPlugin)
+        .add_systems(Startup, load_assets);
 
-    Ok(())
-}
+    // A project given on the command line opens once the editor is up
+    if let Some(project) = args
+        .iter()
+        .find(|arg| arg.ends_with(&format!(".{}", editor::project::EXTENSION)))
+    {
+        app.world_mut()
+            .send_event(editor::project::OpenProject(project.into()));
+    }
+
+    app.run();
 
-pub fn load_assets(mut commands: Commands, asset_server: Res<AssetServer>) {
-    commands.insert_resource(maps::MapFolder(asset_server.load_folder("maps")));
+    // Only reached on a clean exit, a panic leaves the lock for the next start to find
+    settings::recovery::release_lock();
+
+    Ok(())
 }
 
-pub fn get_maps(maps: Res<Assets<maps::Map>>) {
-    println!("Maps loaded: {}", maps.len());
+pub fn load_assets(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    safe_mode: Res<settings::recovery::SafeMode>,
+) {
+    // Scanned when safe mode is left, in case a map in it is what crashed
+    if !safe_mode.active {
+        commands.insert_resource(maps::MapFolder(asset_server.load_folder("maps")));
+    }
 }
@@ -0,0 +1,51 @@
+use std::io::{self, Cursor, Read, Seek, Write};
+
+use serde::{Deserialize, Serialize};
+use zip::{ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+use crate::maps::{
+    Map,
+    parser::{MapSerializer, SSPMSerializer},
+};
+
+// Maps kept inside the app's own zip files, like snapshots and projects. The map is stored as
+// SSPM with note ids so they survive, next to what SSPM can't store.
+#[derive(Serialize, Deserialize)]
+struct MapExtras {
+    artists: Vec<String>,
+    save_note_ids: bool,
+}
+
+pub fn write_map_entries<W: Write + Seek>(zip: &mut ZipWriter<W>, map: &Map) -> io::Result<()> {
+    let extras = MapExtras {
+        artists: map.artists.clone(),
+        save_note_ids: map.save_note_ids,
+    };
+
+    let mut stored = map.clone();
+    stored.save_note_ids = true;
+    let mut sspm = Cursor::new(Vec::new());
+    SSPMSerializer::serialize(&stored, &mut sspm)?;
+
+    let options = SimpleFileOptions::default();
+    zip.start_file("map.json", options)?;
+    zip.write_all(serde_json::to_string(&extras)?.as_bytes())?;
+    zip.start_file("map.sspm", options)?;
+    zip.write_all(&sspm.into_inner())?;
+
+    Ok(())
+}
+
+pub fn read_map_entries<R: Read + Seek>(zip: &mut ZipArchive<R>) -> io::Result<Map> {
+    let mut contents = String::new();
+    zip.by_name("map.json")?.read_to_string(&mut contents)?;
+    let extras: MapExtras = serde_json::from_str(&contents)?;
+
+    let mut sspm = Vec::new();
+    zip.by_name("map.sspm")?.read_to_end(&mut sspm)?;
+
+    let mut map = SSPMSerializer::deserialize(Cursor::new(sspm))?;
+    map.artists = extras.artists;
+    map.save_note_ids = extras.save_note_ids;
+    Ok(map)
+}
@@ -0,0 +1,252 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::math::Vec2;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    jukebox::audio_extension,
+    maps::{
+        Map,
+        export::{normalize, template_filename},
+        parser::is_quantum,
+    },
+};
+
+// Experimental, lays a chart out as a Beat Saber custom level so it can be tried in VR. The
+// level has a single Expert+ difficulty in the v3 beatmap format, with every note a dot note
+
+// Beats are counted at this tempo when the map has no timing points, times stay exact either way
+const FALLBACK_BPM: f64 = 120.0;
+
+// Beat Saber's grid is four lanes wide and three layers high, counted from the bottom left
+const LANES: f32 = 4.0;
+const LAYERS: f32 = 3.0;
+
+const RED: u8 = 0;
+const BLUE: u8 = 1;
+const ANY_DIRECTION: u8 = 8;
+
+const BEATMAP_FILE: &str = "ExpertPlusStandard.dat";
+const SONG_FILE: &str = "song.egg";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ColorNote {
+    // Beat, lane, layer, color, cut direction and angle offset
+    pub b: f64,
+    pub x: u8,
+    pub y: u8,
+    pub c: u8,
+    pub d: u8,
+    pub a: i32,
+}
+
+#[derive(Debug)]
+pub struct BeatSaberExport {
+    pub folder: PathBuf,
+    pub warnings: Vec<String>,
+}
+
+fn bpm(map: &Map) -> f64 {
+    map.timing_points
+        .first()
+        .map_or(FALLBACK_BPM, |timing| timing.bpm as f64)
+}
+
+// Notes left of the middle column are cut with the left saber and the ones right of it with the
+// right saber, middle notes go to whichever saber didn't cut the note before
+pub fn color_notes(map: &Map) -> Vec<ColorNote> {
+    let beats_per_ms = bpm(map) / 60000.0;
+    let mut notes: Vec<ColorNote> = Vec::new();
+    let mut last_color = BLUE;
+
+    for note in map.notes.iter() {
+        let position = note.position.clamp(Vec2::ZERO, Vec2::splat(2.0));
+
+        let color = match position.x {
+            x if x < 1.0 => RED,
+            x if x > 1.0 => BLUE,
+            _ if last_color == RED => BLUE,
+            _ => RED,
+        };
+        last_color = color;
+
+        // The middle column is split between the two inner lanes, one per saber
+        let lane = match color {
+            _ if position.x != 1.0 => (position.x / 2.0 * (LANES - 1.0)).round(),
+            RED => 1.0,
+            _ => 2.0,
+        };
+        let layer = ((2.0 - position.y) / 2.0 * (LAYERS - 1.0)).round();
+
+        let color_note = ColorNote {
+            b: note.millisecond as f64 * beats_per_ms,
+            x: lane as u8,
+            y: layer as u8,
+            c: color,
+            d: ANY_DIRECTION,
+            a: 0,
+        };
+
+        // Quantum notes that land on the same lane at once would overlap into one
+        if notes
+            .iter()
+            .rev()
+            .take_while(|n| n.b == color_note.b)
+            .any(|n| (n.x, n.y) == (color_note.x, color_note.y))
+        {
+            continue;
+        }
+
+        notes.push(color_note);
+    }
+
+    notes
+}
+
+fn warnings(map: &Map) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    match map
+        .audio
+        .as_ref()
+        .map(|audio| audio_extension(&audio.bytes))
+    {
+        None => warnings.push(format!(
+            "The map has no audio, Beat Saber won't load the level without {SONG_FILE}"
+        )),
+        Some(Some("ogg")) => {}
+        Some(extension) => warnings.push(format!(
+            "Beat Saber only plays Ogg Vorbis, convert song.{} to {SONG_FILE}",
+            extension.unwrap_or("mp3")
+        )),
+    }
+
+    if map.cover.is_empty() {
+        warnings.push("The map has no cover, Beat Saber shows the level without one".to_string());
+    }
+
+    if map.timing_points.len() > 1 {
+        warnings.push(format!(
+            "Beats are counted at {} BPM, the grid in Beat Saber won't follow later tempo changes",
+            bpm(map)
+        ));
+    }
+
+    let quantum = map.notes.iter().filter(|n| is_quantum(n.position)).count();
+    if quantum > 0 {
+        warnings.push(format!(
+            "{quantum} quantum notes are moved to the nearest lane"
+        ));
+    }
+
+    if !map.mod_events.is_empty() || !map.speed_changes.is_empty() {
+        warnings.push("Mods and speed changes have no counterpart and are dropped".to_string());
+    }
+
+    warnings
+}
+
+fn info(map: &Map, cover_file: Option<&str>) -> serde_json::Value {
+    let preview_start = map
+        .notes
+        .first()
+        .map_or(0.0, |note| note.millisecond as f64 / 1000.0);
+
+    json!({
+        "_version": "2.1.0",
+        "_songName": map.title,
+        "_songSubName": "",
+        "_songAuthorName": map.artists.join(", "),
+        "_levelAuthorName": map.mappers.join(", "),
+        "_beatsPerMinute": bpm(map),
+        "_shuffle": 0,
+        "_shufflePeriod": 0.5,
+        "_previewStartTime": preview_start,
+        "_previewDuration": 10,
+        "_songFilename": SONG_FILE,
+        "_coverImageFilename": cover_file.unwrap_or(""),
+        "_environmentName": "DefaultEnvironment",
+        "_allDirectionsEnvironmentName": "GlassDesertEnvironment",
+        "_songTimeOffset": 0,
+        "_difficultyBeatmapSets": [{
+            "_beatmapCharacteristicName": "Standard",
+            "_difficultyBeatmaps": [{
+                "_difficulty": "ExpertPlus",
+                "_difficultyRank": 9,
+                "_beatmapFilename": BEATMAP_FILE,
+                "_noteJumpMovementSpeed": 16,
+                "_noteJumpStartBeatOffset": 0,
+            }],
+        }],
+    })
+}
+
+fn beatmap(map: &Map) -> serde_json::Value {
+    json!({
+        "version": "3.2.0",
+        "bpmEvents": [],
+        "rotationEvents": [],
+        "colorNotes": color_notes(map),
+        "bombNotes": [],
+        "obstacles": [],
+        "sliders": [],
+        "burstSliders": [],
+        "waypoints": [],
+        "basicBeatmapEvents": [],
+        "colorBoostBeatmapEvents": [],
+        "lightColorEventBoxGroups": [],
+        "lightRotationEventBoxGroups": [],
+        "basicEventTypesWithKeywords": {},
+        "useNormalEventsAsCompatibleEvents": true,
+    })
+}
+
+// Writes the level into its own folder inside `folder`, named with the export filename template
+pub fn export_beat_saber(
+    map: &Map,
+    folder: &Path,
+    template: &str,
+) -> std::io::Result<BeatSaberExport> {
+    let map = normalize(map);
+
+    let name = template_filename(&map, template, "dat");
+    let name = name.strip_suffix(".dat").unwrap_or(&name);
+    let level = folder.join(format!("{name} (Beat Saber)"));
+    fs::create_dir_all(&level)?;
+
+    if let Some(audio) = &map.audio {
+        // Written under its own extension when it isn't Ogg, so it's clear it needs converting
+        let file = match audio_extension(&audio.bytes) {
+            Some("ogg") => SONG_FILE.to_string(),
+            extension => format!("song.{}", extension.unwrap_or("mp3")),
+        };
+        fs::write(level.join(file), &audio.bytes)?;
+    }
+
+    let cover_file = match map.cover.starts_with(b"\x89PNG") {
+        _ if map.cover.is_empty() => None,
+        true => Some("cover.png"),
+        false => Some("cover.jpg"),
+    };
+    if let Some(file) = cover_file {
+        fs::write(level.join(file), &map.cover)?;
+    }
+
+    fs::write(
+        level.join("Info.dat"),
+        serde_json::to_string_pretty(&info(&map, cover_file))?,
+    )?;
+    fs::write(
+        level.join(BEATMAP_FILE),
+        serde_json::to_string(&beatmap(&map))?,
+    )?;
+
+    Ok(BeatSaberExport {
+        folder: level,
+        warnings: warnings(&map),
+    })
+}
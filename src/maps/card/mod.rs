@@ -0,0 +1,327 @@
+// Renders a shareable image of a chart: cover, title, mappers, difficulty, a few stats and the
+// note density over the whole map. Drawn on the CPU so the command line can make them too.
+
+mod font;
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use image::{Rgba, RgbaImage, imageops};
+
+use crate::{
+    analysis::difficulty::peak_notes_per_second,
+    maps::{
+        DIFFICULTY_NAMES, Map,
+        cover::{self, CoverEncoding},
+        export::template_filename,
+        format_time,
+    },
+};
+
+// The usual size of link previews, so cards aren't cropped when posted
+pub const CARD_WIDTH: u32 = 1200;
+pub const CARD_HEIGHT: u32 = 630;
+
+const MARGIN: u32 = 40;
+const COVER_SIZE: u32 = 390;
+const TEXT_LEFT: u32 = MARGIN * 2 + COVER_SIZE;
+const TEXT_WIDTH: u32 = CARD_WIDTH - TEXT_LEFT - MARGIN;
+
+const GRAPH_TOP: u32 = 470;
+const GRAPH_HEIGHT: u32 = 100;
+const BAR_WIDTH: u32 = 8;
+const BAR_GAP: u32 = 2;
+
+const BACKGROUND: Rgba<u8> = Rgba([18, 20, 26, 255]);
+const PANEL: Rgba<u8> = Rgba([40, 44, 54, 255]);
+const TEXT: Rgba<u8> = Rgba([236, 238, 244, 255]);
+const MUTED: Rgba<u8> = Rgba([150, 156, 172, 255]);
+const ACCENT: Rgba<u8> = Rgba([112, 172, 255, 255]);
+
+// How much of the blurred cover shows through behind everything
+const BACKDROP_BRIGHTNESS: f32 = 0.22;
+
+struct CardStats {
+    notes: usize,
+    length_ms: u32,
+    // Lowest and highest of the timing points
+    bpm: Option<(f32, f32)>,
+    // Most notes within any one second
+    peak_nps: usize,
+}
+
+impl CardStats {
+    fn new(map: &Map) -> Self {
+        let bpm = map
+            .timing_points
+            .iter()
+            .map(|t| t.bpm)
+            .fold(None, |range, bpm| {
+                let (low, high) = range.unwrap_or((bpm, bpm));
+                Some((low.min(bpm), high.max(bpm)))
+            });
+
+        Self {
+            notes: map.notes.len(),
+            length_ms: map.length.max(map.last_object_ms()),
+            bpm,
+            peak_nps: peak_notes_per_second(&map.notes),
+        }
+    }
+}
+
+fn blend(pixel: &mut Rgba<u8>, color: Rgba<u8>) {
+    let alpha = color.0[3] as u16;
+    for channel in 0..3 {
+        let mixed = pixel.0[channel] as u16 * (255 - alpha) + color.0[channel] as u16 * alpha;
+        pixel.0[channel] = (mixed / 255) as u8;
+    }
+}
+
+// Clipped to the image, so callers don't have to check their layout fits
+fn fill_rect(image: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32, color: Rgba<u8>) {
+    let right = (x + width).min(image.width());
+    let bottom = (y + height).min(image.height());
+    for py in y..bottom {
+        for px in x..right {
+            blend(image.get_pixel_mut(px, py), color);
+        }
+    }
+}
+
+fn draw_text(image: &mut RgbaImage, text: &str, x: u32, y: u32, scale: u32, color: Rgba<u8>) {
+    for (index, c) in text.chars().enumerate() {
+        let left = x + index as u32 * font::ADVANCE * scale;
+        for (row, bits) in font::glyph(c).iter().enumerate() {
+            for column in 0..font::GLYPH_WIDTH {
+                if bits & (1 << (font::GLYPH_WIDTH - 1 - column)) != 0 {
+                    let (px, py) = (left + column * scale, y + row as u32 * scale);
+                    fill_rect(image, px, py, scale, scale, color);
+                }
+            }
+        }
+    }
+}
+
+// Cut short with an ellipsis when wider than `width`
+fn fit(text: &str, scale: u32, width: u32) -> String {
+    if font::text_width(text, scale) <= width {
+        return text.to_string();
+    }
+
+    let mut fitted: String = text.chars().collect();
+    while !fitted.is_empty() && font::text_width(&format!("{fitted}..."), scale) > width {
+        fitted.pop();
+    }
+    format!("{}...", fitted.trim_end())
+}
+
+// Breaks at spaces into at most `max_lines`, the last line is cut short if there is more
+fn wrap(text: &str, scale: u32, width: u32, max_lines: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut words = text.split_whitespace().peekable();
+
+    while let Some(word) = words.next() {
+        if lines.len() == max_lines {
+            break;
+        }
+
+        let mut line = word.to_string();
+        while let Some(next) = words.peek() {
+            let longer = format!("{line} {next}");
+            if font::text_width(&longer, scale) > width {
+                break;
+            }
+            line = longer;
+            words.next();
+        }
+
+        if lines.len() + 1 == max_lines && words.peek().is_some() {
+            let rest: Vec<&str> = words.by_ref().collect();
+            line = format!("{line} {}", rest.join(" "));
+        }
+        lines.push(fit(&line, scale, width));
+    }
+
+    lines
+}
+
+fn backdrop(cover: Option<&RgbaImage>) -> RgbaImage {
+    let Some(cover) = cover else {
+        return RgbaImage::from_pixel(CARD_WIDTH, CARD_HEIGHT, BACKGROUND);
+    };
+
+    // Scaled down first so the stretch back up blurs it
+    let cropped = cover::crop_to_aspect(cover, CARD_WIDTH as f32 / CARD_HEIGHT as f32);
+    let small = imageops::resize(&cropped, 48, 25, imageops::FilterType::Triangle);
+    let mut image = imageops::resize(
+        &small,
+        CARD_WIDTH,
+        CARD_HEIGHT,
+        imageops::FilterType::Triangle,
+    );
+
+    for pixel in image.pixels_mut() {
+        for channel in 0..3 {
+            let dimmed = pixel.0[channel] as f32 * BACKDROP_BRIGHTNESS;
+            let base = BACKGROUND.0[channel] as f32 * (1.0 - BACKDROP_BRIGHTNESS);
+            pixel.0[channel] = (dimmed + base) as u8;
+        }
+        pixel.0[3] = 255;
+    }
+    image
+}
+
+fn draw_density(image: &mut RgbaImage, map: &Map, length_ms: u32) {
+    let width = CARD_WIDTH - MARGIN * 2;
+    let bars = (width + BAR_GAP) / (BAR_WIDTH + BAR_GAP);
+
+    let mut counts = vec![0u32; bars as usize];
+    for note in map.notes.iter() {
+        let bar = (note.millisecond as u64 * bars as u64 / length_ms.max(1) as u64) as usize;
+        counts[bar.min(bars as usize - 1)] += 1;
+    }
+    let most = counts.iter().copied().max().unwrap_or(0).max(1);
+
+    for (index, count) in counts.iter().enumerate() {
+        let x = MARGIN + index as u32 * (BAR_WIDTH + BAR_GAP);
+        fill_rect(
+            image,
+            x,
+            GRAPH_TOP,
+            BAR_WIDTH,
+            GRAPH_HEIGHT,
+            Rgba([255, 255, 255, 12]),
+        );
+
+        // Any notes at all get a sliver, so quiet parts don't look empty
+        let height = match count {
+            0 => 0,
+            _ => (count * GRAPH_HEIGHT / most).max(2),
+        };
+        let top = GRAPH_TOP + GRAPH_HEIGHT - height;
+        fill_rect(image, x, top, BAR_WIDTH, height, ACCENT);
+    }
+
+    let labels_top = GRAPH_TOP + GRAPH_HEIGHT + 10;
+    draw_text(image, "0:00", MARGIN, labels_top, 2, MUTED);
+    let end = format_time(length_ms);
+    let end_left = CARD_WIDTH - MARGIN - font::text_width(&end, 2);
+    draw_text(image, &end, end_left, labels_top, 2, MUTED);
+}
+
+pub fn render_card(map: &Map) -> RgbaImage {
+    let cover = match map.cover.is_empty() {
+        true => None,
+        false => cover::decode(&map.cover).ok(),
+    };
+    let stats = CardStats::new(map);
+
+    let mut image = backdrop(cover.as_ref());
+
+    match &cover {
+        Some(cover) => {
+            let square = cover::crop_to_aspect(cover, 1.0);
+            let scaled = imageops::resize(
+                &square,
+                COVER_SIZE,
+                COVER_SIZE,
+                imageops::FilterType::Lanczos3,
+            );
+            imageops::overlay(&mut image, &scaled, MARGIN as i64, MARGIN as i64);
+        }
+        None => {
+            fill_rect(&mut image, MARGIN, MARGIN, COVER_SIZE, COVER_SIZE, PANEL);
+            let label = "NO COVER";
+            let x = MARGIN + (COVER_SIZE - font::text_width(label, 3)) / 2;
+            draw_text(&mut image, label, x, MARGIN + COVER_SIZE / 2 - 10, 3, MUTED);
+        }
+    }
+
+    let mut y = MARGIN + 10;
+    let title = match map.title.trim() {
+        "" => "Untitled",
+        title => title,
+    };
+    for line in wrap(title, 5, TEXT_WIDTH, 2) {
+        draw_text(&mut image, &line, TEXT_LEFT, y, 5, TEXT);
+        y += font::GLYPH_HEIGHT * 5 + 12;
+    }
+
+    y += 4;
+    if !map.artists.is_empty() {
+        let artists = fit(&map.artists.join(", "), 3, TEXT_WIDTH);
+        draw_text(&mut image, &artists, TEXT_LEFT, y, 3, MUTED);
+        y += font::GLYPH_HEIGHT * 3 + 14;
+    }
+
+    if !map.mappers.is_empty() {
+        let mappers = format!("Mapped by {}", map.mappers.join(", "));
+        draw_text(
+            &mut image,
+            &fit(&mappers, 3, TEXT_WIDTH),
+            TEXT_LEFT,
+            y,
+            3,
+            TEXT,
+        );
+        y += font::GLYPH_HEIGHT * 3 + 14;
+    }
+
+    let difficulty = DIFFICULTY_NAMES
+        .get(map.difficulty as usize)
+        .copied()
+        .unwrap_or(DIFFICULTY_NAMES[0]);
+    let difficulty = match map.difficulty_name.trim() {
+        "" => difficulty.to_string(),
+        name => format!("{difficulty} - {name}"),
+    };
+    draw_text(
+        &mut image,
+        &fit(&difficulty, 3, TEXT_WIDTH),
+        TEXT_LEFT,
+        y,
+        3,
+        ACCENT,
+    );
+
+    let bpm = match stats.bpm {
+        None => "-".to_string(),
+        Some((low, high)) if (high - low).abs() < 0.5 => format!("{low:.0}"),
+        Some((low, high)) => format!("{low:.0}-{high:.0}"),
+    };
+    let columns = [
+        ("NOTES", stats.notes.to_string()),
+        ("LENGTH", format_time(stats.length_ms)),
+        ("BPM", bpm),
+        ("PEAK NPS", stats.peak_nps.to_string()),
+    ];
+
+    // Along the bottom of the cover
+    let stats_top = MARGIN + COVER_SIZE - 60;
+    let column_width = TEXT_WIDTH / columns.len() as u32;
+    for (index, (label, value)) in columns.iter().enumerate() {
+        let x = TEXT_LEFT + index as u32 * column_width;
+        draw_text(&mut image, label, x, stats_top, 2, MUTED);
+        let value = fit(value, 4, column_width - 10);
+        draw_text(&mut image, &value, x, stats_top + 26, 4, TEXT);
+    }
+
+    draw_density(&mut image, map, stats.length_ms);
+    image
+}
+
+// Named like the map exports, with a png extension
+pub fn card_path(map: &Map, folder: &Path, template: &str) -> PathBuf {
+    folder.join(template_filename(map, template, "png"))
+}
+
+pub fn save_card(map: &Map, path: &Path) -> io::Result<()> {
+    if let Some(folder) = path.parent() {
+        fs::create_dir_all(folder)?;
+    }
+    let bytes = cover::encode(&render_card(map), CoverEncoding::Png)?;
+    fs::write(path, bytes)
+}
@@ -0,0 +1,313 @@
+use std::f32::consts::{FRAC_1_SQRT_2, PI};
+
+use image::RgbImage;
+
+// Baseline JPEG encoder using the example tables of the specification (Annex K). Colors are not
+// subsampled, covers are small enough that the extra size is not worth the blurred edges.
+
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20,
+    13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59,
+    52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+const LUMA_QUANTIZATION: [u16; 64] = [
+    16, 11, 10, 16, 24, 40, 51, 61, 12, 12, 14, 19, 26, 58, 60, 55, 14, 13, 16, 24, 40, 57, 69, 56,
+    14, 17, 22, 29, 51, 87, 80, 62, 18, 22, 37, 56, 68, 109, 103, 77, 24, 35, 55, 64, 81, 104, 113,
+    92, 49, 64, 78, 87, 103, 121, 120, 101, 72, 92, 95, 98, 112, 100, 103, 99,
+];
+
+const CHROMA_QUANTIZATION: [u16; 64] = [
+    17, 18, 24, 47, 99, 99, 99, 99, 18, 21, 26, 66, 99, 99, 99, 99, 24, 26, 56, 99, 99, 99, 99, 99,
+    47, 66, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+    99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+];
+
+const LUMA_DC_BITS: [u8; 16] = [0, 1, 5, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0];
+const CHROMA_DC_BITS: [u8; 16] = [0, 3, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0];
+const DC_VALUES: [u8; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+
+const LUMA_AC_BITS: [u8; 16] = [0, 2, 1, 3, 3, 2, 4, 3, 5, 5, 4, 4, 0, 0, 1, 0x7d];
+const LUMA_AC_VALUES: [u8; 162] = [
+    0x01, 0x02, 0x03, 0x00, 0x04, 0x11, 0x05, 0x12, 0x21, 0x31, 0x41, 0x06, 0x13, 0x51, 0x61, 0x07,
+    0x22, 0x71, 0x14, 0x32, 0x81, 0x91, 0xa1, 0x08, 0x23, 0x42, 0xb1, 0xc1, 0x15, 0x52, 0xd1, 0xf0,
+    0x24, 0x33, 0x62, 0x72, 0x82, 0x09, 0x0a, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x25, 0x26, 0x27, 0x28,
+    0x29, 0x2a, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3a, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49,
+    0x4a, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69,
+    0x6a, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7a, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89,
+    0x8a, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9a, 0xa2, 0xa3, 0xa4, 0xa5, 0xa6, 0xa7,
+    0xa8, 0xa9, 0xaa, 0xb2, 0xb3, 0xb4, 0xb5, 0xb6, 0xb7, 0xb8, 0xb9, 0xba, 0xc2, 0xc3, 0xc4, 0xc5,
+    0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xd2, 0xd3, 0xd4, 0xd5, 0xd6, 0xd7, 0xd8, 0xd9, 0xda, 0xe1, 0xe2,
+    0xe3, 0xe4, 0xe5, 0xe6, 0xe7, 0xe8, 0xe9, 0xea, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8,
+    0xf9, 0xfa,
+];
+
+const CHROMA_AC_BITS: [u8; 16] = [0, 2, 1, 2, 4, 4, 3, 4, 7, 5, 4, 4, 0, 1, 2, 0x77];
+const CHROMA_AC_VALUES: [u8; 162] = [
+    0x00, 0x01, 0x02, 0x03, 0x11, 0x04, 0x05, 0x21, 0x31, 0x06, 0x12, 0x41, 0x51, 0x07, 0x61, 0x71,
+    0x13, 0x22, 0x32, 0x81, 0x08, 0x14, 0x42, 0x91, 0xa1, 0xb1, 0xc1, 0x09, 0x23, 0x33, 0x52, 0xf0,
+    0x15, 0x62, 0x72, 0xd1, 0x0a, 0x16, 0x24, 0x34, 0xe1, 0x25, 0xf1, 0x17, 0x18, 0x19, 0x1a, 0x26,
+    0x27, 0x28, 0x29, 0x2a, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3a, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48,
+    0x49, 0x4a, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68,
+    0x69, 0x6a, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7a, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87,
+    0x88, 0x89, 0x8a, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9a, 0xa2, 0xa3, 0xa4, 0xa5,
+    0xa6, 0xa7, 0xa8, 0xa9, 0xaa, 0xb2, 0xb3, 0xb4, 0xb5, 0xb6, 0xb7, 0xb8, 0xb9, 0xba, 0xc2, 0xc3,
+    0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xd2, 0xd3, 0xd4, 0xd5, 0xd6, 0xd7, 0xd8, 0xd9, 0xda,
+    0xe2, 0xe3, 0xe4, 0xe5, 0xe6, 0xe7, 0xe8, 0xe9, 0xea, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8,
+    0xf9, 0xfa,
+];
+
+// Code and length of every symbol, built the way decoders rebuild them from the table
+struct HuffmanTable {
+    codes: [(u16, u8); 256],
+}
+
+impl HuffmanTable {
+    fn new(bits: &[u8; 16], values: &[u8]) -> Self {
+        let mut codes = [(0, 0); 256];
+        let mut code = 0u16;
+        let mut values = values.iter();
+
+        for (length, count) in bits.iter().enumerate() {
+            for _ in 0..*count {
+                if let Some(&value) = values.next() {
+                    codes[value as usize] = (code, length as u8 + 1);
+                }
+                code += 1;
+            }
+            code <<= 1;
+        }
+
+        Self { codes }
+    }
+}
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    count: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, bits: u16, length: u8) {
+        for shift in (0..length).rev() {
+            self.buffer = self.buffer << 1 | (bits >> shift & 1) as u32;
+            self.count += 1;
+
+            if self.count == 8 {
+                let byte = self.buffer as u8;
+                self.bytes.push(byte);
+                // A 0xFF in the data would read as a marker, so it is followed by a zero
+                if byte == 0xFF {
+                    self.bytes.push(0x00);
+                }
+                self.buffer = 0;
+                self.count = 0;
+            }
+        }
+    }
+
+    // The last byte is filled up with ones
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.write(0xFF, 8 - self.count);
+        }
+        self.bytes
+    }
+}
+
+// Number of bits of the value and the bits themselves, negative values are stored one less
+fn magnitude(value: i32) -> (u8, u16) {
+    let size = (32 - value.unsigned_abs().leading_zeros()) as u8;
+    let bits = match value < 0 {
+        true => (value - 1) as u16 & ((1u32 << size) - 1) as u16,
+        false => value as u16,
+    };
+    (size, bits)
+}
+
+fn scaled_quantization(table: &[u16; 64], quality: u8) -> [u16; 64] {
+    let quality = quality.clamp(1, 100) as u32;
+    let scale = match quality < 50 {
+        true => 5000 / quality,
+        false => 200 - quality * 2,
+    };
+
+    table.map(|value| ((value as u32 * scale + 50) / 100).clamp(1, 255) as u16)
+}
+
+struct Component {
+    quantization: [u16; 64],
+    dc: HuffmanTable,
+    ac: HuffmanTable,
+    previous_dc: i32,
+}
+
+impl Component {
+    fn encode_block(&mut self, block: &[f32; 64], cosines: &[[f32; 8]; 8], out: &mut BitWriter) {
+        let mut coefficients = [0i32; 64];
+
+        for v in 0..8 {
+            for u in 0..8 {
+                let mut sum = 0.0;
+                for y in 0..8 {
+                    for x in 0..8 {
+                        sum += block[y * 8 + x] * cosines[u][x] * cosines[v][y];
+                    }
+                }
+
+                let cu = if u == 0 { FRAC_1_SQRT_2 } else { 1.0 };
+                let cv = if v == 0 { FRAC_1_SQRT_2 } else { 1.0 };
+                let index = v * 8 + u;
+                coefficients[index] =
+                    (0.25 * cu * cv * sum / self.quantization[index] as f32).round() as i32;
+            }
+        }
+
+        let difference = coefficients[0] - self.previous_dc;
+        self.previous_dc = coefficients[0];
+
+        let (size, bits) = magnitude(difference);
+        let (code, length) = self.dc.codes[size as usize];
+        out.write(code, length);
+        out.write(bits, size);
+
+        let mut zeros = 0;
+        for &index in ZIGZAG[1..].iter() {
+            let value = coefficients[index];
+            if value == 0 {
+                zeros += 1;
+                continue;
+            }
+
+            // Runs longer than 15 zeros are split up
+            while zeros > 15 {
+                let (code, length) = self.ac.codes[0xF0];
+                out.write(code, length);
+                zeros -= 16;
+            }
+
+            let (size, bits) = magnitude(value);
+            let (code, length) = self.ac.codes[(zeros << 4 | size) as usize];
+            out.write(code, length);
+            out.write(bits, size);
+            zeros = 0;
+        }
+
+        if zeros > 0 {
+            let (code, length) = self.ac.codes[0x00];
+            out.write(code, length);
+        }
+    }
+}
+
+fn segment(out: &mut Vec<u8>, marker: u8, data: &[u8]) {
+    out.extend_from_slice(&[0xFF, marker]);
+    out.extend_from_slice(&(data.len() as u16 + 2).to_be_bytes());
+    out.extend_from_slice(data);
+}
+
+// `quality` goes from 1 to 100 like in other encoders
+pub fn encode(image: &RgbImage, quality: u8) -> Vec<u8> {
+    let (width, height) = image.dimensions();
+
+    let luma = scaled_quantization(&LUMA_QUANTIZATION, quality);
+    let chroma = scaled_quantization(&CHROMA_QUANTIZATION, quality);
+
+    let mut out = vec![0xFF, 0xD8];
+    segment(
+        &mut out,
+        0xE0,
+        b"JFIF\0\x01\x01\x00\x00\x01\x00\x01\x00\x00",
+    );
+
+    let mut tables = Vec::new();
+    for (id, table) in [luma, chroma].iter().enumerate() {
+        tables.push(id as u8);
+        tables.extend(ZIGZAG.iter().map(|&index| table[index] as u8));
+    }
+    segment(&mut out, 0xDB, &tables);
+
+    let mut frame = vec![8];
+    frame.extend_from_slice(&(height as u16).to_be_bytes());
+    frame.extend_from_slice(&(width as u16).to_be_bytes());
+    frame.extend_from_slice(&[3, 1, 0x11, 0, 2, 0x11, 1, 3, 0x11, 1]);
+    segment(&mut out, 0xC0, &frame);
+
+    let mut huffman = Vec::new();
+    for (class_id, bits, values) in [
+        (0x00, &LUMA_DC_BITS, &DC_VALUES[..]),
+        (0x10, &LUMA_AC_BITS, &LUMA_AC_VALUES[..]),
+        (0x01, &CHROMA_DC_BITS, &DC_VALUES[..]),
+        (0x11, &CHROMA_AC_BITS, &CHROMA_AC_VALUES[..]),
+    ] {
+        huffman.push(class_id);
+        huffman.extend_from_slice(bits);
+        huffman.extend_from_slice(values);
+    }
+    segment(&mut out, 0xC4, &huffman);
+
+    segment(&mut out, 0xDA, &[3, 1, 0x00, 2, 0x11, 3, 0x11, 0, 63, 0]);
+
+    let mut components = [
+        Component {
+            quantization: luma,
+            dc: HuffmanTable::new(&LUMA_DC_BITS, &DC_VALUES),
+            ac: HuffmanTable::new(&LUMA_AC_BITS, &LUMA_AC_VALUES),
+            previous_dc: 0,
+        },
+        Component {
+            quantization: chroma,
+            dc: HuffmanTable::new(&CHROMA_DC_BITS, &DC_VALUES),
+            ac: HuffmanTable::new(&CHROMA_AC_BITS, &CHROMA_AC_VALUES),
+            previous_dc: 0,
+        },
+        Component {
+            quantization: chroma,
+            dc: HuffmanTable::new(&CHROMA_DC_BITS, &DC_VALUES),
+            ac: HuffmanTable::new(&CHROMA_AC_BITS, &CHROMA_AC_VALUES),
+            previous_dc: 0,
+        },
+    ];
+
+    let mut cosines = [[0.0f32; 8]; 8];
+    for (u, row) in cosines.iter_mut().enumerate() {
+        for (x, value) in row.iter_mut().enumerate() {
+            *value = ((2 * x + 1) as f32 * u as f32 * PI / 16.0).cos();
+        }
+    }
+
+    let mut data = BitWriter {
+        bytes: Vec::new(),
+        buffer: 0,
+        count: 0,
+    };
+
+    for block_y in (0..height).step_by(8) {
+        for block_x in (0..width).step_by(8) {
+            let mut blocks = [[0.0f32; 64]; 3];
+
+            // Blocks past the edge repeat the last row and column instead of fading to black
+            for y in 0..8 {
+                for x in 0..8 {
+                    let pixel = image
+                        .get_pixel((block_x + x).min(width - 1), (block_y + y).min(height - 1));
+                    let [r, g, b] = pixel.0.map(|c| c as f32);
+                    let index = (y * 8 + x) as usize;
+
+                    blocks[0][index] = 0.299 * r + 0.587 * g + 0.114 * b - 128.0;
+                    blocks[1][index] = -0.168_736 * r - 0.331_264 * g + 0.5 * b;
+                    blocks[2][index] = 0.5 * r - 0.418_688 * g - 0.081_312 * b;
+                }
+            }
+
+            for (component, block) in components.iter_mut().zip(blocks.iter()) {
+                component.encode_block(block, &cosines, &mut data);
+            }
+        }
+    }
+
+    out.extend(data.finish());
+    out.extend_from_slice(&[0xFF, 0xD9]);
+    out
+}
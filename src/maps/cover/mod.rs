@@ -0,0 +1,216 @@
+pub mod jpeg;
+
+use std::io::{self, Cursor};
+
+use image::{
+    DynamicImage, ImageEncoder, RgbImage, RgbaImage,
+    codecs::png::{CompressionType, FilterType, PngEncoder},
+    imageops,
+};
+
+// Games show covers as squares, anything else gets stretched or cut off
+pub const ASPECT_RATIO: f32 = 1.0;
+
+// Larger than covers are ever shown, even on high resolution screens
+pub const DEFAULT_MAX_SIZE: u32 = 512;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CoverEncoding {
+    // Lossless and keeps transparency, best for drawn art with flat colors
+    Png,
+    // Lossy from 1 to 100, far smaller for photos
+    Jpeg { quality: u8 },
+}
+
+impl CoverEncoding {
+    pub fn label(&self) -> &'static str {
+        match self {
+            CoverEncoding::Png => "PNG",
+            CoverEncoding::Jpeg { .. } => "JPEG",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CoverOptions {
+    // Cuts the longer side down to the expected aspect ratio, keeping the center
+    pub crop: bool,
+    // Longest side in pixels, larger covers are scaled down
+    pub max_size: u32,
+    pub encoding: CoverEncoding,
+}
+
+impl Default for CoverOptions {
+    fn default() -> Self {
+        Self {
+            crop: true,
+            max_size: DEFAULT_MAX_SIZE,
+            encoding: CoverEncoding::Jpeg { quality: 85 },
+        }
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// Covers are PNG or JPEG, the two formats maps are found with
+pub fn decode(bytes: &[u8]) -> io::Result<RgbaImage> {
+    if bytes.starts_with(b"\x89PNG") {
+        return image::load_from_memory_with_format(bytes, image::ImageFormat::Png)
+            .map(|image| image.into_rgba8())
+            .map_err(|e| invalid(format!("Could not read PNG cover: {e}")));
+    }
+
+    if !bytes.starts_with(&[0xFF, 0xD8]) {
+        return Err(invalid("Cover is neither PNG nor JPEG".to_string()));
+    }
+
+    let mut decoder = jpeg_decoder::Decoder::new(bytes);
+    let pixels = decoder
+        .decode()
+        .map_err(|e| invalid(format!("Could not read JPEG cover: {e}")))?;
+    let info = decoder
+        .info()
+        .ok_or_else(|| invalid("JPEG cover has no size".to_string()))?;
+
+    let rgba: Vec<u8> = match info.pixel_format {
+        jpeg_decoder::PixelFormat::L8 => pixels.iter().flat_map(|&l| [l, l, l, 255]).collect(),
+        // Stored big endian, the high byte is plenty for a cover
+        jpeg_decoder::PixelFormat::L16 => pixels
+            .chunks_exact(2)
+            .flat_map(|l| [l[0], l[0], l[0], 255])
+            .collect(),
+        jpeg_decoder::PixelFormat::RGB24 => pixels
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect(),
+        // Adobe writes CMYK inverted, which makes this the plain product
+        jpeg_decoder::PixelFormat::CMYK32 => pixels
+            .chunks_exact(4)
+            .flat_map(|p| {
+                let k = p[3] as u16;
+                [p[0], p[1], p[2]]
+                    .map(|c| (c as u16 * k / 255) as u8)
+                    .into_iter()
+                    .chain([255])
+            })
+            .collect(),
+    };
+
+    RgbaImage::from_raw(info.width as u32, info.height as u32, rgba)
+        .ok_or_else(|| invalid("JPEG cover has the wrong number of pixels".to_string()))
+}
+
+// Read from the headers only, cheap enough to show every frame
+pub fn dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    if bytes.starts_with(b"\x89PNG") {
+        let size = |offset: usize| {
+            let field = bytes.get(offset..offset + 4)?;
+            Some(u32::from_be_bytes(field.try_into().ok()?))
+        };
+        return Some((size(16)?, size(20)?));
+    }
+
+    let mut decoder = jpeg_decoder::Decoder::new(bytes);
+    decoder.read_info().ok()?;
+    decoder
+        .info()
+        .map(|info| (info.width as u32, info.height as u32))
+}
+
+pub fn crop_to_aspect(image: &RgbaImage, ratio: f32) -> RgbaImage {
+    let (width, height) = image.dimensions();
+
+    let (cropped_width, cropped_height) = match (width as f32 / height as f32) > ratio {
+        true => ((height as f32 * ratio).round() as u32, height),
+        false => (width, (width as f32 / ratio).round() as u32),
+    };
+    let (cropped_width, cropped_height) = (cropped_width.max(1), cropped_height.max(1));
+
+    imageops::crop_imm(
+        image,
+        (width - cropped_width) / 2,
+        (height - cropped_height) / 2,
+        cropped_width,
+        cropped_height,
+    )
+    .to_image()
+}
+
+// Never scales up, that only makes the file larger
+pub fn downscale(image: RgbaImage, max_size: u32) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let longest = width.max(height);
+
+    if longest <= max_size || max_size == 0 {
+        return image;
+    }
+
+    let scale = max_size as f32 / longest as f32;
+    let new_width = ((width as f32 * scale).round() as u32).max(1);
+    let new_height = ((height as f32 * scale).round() as u32).max(1);
+
+    imageops::resize(
+        &image,
+        new_width,
+        new_height,
+        imageops::FilterType::Lanczos3,
+    )
+}
+
+pub fn encode(image: &RgbaImage, encoding: CoverEncoding) -> io::Result<Vec<u8>> {
+    match encoding {
+        CoverEncoding::Png => {
+            let mut bytes = Vec::new();
+            let encoder = PngEncoder::new_with_quality(
+                Cursor::new(&mut bytes),
+                CompressionType::Best,
+                FilterType::Adaptive,
+            );
+
+            // The alpha channel is only kept when something is see through
+            let opaque = image.pixels().all(|pixel| pixel.0[3] == 255);
+            let result = match opaque {
+                true => {
+                    let rgb = DynamicImage::ImageRgba8(image.clone()).into_rgb8();
+                    encoder.write_image(
+                        &rgb,
+                        rgb.width(),
+                        rgb.height(),
+                        image::ExtendedColorType::Rgb8,
+                    )
+                }
+                false => encoder.write_image(
+                    image,
+                    image.width(),
+                    image.height(),
+                    image::ExtendedColorType::Rgba8,
+                ),
+            };
+
+            result.map_err(|e| invalid(format!("Could not write PNG cover: {e}")))?;
+            Ok(bytes)
+        }
+        CoverEncoding::Jpeg { quality } => Ok(jpeg::encode(&flatten(image), quality)),
+    }
+}
+
+// JPEG has no transparency, see through parts end up black
+fn flatten(image: &RgbaImage) -> RgbImage {
+    RgbImage::from_fn(image.width(), image.height(), |x, y| {
+        let [r, g, b, a] = image.get_pixel(x, y).0;
+        image::Rgb([r, g, b].map(|c| (c as u16 * a as u16 / 255) as u8))
+    })
+}
+
+// Everything applied in order, the result is ready to be stored in a map
+pub fn process(bytes: &[u8], options: &CoverOptions) -> io::Result<Vec<u8>> {
+    let mut image = decode(bytes)?;
+
+    if options.crop {
+        image = crop_to_aspect(&image, ASPECT_RATIO);
+    }
+
+    encode(&downscale(image, options.max_size), options.encoding)
+}
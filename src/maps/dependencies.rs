@@ -0,0 +1,49 @@
+use std::{
+    fs,
+    io::{self, Cursor},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    maps::Map,
+    modchart::sidecar::{self, read_modchart, sidecar_path},
+};
+
+// Files that belong to a map without being part of it, found next to the map file. Only the
+// modchart sidecar so far, maps embed their audio, cover and hitsounds. None of them are
+// required, a map without them still plays
+pub fn dependencies(map_path: &Path) -> Vec<PathBuf> {
+    vec![sidecar_path(map_path)]
+}
+
+// Dependencies are read along with the map so a broken one shows up when the map is loaded
+// instead of once it is played
+pub fn check_dependency(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(sidecar::EXTENSION) => read_modchart(Cursor::new(bytes)).map(|_| ()),
+        _ => Ok(()),
+    }
+}
+
+pub fn dependency_warning(path: &Path, error: &io::Error) -> String {
+    let name = path
+        .file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy();
+    format!("{name} could not be read: {error}")
+}
+
+// For maps read outside of the asset server, problems end up with the map's load warnings
+pub fn check_dependencies(map: &mut Map, map_path: &Path) {
+    for path in dependencies(map_path) {
+        let result = match fs::read(&path) {
+            Ok(bytes) => check_dependency(&path, &bytes),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => Err(e),
+        };
+
+        if let Err(e) = result {
+            map.load_warnings.push(dependency_warning(&path, &e));
+        }
+    }
+}
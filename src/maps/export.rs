@@ -0,0 +1,395 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use bevy::log::{info_span, warn};
+
+use crate::{
+    jukebox::audio_extension,
+    maps::{
+        Map, MapFormat,
+        io::WriteOptions,
+        legacy::LegacySerializer,
+        limit::{LimitCheck, SizeLimit, fit_to_limit},
+        parser::{MapSerializer, PHXMParser, SSPMSerializer, is_quantum_within},
+        size::SizeReport,
+    },
+};
+
+// How many notes listed by name before the rest are only counted
+const LISTED_NOTES: usize = 5;
+
+// Notes just off a whole cell are snapped onto the grid when written, closer than `epsilon`
+pub fn grid_precision_warning(map: &Map, epsilon: f32) -> Option<String> {
+    let snapped: Vec<_> = map
+        .notes
+        .iter()
+        .filter(|note| {
+            !is_quantum_within(note.position, epsilon) && note.position != note.position.round()
+        })
+        .collect();
+    if snapped.is_empty() {
+        return None;
+    }
+
+    let mut listed: Vec<String> = snapped
+        .iter()
+        .take(LISTED_NOTES)
+        .map(|note| {
+            format!(
+                "{}ms at ({}, {})",
+                note.millisecond, note.position.x, note.position.y
+            )
+        })
+        .collect();
+    if snapped.len() > LISTED_NOTES {
+        listed.push(format!("{} more", snapped.len() - LISTED_NOTES));
+    }
+
+    Some(format!(
+        "{} notes are within {epsilon} of a cell and are snapped onto it: {}",
+        snapped.len(),
+        listed.join(", ")
+    ))
+}
+
+#[derive(Debug)]
+pub struct ExportResult {
+    pub format: MapFormat,
+    pub path: PathBuf,
+    pub result: io::Result<()>,
+    pub warnings: Vec<String>,
+    // Only measured when the export succeeded
+    pub size: Option<SizeReport>,
+    // Only checked when a size limit is set
+    pub limit: Option<LimitCheck>,
+}
+
+impl MapFormat {
+    pub fn serialize<T: Write + Seek>(
+        &self,
+        map: &Map,
+        writer: T,
+        options: WriteOptions,
+    ) -> io::Result<()> {
+        match self {
+            MapFormat::SSPM => SSPMSerializer::serialize_with(map, writer, options),
+            MapFormat::PHXM => PHXMParser::serialize_with(map, writer, options),
+            MapFormat::Legacy => LegacySerializer::serialize_with(map, writer, options),
+        }
+    }
+
+    // Everything in the map that this format drops or stores in a lossy way
+    pub fn warnings(&self, map: &Map, options: WriteOptions) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        match self {
+            MapFormat::SSPM => {
+                let unsupported = map
+                    .objects
+                    .iter()
+                    .filter(|object| object.sspm_types().is_none())
+                    .count();
+                if unsupported > 0 {
+                    warnings.push(format!(
+                        "{unsupported} custom objects have fields SSPM can't store and are dropped"
+                    ));
+                }
+
+                if !map.mod_events.is_empty() {
+                    warnings.push(
+                        "Imported mods are kept in the modchart file, not in the map".to_string(),
+                    );
+                }
+
+                if !map.artists.is_empty() {
+                    warnings.push("SSPM has no artist field, artists are dropped".to_string());
+                }
+
+                if !map.timing_points.is_empty() {
+                    warnings.push(
+                        "Timing points are stored as mm_timing_point objects, other games ignore them"
+                            .to_string(),
+                    );
+                }
+
+                if !map.speed_changes.is_empty() {
+                    warnings.push(
+                        "Speed changes are stored as mm_speed_change objects, other games ignore them"
+                            .to_string(),
+                    );
+                }
+
+                warnings.extend(grid_precision_warning(map, options.grid_epsilon));
+
+                let open = map.annotations.iter().filter(|a| !a.resolved).count();
+                if open > 0 {
+                    warnings.push(format!(
+                        "{open} review comments are still open and are saved into the map"
+                    ));
+                }
+            }
+            MapFormat::PHXM => {
+                if !map.objects.is_empty() {
+                    warnings.push(format!(
+                        "PHXM has no custom objects, {} are dropped",
+                        map.objects.len()
+                    ));
+                }
+                if !map.mod_events.is_empty() {
+                    warnings.push("PHXM has no mods, the imported ones are dropped".to_string());
+                }
+                if !map.timing_points.is_empty() {
+                    warnings.push(format!(
+                        "PHXM has no timing points, {} are dropped",
+                        map.timing_points.len()
+                    ));
+                }
+
+                if !map.speed_changes.is_empty() {
+                    warnings.push(format!(
+                        "PHXM has no speed changes, {} are dropped",
+                        map.speed_changes.len()
+                    ));
+                }
+
+                if !map.bookmarks.is_empty() {
+                    warnings.push(format!(
+                        "PHXM has no bookmarks, {} are dropped",
+                        map.bookmarks.len()
+                    ));
+                }
+
+                if !map.annotations.is_empty() {
+                    warnings.push(format!(
+                        "PHXM has no review comments, {} are dropped",
+                        map.annotations.len()
+                    ));
+                }
+
+                if !map.lyrics.is_empty() {
+                    warnings.push(format!(
+                        "PHXM has no lyrics, {} are dropped",
+                        map.lyrics.len()
+                    ));
+                }
+
+                let hitsounds = map.notes.iter().filter(|n| n.hitsound.is_some()).count();
+                if hitsounds > 0 {
+                    warnings.push(format!("PHXM has no hitsounds, {hitsounds} are dropped"));
+                }
+
+                let colors = map.notes.iter().filter(|n| n.color.is_some()).count();
+                if colors > 0 {
+                    warnings.push(format!("PHXM has no note colors, {colors} are dropped"));
+                }
+
+                if map.artists.len() > 1 {
+                    warnings.push("Artists are joined into a single field".to_string());
+                }
+
+                if !map.cover.is_empty() && !map.cover.starts_with(b"\x89PNG") {
+                    warnings.push("Cover is not a PNG but is stored as cover.png".to_string());
+                }
+
+                if map
+                    .audio
+                    .as_ref()
+                    .is_some_and(|audio| audio_extension(&audio.bytes).is_none())
+                {
+                    warnings.push("Unknown audio format, stored as audio.mp3".to_string());
+                }
+            }
+            MapFormat::Legacy => {
+                warnings.push(
+                    "Legacy text maps only keep the id and the notes, everything else is dropped"
+                        .to_string(),
+                );
+
+                if map.id.contains([',', '|']) {
+                    warnings.push("Commas and bars are removed from the id".to_string());
+                }
+
+                warnings.extend(grid_precision_warning(map, options.grid_epsilon));
+            }
+        }
+
+        warnings
+    }
+}
+
+// Shared starting point for every format so they all export exactly the same chart
+pub fn normalize(map: &Map) -> Map {
+    let mut map = map.clone();
+
+    map.id = map.id.trim().to_string();
+    map.title = map.title.trim().to_string();
+    map.difficulty_name = map.difficulty_name.trim().to_string();
+
+    for names in [&mut map.artists, &mut map.mappers] {
+        *names = names
+            .iter()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect();
+    }
+
+    // Ids differ between notes placed twice, so duplicates are found by time and position
+    map.notes.sort_by(|a, b| {
+        a.millisecond
+            .cmp(&b.millisecond)
+            .then(a.position.x.total_cmp(&b.position.x))
+            .then(a.position.y.total_cmp(&b.position.y))
+    });
+    map.notes
+        .dedup_by(|a, b| a.millisecond == b.millisecond && a.position == b.position);
+
+    map.timing_points.sort_by_key(|timing| timing.millisecond);
+    map.timing_points.dedup_by_key(|timing| timing.millisecond);
+
+    map.speed_changes.sort_by_key(|change| change.millisecond);
+    map.speed_changes.dedup_by_key(|change| change.millisecond);
+
+    map.bookmarks.sort_by_key(|bookmark| bookmark.millisecond);
+    map.annotations
+        .sort_by_key(|annotation| annotation.millisecond);
+    map.lyrics.sort_by_key(|lyric| lyric.millisecond);
+
+    if let Some(last) = map.notes.last() {
+        map.length = map.length.max(last.millisecond);
+    }
+
+    map
+}
+
+// Fills in a filename template such as "{artist} - {title} [{diff_name}]". A trailing extension,
+// written out or as {ext}, is replaced by the one of the format being exported.
+pub fn export_filename(map: &Map, format: MapFormat, template: &str) -> String {
+    template_filename(map, template, format.extension())
+}
+
+// Same as export_filename for files that aren't maps, such as chart cards
+pub fn template_filename(map: &Map, template: &str, extension: &str) -> String {
+    let mut name = template.to_string();
+
+    for (key, value) in [
+        ("{id}", map.id.clone()),
+        ("{title}", map.title.clone()),
+        ("{artist}", map.artists.join(", ")),
+        ("{mappers}", map.mappers.join(", ")),
+        ("{diff_name}", map.difficulty_name.clone()),
+        ("{difficulty}", map.difficulty.to_string()),
+        ("{ext}", extension.to_string()),
+    ] {
+        name = name.replace(key, &value);
+    }
+
+    let mut known: Vec<&str> = MapFormat::ALL.iter().map(MapFormat::extension).collect();
+    known.push(extension);
+    for known in known {
+        if let Some(stem) = name.strip_suffix(&format!(".{known}")) {
+            name = stem.to_string();
+            break;
+        }
+    }
+
+    format!("{}.{extension}", sanitize_filename(&name))
+}
+
+// Makes a name safe to use as a file on every platform the editor runs on
+pub fn sanitize_filename(name: &str) -> String {
+    let mut clean: String = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    // Placeholders that were empty leave brackets and double spaces behind
+    for empty in ["[]", "()", "[ ]", "( )"] {
+        clean = clean.replace(empty, "");
+    }
+    let mut clean = clean
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_matches(|c: char| c == '-' || c.is_whitespace())
+        .trim_end_matches('.')
+        .trim()
+        .to_string();
+
+    const RESERVED: [&str; 22] = [
+        "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+        "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+    ];
+
+    let stem = clean.split('.').next().unwrap_or_default();
+    if RESERVED
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+    {
+        clean.insert(0, '_');
+    }
+
+    match clean.is_empty() {
+        true => "untitled".to_string(),
+        false => clean,
+    }
+}
+
+pub fn export_all(
+    map: &Map,
+    folder: &Path,
+    template: &str,
+    limit: &SizeLimit,
+    options: WriteOptions,
+) -> Vec<ExportResult> {
+    let normalized = normalize(map);
+
+    MapFormat::ALL
+        .iter()
+        .map(|format| {
+            let path = folder.join(export_filename(&normalized, *format, template));
+            let _span = info_span!("export", format = ?format, path = %path.display()).entered();
+
+            // Shrinking changes the cover and audio of this format's copy only
+            let mut map = normalized.clone();
+            let (limit, result) = match fit_to_limit(&mut map, *format, limit, options) {
+                Ok(check) => {
+                    let result = fs::create_dir_all(folder)
+                        .and_then(|_| File::create(&path))
+                        .and_then(|file| {
+                            let mut writer = BufWriter::new(file);
+                            format.serialize(&map, &mut writer, options)?;
+                            writer.flush()
+                        });
+                    (check, result)
+                }
+                Err(e) => (None, Err(e)),
+            };
+
+            let size = match &result {
+                Ok(()) => SizeReport::new(&map, *format, &path, options)
+                    .inspect_err(|e| warn!("Could not measure {}: {e}", path.display()))
+                    .ok(),
+                Err(_) => None,
+            };
+
+            let mut warnings = format.warnings(&map, options);
+            warnings.extend(limit.iter().flat_map(LimitCheck::warnings));
+
+            ExportResult {
+                format: *format,
+                path,
+                result,
+                warnings,
+                size,
+                limit,
+            }
+        })
+        .collect()
+}
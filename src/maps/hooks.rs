@@ -0,0 +1,195 @@
+use std::{
+    ffi::OsStr,
+    io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use serde::{Deserialize, Serialize};
+
+// A command run on everything an export writes, to copy it into a game folder, upload it or check
+// it with another tool. Run without a shell so titles in the path can't turn into commands
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ExportHook {
+    pub name: String,
+    // "{path}", "{folder}" and "{name}" are replaced in every argument, the path is added as the
+    // last argument when none of them are used
+    pub command: String,
+    pub enabled: bool,
+}
+
+impl Default for ExportHook {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            command: String::new(),
+            enabled: true,
+        }
+    }
+}
+
+impl ExportHook {
+    pub fn label(&self) -> String {
+        match self.name.trim() {
+            "" => self
+                .command
+                .split_whitespace()
+                .next()
+                .unwrap_or("Hook")
+                .to_string(),
+            name => name.to_string(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct HookRun {
+    pub hook: String,
+    pub path: PathBuf,
+    // What the command printed, or why it failed
+    pub result: io::Result<String>,
+}
+
+// Splits on whitespace, quotes group an argument. Backslashes are kept as they are since Windows
+// paths are full of them
+pub fn split_command(command: &str) -> io::Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote = None;
+
+    for c in command.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "The command has an unclosed quote",
+        ));
+    }
+    if in_arg {
+        args.push(current);
+    }
+
+    Ok(args)
+}
+
+pub fn hook_args(command: &str, path: &Path) -> io::Result<Vec<String>> {
+    let mut args = split_command(command)?;
+    if args.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "The hook has no command",
+        ));
+    }
+
+    let folder = path.parent().unwrap_or(Path::new(""));
+    let name = path.file_name().unwrap_or_default();
+    let placeholders = [
+        ("{path}", path.as_os_str()),
+        ("{folder}", folder.as_os_str()),
+        ("{name}", name),
+    ];
+
+    let used = args
+        .iter()
+        .any(|arg| placeholders.iter().any(|(key, _)| arg.contains(key)));
+    if !used {
+        args.push(path.to_string_lossy().into_owned());
+        return Ok(args);
+    }
+
+    for arg in args.iter_mut() {
+        *arg = substitute(arg, &placeholders);
+    }
+
+    Ok(args)
+}
+
+// One pass from the left, so a placeholder in a substituted path is left as it is
+fn substitute(arg: &str, placeholders: &[(&str, &OsStr)]) -> String {
+    let mut substituted = String::new();
+    let mut rest = arg;
+
+    while !rest.is_empty() {
+        match placeholders.iter().find(|(key, _)| rest.starts_with(key)) {
+            Some((key, value)) => {
+                substituted.push_str(&value.to_string_lossy());
+                rest = &rest[key.len()..];
+            }
+            None => {
+                let next = rest.chars().next().map_or(1, char::len_utf8);
+                substituted.push_str(&rest[..next]);
+                rest = &rest[next..];
+            }
+        }
+    }
+
+    substituted
+}
+
+pub fn run_hook(hook: &ExportHook, path: &Path) -> io::Result<String> {
+    // The app may run from anywhere, the command gets the full path
+    let path = std::path::absolute(path)?;
+    let args = hook_args(&hook.command, &path)?;
+
+    let output = Command::new(&args[0])
+        .args(&args[1..])
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| io::Error::new(e.kind(), format!("Could not start {}: {e}", args[0])))?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::other(format!(
+            "{} failed with {}: {}",
+            args[0],
+            output.status,
+            error.trim()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// Enabled hooks run one after another in the order they're listed. One that fails stops the rest,
+// so a validator listed first keeps a broken map out of the game folder
+pub fn run_hooks(hooks: &[ExportHook], path: &Path) -> Vec<HookRun> {
+    let mut runs = Vec::new();
+
+    for hook in hooks.iter().filter(|hook| hook.enabled) {
+        let result = run_hook(hook, path);
+        let failed = result.is_err();
+
+        runs.push(HookRun {
+            hook: hook.label(),
+            path: path.to_path_buf(),
+            result,
+        });
+
+        if failed {
+            break;
+        }
+    }
+
+    runs
+}
@@ -0,0 +1,151 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use bevy::log::warn;
+
+use crate::maps::{
+    Map,
+    io::WriteOptions,
+    parser::{SSPMSections, SSPMSerializer},
+};
+
+// Room left in front of the audio so renames, new mappers and custom data don't force a full save
+const METADATA_PADDING: u64 = 16 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveKind {
+    Full,
+    Incremental,
+}
+
+// What the file on disk looked like after the last save. The audio and cover are the large but
+// rarely changing part of a map, as long as they match the file only needs its head and objects
+// rewritten.
+struct SaveCache {
+    sections: SSPMSections,
+    audio: Option<Arc<[u8]>>,
+    cover: Vec<u8>,
+    length: u64,
+    modified: Option<SystemTime>,
+}
+
+impl SaveCache {
+    fn matches(&self, map: &Map, path: &Path) -> bool {
+        let audio_unchanged = match (&self.audio, &map.audio) {
+            (None, None) => true,
+            (Some(cached), Some(audio)) => {
+                Arc::ptr_eq(cached, &audio.bytes) || cached[..] == audio.bytes[..]
+            }
+            _ => false,
+        };
+
+        // Anything else touching the file invalidates the cached layout
+        let file_unchanged = fs::metadata(path).is_ok_and(|metadata| {
+            metadata.len() == self.length && metadata.modified().ok() == self.modified
+        });
+
+        audio_unchanged && self.cover == map.cover && file_unchanged
+    }
+}
+
+pub struct IncrementalSave {
+    path: PathBuf,
+    cache: Option<SaveCache>,
+}
+
+impl IncrementalSave {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, cache: None }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Falls back to a full save whenever the cached layout can't be trusted
+    pub fn save(&mut self, map: &Map) -> io::Result<SaveKind> {
+        if let Some(cache) = self
+            .cache
+            .take()
+            .filter(|cache| cache.matches(map, &self.path))
+        {
+            match self.save_incremental(map, &cache.sections) {
+                Ok(Some(sections)) => {
+                    self.cache = Some(self.cache_for(map, sections)?);
+                    return Ok(SaveKind::Incremental);
+                }
+                Ok(None) => {}
+                Err(e) => warn!(
+                    "Incremental save of {} failed: {e}, writing the whole map",
+                    self.path.display()
+                ),
+            }
+        }
+
+        let sections = self.save_full(map)?;
+        self.cache = Some(self.cache_for(map, sections)?);
+
+        Ok(SaveKind::Full)
+    }
+
+    fn save_incremental(
+        &self,
+        map: &Map,
+        previous: &SSPMSections,
+    ) -> io::Result<Option<SSPMSections>> {
+        let file = OpenOptions::new().write(true).open(&self.path)?;
+        let mut writer = BufWriter::new(file);
+
+        let Some((sections, end)) = SSPMSerializer::rewrite_objects(map, &mut writer, previous)?
+        else {
+            return Ok(None);
+        };
+
+        writer.flush()?;
+        writer
+            .into_inner()
+            .map_err(|e| e.into_error())?
+            .set_len(end)?;
+
+        Ok(Some(sections))
+    }
+
+    // Written next to the target first so a failed save never leaves a broken map behind
+    fn save_full(&self, map: &Map) -> io::Result<SSPMSections> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let temporary = self.path.with_extension("sspm.tmp");
+        let mut writer = BufWriter::new(File::create(&temporary)?);
+        let sections = SSPMSerializer::serialize_padded(
+            map,
+            &mut writer,
+            METADATA_PADDING,
+            WriteOptions::default(),
+        )?;
+        writer.flush()?;
+        drop(writer);
+
+        fs::rename(&temporary, &self.path)?;
+
+        Ok(sections)
+    }
+
+    fn cache_for(&self, map: &Map, sections: SSPMSections) -> io::Result<SaveCache> {
+        let metadata = fs::metadata(&self.path)?;
+
+        Ok(SaveCache {
+            sections,
+            audio: map.audio.as_ref().map(|audio| audio.bytes.clone()),
+            cover: map.cover.clone(),
+            length: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+}
@@ -1,24 +1,108 @@
 use std::io::{self, Read, Seek, SeekFrom, Write};
 
-use bevy::{app::PanicHandlerPlugin, math::Vec2};
+use bevy::math::{Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::maps::parser::{is_quantum_value_within, is_quantum_within};
+
+// Lengths are written in front of strings and buffers, longer ones can't be read back
+fn too_long(length: usize, max: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("{length} bytes don't fit in a field of at most {max}"),
+    )
+}
+
+// How maps are read. Old community maps have Latin-1 metadata that isn't valid UTF-8, which is
+// replaced with a warning instead of failing the load unless lossy strings are turned off
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ReadOptions {
+    pub lossy_strings: bool,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self {
+            lossy_strings: true,
+        }
+    }
+}
+
+// Positions at least this far from a whole cell are written as floats, closer ones are rounded
+// onto the grid
+pub const DEFAULT_GRID_EPSILON: f32 = 0.005;
+
+// How maps are written, exports take theirs from the export settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WriteOptions {
+    pub grid_epsilon: f32,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        Self {
+            grid_epsilon: DEFAULT_GRID_EPSILON,
+        }
+    }
+}
+
+// Invalid sequences are replaced and noted in `warnings` when `lossy` is set
+pub fn decode_string(
+    bytes: Vec<u8>,
+    lossy: bool,
+    warnings: &mut Vec<String>,
+) -> io::Result<String> {
+    match String::from_utf8(bytes) {
+        Ok(s) => Ok(s),
+        Err(e) if lossy => {
+            let s = String::from_utf8_lossy(e.as_bytes()).into_owned();
+            warnings.push(format!("Replaced invalid UTF-8 in \"{s}\""));
+            Ok(s)
+        }
+        Err(_) => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Invalid UTF-8 sequence",
+        )),
+    }
+}
 
 pub struct BinaryReader<T: Read + Seek> {
     reader: T,
+    options: ReadOptions,
+    warnings: Vec<String>,
 }
 
 pub struct BinaryWriter<T: Write + Seek> {
     writer: T,
+    options: WriteOptions,
 }
 
 impl<T: Seek + Read> BinaryReader<T> {
     pub fn new(reader: T) -> Self {
-        Self { reader }
+        Self::with_options(reader, ReadOptions::default())
+    }
+
+    pub fn with_options(reader: T, options: ReadOptions) -> Self {
+        Self {
+            reader,
+            options,
+            warnings: Vec::new(),
+        }
     }
 
     pub fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
         self.reader.seek(pos)
     }
 
+    pub fn warn(&mut self, warning: String) {
+        self.warnings.push(warning);
+    }
+
+    // Everything that was read but had to be fixed up on the way, like lossy strings
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+
     pub fn stream_position(&mut self) -> io::Result<u64> {
         self.reader.stream_position()
     }
@@ -70,15 +154,7 @@ impl<T: Seek + Read> BinaryReader<T> {
         let mut buffer = vec![0u8; buf as usize];
         self.reader.read_exact(&mut buffer)?;
 
-        let str = String::from_utf8(buffer);
-
-        match str {
-            Ok(s) => Ok(s),
-            Err(_) => Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Invalid UTF-8 sequence",
-            )),
-        }
+        decode_string(buffer, self.options.lossy_strings, &mut self.warnings)
     }
 
     pub fn read_long_string(&mut self) -> io::Result<String> {
@@ -86,15 +162,7 @@ impl<T: Seek + Read> BinaryReader<T> {
         let mut buffer = vec![0u8; buf as usize];
         self.reader.read_exact(&mut buffer)?;
 
-        let str = String::from_utf8(buffer);
-
-        match str {
-            Ok(s) => Ok(s),
-            Err(_) => Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Invalid UTF-8 sequence",
-            )),
-        }
+        decode_string(buffer, self.options.lossy_strings, &mut self.warnings)
     }
 
     pub fn read_sha1(&mut self) -> io::Result<[u8; 20]> {
@@ -144,11 +212,92 @@ impl<T: Seek + Read> BinaryReader<T> {
     pub fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
         self.reader.read_exact(buf)
     }
+
+    fn read_array<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let mut buf = [0u8; N];
+        self.reader.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    // Big-endian variants for formats other than SSPM, like MIDI
+    pub fn read_u16_be(&mut self) -> io::Result<u16> {
+        Ok(u16::from_be_bytes(self.read_array()?))
+    }
+
+    // MIDI tempo events store microseconds per beat in 3 bytes
+    pub fn read_u24_be(&mut self) -> io::Result<u32> {
+        let [a, b, c] = self.read_array()?;
+        Ok(u32::from_be_bytes([0, a, b, c]))
+    }
+
+    pub fn read_u32_be(&mut self) -> io::Result<u32> {
+        Ok(u32::from_be_bytes(self.read_array()?))
+    }
+
+    pub fn read_u64_be(&mut self) -> io::Result<u64> {
+        Ok(u64::from_be_bytes(self.read_array()?))
+    }
+
+    pub fn read_f32_be(&mut self) -> io::Result<f32> {
+        Ok(f32::from_be_bytes(self.read_array()?))
+    }
+
+    pub fn read_f64_be(&mut self) -> io::Result<f64> {
+        Ok(f64::from_be_bytes(self.read_array()?))
+    }
+
+    // 7 bits per byte, lowest group first, the high bit is set on every byte but the last
+    pub fn read_varint(&mut self) -> io::Result<u64> {
+        let mut value = 0u64;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_u8()?;
+
+            // The tenth byte only has room for the highest bit
+            if shift == 63 && byte > 0x01 {
+                break;
+            }
+            value |= ((byte & 0x7F) as u64) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Varint is longer than 64 bits",
+        ))
+    }
+
+    // MIDI's variable length quantity, like a varint but with the highest group first and at
+    // most 4 bytes
+    pub fn read_vlq(&mut self) -> io::Result<u32> {
+        let mut value = 0u32;
+
+        for _ in 0..4 {
+            let byte = self.read_u8()?;
+            value = (value << 7) | (byte & 0x7F) as u32;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Variable length quantity is longer than 4 bytes",
+        ))
+    }
 }
 
 impl<T: Write + Seek> BinaryWriter<T> {
     pub fn new(writer: T) -> Self {
-        Self { writer }
+        Self::with_options(writer, WriteOptions::default())
+    }
+
+    pub fn with_options(writer: T, options: WriteOptions) -> Self {
+        Self { writer, options }
     }
 
     pub fn write_bool(&mut self, value: bool) -> io::Result<()> {
@@ -180,16 +329,117 @@ impl<T: Write + Seek> BinaryWriter<T> {
         self.writer.write_all(&value.to_le_bytes())
     }
 
+    // Lengths are counted in bytes like the reader does, not characters
     pub fn write_string(&mut self, value: &str) -> io::Result<()> {
-        let len = value.len() as u16;
-        self.writer.write_all(&len.to_le_bytes())?;
-        self.writer.write_all(value.as_bytes())
+        self.write_buf(value.as_bytes())
     }
 
     pub fn write_long_string(&mut self, value: &str) -> io::Result<()> {
-        let len = value.len() as u32;
+        self.write_long_buf(value.as_bytes())
+    }
+
+    pub fn write_buf(&mut self, value: &[u8]) -> io::Result<()> {
+        let len =
+            u16::try_from(value.len()).map_err(|_| too_long(value.len(), u16::MAX as usize))?;
+        self.writer.write_all(&len.to_le_bytes())?;
+        self.writer.write_all(value)
+    }
+
+    pub fn write_long_buf(&mut self, value: &[u8]) -> io::Result<()> {
+        let len =
+            u32::try_from(value.len()).map_err(|_| too_long(value.len(), u32::MAX as usize))?;
         self.writer.write_all(&len.to_le_bytes())?;
-        self.writer.write_all(value.as_bytes())
+        self.writer.write_all(value)
+    }
+
+    // Whole grid cells are written as u8s, rounded since positions can be a hair off a cell
+    pub fn write_vec2(&mut self, value: Vec2) -> io::Result<()> {
+        let quantum = is_quantum_within(value, self.options.grid_epsilon);
+        self.write_bool(quantum)?;
+
+        match quantum {
+            true => {
+                self.write_f32(value.x)?;
+                self.write_f32(value.y)
+            }
+            false => {
+                self.write_u8(value.x.round() as u8)?;
+                self.write_u8(value.y.round() as u8)
+            }
+        }
+    }
+
+    pub fn write_vec3(&mut self, value: Vec3) -> io::Result<()> {
+        let quantum = value
+            .to_array()
+            .into_iter()
+            .any(|component| is_quantum_value_within(component, self.options.grid_epsilon));
+        self.write_bool(quantum)?;
+
+        for component in value.to_array() {
+            match quantum {
+                true => self.write_f32(component)?,
+                false => self.write_u8(component.round() as u8)?,
+            }
+        }
+        Ok(())
+    }
+
+    pub fn write_u16_be(&mut self, value: u16) -> io::Result<()> {
+        self.writer.write_all(&value.to_be_bytes())
+    }
+
+    pub fn write_u24_be(&mut self, value: u32) -> io::Result<()> {
+        if value > 0xFF_FFFF {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{value} doesn't fit in 3 bytes"),
+            ));
+        }
+        self.writer.write_all(&value.to_be_bytes()[1..])
+    }
+
+    pub fn write_u32_be(&mut self, value: u32) -> io::Result<()> {
+        self.writer.write_all(&value.to_be_bytes())
+    }
+
+    pub fn write_u64_be(&mut self, value: u64) -> io::Result<()> {
+        self.writer.write_all(&value.to_be_bytes())
+    }
+
+    pub fn write_f32_be(&mut self, value: f32) -> io::Result<()> {
+        self.writer.write_all(&value.to_be_bytes())
+    }
+
+    pub fn write_f64_be(&mut self, value: f64) -> io::Result<()> {
+        self.writer.write_all(&value.to_be_bytes())
+    }
+
+    pub fn write_varint(&mut self, mut value: u64) -> io::Result<()> {
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+
+            if value == 0 {
+                return self.write_u8(byte);
+            }
+            self.write_u8(byte | 0x80)?;
+        }
+    }
+
+    pub fn write_vlq(&mut self, value: u32) -> io::Result<()> {
+        if value > 0x0FFF_FFFF {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{value} doesn't fit in a variable length quantity"),
+            ));
+        }
+
+        let groups = (1..4).take_while(|i| value >> (7 * i) != 0).count();
+        for i in (1..=groups).rev() {
+            self.write_u8(((value >> (7 * i)) & 0x7F) as u8 | 0x80)?;
+        }
+        self.write_u8((value & 0x7F) as u8)
     }
 
     pub fn write_sha1(&mut self, value: &[u8; 20]) -> io::Result<()> {
@@ -207,4 +457,8 @@ impl<T: Write + Seek> BinaryWriter<T> {
     pub fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
         self.writer.seek(pos)
     }
+
+    pub fn into_inner(self) -> T {
+        self.writer
+    }
 }
@@ -0,0 +1,149 @@
+use std::{
+    fs::File,
+    io::{self, Read, Seek, Write},
+    path::Path,
+};
+
+use bevy::math::Vec2;
+
+use crate::maps::{
+    Map, MapFormat,
+    io::{ReadOptions, WriteOptions, decode_string},
+    objects::{Note, NoteId},
+    parser::{MapSerializer, is_quantum_within},
+};
+
+// The original Sound Space text maps, a single line of "id,x|y|ms,x|y|ms,...". Early converters
+// wrote the time first instead, both are read and the order is told apart by which field goes
+// past the grid. Written in the order the game itself reads.
+pub struct LegacySerializer;
+
+// Positions further out than this can only be times
+const OFF_GRID: f64 = 3.0;
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// Enough of a text file to tell a map from any other text
+const SNIFF_BYTES: u64 = 4096;
+
+// Map folders also hold readmes and credits as .txt files, only ones that start like a map, an id
+// followed by an x|y|ms note, are taken for maps
+pub fn looks_like_legacy(path: &Path) -> bool {
+    let mut start = Vec::new();
+    File::open(path)
+        .and_then(|file| file.take(SNIFF_BYTES).read_to_end(&mut start))
+        .is_ok_and(|_| is_legacy_start(&String::from_utf8_lossy(&start)))
+}
+
+pub fn is_legacy_start(text: &str) -> bool {
+    let mut fields = text.trim_start_matches('\u{feff}').trim_start().split(',');
+    let id = fields.next().unwrap_or_default();
+
+    !id.contains(['\n', '\r'])
+        && fields.next().is_some_and(|note| {
+            let values: Vec<&str> = note.split('|').collect();
+            values.len() == 3 && values.iter().all(|v| v.trim().parse::<f64>().is_ok())
+        })
+}
+
+// Separators in the id would split it into notes
+pub fn legacy_id(id: &str) -> String {
+    id.chars().filter(|c| !matches!(c, ',' | '|')).collect()
+}
+
+impl MapSerializer for LegacySerializer {
+    fn serialize_with<T: Write + Seek>(
+        map: &Map,
+        mut writer: T,
+        options: WriteOptions,
+    ) -> io::Result<()> {
+        write!(writer, "{}", legacy_id(&map.id))?;
+
+        for note in map.notes.iter() {
+            let position = match is_quantum_within(note.position, options.grid_epsilon) {
+                true => note.position,
+                false => note.position.round(),
+            };
+            write!(
+                writer,
+                ",{}|{}|{}",
+                position.x, position.y, note.millisecond
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn deserialize_with<T: Read + Seek>(mut reader: T, options: ReadOptions) -> io::Result<Map> {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+
+        let mut load_warnings = Vec::new();
+        let text = decode_string(buf, options.lossy_strings, &mut load_warnings)?;
+        let mut fields = text.trim_start_matches('\u{feff}').trim().split(',');
+
+        let id = fields.next().unwrap_or_default().trim().to_string();
+
+        let mut parsed = Vec::new();
+        for (index, field) in fields.map(str::trim).enumerate() {
+            // Many archived maps end with a trailing comma
+            if field.is_empty() {
+                continue;
+            }
+
+            let values: Vec<f64> = field
+                .split('|')
+                .map(|value| value.trim().parse::<f64>())
+                .collect::<Result<_, _>>()
+                .map_err(|_| invalid(format!("Note {index} \"{field}\" is not x|y|ms")))?;
+
+            match values[..] {
+                [a, b, c] => parsed.push((a, b, c)),
+                _ => return Err(invalid(format!("Note {index} \"{field}\" is not x|y|ms"))),
+            }
+        }
+
+        if parsed.is_empty() {
+            return Err(invalid(
+                "Not a Sound Space map, there are no notes after the id".to_string(),
+            ));
+        }
+
+        let largest =
+            |field: fn(&(f64, f64, f64)) -> f64| parsed.iter().map(field).fold(f64::MIN, f64::max);
+        // Quantum positions stay close to the grid, times run far past it
+        let time_first = largest(|n| n.2) <= OFF_GRID && largest(|n| n.0) > OFF_GRID;
+
+        let mut notes: Vec<Note> = parsed
+            .into_iter()
+            .map(|(a, b, c)| {
+                let (millisecond, x, y) = match time_first {
+                    true => (a, b, c),
+                    false => (c, a, b),
+                };
+
+                Note {
+                    id: NoteId::next(),
+                    millisecond: millisecond.max(0.0).round() as u32,
+                    position: Vec2::new(x as f32, y as f32),
+                    hitsound: None,
+                    color: None,
+                }
+            })
+            .collect();
+
+        notes.sort_by_key(|note| note.millisecond);
+
+        Ok(Map {
+            title: id.clone(),
+            id,
+            length: notes.last().map_or(0, |n| n.millisecond),
+            notes,
+            format: MapFormat::Legacy,
+            load_warnings,
+            ..Map::default()
+        })
+    }
+}
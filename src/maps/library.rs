@@ -0,0 +1,362 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        mpsc::{Receiver, channel},
+    },
+    time::{Duration, Instant},
+};
+
+use bevy::{
+    prelude::*,
+    tasks::{IoTaskPool, Task, block_on, futures_lite::future},
+};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{
+    analysis::tags::{ChartTag, chart_tags},
+    maps::{Map, MapMeta, io::ReadOptions, legacy::looks_like_legacy, read_map},
+    settings::Settings,
+};
+
+// Legacy .txt maps are told apart from other text by what's in them, see is_map_file
+const MAP_EXTENSIONS: [&str; 2] = ["sspm", "phxm"];
+
+// Copying a large map fires a burst of events, it is only read once they stop for this long
+const SETTLE_TIME: Duration = Duration::from_millis(500);
+
+// Games that keep maps in their user data folder, under these folder names
+const GAME_FOLDERS: [&str; 3] = ["SoundSpacePlus", "Rhythia", "Sound Space Phoenix"];
+
+// Map folders of games that are installed in their usual place, only ones that exist are listed
+pub fn detect_map_folders() -> Vec<PathBuf> {
+    let env = |name: &str| std::env::var_os(name).map(PathBuf::from);
+    let home = env("HOME").or_else(|| env("USERPROFILE"));
+
+    let data_dirs: Vec<PathBuf> = if cfg!(target_os = "windows") {
+        [env("APPDATA"), env("LOCALAPPDATA")]
+            .into_iter()
+            .flatten()
+            .collect()
+    } else if cfg!(target_os = "macos") {
+        home.iter()
+            .map(|home| home.join("Library/Application Support"))
+            .collect()
+    } else {
+        // Godot games write to the XDG data folder, Steam's Proton keeps the Windows layout
+        let data = env("XDG_DATA_HOME").or_else(|| home.as_ref().map(|h| h.join(".local/share")));
+        data.into_iter().collect()
+    };
+
+    let mut candidates: Vec<PathBuf> = data_dirs
+        .iter()
+        .flat_map(|dir| {
+            GAME_FOLDERS
+                .iter()
+                .map(move |game| dir.join(game).join("maps"))
+        })
+        .collect();
+
+    if let Some(home) = &home {
+        candidates.extend(
+            GAME_FOLDERS
+                .iter()
+                .map(|game| home.join("Documents").join(game).join("maps")),
+        );
+    }
+
+    candidates
+        .into_iter()
+        .filter(|path| path.is_dir())
+        .collect()
+}
+
+#[derive(Debug)]
+pub struct LibraryEntry {
+    pub map: Option<Handle<Map>>,
+    pub title: String,
+    pub artists: Vec<String>,
+    pub mappers: Vec<String>,
+    // Worked out when the map loads so big imported collections can be browsed by style
+    pub tags: Vec<ChartTag>,
+    pub error: Option<String>,
+}
+
+// Every map found in the configured map folders, kept in sync with the disk while the app runs
+#[derive(Resource, Default)]
+pub struct MapLibrary {
+    pub entries: BTreeMap<PathBuf, LibraryEntry>,
+    folders: Vec<PathBuf>,
+    loading: Vec<Task<(PathBuf, io::Result<Map>)>>,
+    // From the settings, the loads run on other threads that can't read them
+    options: ReadOptions,
+    // Every load that was started, for the progress on the loading screen
+    queued: usize,
+}
+
+impl MapLibrary {
+    pub fn is_loading(&self) -> bool {
+        !self.loading.is_empty()
+    }
+
+    // Finished and started loads
+    pub fn progress(&self) -> (usize, usize) {
+        (self.queued - self.loading.len(), self.queued)
+    }
+
+    fn load(&mut self, path: PathBuf) {
+        let options = self.options;
+        let task = IoTaskPool::get().spawn(async move {
+            let result = read_map(&path, options);
+            (path, result)
+        });
+
+        self.loading.push(task);
+        self.queued += 1;
+    }
+}
+
+#[derive(Resource)]
+pub struct FolderWatcher {
+    watcher: RecommendedWatcher,
+    events: Mutex<Receiver<notify::Result<notify::Event>>>,
+    changed: HashMap<PathBuf, Instant>,
+}
+
+// Text files are opened to check they hold a map, see legacy::looks_like_legacy
+pub fn is_map_file(path: &Path) -> bool {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("txt") => looks_like_legacy(path),
+        Some(extension) => MAP_EXTENSIONS.contains(&extension),
+        None => false,
+    }
+}
+
+pub fn find_maps(folder: &Path, found: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(folder) else {
+        warn!("Could not read map folder {}", folder.display());
+        return;
+    };
+
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.is_dir() {
+            find_maps(&path, found);
+        } else if is_map_file(&path) {
+            found.push(path);
+        }
+    }
+}
+
+pub fn start_watcher(mut commands: Commands) {
+    let (sender, receiver) = channel();
+
+    match notify::recommended_watcher(move |event| {
+        let _ = sender.send(event);
+    }) {
+        Ok(watcher) => commands.insert_resource(FolderWatcher {
+            watcher,
+            events: Mutex::new(receiver),
+            changed: HashMap::new(),
+        }),
+        Err(e) => warn!("Map folders won't be watched for changes: {e}"),
+    }
+}
+
+// Scans folders that were added to the settings and drops the entries of removed ones
+pub fn sync_map_folders(
+    settings: Res<Settings>,
+    mut library: ResMut<MapLibrary>,
+    mut watcher: Option<ResMut<FolderWatcher>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    library.options = settings.read_options();
+
+    // Watcher events carry absolute paths, so folders are resolved to match them
+    let folders: Vec<PathBuf> = settings
+        .map_folders
+        .iter()
+        .map(|folder| fs::canonicalize(folder).unwrap_or_else(|_| PathBuf::from(folder)))
+        .collect();
+    if folders == library.folders {
+        return;
+    }
+
+    for removed in library
+        .folders
+        .clone()
+        .iter()
+        .filter(|f| !folders.contains(f))
+    {
+        if let Some(watcher) = watcher.as_mut() {
+            let _ = watcher.watcher.unwatch(removed);
+        }
+        library.entries.retain(|path, _| !path.starts_with(removed));
+    }
+
+    let added: Vec<PathBuf> = folders
+        .iter()
+        .filter(|f| !library.folders.contains(f))
+        .cloned()
+        .collect();
+
+    for added in added.iter() {
+        if let Some(watcher) = watcher.as_mut()
+            && let Err(e) = watcher.watcher.watch(added, RecursiveMode::Recursive)
+        {
+            warn!("Could not watch {}: {e}", added.display());
+        }
+
+        let mut found = Vec::new();
+        find_maps(added, &mut found);
+
+        for path in found {
+            library.load(path);
+        }
+    }
+
+    library.folders = folders;
+}
+
+pub fn process_folder_events(
+    mut watcher: ResMut<FolderWatcher>,
+    mut library: ResMut<MapLibrary>,
+    mut maps: ResMut<Assets<Map>>,
+) {
+    let watcher = watcher.as_mut();
+    let now = Instant::now();
+
+    if let Ok(events) = watcher.events.lock() {
+        for event in events.try_iter() {
+            match event {
+                Ok(event) => {
+                    for path in event.paths {
+                        let mut affected = Vec::new();
+
+                        if is_map_file(&path) {
+                            affected.push(path);
+                        } else if path.is_dir() {
+                            // Folders copied in at once don't always report the files inside
+                            find_maps(&path, &mut affected);
+                        } else {
+                            // A removed folder takes all of its maps with it
+                            affected.extend(
+                                library
+                                    .entries
+                                    .keys()
+                                    .filter(|entry| entry.starts_with(&path))
+                                    .cloned(),
+                            );
+                        }
+
+                        for path in affected {
+                            watcher.changed.insert(path, now);
+                        }
+                    }
+                }
+                Err(e) => warn!("Map folder watcher error: {e}"),
+            }
+        }
+    }
+
+    let settled: Vec<PathBuf> = watcher
+        .changed
+        .iter()
+        .filter(|(_, changed)| now.duration_since(**changed) >= SETTLE_TIME)
+        .map(|(path, _)| path.clone())
+        .collect();
+
+    // The event kind isn't trusted on its own, renames and editors saving through temporary files
+    // report differently on every platform, so whether the file still exists decides
+    for path in settled {
+        watcher.changed.remove(&path);
+
+        if path.exists() {
+            library.load(path);
+        } else if let Some(entry) = library.entries.remove(&path) {
+            info!("Removed {} from the library", path.display());
+
+            if let Some(handle) = entry.map {
+                maps.remove(&handle);
+            }
+        }
+    }
+}
+
+pub fn poll_library_loads(mut library: ResMut<MapLibrary>, mut maps: ResMut<Assets<Map>>) {
+    let mut finished = Vec::new();
+
+    library
+        .loading
+        .retain_mut(|task| match block_on(future::poll_once(task)) {
+            Some(result) => {
+                finished.push(result);
+                false
+            }
+            None => true,
+        });
+
+    for (path, result) in finished {
+        // The folder may have been removed while the map was loading
+        if !library
+            .folders
+            .iter()
+            .any(|folder| path.starts_with(folder))
+        {
+            continue;
+        }
+
+        let existing = library.entries.remove(&path).and_then(|entry| entry.map);
+
+        let entry = match result {
+            Ok(map) => {
+                let entry = LibraryEntry {
+                    map: None,
+                    title: map.get_title(),
+                    artists: map.get_artists(),
+                    mappers: map.get_mappers(),
+                    tags: chart_tags(&map.notes),
+                    error: None,
+                };
+
+                // Reloads replace the asset behind the existing handle so an open map updates in place
+                let handle = match existing {
+                    Some(handle) => {
+                        maps.insert(&handle, map);
+                        info!("Reloaded {}", path.display());
+                        handle
+                    }
+                    None => {
+                        info!("Added {} to the library", path.display());
+                        maps.add(map)
+                    }
+                };
+
+                LibraryEntry {
+                    map: Some(handle),
+                    ..entry
+                }
+            }
+            Err(e) => {
+                warn!("Could not load {}: {e}", path.display());
+
+                LibraryEntry {
+                    map: existing,
+                    title: path
+                        .file_stem()
+                        .map(|s| s.to_string_lossy().into_owned())
+                        .unwrap_or_default(),
+                    artists: Vec::new(),
+                    mappers: Vec::new(),
+                    tags: Vec::new(),
+                    error: Some(e.to_string()),
+                }
+            }
+        };
+
+        library.entries.insert(path, entry);
+    }
+}
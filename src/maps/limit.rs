@@ -0,0 +1,194 @@
+use std::{
+    io::{self, Read, Write},
+    process::{Command, Stdio},
+    thread,
+};
+
+use bevy::audio::AudioSource;
+use serde::{Deserialize, Serialize};
+
+use crate::maps::{
+    Map, MapFormat,
+    cover::{self, CoverEncoding, CoverOptions},
+    io::WriteOptions,
+    size::{SizeReport, format_size},
+};
+
+// Some games and sites refuse maps over a size. Exports are checked against it before they are
+// written, and the cover and audio can be shrunk until the file fits
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct SizeLimit {
+    // Zero turns the check off
+    pub max_mb: f32,
+    // Shrinks oversized exports instead of only warning about them
+    pub shrink: bool,
+    // Longest side of the cover once it is shrunk
+    pub cover_size: u32,
+    // Audio is re-encoded as MP3 at this bitrate, after the cover if that wasn't enough
+    pub audio_kbps: u32,
+}
+
+impl Default for SizeLimit {
+    fn default() -> Self {
+        Self {
+            max_mb: 0.0,
+            shrink: false,
+            cover_size: 512,
+            audio_kbps: 128,
+        }
+    }
+}
+
+impl SizeLimit {
+    pub fn max_bytes(&self) -> Option<u64> {
+        match self.max_mb > 0.0 {
+            true => Some((self.max_mb as f64 * 1024.0 * 1024.0) as u64),
+            false => None,
+        }
+    }
+}
+
+// What an export was measured at against the limit, and what was done to fit it
+#[derive(Debug, Clone, Default)]
+pub struct LimitCheck {
+    pub limit: u64,
+    // As the map would have been written without shrinking
+    pub original: u64,
+    // As it is written, the same as the original when nothing was shrunk
+    pub size: u64,
+    pub steps: Vec<String>,
+}
+
+impl LimitCheck {
+    pub fn over(&self) -> bool {
+        self.size > self.limit
+    }
+
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = self.steps.clone();
+        if self.over() {
+            warnings.push(format!(
+                "The file is {}, over the {} limit",
+                format_size(self.size),
+                format_size(self.limit)
+            ));
+        }
+        warnings
+    }
+}
+
+// None when the limit is off. The cover goes first since it rarely shows the loss, the audio only
+// when that wasn't enough. Either is kept only when it got smaller
+pub fn fit_to_limit(
+    map: &mut Map,
+    format: MapFormat,
+    limit: &SizeLimit,
+    write_options: WriteOptions,
+) -> io::Result<Option<LimitCheck>> {
+    let Some(max) = limit.max_bytes() else {
+        return Ok(None);
+    };
+
+    let original = SizeReport::measure(map, format, write_options)?;
+    let mut check = LimitCheck {
+        limit: max,
+        original,
+        size: original,
+        steps: Vec::new(),
+    };
+    if !check.over() || !limit.shrink {
+        return Ok(Some(check));
+    }
+
+    // PHXM always stores its cover as cover.png
+    let encoding = match format {
+        MapFormat::PHXM => CoverEncoding::Png,
+        _ => CoverEncoding::Jpeg { quality: 80 },
+    };
+    if !map.cover.is_empty() {
+        let options = CoverOptions {
+            crop: false,
+            max_size: limit.cover_size,
+            encoding,
+        };
+        match cover::process(&map.cover, &options) {
+            Ok(shrunk) if shrunk.len() < map.cover.len() => {
+                check.steps.push(format!(
+                    "Cover shrunk to {}px {}, {} to {}",
+                    limit.cover_size,
+                    encoding.label(),
+                    format_size(map.cover.len() as u64),
+                    format_size(shrunk.len() as u64)
+                ));
+                map.cover = shrunk;
+                check.size = SizeReport::measure(map, format, write_options)?;
+            }
+            Ok(_) => {}
+            Err(e) => check.steps.push(format!("Could not shrink the cover: {e}")),
+        }
+    }
+
+    if check.over()
+        && let Some(audio) = map.audio.as_ref()
+    {
+        match reencode_audio(&audio.bytes, limit.audio_kbps) {
+            Ok(shrunk) if shrunk.len() < audio.bytes.len() => {
+                check.steps.push(format!(
+                    "Audio re-encoded at {} kbps, {} to {}",
+                    limit.audio_kbps,
+                    format_size(audio.bytes.len() as u64),
+                    format_size(shrunk.len() as u64)
+                ));
+                map.audio = Some(AudioSource {
+                    bytes: shrunk.into(),
+                });
+                check.size = SizeReport::measure(map, format, write_options)?;
+            }
+            Ok(_) => {}
+            Err(e) => check
+                .steps
+                .push(format!("Could not re-encode the audio: {e}")),
+        }
+    }
+
+    Ok(Some(check))
+}
+
+// Needs ffmpeg on the path like click tracks and clips. The input is written from another thread
+// so a full output pipe can't stall both sides
+pub fn reencode_audio(bytes: &[u8], kbps: u32) -> io::Result<Vec<u8>> {
+    let mut ffmpeg = Command::new("ffmpeg")
+        .args(["-loglevel", "error", "-i", "-", "-vn"])
+        .args(["-c:a", "libmp3lame", "-b:a"])
+        .arg(format!("{kbps}k"))
+        .args(["-f", "mp3", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("Could not start ffmpeg: {e}")))?;
+
+    let input = bytes.to_vec();
+    let mut stdin = ffmpeg.stdin.take();
+    let writer = thread::spawn(move || match stdin.as_mut() {
+        Some(stdin) => stdin.write_all(&input),
+        None => Ok(()),
+    });
+
+    let mut encoded = Vec::new();
+    if let Some(mut stdout) = ffmpeg.stdout.take() {
+        stdout.read_to_end(&mut encoded)?;
+    }
+
+    let output = ffmpeg.wait_with_output()?;
+    // ffmpeg stops reading once it fails, the broken pipe is reported through its own error
+    let written = writer.join().unwrap_or(Ok(()));
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::other(format!("ffmpeg failed: {}", error.trim())));
+    }
+    written?;
+
+    Ok(encoded)
+}
@@ -0,0 +1,114 @@
+use std::io;
+
+use crate::maps::objects::lyric::{Lyric, LyricStyle};
+
+// The last line has nothing after it to end it
+const LAST_LINE_MS: u32 = 4000;
+
+// Reads `[mm:ss.xx]` or `[mm:ss]` into milliseconds
+fn timestamp(tag: &str) -> Option<u32> {
+    let (minutes, seconds) = tag.split_once(':')?;
+    let minutes: u32 = minutes.trim().parse().ok()?;
+    // Some editors write the hundredths after a second colon
+    let seconds: f64 = seconds.trim().replacen(':', ".", 1).parse().ok()?;
+    if !(0.0..60.0).contains(&seconds) {
+        return None;
+    }
+    Some(minutes * 60_000 + (seconds * 1000.0).round() as u32)
+}
+
+// Enhanced LRC times single words with <mm:ss.xx>, only whole lines are shown so they are dropped
+fn strip_word_times(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('<') {
+        match rest[start..].find('>') {
+            Some(end) if timestamp(&rest[start + 1..start + end]).is_some() => {
+                stripped.push_str(&rest[..start]);
+                rest = &rest[start + end + 1..];
+            }
+            _ => {
+                stripped.push_str(&rest[..=start]);
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    stripped.push_str(rest);
+    stripped.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Lyrics from an LRC file. Every line lasts until the next one starts, blank lines only end the
+// one before them. Metadata tags other than the offset are ignored
+pub fn parse_lrc(text: &str) -> io::Result<Vec<Lyric>> {
+    let mut offset: i64 = 0;
+    let mut lines: Vec<(u32, String)> = Vec::new();
+
+    for line in text.lines() {
+        let mut rest = line.trim();
+        let mut times = Vec::new();
+
+        while let Some(tag) = rest.strip_prefix('[') {
+            let Some(end) = tag.find(']') else {
+                break;
+            };
+            let (tag, after) = (&tag[..end], &tag[end + 1..]);
+
+            match timestamp(tag) {
+                Some(millisecond) => times.push(millisecond),
+                None => {
+                    // A positive offset shows the lyrics earlier
+                    if let Some(value) = tag.strip_prefix("offset:") {
+                        offset = value.trim().parse().map_err(|_| {
+                            io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                format!("LRC offset '{value}' is not a number"),
+                            )
+                        })?;
+                    }
+                }
+            }
+            rest = after;
+        }
+
+        let text = strip_word_times(rest);
+        lines.extend(
+            times
+                .into_iter()
+                .map(|millisecond| (millisecond, text.clone())),
+        );
+    }
+
+    if lines.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "LRC file has no timed lines",
+        ));
+    }
+
+    // Tags for repeated lines can come in any order
+    let shift = |millisecond: u32| (millisecond as i64 - offset).max(0) as u32;
+    lines.sort_by_key(|(millisecond, _)| *millisecond);
+
+    let lyrics = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, text))| !text.is_empty())
+        .map(|(index, (millisecond, text))| {
+            let start = shift(*millisecond);
+            let end = lines
+                .get(index + 1)
+                .map_or(start + LAST_LINE_MS, |(next, _)| shift(*next));
+
+            Lyric {
+                millisecond: start,
+                duration_ms: end.saturating_sub(start),
+                text: text.clone(),
+                style: LyricStyle::Subtitle,
+            }
+        })
+        .filter(|lyric| lyric.duration_ms > 0)
+        .collect();
+
+    Ok(lyrics)
+}
@@ -1,8 +1,15 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use crate::maps::objects::note::Note;
+use crate::maps::objects::{
+    lyric::Lyric,
+    note::Note,
+    speed::{ScrollTimeline, SpeedChange},
+    timing::TimingPoint,
+};
 
 use super::parser::ObjectDefinition;
+use crate::modchart::ModEvent;
 
 pub trait MapInfo {}
 
@@ -14,13 +21,32 @@ pub trait MapMeta {
     fn get_length(&self) -> u32;
 }
 
-#[derive(Debug)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum MapFormat {
+    #[default]
     SSPM,
     PHXM,
+    // Sound Space's original text maps, see maps::legacy
+    Legacy,
 }
 
-#[derive(Debug, TypePath, Asset)]
+impl MapFormat {
+    pub const ALL: [MapFormat; 3] = [MapFormat::SSPM, MapFormat::PHXM, MapFormat::Legacy];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            MapFormat::SSPM => "sspm",
+            MapFormat::PHXM => "phxm",
+            MapFormat::Legacy => "txt",
+        }
+    }
+}
+
+// What the values of `Map::difficulty` stand for
+pub const DIFFICULTY_NAMES: [&str; 6] = ["N/A", "Easy", "Medium", "Hard", "Logic", "Tasukete"];
+
+#[derive(Debug, Clone, Default, TypePath, Asset)]
 pub struct Map {
     pub id: String,
     pub length: u32,
@@ -32,14 +58,154 @@ pub struct Map {
     pub audio: Option<AudioSource>,
     pub cover: Vec<u8>,
     pub notes: Vec<Note>,
+    pub timing_points: Vec<TimingPoint>,
+    // Sorted by millisecond like the timing points
+    pub speed_changes: Vec<SpeedChange>,
+    pub bookmarks: Vec<Bookmark>,
+    // Review comments left between mappers, sorted by millisecond like the bookmarks
+    pub annotations: Vec<Annotation>,
+    // Timed text shown during playback, sorted by millisecond
+    pub lyrics: Vec<Lyric>,
     pub objects: Vec<ObjectDefinition>,
+    // Imported from maps with the has_mod flag, see modchart::ssp. Never written back
+    pub mod_events: Vec<ModEvent>,
+    // Writes note ids into SSPM custom data so they survive reopening the map, off by default
+    // since every note adds a few bytes. Turned on for maps that were saved with them
+    pub save_note_ids: bool,
     pub format: MapFormat,
+    // Problems that were worked around while reading the file, like text that wasn't UTF-8
+    pub load_warnings: Vec<String>,
+}
+
+// Named marker for a section of the chart, kept sorted by millisecond
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub millisecond: u32,
+    pub label: String,
+    pub color: [u8; 3],
+}
+
+impl Bookmark {
+    pub fn color(&self) -> Color {
+        let [r, g, b] = self.color;
+        Color::srgb_u8(r, g, b)
+    }
+}
+
+// Comment on a timestamp, or on the note at `position` when it has one. Notes are found again by
+// time and position so the comment survives maps saved without note ids
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub millisecond: u32,
+    #[serde(default)]
+    pub position: Option<[f32; 2]>,
+    pub text: String,
+    #[serde(default)]
+    pub resolved: bool,
+}
+
+impl Annotation {
+    pub fn is_on(&self, note: &Note) -> bool {
+        self.position.is_some_and(|[x, y]| {
+            note.millisecond == self.millisecond && note.position.distance(Vec2::new(x, y)) < 0.01
+        })
+    }
+}
+
+impl Map {
+    pub fn add_bookmark(&mut self, bookmark: Bookmark) -> usize {
+        let index = self
+            .bookmarks
+            .partition_point(|b| b.millisecond <= bookmark.millisecond);
+        self.bookmarks.insert(index, bookmark);
+        index
+    }
+
+    pub fn next_bookmark(&self, millisecond: f64) -> Option<&Bookmark> {
+        self.bookmarks
+            .iter()
+            .find(|b| b.millisecond as f64 > millisecond.floor())
+    }
+
+    pub fn previous_bookmark(&self, millisecond: f64) -> Option<&Bookmark> {
+        self.bookmarks
+            .iter()
+            .rev()
+            .find(|b| (b.millisecond as f64) < millisecond.ceil())
+    }
+
+    pub fn timing_point_at(&self, millisecond: u32) -> Option<&TimingPoint> {
+        let index = self
+            .timing_points
+            .partition_point(|t| t.millisecond <= millisecond);
+
+        match index {
+            0 => self.timing_points.first(),
+            _ => self.timing_points.get(index - 1),
+        }
+    }
+
+    pub fn scroll_timeline(&self) -> ScrollTimeline {
+        ScrollTimeline::new(&self.speed_changes)
+    }
+
+    // Time of the last note or object, timing points and bookmarks don't need to be played through
+    pub fn last_object_ms(&self) -> u32 {
+        let notes = self.notes.last().map_or(0, |n| n.millisecond);
+        let objects = self
+            .objects
+            .iter()
+            .map(|o| o.millisecond)
+            .max()
+            .unwrap_or(0);
+        notes.max(objects)
+    }
+
+    // Rounds a time to the nearest 1/divisor beat of the active timing point
+    pub fn snap(&self, millisecond: f64, divisor: u32) -> f64 {
+        let Some(timing) = self.timing_point_at(millisecond.max(0.0) as u32) else {
+            return millisecond;
+        };
+
+        let step = timing.beat_length() / divisor.max(1) as f64;
+        let beats = ((millisecond - timing.millisecond as f64) / step).round();
+
+        timing.millisecond as f64 + beats * step
+    }
 }
 
+// What a map is listed by, read without its audio, cover or objects
+#[derive(Debug, Clone, PartialEq)]
 pub struct PartialMap {
+    pub id: String,
+    pub length: u32,
     pub title: String,
-    pub mappers: Vec<String>,
     pub artists: Vec<String>,
+    pub difficulty: u8,
+    pub difficulty_name: String,
+    pub mappers: Vec<String>,
+    pub note_count: u32,
+    pub has_audio: bool,
+    pub has_cover: bool,
+    pub format: MapFormat,
+}
+
+impl From<&Map> for PartialMap {
+    fn from(map: &Map) -> Self {
+        Self {
+            id: map.id.clone(),
+            length: map.length,
+            title: map.title.clone(),
+            artists: map.artists.clone(),
+            difficulty: map.difficulty,
+            difficulty_name: map.difficulty_name.clone(),
+            mappers: map.mappers.clone(),
+            note_count: map.notes.len() as u32,
+            has_audio: map.audio.is_some(),
+            has_cover: !map.cover.is_empty(),
+            format: map.format,
+        }
+    }
 }
 
 impl MapMeta for Map {
@@ -63,3 +229,25 @@ impl MapMeta for Map {
         self.length
     }
 }
+
+impl MapMeta for PartialMap {
+    fn get_id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn get_title(&self) -> String {
+        self.title.clone()
+    }
+
+    fn get_mappers(&self) -> Vec<String> {
+        self.mappers.clone()
+    }
+
+    fn get_artists(&self) -> Vec<String> {
+        self.artists.clone()
+    }
+
+    fn get_length(&self) -> u32 {
+        self.length
+    }
+}
@@ -0,0 +1,165 @@
+use std::io;
+
+use bevy::audio::AudioSource;
+
+use crate::{
+    jukebox::pcm::Pcm,
+    maps::{Map, objects::NoteId},
+    modchart::{Keyframe, ModEvent},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MashupMode {
+    // The second map starts `gap_ms` after the first one's audio ends, with silence in between
+    Append { gap_ms: u32 },
+    // Both note sets on the first map's audio and timing
+    Overlay,
+}
+
+// A new map made of two others, which are left as they are
+pub fn mashup(first: &Map, second: &Map, mode: MashupMode) -> io::Result<Map> {
+    let mut map = first.clone();
+
+    map.id = format!("{}+{}", first.id, second.id);
+    map.title = format!("{} + {}", first.title, second.title);
+    for (names, other) in [
+        (&mut map.artists, &second.artists),
+        (&mut map.mappers, &second.mappers),
+    ] {
+        for name in other {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+    }
+    map.difficulty = first.difficulty.max(second.difficulty);
+    map.load_warnings.clear();
+
+    let offset = match mode {
+        MashupMode::Append { gap_ms } => {
+            let first_audio = first
+                .audio
+                .as_ref()
+                .map(|a| Pcm::decode(&a.bytes))
+                .transpose()?;
+            let second_audio = second
+                .audio
+                .as_ref()
+                .map(|a| Pcm::decode(&a.bytes))
+                .transpose()?;
+
+            let end = first_audio
+                .as_ref()
+                .map_or(first.length, |pcm| pcm.duration_ms())
+                .max(first.last_object_ms());
+            let offset = end + gap_ms;
+
+            map.audio = splice(first_audio, second_audio, offset);
+            offset
+        }
+        MashupMode::Overlay => 0,
+    };
+
+    // Ids only stay unique within a map, the second map's notes get new ones
+    map.notes.extend(second.notes.iter().map(|note| {
+        let mut note = *note;
+        note.id = NoteId::next();
+        note.millisecond += offset;
+        note
+    }));
+
+    map.objects
+        .extend(second.objects.iter().cloned().map(|mut object| {
+            object.millisecond += offset;
+            object
+        }));
+
+    map.bookmarks
+        .extend(second.bookmarks.iter().cloned().map(|mut bookmark| {
+            bookmark.millisecond += offset;
+            bookmark
+        }));
+
+    map.annotations
+        .extend(second.annotations.iter().cloned().map(|mut annotation| {
+            annotation.millisecond += offset;
+            annotation
+        }));
+
+    map.lyrics
+        .extend(second.lyrics.iter().cloned().map(|mut lyric| {
+            lyric.millisecond += offset;
+            lyric
+        }));
+
+    map.mod_events.extend(second.mod_events.iter().map(|event| {
+        let keyframes = event
+            .keyframes()
+            .iter()
+            .map(|k| Keyframe {
+                millisecond: k.millisecond + offset,
+                ..*k
+            })
+            .collect();
+        ModEvent::new(event.channel, keyframes)
+            .with_space(event.space)
+            .with_blend(event.blend)
+            .with_jitter(event.jitter)
+    }));
+
+    // Overlaid maps share the first map's audio, so its timing is the one that lines up
+    if let MashupMode::Append { .. } = mode {
+        map.timing_points
+            .extend(second.timing_points.iter().map(|timing| {
+                let mut timing = *timing;
+                timing.millisecond += offset;
+                timing
+            }));
+        map.speed_changes
+            .extend(second.speed_changes.iter().map(|change| {
+                let mut change = *change;
+                change.millisecond += offset;
+                change
+            }));
+    }
+
+    map.notes.sort_by_key(|note| note.millisecond);
+    map.notes
+        .dedup_by(|a, b| a.millisecond == b.millisecond && a.position == b.position);
+    map.objects.sort_by_key(|object| object.millisecond);
+    map.bookmarks.sort_by_key(|bookmark| bookmark.millisecond);
+    map.annotations
+        .sort_by_key(|annotation| annotation.millisecond);
+    map.lyrics.sort_by_key(|lyric| lyric.millisecond);
+    map.timing_points.sort_by_key(|timing| timing.millisecond);
+    map.speed_changes.sort_by_key(|change| change.millisecond);
+
+    map.length = match mode {
+        MashupMode::Append { .. } => offset + second.length,
+        MashupMode::Overlay => first.length.max(second.length),
+    };
+
+    Ok(map)
+}
+
+// The first audio, silence until `offset` and then the second audio. Stored as WAV like trimmed
+// audio, at the first audio's sample rate.
+fn splice(first: Option<Pcm>, second: Option<Pcm>, offset: u32) -> Option<AudioSource> {
+    let format = first.as_ref().or(second.as_ref())?;
+    let (sample_rate, channels) = (format.sample_rate, format.channels);
+
+    let mut spliced = match first {
+        Some(pcm) => pcm,
+        None => Pcm::silence(sample_rate, channels, 0.0),
+    };
+    let padding = offset as f64 - spliced.duration_ms() as f64;
+    spliced.append(&Pcm::silence(sample_rate, channels, padding));
+
+    if let Some(second) = second {
+        spliced.append(&second);
+    }
+
+    Some(AudioSource {
+        bytes: spliced.to_wav().into(),
+    })
+}
@@ -0,0 +1,125 @@
+use std::{collections::BTreeSet, ops::Range};
+
+use crate::maps::{Map, objects::Note};
+
+// Notes this close on the grid are the same note written twice, not a jump
+const SAME_POSITION: f32 = 0.01;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    // Duplicates on the same spot that were removed
+    pub merged: usize,
+    // Notes on other spots that were moved to the time of their cluster
+    pub aligned: usize,
+}
+
+impl MergeReport {
+    pub fn is_empty(&self) -> bool {
+        self.merged == 0 && self.aligned == 0
+    }
+
+    pub fn summary(&self, epsilon_ms: u32) -> String {
+        format!(
+            "Merged {} notes and aligned {} within {epsilon_ms}ms of each other",
+            self.merged, self.aligned
+        )
+    }
+}
+
+// Runs of notes meant to be at one time that drifted a few ms apart, usually from float rounding
+// in other tools. A run goes on while notes are within `epsilon_ms` of its first one, so a dense
+// stream isn't chained into one cluster. `notes` has to be sorted by time
+pub fn close_clusters(notes: &[Note], epsilon_ms: u32) -> Vec<Range<usize>> {
+    let mut clusters = Vec::new();
+    if epsilon_ms == 0 {
+        return clusters;
+    }
+
+    let mut start = 0;
+    while start < notes.len() {
+        let first = notes[start].millisecond;
+        let end =
+            start + notes[start..].partition_point(|note| note.millisecond - first <= epsilon_ms);
+
+        // Notes at exactly the same time are chords, unless some of them are on the same spot
+        let drifted = notes[end - 1].millisecond != first;
+        let stacked = (start..end).any(|a| {
+            (a + 1..end).any(|b| notes[a].position.distance(notes[b].position) < SAME_POSITION)
+        });
+        if drifted || stacked {
+            clusters.push(start..end);
+        }
+        start = end;
+    }
+
+    clusters
+}
+
+// Indices of the notes to take out, and the notes to put back at the time of their cluster.
+// Duplicates keep the first note, so its hitsound and color win
+pub fn plan_merge(notes: &[Note], epsilon_ms: u32) -> (BTreeSet<usize>, Vec<Note>, MergeReport) {
+    let mut removed = BTreeSet::new();
+    let mut moved = Vec::new();
+    let mut report = MergeReport::default();
+
+    for cluster in close_clusters(notes, epsilon_ms) {
+        let millisecond = notes[cluster.start].millisecond;
+        let mut kept: Vec<&Note> = Vec::new();
+
+        for index in cluster {
+            let note = &notes[index];
+            if kept
+                .iter()
+                .any(|k| k.position.distance(note.position) < SAME_POSITION)
+            {
+                removed.insert(index);
+                report.merged += 1;
+                continue;
+            }
+
+            kept.push(note);
+            if note.millisecond != millisecond {
+                removed.insert(index);
+                moved.push(Note {
+                    millisecond,
+                    ..*note
+                });
+                report.aligned += 1;
+            }
+        }
+    }
+
+    (removed, moved, report)
+}
+
+pub fn merge_close_notes(map: &mut Map, epsilon_ms: u32) -> MergeReport {
+    map.notes.sort_by_key(|note| note.millisecond);
+
+    let (removed, moved, report) = plan_merge(&map.notes, epsilon_ms);
+    if report.is_empty() {
+        return report;
+    }
+
+    let mut index = 0;
+    map.notes.retain(|_| {
+        index += 1;
+        !removed.contains(&(index - 1))
+    });
+    map.notes.extend(moved);
+    map.notes.sort_by_key(|note| note.millisecond);
+
+    report
+}
+
+// Applied to maps as the editor opens them when the option is on, noted with the load warnings
+// so it doesn't go unnoticed. Zero leaves the notes alone
+pub fn merge_on_open(map: &mut Map, epsilon_ms: u32) {
+    if epsilon_ms == 0 {
+        return;
+    }
+
+    let report = merge_close_notes(map, epsilon_ms);
+    if !report.is_empty() {
+        map.load_warnings.push(report.summary(epsilon_ms));
+    }
+}
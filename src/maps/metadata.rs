@@ -0,0 +1,242 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::maps::{DIFFICULTY_NAMES, Map, MapFormat, io::WriteOptions};
+
+// Metadata changes applied to many maps at once, from the library
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Casing {
+    #[default]
+    Keep,
+    Title,
+    Lower,
+    Upper,
+}
+
+impl Casing {
+    pub const ALL: [Casing; 4] = [Casing::Keep, Casing::Title, Casing::Lower, Casing::Upper];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Casing::Keep => "Keep",
+            Casing::Title => "Title Case",
+            Casing::Lower => "lower case",
+            Casing::Upper => "UPPER CASE",
+        }
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        match self {
+            Casing::Keep => text.to_string(),
+            Casing::Lower => text.to_lowercase(),
+            Casing::Upper => text.to_uppercase(),
+            Casing::Title => text
+                .split(' ')
+                .map(|word| {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) => first
+                            .to_uppercase()
+                            .chain(chars.flat_map(char::to_lowercase))
+                            .collect(),
+                        None => String::new(),
+                    }
+                })
+                .collect::<Vec<String>>()
+                .join(" "),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Renumber {
+    #[default]
+    Keep,
+    Set(u8),
+    Shift(i8),
+    // Picked from the difficulty name when it is one of DIFFICULTY_NAMES
+    FromName,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BulkEdit {
+    pub artist_casing: Casing,
+    // Trims names and spells them the way most of the maps do
+    pub normalize_mappers: bool,
+    pub difficulty: Renumber,
+}
+
+// The fields a bulk edit touches, small enough to plan over a whole library
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Metadata {
+    pub artists: Vec<String>,
+    pub mappers: Vec<String>,
+    pub difficulty: u8,
+    pub difficulty_name: String,
+}
+
+impl Metadata {
+    pub fn of(map: &Map) -> Self {
+        Self {
+            artists: map.artists.clone(),
+            mappers: map.mappers.clone(),
+            difficulty: map.difficulty,
+            difficulty_name: map.difficulty_name.clone(),
+        }
+    }
+
+    pub fn apply_to(&self, map: &mut Map) {
+        map.artists = self.artists.clone();
+        map.mappers = self.mappers.clone();
+        map.difficulty = self.difficulty;
+        map.difficulty_name = self.difficulty_name.clone();
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlannedChange {
+    pub path: PathBuf,
+    pub before: Metadata,
+    pub after: Metadata,
+}
+
+impl PlannedChange {
+    // One line per changed field, for the dry run
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        let (before, after) = (&self.before, &self.after);
+
+        if before.artists != after.artists {
+            lines.push(format!(
+                "Artists: {} -> {}",
+                before.artists.join(", "),
+                after.artists.join(", ")
+            ));
+        }
+        if before.mappers != after.mappers {
+            lines.push(format!(
+                "Mappers: {} -> {}",
+                before.mappers.join(", "),
+                after.mappers.join(", ")
+            ));
+        }
+        if before.difficulty != after.difficulty {
+            let name = |difficulty: u8| DIFFICULTY_NAMES.get(difficulty as usize).copied();
+            lines.push(format!(
+                "Difficulty: {} -> {}",
+                name(before.difficulty).unwrap_or("?"),
+                name(after.difficulty).unwrap_or("?")
+            ));
+        }
+
+        lines
+    }
+}
+
+fn collapse_whitespace(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// The most used spelling of every mapper name, ignoring case
+fn mapper_spellings<'a>(maps: impl Iterator<Item = &'a Map>) -> HashMap<String, String> {
+    let mut counts: HashMap<String, HashMap<String, usize>> = HashMap::new();
+
+    for map in maps {
+        for mapper in map.mappers.iter() {
+            let name = collapse_whitespace(mapper);
+            *counts
+                .entry(name.to_lowercase())
+                .or_default()
+                .entry(name)
+                .or_default() += 1;
+        }
+    }
+
+    counts
+        .into_iter()
+        .filter_map(|(key, spellings)| {
+            // Ties go to the spelling that sorts first so the result doesn't depend on hashing
+            let spelling = spellings
+                .into_iter()
+                .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then(b.cmp(a)))?;
+            Some((key, spelling.0))
+        })
+        .collect()
+}
+
+fn renumber(difficulty: u8, name: &str, renumber: Renumber) -> u8 {
+    let highest = DIFFICULTY_NAMES.len() as i32 - 1;
+
+    match renumber {
+        Renumber::Keep => difficulty,
+        Renumber::Set(value) => value.min(highest as u8),
+        Renumber::Shift(by) => (difficulty as i32 + by as i32).clamp(0, highest) as u8,
+        Renumber::FromName => DIFFICULTY_NAMES
+            .iter()
+            .position(|known| known.eq_ignore_ascii_case(name.trim()))
+            .map_or(difficulty, |index| index as u8),
+    }
+}
+
+// What the edit would change, only maps that change are listed
+pub fn plan_bulk_edit(maps: &[(PathBuf, &Map)], edit: &BulkEdit) -> Vec<PlannedChange> {
+    let spellings = mapper_spellings(maps.iter().map(|(_, map)| *map));
+
+    maps.iter()
+        .filter_map(|(path, map)| {
+            let before = Metadata::of(map);
+            let mut after = before.clone();
+
+            after.artists = before
+                .artists
+                .iter()
+                .map(|artist| edit.artist_casing.apply(artist))
+                .collect();
+
+            if edit.normalize_mappers {
+                after.mappers.clear();
+                for mapper in before.mappers.iter() {
+                    let name = collapse_whitespace(mapper);
+                    let name = spellings.get(&name.to_lowercase()).cloned().unwrap_or(name);
+                    if !name.is_empty() && !after.mappers.contains(&name) {
+                        after.mappers.push(name);
+                    }
+                }
+            }
+
+            after.difficulty =
+                renumber(before.difficulty, &before.difficulty_name, edit.difficulty);
+
+            (after != before).then(|| PlannedChange {
+                path: path.clone(),
+                before,
+                after,
+            })
+        })
+        .collect()
+}
+
+// Rewrites the map in its own format. Written next to the target first so a failed save never
+// leaves a broken map behind
+pub fn write_map(path: &Path, map: &Map) -> io::Result<()> {
+    if map.format == MapFormat::Legacy {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Legacy text maps have no metadata to write",
+        ));
+    }
+
+    let temporary = path.with_extension(format!("{}.tmp", map.format.extension()));
+    let mut writer = BufWriter::new(File::create(&temporary)?);
+    map.format
+        .serialize(map, &mut writer, WriteOptions::default())?;
+    writer.flush()?;
+    drop(writer);
+
+    fs::rename(&temporary, path)
+}
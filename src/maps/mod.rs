@@ -1,17 +1,52 @@
+pub mod archive;
+pub mod beat_saber;
+pub mod card;
+pub mod cover;
+pub mod dependencies;
+pub mod export;
+pub mod hooks;
+pub mod incremental;
 pub mod io;
+pub mod legacy;
+pub mod library;
+pub mod limit;
+pub mod lrc;
 pub mod map;
+pub mod mashup;
+pub mod merge;
+pub mod metadata;
 pub mod objects;
 pub mod parser;
+pub mod rhythm;
+pub mod server;
+pub mod simplify;
+pub mod size;
+pub mod snapshot;
+pub mod tempo;
+pub mod trim;
 
 use bevy::{
-    asset::{io::Reader, *},
+    asset::{
+        io::{AssetReaderError, Reader},
+        *,
+    },
     prelude::*,
 };
-use std::io::Cursor;
+use std::{
+    fs::File,
+    io::{BufReader, Cursor},
+    path::{Path, PathBuf},
+};
 
 pub use map::*;
 
-use crate::maps::parser::{MapSerializer, SSPMSerializer};
+use crate::maps::{
+    dependencies::{check_dependencies, check_dependency, dependencies, dependency_warning},
+    io::ReadOptions,
+    legacy::LegacySerializer,
+    library::{FolderWatcher, MapLibrary},
+    parser::{MapSerializer, PHXMParser, SSPMSerializer},
+};
 
 #[derive(Resource)]
 pub struct MapFolder(pub Handle<LoadedFolder>);
@@ -23,29 +58,165 @@ pub struct MapPlugin;
 
 impl Plugin for MapPlugin {
     fn build(&self, app: &mut App) {
-        app.init_asset::<Map>().init_asset_loader::<SSPMLoader>();
+        app.init_asset::<Map>()
+            .init_asset_loader::<SSPMLoader>()
+            .init_resource::<MapLibrary>()
+            .add_systems(Startup, library::start_watcher)
+            .add_systems(
+                Update,
+                (
+                    select_current_map.run_if(
+                        resource_exists::<MapFolder>.and(not(resource_exists::<CurrentMap>)),
+                    ),
+                    (
+                        library::sync_map_folders,
+                        library::process_folder_events.run_if(resource_exists::<FolderWatcher>),
+                        library::poll_library_loads,
+                    )
+                        .chain(),
+                ),
+            );
+    }
+}
+
+// Until there is a song select, the first map found in the folder becomes the current one
+pub fn select_current_map(
+    mut commands: Commands,
+    folder: Res<MapFolder>,
+    folders: Res<Assets<LoadedFolder>>,
+) {
+    let Some(loaded) = folders.get(&folder.0) else {
+        return;
+    };
+
+    if let Some(handle) = loaded
+        .handles
+        .iter()
+        .find_map(|handle| handle.clone().try_typed::<Map>().ok())
+    {
+        commands.insert_resource(CurrentMap(handle));
+    }
+}
+
+// Reads a map outside of the asset server, the format is picked from the file extension
+pub fn read_map(path: &Path, options: ReadOptions) -> std::io::Result<Map> {
+    let _span = info_span!("read_map", path = %path.display()).entered();
+    let reader = BufReader::new(File::open(path)?);
+
+    let mut map = match path.extension().and_then(|e| e.to_str()) {
+        Some("sspm") => SSPMSerializer::deserialize_with(reader, options),
+        Some("phxm") => PHXMParser::deserialize_with(reader, options),
+        // Legacy maps have no title, archives name the files after their songs instead
+        Some("txt") => LegacySerializer::deserialize_with(reader, options).map(|mut map| {
+            if let Some(stem) = path.file_stem() {
+                map.title = stem.to_string_lossy().into_owned();
+            }
+            map
+        }),
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Unsupported map format: {}", path.display()),
+        )),
+    }?;
+
+    check_dependencies(&mut map, path);
+    log_load_warnings(&map, &path.display().to_string());
+    Ok(map)
+}
+
+// Just what a map is listed by. SSPM maps stop after their header, the other formats keep
+// everything in one piece and are read in full
+pub fn read_map_meta(path: &Path, options: ReadOptions) -> std::io::Result<PartialMap> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("sspm") => {
+            SSPMSerializer::deserialize_meta_with(BufReader::new(File::open(path)?), options)
+        }
+        _ => read_map(path, options).map(|map| PartialMap::from(&map)),
     }
 }
 
+// Times as shown in labels and file names, minutes and seconds
+pub fn format_time(millisecond: u32) -> String {
+    format!("{}:{:02}", millisecond / 60_000, millisecond / 1000 % 60)
+}
+
+// With the milliseconds too, for lists of objects and the playhead
+pub fn format_precise_time(millisecond: u32) -> String {
+    format!(
+        "{}:{:02}.{:03}",
+        millisecond / 60_000,
+        millisecond / 1000 % 60,
+        millisecond % 1000
+    )
+}
+
+fn log_load_warnings(map: &Map, path: &str) {
+    for warning in map.load_warnings.iter() {
+        warn!("{path}: {warning}");
+    }
+}
+
+// File a map was read from, either through the library or the asset folder.
+// Maps made in the editor have none.
+pub fn map_file(
+    handle: &Handle<Map>,
+    library: &MapLibrary,
+    asset_server: &AssetServer,
+) -> Option<PathBuf> {
+    library
+        .entries
+        .iter()
+        .find(|(_, entry)| entry.map.as_ref() == Some(handle))
+        .map(|(path, _)| path.clone())
+        .or_else(|| {
+            let path = asset_server.get_path(handle.id())?;
+            Some(
+                bevy::asset::io::file::FileAssetReader::get_base_path()
+                    .join("assets")
+                    .join(path.path()),
+            )
+        })
+}
+
 #[derive(Default)]
 pub struct SSPMLoader;
 
 impl AssetLoader for SSPMLoader {
     type Asset = Map;
-    type Settings = ();
+    type Settings = ReadOptions;
     type Error = std::io::Error;
 
     async fn load(
         &self,
         reader: &mut dyn Reader,
-        _settings: &(),
-        _load_context: &mut LoadContext<'_>,
+        options: &ReadOptions,
+        load_context: &mut LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error> {
         let mut buf = Vec::new();
         reader.read_to_end(&mut buf).await?;
-        let cursor = Cursor::new(buf);
-        let map = SSPMSerializer::deserialize(cursor)?;
 
+        // Entered after reading, a span can't be held across the await
+        let path = load_context.path().display().to_string();
+        let mut map = info_span!("load_map", path, bytes = buf.len())
+            .in_scope(|| SSPMSerializer::deserialize_with(Cursor::new(buf), *options))?;
+
+        // Read through the loader so they become dependencies of the map asset, and changing
+        // one reloads the map with the asset server watching for changes
+        for dependency in dependencies(load_context.path()) {
+            let result = match load_context.read_asset_bytes(dependency.clone()).await {
+                Ok(bytes) => check_dependency(&dependency, &bytes),
+                Err(ReadAssetBytesError::AssetReaderError(AssetReaderError::NotFound(_))) => {
+                    continue;
+                }
+                Err(e) => Err(std::io::Error::other(e.to_string())),
+            };
+
+            if let Err(e) = result {
+                map.load_warnings.push(dependency_warning(&dependency, &e));
+            }
+        }
+
+        log_load_warnings(&map, &path);
         Ok(map)
     }
 
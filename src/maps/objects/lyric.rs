@@ -0,0 +1,126 @@
+use std::io;
+
+use serde::{Deserialize, Serialize};
+
+use crate::maps::{
+    objects::MapObject,
+    parser::{ObjectDefinition, ObjectParser, ObjectType},
+};
+
+// Name lyrics are stored under as SSPM custom objects, other games skip them as unknown objects
+pub const LYRIC_OBJECT: &str = "mm_lyric";
+
+// Where and how large a lyric is drawn over the playfield
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LyricStyle {
+    // Along the bottom of the screen
+    #[default]
+    Subtitle,
+    // Large in the middle, for song titles and callouts
+    Title,
+    // Small along the top
+    Caption,
+}
+
+impl LyricStyle {
+    pub const ALL: [LyricStyle; 3] = [LyricStyle::Subtitle, LyricStyle::Title, LyricStyle::Caption];
+
+    // Stable number of the style in the map file, new styles only go at the end
+    pub fn index(&self) -> u8 {
+        LyricStyle::ALL.iter().position(|s| s == self).unwrap_or(0) as u8
+    }
+
+    pub fn from_index(index: u8) -> Option<Self> {
+        LyricStyle::ALL.get(index as usize).copied()
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LyricStyle::Subtitle => "Subtitle",
+            LyricStyle::Title => "Title",
+            LyricStyle::Caption => "Caption",
+        }
+    }
+}
+
+// Text shown from `millisecond` for `duration_ms`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lyric {
+    pub millisecond: u32,
+    pub duration_ms: u32,
+    pub text: String,
+    pub style: LyricStyle,
+}
+
+impl Lyric {
+    pub fn end(&self) -> u32 {
+        self.millisecond.saturating_add(self.duration_ms)
+    }
+
+    pub fn is_shown(&self, millisecond: f64) -> bool {
+        self.millisecond as f64 <= millisecond && millisecond < self.end() as f64
+    }
+
+    pub fn to_definition(&self) -> ObjectDefinition {
+        ObjectDefinition {
+            name: LYRIC_OBJECT.to_string(),
+            millisecond: self.millisecond,
+            definitions: vec![
+                ObjectType::U32(Some(self.duration_ms)),
+                ObjectType::LongString(Some(self.text.clone())),
+                ObjectType::U8(Some(self.style.index())),
+            ],
+        }
+    }
+}
+
+impl MapObject for Lyric {
+    fn get_millisecond(&self) -> u32 {
+        self.millisecond
+    }
+}
+
+impl ObjectParser for Lyric {
+    fn from_definition(obj: ObjectDefinition) -> io::Result<Self> {
+        let style = obj.get_u8(2)?;
+
+        Ok(Lyric {
+            millisecond: obj.millisecond,
+            duration_ms: obj.get_u32(0)?,
+            text: obj.get_string(1)?.to_string(),
+            // Styles from newer versions fall back to subtitles rather than losing the text
+            style: LyricStyle::from_index(style).unwrap_or_default(),
+        })
+    }
+}
+
+// Takes the lyric objects out of `objects`, sorted by time. Ones that don't read as lyrics stay
+// behind as raw objects
+pub fn take_lyrics(objects: &mut Vec<ObjectDefinition>) -> Vec<Lyric> {
+    let mut lyrics = Vec::new();
+
+    objects.retain(|object| {
+        if object.name != LYRIC_OBJECT {
+            return true;
+        }
+
+        match Lyric::from_definition(object.clone()) {
+            Ok(lyric) => {
+                lyrics.push(lyric);
+                false
+            }
+            Err(_) => true,
+        }
+    });
+
+    lyrics.sort_by_key(|lyric| lyric.millisecond);
+    lyrics
+}
+
+// Lyrics on screen at `millisecond`, lyrics are sorted so only the ones started so far are looked at
+pub fn shown_lyrics(lyrics: &[Lyric], millisecond: f64) -> impl Iterator<Item = &Lyric> {
+    let started = lyrics.partition_point(|lyric| lyric.millisecond as f64 <= millisecond);
+    lyrics[..started]
+        .iter()
+        .filter(move |lyric| lyric.is_shown(millisecond))
+}
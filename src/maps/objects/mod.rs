@@ -1,6 +1,12 @@
+pub mod lyric;
 pub mod note;
+pub mod speed;
+pub mod timing;
 
+pub use lyric::*;
 pub use note::*;
+pub use speed::*;
+pub use timing::*;
 
 pub trait MapObject {
     fn get_millisecond(&self) -> u32;
@@ -1,16 +1,69 @@
-use std::io;
+use std::{
+    io,
+    sync::{
+        OnceLock,
+        atomic::{AtomicU32, Ordering},
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use bevy::math::Vec2;
+use serde::{Deserialize, Serialize};
 
 use crate::maps::{
     objects::MapObject,
-    parser::{ObjectDefinition, ObjectParser, ObjectType},
+    parser::{ObjectDefinition, ObjectParser},
 };
 
-#[derive(Debug)]
+// Stays with a note through every edit, unlike its index which shifts whenever a note is added
+// before it. The counter is in the low half and the high half differs between sessions, so ids
+// made by two instances or in another session don't collide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct NoteId(pub u64);
+
+impl NoteId {
+    pub fn next() -> Self {
+        static SESSION: OnceLock<u64> = OnceLock::new();
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+        let session = SESSION.get_or_init(|| {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.subsec_nanos() ^ time.as_secs() as u32);
+            (nanos ^ std::process::id().rotate_left(16)) as u64
+        });
+
+        NoteId(session << 32 | COUNTER.fetch_add(1, Ordering::Relaxed) as u64)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Note {
+    #[serde(default = "NoteId::next")]
+    pub id: NoteId,
     pub millisecond: u32,
     pub position: Vec2,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hitsound: Option<Hitsound>,
+    // Index into the theme's note colors, notes are colored by their position in the map otherwise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<u8>,
+}
+
+// Sample played when the playhead passes the note, the index points into the built in sample bank
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Hitsound {
+    pub sample: u8,
+    pub volume: f32,
+}
+
+impl Default for Hitsound {
+    fn default() -> Self {
+        Self {
+            sample: 0,
+            volume: 1.0,
+        }
+    }
 }
 
 impl MapObject for Note {
@@ -21,26 +74,12 @@ impl MapObject for Note {
 
 impl ObjectParser for Note {
     fn from_definition(obj: ObjectDefinition) -> io::Result<Self> {
-        if obj.definitions.len() < 1 {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Object definition has no types",
-            ));
-        }
-
-        let pos = match obj.definitions[0] {
-            ObjectType::Vec2(Some(v)) => v,
-            _ => {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    "Object could not be parsed as Note",
-                ));
-            }
-        };
-
         Ok(Note {
+            id: NoteId::next(),
             millisecond: obj.millisecond,
-            position: pos,
+            position: obj.get_vec2(0)?,
+            hitsound: None,
+            color: None,
         })
     }
 }
@@ -0,0 +1,81 @@
+use std::io;
+
+use serde::{Deserialize, Serialize};
+
+use crate::maps::{
+    objects::MapObject,
+    parser::{ObjectDefinition, ObjectParser},
+};
+
+// Scroll velocity from this time on. Only changes how fast objects approach, note timing is
+// still decided by the timing points.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SpeedChange {
+    pub millisecond: u32,
+    pub multiplier: f32,
+}
+
+impl MapObject for SpeedChange {
+    fn get_millisecond(&self) -> u32 {
+        self.millisecond
+    }
+}
+
+impl ObjectParser for SpeedChange {
+    fn from_definition(obj: ObjectDefinition) -> io::Result<Self> {
+        let multiplier = obj.get_f32(0)?;
+
+        // Objects would move backwards otherwise, and the renderer relies on them never doing so
+        if multiplier.is_nan() || multiplier < 0.0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Speed change must not have a negative multiplier",
+            ));
+        }
+
+        Ok(SpeedChange {
+            millisecond: obj.millisecond,
+            multiplier,
+        })
+    }
+}
+
+// Converts times into scroll positions, the distance scrolled so far in milliseconds at 1x.
+// Objects are drawn by how far their scroll position is ahead of the playhead's.
+#[derive(Debug, Default)]
+pub struct ScrollTimeline {
+    // Time, scroll position and multiplier at each speed change
+    segments: Vec<(f64, f64, f64)>,
+}
+
+impl ScrollTimeline {
+    // Expects the changes sorted by time, the speed is 1x before the first one
+    pub fn new(changes: &[SpeedChange]) -> Self {
+        let mut segments = Vec::with_capacity(changes.len());
+        let (mut time, mut position, mut multiplier) = (0.0, 0.0, 1.0);
+
+        for change in changes {
+            let at = change.millisecond as f64;
+            position += (at - time) * multiplier;
+            time = at;
+            multiplier = change.multiplier as f64;
+            segments.push((time, position, multiplier));
+        }
+
+        Self { segments }
+    }
+
+    pub fn position(&self, millisecond: f64) -> f64 {
+        let index = self
+            .segments
+            .partition_point(|(time, ..)| *time <= millisecond);
+
+        match index {
+            0 => millisecond,
+            _ => {
+                let (time, position, multiplier) = self.segments[index - 1];
+                position + (millisecond - time) * multiplier
+            }
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use std::io;
+
+use serde::{Deserialize, Serialize};
+
+use crate::maps::{
+    objects::MapObject,
+    parser::{ObjectDefinition, ObjectParser},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TimingPoint {
+    pub millisecond: u32,
+    pub bpm: f32,
+    pub beats_per_measure: u8,
+}
+
+impl TimingPoint {
+    pub fn beat_length(&self) -> f64 {
+        60000.0 / self.bpm as f64
+    }
+}
+
+impl MapObject for TimingPoint {
+    fn get_millisecond(&self) -> u32 {
+        self.millisecond
+    }
+}
+
+impl ObjectParser for TimingPoint {
+    fn from_definition(obj: ObjectDefinition) -> io::Result<Self> {
+        let bpm = obj.get_f32(0)?;
+        let beats_per_measure = obj.get_u8(1)?;
+
+        if bpm <= 0.0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Timing point must have a positive BPM",
+            ));
+        }
+
+        Ok(TimingPoint {
+            millisecond: obj.millisecond,
+            bpm,
+            beats_per_measure,
+        })
+    }
+}
@@ -1,4 +1,5 @@
 use std::{
+    borrow::Cow,
     collections::HashMap,
     io::{self, Cursor, Read, Seek, SeekFrom, Write},
 };
@@ -8,11 +9,21 @@ use bevy::{
     math::{Vec2, Vec3, ops::round},
 };
 use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
 
-use crate::maps::{Map, objects::Note};
 use crate::maps::{
-    MapFormat,
-    io::{BinaryReader, BinaryWriter},
+    MapFormat, PartialMap,
+    io::{
+        BinaryReader, BinaryWriter, DEFAULT_GRID_EPSILON, ReadOptions, WriteOptions, decode_string,
+    },
+};
+use crate::{
+    jukebox::audio_extension,
+    maps::{
+        Map,
+        objects::{Hitsound, Note, NoteId, SpeedChange, TimingPoint, take_lyrics},
+    },
+    modchart::ssp,
 };
 
 pub struct SSPMSerializer;
@@ -20,8 +31,20 @@ pub struct SSPMSerializer;
 pub struct PHXMParser;
 
 pub trait MapSerializer {
-    fn deserialize<T: Read + Seek>(reader: T) -> io::Result<Map>;
-    fn serialize<T: Write + Seek>(map: &Map, writer: T) -> io::Result<()>;
+    fn deserialize_with<T: Read + Seek>(reader: T, options: ReadOptions) -> io::Result<Map>;
+    fn serialize_with<T: Write + Seek>(
+        map: &Map,
+        writer: T,
+        options: WriteOptions,
+    ) -> io::Result<()>;
+
+    fn deserialize<T: Read + Seek>(reader: T) -> io::Result<Map> {
+        Self::deserialize_with(reader, ReadOptions::default())
+    }
+
+    fn serialize<T: Write + Seek>(map: &Map, writer: T) -> io::Result<()> {
+        Self::serialize_with(map, writer, WriteOptions::default())
+    }
 }
 
 pub trait ObjectParser {
@@ -30,14 +53,102 @@ pub trait ObjectParser {
         Self: Sized;
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ObjectDefinition {
     pub name: String,
     pub millisecond: u32,
     pub definitions: Vec<ObjectType>,
 }
 
-#[derive(Debug)]
+impl ObjectDefinition {
+    // Type bytes of every field, None when one of them has no SSPM encoding or no value
+    pub fn sspm_types(&self) -> Option<Vec<u8>> {
+        self.definitions
+            .iter()
+            .map(|field| field.has_value().then(|| field.sspm_type()).flatten())
+            .collect()
+    }
+
+    // Index and type name of every field, for telling what an unknown object holds
+    pub fn fields(&self) -> impl Iterator<Item = (usize, &'static str)> + '_ {
+        self.definitions
+            .iter()
+            .enumerate()
+            .map(|(index, field)| (index, field.type_name()))
+    }
+
+    pub fn get_u8(&self, index: usize) -> io::Result<u8> {
+        self.field(index, "u8", |field| match field {
+            ObjectType::U8(Some(value)) => Some(*value),
+            _ => None,
+        })
+    }
+
+    pub fn get_u32(&self, index: usize) -> io::Result<u32> {
+        self.field(index, "u32", |field| match field {
+            ObjectType::U32(Some(value)) => Some(*value),
+            _ => None,
+        })
+    }
+
+    pub fn get_f32(&self, index: usize) -> io::Result<f32> {
+        self.field(index, "f32", |field| match field {
+            ObjectType::F32(Some(value)) => Some(*value),
+            _ => None,
+        })
+    }
+
+    pub fn get_vec2(&self, index: usize) -> io::Result<Vec2> {
+        self.field(index, "vec2", |field| match field {
+            ObjectType::Vec2(Some(value)) => Some(*value),
+            _ => None,
+        })
+    }
+
+    // Short and long strings alike
+    pub fn get_string(&self, index: usize) -> io::Result<&str> {
+        self.field(index, "string", |field| match field {
+            ObjectType::String(Some(value)) | ObjectType::LongString(Some(value)) => {
+                Some(value.as_str())
+            }
+            _ => None,
+        })
+    }
+
+    // Errors name the object and the field so a bad file can be tracked down
+    fn field<'a, V>(
+        &'a self,
+        index: usize,
+        expected: &str,
+        get: impl FnOnce(&'a ObjectType) -> Option<V>,
+    ) -> io::Result<V> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let Some(field) = self.definitions.get(index) else {
+            return Err(invalid(format!(
+                "{} at {}ms has no field {index}, it only has {}",
+                self.name,
+                self.millisecond,
+                self.definitions.len()
+            )));
+        };
+
+        get(field).ok_or_else(|| match field.has_value() {
+            true => invalid(format!(
+                "Field {index} of {} at {}ms is a {}, expected a {expected}",
+                self.name,
+                self.millisecond,
+                field.type_name()
+            )),
+            false => invalid(format!(
+                "Field {index} of {} at {}ms has no value",
+                self.name, self.millisecond
+            )),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum ObjectType {
     U8(Option<u8>),
     U16(Option<u16>),
@@ -73,121 +184,424 @@ impl ObjectType {
             _ => Err(io::Error::new(io::ErrorKind::InvalidData, "")),
         }
     }
+
+    // Inverse of `from_sspm`, vectors are left out since they can't be read back yet
+    pub fn sspm_type(&self) -> Option<u8> {
+        match self {
+            ObjectType::U8(_) => Some(0x01),
+            ObjectType::U16(_) => Some(0x02),
+            ObjectType::U32(_) => Some(0x03),
+            ObjectType::U64(_) => Some(0x04),
+            ObjectType::F32(_) => Some(0x05),
+            ObjectType::F64(_) => Some(0x06),
+            ObjectType::Vec2(_) => Some(0x07),
+            ObjectType::Buf(_) => Some(0x08),
+            ObjectType::String(_) => Some(0x09),
+            ObjectType::LongBuf(_) => Some(0x0A),
+            ObjectType::LongString(_) => Some(0x0B),
+            ObjectType::I64(_) | ObjectType::Vec3(_) | ObjectType::Vec(_) => None,
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            ObjectType::U8(_) => "u8",
+            ObjectType::U16(_) => "u16",
+            ObjectType::U32(_) => "u32",
+            ObjectType::U64(_) => "u64",
+            ObjectType::F32(_) => "f32",
+            ObjectType::F64(_) => "f64",
+            ObjectType::Vec2(_) => "vec2",
+            ObjectType::Buf(_) => "buffer",
+            ObjectType::String(_) => "string",
+            ObjectType::LongBuf(_) => "long buffer",
+            ObjectType::LongString(_) => "long string",
+            ObjectType::I64(_) => "i64",
+            ObjectType::Vec3(_) => "vec3",
+            ObjectType::Vec(_) => "list",
+        }
+    }
+
+    fn has_value(&self) -> bool {
+        match self {
+            ObjectType::U8(v) => v.is_some(),
+            ObjectType::U16(v) => v.is_some(),
+            ObjectType::U32(v) => v.is_some(),
+            ObjectType::U64(v) => v.is_some(),
+            ObjectType::F32(v) => v.is_some(),
+            ObjectType::F64(v) => v.is_some(),
+            ObjectType::Vec2(v) => v.is_some(),
+            ObjectType::Buf(v) | ObjectType::LongBuf(v) => v.is_some(),
+            ObjectType::String(v) | ObjectType::LongString(v) => v.is_some(),
+            ObjectType::I64(v) => v.is_some(),
+            ObjectType::Vec3(v) => v.is_some(),
+            ObjectType::Vec(v) => v.is_some(),
+        }
+    }
 }
 
 impl MapSerializer for SSPMSerializer {
-    fn serialize<T: Write + Seek>(map: &Map, writer: T) -> io::Result<()> {
-        let mut writer = BinaryWriter::new(writer);
+    fn serialize_with<T: Write + Seek>(
+        map: &Map,
+        writer: T,
+        options: WriteOptions,
+    ) -> io::Result<()> {
+        SSPMSerializer::serialize_padded(map, writer, 0, options).map(|_| ())
+    }
 
-        // Header
-        writer.write_all(b"SS+m")?; // File signature
-        writer.write_all(&[0x02, 0x00])?; // Version 2
-        writer.write_all(&[0u8; 4])?; // Unused bytes
+    fn deserialize_with<T: Read + Seek>(reader: T, options: ReadOptions) -> io::Result<Map> {
+        let mut reader = BinaryReader::with_options(reader, options);
+        let SSPMHead {
+            millisecond,
+            difficulty,
+            has_audio,
+            has_cover,
+            has_mod,
+            sections:
+                SSPMSections {
+                    audio: (audio_data_offset, audio_data_length),
+                    cover: (cover_data_offset, cover_data_length),
+                    object_definitions: (object_definition_offset, object_definition_length),
+                    object_data: (object_data_offset, object_data_length),
+                    ..
+                },
+            map_id,
+            song_name,
+            mappers,
+            mut custom_data,
+            ..
+        } = SSPMSerializer::read_head(&mut reader)?;
 
-        // Static Metadata
-        writer.write_sha1(&[0u8; 20])?; // SHA1 is never used yet so ignore for now
-        writer.write_u32(map.length)?;
-        writer.write_u32(map.notes.len() as u32)?;
-        writer.write_u32((map.notes.len() + map.objects.len()) as u32)?;
+        // Offsets come straight from the file, so they are checked before anything is read from
+        // them or allocated for them
+        let header_end = reader.stream_position()?;
+        let file_length = reader.seek(io::SeekFrom::End(0))?;
 
-        writer.write_u8(map.difficulty)?;
-        writer.write_u16(0)?; // Star rating is never used
-        writer.write_bool(map.audio.is_some())?;
-        writer.write_bool(!map.cover.is_empty())?;
-        writer.write_bool(false)?;
+        if has_audio && audio_data_length == 0 {
+            reader.warn("Map says it has audio but its audio section is empty".to_string());
+        }
+        if has_cover && cover_data_length == 0 {
+            reader.warn("Map says it has a cover but its cover section is empty".to_string());
+        }
 
-        let data_offset = writer.stream_position()?;
-        writer.write_all(&[0u8; 80])?; // Placeholder for data offsets and lengths
+        let mut sections = vec![
+            (
+                "object definitions",
+                object_definition_offset,
+                object_definition_length,
+            ),
+            ("object data", object_data_offset, object_data_length),
+        ];
+        if has_audio && audio_data_length > 0 {
+            sections.push(("audio", audio_data_offset, audio_data_length));
+        }
+        if has_cover && cover_data_length > 0 {
+            sections.push(("cover", cover_data_offset, cover_data_length));
+        }
+        check_sections(&mut sections, header_end, file_length)?;
 
-        writer.write_string(&map.id)?;
-        writer.write_string(&map.title)?;
-        writer.write_string(&map.title)?; // Song name is the same as title for now
+        let mut audio_buf = Vec::new();
+        let mut cover_buf = Vec::new();
 
-        writer.write_u16(map.mappers.len() as u16)?;
-        for mapper in map.mappers.iter() {
-            writer.write_string(mapper)?;
+        if has_audio && audio_data_length > 0 {
+            audio_buf = vec![0u8; audio_data_length as usize];
+            reader.seek(io::SeekFrom::Start(audio_data_offset))?;
+            reader.read_exact(&mut audio_buf)?;
         }
 
-        let mut custom_data_offset: u64 = 0;
-        let mut custom_data_length: u64 = 0;
+        if has_cover && cover_data_length > 0 {
+            cover_buf = vec![0u8; cover_data_length as usize];
+            reader.seek(io::SeekFrom::Start(cover_data_offset))?;
+            reader.read_exact(&mut cover_buf)?;
+        }
 
-        if !map.difficulty_name.is_empty() {
-            custom_data_offset = writer.stream_position()?;
+        let mut object_definitions = HashMap::<u8, ObjectDefinition>::new();
 
-            writer.write_u16(1)?; // One custom data field
-            writer.write_string("difficulty_name")?;
-            writer.write_u8(0x09)?; // String type
-            writer.write_string(&map.difficulty_name)?;
+        reader.seek(io::SeekFrom::Start(object_definition_offset))?;
 
-            custom_data_length = writer.stream_position()? - custom_data_offset;
-        } else {
-            writer.write_u16(0)?; // zero custom data fields
+        let object_count = reader.read_u8()?;
+
+        for count in 0..object_count {
+            let name = reader.read_string()?;
+            let values = reader.read_u8()?;
+
+            let mut definitions = Vec::<ObjectType>::new();
+
+            for _ in 0..values {
+                let obj_type = reader.read_u8()?;
+                let data = ObjectType::from_sspm(obj_type)?;
+
+                definitions.push(data);
+            }
+
+            // There should be an empty byte after each object definition
+            assert_eq!(reader.read_u8()?, 0x00);
+
+            object_definitions.insert(
+                count,
+                ObjectDefinition {
+                    name,
+                    millisecond: 0,
+                    definitions,
+                },
+            );
         }
 
-        let audio_offset = writer.stream_position()?;
-        if let Some(audio) = &map.audio {
-            writer.write_all(&audio.bytes)?;
+        reader.seek(io::SeekFrom::Start(object_data_offset))?;
+        let object_section_end = reader.stream_position()? + object_data_length;
+
+        let mut notes = Vec::<Note>::new();
+        let mut timing_points = Vec::<TimingPoint>::new();
+        let mut speed_changes = Vec::<SpeedChange>::new();
+        let mut objects = Vec::<ObjectDefinition>::new();
+
+        while reader.stream_position()? < object_section_end {
+            let ms = reader.read_u32()?;
+            let definition = reader.read_u8()?;
+
+            let object = SSPMSerializer::parse_definitions(
+                &object_definitions[&definition],
+                ms,
+                &mut reader,
+            )?;
+
+            match object.name.as_str() {
+                "ssp_note" => notes.push(Note::from_definition(object)?),
+                "mm_timing_point" => timing_points.push(TimingPoint::from_definition(object)?),
+                "mm_speed_change" => speed_changes.push(SpeedChange::from_definition(object)?),
+                _ => objects.push(object),
+            }
         }
-        let audio_length = writer.stream_position()? - audio_offset;
 
-        let mut cover_offset = 0;
-        let mut cover_length = 0;
+        // Hitsounds and colors refer to notes by their index in the file, so they are attached
+        // before sorting
+        if let Some(ObjectType::LongString(Some(json))) = custom_data.remove("mm_hitsounds") {
+            let hitsounds: Vec<(usize, Hitsound)> = serde_json::from_str(&json)?;
 
-        if !map.cover.is_empty() {
-            cover_offset = writer.stream_position()?;
-            writer.write_all(&map.cover)?;
-            cover_length = writer.stream_position()? - cover_offset;
+            for (index, hitsound) in hitsounds {
+                if let Some(note) = notes.get_mut(index) {
+                    note.hitsound = Some(hitsound);
+                }
+            }
         }
 
-        let object_definition_offset = writer.stream_position()?;
-        writer.write_u8(1)?;
-        writer.write_string("ssp_note")?;
-        writer.write_all(&[0x01, 0x07, 0x00])?; // One definition of type Vec2
-        let object_definition_length = writer.stream_position()? - object_definition_offset;
+        if let Some(ObjectType::LongString(Some(json))) = custom_data.remove("mm_note_colors") {
+            let colors: Vec<(usize, u8)> = serde_json::from_str(&json)?;
 
-        let object_data_offset = writer.stream_position()?;
+            for (index, color) in colors {
+                if let Some(note) = notes.get_mut(index) {
+                    note.color = Some(color);
+                }
+            }
+        }
 
-        for note in map.notes.iter() {
-            writer.write_u32(note.millisecond)?;
-            writer.write_u8(0x00)?;
+        let save_note_ids = match custom_data.remove("mm_note_ids") {
+            Some(ObjectType::LongString(Some(json))) => {
+                let ids: Vec<u64> = serde_json::from_str(&json)?;
 
-            let quantum = round(note.position.x) != round_to_places(note.position.x, 2)
-                || round(note.position.y) != round_to_places(note.position.y, 2);
+                for (note, id) in notes.iter_mut().zip(ids) {
+                    note.id = NoteId(id);
+                }
+                true
+            }
+            _ => false,
+        };
 
-            writer.write_bool(quantum)?;
+        let mod_events = match has_mod {
+            true => ssp::take_mod_events(&mut objects),
+            false => Vec::new(),
+        };
+        let lyrics = take_lyrics(&mut objects);
 
-            if quantum {
-                writer.write_f32(note.position.x)?;
-                writer.write_f32(note.position.y)?;
-            } else {
-                writer.write_u8(note.position.x as u8 + 1)?;
-                writer.write_u8(note.position.y as u8 + 1)?;
+        // Objects are not guaranteed to be stored in time order, everything else expects them sorted
+        notes.sort_by_key(|note| note.millisecond);
+        timing_points.sort_by_key(|timing| timing.millisecond);
+        speed_changes.sort_by_key(|change| change.millisecond);
+
+        let difficulty_name = take_difficulty_name(&mut custom_data);
+
+        // Bookmarks are our own addition, stored as json so other games skip them as an unknown field
+        let bookmarks = match custom_data.remove("mm_bookmarks") {
+            Some(ObjectType::LongString(Some(json))) => serde_json::from_str(&json)?,
+            _ => Vec::new(),
+        };
+
+        let annotations = match custom_data.remove("mm_annotations") {
+            Some(ObjectType::LongString(Some(json))) => serde_json::from_str(&json)?,
+            _ => Vec::new(),
+        };
+
+        let audio_source = match audio_buf.is_empty() {
+            true => None,
+            false => Some(AudioSource {
+                bytes: audio_buf.into(),
+            }),
+        };
+
+        Ok(Map {
+            id: map_id,
+            length: millisecond,
+            title: song_name,
+            artists: vec![],
+            difficulty,
+            difficulty_name,
+            mappers,
+            audio: audio_source,
+            cover: cover_buf,
+            notes,
+            timing_points,
+            speed_changes,
+            bookmarks,
+            annotations,
+            lyrics,
+            objects,
+            mod_events,
+            save_note_ids,
+            format: MapFormat::SSPM,
+            load_warnings: reader.take_warnings(),
+        })
+    }
+}
+// Every section has to lie after the header and inside the file without running into another one
+fn check_sections(
+    sections: &mut [(&str, u64, u64)],
+    header_end: u64,
+    file_length: u64,
+) -> io::Result<()> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+    for &(name, offset, length) in sections.iter() {
+        if offset < header_end {
+            return Err(invalid(format!(
+                "SSPM {name} section at {offset} overlaps the header, which ends at {header_end}"
+            )));
+        }
+
+        match offset.checked_add(length) {
+            Some(end) if end <= file_length => {}
+            _ => {
+                return Err(invalid(format!(
+                    "SSPM {name} section at {offset} with length {length} runs past the end of \
+                     the file at {file_length}"
+                )));
             }
         }
+    }
 
-        let object_data_length = writer.stream_position()? - object_data_offset;
+    sections.sort_by_key(|&(_, offset, length)| (offset, length));
+    for pair in sections.windows(2) {
+        let [(first, offset, length), (second, next, _)] = pair else {
+            continue;
+        };
+        if offset + length > *next {
+            return Err(invalid(format!(
+                "SSPM {first} section at {offset} overlaps the {second} section at {next}"
+            )));
+        }
+    }
 
-        writer.seek(SeekFrom::Start(data_offset))?;
-        writer.write_u64(custom_data_offset)?;
-        writer.write_u64(custom_data_length)?;
-        writer.write_u64(audio_offset)?;
-        writer.write_u64(audio_length)?;
-        writer.write_u64(cover_offset)?;
-        writer.write_u64(cover_length)?;
-        writer.write_u64(object_definition_offset)?;
-        writer.write_u64(object_definition_length)?;
-        writer.write_u64(object_data_offset)?;
-        writer.write_u64(object_data_length)?;
+    Ok(())
+}
 
-        writer.seek(SeekFrom::End(0))?;
-        writer.write_string(format!("MM Export - {}", "0.0.1").as_str())?;
+// Only whole cell positions on the grid fit into the compact u8 encoding, see WriteOptions. Maps
+// are shown as written with the default epsilon, exports may use their own
+pub fn is_quantum(position: Vec2) -> bool {
+    is_quantum_within(position, DEFAULT_GRID_EPSILON)
+}
 
-        Ok(())
+pub fn is_quantum_value(value: f32) -> bool {
+    is_quantum_value_within(value, DEFAULT_GRID_EPSILON)
+}
+
+pub fn is_quantum_within(position: Vec2, epsilon: f32) -> bool {
+    [position.x, position.y]
+        .into_iter()
+        .any(|value| is_quantum_value_within(value, epsilon))
+}
+
+// Positions are typed in as decimals that f32 can't hold exactly, 1.005 is stored a little under
+// and 0.005 a little over. Distances within this of the epsilon count as reaching it, so values
+// the same distance off the grid are treated the same
+const QUANTUM_TOLERANCE: f32 = 1e-6;
+
+pub fn is_quantum_value_within(value: f32, epsilon: f32) -> bool {
+    let off = (value - round(value)).abs();
+    (off > 0.0 && off + QUANTUM_TOLERANCE >= epsilon) || !(0.0..=2.0).contains(&round(value))
+}
+
+// Offset and length of every section, as stored in the SSPM header
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SSPMSections {
+    pub custom_data: (u64, u64),
+    pub audio: (u64, u64),
+    pub cover: (u64, u64),
+    pub object_definitions: (u64, u64),
+    pub object_data: (u64, u64),
+}
+
+impl SSPMSections {
+    // Where the padding ends and the first section with data in it starts
+    pub fn data_start(&self) -> u64 {
+        [self.audio, self.cover]
+            .into_iter()
+            .filter(|&(_, length)| length > 0)
+            .map(|(offset, _)| offset)
+            .fold(self.object_definitions.0, u64::min)
     }
+}
 
-    fn deserialize<T: Read + Seek>(reader: T) -> io::Result<Map> {
-        let mut reader = BinaryReader::new(reader);
+// Everything in front of the sections, as read by `SSPMSerializer::read_head`
+struct SSPMHead {
+    millisecond: u32,
+    note_count: u32,
+    difficulty: u8,
+    has_audio: bool,
+    has_cover: bool,
+    has_mod: bool,
+    sections: SSPMSections,
+    map_id: String,
+    song_name: String,
+    mappers: Vec<String>,
+    custom_data: HashMap<String, ObjectType>,
+}
 
+fn take_difficulty_name(custom_data: &mut HashMap<String, ObjectType>) -> String {
+    match custom_data.remove("difficulty_name") {
+        Some(ObjectType::String(Some(name))) => name,
+        _ => String::new(),
+    }
+}
+
+impl SSPMSerializer {
+    // Only the header and the strings after it, the audio, cover and objects are never read. Enough
+    // to list a map, so folders with thousands of them can be indexed without loading each one
+    pub fn deserialize_meta<T: Read + Seek>(reader: T) -> io::Result<PartialMap> {
+        SSPMSerializer::deserialize_meta_with(reader, ReadOptions::default())
+    }
+
+    pub fn deserialize_meta_with<T: Read + Seek>(
+        reader: T,
+        options: ReadOptions,
+    ) -> io::Result<PartialMap> {
+        let mut reader = BinaryReader::with_options(reader, options);
+        let mut head = SSPMSerializer::read_head(&mut reader)?;
+
+        Ok(PartialMap {
+            id: head.map_id,
+            length: head.millisecond,
+            title: head.song_name,
+            artists: vec![],
+            difficulty: head.difficulty,
+            difficulty_name: take_difficulty_name(&mut head.custom_data),
+            mappers: head.mappers,
+            note_count: head.note_count,
+            has_audio: head.has_audio && head.sections.audio.1 > 0,
+            has_cover: head.has_cover && head.sections.cover.1 > 0,
+            format: MapFormat::SSPM,
+        })
+    }
+
+    fn read_head<T: Read + Seek>(reader: &mut BinaryReader<T>) -> io::Result<SSPMHead> {
         // Header structure:
         // The first 4 bytes are the file signature "SS+m"
         // The next 2 bytes are the version of the sspm (currently only version 2 is supported)
@@ -213,23 +627,23 @@ impl MapSerializer for SSPMSerializer {
 
         let _hash = reader.read_sha1()?; // SHA1 hash of the file
         let millisecond = reader.read_u32()?; // Last object millisecond
-        let _note_count = reader.read_u32()?; // Note object count
+        let note_count = reader.read_u32()?; // Note object count
         let _object_count = reader.read_u32()?; // Total object count ( including notes )
         //
-        let _difficulty = reader.read_u8()?;
+        let difficulty = reader.read_u8()?;
         let _star_rating = reader.read_u16()?; // never used
         let has_audio = reader.read_bool()?; // Whether the map has audio data
         let has_cover = reader.read_bool()?; // Whether the map has cover data
-        let _has_mod = reader.read_bool()?; // never used
-
-        let _custom_data_offset = reader.read_u64()?; // never used
-        let _custom_data_length = reader.read_u64()?; // never used
-        let audio_data_offset = reader.read_u64()?; // Length of audio data
-        let audio_data_length = reader.read_u64()?; // Offset of audio data
-        let cover_data_offset = reader.read_u64()?; // Length of cover data
-        let cover_data_length = reader.read_u64()?; // Offset of cover data
+        let has_mod = reader.read_bool()?; // Whether the map has mod objects
+
+        let custom_data_offset = reader.read_u64()?; // Offset of custom data
+        let custom_data_length = reader.read_u64()?; // Length of custom data
+        let audio_data_offset = reader.read_u64()?; // Offset of audio data
+        let audio_data_length = reader.read_u64()?; // Length of audio data
+        let cover_data_offset = reader.read_u64()?; // Offset of cover data
+        let cover_data_length = reader.read_u64()?; // Length of cover data
         let object_definition_offset = reader.read_u64()?; // Offset of object definitions
-        let _object_definition_length = reader.read_u64()?; // Length of object definitions
+        let object_definition_length = reader.read_u64()?; // Length of object definitions
         let object_data_offset = reader.read_u64()?; // Offset of object data
         let object_data_length = reader.read_u64()?; // Length of object data
 
@@ -250,107 +664,394 @@ impl MapSerializer for SSPMSerializer {
         for _ in 0..custom_data_fields {
             let name = reader.read_string()?;
             let data_type = ObjectType::from_sspm(reader.read_u8()?)?;
-            let value = SSPMSerializer::parse_types(&data_type, &mut reader)?;
+            let value = SSPMSerializer::parse_types(&data_type, reader)?;
 
             custom_data.insert(name, value);
         }
 
-        let mut audio_buf = vec![0u8; audio_data_length as usize];
-        let mut cover_buf = vec![0u8; cover_data_length as usize];
+        Ok(SSPMHead {
+            millisecond,
+            note_count,
+            difficulty,
+            has_audio,
+            has_cover,
+            has_mod,
+            sections: SSPMSections {
+                custom_data: (custom_data_offset, custom_data_length),
+                audio: (audio_data_offset, audio_data_length),
+                cover: (cover_data_offset, cover_data_length),
+                object_definitions: (object_definition_offset, object_definition_length),
+                object_data: (object_data_offset, object_data_length),
+            },
+            map_id,
+            song_name,
+            mappers,
+            custom_data,
+        })
+    }
+
+    // Leaves `padding` empty bytes in front of the audio so the metadata can grow in place later
+    pub fn serialize_padded<T: Write + Seek>(
+        map: &Map,
+        writer: T,
+        padding: u64,
+        options: WriteOptions,
+    ) -> io::Result<SSPMSections> {
+        let mut writer = BinaryWriter::with_options(writer, options);
+        let mut sections = SSPMSections {
+            custom_data: SSPMSerializer::write_head(&mut writer, map, &SSPMSections::default())?,
+            ..Default::default()
+        };
 
-        if has_audio {
-            reader.seek(io::SeekFrom::Start(audio_data_offset))?;
-            reader.read_exact(&mut audio_buf)?;
+        writer.write_all(&vec![0u8; padding as usize])?;
+
+        // Without audio the section is left at zero instead of pointing at whatever comes next
+        if let Some(audio) = &map.audio {
+            let audio_offset = writer.stream_position()?;
+            writer.write_all(&audio.bytes)?;
+            sections.audio = (audio_offset, writer.stream_position()? - audio_offset);
         }
 
-        if has_cover {
-            reader.seek(io::SeekFrom::Start(cover_data_offset))?;
-            reader.read_exact(&mut cover_buf)?;
+        if !map.cover.is_empty() {
+            let cover_offset = writer.stream_position()?;
+            writer.write_all(&map.cover)?;
+            sections.cover = (cover_offset, writer.stream_position()? - cover_offset);
         }
 
-        let mut object_definitions = HashMap::<u8, ObjectDefinition>::new();
+        (sections.object_definitions, sections.object_data) =
+            SSPMSerializer::write_objects(&mut writer, map)?;
 
-        reader.seek(io::SeekFrom::Start(object_definition_offset))?;
+        // The head is written again now that all offsets are known
+        writer.seek(SeekFrom::Start(0))?;
+        SSPMSerializer::write_head(&mut writer, map, &sections)?;
+        writer.seek(SeekFrom::End(0))?;
 
-        let object_count = reader.read_u8()?;
+        Ok(sections)
+    }
 
-        for count in 0..object_count {
-            let name = reader.read_string()?;
-            let values = reader.read_u8()?;
+    // Rewrites the head and objects of a file written by `serialize_padded` while keeping its audio
+    // and cover, returns None when the metadata outgrew the padding and a full save is needed.
+    // The second value is where the file ends now, the caller truncates anything after it.
+    pub fn rewrite_objects<T: Write + Seek>(
+        map: &Map,
+        writer: T,
+        previous: &SSPMSections,
+    ) -> io::Result<Option<(SSPMSections, u64)>> {
+        let mut head = BinaryWriter::new(Cursor::new(Vec::<u8>::new()));
+        SSPMSerializer::write_head(&mut head, map, previous)?;
+
+        let head_length = head.stream_position()?;
+        let data_start = previous.data_start();
+        if head_length > data_start {
+            return Ok(None);
+        }
 
-            let mut definitions = Vec::<ObjectType>::new();
+        let mut writer = BinaryWriter::new(writer);
+        writer.seek(SeekFrom::Start(previous.object_definitions.0))?;
 
-            for _ in 0..values {
-                let obj_type = reader.read_u8()?;
-                let data = ObjectType::from_sspm(obj_type)?;
+        let mut sections = *previous;
+        (sections.object_definitions, sections.object_data) =
+            SSPMSerializer::write_objects(&mut writer, map)?;
+        let end = writer.stream_position()?;
 
-                definitions.push(data);
+        writer.seek(SeekFrom::Start(0))?;
+        sections.custom_data = SSPMSerializer::write_head(&mut writer, map, &sections)?;
+        writer.write_all(&vec![0u8; (data_start - head_length) as usize])?;
+
+        Ok(Some((sections, end)))
+    }
+
+    // Header, section offsets and metadata, returns where the custom data ended up
+    fn write_head<T: Write + Seek>(
+        writer: &mut BinaryWriter<T>,
+        map: &Map,
+        sections: &SSPMSections,
+    ) -> io::Result<(u64, u64)> {
+        // Header
+        writer.write_all(b"SS+m")?; // File signature
+        writer.write_all(&[0x02, 0x00])?; // Version 2
+        writer.write_all(&[0u8; 4])?; // Unused bytes
+
+        // Static Metadata
+        writer.write_sha1(&[0u8; 20])?; // SHA1 is never used yet so ignore for now
+        writer.write_u32(map.length)?;
+        writer.write_u32(map.notes.len() as u32)?;
+        writer.write_u32(
+            (map.notes.len()
+                + map.timing_points.len()
+                + map.speed_changes.len()
+                + SSPMSerializer::custom_objects(map).len()) as u32,
+        )?;
+
+        writer.write_u8(map.difficulty)?;
+        writer.write_u16(0)?; // Star rating is never used
+        writer.write_bool(map.audio.is_some())?;
+        writer.write_bool(!map.cover.is_empty())?;
+        writer.write_bool(false)?; // Mods are never written, see modchart::ssp
+
+        for (offset, length) in [
+            sections.custom_data,
+            sections.audio,
+            sections.cover,
+            sections.object_definitions,
+            sections.object_data,
+        ] {
+            writer.write_u64(offset)?;
+            writer.write_u64(length)?;
+        }
+
+        writer.write_string(&map.id)?;
+        writer.write_string(&map.title)?;
+        writer.write_string(&map.title)?; // Song name is the same as title for now
+
+        writer.write_u16(map.mappers.len() as u16)?;
+        for mapper in map.mappers.iter() {
+            writer.write_string(mapper)?;
+        }
+
+        let mut custom_data_offset: u64 = 0;
+        let mut custom_data_length: u64 = 0;
+
+        // Notes are written in map order, so their index is enough to find them again
+        let hitsounds: Vec<(usize, Hitsound)> = map
+            .notes
+            .iter()
+            .enumerate()
+            .filter_map(|(index, note)| note.hitsound.map(|hitsound| (index, hitsound)))
+            .collect();
+
+        let colors: Vec<(usize, u8)> = map
+            .notes
+            .iter()
+            .enumerate()
+            .filter_map(|(index, note)| note.color.map(|color| (index, color)))
+            .collect();
+
+        let save_note_ids = map.save_note_ids && !map.notes.is_empty();
+
+        let fields = !map.difficulty_name.is_empty() as u16
+            + !map.bookmarks.is_empty() as u16
+            + !map.annotations.is_empty() as u16
+            + !hitsounds.is_empty() as u16
+            + !colors.is_empty() as u16
+            + save_note_ids as u16;
+
+        if fields > 0 {
+            custom_data_offset = writer.stream_position()?;
+
+            writer.write_u16(fields)?;
+
+            if !map.difficulty_name.is_empty() {
+                writer.write_string("difficulty_name")?;
+                writer.write_u8(0x09)?; // String type
+                writer.write_string(&map.difficulty_name)?;
             }
 
-            // There should be an empty byte after each object definition
-            assert_eq!(reader.read_u8()?, 0x00);
+            if !map.bookmarks.is_empty() {
+                writer.write_string("mm_bookmarks")?;
+                writer.write_u8(0x0B)?; // Long string type
+                writer.write_long_string(&serde_json::to_string(&map.bookmarks)?)?;
+            }
 
-            object_definitions.insert(
-                count,
-                ObjectDefinition {
-                    name,
-                    millisecond: 0,
-                    definitions,
-                },
-            );
+            if !map.annotations.is_empty() {
+                writer.write_string("mm_annotations")?;
+                writer.write_u8(0x0B)?; // Long string type
+                writer.write_long_string(&serde_json::to_string(&map.annotations)?)?;
+            }
+
+            if !hitsounds.is_empty() {
+                writer.write_string("mm_hitsounds")?;
+                writer.write_u8(0x0B)?; // Long string type
+                writer.write_long_string(&serde_json::to_string(&hitsounds)?)?;
+            }
+
+            if !colors.is_empty() {
+                writer.write_string("mm_note_colors")?;
+                writer.write_u8(0x0B)?; // Long string type
+                writer.write_long_string(&serde_json::to_string(&colors)?)?;
+            }
+
+            if save_note_ids {
+                let ids: Vec<u64> = map.notes.iter().map(|note| note.id.0).collect();
+                writer.write_string("mm_note_ids")?;
+                writer.write_u8(0x0B)?; // Long string type
+                writer.write_long_string(&serde_json::to_string(&ids)?)?;
+            }
+
+            custom_data_length = writer.stream_position()? - custom_data_offset;
+        } else {
+            writer.write_u16(0)?; // zero custom data fields
         }
 
-        reader.seek(io::SeekFrom::Start(object_data_offset))?;
-        let object_section_end = reader.stream_position()? + object_data_length;
+        Ok((custom_data_offset, custom_data_length))
+    }
 
-        let mut notes = Vec::<Note>::new();
-        let mut objects = Vec::<ObjectDefinition>::new();
+    // Object definitions and data followed by the export marker,
+    // returns the offset and length of both sections
+    fn write_objects<T: Write + Seek>(
+        writer: &mut BinaryWriter<T>,
+        map: &Map,
+    ) -> io::Result<((u64, u64), (u64, u64))> {
+        let object_definition_offset = writer.stream_position()?;
+        let has_timing = !map.timing_points.is_empty();
+        let has_speed = !map.speed_changes.is_empty();
+
+        // Custom objects get a definition per distinct name and field layout
+        let objects = SSPMSerializer::custom_objects(map);
+        let mut custom_definitions: Vec<(&str, &[u8])> = Vec::new();
+        let mut custom_objects = Vec::new();
+        for (object, types) in objects.iter() {
+            let index = match custom_definitions
+                .iter()
+                .position(|(name, t)| *name == object.name && t == types)
+            {
+                Some(index) => index,
+                None => {
+                    custom_definitions.push((&object.name, types));
+                    custom_definitions.len() - 1
+                }
+            };
+            custom_objects.push((object, index));
+        }
 
-        while reader.stream_position()? < object_section_end {
-            let ms = reader.read_u32()?;
-            let definition = reader.read_u8()?;
+        let custom_offset = 1 + has_timing as u8 + has_speed as u8;
+        if custom_offset as usize + custom_definitions.len() > u8::MAX as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Too many different kinds of custom objects",
+            ));
+        }
 
-            let object = SSPMSerializer::parse_definitions(
-                &object_definitions[&definition],
-                ms,
-                &mut reader,
-            )?;
+        writer.write_u8(custom_offset + custom_definitions.len() as u8)?;
+        writer.write_string("ssp_note")?;
+        writer.write_all(&[0x01, 0x07, 0x00])?; // One definition of type Vec2
 
-            match object.name.as_str() {
-                "ssp_note" => notes.push(Note::from_definition(object)?),
-                _ => objects.push(object),
+        if has_timing {
+            writer.write_string("mm_timing_point")?;
+            writer.write_all(&[0x02, 0x05, 0x01, 0x00])?; // F32 bpm and U8 beats per measure
+        }
+
+        // Definitions are numbered in the order they were written
+        let speed_definition = 1 + has_timing as u8;
+        if has_speed {
+            writer.write_string("mm_speed_change")?;
+            writer.write_all(&[0x01, 0x05, 0x00])?; // F32 multiplier
+        }
+
+        for (name, types) in custom_definitions.iter() {
+            writer.write_string(name)?;
+            writer.write_u8(types.len() as u8)?;
+            writer.write_all(types)?;
+            writer.write_u8(0x00)?;
+        }
+        let object_definition_length = writer.stream_position()? - object_definition_offset;
+
+        let object_data_offset = writer.stream_position()?;
+
+        // Timing points and speed changes are written in between the notes so objects stay in
+        // time order
+        let mut timing_points = map.timing_points.iter().peekable();
+        let mut speed_changes = map.speed_changes.iter().peekable();
+
+        for note in map.notes.iter() {
+            while let Some(timing) = timing_points.next_if(|t| t.millisecond <= note.millisecond) {
+                SSPMSerializer::write_timing_point(writer, timing)?;
             }
+            while let Some(change) = speed_changes.next_if(|c| c.millisecond <= note.millisecond) {
+                SSPMSerializer::write_speed_change(writer, change, speed_definition)?;
+            }
+
+            writer.write_u32(note.millisecond)?;
+            writer.write_u8(0x00)?;
+            writer.write_vec2(note.position)?;
         }
 
-        let audio_source = match audio_buf.is_empty() {
-            true => None,
-            false => Some(AudioSource {
-                bytes: audio_buf.into(),
-            }),
-        };
+        for timing in timing_points {
+            SSPMSerializer::write_timing_point(writer, timing)?;
+        }
+        for change in speed_changes {
+            SSPMSerializer::write_speed_change(writer, change, speed_definition)?;
+        }
 
-        Ok(Map {
-            id: map_id,
-            length: millisecond,
-            title: song_name,
-            artists: vec![],
-            difficulty: 0,
-            difficulty_name: String::new(),
-            mappers,
-            audio: audio_source,
-            cover: cover_buf,
-            notes,
-            objects,
-            format: MapFormat::SSPM,
-        })
+        // Objects are sorted when read, so these can go after everything else
+        for (object, index) in custom_objects {
+            writer.write_u32(object.millisecond)?;
+            writer.write_u8(custom_offset + index as u8)?;
+
+            for field in object.definitions.iter() {
+                SSPMSerializer::write_field(writer, field)?;
+            }
+        }
+
+        let object_data_length = writer.stream_position()? - object_data_offset;
+
+        writer.write_string(format!("MM Export - {}", "0.0.1").as_str())?;
+
+        Ok((
+            (object_definition_offset, object_definition_length),
+            (object_data_offset, object_data_length),
+        ))
+    }
+
+    fn write_timing_point<T: Write + Seek>(
+        writer: &mut BinaryWriter<T>,
+        timing: &TimingPoint,
+    ) -> io::Result<()> {
+        writer.write_u32(timing.millisecond)?;
+        writer.write_u8(0x01)?;
+        writer.write_f32(timing.bpm)?;
+        writer.write_u8(timing.beats_per_measure)
+    }
+
+    fn write_speed_change<T: Write + Seek>(
+        writer: &mut BinaryWriter<T>,
+        change: &SpeedChange,
+        definition: u8,
+    ) -> io::Result<()> {
+        writer.write_u32(change.millisecond)?;
+        writer.write_u8(definition)?;
+        writer.write_f32(change.multiplier)
+    }
+
+    // Custom objects that can be stored, along with their field types. Lyrics are turned back into
+    // the objects they were read from, imported mods are left to the modchart sidecar
+    fn custom_objects(map: &Map) -> Vec<(Cow<'_, ObjectDefinition>, Vec<u8>)> {
+        map.objects
+            .iter()
+            .map(Cow::Borrowed)
+            .chain(
+                map.lyrics
+                    .iter()
+                    .map(|lyric| Cow::Owned(lyric.to_definition())),
+            )
+            .filter(|object| object.definitions.len() <= u8::MAX as usize)
+            .filter_map(|object| object.sspm_types().map(|types| (object, types)))
+            .collect()
+    }
+
+    fn write_field<T: Write + Seek>(
+        writer: &mut BinaryWriter<T>,
+        field: &ObjectType,
+    ) -> io::Result<()> {
+        match field {
+            ObjectType::U8(Some(value)) => writer.write_u8(*value),
+            ObjectType::U16(Some(value)) => writer.write_u16(*value),
+            ObjectType::U32(Some(value)) => writer.write_u32(*value),
+            ObjectType::U64(Some(value)) => writer.write_u64(*value),
+            ObjectType::F32(Some(value)) => writer.write_f32(*value),
+            ObjectType::F64(Some(value)) => writer.write_f64(*value),
+            ObjectType::Vec2(Some(position)) => writer.write_vec2(*position),
+            ObjectType::Buf(Some(bytes)) => writer.write_buf(bytes),
+            ObjectType::LongBuf(Some(bytes)) => writer.write_long_buf(bytes),
+            ObjectType::String(Some(value)) => writer.write_string(value),
+            ObjectType::LongString(Some(value)) => writer.write_long_string(value),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Object field can't be stored in SSPM",
+            )),
+        }
     }
-}
-fn round_to_places(value: f32, places: u32) -> f32 {
-    let factor = 10f32.powi(places as i32);
-    (value * factor).round() / factor
-}
 
-impl SSPMSerializer {
     fn parse_definitions<T: Read + Seek>(
         marker_definition: &ObjectDefinition,
         ms: u32,
@@ -426,6 +1127,9 @@ impl SSPMSerializer {
         Ok(ObjectType::F64(Some(parser.read_f64()?)))
     }
 
+    // Grid positions are stored as the cell itself, 0 to 2, the way Sound Space Plus writes them.
+    // This used to write the cell plus one and read it back minus two, so every save moved grid
+    // notes and maps from the game opened a cell off
     fn parse_vec2<T: Read + Seek>(parser: &mut BinaryReader<T>) -> io::Result<ObjectType> {
         let quantum = parser.read_bool()?;
         let mut pos = Vec2::ZERO;
@@ -436,8 +1140,8 @@ impl SSPMSerializer {
                 pos.y = parser.read_f32()?;
             }
             false => {
-                pos.x = (parser.read_u8()? as f32) - 2.0;
-                pos.y = (parser.read_u8()? as f32) - 2.0;
+                pos.x = parser.read_u8()? as f32;
+                pos.y = parser.read_u8()? as f32;
             }
         };
 
@@ -470,6 +1174,7 @@ impl SSPMSerializer {
         Ok(ObjectType::LongString(Some(parser.read_long_string()?)))
     }
 
+    #[allow(dead_code)]
     fn parse_vec<T: Read + Seek>(_parser: &mut BinaryReader<T>) -> io::Result<ObjectType> {
         Err(io::Error::new(
             io::ErrorKind::InvalidData,
@@ -496,11 +1201,75 @@ struct PHXMMetadata {
 }
 
 impl MapSerializer for PHXMParser {
-    fn serialize<T: Write + Seek>(_map: &Map, _writer: T) -> io::Result<()> {
-        todo!()
+    fn serialize_with<T: Write + Seek>(
+        map: &Map,
+        writer: T,
+        write_options: WriteOptions,
+    ) -> io::Result<()> {
+        let mut folder = zip::ZipWriter::new(writer);
+        let options = SimpleFileOptions::default();
+
+        let audio_extension = map
+            .audio
+            .as_ref()
+            .and_then(|audio| audio_extension(&audio.bytes))
+            .unwrap_or("mp3");
+
+        let metadata = PHXMMetadata {
+            id: map.id.clone(),
+            has_audio: map.audio.is_some(),
+            has_cover: !map.cover.is_empty(),
+            has_video: false,
+            audio_extension: audio_extension.to_string(),
+            artist: map.artists.join(", "),
+            title: map.title.clone(),
+            mappers: map.mappers.clone(),
+            difficulty: map.difficulty,
+            difficulty_name: map.difficulty_name.clone(),
+            notes_count: map.notes.len() as u32,
+        };
+
+        folder.start_file("metadata.json", options)?;
+        folder.write_all(serde_json::to_string(&metadata)?.as_bytes())?;
+
+        let mut objects = BinaryWriter::new(Cursor::new(Vec::<u8>::new()));
+        objects.write_u32(1)?; // Only notes are stored
+        objects.write_u32(map.notes.len() as u32)?;
+
+        for note in map.notes.iter() {
+            let quantum = is_quantum_within(note.position, write_options.grid_epsilon);
+
+            objects.write_u32(note.millisecond)?;
+            objects.write_bool(quantum)?;
+
+            if quantum {
+                objects.write_f32(note.position.x)?;
+                objects.write_f32(note.position.y)?;
+            } else {
+                objects.write_u8(round(note.position.x) as u8 + 1)?;
+                objects.write_u8(round(note.position.y) as u8 + 1)?;
+            }
+        }
+
+        folder.start_file("objects.phxmo", options)?;
+        folder.write_all(&objects.into_inner().into_inner())?;
+
+        if let Some(audio) = &map.audio {
+            folder.start_file(format!("audio.{audio_extension}"), options)?;
+            folder.write_all(&audio.bytes)?;
+        }
+
+        if !map.cover.is_empty() {
+            folder.start_file("cover.png", options)?;
+            folder.write_all(&map.cover)?;
+        }
+
+        folder.finish()?;
+
+        Ok(())
     }
 
-    fn deserialize<T: Read + Seek>(reader: T) -> io::Result<Map> {
+    fn deserialize_with<T: Read + Seek>(reader: T, options: ReadOptions) -> io::Result<Map> {
         let mut folder = zip::ZipArchive::new(reader)?;
         let mut parser: BinaryReader<Cursor<Vec<u8>>>;
 
@@ -509,13 +1278,18 @@ impl MapSerializer for PHXMParser {
         let mut video_buf = Vec::<u8>::new();
         let metadata: PHXMMetadata;
         let mut notes: Vec<Note>;
+        let mut load_warnings = Vec::new();
 
         {
             let mut file = folder.by_name("metadata.json")?;
-            let mut buf = String::new();
-            file.read_to_string(&mut buf)?;
+            let mut buf = Vec::new();
+            file.read_to_end(&mut buf)?;
 
-            metadata = serde_json::from_str(&buf)?;
+            metadata = serde_json::from_str(&decode_string(
+                buf,
+                options.lossy_strings,
+                &mut load_warnings,
+            )?)?;
         }
 
         {
@@ -525,7 +1299,7 @@ impl MapSerializer for PHXMParser {
 
             let mut cursor = Cursor::new(buf);
             cursor.seek(SeekFrom::Start(0))?;
-            parser = BinaryReader::new(cursor);
+            parser = BinaryReader::with_options(cursor, options);
         }
 
         if metadata.has_audio {
@@ -557,8 +1331,11 @@ impl MapSerializer for PHXMParser {
                     let y = parser.read_f32()?;
 
                     notes.push(Note {
+                        id: NoteId::next(),
                         millisecond,
                         position: Vec2::new(x, y),
+                        hitsound: None,
+                        color: None,
                     });
                 }
                 false => {
@@ -566,8 +1343,11 @@ impl MapSerializer for PHXMParser {
                     let y = (parser.read_u8()? - 1) as f32;
 
                     notes.push(Note {
+                        id: NoteId::next(),
                         millisecond,
                         position: Vec2::new(x, y),
+                        hitsound: None,
+                        color: None,
                     });
                 }
             }
@@ -580,6 +1360,8 @@ impl MapSerializer for PHXMParser {
             }),
         };
 
+        notes.sort_by_key(|note| note.millisecond);
+
         Ok(Map {
             id: metadata.id,
             length: notes.last().map_or(0, |n| n.millisecond),
@@ -590,9 +1372,10 @@ impl MapSerializer for PHXMParser {
             mappers: metadata.mappers,
             audio: audio_source,
             cover: cover_buf,
-            notes: notes,
-            objects: vec![],
+            notes,
             format: MapFormat::PHXM,
+            load_warnings,
+            ..Map::default()
         })
     }
 }
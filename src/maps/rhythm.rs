@@ -0,0 +1,414 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    jukebox::audio_extension,
+    maps::{
+        Map,
+        export::{normalize, template_filename},
+    },
+};
+
+// Exports for games that only have rhythm, positions are dropped and lanes are picked from the
+// timing and density of the notes instead. Each export is a folder the game can open directly
+
+// Beats are counted at this tempo when the map has no timing points
+const FALLBACK_BPM: f64 = 120.0;
+
+// Notes this close to a beat are on it, maps are rarely timed exactly
+const ON_BEAT_TOLERANCE_MS: f64 = 3.0;
+
+// Half the width of the window density is measured over
+const DENSITY_WINDOW_MS: u32 = 1000;
+
+// Taiko finishers are only used below this many notes per second, in streams they can't be hit
+const FINISHER_DENSITY: f32 = 4.0;
+
+// Ticks per beat in the Clone Hero chart
+const CHART_RESOLUTION: f64 = 192.0;
+const FRETS: u8 = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RhythmFormat {
+    OsuTaiko,
+    CloneHero,
+}
+
+impl RhythmFormat {
+    pub const ALL: [RhythmFormat; 2] = [RhythmFormat::OsuTaiko, RhythmFormat::CloneHero];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RhythmFormat::OsuTaiko => "osu!taiko",
+            RhythmFormat::CloneHero => "Clone Hero",
+        }
+    }
+}
+
+// Notes at the same millisecond collapse into one hit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hit {
+    pub millisecond: u32,
+    pub count: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaikoHit {
+    Don { big: bool },
+    Kat { big: bool },
+}
+
+#[derive(Debug)]
+pub struct RhythmExport {
+    pub folder: PathBuf,
+    pub warnings: Vec<String>,
+}
+
+// Notes must be sorted by time, like they are after normalize
+pub fn hits(map: &Map) -> Vec<Hit> {
+    let mut hits: Vec<Hit> = Vec::new();
+
+    for note in map.notes.iter() {
+        match hits.last_mut() {
+            Some(last) if last.millisecond == note.millisecond => last.count += 1,
+            _ => hits.push(Hit {
+                millisecond: note.millisecond,
+                count: 1,
+            }),
+        }
+    }
+
+    hits
+}
+
+// Hits per second around each hit. Hits are sorted by time, so the window's edges only ever move
+// forward
+pub fn densities(hits: &[Hit]) -> Vec<f32> {
+    let mut first = 0;
+    let mut last = 0;
+
+    hits.iter()
+        .map(|hit| {
+            let start = hit.millisecond.saturating_sub(DENSITY_WINDOW_MS);
+            let end = hit.millisecond.saturating_add(DENSITY_WINDOW_MS);
+
+            while hits[first].millisecond < start {
+                first += 1;
+            }
+            while last < hits.len() && hits[last].millisecond <= end {
+                last += 1;
+            }
+
+            (last - first) as f32 * 1000.0 / (end - start) as f32
+        })
+        .collect()
+}
+
+fn on_beat(map: &Map, millisecond: u32) -> bool {
+    let (origin, beat) = match map.timing_point_at(millisecond) {
+        Some(timing) => (timing.millisecond as f64, timing.beat_length()),
+        None => (0.0, 60000.0 / FALLBACK_BPM),
+    };
+
+    let beats = (millisecond as f64 - origin) / beat;
+    ((beats - beats.round()) * beat).abs() <= ON_BEAT_TOLERANCE_MS
+}
+
+// Dons on the beat and kats between them, chords become finishers where there is time for them
+pub fn taiko_hits(map: &Map, hits: &[Hit]) -> Vec<TaikoHit> {
+    hits.iter()
+        .zip(densities(hits))
+        .map(|(hit, density)| {
+            let big = hit.count > 1 && density < FINISHER_DENSITY;
+            match on_beat(map, hit.millisecond) {
+                true => TaikoHit::Don { big },
+                false => TaikoHit::Kat { big },
+            }
+        })
+        .collect()
+}
+
+// Frets walk up when the notes speed up and down when they slow down, and keep going the same
+// way at a steady pace. Sparse sections stay on the lower frets, dense ones use all of them.
+// Chords add the frets above the lowest one
+pub fn chart_frets(hits: &[Hit]) -> Vec<Vec<u8>> {
+    let mut frets = Vec::with_capacity(hits.len());
+    let mut fret: i32 = 0;
+    let mut direction: i32 = 1;
+    let mut previous_gap = None;
+
+    for ((index, hit), density) in hits.iter().enumerate().zip(densities(hits)) {
+        let available = ((density / 2.0).ceil() as i32).clamp(2, FRETS as i32);
+        let chord = (hit.count as i32).min(available);
+
+        if index > 0 {
+            let gap = hit.millisecond - hits[index - 1].millisecond;
+            direction = match previous_gap {
+                Some(previous) if gap < previous => 1,
+                Some(previous) if gap > previous => -1,
+                _ => direction,
+            };
+            previous_gap = Some(gap);
+
+            fret += direction;
+        }
+
+        // Bounces off the edges of the frets in use
+        let highest = available - chord;
+        if fret > highest || fret < 0 {
+            direction = -direction;
+            fret = fret.clamp(0, highest);
+        }
+
+        frets.push((fret..fret + chord).map(|f| f as u8).collect());
+    }
+
+    frets
+}
+
+fn osu_file(map: &Map, audio_file: Option<&str>, cover_file: Option<&str>) -> String {
+    let hits = hits(map);
+    let taiko = taiko_hits(map, &hits);
+    let artists = map.artists.join(", ");
+
+    let mut lines = vec![
+        "osu file format v14".to_string(),
+        String::new(),
+        "[General]".to_string(),
+        format!("AudioFilename: {}", audio_file.unwrap_or_default()),
+        "AudioLeadIn: 0".to_string(),
+        "PreviewTime: -1".to_string(),
+        "Countdown: 0".to_string(),
+        "SampleSet: Normal".to_string(),
+        "Mode: 1".to_string(),
+        String::new(),
+        "[Metadata]".to_string(),
+        format!("Title:{}", map.title),
+        format!("TitleUnicode:{}", map.title),
+        format!("Artist:{artists}"),
+        format!("ArtistUnicode:{artists}"),
+        format!("Creator:{}", map.mappers.join(", ")),
+        format!("Version:{}", map.difficulty_name),
+        "BeatmapID:0".to_string(),
+        "BeatmapSetID:-1".to_string(),
+        String::new(),
+        "[Difficulty]".to_string(),
+        "HPDrainRate:5".to_string(),
+        "CircleSize:5".to_string(),
+        "OverallDifficulty:5".to_string(),
+        "ApproachRate:5".to_string(),
+        "SliderMultiplier:1.4".to_string(),
+        "SliderTickRate:1".to_string(),
+        String::new(),
+        "[Events]".to_string(),
+    ];
+
+    if let Some(cover) = cover_file {
+        lines.push(format!("0,0,\"{cover}\",0,0"));
+    }
+
+    // Time, beat length, meter, sample set, sample index, volume, uninherited and effects
+    lines.push(String::new());
+    lines.push("[TimingPoints]".to_string());
+    if map.timing_points.is_empty() {
+        lines.push(format!("0,{},4,1,0,100,1,0", 60000.0 / FALLBACK_BPM));
+    }
+    for timing in map.timing_points.iter() {
+        lines.push(format!(
+            "{},{},{},1,0,100,1,0",
+            timing.millisecond,
+            timing.beat_length(),
+            timing.beats_per_measure
+        ));
+    }
+
+    // Taiko reads claps as kats and finishes as big notes, positions are ignored
+    lines.push(String::new());
+    lines.push("[HitObjects]".to_string());
+    for (hit, taiko) in hits.iter().zip(taiko) {
+        let hitsound = match taiko {
+            TaikoHit::Don { big } => 4 * big as u8,
+            TaikoHit::Kat { big } => 8 + 4 * big as u8,
+        };
+        lines.push(format!("256,192,{},1,{hitsound},0:0:0:0:", hit.millisecond));
+    }
+
+    lines.join("\n") + "\n"
+}
+
+// Tempo changes in chart ticks, along with the milliseconds they start at
+fn tempo_map(map: &Map) -> Vec<(u32, u64, f64)> {
+    let mut tempos: Vec<(u32, u64, f64)> = Vec::new();
+
+    for timing in map.timing_points.iter() {
+        let tick = match tempos.last() {
+            Some(&(millisecond, tick, bpm)) => {
+                tick + ((timing.millisecond - millisecond) as f64 / 60000.0
+                    * bpm
+                    * CHART_RESOLUTION)
+                    .round() as u64
+            }
+            // The first tempo also covers the time before it
+            None => (timing.millisecond as f64 / 60000.0 * timing.bpm as f64 * CHART_RESOLUTION)
+                .round() as u64,
+        };
+        tempos.push((timing.millisecond, tick, timing.bpm as f64));
+    }
+
+    if tempos.is_empty() {
+        tempos.push((0, 0, FALLBACK_BPM));
+    }
+
+    tempos
+}
+
+fn chart_tick(tempos: &[(u32, u64, f64)], millisecond: u32) -> u64 {
+    let &(start, tick, bpm) = tempos
+        .iter()
+        .rev()
+        .find(|(start, ..)| *start <= millisecond)
+        .unwrap_or(&tempos[0]);
+
+    let ms = millisecond as f64 - start as f64;
+    (tick as f64 + ms / 60000.0 * bpm * CHART_RESOLUTION).round() as u64
+}
+
+// Chart strings are quoted and have no escapes, so quotes are dropped and line breaks would end
+// the field early
+fn chart_text(text: &str) -> String {
+    text.chars()
+        .filter(|c| *c != '"')
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect()
+}
+
+fn chart_file(map: &Map, audio_file: Option<&str>) -> String {
+    let hits = hits(map);
+    let frets = chart_frets(&hits);
+    let tempos = tempo_map(map);
+
+    let mut lines = vec![
+        "[Song]".to_string(),
+        "{".to_string(),
+        format!("  Name = \"{}\"", chart_text(&map.title)),
+        format!("  Artist = \"{}\"", chart_text(&map.artists.join(", "))),
+        format!("  Charter = \"{}\"", chart_text(&map.mappers.join(", "))),
+        "  Offset = 0".to_string(),
+        format!("  Resolution = {CHART_RESOLUTION}"),
+        "  Player2 = bass".to_string(),
+        format!("  Difficulty = {}", map.difficulty),
+        "  MediaType = \"cd\"".to_string(),
+    ];
+    if let Some(audio) = audio_file {
+        lines.push(format!("  MusicStream = \"{audio}\""));
+    }
+    lines.push("}".to_string());
+
+    // Every timing point brings its meter along with its tempo, which is written in thousandths of
+    // a beat per minute. The first meter also covers the time before it
+    let meters = map
+        .timing_points
+        .iter()
+        .map(|timing| timing.beats_per_measure)
+        .chain(map.timing_points.is_empty().then_some(4));
+    lines.push("[SyncTrack]".to_string());
+    lines.push("{".to_string());
+    for (index, (&(_, tick, bpm), meter)) in tempos.iter().zip(meters).enumerate() {
+        let meter_tick = if index == 0 { 0 } else { tick };
+        lines.push(format!("  {meter_tick} = TS {meter}"));
+        lines.push(format!("  {tick} = B {}", (bpm * 1000.0).round() as u64));
+    }
+    lines.push("}".to_string());
+
+    lines.push("[Events]".to_string());
+    lines.push("{".to_string());
+    lines.push("}".to_string());
+
+    lines.push("[ExpertSingle]".to_string());
+    lines.push("{".to_string());
+    for (hit, frets) in hits.iter().zip(frets) {
+        let tick = chart_tick(&tempos, hit.millisecond);
+        for fret in frets {
+            lines.push(format!("  {tick} = N {fret} 0"));
+        }
+    }
+    lines.push("}".to_string());
+
+    lines.join("\n") + "\n"
+}
+
+fn warnings(map: &Map, format: RhythmFormat) -> Vec<String> {
+    let mut warnings = vec!["Positions are dropped, lanes are picked from the rhythm".to_string()];
+
+    if map.audio.is_none() {
+        warnings.push(format!(
+            "The map has no audio, {} needs a song next to the chart",
+            format.label()
+        ));
+    }
+
+    if format == RhythmFormat::CloneHero && map.timing_points.is_empty() {
+        warnings.push(format!(
+            "The map has no timing points, the chart is counted at {FALLBACK_BPM} BPM"
+        ));
+    }
+
+    warnings
+}
+
+// Writes the chart into its own folder inside `folder`, named with the export filename template
+pub fn export_rhythm(
+    map: &Map,
+    format: RhythmFormat,
+    folder: &Path,
+    template: &str,
+) -> std::io::Result<RhythmExport> {
+    let map = normalize(map);
+
+    let name = template_filename(&map, template, "txt");
+    let name = name.strip_suffix(".txt").unwrap_or(&name);
+    let level = folder.join(format!("{name} ({})", format.label()));
+    fs::create_dir_all(&level)?;
+
+    let audio_file = map.audio.as_ref().map(|audio| {
+        let extension = audio_extension(&audio.bytes).unwrap_or("mp3");
+        match format {
+            RhythmFormat::OsuTaiko => format!("audio.{extension}"),
+            RhythmFormat::CloneHero => format!("song.{extension}"),
+        }
+    });
+    if let (Some(audio), Some(file)) = (&map.audio, &audio_file) {
+        fs::write(level.join(file), &audio.bytes)?;
+    }
+
+    let cover_extension = match map.cover.starts_with(b"\x89PNG") {
+        _ if map.cover.is_empty() => None,
+        true => Some("png"),
+        false => Some("jpg"),
+    };
+    let cover_file = cover_extension.map(|extension| match format {
+        RhythmFormat::OsuTaiko => format!("cover.{extension}"),
+        RhythmFormat::CloneHero => format!("album.{extension}"),
+    });
+    if let Some(file) = &cover_file {
+        fs::write(level.join(file), &map.cover)?;
+    }
+
+    match format {
+        RhythmFormat::OsuTaiko => fs::write(
+            level.join(format!("{name}.osu")),
+            osu_file(&map, audio_file.as_deref(), cover_file.as_deref()),
+        )?,
+        RhythmFormat::CloneHero => fs::write(
+            level.join("notes.chart"),
+            chart_file(&map, audio_file.as_deref()),
+        )?,
+    }
+
+    Ok(RhythmExport {
+        folder: level,
+        warnings: warnings(&map, format),
+    })
+}
@@ -0,0 +1,512 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError, mpsc},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    analysis::{
+        difficulty::{DifficultyCurve, SAMPLE_MS, peak_notes_per_second},
+        readability::{Readability, UnreadableSection},
+        tags::{ChartTag, chart_tags},
+    },
+    maps::{DIFFICULTY_NAMES, Map, io::ReadOptions, library::find_maps, read_map},
+    theme::approach::ApproachProfile,
+};
+
+// Serves the map library over HTTP without starting the editor, for a home server feeding a web
+// chart browser. Responses are JSON apart from downloads, and any site may fetch them.
+//
+//   GET /maps             every map that could be read
+//   GET /maps/<id>        one map's metadata
+//   GET /maps/<id>/stats  note counts and the difficulty curve
+//   GET /maps/<id>/file   the map file as it is on disk
+
+pub const DEFAULT_PORT: u16 = 8490;
+
+// Folders are walked again this often in the background, only files that changed are read again
+const RESCAN_INTERVAL: Duration = Duration::from_secs(30);
+
+// Requests answered at the same time, more connections wait for a free worker
+const WORKERS: usize = 4;
+
+// Requests are a line and a few headers, clients that send more or stall are cut off
+const MAX_REQUEST_BYTES: u64 = 16 * 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MapSummary {
+    pub id: String,
+    pub file: String,
+    pub format: &'static str,
+    pub title: String,
+    pub artists: Vec<String>,
+    pub mappers: Vec<String>,
+    pub difficulty: u8,
+    pub difficulty_name: String,
+    pub length_ms: u32,
+    pub notes: usize,
+    // Of the first timing point
+    pub bpm: Option<f32>,
+    pub tags: Vec<ChartTag>,
+    pub has_audio: bool,
+    pub has_cover: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MapStats {
+    pub notes: usize,
+    pub length_ms: u32,
+    pub notes_per_second: f32,
+    pub peak_notes_per_second: usize,
+    // The strain from analysis::difficulty, one value every `sample_ms`
+    pub peak_strain: f32,
+    pub sample_ms: u32,
+    pub strain: Vec<f32>,
+    // From analysis::readability with the default approach profile
+    pub readability: f32,
+    pub unreadable: Vec<UnreadableSection>,
+}
+
+struct ServedMap {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    summary: MapSummary,
+    stats: MapStats,
+}
+
+impl ServedMap {
+    // Only what is served is kept, not the map with its audio. The id is made unique when the map
+    // is added to the library
+    fn new(path: PathBuf, modified: Option<SystemTime>, map: &Map) -> Self {
+        let difficulty_name = match map.difficulty_name.trim() {
+            "" => DIFFICULTY_NAMES
+                .get(map.difficulty as usize)
+                .copied()
+                .unwrap_or_default()
+                .to_string(),
+            name => name.to_string(),
+        };
+
+        let summary = MapSummary {
+            id: url_id(&map.id),
+            file: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            format: map.format.extension(),
+            title: map.title.clone(),
+            artists: map.artists.clone(),
+            mappers: map.mappers.clone(),
+            difficulty: map.difficulty,
+            difficulty_name,
+            length_ms: map.length.max(map.last_object_ms()),
+            notes: map.notes.len(),
+            bpm: map.timing_points.first().map(|timing| timing.bpm),
+            tags: chart_tags(&map.notes),
+            has_audio: map.audio.is_some(),
+            has_cover: !map.cover.is_empty(),
+        };
+
+        Self {
+            path,
+            modified,
+            summary,
+            stats: map_stats(map),
+        }
+    }
+}
+
+// Files in the folders with when they were last changed, and what reading the new ones gave
+pub struct Scan {
+    found: BTreeMap<PathBuf, Option<SystemTime>>,
+    read: Vec<(PathBuf, Option<SystemTime>, Result<ServedMap, String>)>,
+}
+
+pub struct ScanPlan {
+    folders: Vec<PathBuf>,
+    options: ReadOptions,
+    known: BTreeMap<PathBuf, Option<SystemTime>>,
+}
+
+impl ScanPlan {
+    // Only files that are new or changed since the plan was made are read
+    pub fn run(self) -> Scan {
+        let mut paths = Vec::new();
+        for folder in self.folders.iter() {
+            find_maps(folder, &mut paths);
+        }
+        let found: BTreeMap<PathBuf, Option<SystemTime>> = paths
+            .into_iter()
+            .map(|path| {
+                let time = modified(&path);
+                (path, time)
+            })
+            .collect();
+
+        let read = found
+            .iter()
+            .filter(|(path, time)| self.known.get(*path) != Some(*time))
+            .map(|(path, time)| {
+                let read = read_map(path, self.options)
+                    .map(|map| ServedMap::new(path.clone(), *time, &map))
+                    .map_err(|e| e.to_string());
+                (path.clone(), *time, read)
+            })
+            .collect();
+
+        Scan { found, read }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+    // Offered as the name to save downloads under
+    pub filename: Option<String>,
+}
+
+fn json_response(status: u16, value: &impl Serialize) -> Response {
+    Response {
+        status,
+        content_type: "application/json",
+        body: serde_json::to_vec(value).unwrap_or_default(),
+        filename: None,
+    }
+}
+
+fn error_response(status: u16, message: &str) -> Response {
+    json_response(status, &json!({ "error": message }))
+}
+
+// Map ids are free text, the URL only gets the characters that never need escaping
+pub fn url_id(id: &str) -> String {
+    let id: String = id
+        .trim()
+        .chars()
+        .map(
+            |c| match c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                true => c,
+                false => '_',
+            },
+        )
+        .collect();
+
+    match id.is_empty() {
+        true => "map".to_string(),
+        false => id,
+    }
+}
+
+pub fn map_stats(map: &Map) -> MapStats {
+    let length_ms = map.length.max(map.last_object_ms());
+    let span_ms = match (map.notes.first(), map.notes.last()) {
+        (Some(first), Some(last)) => last.millisecond - first.millisecond,
+        _ => 0,
+    };
+    let curve = DifficultyCurve::from_notes(&map.notes, length_ms);
+    let readability = Readability::from_notes(&map.notes, length_ms, &ApproachProfile::default());
+
+    MapStats {
+        notes: map.notes.len(),
+        length_ms,
+        notes_per_second: match span_ms {
+            0 => map.notes.len() as f32,
+            ms => map.notes.len() as f32 / ms as f32 * 1000.0,
+        },
+        peak_notes_per_second: peak_notes_per_second(&map.notes),
+        peak_strain: curve.peak(),
+        sample_ms: SAMPLE_MS,
+        strain: curve.values().to_vec(),
+        readability: readability.score(),
+        unreadable: readability.unreadable().to_vec(),
+    }
+}
+
+#[derive(Default)]
+pub struct ServedLibrary {
+    folders: Vec<PathBuf>,
+    // By id in the URL
+    maps: BTreeMap<String, ServedMap>,
+    // Files that could not be read, kept so they're only tried again once they change
+    failed: BTreeMap<PathBuf, Option<SystemTime>>,
+    options: ReadOptions,
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl ServedLibrary {
+    pub fn new(folders: Vec<PathBuf>) -> Self {
+        Self {
+            folders,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_options(mut self, options: ReadOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.maps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.maps.is_empty()
+    }
+
+    // Maps sharing an id are told apart by a number, in the order they were found
+    pub fn add(&mut self, path: PathBuf, modified: Option<SystemTime>, map: &Map) {
+        self.insert(ServedMap::new(path, modified, map));
+    }
+
+    fn insert(&mut self, mut served: ServedMap) {
+        let base = served.summary.id.clone();
+        let mut id = base.clone();
+        let mut number = 1;
+        while self.maps.contains_key(&id) {
+            number += 1;
+            id = format!("{base}-{number}");
+        }
+
+        served.summary.id = id.clone();
+        self.maps.insert(id, served);
+    }
+
+    // Walks the folders, dropping maps that are gone and reading the ones that are new or changed
+    pub fn rescan(&mut self) {
+        let scan = self.plan_scan().run();
+        self.merge(scan);
+    }
+
+    // What a rescan needs to know about the library, so the folders can be walked and the maps
+    // read without holding on to it
+    pub fn plan_scan(&self) -> ScanPlan {
+        ScanPlan {
+            folders: self.folders.clone(),
+            options: self.options,
+            // Maps without a modification time can't be told apart from a changed one
+            known: self
+                .maps
+                .values()
+                .filter(|served| served.modified.is_some())
+                .map(|served| (served.path.clone(), served.modified))
+                .chain(self.failed.iter().map(|(path, time)| (path.clone(), *time)))
+                .collect(),
+        }
+    }
+
+    pub fn merge(&mut self, scan: Scan) {
+        let unchanged = |path: &PathBuf, time: &Option<SystemTime>| {
+            scan.found.get(path).is_some_and(|found| found == time)
+        };
+        self.maps.retain(|_, served| {
+            served.modified.is_some() && unchanged(&served.path, &served.modified)
+        });
+        self.failed.retain(|path, time| unchanged(path, time));
+
+        for (path, time, read) in scan.read {
+            match read {
+                Ok(served) => self.insert(served),
+                Err(e) => {
+                    eprintln!("{}: {e}", path.display());
+                    self.failed.insert(path, time);
+                }
+            }
+        }
+    }
+
+    pub fn handle(&self, method: &str, target: &str) -> Response {
+        if !matches!(method, "GET" | "HEAD") {
+            return error_response(405, "Only GET requests are served");
+        }
+
+        let path = target.split(['?', '#']).next().unwrap_or_default();
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+        match segments[..] {
+            [] => json_response(
+                200,
+                &json!({
+                    "maps": self.maps.len(),
+                    "endpoints": ["/maps", "/maps/<id>", "/maps/<id>/stats", "/maps/<id>/file"],
+                }),
+            ),
+            ["maps"] => json_response(
+                200,
+                &self
+                    .maps
+                    .values()
+                    .map(|served| &served.summary)
+                    .collect::<Vec<_>>(),
+            ),
+            ["maps", id, ref rest @ ..] => {
+                let Some(served) = self.maps.get(id) else {
+                    return error_response(404, &format!("No map with id {id}"));
+                };
+
+                match rest {
+                    [] => json_response(200, &served.summary),
+                    ["stats"] => json_response(200, &served.stats),
+                    ["file"] => match fs::read(&served.path) {
+                        Ok(body) => Response {
+                            status: 200,
+                            content_type: "application/octet-stream",
+                            body,
+                            filename: Some(served.summary.file.clone()),
+                        },
+                        Err(e) => error_response(500, &format!("Could not read the map: {e}")),
+                    },
+                    _ => error_response(404, "Not found"),
+                }
+            }
+            _ => error_response(404, "Not found"),
+        }
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error",
+    }
+}
+
+fn write_response(mut stream: TcpStream, response: &Response, head_only: bool) -> io::Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\nConnection: close\r\n",
+        response.status,
+        reason(response.status),
+        response.content_type,
+        response.body.len()
+    );
+
+    if let Some(file) = &response.filename {
+        // Header values have to stay plain ASCII
+        let file: String = file
+            .chars()
+            .map(|c| match (c.is_ascii_graphic() && c != '"') || c == ' ' {
+                true => c,
+                false => '_',
+            })
+            .collect();
+        head.push_str(&format!(
+            "Content-Disposition: attachment; filename=\"{file}\"\r\n"
+        ));
+    }
+    head.push_str("\r\n");
+
+    stream.write_all(head.as_bytes())?;
+    if !head_only {
+        stream.write_all(&response.body)?;
+    }
+    stream.flush()
+}
+
+fn respond(stream: TcpStream, library: &Mutex<ServedLibrary>) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?.take(MAX_REQUEST_BYTES));
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    // Headers are read up to the blank line so the client sees its whole request was taken
+    let mut header = String::new();
+    loop {
+        header.clear();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return write_response(stream, &error_response(400, "Malformed request"), false);
+    };
+
+    let response = library
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .handle(method, target);
+
+    write_response(stream, &response, method == "HEAD")
+}
+
+// Runs until the process is stopped. The library is only locked to answer a request or swap in
+// what a rescan read, never while maps are being read
+pub fn serve(library: ServedLibrary, address: impl ToSocketAddrs) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    println!(
+        "Serving {} maps on http://{}",
+        library.len(),
+        listener.local_addr()?
+    );
+
+    let library = Arc::new(Mutex::new(library));
+
+    let scanned = library.clone();
+    thread::spawn(move || {
+        loop {
+            thread::sleep(RESCAN_INTERVAL);
+            let plan = scanned
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .plan_scan();
+            let scan = plan.run();
+            scanned
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .merge(scan);
+        }
+    });
+
+    let (streams, waiting) = mpsc::channel::<TcpStream>();
+    let waiting = Arc::new(Mutex::new(waiting));
+    for _ in 0..WORKERS {
+        let waiting = waiting.clone();
+        let library = library.clone();
+        thread::spawn(move || {
+            loop {
+                let stream = match waiting
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .recv()
+                {
+                    Ok(stream) => stream,
+                    Err(_) => return,
+                };
+                if let Err(e) = respond(stream, &library) {
+                    eprintln!("Could not answer a request: {e}");
+                }
+            }
+        });
+    }
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let _ = streams.send(stream);
+            }
+            Err(e) => eprintln!("Could not accept a connection: {e}"),
+        }
+    }
+
+    Ok(())
+}
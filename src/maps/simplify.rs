@@ -0,0 +1,131 @@
+use bevy::math::Vec2;
+use serde::{Deserialize, Serialize};
+
+use crate::maps::{Map, objects::Note, parser::is_quantum};
+
+// Maps without timing points are thinned as if they were at this many beats per minute
+const FALLBACK_BEAT_MS: f64 = 500.0;
+
+// Notes this close to a kept subdivision still count as on it, maps are rarely timed exactly
+const ON_BEAT_TOLERANCE_MS: f64 = 3.0;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimplifyOptions {
+    // Only notes on 1/divisor beats are kept, 1 keeps one note per beat
+    pub divisor: u32,
+    // Longest distance in cells between two notes in a row, longer jumps are pulled closer
+    pub max_jump: f32,
+    // Quantum notes are moved to the cell they are closest to
+    pub remove_quantum: bool,
+    pub difficulty_name: String,
+}
+
+impl Default for SimplifyOptions {
+    fn default() -> Self {
+        Self {
+            divisor: 2,
+            max_jump: 1.5,
+            remove_quantum: true,
+            difficulty_name: "Easy".to_string(),
+        }
+    }
+}
+
+// What changed, shown next to the tool so the result can be judged before touching it up
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SimplifyReport {
+    pub kept: usize,
+    pub removed: usize,
+    pub shortened_jumps: usize,
+    pub snapped: usize,
+}
+
+fn on_subdivision(map: &Map, millisecond: u32, divisor: u32) -> bool {
+    let (origin, beat) = match map.timing_point_at(millisecond) {
+        Some(timing) => (timing.millisecond as f64, timing.beat_length()),
+        None => (0.0, FALLBACK_BEAT_MS),
+    };
+
+    let step = beat / divisor.max(1) as f64;
+    let steps = (millisecond as f64 - origin) / step;
+    ((steps - steps.round()) * step).abs() <= ON_BEAT_TOLERANCE_MS
+}
+
+// Pulls `position` toward `previous` until it is at most `max_jump` away. On the grid every axis is
+// rounded toward the previous note, which can only make the jump shorter
+fn clamp_jump(previous: Vec2, position: Vec2, max_jump: f32, on_grid: bool) -> Vec2 {
+    let delta = position - previous;
+    if delta.length() <= max_jump {
+        return position;
+    }
+
+    let pulled = delta.normalize_or_zero() * max_jump;
+    let pulled = match on_grid {
+        true => pulled.trunc(),
+        false => pulled,
+    };
+    (previous + pulled).clamp(Vec2::ZERO, Vec2::splat(2.0))
+}
+
+// An easier difficulty of the same song, made from the notes of `map`. Timing, speed changes,
+// bookmarks and mods are kept, only the notes change
+pub fn simplify(map: &Map, options: &SimplifyOptions) -> (Map, SimplifyReport) {
+    let mut report = SimplifyReport::default();
+    let mut notes: Vec<Note> = Vec::new();
+
+    for note in map.notes.iter() {
+        // Stacked notes become a single one
+        let stacked = notes
+            .last()
+            .is_some_and(|last| last.millisecond == note.millisecond);
+        if stacked || !on_subdivision(map, note.millisecond, options.divisor) {
+            report.removed += 1;
+            continue;
+        }
+
+        let mut note = *note;
+        if options.remove_quantum && is_quantum(note.position) {
+            note.position = note.position.round().clamp(Vec2::ZERO, Vec2::splat(2.0));
+            report.snapped += 1;
+        }
+
+        if let Some(previous) = notes.last() {
+            let clamped = clamp_jump(
+                previous.position,
+                note.position,
+                options.max_jump,
+                options.remove_quantum,
+            );
+            if clamped != note.position {
+                note.position = clamped;
+                report.shortened_jumps += 1;
+            }
+        }
+
+        notes.push(note);
+    }
+    report.kept = notes.len();
+
+    let mut simplified = map.clone();
+    simplified.notes = notes;
+    simplified.difficulty = map.difficulty.saturating_sub(1);
+    simplified.difficulty_name = match options.difficulty_name.trim() {
+        "" => format!("{} (simplified)", map.difficulty_name),
+        name => name.to_string(),
+    };
+    simplified.id = format!(
+        "{}_{}",
+        map.id,
+        simplified
+            .difficulty_name
+            .to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("_")
+    );
+    simplified.load_warnings.clear();
+
+    (simplified, report)
+}
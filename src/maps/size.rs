@@ -0,0 +1,247 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use crate::maps::{
+    Map, MapFormat,
+    io::WriteOptions,
+    legacy::{LegacySerializer, legacy_id},
+    parser::{MapSerializer, SSPMSerializer, is_quantum_within},
+};
+
+// Quantum notes further than this from a cell are placed on purpose, closer ones could be snapped
+const SNAP_DISTANCE: f32 = 0.05;
+
+// Covers bigger than this are larger than any game shows them
+const LARGE_COVER: u64 = 1024 * 1024;
+
+// Where the bytes of an exported map went, so mappers can see what to optimize
+#[derive(Debug, Clone, Default)]
+pub struct SizeReport {
+    pub total: u64,
+    // Every part of the file with its size, largest first
+    pub sections: Vec<(String, u64)>,
+    pub grid_notes: usize,
+    pub quantum_notes: usize,
+    // Quantum notes so close to a cell that they could be on the grid instead
+    pub snappable_notes: usize,
+    // Bytes a single note takes in the object data
+    pub grid_note_bytes: u64,
+    pub quantum_note_bytes: u64,
+}
+
+// Measures what is written without keeping any of it, maps with audio can be large
+#[derive(Default)]
+struct Counter {
+    position: u64,
+    length: u64,
+}
+
+impl Write for Counter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.position += buf.len() as u64;
+        self.length = self.length.max(self.position);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for Counter {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.position = match pos {
+            SeekFrom::Start(offset) => offset,
+            SeekFrom::End(offset) => self.length.saturating_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.saturating_add_signed(offset),
+        };
+        Ok(self.position)
+    }
+}
+
+impl SizeReport {
+    // SSPM is measured by writing it again, PHXM is read from the exported archive since its
+    // entries are compressed
+    pub fn new(
+        map: &Map,
+        format: MapFormat,
+        path: &Path,
+        options: WriteOptions,
+    ) -> io::Result<Self> {
+        let mut report = match format {
+            MapFormat::SSPM => SizeReport::sspm(map, options)?,
+            MapFormat::PHXM => SizeReport::phxm(path)?,
+            MapFormat::Legacy => SizeReport::legacy(map, options)?,
+        };
+
+        // Object data is time, type and then either two cell bytes or two floats
+        (report.grid_note_bytes, report.quantum_note_bytes) = match format {
+            MapFormat::SSPM => (8, 14),
+            MapFormat::PHXM => (7, 13),
+            // Text, for a five digit time and quantum positions with a few decimals
+            MapFormat::Legacy => (10, 18),
+        };
+
+        for note in map.notes.iter() {
+            if !is_quantum_within(note.position, options.grid_epsilon) {
+                report.grid_notes += 1;
+                continue;
+            }
+
+            report.quantum_notes += 1;
+            if note.position.distance(note.position.round()) <= SNAP_DISTANCE {
+                report.snappable_notes += 1;
+            }
+        }
+
+        report.sections.retain(|(_, size)| *size > 0);
+        report
+            .sections
+            .sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+
+        Ok(report)
+    }
+
+    // Writes the map without keeping it, for checking a size before the real file is written
+    pub fn measure(map: &Map, format: MapFormat, options: WriteOptions) -> io::Result<u64> {
+        let mut counter = Counter::default();
+        format.serialize(map, &mut counter, options)?;
+        Ok(counter.length)
+    }
+
+    fn sspm(map: &Map, options: WriteOptions) -> io::Result<Self> {
+        let mut counter = Counter::default();
+        let sections = SSPMSerializer::serialize_padded(map, &mut counter, 0, options)?;
+
+        let parts = [
+            ("Audio", sections.audio.1),
+            ("Cover", sections.cover.1),
+            ("Custom data", sections.custom_data.1),
+            ("Object definitions", sections.object_definitions.1),
+            ("Objects", sections.object_data.1),
+        ];
+        let named: u64 = parts.iter().map(|(_, size)| size).sum();
+
+        let mut sections: Vec<(String, u64)> = parts
+            .into_iter()
+            .map(|(name, size)| (name.to_string(), size))
+            .collect();
+        sections.push(("Header and metadata".to_string(), counter.length - named));
+
+        Ok(SizeReport {
+            total: counter.length,
+            sections,
+            ..Default::default()
+        })
+    }
+
+    fn phxm(path: &Path) -> io::Result<Self> {
+        let file = BufReader::new(File::open(path)?);
+        let total = file.get_ref().metadata()?.len();
+        let mut archive = zip::ZipArchive::new(file)?;
+
+        let mut sections = Vec::new();
+        for index in 0..archive.len() {
+            let entry = archive.by_index(index)?;
+            sections.push((entry.name().to_string(), entry.compressed_size()));
+        }
+
+        // Whatever the entries don't account for is the archive's own bookkeeping
+        let entries: u64 = sections.iter().map(|(_, size)| size).sum();
+        sections.push(("Archive headers".to_string(), total.saturating_sub(entries)));
+
+        Ok(SizeReport {
+            total,
+            sections,
+            ..Default::default()
+        })
+    }
+
+    fn legacy(map: &Map, options: WriteOptions) -> io::Result<Self> {
+        let mut counter = Counter::default();
+        LegacySerializer::serialize_with(map, &mut counter, options)?;
+
+        let id = legacy_id(&map.id).len() as u64;
+
+        Ok(SizeReport {
+            total: counter.length,
+            sections: vec![
+                ("Notes".to_string(), counter.length - id),
+                ("Id".to_string(), id),
+            ],
+            ..Default::default()
+        })
+    }
+
+    pub fn share(&self, size: u64) -> f32 {
+        match self.total {
+            0 => 0.0,
+            total => size as f32 / total as f32,
+        }
+    }
+
+    pub fn note_bytes(&self) -> u64 {
+        self.grid_notes as u64 * self.grid_note_bytes
+            + self.quantum_notes as u64 * self.quantum_note_bytes
+    }
+
+    // What would make the biggest difference, most effective first
+    pub fn hints(&self) -> Vec<String> {
+        let mut hints = Vec::new();
+        // Sections are "Audio" in SSPM and files like "audio.mp3" in PHXM
+        let size_of = |prefix: &str| {
+            self.sections
+                .iter()
+                .filter(|(section, _)| section.to_lowercase().starts_with(prefix))
+                .map(|(_, size)| *size)
+                .sum::<u64>()
+        };
+
+        let audio = size_of("audio");
+        if self.share(audio) > 0.8 {
+            hints.push(format!(
+                "Audio is {:.0}% of the file, a lower bitrate or a mono track saves the most",
+                self.share(audio) * 100.0
+            ));
+        }
+
+        let cover = size_of("cover");
+        if cover > LARGE_COVER {
+            hints.push(format!(
+                "The cover is {}, a smaller image looks the same in game",
+                format_size(cover)
+            ));
+        }
+
+        if self.snappable_notes > 0 {
+            let saved =
+                self.snappable_notes as u64 * (self.quantum_note_bytes - self.grid_note_bytes);
+            hints.push(format!(
+                "{} quantum notes are almost on a cell, snapping them saves {}",
+                self.snappable_notes,
+                format_size(saved)
+            ));
+        }
+
+        hints
+    }
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    match unit {
+        0 => format!("{bytes} B"),
+        _ => format!("{size:.1} {}", UNITS[unit]),
+    }
+}
@@ -0,0 +1,273 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Cursor, ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
+
+use crate::{
+    maps::{
+        Map,
+        archive::{read_map_entries, write_map_entries},
+        objects::{Note, NoteId},
+        parser::{MapSerializer, SSPMSerializer},
+    },
+    settings::config_dir,
+};
+
+pub const EXTENSION: &str = "mmsnap";
+
+// The first snapshots had no version, kept the artists in snapshot.json and only the SSPM next to
+// it. Since 2 the map is stored the same way as in projects
+const VERSION: u32 = 2;
+
+// Used for maps that haven't been exported yet and have no folder of their own
+const SNAPSHOT_FOLDER: &str = "snapshots";
+
+// Named copies of a map kept next to it. Each one is a zip with the map, which keeps its note ids
+// so diffs can follow notes that moved, and a small JSON file describing the snapshot.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub path: PathBuf,
+    pub name: String,
+    // Seconds since the unix epoch
+    pub created: u64,
+    pub notes: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SnapshotInfo {
+    #[serde(default = "first_version")]
+    version: u32,
+    name: String,
+    created: u64,
+    notes: usize,
+    // Only in the first version
+    #[serde(default, skip_serializing)]
+    artists: Vec<String>,
+    #[serde(default, skip_serializing)]
+    save_note_ids: bool,
+}
+
+fn first_version() -> u32 {
+    1
+}
+
+// `<map>.snapshots` next to the map file, or one per map id in the config folder
+pub fn snapshot_folder(map: &Map, map_path: Option<&Path>) -> Option<PathBuf> {
+    match map_path {
+        Some(path) => Some(path.with_extension(SNAPSHOT_FOLDER)),
+        None => {
+            let id = if map.id.is_empty() {
+                "untitled"
+            } else {
+                &map.id
+            };
+            config_dir().map(|dir| dir.join(SNAPSHOT_FOLDER).join(id))
+        }
+    }
+}
+
+pub fn create_snapshot(map: &Map, folder: &Path, name: &str) -> io::Result<Snapshot> {
+    let created = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let info = SnapshotInfo {
+        version: VERSION,
+        name: name.to_string(),
+        created,
+        notes: map.notes.len(),
+        artists: Vec::new(),
+        save_note_ids: false,
+    };
+
+    fs::create_dir_all(folder)?;
+
+    // Two snapshots taken within a second get told apart by a counter
+    let path = (0..)
+        .map(|index| match index {
+            0 => folder.join(format!("{created}.{EXTENSION}")),
+            _ => folder.join(format!("{created}-{index}.{EXTENSION}")),
+        })
+        .find(|path| !path.exists())
+        .unwrap_or_default();
+
+    let mut zip = zip::ZipWriter::new(BufWriter::new(File::create(&path)?));
+    let options = SimpleFileOptions::default();
+
+    zip.start_file("snapshot.json", options)?;
+    zip.write_all(serde_json::to_string(&info)?.as_bytes())?;
+    write_map_entries(&mut zip, map)?;
+    zip.finish()?.flush()?;
+
+    Ok(Snapshot {
+        path,
+        name: info.name,
+        created,
+        notes: info.notes,
+    })
+}
+
+fn read_info(zip: &mut zip::ZipArchive<BufReader<File>>) -> io::Result<SnapshotInfo> {
+    let mut contents = String::new();
+    zip.by_name("snapshot.json")?
+        .read_to_string(&mut contents)?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
+// Newest first, files that can't be read are left out
+pub fn list_snapshots(folder: &Path) -> Vec<Snapshot> {
+    let Ok(entries) = fs::read_dir(folder) else {
+        return Vec::new();
+    };
+
+    let mut snapshots: Vec<Snapshot> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == EXTENSION))
+        .filter_map(|path| {
+            let mut zip = zip::ZipArchive::new(BufReader::new(File::open(&path).ok()?)).ok()?;
+            let info = read_info(&mut zip).ok()?;
+            Some(Snapshot {
+                path,
+                name: info.name,
+                created: info.created,
+                notes: info.notes,
+            })
+        })
+        .collect();
+
+    snapshots.sort_by_key(|snapshot| std::cmp::Reverse(snapshot.created));
+    snapshots
+}
+
+pub fn read_snapshot(snapshot: &Snapshot) -> io::Result<Map> {
+    let mut zip = zip::ZipArchive::new(BufReader::new(File::open(&snapshot.path)?))?;
+    let info = read_info(&mut zip)?;
+
+    match info.version {
+        1 => {
+            let mut sspm = Vec::new();
+            zip.by_name("map.sspm")?.read_to_end(&mut sspm)?;
+
+            let mut map = SSPMSerializer::deserialize(Cursor::new(sspm))?;
+            map.artists = info.artists;
+            map.save_note_ids = info.save_note_ids;
+            Ok(map)
+        }
+        version if version > VERSION => Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("Snapshot version {version} is newer than this app supports"),
+        )),
+        _ => read_map_entries(&mut zip),
+    }
+}
+
+pub fn delete_snapshot(snapshot: &Snapshot) -> io::Result<()> {
+    fs::remove_file(&snapshot.path)
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MapDiff {
+    pub added: usize,
+    pub removed: usize,
+    // Same note at another time or position
+    pub moved: usize,
+    // Everything else that differs, one line each
+    pub changes: Vec<String>,
+}
+
+impl MapDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added == 0 && self.removed == 0 && self.moved == 0 && self.changes.is_empty()
+    }
+}
+
+// What changed going from `from` to `to`. Notes are matched up by id, so this is only exact
+// between maps that share their note ids, like a snapshot and the map it was taken of
+pub fn diff_maps(from: &Map, to: &Map) -> MapDiff {
+    let before: HashMap<NoteId, &Note> = from.notes.iter().map(|n| (n.id, n)).collect();
+
+    let mut diff = MapDiff::default();
+    let mut matched = 0;
+
+    for note in to.notes.iter() {
+        match before.get(&note.id) {
+            Some(old) => {
+                matched += 1;
+                if old.millisecond != note.millisecond || old.position != note.position {
+                    diff.moved += 1;
+                }
+            }
+            None => diff.added += 1,
+        }
+    }
+    diff.removed = from.notes.len() - matched.min(from.notes.len());
+
+    let mut field = |name: &str, old: String, new: String| {
+        if old != new {
+            diff.changes.push(format!("{name}: {old} -> {new}"));
+        }
+    };
+
+    field("Title", from.title.clone(), to.title.clone());
+    field("Artists", from.artists.join(", "), to.artists.join(", "));
+    field("Mappers", from.mappers.join(", "), to.mappers.join(", "));
+    field(
+        "Difficulty",
+        from.difficulty_name.clone(),
+        to.difficulty_name.clone(),
+    );
+
+    let mut list = |name: &str, changed: bool, old: usize, new: usize| {
+        if changed {
+            diff.changes
+                .push(format!("{name} changed ({old} -> {new})"));
+        }
+    };
+
+    list(
+        "Timing points",
+        from.timing_points != to.timing_points,
+        from.timing_points.len(),
+        to.timing_points.len(),
+    );
+    list(
+        "Speed changes",
+        from.speed_changes != to.speed_changes,
+        from.speed_changes.len(),
+        to.speed_changes.len(),
+    );
+    list(
+        "Bookmarks",
+        from.bookmarks != to.bookmarks,
+        from.bookmarks.len(),
+        to.bookmarks.len(),
+    );
+    list(
+        "Review comments",
+        from.annotations != to.annotations,
+        from.annotations.len(),
+        to.annotations.len(),
+    );
+    list(
+        "Lyrics",
+        from.lyrics != to.lyrics,
+        from.lyrics.len(),
+        to.lyrics.len(),
+    );
+
+    let audio = |map: &Map| map.audio.as_ref().map(|audio| audio.bytes.len());
+    if audio(from) != audio(to) {
+        diff.changes.push("Audio was replaced".to_string());
+    }
+    if from.cover != to.cover {
+        diff.changes.push("Cover was replaced".to_string());
+    }
+
+    diff
+}
@@ -0,0 +1,209 @@
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::maps::{io::BinaryReader, objects::TimingPoint};
+
+// MIDI files without tempo or meter events play at 120 BPM in 4/4
+const DEFAULT_MICROS_PER_BEAT: u32 = 500_000;
+const DEFAULT_BEATS_PER_MEASURE: u8 = 4;
+
+#[derive(Debug, Clone, Copy)]
+enum TempoEvent {
+    Tempo(u32),
+    Meter(u8),
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+// Lengths come from the file, one longer than what is left of the track is broken or made up
+fn check_length<T: Read + Seek>(
+    reader: &mut BinaryReader<T>,
+    end: u64,
+    length: u64,
+) -> io::Result<()> {
+    match length > end.saturating_sub(reader.stream_position()?) {
+        true => Err(invalid("MIDI event runs past the end of its track")),
+        false => Ok(()),
+    }
+}
+
+// Tempo and time signature events of one track, by tick. Notes and everything else are skipped
+fn read_track<T: Read + Seek>(
+    reader: &mut BinaryReader<T>,
+    end: u64,
+    events: &mut Vec<(u64, TempoEvent)>,
+) -> io::Result<()> {
+    let mut tick = 0u64;
+    let mut running_status = None;
+
+    while reader.stream_position()? < end {
+        tick += reader.read_vlq()? as u64;
+
+        // First byte after the status, the type of meta events and the first data byte otherwise
+        let mut first = reader.read_u8()?;
+        let status = match first & 0x80 {
+            0 => running_status.ok_or_else(|| invalid("MIDI event has no status byte"))?,
+            _ => {
+                let status = first;
+                if status < 0xF0 {
+                    running_status = Some(status);
+                }
+                first = reader.read_u8()?;
+                status
+            }
+        };
+
+        match status {
+            0xFF => {
+                let kind = first;
+                let length = reader.read_vlq()? as u64;
+                check_length(reader, end, length)?;
+                let mut data = vec![0u8; length as usize];
+                reader.read_exact(&mut data)?;
+
+                match (kind, data.as_slice()) {
+                    (0x51, [a, b, c]) => {
+                        let micros = u32::from_be_bytes([0, *a, *b, *c]);
+                        if micros > 0 {
+                            events.push((tick, TempoEvent::Tempo(micros)));
+                        }
+                    }
+                    // Timing points count quarter notes, the beat MIDI tempos are given in, so a
+                    // bar of 6/8 is 3 of them
+                    (0x58, [numerator, denominator, ..]) => {
+                        let quarters = *numerator as f32 * 4.0 / 2f32.powi(*denominator as i32);
+                        let beats = quarters.round().clamp(1.0, u8::MAX as f32) as u8;
+                        events.push((tick, TempoEvent::Meter(beats)));
+                    }
+                    (0x2F, _) => break,
+                    _ => {}
+                }
+                running_status = None;
+            }
+            0xF0 | 0xF7 => {
+                // The length was read as the first byte already when it fits in one
+                let mut length = (first & 0x7F) as u64;
+                if first & 0x80 != 0 {
+                    reader.seek(SeekFrom::Current(-1))?;
+                    length = reader.read_vlq()? as u64;
+                }
+                check_length(reader, end, length)?;
+                reader.seek(SeekFrom::Current(length as i64))?;
+                running_status = None;
+            }
+            // Program change and channel pressure have one data byte, already read
+            0xC0..=0xDF => {}
+            0x80..=0xEF => {
+                reader.read_u8()?;
+            }
+            _ => return Err(invalid(format!("Unknown MIDI status {status:#04X}"))),
+        }
+    }
+
+    Ok(())
+}
+
+// Timing points for the tempo and time signature changes in a standard MIDI file, like the ones
+// DAWs export. The first one is at the start of the file
+pub fn read_midi_tempo_map<T: Read + Seek>(reader: T) -> io::Result<Vec<TimingPoint>> {
+    let mut reader = BinaryReader::new(reader);
+
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if magic != *b"MThd" {
+        return Err(invalid("Not a MIDI file"));
+    }
+
+    let header_length = reader.read_u32_be()?;
+    if header_length < 6 {
+        return Err(invalid("MIDI header is too short"));
+    }
+    let _format = reader.read_u16_be()?;
+    let track_count = reader.read_u16_be()?;
+    let division = reader.read_u16_be()?;
+    reader.seek(SeekFrom::Current(header_length as i64 - 6))?;
+
+    if division & 0x8000 != 0 {
+        return Err(invalid(
+            "MIDI file is timed in SMPTE frames and has no tempo map",
+        ));
+    }
+    if division == 0 {
+        return Err(invalid("MIDI file has no ticks per beat"));
+    }
+
+    let mut events = Vec::new();
+    for _ in 0..track_count {
+        let mut id = [0u8; 4];
+        reader.read_exact(&mut id)?;
+        let length = reader.read_u32_be()? as u64;
+        let end = reader.stream_position()? + length;
+
+        // Chunks other than tracks may be added by newer versions of the format
+        if id == *b"MTrk" {
+            read_track(&mut reader, end, &mut events)?;
+        }
+        reader.seek(SeekFrom::Start(end))?;
+    }
+
+    // Tracks are merged in time order, tempos usually live in the first one but don't have to
+    events.sort_by_key(|(tick, _)| *tick);
+
+    let mut points: Vec<TimingPoint> = Vec::new();
+    let (mut micros, mut beats) = (DEFAULT_MICROS_PER_BEAT, DEFAULT_BEATS_PER_MEASURE);
+    let (mut tick, mut last_tick, mut millisecond) = (0u64, 0u64, 0f64);
+    let mut index = 0;
+
+    loop {
+        millisecond += (tick - last_tick) as f64 * micros as f64 / division as f64 / 1000.0;
+        last_tick = tick;
+
+        // Several events can share a tick, the point only goes in once they are all applied
+        while let Some((_, event)) = events.get(index).filter(|(at, _)| *at == tick) {
+            match event {
+                TempoEvent::Tempo(value) => micros = *value,
+                TempoEvent::Meter(value) => beats = *value,
+            }
+            index += 1;
+        }
+
+        let point = TimingPoint {
+            millisecond: millisecond.round() as u32,
+            bpm: (60_000_000.0 / micros as f64) as f32,
+            beats_per_measure: beats,
+        };
+        match points.last_mut() {
+            Some(last) if last.millisecond == point.millisecond => *last = point,
+            Some(last)
+                if last.bpm == point.bpm && last.beats_per_measure == point.beats_per_measure => {}
+            _ => points.push(point),
+        }
+
+        match events.get(index) {
+            Some((next, _)) => tick = *next,
+            None => break,
+        }
+    }
+
+    Ok(points)
+}
+
+// Moves timing points by `offset_ms` for audio that doesn't start where the DAW project does.
+// The point in effect at the start of the map is kept there when it would move before it
+pub fn offset_timing_points(points: &[TimingPoint], offset_ms: i64) -> Vec<TimingPoint> {
+    let mut shifted: Vec<TimingPoint> = Vec::with_capacity(points.len());
+
+    for point in points {
+        let millisecond = point.millisecond as i64 + offset_ms;
+        if millisecond <= 0 {
+            shifted.clear();
+        }
+        shifted.push(TimingPoint {
+            millisecond: millisecond.max(0) as u32,
+            ..*point
+        });
+    }
+
+    shifted
+}
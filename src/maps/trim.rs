@@ -0,0 +1,199 @@
+use std::io;
+
+use bevy::audio::AudioSource;
+
+use crate::{
+    jukebox::pcm::Pcm,
+    maps::{
+        Map, format_time,
+        objects::{SpeedChange, TimingPoint},
+    },
+    modchart::{Keyframe, ModEvent},
+};
+
+// Only the part of a map between `start` and `end`, for practice diffs and preview clips.
+// Everything is moved so the range starts at zero, and the audio is cut to match.
+pub fn trim(map: &Map, start: u32, end: u32) -> io::Result<Map> {
+    if end <= start {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "The range has to end after it starts",
+        ));
+    }
+
+    let range = start..=end;
+    // Everything rebased was checked to be in the range, saturating only guards against that changing
+    let rebase = |millisecond: u32| millisecond.saturating_sub(start);
+    let mut trimmed = map.clone();
+
+    trimmed.id = format!("{}_{start}-{end}", map.id);
+    trimmed.difficulty_name = match map.difficulty_name.trim() {
+        "" => format!("{} - {}", format_time(start), format_time(end)),
+        name => format!("{name} ({} - {})", format_time(start), format_time(end)),
+    };
+    trimmed.length = end.saturating_sub(start);
+
+    trimmed
+        .notes
+        .retain(|note| range.contains(&note.millisecond));
+    trimmed
+        .notes
+        .iter_mut()
+        .for_each(|n| n.millisecond = rebase(n.millisecond));
+
+    trimmed
+        .objects
+        .retain(|object| range.contains(&object.millisecond));
+    trimmed
+        .objects
+        .iter_mut()
+        .for_each(|o| o.millisecond = rebase(o.millisecond));
+
+    trimmed
+        .bookmarks
+        .retain(|bookmark| range.contains(&bookmark.millisecond));
+    trimmed
+        .bookmarks
+        .iter_mut()
+        .for_each(|b| b.millisecond = rebase(b.millisecond));
+
+    trimmed
+        .annotations
+        .retain(|annotation| range.contains(&annotation.millisecond));
+    trimmed
+        .annotations
+        .iter_mut()
+        .for_each(|a| a.millisecond = rebase(a.millisecond));
+
+    // Lyrics running into the cut are shortened rather than dropped
+    trimmed
+        .lyrics
+        .retain(|lyric| range.contains(&lyric.millisecond));
+    trimmed.lyrics.iter_mut().for_each(|l| {
+        l.duration_ms = l.duration_ms.min(end.saturating_sub(l.millisecond));
+        l.millisecond = rebase(l.millisecond);
+    });
+
+    trimmed.timing_points = trim_timing_points(&map.timing_points, start, end);
+    trimmed.speed_changes = trim_speed_changes(&map.speed_changes, start, end);
+    trimmed.mod_events = map
+        .mod_events
+        .iter()
+        .filter_map(|event| trim_mod_event(event, start, end))
+        .collect();
+
+    trimmed.audio = match &map.audio {
+        Some(audio) => {
+            let pcm = Pcm::decode(&audio.bytes)?.slice(start as f64, end as f64);
+            Some(AudioSource {
+                bytes: pcm.to_wav().into(),
+            })
+        }
+        None => None,
+    };
+
+    Ok(trimmed)
+}
+
+// The timing point active at the start is kept, moved to its first beat in the range so beat
+// lines and snapping stay where they were
+fn trim_timing_points(timing_points: &[TimingPoint], start: u32, end: u32) -> Vec<TimingPoint> {
+    let inside: Vec<TimingPoint> = timing_points
+        .iter()
+        .filter(|t| (start..=end).contains(&t.millisecond))
+        .map(|t| TimingPoint {
+            millisecond: t.millisecond.saturating_sub(start),
+            ..*t
+        })
+        .collect();
+
+    let active = timing_points
+        .iter()
+        .rev()
+        .find(|t| t.millisecond < start)
+        .map(|timing| {
+            let beat = timing.beat_length();
+            let beats = (start.saturating_sub(timing.millisecond) as f64 / beat).ceil();
+            let first_beat = timing.millisecond as f64 + beats * beat;
+
+            TimingPoint {
+                millisecond: (first_beat.round() as u32).saturating_sub(start),
+                ..*timing
+            }
+        })
+        .filter(|active| {
+            let next = inside
+                .first()
+                .map_or(end.saturating_sub(start), |t| t.millisecond);
+            active.millisecond < next
+        });
+
+    active.into_iter().chain(inside).collect()
+}
+
+// Whatever speed is active at the start holds from zero
+fn trim_speed_changes(speed_changes: &[SpeedChange], start: u32, end: u32) -> Vec<SpeedChange> {
+    let active = speed_changes
+        .iter()
+        .rev()
+        .find(|c| c.millisecond < start)
+        .filter(|_| speed_changes.iter().all(|c| c.millisecond != start))
+        .map(|change| SpeedChange {
+            millisecond: 0,
+            ..*change
+        });
+
+    let inside = speed_changes
+        .iter()
+        .filter(|c| (start..=end).contains(&c.millisecond))
+        .map(|c| SpeedChange {
+            millisecond: c.millisecond.saturating_sub(start),
+            ..*c
+        });
+
+    active.into_iter().chain(inside).collect()
+}
+
+// Keyframes are added at both ends with the value the event has there, so the trimmed event
+// moves the same way inside the range. Eased segments cut in half are only approximated.
+fn trim_mod_event(event: &ModEvent, start: u32, end: u32) -> Option<ModEvent> {
+    if event.start()? > end {
+        return None;
+    }
+
+    let keyframes = event.keyframes();
+    let mut trimmed = Vec::new();
+
+    if let Some(value) = event.sample(start as f64)
+        && keyframes.iter().all(|k| k.millisecond != start)
+    {
+        trimmed.push(Keyframe::new(0, value));
+    }
+
+    trimmed.extend(
+        keyframes
+            .iter()
+            .filter(|k| (start..=end).contains(&k.millisecond))
+            .map(|k| Keyframe {
+                millisecond: k.millisecond.saturating_sub(start),
+                ..*k
+            }),
+    );
+
+    if let Some(next) = keyframes.iter().find(|k| k.millisecond > end)
+        && let Some(value) = event.sample(end as f64)
+    {
+        trimmed.push(Keyframe {
+            millisecond: end.saturating_sub(start),
+            value,
+            easing: next.easing,
+        });
+    }
+
+    Some(
+        ModEvent::new(event.channel, trimmed)
+            .with_space(event.space)
+            .with_blend(event.blend)
+            .with_jitter(event.jitter),
+    )
+}
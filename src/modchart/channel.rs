@@ -0,0 +1,222 @@
+use bevy::math::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::modchart::{CoordinateSpace, Keyframe, random::ModRandom};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ModChannel {
+    CameraOffset,
+    CameraRotation,
+    PlayfieldOffset,
+    PlayfieldRotation,
+    PlayfieldScale,
+    // Override the theme's approach profile while active, see theme::approach
+    NoteApproachScale,
+    NoteApproachFade,
+    NoteApproachOffset,
+    // Multiplies the size of every note on each axis
+    NoteScale,
+    // x picks the note mesh by its index in NoteShape::ALL, see theme::shape
+    NoteShape,
+    // x is how much bigger notes get as each beat passes, 0.2 for a fifth. Beats are counted
+    // down to every note's own time, so off-beat notes pulse in their own rhythm
+    NotePulse,
+}
+
+impl ModChannel {
+    pub const ALL: [ModChannel; 11] = [
+        ModChannel::CameraOffset,
+        ModChannel::CameraRotation,
+        ModChannel::PlayfieldOffset,
+        ModChannel::PlayfieldRotation,
+        ModChannel::PlayfieldScale,
+        ModChannel::NoteApproachScale,
+        ModChannel::NoteApproachFade,
+        ModChannel::NoteApproachOffset,
+        ModChannel::NoteScale,
+        ModChannel::NoteShape,
+        ModChannel::NotePulse,
+    ];
+
+    // Stable number of the channel in saved modcharts, new channels only go at the end
+    pub fn index(&self) -> u8 {
+        ModChannel::ALL.iter().position(|c| c == self).unwrap_or(0) as u8
+    }
+
+    pub fn from_index(index: u8) -> Option<Self> {
+        ModChannel::ALL.get(index as usize).copied()
+    }
+
+    // Value of the channel when no event is affecting it
+    pub fn default_value(&self) -> Vec3 {
+        match self {
+            ModChannel::PlayfieldScale | ModChannel::NoteApproachScale | ModChannel::NoteScale => {
+                Vec3::ONE
+            }
+            _ => Vec3::ZERO,
+        }
+    }
+
+    // Space the rest of the app reads the channel in, None for rotations and scales that the
+    // event's space doesn't apply to
+    pub fn space(&self) -> Option<CoordinateSpace> {
+        match self {
+            ModChannel::CameraOffset | ModChannel::PlayfieldOffset => Some(CoordinateSpace::World),
+            ModChannel::NoteApproachOffset => Some(CoordinateSpace::Grid),
+            _ => None,
+        }
+    }
+}
+
+// How an event combines with the events on the same channel that came before it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum ModBlend {
+    // Replaces what came before, like events always did
+    #[default]
+    Override,
+    Add,
+    Multiply,
+    // Per axis
+    Max,
+}
+
+impl ModBlend {
+    pub const ALL: [ModBlend; 4] = [
+        ModBlend::Override,
+        ModBlend::Add,
+        ModBlend::Multiply,
+        ModBlend::Max,
+    ];
+
+    // Stable number of the blend in saved modcharts, new blends only go at the end
+    pub fn index(&self) -> u8 {
+        ModBlend::ALL.iter().position(|b| b == self).unwrap_or(0) as u8
+    }
+
+    pub fn from_index(index: u8) -> Option<Self> {
+        ModBlend::ALL.get(index as usize).copied()
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ModBlend::Override => "Override",
+            ModBlend::Add => "Add",
+            ModBlend::Multiply => "Multiply",
+            ModBlend::Max => "Max",
+        }
+    }
+
+    // `below` is the channel's default value when no earlier event is active
+    pub fn apply(&self, below: Vec3, value: Vec3) -> Vec3 {
+        match self {
+            ModBlend::Override => value,
+            ModBlend::Add => below + value,
+            ModBlend::Multiply => below * value,
+            ModBlend::Max => below.max(value),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModEvent {
+    pub channel: ModChannel,
+    // What the keyframe values are written in, converted into the channel's own space
+    pub space: CoordinateSpace,
+    pub blend: ModBlend,
+    // For glitches: every keyframe is moved by up to this much on each axis, rolled from the
+    // mod seed so a run always glitches the same way
+    pub jitter: Vec3,
+    // Always kept sorted by millisecond
+    keyframes: Vec<Keyframe>,
+}
+
+impl ModEvent {
+    pub fn new(channel: ModChannel, mut keyframes: Vec<Keyframe>) -> Self {
+        keyframes.sort_by_key(|k| k.millisecond);
+        Self {
+            channel,
+            space: CoordinateSpace::World,
+            blend: ModBlend::Override,
+            jitter: Vec3::ZERO,
+            keyframes,
+        }
+    }
+
+    pub fn with_space(mut self, space: CoordinateSpace) -> Self {
+        self.space = space;
+        self
+    }
+
+    pub fn with_blend(mut self, blend: ModBlend) -> Self {
+        self.blend = blend;
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: Vec3) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    pub fn insert(&mut self, keyframe: Keyframe) {
+        let index = self
+            .keyframes
+            .partition_point(|k| k.millisecond <= keyframe.millisecond);
+        self.keyframes.insert(index, keyframe);
+    }
+
+    pub fn start(&self) -> Option<u32> {
+        self.keyframes.first().map(|k| k.millisecond)
+    }
+
+    pub fn end(&self) -> Option<u32> {
+        self.keyframes.last().map(|k| k.millisecond)
+    }
+
+    // Samples the event at an arbitrary time without depending on any previous sample,
+    // returns None before the first keyframe and holds the last value after the final one.
+    // Jitter is left out, see sample_seeded
+    pub fn sample(&self, millisecond: f64) -> Option<Vec3> {
+        self.sample_with(millisecond, |_| Vec3::ZERO)
+    }
+
+    // `stream` tells events apart so they don't all glitch the same way
+    pub fn sample_seeded(&self, millisecond: f64, random: &ModRandom, stream: u64) -> Option<Vec3> {
+        if self.jitter == Vec3::ZERO {
+            return self.sample(millisecond);
+        }
+
+        self.sample_with(millisecond, |index| {
+            random.signed_vec3(stream, index as u64) * self.jitter
+        })
+    }
+
+    fn sample_with(&self, millisecond: f64, offset: impl Fn(usize) -> Vec3) -> Option<Vec3> {
+        let first = self.keyframes.first()?;
+
+        if millisecond < first.millisecond as f64 {
+            return None;
+        }
+
+        let next = self
+            .keyframes
+            .partition_point(|k| (k.millisecond as f64) <= millisecond);
+
+        if next >= self.keyframes.len() {
+            let last = self.keyframes.len() - 1;
+            return Some(self.keyframes[last].value + offset(last));
+        }
+
+        let from = &self.keyframes[next - 1];
+        let to = &self.keyframes[next];
+        let span = (to.millisecond - from.millisecond) as f64;
+        let t = ((millisecond - from.millisecond as f64) / span) as f32;
+
+        let from_value = from.value + offset(next - 1);
+        let to_value = to.value + offset(next);
+        Some(from_value.lerp(to_value, to.easing.apply(t)))
+    }
+}
@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+
+use bevy::{
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, Task, block_on, futures_lite::future},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    gameplay::Session,
+    maps::{CurrentMap, Map},
+    modchart::{ModChannel, ModState, Modchart, ScreenFrame, random::ModRandom},
+    settings::Settings,
+};
+
+// Turning is what makes charts hard to watch for some players, offsets and scales are left alone
+const GUARDED: [ModChannel; 2] = [ModChannel::PlayfieldRotation, ModChannel::CameraRotation];
+
+// Capped rotations are worked out ahead at this resolution, seeking then only looks them up
+const STEP_MS: f64 = 10.0;
+
+// Furthest a tilt leans on each axis, in radians, about 6 degrees
+const TILT: f32 = 0.1;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RotationComfort {
+    // Rotations play as charted
+    #[default]
+    Full,
+    // Rotations turn no faster than `max_turn_speed`, catching up with the chart when it slows
+    Capped,
+    // Rotations become a slight tilt that leans the way the chart turns
+    Tilt,
+}
+
+impl RotationComfort {
+    pub const ALL: [RotationComfort; 3] = [
+        RotationComfort::Full,
+        RotationComfort::Capped,
+        RotationComfort::Tilt,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RotationComfort::Full => "As charted",
+            RotationComfort::Capped => "Capped speed",
+            RotationComfort::Tilt => "Tilt only",
+        }
+    }
+
+    // How much effects that move the view, like the hit camera punch, are played out
+    pub fn motion_scale(&self) -> f32 {
+        match self {
+            RotationComfort::Full => 1.0,
+            RotationComfort::Capped => 0.5,
+            RotationComfort::Tilt => 0.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ComfortSettings {
+    pub rotation: RotationComfort,
+    // Degrees per second on each axis with capped rotations
+    pub max_turn_speed: f32,
+}
+
+impl Default for ComfortSettings {
+    fn default() -> Self {
+        Self {
+            rotation: RotationComfort::Full,
+            max_turn_speed: 90.0,
+        }
+    }
+}
+
+// Rotation the spin mod adds by `millisecond`, at `spin` degrees per second
+pub fn spin_at(spin: f32, millisecond: f64) -> Vec3 {
+    Vec3::Z * (spin.to_radians() as f64 * millisecond / 1000.0) as f32
+}
+
+// Rotation of a guarded channel before the guard, the spin mod turns the playfield on top of
+// whatever the chart does
+fn unguarded(
+    modchart: &Modchart,
+    channel: ModChannel,
+    millisecond: f64,
+    frame: &ScreenFrame,
+    random: &ModRandom,
+    spin: f32,
+) -> Option<Vec3> {
+    let charted = modchart.evaluate_channel(channel, millisecond, frame, random);
+    match channel == ModChannel::PlayfieldRotation && spin != 0.0 {
+        true => Some(charted.unwrap_or(Vec3::ZERO) + spin_at(spin, millisecond)),
+        false => charted,
+    }
+}
+
+// Rotations limited to a turn speed. How far a capped rotation has come depends on everything
+// before it, so it is followed from the start of the map once, up front, and evaluating stays
+// independent of the previous frame
+#[derive(Resource, Default)]
+pub struct RotationGuard {
+    tracks: HashMap<ModChannel, Vec<Vec3>>,
+    // Spin, turn speed and length the tracks were made for
+    made_for: Option<(f32, f32, u32)>,
+    // Tracks for a changed chart, worked out in the background. The old ones are used until then
+    pending: Option<Task<RotationGuard>>,
+}
+
+impl RotationGuard {
+    pub fn capped(
+        modchart: &Modchart,
+        frame: &ScreenFrame,
+        random: &ModRandom,
+        spin: f32,
+        max_turn_speed: f32,
+        length_ms: u32,
+    ) -> Self {
+        let max_step = max_turn_speed.max(0.0).to_radians() * (STEP_MS / 1000.0) as f32;
+        let steps = (length_ms as f64 / STEP_MS) as usize + 1;
+
+        let tracks = GUARDED
+            .into_iter()
+            .map(|channel| {
+                let mut track: Vec<Vec3> = Vec::with_capacity(steps);
+                for step in 0..steps {
+                    let millisecond = step as f64 * STEP_MS;
+                    let target = unguarded(modchart, channel, millisecond, frame, random, spin)
+                        .unwrap_or_else(|| channel.default_value());
+
+                    track.push(match track.last() {
+                        Some(last) => {
+                            *last
+                                + (target - *last)
+                                    .clamp(Vec3::splat(-max_step), Vec3::splat(max_step))
+                        }
+                        None => target,
+                    });
+                }
+                (channel, track)
+            })
+            .collect();
+
+        Self {
+            tracks,
+            made_for: Some((spin, max_turn_speed, length_ms)),
+            pending: None,
+        }
+    }
+
+    // None past the end of the map, the chart plays unguarded there
+    pub fn sample(&self, channel: ModChannel, millisecond: f64) -> Option<Vec3> {
+        let track = self.tracks.get(&channel)?;
+        let position = (millisecond / STEP_MS).max(0.0);
+        let index = position as usize;
+
+        let from = track.get(index)?;
+        let to = track.get(index + 1).unwrap_or(from);
+        Some(from.lerp(*to, position.fract() as f32))
+    }
+}
+
+// Spins and guards the rotations in an evaluated state, `guard` is only read for capped
+// rotations
+pub fn guard_rotations(
+    state: &mut ModState,
+    millisecond: f64,
+    spin: f32,
+    settings: &ComfortSettings,
+    guard: &RotationGuard,
+) {
+    if spin != 0.0 {
+        let rotation = state.get(ModChannel::PlayfieldRotation) + spin_at(spin, millisecond);
+        state.values.insert(ModChannel::PlayfieldRotation, rotation);
+    }
+
+    for channel in GUARDED {
+        let Some(rotation) = state.active(channel) else {
+            continue;
+        };
+
+        let guarded = match settings.rotation {
+            RotationComfort::Full => rotation,
+            RotationComfort::Capped => guard.sample(channel, millisecond).unwrap_or(rotation),
+            RotationComfort::Tilt => {
+                Vec3::new(rotation.x.sin(), rotation.y.sin(), rotation.z.sin()) * TILT
+            }
+        };
+        state.values.insert(channel, guarded);
+    }
+}
+
+// Spin applies to sessions only, editing shows the chart the way it is written
+pub fn session_spin(session: Option<&Session>) -> f32 {
+    session.map_or(0.0, |session| session.score.mods.spin())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_rotation_guard(
+    modchart: Res<Modchart>,
+    settings: Res<Settings>,
+    frame: Res<ScreenFrame>,
+    random: Res<ModRandom>,
+    session: Option<Res<Session>>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+    mut guard: ResMut<RotationGuard>,
+) {
+    if settings.comfort.rotation != RotationComfort::Capped {
+        if guard.made_for.is_some() {
+            *guard = RotationGuard::default();
+        }
+        return;
+    }
+
+    let spin = session_spin(session.as_deref());
+    let max_turn_speed = settings.comfort.max_turn_speed;
+    let map_length = current_map
+        .and_then(|current| maps.get(&current.0))
+        .map_or(0, |map| map.length);
+    let events_end = modchart.events().iter().filter_map(|e| e.end()).max();
+    let length_ms = map_length.max(events_end.unwrap_or(0));
+
+    let made_for = Some((spin, max_turn_speed, length_ms));
+    if guard.made_for != made_for
+        || modchart.is_changed()
+        || frame.is_changed()
+        || random.is_changed()
+    {
+        // Following a long map takes a while, a newer change replaces a guard still being made
+        let modchart = Modchart::new(modchart.events().to_vec());
+        let (frame, random) = (*frame, *random);
+        guard.made_for = made_for;
+        guard.pending = Some(AsyncComputeTaskPool::get().spawn(async move {
+            RotationGuard::capped(&modchart, &frame, &random, spin, max_turn_speed, length_ms)
+        }));
+    }
+
+    if let Some(ready) = guard
+        .pending
+        .as_mut()
+        .and_then(|task| block_on(future::poll_once(task)))
+    {
+        guard.tracks = ready.tracks;
+        guard.pending = None;
+    }
+}
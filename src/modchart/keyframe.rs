@@ -0,0 +1,83 @@
+use std::f32::consts::PI;
+
+use bevy::math::Vec3;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Easing {
+    #[default]
+    Linear,
+    Constant,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    SineInOut,
+}
+
+impl Easing {
+    pub const ALL: [Easing; 8] = [
+        Easing::Linear,
+        Easing::Constant,
+        Easing::QuadIn,
+        Easing::QuadOut,
+        Easing::QuadInOut,
+        Easing::CubicIn,
+        Easing::CubicOut,
+        Easing::SineInOut,
+    ];
+
+    // Stable number of the easing in saved modcharts, new easings only go at the end
+    pub fn index(&self) -> u8 {
+        Easing::ALL.iter().position(|e| e == self).unwrap_or(0) as u8
+    }
+
+    pub fn from_index(index: u8) -> Option<Self> {
+        Easing::ALL.get(index as usize).copied()
+    }
+
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+
+        match self {
+            Easing::Linear => t,
+            Easing::Constant => 0.0,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::SineInOut => -((PI * t).cos() - 1.0) / 2.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Keyframe {
+    pub millisecond: u32,
+    pub value: Vec3,
+    // Easing used while interpolating from the previous keyframe into this one
+    pub easing: Easing,
+}
+
+impl Keyframe {
+    pub fn new(millisecond: u32, value: Vec3) -> Self {
+        Self {
+            millisecond,
+            value,
+            easing: Easing::Linear,
+        }
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+}
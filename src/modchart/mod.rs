@@ -0,0 +1,217 @@
+pub mod channel;
+pub mod comfort;
+pub mod keyframe;
+pub mod pack;
+pub mod random;
+pub mod script;
+pub mod sidecar;
+pub mod space;
+pub mod ssp;
+
+use std::{collections::HashMap, sync::OnceLock};
+
+use bevy::prelude::*;
+
+pub use channel::*;
+pub use keyframe::*;
+pub use space::*;
+
+use crate::{
+    debug::profiler::{MOD_EVALUATOR, timed},
+    gameplay::Session,
+    modchart::{
+        comfort::{RotationGuard, guard_rotations, session_spin},
+        random::ModRandom,
+        sidecar::ModchartFile,
+    },
+    player::{
+        playback::{PlaybackClock, advance_clock},
+        playfield::Playfield,
+    },
+    settings::Settings,
+};
+
+#[derive(Resource, Default, Debug)]
+pub struct Modchart {
+    events: Vec<ModEvent>,
+    // Indices of the events in evaluation order, sorted the first time it's needed after the
+    // events change instead of on every evaluation
+    order: OnceLock<Vec<usize>>,
+}
+
+impl Modchart {
+    pub fn new(events: Vec<ModEvent>) -> Self {
+        Self {
+            events,
+            order: OnceLock::new(),
+        }
+    }
+
+    pub fn events(&self) -> &[ModEvent] {
+        &self.events
+    }
+
+    // Changes to the events are sorted again on the next evaluation
+    pub fn events_mut(&mut self) -> &mut Vec<ModEvent> {
+        self.order.take();
+        &mut self.events
+    }
+
+    // Events of a channel are stacked in the order they start, ties keep the order in the list.
+    // Each one blends onto the result of the ones before it. Events come with their index in the
+    // list, which is also the stream their randomness is drawn from
+    pub fn evaluation_order(&self) -> impl Iterator<Item = (usize, &ModEvent)> {
+        let order = self.order.get_or_init(|| {
+            let mut order: Vec<usize> = (0..self.events.len()).collect();
+            order.sort_by_key(|&index| self.events[index].start());
+            order
+        });
+        order.iter().map(|&index| (index, &self.events[index]))
+    }
+
+    // State is rebuilt from the keyframes every time so that seeking renders exactly
+    // what playing forward up to the same point would have rendered
+    pub fn evaluate(&self, millisecond: f64, frame: &ScreenFrame, random: &ModRandom) -> ModState {
+        let mut state = ModState::default();
+
+        for (index, event) in self.evaluation_order() {
+            if let Some(value) = Self::sample_event(index, event, millisecond, frame, random) {
+                let below = state.get(event.channel);
+                state
+                    .values
+                    .insert(event.channel, event.blend.apply(below, value));
+            }
+        }
+
+        state
+    }
+
+    // What `evaluate` gives for one channel, None while no event is affecting it
+    pub fn evaluate_channel(
+        &self,
+        channel: ModChannel,
+        millisecond: f64,
+        frame: &ScreenFrame,
+        random: &ModRandom,
+    ) -> Option<Vec3> {
+        let mut value = None;
+
+        for (index, event) in self.evaluation_order() {
+            if event.channel != channel {
+                continue;
+            }
+            if let Some(sampled) = Self::sample_event(index, event, millisecond, frame, random) {
+                let below = value.unwrap_or_else(|| channel.default_value());
+                value = Some(event.blend.apply(below, sampled));
+            }
+        }
+
+        value
+    }
+
+    fn sample_event(
+        index: usize,
+        event: &ModEvent,
+        millisecond: f64,
+        frame: &ScreenFrame,
+        random: &ModRandom,
+    ) -> Option<Vec3> {
+        let value = event.sample_seeded(millisecond, random, index as u64)?;
+        Some(match event.channel.space() {
+            Some(space) => event.space.convert(value, space, frame),
+            None => value,
+        })
+    }
+}
+
+#[derive(Resource, Default, Debug, Clone)]
+pub struct ModState {
+    values: HashMap<ModChannel, Vec3>,
+}
+
+impl ModState {
+    pub fn get(&self, channel: ModChannel) -> Vec3 {
+        self.values
+            .get(&channel)
+            .copied()
+            .unwrap_or_else(|| channel.default_value())
+    }
+
+    // Only Some while an event is affecting the channel
+    pub fn active(&self, channel: ModChannel) -> Option<Vec3> {
+        self.values.get(&channel).copied()
+    }
+}
+
+pub struct ModchartPlugin;
+
+impl Plugin for ModchartPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Modchart>()
+            .init_resource::<ModState>()
+            .init_resource::<ModchartFile>()
+            .init_resource::<ScreenFrame>()
+            .init_resource::<ModRandom>()
+            .init_resource::<RotationGuard>()
+            .add_systems(
+                Update,
+                (
+                    sidecar::load_modchart_sidecar,
+                    random::sync_map_seed,
+                    comfort::update_rotation_guard,
+                    timed(&MOD_EVALUATOR, update_mod_state).after(advance_clock),
+                    timed(&MOD_EVALUATOR, apply_playfield_mods),
+                )
+                    .chain(),
+            )
+            .add_systems(Update, sidecar::save_modchart_sidecar);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_mod_state(
+    clock: Res<PlaybackClock>,
+    modchart: Res<Modchart>,
+    settings: Res<Settings>,
+    frame: Res<ScreenFrame>,
+    random: Res<ModRandom>,
+    guard: Res<RotationGuard>,
+    session: Option<Res<Session>>,
+    mut state: ResMut<ModState>,
+) {
+    if clock.is_changed()
+        || modchart.is_changed()
+        || settings.is_changed()
+        || frame.is_changed()
+        || random.is_changed()
+        || guard.is_changed()
+    {
+        let millisecond = clock.visual_ms(&settings);
+        let mut evaluated = modchart.evaluate(millisecond, &frame, &random);
+        guard_rotations(
+            &mut evaluated,
+            millisecond,
+            session_spin(session.as_deref()),
+            &settings.comfort,
+            &guard,
+        );
+        *state = evaluated;
+    }
+}
+
+pub fn apply_playfield_mods(
+    state: Res<ModState>,
+    mut playfield: Query<&mut Transform, With<Playfield>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+
+    let rotation = state.get(ModChannel::PlayfieldRotation);
+
+    for mut transform in playfield.iter_mut() {
+        transform.translation = state.get(ModChannel::PlayfieldOffset);
+        transform.rotation = Quat::from_euler(EulerRot::YXZ, rotation.y, rotation.x, rotation.z);
+        transform.scale = state.get(ModChannel::PlayfieldScale);
+    }
+}
@@ -0,0 +1,353 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Cursor, ErrorKind, Read, Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use bevy::asset::ron;
+use serde::{Deserialize, Serialize};
+use zip::{ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+use crate::{
+    maps::export::sanitize_filename,
+    modchart::{
+        Modchart,
+        script::{self, read_script},
+        sidecar::{self, read_modchart, save_modchart, write_modchart},
+    },
+    settings::config_dir,
+    theme::Theme,
+};
+
+pub const EXTENSION: &str = "mmpack";
+
+const VERSION: u32 = 1;
+const MANIFEST: &str = "pack.json";
+const PACK_FOLDER: &str = "mods";
+
+const MODCHARTS: &str = "modcharts";
+const SKINS: &str = "skins";
+
+// Packs come from strangers, no single entry gets to fill the disk when unpacked
+const MAX_ENTRY_BYTES: u64 = 64 * 1024 * 1024;
+
+// A zip of modcharts and the skin files they were made for, shared between modcharters:
+//
+//   pack.json              the manifest
+//   modcharts/<name>.mmmod sidecars
+//   modcharts/<name>.ron   scripts, turned into sidecars when installed
+//   skins/<file>           themes and whatever else they need, kept flat
+//
+// Themes among the skins show up in the theme picker once the pack is installed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PackManifest {
+    pub version: u32,
+    pub name: String,
+    pub author: String,
+    pub description: String,
+}
+
+impl Default for PackManifest {
+    fn default() -> Self {
+        Self {
+            version: VERSION,
+            name: String::new(),
+            author: String::new(),
+            description: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ModPack {
+    pub manifest: PackManifest,
+    // By name, without the extension
+    pub modcharts: BTreeMap<String, Modchart>,
+    // By file name
+    pub skins: BTreeMap<String, Vec<u8>>,
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
+}
+
+fn is_theme(file: &str) -> bool {
+    Path::new(file).extension().is_some_and(|e| e == "ron")
+}
+
+pub fn write_pack<W: Write + Seek>(pack: &ModPack, writer: W) -> io::Result<()> {
+    let mut zip = ZipWriter::new(writer);
+    let options = SimpleFileOptions::default();
+
+    let manifest = PackManifest {
+        version: VERSION,
+        ..pack.manifest.clone()
+    };
+    zip.start_file(MANIFEST, options)?;
+    zip.write_all(serde_json::to_string_pretty(&manifest)?.as_bytes())?;
+
+    for (name, modchart) in pack.modcharts.iter() {
+        let mut mods = Cursor::new(Vec::new());
+        write_modchart(modchart, &mut mods)?;
+        let name = sanitize_filename(name);
+        zip.start_file(
+            format!("{MODCHARTS}/{name}.{}", sidecar::EXTENSION),
+            options,
+        )?;
+        zip.write_all(&mods.into_inner())?;
+    }
+
+    for (file, contents) in pack.skins.iter() {
+        zip.start_file(format!("{SKINS}/{}", sanitize_filename(file)), options)?;
+        zip.write_all(contents)?;
+    }
+
+    zip.finish()?;
+    Ok(())
+}
+
+// Everything is read and checked before anything is installed, so a broken pack installs nothing.
+// Entries outside the known folders are left out, they may be from a newer version
+pub fn read_pack<R: Read + Seek>(reader: R) -> io::Result<ModPack> {
+    let mut zip = ZipArchive::new(reader)?;
+
+    let mut contents = String::new();
+    zip.by_name(MANIFEST)
+        .map_err(|_| invalid("Not a mod pack, it has no pack.json".to_string()))?
+        .read_to_string(&mut contents)?;
+    let manifest: PackManifest = serde_json::from_str(&contents)?;
+
+    if manifest.version > VERSION {
+        return Err(invalid(format!(
+            "Mod pack version {} is newer than this app supports",
+            manifest.version
+        )));
+    }
+    if manifest.name.trim().is_empty() {
+        return Err(invalid("The mod pack has no name".to_string()));
+    }
+
+    let mut pack = ModPack {
+        manifest,
+        ..Default::default()
+    };
+
+    for index in 0..zip.len() {
+        let mut entry = zip.by_index(index)?;
+        if entry.is_dir() || entry.name() == MANIFEST {
+            continue;
+        }
+
+        // Names like ../../file would be written outside the pack's folder
+        let name = entry.name().to_string();
+        if entry.enclosed_name().is_none() {
+            return Err(invalid(format!("{name} can't be unpacked safely")));
+        }
+        let parts: Vec<&str> = name.split('/').collect();
+        let [folder, file] = parts[..] else {
+            continue;
+        };
+        if sanitize_filename(file) != file {
+            return Err(invalid(format!("{name} can't be unpacked safely")));
+        }
+
+        let mut bytes = Vec::new();
+        (&mut entry)
+            .take(MAX_ENTRY_BYTES + 1)
+            .read_to_end(&mut bytes)?;
+        if bytes.len() as u64 > MAX_ENTRY_BYTES {
+            return Err(invalid(format!("{name} is too large")));
+        }
+
+        let path = Path::new(file);
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let extension = path.extension().and_then(|e| e.to_str());
+
+        let failed = |e: io::Error| invalid(format!("{name}: {e}"));
+        match folder {
+            MODCHARTS if extension == Some(sidecar::EXTENSION) => {
+                let modchart = read_modchart(Cursor::new(bytes)).map_err(failed)?;
+                pack.modcharts.insert(stem, modchart);
+            }
+            MODCHARTS if extension == Some(script::EXTENSION) => {
+                let text = String::from_utf8(bytes).map_err(|e| invalid(format!("{name}: {e}")))?;
+                pack.modcharts
+                    .insert(stem, read_script(&text).map_err(failed)?);
+            }
+            SKINS => {
+                if is_theme(file) {
+                    let text =
+                        std::str::from_utf8(&bytes).map_err(|e| invalid(format!("{name}: {e}")))?;
+                    ron::from_str::<Theme>(text)
+                        .map_err(|e| invalid(format!("{name} is not a theme: {e}")))?;
+                }
+                pack.skins.insert(file.to_string(), bytes);
+            }
+            _ => {}
+        }
+    }
+
+    if pack.modcharts.is_empty() && pack.skins.is_empty() {
+        return Err(invalid("The mod pack is empty".to_string()));
+    }
+
+    Ok(pack)
+}
+
+pub fn packs_folder() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(PACK_FOLDER))
+}
+
+// Folder and theme names start with this, so packs don't overwrite each other's files
+pub fn pack_id(manifest: &PackManifest) -> String {
+    sanitize_filename(manifest.name.trim())
+}
+
+fn theme_name(id: &str, file: &str) -> String {
+    let stem = Path::new(file)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    format!("{id} - {stem}")
+}
+
+#[derive(Clone, Debug)]
+pub struct InstalledPack {
+    pub path: PathBuf,
+    pub manifest: PackManifest,
+    // Names of the modcharts, sorted
+    pub modcharts: Vec<String>,
+    // Names the pack's themes were installed under
+    pub themes: Vec<String>,
+}
+
+impl InstalledPack {
+    pub fn modchart_path(&self, name: &str) -> PathBuf {
+        self.path
+            .join(MODCHARTS)
+            .join(format!("{name}.{}", sidecar::EXTENSION))
+    }
+
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let manifest: PackManifest =
+            serde_json::from_str(&fs::read_to_string(path.join(MANIFEST))?)?;
+
+        let mut modcharts = Vec::new();
+        if let Ok(entries) = fs::read_dir(path.join(MODCHARTS)) {
+            for file in entries.flatten().map(|entry| entry.path()) {
+                if file.extension().is_some_and(|e| e == sidecar::EXTENSION)
+                    && let Some(stem) = file.file_stem()
+                {
+                    modcharts.push(stem.to_string_lossy().into_owned());
+                }
+            }
+        }
+        modcharts.sort();
+
+        let id = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut themes: Vec<String> = fs::read_dir(path.join(SKINS))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|file| is_theme(file))
+            .map(|file| theme_name(&id, &file))
+            .collect();
+        themes.sort();
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            manifest,
+            modcharts,
+            themes,
+        })
+    }
+}
+
+// Unpacked next to the target first, a pack that is installed again replaces the old one
+pub fn install_pack(
+    pack: &ModPack,
+    packs_folder: &Path,
+    themes_folder: &Path,
+) -> io::Result<InstalledPack> {
+    let id = pack_id(&pack.manifest);
+    let folder = packs_folder.join(&id);
+    let temporary = packs_folder.join(format!("{id}.tmp"));
+
+    if temporary.exists() {
+        fs::remove_dir_all(&temporary)?;
+    }
+    fs::create_dir_all(temporary.join(MODCHARTS))?;
+    fs::create_dir_all(temporary.join(SKINS))?;
+
+    fs::write(
+        temporary.join(MANIFEST),
+        serde_json::to_string_pretty(&pack.manifest)?,
+    )?;
+    for (name, modchart) in pack.modcharts.iter() {
+        let path = temporary.join(MODCHARTS).join(format!(
+            "{}.{}",
+            sanitize_filename(name),
+            sidecar::EXTENSION
+        ));
+        save_modchart(modchart, &path)?;
+    }
+    for (file, contents) in pack.skins.iter() {
+        fs::write(
+            temporary.join(SKINS).join(sanitize_filename(file)),
+            contents,
+        )?;
+    }
+
+    if folder.exists() {
+        remove_pack_themes(&folder, themes_folder)?;
+        fs::remove_dir_all(&folder)?;
+    }
+    fs::rename(&temporary, &folder)?;
+
+    fs::create_dir_all(themes_folder)?;
+    for (file, contents) in pack.skins.iter().filter(|(file, _)| is_theme(file)) {
+        let name = theme_name(&id, &sanitize_filename(file));
+        fs::write(themes_folder.join(format!("{name}.ron")), contents)?;
+    }
+
+    InstalledPack::read(&folder)
+}
+
+fn remove_pack_themes(folder: &Path, themes_folder: &Path) -> io::Result<()> {
+    for name in InstalledPack::read(folder)?.themes {
+        match fs::remove_file(themes_folder.join(format!("{name}.ron"))) {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn uninstall_pack(pack: &InstalledPack, themes_folder: &Path) -> io::Result<()> {
+    remove_pack_themes(&pack.path, themes_folder)?;
+    fs::remove_dir_all(&pack.path)
+}
+
+// Sorted by name, folders that aren't packs or are left over from a failed install are skipped
+pub fn installed_packs(packs_folder: &Path) -> Vec<InstalledPack> {
+    let mut packs: Vec<InstalledPack> = fs::read_dir(packs_folder)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir() && path.extension().is_none_or(|e| e != "tmp"))
+        .filter_map(|path| InstalledPack::read(&path).ok())
+        .collect();
+
+    packs.sort_by_key(|pack| pack.manifest.name.to_lowercase());
+    packs
+}
@@ -0,0 +1,113 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+
+use crate::maps::{CurrentMap, Map};
+
+// SplitMix64, small and the same on every platform so a seed always gives the same numbers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        mix(self.0)
+    }
+
+    // Uniform in 0..1
+    pub fn unit(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+// FNV-1a, unlike the std hashers it gives the same value in every build
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xCBF2_9CE4_8422_2325u64, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01B3)
+    })
+}
+
+// So the seed of a map only depends on its id
+pub fn map_seed(map_id: &str) -> u64 {
+    mix(fnv1a(map_id.as_bytes()))
+}
+
+// For runs that roll their randomness again, kept with the score so the run can be replayed
+pub fn fresh_seed() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+    mix(nanos)
+}
+
+// Randomness for mods. Modcharts are evaluated from scratch every frame and seeking can land
+// anywhere, so numbers aren't drawn in sequence: each one is looked up by a stream, like the
+// index of the event asking, and an index within it. The same seed then renders the same run no
+// matter the frame rate or how often it was seeked.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ModRandom {
+    // Of the current map, used outside of sessions
+    pub map: u64,
+    // Of the session being played, from its score
+    pub run: Option<u64>,
+}
+
+impl ModRandom {
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            map: seed,
+            run: None,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.run.unwrap_or(self.map)
+    }
+
+    // Uniform in 0..1
+    pub fn unit(&self, stream: u64, index: u64) -> f32 {
+        let z = mix(self.seed() ^ mix(stream.wrapping_add(0x9E37_79B9_7F4A_7C15)))
+            .wrapping_add(index.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        (mix(z) >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    // Uniform in -1..1
+    pub fn signed(&self, stream: u64, index: u64) -> f32 {
+        self.unit(stream, index) * 2.0 - 1.0
+    }
+
+    // Each axis on its own in -1..1
+    pub fn signed_vec3(&self, stream: u64, index: u64) -> Vec3 {
+        Vec3::new(
+            self.signed(stream, index * 3),
+            self.signed(stream, index * 3 + 1),
+            self.signed(stream, index * 3 + 2),
+        )
+    }
+}
+
+// Outside of sessions the randomness follows the map, so a modchart looks the same every time
+// it is opened
+pub fn sync_map_seed(
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+    mut random: ResMut<ModRandom>,
+) {
+    let seed = current_map
+        .and_then(|current| maps.get(&current.0))
+        .map_or(0, |map| map_seed(&map.id));
+
+    if random.map != seed {
+        random.map = seed;
+    }
+}
@@ -0,0 +1,114 @@
+use std::io::{self, ErrorKind};
+
+use bevy::{
+    asset::ron::{self, ser::PrettyConfig},
+    math::Vec3,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::modchart::{
+    CoordinateSpace, Easing, Keyframe, ModBlend, ModChannel, ModEvent, Modchart,
+};
+
+pub const EXTENSION: &str = "ron";
+
+// The same modchart as the binary sidecar, as text that can be reviewed, diffed and edited by
+// hand. Every keyframe goes on a line of its own:
+//
+// (
+//     events: [
+//         (
+//             channel: CameraOffset,
+//             space: Screen,
+//             blend: Add,
+//             jitter: (0.1, 0.1, 0.0),
+//             keyframes: [
+//                 (at: 1000, value: (0.0, 0.5, 0.0), easing: QuadOut),
+//             ],
+//         ),
+//     ],
+// )
+//
+// Space, blend, jitter and easing can be left out, they default to world space, override, none
+// and linear. Jitter moves every keyframe by a random amount up to it, see ModEvent::jitter.
+#[derive(Serialize, Deserialize)]
+struct Script {
+    events: Vec<ScriptEvent>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ScriptEvent {
+    channel: ModChannel,
+    #[serde(default)]
+    space: CoordinateSpace,
+    #[serde(default)]
+    blend: ModBlend,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    jitter: (f32, f32, f32),
+    keyframes: Vec<ScriptKeyframe>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ScriptKeyframe {
+    at: u32,
+    value: (f32, f32, f32),
+    #[serde(default)]
+    easing: Easing,
+}
+
+fn is_zero(value: &(f32, f32, f32)) -> bool {
+    *value == (0.0, 0.0, 0.0)
+}
+
+pub fn write_script(modchart: &Modchart) -> io::Result<String> {
+    let script = Script {
+        events: modchart
+            .events()
+            .iter()
+            .map(|event| ScriptEvent {
+                channel: event.channel,
+                space: event.space,
+                blend: event.blend,
+                jitter: event.jitter.into(),
+                keyframes: event
+                    .keyframes()
+                    .iter()
+                    .map(|keyframe| ScriptKeyframe {
+                        at: keyframe.millisecond,
+                        value: keyframe.value.into(),
+                        easing: keyframe.easing,
+                    })
+                    .collect(),
+            })
+            .collect(),
+    };
+
+    // Anything deeper than the keyframe lists is kept on one line
+    let config = PrettyConfig::default().depth_limit(4);
+    ron::ser::to_string_pretty(&script, config)
+        .map_err(|e| io::Error::new(ErrorKind::InvalidData, e.to_string()))
+}
+
+// Keyframes can be written in any order, events keep them sorted
+pub fn read_script(contents: &str) -> io::Result<Modchart> {
+    let script: Script = ron::from_str(contents)
+        .map_err(|e| io::Error::new(ErrorKind::InvalidData, e.to_string()))?;
+
+    let events = script
+        .events
+        .into_iter()
+        .map(|event| {
+            let keyframes = event
+                .keyframes
+                .into_iter()
+                .map(|k| Keyframe::new(k.at, Vec3::from(k.value)).with_easing(k.easing))
+                .collect();
+            ModEvent::new(event.channel, keyframes)
+                .with_space(event.space)
+                .with_blend(event.blend)
+                .with_jitter(Vec3::from(event.jitter))
+        })
+        .collect();
+
+    Ok(Modchart::new(events))
+}
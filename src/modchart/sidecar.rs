@@ -0,0 +1,201 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    editor::shortcuts::{EditorAction, EditorActionEvent},
+    maps::{
+        CurrentMap, Map,
+        io::{BinaryReader, BinaryWriter},
+        library::MapLibrary,
+        map_file,
+    },
+    modchart::{CoordinateSpace, Easing, Keyframe, ModBlend, ModChannel, ModEvent, Modchart},
+};
+
+pub const EXTENSION: &str = "mmmod";
+
+const SIGNATURE: [u8; 4] = *b"MMOD";
+const VERSION: u16 = 4;
+
+// Where the modchart of the current map is kept, next to the map file so the map itself
+// never has to be rewritten. Maps made in the editor have none until they are exported.
+#[derive(Resource, Default, Debug)]
+pub struct ModchartFile {
+    pub path: Option<PathBuf>,
+    // Came with the map from somewhere other than a sidecar, like a project, and replaces it
+    pub preloaded: Option<Modchart>,
+}
+
+pub fn sidecar_path(map_path: &Path) -> PathBuf {
+    map_path.with_extension(EXTENSION)
+}
+
+// Layout: signature, version, then every event as its channel, space, blend, keyframe count and
+// keyframes. Version 1 had no space, its events are all in world space. Versions before 3 had
+// no blend, their events all override, and versions before 4 had no jitter
+pub fn write_modchart<T: Write + Seek>(modchart: &Modchart, writer: T) -> io::Result<()> {
+    let mut writer = BinaryWriter::new(writer);
+
+    writer.write_all(&SIGNATURE)?;
+    writer.write_u16(VERSION)?;
+    writer.write_u32(modchart.events().len() as u32)?;
+
+    for event in modchart.events().iter() {
+        writer.write_u8(event.channel.index())?;
+        writer.write_u8(event.space.index())?;
+        writer.write_u8(event.blend.index())?;
+        writer.write_f32(event.jitter.x)?;
+        writer.write_f32(event.jitter.y)?;
+        writer.write_f32(event.jitter.z)?;
+        writer.write_u32(event.keyframes().len() as u32)?;
+
+        for keyframe in event.keyframes() {
+            writer.write_u32(keyframe.millisecond)?;
+            writer.write_f32(keyframe.value.x)?;
+            writer.write_f32(keyframe.value.y)?;
+            writer.write_f32(keyframe.value.z)?;
+            writer.write_u8(keyframe.easing.index())?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn read_modchart<T: Read + Seek>(reader: T) -> io::Result<Modchart> {
+    let mut reader = BinaryReader::new(reader);
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+    let mut signature = [0u8; 4];
+    reader.read_exact(&mut signature)?;
+    if signature != SIGNATURE {
+        return Err(invalid("Not a modchart file"));
+    }
+
+    let version = reader.read_u16()?;
+    if version > VERSION {
+        return Err(invalid(&format!(
+            "Modchart version {version} is newer than this app supports"
+        )));
+    }
+
+    let count = reader.read_u32()?;
+    let mut events = Vec::new();
+
+    for _ in 0..count {
+        let channel = ModChannel::from_index(reader.read_u8()?)
+            .ok_or_else(|| invalid("Unknown modchart channel"))?;
+        let space = match version {
+            1 => CoordinateSpace::World,
+            _ => CoordinateSpace::from_index(reader.read_u8()?)
+                .ok_or_else(|| invalid("Unknown coordinate space"))?,
+        };
+        let blend = match version {
+            1 | 2 => ModBlend::Override,
+            _ => ModBlend::from_index(reader.read_u8()?).ok_or_else(|| invalid("Unknown blend"))?,
+        };
+        let jitter = match version {
+            1..=3 => Vec3::ZERO,
+            _ => Vec3::new(reader.read_f32()?, reader.read_f32()?, reader.read_f32()?),
+        };
+
+        let mut keyframes = Vec::new();
+        for _ in 0..reader.read_u32()? {
+            let millisecond = reader.read_u32()?;
+            let value = Vec3::new(reader.read_f32()?, reader.read_f32()?, reader.read_f32()?);
+            let easing =
+                Easing::from_index(reader.read_u8()?).ok_or_else(|| invalid("Unknown easing"))?;
+
+            keyframes.push(Keyframe::new(millisecond, value).with_easing(easing));
+        }
+
+        events.push(
+            ModEvent::new(channel, keyframes)
+                .with_space(space)
+                .with_blend(blend)
+                .with_jitter(jitter),
+        );
+    }
+
+    Ok(Modchart::new(events))
+}
+
+// Written next to the target first so a failed save never leaves a broken modchart behind
+pub fn save_modchart(modchart: &Modchart, path: &Path) -> io::Result<()> {
+    let temporary = path.with_extension(format!("{EXTENSION}.tmp"));
+    let mut writer = BufWriter::new(File::create(&temporary)?);
+    write_modchart(modchart, &mut writer)?;
+    writer.flush()?;
+    drop(writer);
+
+    fs::rename(&temporary, path)
+}
+
+// Every map starts with its own modchart. Without a sidecar it starts with the mods imported
+// from the map file, if there are any.
+pub fn load_modchart_sidecar(
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+    library: Res<MapLibrary>,
+    asset_server: Res<AssetServer>,
+    mut file: ResMut<ModchartFile>,
+    mut modchart: ResMut<Modchart>,
+) {
+    let Some(current_map) = current_map.filter(|m| m.is_changed()) else {
+        return;
+    };
+
+    file.path = map_file(&current_map.0, &library, &asset_server).map(|p| sidecar_path(&p));
+
+    let imported = || {
+        Modchart::new(
+            maps.get(&current_map.0)
+                .map_or_else(Vec::new, |map| map.mod_events.clone()),
+        )
+    };
+
+    if let Some(preloaded) = file.preloaded.take() {
+        *modchart = preloaded;
+        return;
+    }
+
+    *modchart = match file.path.as_deref().filter(|path| path.exists()) {
+        Some(path) => match File::open(path).and_then(|f| read_modchart(BufReader::new(f))) {
+            Ok(loaded) => {
+                info!("Loaded modchart {}", path.display());
+                loaded
+            }
+            Err(e) => {
+                warn!("Could not read modchart {}: {e}", path.display());
+                imported()
+            }
+        },
+        None => imported(),
+    };
+}
+
+pub fn save_modchart_sidecar(
+    mut actions: EventReader<EditorActionEvent>,
+    file: Res<ModchartFile>,
+    modchart: Res<Modchart>,
+) {
+    for EditorActionEvent(action) in actions.read() {
+        if *action != EditorAction::SaveModchart {
+            continue;
+        }
+
+        let Some(path) = &file.path else {
+            warn!("The modchart can only be saved once the map has been exported");
+            continue;
+        };
+
+        match save_modchart(&modchart, path) {
+            Ok(()) => info!("Saved modchart to {}", path.display()),
+            Err(e) => warn!("Saving modchart to {} failed: {e}", path.display()),
+        }
+    }
+}
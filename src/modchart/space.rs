@@ -0,0 +1,95 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::player::playfield::CELL_SIZE;
+
+// What the x and y of an event's keyframes are measured in. Depth is never screen relative,
+// so z stays in world units for screen space.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CoordinateSpace {
+    #[default]
+    World,
+    // Cells of the grid, with y pointing down like note positions
+    Grid,
+    // -1 to 1 across the area the gameplay camera sees around the grid, with y pointing up,
+    // so an event reaches the edge of the window at any size
+    Screen,
+}
+
+impl CoordinateSpace {
+    pub const ALL: [CoordinateSpace; 3] = [
+        CoordinateSpace::World,
+        CoordinateSpace::Grid,
+        CoordinateSpace::Screen,
+    ];
+
+    // Stable number of the space in saved modcharts, new spaces only go at the end
+    pub fn index(&self) -> u8 {
+        CoordinateSpace::ALL
+            .iter()
+            .position(|s| s == self)
+            .unwrap_or(0) as u8
+    }
+
+    pub fn from_index(index: u8) -> Option<Self> {
+        CoordinateSpace::ALL.get(index as usize).copied()
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CoordinateSpace::World => "World",
+            CoordinateSpace::Grid => "Grid",
+            CoordinateSpace::Screen => "Screen",
+        }
+    }
+
+    // Values are offsets rather than points, so every space shares the same origin
+    pub fn to_world(&self, value: Vec3, frame: &ScreenFrame) -> Vec3 {
+        match self {
+            CoordinateSpace::World => value,
+            CoordinateSpace::Grid => Vec3::new(value.x, -value.y, value.z) * CELL_SIZE,
+            CoordinateSpace::Screen => (value.xy() * frame.half_extent).extend(value.z),
+        }
+    }
+
+    pub fn from_world(&self, value: Vec3, frame: &ScreenFrame) -> Vec3 {
+        match self {
+            CoordinateSpace::World => value,
+            CoordinateSpace::Grid => Vec3::new(value.x, -value.y, value.z) / CELL_SIZE,
+            CoordinateSpace::Screen => {
+                let half_extent = frame.half_extent.max(Vec2::splat(f32::EPSILON));
+                (value.xy() / half_extent).extend(value.z)
+            }
+        }
+    }
+
+    pub fn convert(&self, value: Vec3, to: CoordinateSpace, frame: &ScreenFrame) -> Vec3 {
+        match *self == to {
+            true => value,
+            false => to.from_world(self.to_world(value, frame), frame),
+        }
+    }
+}
+
+// Half the width and height the gameplay camera sees on the grid plane, in world units. Kept up
+// to date with the window by the camera, screen space events are converted with it.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct ScreenFrame {
+    pub half_extent: Vec2,
+}
+
+impl ScreenFrame {
+    pub fn from_perspective(fov: f32, aspect_ratio: f32, distance: f32) -> Self {
+        let half_height = distance * (fov / 2.0).tan();
+        Self {
+            half_extent: Vec2::new(half_height * aspect_ratio, half_height),
+        }
+    }
+}
+
+impl Default for ScreenFrame {
+    // The default camera looking at the grid from 4 units away through a 16:9 window
+    fn default() -> Self {
+        Self::from_perspective(std::f32::consts::FRAC_PI_4, 16.0 / 9.0, 4.0)
+    }
+}
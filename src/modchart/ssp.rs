@@ -0,0 +1,64 @@
+use bevy::math::Vec3;
+
+use crate::{
+    maps::parser::{ObjectDefinition, ObjectType},
+    modchart::{Easing, Keyframe, ModChannel, ModEvent},
+};
+
+// Mod objects in maps with the has_mod flag. SSPM doesn't name any mod objects, so these names
+// are this app's own like mm_timing_point, read when a map has them but never written: mods are
+// saved to the modchart sidecar instead. Every object is a single keyframe of x, y and z,
+// optionally followed by the easing into it.
+const MOD_OBJECTS: [(&str, ModChannel); 5] = [
+    ("mm_mod_camera_offset", ModChannel::CameraOffset),
+    ("mm_mod_camera_rotation", ModChannel::CameraRotation),
+    ("mm_mod_playfield_offset", ModChannel::PlayfieldOffset),
+    ("mm_mod_playfield_rotation", ModChannel::PlayfieldRotation),
+    ("mm_mod_playfield_scale", ModChannel::PlayfieldScale),
+];
+
+fn keyframe(object: &ObjectDefinition) -> Option<(ModChannel, Keyframe)> {
+    let (_, channel) = MOD_OBJECTS.iter().find(|(name, _)| *name == object.name)?;
+
+    let (value, easing) = match object.definitions.as_slice() {
+        [
+            ObjectType::F32(Some(x)),
+            ObjectType::F32(Some(y)),
+            ObjectType::F32(Some(z)),
+            rest @ ..,
+        ] => (Vec3::new(*x, *y, *z), rest),
+        _ => return None,
+    };
+
+    // Easings we don't know would change how the chart plays, so the object is kept as it is
+    let easing = match easing {
+        [] => Easing::Linear,
+        [ObjectType::U8(Some(index))] => Easing::from_index(*index)?,
+        _ => return None,
+    };
+
+    Some((
+        *channel,
+        Keyframe::new(object.millisecond, value).with_easing(easing),
+    ))
+}
+
+// Takes the recognized mod objects out of `objects`, one event per channel in use.
+// Anything else stays behind as a raw object.
+pub fn take_mod_events(objects: &mut Vec<ObjectDefinition>) -> Vec<ModEvent> {
+    let mut events: Vec<ModEvent> = Vec::new();
+
+    objects.retain(|object| {
+        let Some((channel, keyframe)) = keyframe(object) else {
+            return true;
+        };
+
+        match events.iter_mut().find(|event| event.channel == channel) {
+            Some(event) => event.insert(keyframe),
+            None => events.push(ModEvent::new(channel, vec![keyframe])),
+        }
+        false
+    });
+
+    events
+}
@@ -0,0 +1,169 @@
+// Everything an app embedding the player as a chart preview needs. Adding MapPlugin, PlayerPlugin
+// and JukeboxPlugin is enough, the preview is then driven with these events and read back from
+// PlaybackStatus without touching the clock or states directly.
+
+use std::{io, path::PathBuf};
+
+use bevy::{
+    prelude::*,
+    tasks::{IoTaskPool, Task, block_on, futures_lite::future},
+};
+
+use crate::{
+    jukebox::DecodedAudio,
+    maps::{CurrentMap, Map, read_map},
+    player::{
+        SimulationState,
+        mods::Mods,
+        playback::{PlaybackClock, map_end_ms},
+    },
+    settings::Settings,
+};
+
+// Replaces the map being previewed, it starts paused at the beginning
+#[derive(Event, Clone, Debug)]
+pub enum LoadMap {
+    // Read in the background, outside of the asset folder
+    File(PathBuf),
+    // A map the app already loaded or made itself
+    Asset(Handle<Map>),
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Play;
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Pause;
+
+// In milliseconds from the start of the map
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Seek(pub f64);
+
+// Only the speed mod changes the preview, the others affect gameplay. With GameplayPlugin added,
+// sessions set the rate from their own mods instead
+#[derive(Event, Clone, Debug)]
+pub struct SetMods(pub Mods);
+
+// Read only for the embedding app, updated every frame
+#[derive(Resource, Default, Debug)]
+pub struct PlaybackStatus {
+    pub map: Option<Handle<Map>>,
+    pub title: String,
+    pub millisecond: f64,
+    // Until the audio is decoded this is only the length of the objects
+    pub length_ms: f64,
+    pub playing: bool,
+    pub mods: Mods,
+    // A file from LoadMap is being read
+    pub loading: bool,
+    // Why the last LoadMap failed, cleared by the next one
+    pub error: Option<String>,
+}
+
+#[derive(Resource, Default)]
+pub struct PendingLoad(Option<Task<(PathBuf, io::Result<Map>)>>);
+
+#[allow(clippy::too_many_arguments)]
+pub fn load_maps(
+    mut commands: Commands,
+    mut requests: EventReader<LoadMap>,
+    mut pending: ResMut<PendingLoad>,
+    mut status: ResMut<PlaybackStatus>,
+    mut maps: ResMut<Assets<Map>>,
+    mut clock: ResMut<PlaybackClock>,
+    mut next_simulation: ResMut<NextState<SimulationState>>,
+    settings: Res<Settings>,
+) {
+    // A newer request replaces one that is still being read
+    if let Some(request) = requests.read().last() {
+        status.error = None;
+        pending.0 = None;
+
+        match request {
+            LoadMap::File(path) => {
+                let path = path.clone();
+                let options = settings.read_options();
+                pending.0 = Some(IoTaskPool::get().spawn(async move {
+                    let result = read_map(&path, options);
+                    (path, result)
+                }));
+            }
+            LoadMap::Asset(handle) => {
+                commands.insert_resource(CurrentMap(handle.clone()));
+                clock.seek(0.0);
+                next_simulation.set(SimulationState::Paused);
+            }
+        }
+    }
+
+    let Some((path, result)) = pending
+        .0
+        .as_mut()
+        .and_then(|task| block_on(future::poll_once(task)))
+    else {
+        return;
+    };
+    pending.0 = None;
+
+    match result {
+        Ok(map) => {
+            commands.insert_resource(CurrentMap(maps.add(map)));
+            clock.seek(0.0);
+            next_simulation.set(SimulationState::Paused);
+        }
+        Err(e) => {
+            warn!("Could not load {}: {e}", path.display());
+            status.error = Some(format!("Could not load {}: {e}", path.display()));
+        }
+    }
+}
+
+pub fn control_playback(
+    mut plays: EventReader<Play>,
+    mut pauses: EventReader<Pause>,
+    mut seeks: EventReader<Seek>,
+    mut set_mods: EventReader<SetMods>,
+    mut status: ResMut<PlaybackStatus>,
+    mut clock: ResMut<PlaybackClock>,
+    mut next_simulation: ResMut<NextState<SimulationState>>,
+) {
+    // Whichever of play and pause was sent at all wins, pause when both were
+    if pauses.read().count() > 0 {
+        plays.clear();
+        next_simulation.set(SimulationState::Paused);
+    } else if plays.read().count() > 0 {
+        next_simulation.set(SimulationState::Running);
+    }
+
+    if let Some(Seek(millisecond)) = seeks.read().last() {
+        clock.seek(*millisecond);
+    }
+
+    if let Some(SetMods(mods)) = set_mods.read().last() {
+        status.mods = mods.clone();
+        clock.rate = mods.rate();
+    }
+}
+
+pub fn update_playback_status(
+    mut status: ResMut<PlaybackStatus>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+    audio: Res<DecodedAudio>,
+    clock: Res<PlaybackClock>,
+    simulation: Res<State<SimulationState>>,
+    pending: Res<PendingLoad>,
+) {
+    let map = current_map.as_ref().and_then(|m| maps.get(&m.0));
+
+    status.map = current_map.map(|m| m.0.clone());
+    status.title = map.map(|m| m.title.clone()).unwrap_or_default();
+    status.length_ms = match map {
+        Some(map) if !audio.is_decoding() => map_end_ms(map, &audio),
+        Some(map) => map.last_object_ms() as f64,
+        None => 0.0,
+    };
+    status.millisecond = clock.millisecond;
+    status.playing = *simulation.get() == SimulationState::Running;
+    status.loading = pending.0.is_some();
+}
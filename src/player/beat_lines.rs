@@ -0,0 +1,131 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    maps::{CurrentMap, Map},
+    player::{
+        playback::PlaybackClock,
+        playfield::{
+            APPROACH_DISTANCE, APPROACH_SPEED, CELL_SIZE, GRID_CELLS, Playfield, time_to_depth,
+        },
+    },
+    settings::Settings,
+    theme::Theme,
+};
+
+// Stops zero speed sections from filling the lane with lines that never move
+const MAX_LINES: usize = 256;
+
+// Beats are drawn at this fraction of the measure lines' opacity, subdivisions at half of that
+const BEAT_ALPHA: f32 = 0.5;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct BeatLineSettings {
+    pub enabled: bool,
+    // Of the measure lines, beats and subdivisions are fainter
+    pub opacity: f32,
+    // Lines per beat, 1 only draws the beats themselves
+    pub subdivisions: u32,
+}
+
+impl Default for BeatLineSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            opacity: 0.4,
+            subdivisions: 1,
+        }
+    }
+}
+
+// A frame around the lane at every measure, beat and subdivision of the timing points, placed by
+// scroll position so they move with the notes through speed changes
+pub fn draw_beat_lines(
+    mut gizmos: Gizmos,
+    settings: Res<Settings>,
+    clock: Res<PlaybackClock>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+    playfield: Query<&GlobalTransform, With<Playfield>>,
+    theme: Res<Theme>,
+) {
+    let millisecond = clock.visual_ms(&settings);
+    let settings = &settings.beat_lines;
+    if !settings.enabled {
+        return;
+    }
+
+    let (Some(map), Ok(playfield)) = (current_map.and_then(|m| maps.get(&m.0)), playfield.single())
+    else {
+        return;
+    };
+
+    let scroll = map.scroll_timeline();
+    let now = scroll.position(millisecond);
+    let window = (APPROACH_DISTANCE / APPROACH_SPEED * 1000.0) as f64;
+    let end_ms = map.length.max(map.last_object_ms()) as f64;
+
+    let subdivisions = settings.subdivisions.max(1);
+    let half = GRID_CELLS as f32 * CELL_SIZE / 2.0;
+    let corners = [
+        Vec2::new(-half, -half),
+        Vec2::new(half, -half),
+        Vec2::new(half, half),
+        Vec2::new(-half, half),
+    ];
+
+    let mut drawn = 0;
+
+    for (index, timing) in map.timing_points.iter().enumerate() {
+        let section_end = map
+            .timing_points
+            .get(index + 1)
+            .map_or(end_ms, |next| next.millisecond as f64);
+
+        if section_end < millisecond {
+            continue;
+        }
+
+        let step = timing.beat_length() / subdivisions as f64;
+        let per_measure = subdivisions * timing.beats_per_measure.max(1) as u32;
+        let first = ((millisecond - timing.millisecond as f64) / step)
+            .ceil()
+            .max(0.0) as u32;
+
+        for line in first.. {
+            let millisecond = timing.millisecond as f64 + line as f64 * step;
+            let ahead = scroll.position(millisecond) - now;
+
+            if millisecond >= section_end || ahead > window || drawn >= MAX_LINES {
+                break;
+            }
+
+            let alpha = if line % per_measure == 0 {
+                settings.opacity
+            } else if line % subdivisions == 0 {
+                settings.opacity * BEAT_ALPHA
+            } else {
+                settings.opacity * BEAT_ALPHA / 2.0
+            };
+
+            let color = theme.grid.color().with_alpha(alpha);
+            let depth = time_to_depth(ahead as f32);
+
+            for (i, corner) in corners.iter().enumerate() {
+                let next = corners[(i + 1) % corners.len()];
+                gizmos.line(
+                    playfield.transform_point(corner.extend(depth)),
+                    playfield.transform_point(next.extend(depth)),
+                    color,
+                );
+            }
+
+            drawn += 1;
+        }
+
+        if drawn >= MAX_LINES {
+            break;
+        }
+    }
+}
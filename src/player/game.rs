@@ -2,6 +2,7 @@ use bevy::prelude::*;
 
 use crate::player::mods::Mods;
 
+#[allow(dead_code)]
 #[derive(Bundle)]
 pub struct Game {
     mods: Mods,
@@ -0,0 +1,73 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    maps::{
+        CurrentMap, Map,
+        objects::lyric::{LyricStyle, shown_lyrics},
+    },
+    player::playback::PlaybackClock,
+    settings::Settings,
+};
+
+// Lyrics ease in and out over this long instead of popping
+const FADE_MS: f64 = 150.0;
+
+fn placement(style: LyricStyle) -> (egui::Align2, egui::Vec2, f32) {
+    match style {
+        LyricStyle::Subtitle => (egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -48.0), 28.0),
+        LyricStyle::Title => (egui::Align2::CENTER_CENTER, egui::Vec2::ZERO, 56.0),
+        LyricStyle::Caption => (egui::Align2::CENTER_TOP, egui::vec2(0.0, 48.0), 18.0),
+    }
+}
+
+// How visible a lyric is `millisecond` into the map, fading at both ends
+pub fn lyric_opacity(start: u32, end: u32, millisecond: f64) -> f32 {
+    let since_start = millisecond - start as f64;
+    let until_end = end as f64 - millisecond;
+    (since_start.min(until_end) / FADE_MS).clamp(0.0, 1.0) as f32
+}
+
+pub fn lyrics_ui(
+    mut contexts: EguiContexts,
+    clock: Res<PlaybackClock>,
+    settings: Res<Settings>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+) -> Result {
+    let Some(map) = current_map.and_then(|current| maps.get(&current.0)) else {
+        return Ok(());
+    };
+    if map.lyrics.is_empty() {
+        return Ok(());
+    }
+
+    let millisecond = clock.visual_ms(&settings);
+    let ctx = contexts.ctx_mut()?;
+
+    for style in LyricStyle::ALL {
+        let lines: Vec<_> = shown_lyrics(&map.lyrics, millisecond)
+            .filter(|lyric| lyric.style == style)
+            .collect();
+        if lines.is_empty() {
+            continue;
+        }
+
+        let (anchor, offset, size) = placement(style);
+        egui::Area::new(egui::Id::new(("lyrics", style.index())))
+            .anchor(anchor, offset)
+            .interactable(false)
+            .order(egui::Order::Background)
+            .show(ctx, |ui| {
+                ui.vertical_centered(|ui| {
+                    for lyric in lines {
+                        let opacity = lyric_opacity(lyric.millisecond, lyric.end(), millisecond);
+                        let color = ui.visuals().strong_text_color().gamma_multiply(opacity);
+                        ui.label(egui::RichText::new(&lyric.text).size(size).color(color));
+                    }
+                });
+            });
+    }
+
+    Ok(())
+}
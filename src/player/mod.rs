@@ -1,10 +1,28 @@
 use bevy::prelude::*;
+use bevy_egui::EguiPrimaryContextPass;
 
+pub mod api;
+pub mod beat_lines;
 mod game;
-mod mods;
+pub mod lyrics;
+pub mod mods;
+pub mod note_path;
+pub mod notes;
+pub mod playback;
+pub mod playfield;
 
-use game::*;
-use mods::*;
+use api::{LoadMap, Pause, PendingLoad, Play, PlaybackStatus, Seek, SetMods};
+use note_path::NotePathSettings;
+use notes::NotePool;
+use playback::PlaybackClock;
+
+use crate::{
+    debug::profiler::{NOTE_SPAWNER, RENDERER, timed},
+    jukebox::DecodedAudio,
+    modchart::ModState,
+    settings::Settings,
+    theme::Theme,
+};
 
 #[derive(States, PartialEq, Eq, Debug, Hash, Clone, Default)]
 pub enum SimulationState {
@@ -17,6 +35,45 @@ pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.init_state::<SimulationState>();
+        // Apps embedding only the player get the defaults, SettingsPlugin and ThemePlugin replace
+        // them with what the user picked
+        app.init_resource::<Settings>()
+            .init_resource::<Theme>()
+            .init_resource::<ModState>()
+            .init_resource::<DecodedAudio>()
+            .init_state::<SimulationState>()
+            .init_resource::<PlaybackClock>()
+            .init_resource::<NotePathSettings>()
+            .init_resource::<NotePool>()
+            .init_resource::<PlaybackStatus>()
+            .init_resource::<PendingLoad>()
+            .add_event::<playback::MapFinished>()
+            .add_event::<LoadMap>()
+            .add_event::<Play>()
+            .add_event::<Pause>()
+            .add_event::<Seek>()
+            .add_event::<SetMods>()
+            .add_systems(
+                Startup,
+                (playfield::spawn_playfield, notes::spawn_note_pool).chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    (api::load_maps, api::control_playback)
+                        .chain()
+                        .before(playback::advance_clock),
+                    (playback::advance_clock, playback::detect_map_end)
+                        .chain()
+                        .run_if(in_state(SimulationState::Running)),
+                    api::update_playback_status.after(playback::detect_map_end),
+                    timed(&RENDERER, playfield::draw_grid),
+                    timed(&RENDERER, beat_lines::draw_beat_lines),
+                    timed(&RENDERER, note_path::draw_note_path),
+                    timed(&NOTE_SPAWNER, notes::render_notes).after(playback::advance_clock),
+                    notes::apply_note_theme.run_if(resource_changed::<Theme>),
+                ),
+            )
+            .add_systems(EguiPrimaryContextPass, lyrics::lyrics_ui);
     }
 }
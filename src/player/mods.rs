@@ -1,6 +1,164 @@
+use std::collections::BTreeMap;
+
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub const NO_FAIL: &str = "no_fail";
+pub const SPEED: &str = "speed";
+pub const SPIN: &str = "spin";
+
+#[derive(Debug)]
+pub struct ParameterDefinition {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub min: f32,
+    pub max: f32,
+    pub step: f32,
+    pub default: f32,
+    // Shown after the value, e.g. "x" for rates
+    pub suffix: &'static str,
+}
+
+impl ParameterDefinition {
+    pub fn clamp(&self, value: f32) -> f32 {
+        value.clamp(self.min, self.max)
+    }
+}
+
+#[derive(Debug)]
+pub struct ModDefinition {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub parameters: &'static [ParameterDefinition],
+}
+
+impl ModDefinition {
+    pub fn get(id: &str) -> Option<&'static ModDefinition> {
+        MOD_DEFINITIONS
+            .iter()
+            .find(|definition| definition.id == id)
+    }
+
+    pub fn parameter(&self, id: &str) -> Option<&'static ParameterDefinition> {
+        self.parameters.iter().find(|parameter| parameter.id == id)
+    }
+}
+
+// Every mod the player can pick. The mod selection UI and the saved mods are built from these,
+// so adding a mod only takes an entry here and the code applying its effect.
+pub const MOD_DEFINITIONS: &[ModDefinition] = &[
+    ModDefinition {
+        id: NO_FAIL,
+        name: "No fail",
+        description: "Health can run out without failing, at the cost of the ruleset's score multiplier",
+        parameters: &[],
+    },
+    ModDefinition {
+        id: SPEED,
+        name: "Speed",
+        description: "Plays the map faster or slower",
+        parameters: &[ParameterDefinition {
+            id: "rate",
+            name: "Rate",
+            min: 0.5,
+            max: 2.0,
+            step: 0.05,
+            default: 1.0,
+            suffix: "x",
+        }],
+    },
+    ModDefinition {
+        id: SPIN,
+        name: "Spin",
+        description: "The playfield keeps turning around its center, on top of the map's own mods",
+        parameters: &[ParameterDefinition {
+            id: "speed",
+            name: "Speed",
+            min: -360.0,
+            max: 360.0,
+            step: 5.0,
+            default: 45.0,
+            suffix: "°/s",
+        }],
+    },
+];
+
+// Enabled mods by id, with the chosen value of each of their parameters. Stored by name so
+// settings and scores keep working when mods are added, unknown ones are ignored.
+#[derive(Component, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Mods(BTreeMap<String, BTreeMap<String, f32>>);
+
+impl Mods {
+    pub fn is_enabled(&self, id: &str) -> bool {
+        self.0.contains_key(id)
+    }
+
+    // Enabling starts every parameter at its default
+    pub fn set_enabled(&mut self, definition: &ModDefinition, enabled: bool) {
+        if !enabled {
+            self.0.remove(definition.id);
+        } else if !self.is_enabled(definition.id) {
+            let parameters = definition
+                .parameters
+                .iter()
+                .map(|parameter| (parameter.id.to_string(), parameter.default))
+                .collect();
+            self.0.insert(definition.id.to_string(), parameters);
+        }
+    }
+
+    // None when the mod is disabled, parameters missing from older files fall back to the default
+    pub fn parameter(&self, id: &str, parameter: &str) -> Option<f32> {
+        let values = self.0.get(id)?;
+        let definition = ModDefinition::get(id)?.parameter(parameter)?;
+
+        Some(
+            values
+                .get(parameter)
+                .map_or(definition.default, |value| definition.clamp(*value)),
+        )
+    }
+
+    pub fn set_parameter(&mut self, id: &str, parameter: &str, value: f32) {
+        let Some(definition) = ModDefinition::get(id).and_then(|d| d.parameter(parameter)) else {
+            return;
+        };
+
+        if let Some(values) = self.0.get_mut(id) {
+            values.insert(parameter.to_string(), definition.clamp(value));
+        }
+    }
+
+    pub fn no_fail(&self) -> bool {
+        self.is_enabled(NO_FAIL)
+    }
+
+    pub fn rate(&self) -> f64 {
+        self.parameter(SPEED, "rate").unwrap_or(1.0) as f64
+    }
+
+    // Degrees per second, counterclockwise on screen. 0 when the mod is off
+    pub fn spin(&self) -> f32 {
+        self.parameter(SPIN, "speed").unwrap_or(0.0)
+    }
 
-#[derive(Component)]
-pub struct Mods {
-    no_fail: bool,
+    // Enabled mods and their parameters in definition order, e.g. "No fail, Speed 1.5x"
+    pub fn summary(&self) -> String {
+        MOD_DEFINITIONS
+            .iter()
+            .filter(|definition| self.is_enabled(definition.id))
+            .map(|definition| {
+                let mut label = definition.name.to_string();
+                for parameter in definition.parameters {
+                    if let Some(value) = self.parameter(definition.id, parameter.id) {
+                        label.push_str(&format!(" {value}{}", parameter.suffix));
+                    }
+                }
+                label
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
 }
@@ -0,0 +1,131 @@
+use bevy::prelude::*;
+
+use crate::{
+    maps::{CurrentMap, Map, objects::Note},
+    player::{
+        playback::PlaybackClock,
+        playfield::{APPROACH_DISTANCE, APPROACH_SPEED, Playfield, grid_to_world, time_to_depth},
+    },
+    settings::Settings,
+    theme::Theme,
+};
+
+const ARC_SEGMENTS: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum NotePathStyle {
+    #[default]
+    Line,
+    Arc,
+}
+
+#[derive(Resource, Debug)]
+pub struct NotePathSettings {
+    pub enabled: bool,
+    pub style: NotePathStyle,
+    // Consecutive notes further apart than this are not connected
+    pub max_gap_ms: u32,
+    pub opacity: f32,
+    // Exponent applied to the distance from the grid, higher values fade paths out sooner
+    pub opacity_falloff: f32,
+}
+
+impl Default for NotePathSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            style: NotePathStyle::Line,
+            max_gap_ms: 1000,
+            opacity: 0.8,
+            opacity_falloff: 1.5,
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn draw_note_path(
+    mut gizmos: Gizmos,
+    settings: Res<NotePathSettings>,
+    clock: Res<PlaybackClock>,
+    app_settings: Res<Settings>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+    playfield: Query<&GlobalTransform, With<Playfield>>,
+    theme: Res<Theme>,
+) {
+    if !settings.enabled {
+        return;
+    }
+
+    let (Some(current_map), Ok(playfield)) = (current_map, playfield.single()) else {
+        return;
+    };
+
+    let Some(map) = maps.get(&current_map.0) else {
+        return;
+    };
+
+    // Follows the same scroll positions as the notes so the path stays attached to them
+    let scroll = map.scroll_timeline();
+    let millisecond = clock.visual_ms(&app_settings);
+    let now = scroll.position(millisecond);
+    let window = (APPROACH_DISTANCE / APPROACH_SPEED * 1000.0) as f64;
+
+    let start = map
+        .notes
+        .partition_point(|n| (n.millisecond as f64) < millisecond);
+    let end = map
+        .notes
+        .partition_point(|n| scroll.position(n.millisecond as f64) < now + window);
+
+    let visible = &map.notes[start..end.min(map.notes.len())];
+
+    for pair in visible.windows(2) {
+        let (from, to) = (&pair[0], &pair[1]);
+        let gap = to.millisecond - from.millisecond;
+
+        if gap > settings.max_gap_ms {
+            continue;
+        }
+
+        let ahead = ((scroll.position(from.millisecond as f64) - now) / window) as f32;
+        let alpha = settings.opacity * (1.0 - ahead).powf(settings.opacity_falloff);
+
+        let color = theme
+            .short_gap
+            .color()
+            .mix(
+                &theme.long_gap.color(),
+                gap as f32 / settings.max_gap_ms as f32,
+            )
+            .with_alpha(alpha);
+
+        let point = |note: &Note| {
+            let mut position = grid_to_world(note.position);
+            position.z = time_to_depth((scroll.position(note.millisecond as f64) - now) as f32);
+            playfield.transform_point(position)
+        };
+
+        let (a, b) = (point(from), point(to));
+
+        match settings.style {
+            NotePathStyle::Line => {
+                gizmos.line(a, b, color);
+            }
+            NotePathStyle::Arc => {
+                // Bend the path sideways in the grid plane as a quadratic curve
+                let along = b - a;
+                let side = Vec3::new(-along.y, along.x, 0.0).normalize_or_zero();
+                let control = a.midpoint(b) + side * along.truncate().length() * 0.25;
+
+                gizmos.linestrip(
+                    (0..=ARC_SEGMENTS).map(|i| {
+                        let t = i as f32 / ARC_SEGMENTS as f32;
+                        a.lerp(control, t).lerp(control.lerp(b, t), t)
+                    }),
+                    color,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,472 @@
+use std::{collections::VecDeque, ops::Range};
+
+use bevy::prelude::*;
+
+use crate::{
+    maps::{
+        CurrentMap, Map,
+        objects::{Note, ScrollTimeline},
+    },
+    modchart::{ModChannel, ModState},
+    player::{
+        playback::PlaybackClock,
+        playfield::{APPROACH_DISTANCE, APPROACH_SPEED, Playfield, grid_to_world, time_to_depth},
+    },
+    settings::Settings,
+    theme::{Theme, approach::ApproachOverride, shape::NoteShape},
+};
+
+// Enough for the densest sections of regular maps, the pool grows if a map needs more
+const INITIAL_POOL_SIZE: usize = 1024;
+
+// Fading notes share materials too, one per step of opacity
+const FADE_LEVELS: usize = 8;
+
+#[derive(Component)]
+pub struct NoteVisual;
+
+#[derive(Resource)]
+pub struct NoteAssets {
+    // One per shape, in the order of NoteShape::ALL
+    meshes: Vec<Handle<Mesh>>,
+    // One set of fade levels per color of the theme's note palette, the last level is opaque
+    materials: Vec<Vec<Handle<StandardMaterial>>>,
+}
+
+impl NoteAssets {
+    // Colors follow the note's index in the map, so they don't shift as notes scroll past,
+    // unless the note was given a color of its own
+    fn material(&self, note: &Note, index: usize, alpha: f32) -> Handle<StandardMaterial> {
+        let index = note.color.map_or(index, usize::from);
+        let level = (alpha.clamp(0.0, 1.0) * (FADE_LEVELS - 1) as f32).round() as usize;
+        self.materials[index % self.materials.len()][level].clone()
+    }
+
+    fn mesh(&self, shape: NoteShape) -> Handle<Mesh> {
+        self.meshes[shape.index()].clone()
+    }
+}
+
+// How much bigger a note is `beats_until` beats before its time. Each pulse starts as a beat
+// passes and dies down before the next one
+pub fn beat_pulse(amount: f32, beats_until: f64) -> f32 {
+    1.0 + amount * (beats_until.rem_euclid(1.0) as f32).powi(3)
+}
+
+// Size and shape mods on top of the theme's notes, the same for every note except the pulse
+#[derive(Clone, Copy, Debug)]
+pub struct NoteLook {
+    pub shape: NoteShape,
+    pub scale: Vec3,
+    pub pulse: f32,
+}
+
+impl NoteLook {
+    pub fn new(theme: &Theme, mods: &ModState) -> Self {
+        Self {
+            shape: mods
+                .active(ModChannel::NoteShape)
+                .and_then(|value| NoteShape::from_value(value.x))
+                .unwrap_or(theme.note_shape),
+            scale: mods.get(ModChannel::NoteScale),
+            pulse: mods
+                .active(ModChannel::NotePulse)
+                .map_or(0.0, |value| value.x),
+        }
+    }
+
+    // Without timing points there are no beats to pulse on
+    pub fn size(&self, note: &Note, map: Option<&Map>, millisecond: f64) -> Vec3 {
+        let beats_until = map
+            .filter(|_| self.pulse != 0.0)
+            .and_then(|map| map.timing_point_at(note.millisecond))
+            .map(|timing| (note.millisecond as f64 - millisecond) / timing.beat_length());
+
+        match beats_until {
+            Some(beats) => self.scale * beat_pulse(self.pulse, beats),
+            None => self.scale,
+        }
+    }
+}
+
+// Note entities are spawned once and recycled, so dense sections don't cause archetype moves
+// and allocations every frame. Turning `pooled` off spawns and despawns notes as they enter and
+// leave the approach window instead, to compare frame times against.
+#[derive(Resource)]
+pub struct NotePool {
+    pub pooled: bool,
+    entities: Vec<Entity>,
+    active: usize,
+    pub grown: u32,
+    spawned: VecDeque<Entity>,
+    spawned_range: Range<usize>,
+}
+
+impl Default for NotePool {
+    fn default() -> Self {
+        Self {
+            pooled: true,
+            entities: Vec::new(),
+            active: 0,
+            grown: 0,
+            spawned: VecDeque::new(),
+            spawned_range: 0..0,
+        }
+    }
+}
+
+impl NotePool {
+    pub fn capacity(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn active(&self) -> usize {
+        match self.pooled {
+            true => self.active,
+            false => self.spawned.len(),
+        }
+    }
+}
+
+fn note_bundle(
+    mesh: Handle<Mesh>,
+    playfield: Entity,
+    material: Handle<StandardMaterial>,
+    transform: Transform,
+    visibility: Visibility,
+) -> impl Bundle {
+    (
+        NoteVisual,
+        ChildOf(playfield),
+        Mesh3d(mesh),
+        MeshMaterial3d(material),
+        transform,
+        visibility,
+    )
+}
+
+// Depth follows the scroll position rather than the time, so speed changes space notes out.
+// Returns the opacity alongside, which picks the note's material
+fn note_transform(
+    note: &Note,
+    scroll: &ScrollTimeline,
+    now: f64,
+    approach: &ApproachOverride,
+    size: Vec3,
+) -> (Transform, f32) {
+    let depth = time_to_depth((scroll.position(note.millisecond as f64) - now) as f32);
+    let pose = approach.pose(note.position, 1.0 + depth / APPROACH_DISTANCE);
+
+    let transform =
+        Transform::from_translation(grid_to_world(note.position + pose.offset) + Vec3::Z * depth)
+            .with_scale(Vec3::splat(pose.scale) * size);
+
+    (transform, pose.alpha)
+}
+
+pub fn spawn_note_pool(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut pool: ResMut<NotePool>,
+    playfield: Query<Entity, With<Playfield>>,
+    theme: Res<Theme>,
+) {
+    let assets = NoteAssets {
+        meshes: NoteShape::ALL
+            .iter()
+            .map(|shape| meshes.add(shape.mesh()))
+            .collect(),
+        materials: (0..theme.notes.len().max(1))
+            .map(|index| fade_materials(&mut materials, theme.note_color(index)))
+            .collect(),
+    };
+
+    let Ok(playfield) = playfield.single() else {
+        return;
+    };
+
+    grow_pool(
+        &mut commands,
+        &assets,
+        &mut pool,
+        playfield,
+        INITIAL_POOL_SIZE,
+    );
+    commands.insert_resource(assets);
+}
+
+fn note_material(color: Color, level: usize) -> StandardMaterial {
+    let alpha = level as f32 / (FADE_LEVELS - 1) as f32;
+
+    StandardMaterial {
+        base_color: color.with_alpha(alpha),
+        alpha_mode: match level == FADE_LEVELS - 1 {
+            true => AlphaMode::Opaque,
+            false => AlphaMode::Blend,
+        },
+        unlit: true,
+        ..default()
+    }
+}
+
+fn fade_materials(
+    materials: &mut Assets<StandardMaterial>,
+    color: Color,
+) -> Vec<Handle<StandardMaterial>> {
+    (0..FADE_LEVELS)
+        .map(|level| materials.add(note_material(color, level)))
+        .collect()
+}
+
+// Notes share one material per palette color, so a theme change only has to touch those
+pub fn apply_note_theme(
+    assets: Option<ResMut<NoteAssets>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    theme: Res<Theme>,
+) {
+    let Some(mut assets) = assets else {
+        return;
+    };
+
+    let count = theme.notes.len().max(1);
+    assets.materials.truncate(count);
+
+    for (index, levels) in assets.materials.iter().enumerate() {
+        for handle in levels.iter() {
+            if let Some(material) = materials.get_mut(handle) {
+                let alpha = material.base_color.alpha();
+                material.base_color = theme.note_color(index).with_alpha(alpha);
+            }
+        }
+    }
+
+    // Notes pick up the new materials the next time they are rendered
+    for index in assets.materials.len()..count {
+        let levels = fade_materials(&mut materials, theme.note_color(index));
+        assets.materials.push(levels);
+    }
+}
+
+fn grow_pool(
+    commands: &mut Commands,
+    assets: &NoteAssets,
+    pool: &mut NotePool,
+    playfield: Entity,
+    count: usize,
+) {
+    for _ in 0..count {
+        let entity = commands
+            .spawn(note_bundle(
+                assets.mesh(NoteShape::default()),
+                playfield,
+                assets.materials[0][FADE_LEVELS - 1].clone(),
+                Transform::default(),
+                Visibility::Hidden,
+            ))
+            .id();
+        pool.entities.push(entity);
+    }
+}
+
+// Notes from the playhead up to `window` ahead of it in scroll position. Scroll positions never
+// decrease, so the visible notes are still a single range
+pub fn visible_range(
+    notes: &[Note],
+    scroll: &ScrollTimeline,
+    millisecond: f64,
+    window: f64,
+) -> Range<usize> {
+    let now = scroll.position(millisecond);
+    let start = notes.partition_point(|n| (n.millisecond as f64) < millisecond);
+    let end = notes.partition_point(|n| scroll.position(n.millisecond as f64) < now + window);
+    start..end.max(start)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn render_notes(
+    mut commands: Commands,
+    mut pool: ResMut<NotePool>,
+    assets: Option<Res<NoteAssets>>,
+    clock: Res<PlaybackClock>,
+    settings: Res<Settings>,
+    theme: Res<Theme>,
+    mods: Res<ModState>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+    playfield: Query<Entity, With<Playfield>>,
+    mut visuals: Query<
+        (
+            &mut Transform,
+            &mut Visibility,
+            &mut Mesh3d,
+            &mut MeshMaterial3d<StandardMaterial>,
+        ),
+        With<NoteVisual>,
+    >,
+) {
+    let (Some(assets), Ok(playfield)) = (assets, playfield.single()) else {
+        return;
+    };
+
+    let map = current_map.and_then(|current| maps.get(&current.0));
+    let notes = map.map_or(&[][..], |map| &map.notes[..]);
+    let scroll = map.map(Map::scroll_timeline).unwrap_or_default();
+
+    let millisecond = clock.visual_ms(&settings);
+    let now = scroll.position(millisecond);
+    let window = (APPROACH_DISTANCE / APPROACH_SPEED * 1000.0) as f64;
+    let approach = theme.approach.with_mods(&mods);
+    let look = NoteLook::new(&theme, &mods);
+    let mesh = assets.mesh(look.shape);
+
+    let Range { start, end } = visible_range(notes, &scroll, millisecond, window);
+
+    match pool.pooled {
+        true => {
+            let pool = pool.as_mut();
+            release_spawned(&mut commands, pool);
+
+            let visible = &notes[start..end];
+
+            if visible.len() > pool.capacity() {
+                // Pooled entities only exist next frame, the overflow is skipped until then
+                let count = (visible.len() - pool.capacity()).max(pool.capacity());
+                grow_pool(&mut commands, &assets, pool, playfield, count);
+                pool.grown += 1;
+            }
+
+            for (index, entity) in pool.entities.iter().enumerate() {
+                let Ok((mut transform, mut visibility, mut note_mesh, mut material)) =
+                    visuals.get_mut(*entity)
+                else {
+                    continue;
+                };
+
+                if let Some(note) = visible.get(index) {
+                    let size = look.size(note, map, millisecond);
+                    let (next, alpha) = note_transform(note, &scroll, now, &approach, size);
+                    *transform = next;
+                    visibility.set_if_neq(Visibility::Inherited);
+                    note_mesh.set_if_neq(Mesh3d(mesh.clone()));
+                    material.set_if_neq(MeshMaterial3d(assets.material(
+                        note,
+                        start + index,
+                        alpha,
+                    )));
+                } else if index < pool.active {
+                    *visibility = Visibility::Hidden;
+                } else {
+                    break;
+                }
+            }
+
+            pool.active = visible.len().min(pool.capacity());
+        }
+        false => {
+            let pool = pool.as_mut();
+
+            for entity in pool.entities.iter().take(pool.active) {
+                if let Ok((_, mut visibility, _, _)) = visuals.get_mut(*entity) {
+                    *visibility = Visibility::Hidden;
+                }
+            }
+            pool.active = 0;
+
+            // Keep the notes still inside the window, spawn the ones that entered it
+            while pool.spawned_range.start < start.min(pool.spawned_range.end) {
+                commands.entity(pool.spawned.pop_front().unwrap()).despawn();
+                pool.spawned_range.start += 1;
+            }
+            while pool.spawned_range.end > end.max(pool.spawned_range.start) {
+                commands.entity(pool.spawned.pop_back().unwrap()).despawn();
+                pool.spawned_range.end -= 1;
+            }
+            if pool.spawned.is_empty() {
+                pool.spawned_range = start..start;
+            }
+
+            for index in (start..pool.spawned_range.start).rev() {
+                let entity = spawn_note(
+                    &mut commands,
+                    &assets,
+                    playfield,
+                    notes,
+                    index,
+                    &scroll,
+                    now,
+                    &approach,
+                    look.size(&notes[index], map, millisecond),
+                    mesh.clone(),
+                );
+                pool.spawned.push_front(entity);
+            }
+            for index in pool.spawned_range.end..end {
+                let entity = spawn_note(
+                    &mut commands,
+                    &assets,
+                    playfield,
+                    notes,
+                    index,
+                    &scroll,
+                    now,
+                    &approach,
+                    look.size(&notes[index], map, millisecond),
+                    mesh.clone(),
+                );
+                pool.spawned.push_back(entity);
+            }
+            pool.spawned_range = start..end;
+
+            for (entity, index) in pool.spawned.iter().zip(pool.spawned_range.clone()) {
+                if let Ok((mut transform, _, mut note_mesh, mut material)) =
+                    visuals.get_mut(*entity)
+                {
+                    let size = look.size(&notes[index], map, millisecond);
+                    let (next, alpha) =
+                        note_transform(&notes[index], &scroll, now, &approach, size);
+                    *transform = next;
+                    note_mesh.set_if_neq(Mesh3d(mesh.clone()));
+                    material.set_if_neq(MeshMaterial3d(assets.material(
+                        &notes[index],
+                        index,
+                        alpha,
+                    )));
+                }
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_note(
+    commands: &mut Commands,
+    assets: &NoteAssets,
+    playfield: Entity,
+    notes: &[Note],
+    index: usize,
+    scroll: &ScrollTimeline,
+    now: f64,
+    approach: &ApproachOverride,
+    size: Vec3,
+    mesh: Handle<Mesh>,
+) -> Entity {
+    let (transform, alpha) = note_transform(&notes[index], scroll, now, approach, size);
+
+    commands
+        .spawn(note_bundle(
+            mesh,
+            playfield,
+            assets.material(&notes[index], index, alpha),
+            transform,
+            Visibility::Inherited,
+        ))
+        .id()
+}
+
+fn release_spawned(commands: &mut Commands, pool: &mut NotePool) {
+    for entity in pool.spawned.drain(..) {
+        commands.entity(entity).despawn();
+    }
+    pool.spawned_range = 0..0;
+}
@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+
+use crate::{
+    jukebox::DecodedAudio,
+    maps::{CurrentMap, Map},
+    player::SimulationState,
+    settings::Settings,
+};
+
+// Objects are still visible and judged for a moment after their time, so the map ends a bit later
+const END_PADDING_MS: f64 = 1000.0;
+
+// Sent once when playback reaches the end of the map, playback is paused at that point
+#[derive(Event, Clone, Copy, Debug)]
+pub struct MapFinished;
+
+#[derive(Resource, Debug)]
+pub struct PlaybackClock {
+    pub millisecond: f64,
+    pub rate: f64,
+    // Counts seeks, so audio following the clock knows when to jump
+    pub seeks: u32,
+}
+
+impl Default for PlaybackClock {
+    fn default() -> Self {
+        Self {
+            millisecond: 0.0,
+            rate: 1.0,
+            seeks: 0,
+        }
+    }
+}
+
+impl PlaybackClock {
+    // Where anything drawn should be, ahead of the clock by the visual offset
+    pub fn visual_ms(&self, settings: &Settings) -> f64 {
+        self.millisecond + settings.visual_offset_ms as f64
+    }
+
+    // Where hits are judged. The song plays ahead of the clock by the audio offset, and a hit
+    // reaches the game late by the display and input latency the visual offset makes up for
+    pub fn judged_ms(&self, settings: &Settings) -> f64 {
+        self.millisecond + settings.audio_offset_ms as f64 - settings.visual_offset_ms as f64
+    }
+
+    pub fn seek(&mut self, millisecond: f64) {
+        self.millisecond = millisecond.max(0.0);
+        self.seeks = self.seeks.wrapping_add(1);
+    }
+}
+
+pub fn advance_clock(time: Res<Time>, mut clock: ResMut<PlaybackClock>) {
+    clock.millisecond += time.delta_secs_f64() * 1000.0 * clock.rate;
+}
+
+// The map lasts until its last object has passed or its audio has played out, whichever is later
+pub fn map_end_ms(map: &Map, audio: &DecodedAudio) -> f64 {
+    let objects = map.last_object_ms() as f64 + END_PADDING_MS;
+    let audio = audio.duration_ms().unwrap_or(0) as f64;
+    objects.max(audio)
+}
+
+pub fn detect_map_end(
+    mut clock: ResMut<PlaybackClock>,
+    mut next_simulation: ResMut<NextState<SimulationState>>,
+    mut finished: EventWriter<MapFinished>,
+    current_map: Option<Res<CurrentMap>>,
+    maps: Res<Assets<Map>>,
+    audio: Res<DecodedAudio>,
+) {
+    // Until the audio is decoded its length is unknown
+    let Some(map) = current_map
+        .and_then(|m| maps.get(&m.0))
+        .filter(|_| !audio.is_decoding())
+    else {
+        return;
+    };
+
+    let end = map_end_ms(map, &audio);
+    if clock.millisecond < end {
+        return;
+    }
+
+    // Set directly rather than seeking, the audio has already stopped on its own
+    clock.millisecond = end;
+    next_simulation.set(SimulationState::Paused);
+    finished.write(MapFinished);
+}
@@ -0,0 +1,88 @@
+use bevy::prelude::*;
+
+use crate::theme::Theme;
+
+// Notes sit on a 3x3 grid with cell centers at 0, 1 and 2 on both axes
+pub const GRID_CELLS: u32 = 3;
+pub const CELL_SIZE: f32 = 1.0;
+// Side of a note, in cells
+pub const NOTE_SIZE: f32 = 0.85;
+
+// World units a note travels towards the grid per second
+pub const APPROACH_SPEED: f32 = 10.0;
+pub const APPROACH_DISTANCE: f32 = 50.0;
+
+// Root of everything drawn relative to the grid, moved around by playfield mods
+#[derive(Component)]
+pub struct Playfield;
+
+// Converts a grid position (y pointing down) into world space on the z = 0 plane
+pub fn grid_to_world(position: Vec2) -> Vec3 {
+    Vec3::new(
+        (position.x - 1.0) * CELL_SIZE,
+        (1.0 - position.y) * CELL_SIZE,
+        0.0,
+    )
+}
+
+pub fn world_to_grid(position: Vec3) -> Vec2 {
+    Vec2::new(position.x / CELL_SIZE + 1.0, 1.0 - position.y / CELL_SIZE)
+}
+
+// Depth of an object that is `delta_ms` milliseconds away from being hit
+pub fn time_to_depth(delta_ms: f32) -> f32 {
+    -delta_ms / 1000.0 * APPROACH_SPEED
+}
+
+pub fn spawn_playfield(mut commands: Commands) {
+    commands.spawn((Playfield, Transform::default(), Visibility::default()));
+}
+
+pub fn draw_grid(
+    mut gizmos: Gizmos,
+    playfield: Query<&GlobalTransform, With<Playfield>>,
+    theme: Res<Theme>,
+) {
+    let Ok(playfield) = playfield.single() else {
+        return;
+    };
+
+    let mut line = |start: Vec3, end: Vec3, color: Color| {
+        gizmos.line(
+            playfield.transform_point(start),
+            playfield.transform_point(end),
+            color,
+        );
+    };
+
+    let half = GRID_CELLS as f32 * CELL_SIZE / 2.0;
+
+    for i in 0..=GRID_CELLS {
+        let offset = -half + i as f32 * CELL_SIZE;
+
+        line(
+            Vec3::new(offset, -half, 0.0),
+            Vec3::new(offset, half, 0.0),
+            theme.grid.color(),
+        );
+        line(
+            Vec3::new(-half, offset, 0.0),
+            Vec3::new(half, offset, 0.0),
+            theme.grid.color(),
+        );
+    }
+
+    // Outline of the approach lane so the top-down view has something to read against
+    for corner in [
+        Vec2::new(-half, -half),
+        Vec2::new(half, -half),
+        Vec2::new(half, half),
+        Vec2::new(-half, half),
+    ] {
+        line(
+            corner.extend(0.0),
+            corner.extend(-APPROACH_DISTANCE),
+            theme.grid.color().with_alpha(0.3),
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use bevy::{
+    prelude::*,
+    window::{MonitorSelection, PresentMode, PrimaryWindow, VideoModeSelection, WindowMode},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+
+// Sleeping is only accurate to a millisecond or so, the rest of the wait is spun out
+const SPIN_TIME: Duration = Duration::from_micros(1500);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisplayMode {
+    #[default]
+    Windowed,
+    Borderless,
+    // Exclusive, keeps the monitor's current video mode
+    Fullscreen,
+}
+
+impl DisplayMode {
+    pub const ALL: [DisplayMode; 3] = [
+        DisplayMode::Windowed,
+        DisplayMode::Borderless,
+        DisplayMode::Fullscreen,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DisplayMode::Windowed => "Windowed",
+            DisplayMode::Borderless => "Borderless fullscreen",
+            DisplayMode::Fullscreen => "Fullscreen",
+        }
+    }
+
+    fn window_mode(&self) -> WindowMode {
+        match self {
+            DisplayMode::Windowed => WindowMode::Windowed,
+            DisplayMode::Borderless => WindowMode::BorderlessFullscreen(MonitorSelection::Current),
+            DisplayMode::Fullscreen => {
+                WindowMode::Fullscreen(MonitorSelection::Current, VideoModeSelection::Current)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplaySettings {
+    pub mode: DisplayMode,
+    // Logical size of the window while windowed
+    pub width: u32,
+    pub height: u32,
+    // Off trades tearing for a frame or more less latency
+    pub vsync: bool,
+    // Frames per second the app is held to, on top of vsync
+    pub frame_cap: Option<u32>,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        Self {
+            mode: DisplayMode::Windowed,
+            width: 1280,
+            height: 720,
+            vsync: true,
+            frame_cap: None,
+        }
+    }
+}
+
+impl DisplaySettings {
+    fn present_mode(&self) -> PresentMode {
+        match self.vsync {
+            true => PresentMode::AutoVsync,
+            false => PresentMode::AutoNoVsync,
+        }
+    }
+}
+
+// Runs on the first frame too, so the window starts out how it was left
+pub fn apply_display_settings(
+    settings: Res<Settings>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut applied: Local<Option<DisplaySettings>>,
+) {
+    if applied.as_ref() == Some(&settings.display) {
+        return;
+    }
+
+    let Ok(mut window) = windows.single_mut() else {
+        return;
+    };
+
+    let display = &settings.display;
+    window.mode = display.mode.window_mode();
+    window.present_mode = display.present_mode();
+
+    // Only touched when it changed, so resizing the window by hand isn't undone by other edits
+    let resized = applied
+        .as_ref()
+        .is_none_or(|a| (a.width, a.height) != (display.width, display.height));
+    if display.mode == DisplayMode::Windowed && resized {
+        window
+            .resolution
+            .set(display.width.max(1) as f32, display.height.max(1) as f32);
+    }
+
+    *applied = Some(display.clone());
+}
+
+#[derive(Resource, Default)]
+pub struct FrameLimiter {
+    last: Option<Instant>,
+}
+
+// Waits out the rest of the frame at the end of the schedule, so input is read as late as it
+// can be before the next one
+pub fn limit_frame_rate(settings: Res<Settings>, mut limiter: ResMut<FrameLimiter>) {
+    let Some(cap) = settings.display.frame_cap.filter(|cap| *cap > 0) else {
+        limiter.last = None;
+        return;
+    };
+
+    let frame = Duration::from_secs_f64(1.0 / cap as f64);
+
+    if let Some(deadline) = limiter.last.map(|last| last + frame) {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining > SPIN_TIME {
+            thread::sleep(remaining - SPIN_TIME);
+        }
+        while Instant::now() < deadline {
+            std::hint::spin_loop();
+        }
+    }
+
+    limiter.last = Some(Instant::now());
+}
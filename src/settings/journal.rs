@@ -0,0 +1,183 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    maps::{Map, format_time},
+    settings::config_dir,
+};
+
+const JOURNAL_FILE: &str = "journal.json";
+
+// Oldest entries fall off once there are this many, a few years of daily sessions
+const MAX_ENTRIES: usize = 2000;
+
+const WEEK_SECS: u64 = 7 * 24 * 60 * 60;
+
+// Without bookmarks to name them, retries are counted per this much of the map
+const SECTION_MS: u32 = 30_000;
+
+// What happened with one map while it was open, from opening it until another one was
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JournalEntry {
+    // Seconds since the unix epoch
+    pub started: u64,
+    pub map_id: String,
+    pub title: String,
+    pub notes_placed: u32,
+    pub notes_removed: u32,
+    pub editing_secs: f64,
+    pub playing_secs: f64,
+    pub plays: u32,
+    pub clears: u32,
+    // Restarts by the section of the map they were made in, see section_at
+    pub retries: BTreeMap<String, u32>,
+}
+
+impl JournalEntry {
+    pub fn new(map: &Map, started: u64) -> Self {
+        Self {
+            started,
+            map_id: map.id.clone(),
+            title: map.title.clone(),
+            ..default()
+        }
+    }
+
+    pub fn total_retries(&self) -> u32 {
+        self.retries.values().sum()
+    }
+
+    // Adds up the counts of both, the first entry's map and start are kept
+    pub fn add(&mut self, other: &JournalEntry) {
+        self.notes_placed += other.notes_placed;
+        self.notes_removed += other.notes_removed;
+        self.editing_secs += other.editing_secs;
+        self.playing_secs += other.playing_secs;
+        self.plays += other.plays;
+        self.clears += other.clears;
+        for (section, count) in other.retries.iter() {
+            *self.retries.entry(section.clone()).or_default() += count;
+        }
+    }
+}
+
+#[derive(Resource, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Journal {
+    // Oldest first
+    pub entries: Vec<JournalEntry>,
+}
+
+impl Journal {
+    pub fn path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join(JOURNAL_FILE))
+    }
+
+    // Like the settings, a broken journal starts over instead of keeping the app from starting
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                warn!(
+                    "Could not parse {}: {e}, starting a new journal",
+                    path.display()
+                );
+                Self::default()
+            }),
+            Err(e) if e.kind() == ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                warn!("Could not read {}: {e}", path.display());
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = Self::path() else {
+            return Err(io::Error::new(
+                ErrorKind::NotFound,
+                "No configuration directory available",
+            ));
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        self.save_to(&path)
+    }
+
+    // Written next to the journal first, quitting halfway through would otherwise lose all of it
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        let temporary = path.with_extension("json.tmp");
+        fs::write(&temporary, serde_json::to_string(self)?)?;
+        fs::rename(&temporary, path)
+    }
+
+    pub fn push(&mut self, entry: JournalEntry) {
+        self.entries.push(entry);
+        let extra = self.entries.len().saturating_sub(MAX_ENTRIES);
+        self.entries.drain(..extra);
+    }
+
+    // Totals per week counting back from `now`, this week first. Weeks without entries are left out
+    pub fn weeks(&self, now: u64) -> Vec<(u64, JournalEntry)> {
+        let mut weeks: BTreeMap<u64, JournalEntry> = BTreeMap::new();
+        for entry in self.entries.iter() {
+            let ago = now.saturating_sub(entry.started) / WEEK_SECS;
+            weeks.entry(ago).or_default().add(entry);
+        }
+        weeks.into_iter().collect()
+    }
+
+    // Sections of a map by how often they were retried over every time it was played, most first
+    pub fn retries_for(&self, map_id: &str) -> Vec<(String, u32)> {
+        let mut total = JournalEntry::default();
+        for entry in self.entries.iter().filter(|e| e.map_id == map_id) {
+            total.add(entry);
+        }
+
+        let mut retries: Vec<(String, u32)> = total.retries.into_iter().collect();
+        retries.sort_by_key(|r| std::cmp::Reverse(r.1));
+        retries
+    }
+}
+
+// Bookmarks name the sections of a map. Before the first one and in maps without any, sections
+// are fixed 30 second windows
+pub fn section_at(map: &Map, millisecond: f64) -> String {
+    let millisecond = millisecond.max(0.0) as u32;
+    let bookmark = map
+        .bookmarks
+        .iter()
+        .filter(|b| b.millisecond <= millisecond)
+        .max_by_key(|b| b.millisecond);
+
+    match bookmark {
+        Some(bookmark) if !bookmark.label.trim().is_empty() => bookmark.label.trim().to_string(),
+        Some(bookmark) => format!("From {}", format_time(bookmark.millisecond)),
+        None => {
+            let start = millisecond / SECTION_MS * SECTION_MS;
+            format!("{}-{}", format_time(start), format_time(start + SECTION_MS))
+        }
+    }
+}
+
+pub fn format_duration(secs: f64) -> String {
+    let minutes = (secs.max(0.0) / 60.0) as u64;
+    match minutes {
+        0..60 => format!("{minutes}m"),
+        _ => format!("{}h {:02}m", minutes / 60, minutes % 60),
+    }
+}
@@ -0,0 +1,266 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, ErrorKind},
+    path::PathBuf,
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub mod display;
+pub mod journal;
+pub mod recent;
+pub mod recovery;
+pub mod sha256;
+pub mod updates;
+
+use display::{DisplaySettings, FrameLimiter};
+use recent::RecentFile;
+use recovery::SafeMode;
+use updates::UpdateSettings;
+
+use crate::{
+    editor::{commands::EditCommand, shortcuts::Keybinds},
+    gameplay::{
+        InputDevice, cursor::CursorSettings, effects::EffectSettings, feedback::FeedbackSettings,
+        ruleset::Ruleset,
+    },
+    jukebox::stretch::PitchMode,
+    maps::{
+        hooks::ExportHook,
+        io::{DEFAULT_GRID_EPSILON, ReadOptions, WriteOptions},
+        limit::SizeLimit,
+    },
+    modchart::comfort::ComfortSettings,
+    player::{beat_lines::BeatLineSettings, mods::Mods},
+    theme::palette::AccessibilitySettings,
+};
+
+const APP_FOLDER: &str = "mm-modchart-maker";
+const SETTINGS_FILE: &str = "settings.json";
+
+#[derive(Resource, Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Settings {
+    pub keybinds: Keybinds,
+    pub macros: BTreeMap<String, Vec<EditCommand>>,
+    pub export: ExportSettings,
+    // Folders scanned into the library and watched for added, changed and removed maps
+    pub map_folders: Vec<String>,
+    // Name of a file in the themes folder, "dark" and "light" also work without one
+    pub theme: String,
+    pub accessibility: AccessibilitySettings,
+    pub beat_lines: BeatLineSettings,
+    // Hit particles and the cursor trail while playing
+    pub effects: EffectSettings,
+    // Camera punches, miss flashes and combo callouts driven by judgements
+    pub feedback: FeedbackSettings,
+    // Eases spinning charts for players who get motion sick
+    pub comfort: ComfortSettings,
+    pub ruleset: Ruleset,
+    // Applied to the next session that is started
+    pub mods: Mods,
+    // Mod randomness is rolled again for every session instead of following the map
+    pub reroll_random: bool,
+    // How the audio sounds when a speed mod changes the rate
+    pub pitch_mode: PitchMode,
+    // Audio and hitsounds play this much earlier, to make up for output latency
+    pub audio_offset_ms: f32,
+    // Output device by name, the system default is used when it's None or not connected
+    pub audio_device: Option<String>,
+    // Notes, beat lines and mods are drawn this much ahead, to make up for display and input
+    // latency. Measured separately from the audio offset with the latency test
+    pub visual_offset_ms: f32,
+    pub input: InputDevice,
+    pub cursor: CursorSettings,
+    // Text in old maps that isn't valid UTF-8 is replaced instead of failing the load
+    pub lossy_strings: bool,
+    // Notes closer in time than this are merged into one, see maps::merge
+    pub merge_epsilon_ms: u32,
+    // Merges them as maps are opened, not only on request in the editor
+    pub merge_on_open: bool,
+    pub display: DisplaySettings,
+    // Newest first, shown on the start screen
+    pub recent: Vec<RecentFile>,
+    pub updates: UpdateSettings,
+    // Set when there was no settings file to load, never written to one
+    #[serde(skip)]
+    pub first_run: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            keybinds: Keybinds::default(),
+            macros: BTreeMap::new(),
+            export: ExportSettings::default(),
+            map_folders: Vec::new(),
+            theme: "dark".to_string(),
+            accessibility: AccessibilitySettings::default(),
+            beat_lines: BeatLineSettings::default(),
+            effects: EffectSettings::default(),
+            feedback: FeedbackSettings::default(),
+            comfort: ComfortSettings::default(),
+            ruleset: Ruleset::default(),
+            mods: Mods::default(),
+            reroll_random: false,
+            pitch_mode: PitchMode::default(),
+            audio_offset_ms: 0.0,
+            audio_device: None,
+            visual_offset_ms: 0.0,
+            input: InputDevice::default(),
+            cursor: CursorSettings::default(),
+            lossy_strings: true,
+            merge_epsilon_ms: 2,
+            merge_on_open: false,
+            display: DisplaySettings::default(),
+            recent: Vec::new(),
+            updates: UpdateSettings::default(),
+            first_run: false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ExportSettings {
+    pub folder: String,
+    // See maps::export::export_filename for the available placeholders
+    pub filename_template: String,
+    // Run on every exported file, see maps::hooks
+    pub hooks: Vec<ExportHook>,
+    // Positions closer than this to a whole cell are written as grid positions, see maps::io
+    pub grid_epsilon: f32,
+    // Largest file some games accept, see maps::limit
+    pub size_limit: SizeLimit,
+}
+
+impl ExportSettings {
+    pub fn write_options(&self) -> WriteOptions {
+        WriteOptions {
+            grid_epsilon: self.grid_epsilon.max(0.0),
+        }
+    }
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        Self {
+            folder: "exports".to_string(),
+            filename_template: "{artist} - {title} [{diff_name}]".to_string(),
+            hooks: Vec::new(),
+            grid_epsilon: DEFAULT_GRID_EPSILON,
+            size_limit: SizeLimit::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join(SETTINGS_FILE))
+    }
+
+    pub fn read_options(&self) -> ReadOptions {
+        ReadOptions {
+            lossy_strings: self.lossy_strings,
+        }
+    }
+
+    // How far apart notes can be to be merged as the editor opens a map, zero when it doesn't
+    pub fn open_merge_epsilon_ms(&self) -> u32 {
+        match self.merge_on_open {
+            true => self.merge_epsilon_ms,
+            false => 0,
+        }
+    }
+
+    // Missing or broken settings never stop the app from starting, defaults are used instead
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+
+        let mut settings = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str::<Settings>(&contents).unwrap_or_else(|e| {
+                warn!("Could not parse {}: {e}, using defaults", path.display());
+                Self::default()
+            }),
+            Err(e) if e.kind() == ErrorKind::NotFound => Self {
+                first_run: true,
+                ..default()
+            },
+            Err(e) => {
+                warn!("Could not read {}: {e}, using defaults", path.display());
+                Self::default()
+            }
+        };
+
+        // Actions added after the file was written get their default binding
+        settings.keybinds.fill_missing();
+
+        for (chord, actions) in settings.keybinds.conflicts() {
+            warn!("Shortcut {chord} is bound to multiple actions: {actions:?}");
+        }
+
+        settings
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = Self::path() else {
+            return Err(io::Error::new(
+                ErrorKind::NotFound,
+                "No configuration directory available",
+            ));
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+
+    base.map(|dir| dir.join(APP_FOLDER))
+}
+
+pub struct SettingsPlugin {
+    pub safe_mode: SafeMode,
+}
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let settings = match self.safe_mode.active {
+            true => Settings::default(),
+            false => Settings::load(),
+        };
+
+        app.insert_resource(settings)
+            .insert_resource(self.safe_mode.clone())
+            .init_resource::<FrameLimiter>()
+            .add_systems(Update, display::apply_display_settings)
+            .add_systems(Last, (save_settings, display::limit_frame_rate).chain());
+    }
+}
+
+pub fn save_settings(settings: Res<Settings>, safe_mode: Res<SafeMode>) {
+    if !settings.is_changed() || settings.is_added() || safe_mode.active {
+        return;
+    }
+
+    if let Err(e) = settings.save() {
+        warn!("Could not save settings: {e}");
+    }
+}
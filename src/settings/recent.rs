@@ -0,0 +1,103 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    maps::cover::{self, CoverEncoding, CoverOptions},
+    modchart::random::fnv1a,
+    settings::config_dir,
+};
+
+// Older entries fall off the end of the list
+pub const MAX_RECENT: usize = 12;
+
+// Covers are only shown as small thumbnails on the start screen
+const COVER_SIZE: u32 = 128;
+const COVER_FOLDER: &str = "recent";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecentKind {
+    Project,
+    Map,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecentFile {
+    pub path: PathBuf,
+    pub kind: RecentKind,
+    pub title: String,
+    // Modification time of the file in seconds since the unix epoch, so saves made anywhere count
+    pub last_edited: u64,
+    // Where the playhead was when the editor last closed with this file open
+    pub playhead_ms: f64,
+}
+
+impl RecentFile {
+    pub fn new(path: PathBuf, kind: RecentKind, title: String) -> Self {
+        Self {
+            kind,
+            title,
+            last_edited: modified(&path).unwrap_or(0),
+            playhead_ms: 0.0,
+            path,
+        }
+    }
+}
+
+pub fn modified(path: &Path) -> Option<u64> {
+    let time = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    time.duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|age| age.as_secs())
+}
+
+// Moves the file to the front, keeping the playhead from the last time it was open
+pub fn remember(recent: &mut Vec<RecentFile>, mut file: RecentFile) {
+    if let Some(index) = recent.iter().position(|r| r.path == file.path) {
+        let old = recent.remove(index);
+        file.playhead_ms = old.playhead_ms;
+        if file.title.is_empty() {
+            file.title = old.title;
+        }
+    }
+
+    recent.insert(0, file);
+    recent.truncate(MAX_RECENT);
+}
+
+// One thumbnail per file in the config folder, named after a hash of its path that stays the
+// same between versions of the app
+pub fn cover_path(path: &Path) -> Option<PathBuf> {
+    let hash = fnv1a(path.to_string_lossy().as_bytes());
+    config_dir().map(|dir| dir.join(COVER_FOLDER).join(format!("{hash:016x}.png")))
+}
+
+// An empty cover removes the thumbnail so a stale one isn't shown
+pub fn save_cover(path: &Path, cover: &[u8]) -> io::Result<()> {
+    let Some(target) = cover_path(path) else {
+        return Ok(());
+    };
+
+    if cover.is_empty() {
+        return match fs::remove_file(target) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+
+    let options = CoverOptions {
+        crop: true,
+        max_size: COVER_SIZE,
+        encoding: CoverEncoding::Png,
+    };
+    let thumbnail = cover::process(cover, &options)?;
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(target, thumbnail)
+}
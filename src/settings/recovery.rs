@@ -0,0 +1,46 @@
+use std::{fs, io, path::PathBuf};
+
+use bevy::prelude::*;
+
+use crate::settings::config_dir;
+
+// Exists while the editor runs, finding it on startup means the last session never got to
+// remove it. A second instance started alongside the first sees it too, leaving safe mode is
+// one click.
+const LOCK_FILE: &str = "running.lock";
+
+// Started after a crash or with --safe-mode: default settings that are never saved over the
+// real ones, and no map folders are scanned until it is left
+#[derive(Resource, Default, Debug, Clone)]
+pub struct SafeMode {
+    pub active: bool,
+    // The lock was found, rather than safe mode being asked for
+    pub crashed: bool,
+}
+
+fn lock_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(LOCK_FILE))
+}
+
+// Returns whether the previous session left its lock behind
+pub fn acquire_lock() -> io::Result<bool> {
+    let Some(path) = lock_path() else {
+        return Ok(false);
+    };
+
+    let crashed = path.exists();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, std::process::id().to_string())?;
+    Ok(crashed)
+}
+
+pub fn release_lock() {
+    if let Some(path) = lock_path()
+        && let Err(e) = fs::remove_file(&path)
+        && e.kind() != io::ErrorKind::NotFound
+    {
+        warn!("Could not remove {}: {e}", path.display());
+    }
+}
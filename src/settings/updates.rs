@@ -0,0 +1,250 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    maps::export::sanitize_filename,
+    settings::{config_dir, sha256},
+};
+
+// Releases are looked up and downloaded with curl, which every supported system ships with, the
+// same way clips are encoded with ffmpeg instead of bundling an HTTP and TLS stack
+
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const DEFAULT_ENDPOINT: &str =
+    "https://api.github.com/repos/f4dedd/mm-modchart-maker/releases/latest";
+
+const CHECK_TIMEOUT_SECONDS: &str = "20";
+const DOWNLOAD_TIMEOUT_SECONDS: &str = "600";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct UpdateSettings {
+    // Off until turned on in the preferences, nothing is sent anywhere without asking
+    pub check_on_startup: bool,
+    // Answers like GitHub's latest release endpoint
+    pub endpoint: String,
+    // Tag of a release that shouldn't be brought up again on startup
+    pub skipped: Option<String>,
+}
+
+impl Default for UpdateSettings {
+    fn default() -> Self {
+        Self {
+            check_on_startup: false,
+            endpoint: DEFAULT_ENDPOINT.to_string(),
+            skipped: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub browser_download_url: String,
+    #[serde(default)]
+    pub size: u64,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub html_url: String,
+    // Release notes, in markdown
+    #[serde(default)]
+    pub body: String,
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+}
+
+// Words release builds are named with for each system, checked in order
+fn platform_names() -> &'static [&'static str] {
+    if cfg!(target_os = "windows") {
+        &["windows", "win64", "win"]
+    } else if cfg!(target_os = "macos") {
+        &["macos", "darwin", "osx", "mac"]
+    } else {
+        &["linux"]
+    }
+}
+
+impl Release {
+    // The build for this system, checksums and signatures next to it are skipped
+    pub fn asset_for_platform(&self) -> Option<&ReleaseAsset> {
+        let builds: Vec<&ReleaseAsset> = self
+            .assets
+            .iter()
+            .filter(|asset| {
+                let name = asset.name.to_lowercase();
+                ![".sha256", ".sha512", ".sig", ".asc", ".txt"]
+                    .iter()
+                    .any(|extension| name.ends_with(extension))
+            })
+            .collect();
+
+        platform_names().iter().find_map(|platform| {
+            builds
+                .iter()
+                .find(|asset| asset.name.to_lowercase().contains(platform))
+                .copied()
+        })
+    }
+
+    // Published next to the build as "<build>.sha256", downloads without one aren't offered
+    pub fn checksum_for(&self, asset: &ReleaseAsset) -> Option<&ReleaseAsset> {
+        let name = format!("{}.sha256", asset.name).to_lowercase();
+        self.assets
+            .iter()
+            .find(|checksum| checksum.name.to_lowercase() == name)
+    }
+
+    pub fn is_newer_than(&self, version: &str) -> bool {
+        match (parse_version(&self.tag_name), parse_version(version)) {
+            (Some(release), Some(current)) => release > current,
+            _ => false,
+        }
+    }
+}
+
+// "v1.2.3", "1.2" and "1.2.3-beta" all read as numbers, whatever follows a dash or plus is ignored
+pub fn parse_version(text: &str) -> Option<(u32, u32, u32)> {
+    let text = text.trim().trim_start_matches(['v', 'V']);
+    let core = text.split(['-', '+']).next()?;
+
+    let mut numbers = core.split('.').map(|part| part.parse::<u32>());
+    let major = numbers.next()?.ok()?;
+    let minor = numbers.next().transpose().ok()?.unwrap_or(0);
+    let patch = numbers.next().transpose().ok()?.unwrap_or(0);
+
+    Some((major, minor, patch))
+}
+
+fn curl() -> Command {
+    let mut command = Command::new("curl");
+    command
+        .args(["--fail", "--silent", "--show-error", "--location"])
+        .args([
+            "--user-agent",
+            &format!("mm-modchart-maker/{CURRENT_VERSION}"),
+        ])
+        .stdin(Stdio::null());
+    command
+}
+
+fn run(mut command: Command) -> io::Result<Vec<u8>> {
+    let output = command
+        .output()
+        .map_err(|e| io::Error::new(e.kind(), format!("Could not start curl: {e}")))?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::other(error.trim().to_string()));
+    }
+
+    Ok(output.stdout)
+}
+
+pub fn fetch_latest(endpoint: &str) -> io::Result<Release> {
+    let mut command = curl();
+    command
+        .args(["--max-time", CHECK_TIMEOUT_SECONDS])
+        .args(["--header", "Accept: application/vnd.github+json"])
+        .arg(endpoint);
+
+    let release = serde_json::from_slice(&run(command)?)?;
+    Ok(release)
+}
+
+// Downloaded builds wait here until they're installed by hand, nothing is replaced while running
+pub fn staging_folder() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("updates"))
+}
+
+pub fn staged_path(folder: &Path, release: &Release, asset: &ReleaseAsset) -> PathBuf {
+    // Both names come from the server, they can't be allowed to point outside the folder
+    folder
+        .join(sanitize_filename(&release.tag_name))
+        .join(sanitize_filename(&asset.name))
+}
+
+fn fetch_checksum(release: &Release, asset: &ReleaseAsset) -> io::Result<sha256::Digest> {
+    let Some(checksum) = release.checksum_for(asset) else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("The release has no checksum for {}", asset.name),
+        ));
+    };
+
+    let mut command = curl();
+    command
+        .args(["--max-time", CHECK_TIMEOUT_SECONDS])
+        .arg(&checksum.browser_download_url);
+    let text = String::from_utf8_lossy(&run(command)?).into_owned();
+
+    sha256::parse_checksum(&text, &asset.name).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} doesn't hold a SHA-256 checksum", checksum.name),
+        )
+    })
+}
+
+// Downloaded next to the target first so a cut off download never looks finished, and only moved
+// into place once it matches the checksum published with the release
+pub fn download(release: &Release, asset: &ReleaseAsset) -> io::Result<PathBuf> {
+    let Some(folder) = staging_folder() else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "No configuration directory available",
+        ));
+    };
+
+    let expected = fetch_checksum(release, asset)?;
+
+    let path = staged_path(&folder, release, asset);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let temporary = path.with_extension("part");
+    let mut command = curl();
+    command
+        .args(["--max-time", DOWNLOAD_TIMEOUT_SECONDS])
+        .arg("--output")
+        .arg(&temporary)
+        .arg(&asset.browser_download_url);
+    run(command)?;
+
+    let size = fs::metadata(&temporary)?.len();
+    if asset.size > 0 && size != asset.size {
+        fs::remove_file(&temporary)?;
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Downloaded {size} bytes of {}, try again later", asset.size),
+        ));
+    }
+
+    let hash = sha256::hash_file(&temporary)?;
+    if hash != expected {
+        fs::remove_file(&temporary)?;
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "The download doesn't match its checksum ({} instead of {}), it was removed",
+                sha256::to_hex(&hash),
+                sha256::to_hex(&expected)
+            ),
+        ));
+    }
+
+    fs::rename(&temporary, &path)?;
+    Ok(path)
+}
@@ -0,0 +1,118 @@
+use bevy::math::{Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::modchart::{Easing, ModChannel, ModState};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FlyDirection {
+    #[default]
+    None,
+    Left,
+    Right,
+    Up,
+    Down,
+    // Away from the center cell, notes on it come straight in
+    Outward,
+}
+
+impl FlyDirection {
+    // Grid space, so y points down
+    fn offset(&self, position: Vec2) -> Vec2 {
+        match self {
+            FlyDirection::None => Vec2::ZERO,
+            FlyDirection::Left => Vec2::NEG_X,
+            FlyDirection::Right => Vec2::X,
+            FlyDirection::Up => Vec2::NEG_Y,
+            FlyDirection::Down => Vec2::Y,
+            FlyDirection::Outward => (position - Vec2::ONE).normalize_or_zero(),
+        }
+    }
+}
+
+// How notes animate on their way in. Progress runs from 0 where a note appears at the far end
+// of the approach to 1 where it is hit, and the easing is applied to scale and fly-in
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApproachProfile {
+    // Size notes appear at, reaching full size when hit
+    pub scale_from: f32,
+    // Portion of the approach notes take to fade in, 0 shows them fully from the start
+    pub fade_in: f32,
+    pub fly_direction: FlyDirection,
+    // Cells away from their position notes appear at
+    pub fly_distance: f32,
+    pub easing: Easing,
+}
+
+impl Default for ApproachProfile {
+    fn default() -> Self {
+        Self {
+            scale_from: 1.0,
+            fade_in: 0.0,
+            fly_direction: FlyDirection::None,
+            fly_distance: 0.0,
+            easing: Easing::Linear,
+        }
+    }
+}
+
+// Where a note is in its approach, worked out for every visible note each frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ApproachPose {
+    pub scale: f32,
+    // Grid cells, added to the note's position
+    pub offset: Vec2,
+    pub alpha: f32,
+}
+
+impl ApproachProfile {
+    pub fn pose(&self, position: Vec2, progress: f32) -> ApproachPose {
+        let progress = progress.clamp(0.0, 1.0);
+        let eased = self.easing.apply(progress);
+        let remaining = 1.0 - eased;
+
+        ApproachPose {
+            scale: self.scale_from + (1.0 - self.scale_from) * eased,
+            offset: self.fly_direction.offset(position) * self.fly_distance * remaining,
+            alpha: match self.fade_in > 0.0 {
+                true => (progress / self.fade_in).min(1.0),
+                false => 1.0,
+            },
+        }
+    }
+
+    // Mod events on the approach channels replace the matching part of the profile while they
+    // are active. The fly-in channel is an offset in cells shared by every note
+    pub fn with_mods(&self, mods: &ModState) -> ApproachOverride {
+        ApproachOverride {
+            profile: *self,
+            scale_from: mods.active(ModChannel::NoteApproachScale).map(|v| v.x),
+            fade_in: mods.active(ModChannel::NoteApproachFade).map(|v| v.x),
+            fly_offset: mods.active(ModChannel::NoteApproachOffset),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ApproachOverride {
+    profile: ApproachProfile,
+    scale_from: Option<f32>,
+    fade_in: Option<f32>,
+    fly_offset: Option<Vec3>,
+}
+
+impl ApproachOverride {
+    pub fn pose(&self, position: Vec2, progress: f32) -> ApproachPose {
+        let mut profile = self.profile;
+        profile.scale_from = self.scale_from.unwrap_or(profile.scale_from);
+        profile.fade_in = self.fade_in.unwrap_or(profile.fade_in);
+
+        let mut pose = profile.pose(position, progress);
+        if let Some(offset) = self.fly_offset {
+            let remaining = 1.0 - profile.easing.apply(progress.clamp(0.0, 1.0));
+            pose.offset = offset.truncate() * remaining;
+        }
+
+        pose
+    }
+}
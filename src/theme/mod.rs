@@ -0,0 +1,340 @@
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::PathBuf,
+    sync::{
+        Mutex,
+        mpsc::{Receiver, channel},
+    },
+    time::{Duration, Instant},
+};
+
+use bevy::{asset::ron, prelude::*};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+
+use crate::settings::{Settings, config_dir};
+
+pub mod approach;
+pub mod palette;
+pub mod shape;
+
+use approach::ApproachProfile;
+use palette::AccessibilitySettings;
+use shape::NoteShape;
+
+const THEME_FOLDER: &str = "themes";
+
+// Editors save in several steps, the file is only read once it has been quiet for this long
+const SETTLE_TIME: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    pub fn color(&self) -> Color {
+        Color::srgb_u8(self.0, self.1, self.2)
+    }
+
+    pub fn egui(&self) -> egui::Color32 {
+        egui::Color32::from_rgb(self.0, self.1, self.2)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThemeBase {
+    Dark,
+    Light,
+}
+
+// Colors of the egui windows and of everything drawn in the world, read from themes/<name>.ron
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    // Egui style the other colors are applied on top of
+    pub base: ThemeBase,
+    pub accent: Rgb,
+    pub text: Rgb,
+    pub panel: Rgb,
+    pub background: Rgb,
+    pub warning: Rgb,
+    pub error: Rgb,
+    pub grid: Rgb,
+    // Consecutive notes cycle through these
+    pub notes: Vec<Rgb>,
+    pub hit: Rgb,
+    pub miss: Rgb,
+    pub short_gap: Rgb,
+    pub long_gap: Rgb,
+    pub approach: ApproachProfile,
+    pub note_shape: NoteShape,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            base: ThemeBase::Dark,
+            accent: Rgb(90, 150, 230),
+            text: Rgb(220, 220, 225),
+            panel: Rgb(27, 27, 30),
+            background: Rgb(20, 20, 24),
+            warning: Rgb(240, 200, 80),
+            error: Rgb(240, 110, 100),
+            grid: Rgb(89, 89, 102),
+            notes: vec![Rgb(230, 230, 255)],
+            hit: Rgb(110, 200, 110),
+            miss: Rgb(230, 90, 80),
+            short_gap: Rgb(255, 89, 77),
+            long_gap: Rgb(77, 140, 255),
+            approach: ApproachProfile::default(),
+            note_shape: NoteShape::Cube,
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            base: ThemeBase::Light,
+            accent: Rgb(40, 110, 210),
+            text: Rgb(30, 30, 35),
+            panel: Rgb(242, 242, 245),
+            background: Rgb(205, 208, 215),
+            warning: Rgb(180, 120, 0),
+            error: Rgb(200, 40, 30),
+            grid: Rgb(110, 110, 125),
+            notes: vec![Rgb(40, 40, 70)],
+            hit: Rgb(40, 150, 60),
+            miss: Rgb(200, 40, 30),
+            short_gap: Rgb(220, 60, 50),
+            long_gap: Rgb(40, 100, 220),
+            approach: ApproachProfile::default(),
+            note_shape: NoteShape::Cube,
+        }
+    }
+
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            _ => None,
+        }
+    }
+
+    // Built in names plus every theme file, sorted
+    pub fn available() -> Vec<String> {
+        let mut names = vec!["dark".to_string(), "light".to_string()];
+
+        if let Some(entries) = Self::folder().and_then(|folder| fs::read_dir(folder).ok()) {
+            for path in entries.flatten().map(|entry| entry.path()) {
+                if path.extension().is_some_and(|e| e == "ron")
+                    && let Some(stem) = path.file_stem()
+                {
+                    names.push(stem.to_string_lossy().into_owned());
+                }
+            }
+        }
+
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    pub fn folder() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join(THEME_FOLDER))
+    }
+
+    pub fn path(name: &str) -> Option<PathBuf> {
+        Self::folder().map(|folder| folder.join(format!("{name}.ron")))
+    }
+
+    // Built in themes don't need a file, but one on disk overrides them
+    pub fn load(name: &str) -> io::Result<Self> {
+        let path = Self::path(name).ok_or_else(|| {
+            io::Error::new(ErrorKind::NotFound, "No configuration directory available")
+        })?;
+
+        match fs::read_to_string(&path) {
+            Ok(contents) => ron::from_str(&contents)
+                .map_err(|e| io::Error::new(ErrorKind::InvalidData, e.to_string())),
+            Err(e) if e.kind() == ErrorKind::NotFound => Self::builtin(name).ok_or(e),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn note_color(&self, index: usize) -> Color {
+        match self.notes.is_empty() {
+            true => Color::WHITE,
+            false => self.notes[index % self.notes.len()].color(),
+        }
+    }
+
+    pub fn visuals(&self) -> egui::Visuals {
+        let mut visuals = match self.base {
+            ThemeBase::Dark => egui::Visuals::dark(),
+            ThemeBase::Light => egui::Visuals::light(),
+        };
+
+        let accent = self.accent.egui();
+
+        visuals.override_text_color = Some(self.text.egui());
+        visuals.hyperlink_color = accent;
+        visuals.selection.bg_fill = accent.gamma_multiply(0.6);
+        visuals.selection.stroke.color = accent;
+        visuals.widgets.hovered.bg_stroke.color = accent;
+        visuals.widgets.active.bg_fill = accent;
+        visuals.panel_fill = self.panel.egui();
+        visuals.window_fill = self.panel.egui();
+        visuals.extreme_bg_color = self.background.egui();
+        visuals.warn_fg_color = self.warning.egui();
+        visuals.error_fg_color = self.error.egui();
+
+        visuals
+    }
+}
+
+#[derive(Resource)]
+pub struct ThemeWatcher {
+    // Never read, the folder is only watched for as long as this is kept
+    _watcher: RecommendedWatcher,
+    events: Mutex<Receiver<notify::Result<notify::Event>>>,
+}
+
+// What the current theme was built from, so it is only rebuilt when one of those changes
+#[derive(Resource, Default)]
+pub struct ThemeState {
+    name: String,
+    accessibility: AccessibilitySettings,
+    file_changed: Option<Instant>,
+}
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Theme>()
+            .init_resource::<ThemeState>()
+            .add_systems(Startup, setup_themes)
+            .add_systems(Update, reload_theme)
+            .add_systems(
+                EguiPrimaryContextPass,
+                apply_egui_theme.run_if(resource_changed::<Theme>),
+            )
+            .add_systems(
+                PostUpdate,
+                apply_clear_color.run_if(resource_changed::<Theme>),
+            );
+    }
+}
+
+// Writes the built in themes out as a starting point for custom ones and starts watching them
+pub fn setup_themes(mut commands: Commands) {
+    let Some(folder) = Theme::folder() else {
+        return;
+    };
+
+    if let Err(e) = fs::create_dir_all(&folder) {
+        warn!("Could not create {}: {e}", folder.display());
+        return;
+    }
+
+    for (name, builtin) in [("dark", Theme::dark()), ("light", Theme::light())] {
+        let path = folder.join(format!("{name}.ron"));
+        if path.exists() {
+            continue;
+        }
+
+        let written = ron::ser::to_string_pretty(&builtin, ron::ser::PrettyConfig::default())
+            .map_err(io::Error::other)
+            .and_then(|contents| fs::write(&path, contents));
+
+        if let Err(e) = written {
+            warn!("Could not write {}: {e}", path.display());
+        }
+    }
+
+    let (sender, receiver) = channel();
+
+    let watcher = notify::recommended_watcher(move |event| {
+        let _ = sender.send(event);
+    })
+    .and_then(|mut watcher| {
+        watcher.watch(&folder, RecursiveMode::NonRecursive)?;
+        Ok(watcher)
+    });
+
+    match watcher {
+        Ok(watcher) => commands.insert_resource(ThemeWatcher {
+            _watcher: watcher,
+            events: Mutex::new(receiver),
+        }),
+        Err(e) => warn!("Themes won't be reloaded when edited: {e}"),
+    }
+}
+
+pub fn reload_theme(
+    watcher: Option<Res<ThemeWatcher>>,
+    mut state: ResMut<ThemeState>,
+    settings: Res<Settings>,
+    mut theme: ResMut<Theme>,
+) {
+    let now = Instant::now();
+
+    if let Some(events) = watcher.as_ref().and_then(|w| w.events.lock().ok()) {
+        for event in events.try_iter().flatten() {
+            // Watcher paths may be resolved differently than the configured one, so only the name counts
+            if event.paths.iter().any(|path| {
+                path.extension().is_some_and(|e| e == "ron")
+                    && path
+                        .file_stem()
+                        .is_some_and(|stem| *stem == *settings.theme)
+            }) {
+                state.file_changed = Some(now);
+            }
+        }
+    }
+
+    let settled = state
+        .file_changed
+        .is_some_and(|changed| now.duration_since(changed) >= SETTLE_TIME);
+
+    // The state starts out empty, so the first run always loads
+    if !settled
+        && state.name == settings.theme
+        && state.accessibility == settings.accessibility
+        && !state.name.is_empty()
+    {
+        return;
+    }
+
+    state.file_changed = None;
+    state.name = settings.theme.clone();
+    state.accessibility = settings.accessibility.clone();
+
+    // A half written file keeps the previous theme instead of flashing back to the default
+    match Theme::load(&settings.theme) {
+        Ok(loaded) => {
+            let loaded = loaded.with_accessibility(&settings.accessibility);
+
+            if loaded != *theme {
+                info!("Loaded theme \"{}\"", settings.theme);
+                *theme = loaded;
+            }
+        }
+        Err(e) => warn!("Could not load theme \"{}\": {e}", settings.theme),
+    }
+}
+
+pub fn apply_egui_theme(mut contexts: EguiContexts, theme: Res<Theme>) -> Result {
+    contexts.ctx_mut()?.set_visuals(theme.visuals());
+    Ok(())
+}
+
+pub fn apply_clear_color(mut clear_color: ResMut<ClearColor>, theme: Res<Theme>) {
+    clear_color.0 = theme.background.color();
+}
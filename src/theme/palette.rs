@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+
+use crate::theme::{Rgb, Theme, ThemeBase};
+
+// Note colors alternate through the palette, hit and miss colors are used for judgement feedback.
+// The colorblind palettes are built from the Okabe-Ito set, avoiding the pairs each type confuses.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NotePalette {
+    // Whatever the theme defines
+    #[default]
+    Theme,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+}
+
+impl NotePalette {
+    pub const ALL: [NotePalette; 4] = [
+        NotePalette::Theme,
+        NotePalette::Deuteranopia,
+        NotePalette::Protanopia,
+        NotePalette::Tritanopia,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NotePalette::Theme => "Theme colors",
+            NotePalette::Deuteranopia => "Deuteranopia",
+            NotePalette::Protanopia => "Protanopia",
+            NotePalette::Tritanopia => "Tritanopia",
+        }
+    }
+
+    // Note colors followed by the hit and miss colors, None keeps the theme's own
+    fn colors(&self) -> Option<(Vec<Rgb>, Rgb, Rgb)> {
+        match self {
+            NotePalette::Theme => None,
+            // Red and green are confused, blue against orange stays distinct
+            NotePalette::Deuteranopia => Some((
+                vec![Rgb(0, 114, 178), Rgb(230, 159, 0)],
+                Rgb(86, 180, 233),
+                Rgb(213, 94, 0),
+            )),
+            // Reds also appear dark, so nothing relies on red at all
+            NotePalette::Protanopia => Some((
+                vec![Rgb(86, 180, 233), Rgb(240, 228, 66)],
+                Rgb(0, 114, 178),
+                Rgb(230, 159, 0),
+            )),
+            // Blue and yellow are confused, red against teal stays distinct
+            NotePalette::Tritanopia => Some((
+                vec![Rgb(213, 94, 0), Rgb(0, 158, 115)],
+                Rgb(0, 158, 115),
+                Rgb(204, 121, 167),
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccessibilitySettings {
+    pub palette: NotePalette,
+    // Black background and white grid, so notes stand out regardless of the theme
+    pub high_contrast: bool,
+}
+
+impl Theme {
+    // Accessibility settings win over the theme, so every theme works with them
+    pub fn with_accessibility(mut self, accessibility: &AccessibilitySettings) -> Self {
+        if let Some((notes, hit, miss)) = accessibility.palette.colors() {
+            self.notes = notes;
+            self.hit = hit;
+            self.miss = miss;
+        }
+
+        if accessibility.high_contrast {
+            self.base = ThemeBase::Dark;
+            self.background = Rgb(0, 0, 0);
+            self.grid = Rgb(255, 255, 255);
+            self.panel = Rgb(0, 0, 0);
+            self.text = Rgb(255, 255, 255);
+        }
+
+        self
+    }
+}
@@ -0,0 +1,49 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::player::playfield::{CELL_SIZE, NOTE_SIZE};
+
+// Mesh notes are drawn with. Themes pick one and the NoteShape mod channel swaps it while active,
+// hit detection always uses the square the note covers on the grid
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum NoteShape {
+    #[default]
+    Cube,
+    Sphere,
+    // Flat square facing the camera
+    Panel,
+}
+
+impl NoteShape {
+    pub const ALL: [NoteShape; 3] = [NoteShape::Cube, NoteShape::Sphere, NoteShape::Panel];
+
+    // Stable number of the shape in mod values, new shapes only go at the end
+    pub fn index(&self) -> usize {
+        NoteShape::ALL.iter().position(|s| s == self).unwrap_or(0)
+    }
+
+    // Mod values are floats, they pick the nearest shape
+    pub fn from_value(value: f32) -> Option<Self> {
+        let index = value.round();
+        (index >= 0.0)
+            .then(|| NoteShape::ALL.get(index as usize).copied())
+            .flatten()
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NoteShape::Cube => "Cube",
+            NoteShape::Sphere => "Sphere",
+            NoteShape::Panel => "Panel",
+        }
+    }
+
+    pub fn mesh(&self) -> Mesh {
+        let side = CELL_SIZE * NOTE_SIZE;
+        match self {
+            NoteShape::Cube => Cuboid::new(side, side, 0.1).into(),
+            NoteShape::Sphere => Sphere::new(side / 2.0).mesh().uv(24, 16),
+            NoteShape::Panel => Rectangle::new(side, side).into(),
+        }
+    }
+}
@@ -0,0 +1,54 @@
+// Review comments are kept in SSPM custom data and find their note again without saved note ids
+
+use std::io::Cursor;
+
+use common::note;
+use mm_modchart_maker::maps::{
+    Annotation, Map,
+    parser::{MapSerializer, SSPMSerializer},
+};
+
+mod common;
+
+#[test]
+fn comments_survive_saving() {
+    let map = Map {
+        mappers: vec!["mapper".to_string()],
+        notes: vec![note(250, 0.0, 2.0), note(250, 2.0, 2.0)],
+        annotations: vec![
+            Annotation {
+                millisecond: 100,
+                position: None,
+                text: "Intro feels empty".to_string(),
+                resolved: true,
+            },
+            Annotation {
+                millisecond: 250,
+                position: Some([2.0, 2.0]),
+                text: "Jump is too wide".to_string(),
+                resolved: false,
+            },
+        ],
+        ..common::map("review")
+    };
+
+    let mut file = Cursor::new(Vec::new());
+    SSPMSerializer::serialize(&map, &mut file).unwrap();
+    file.set_position(0);
+    let read = SSPMSerializer::deserialize(file).unwrap();
+
+    assert_eq!(read.annotations, map.annotations);
+
+    let on: Vec<bool> = read
+        .notes
+        .iter()
+        .map(|note| read.annotations[1].is_on(note))
+        .collect();
+    assert_eq!(on, vec![false, true]);
+    assert!(
+        !read
+            .notes
+            .iter()
+            .any(|note| read.annotations[0].is_on(note))
+    );
+}
@@ -0,0 +1,122 @@
+// Projects read back what was written, and snapshots from before the archive layout still open
+
+use std::{
+    env,
+    fs::{self, File},
+    io::{Cursor, Write},
+    path::PathBuf,
+};
+
+use bevy::math::Vec3;
+use common::note;
+use mm_modchart_maker::{
+    editor::project::{ProjectState, read_project, write_project},
+    maps::{
+        Map,
+        parser::{MapSerializer, SSPMSerializer},
+        snapshot::{EXTENSION, list_snapshots, read_snapshot},
+    },
+    modchart::{Keyframe, ModChannel, ModEvent, Modchart},
+};
+use zip::{ZipWriter, write::SimpleFileOptions};
+
+mod common;
+
+fn folder(name: &str) -> PathBuf {
+    let folder = env::temp_dir().join(format!("mm-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(&folder).unwrap();
+    folder
+}
+
+fn song() -> Map {
+    Map {
+        notes: vec![note(100, 0.0, 0.0), note(400, 2.0, 1.0)],
+        artists: vec!["First".to_string(), "Second".to_string()],
+        ..common::map("song")
+    }
+}
+
+// A snapshot as written before it had a version, with the map only as SSPM
+fn write_first_snapshot(path: &PathBuf, map: &Map, info: &str) {
+    let mut stored = map.clone();
+    stored.save_note_ids = true;
+    let mut sspm = Cursor::new(Vec::new());
+    SSPMSerializer::serialize(&stored, &mut sspm).unwrap();
+
+    let mut zip = ZipWriter::new(File::create(path).unwrap());
+    let options = SimpleFileOptions::default();
+    zip.start_file("snapshot.json", options).unwrap();
+    zip.write_all(info.as_bytes()).unwrap();
+    zip.start_file("map.sspm", options).unwrap();
+    zip.write_all(&sspm.into_inner()).unwrap();
+    zip.finish().unwrap();
+}
+
+#[test]
+fn projects_round_trip() {
+    let folder = folder("project");
+    let path = folder.join("song.mmm");
+
+    let map = song();
+    let modchart = Modchart::new(vec![ModEvent::new(
+        ModChannel::PlayfieldRotation,
+        vec![
+            Keyframe::new(0, Vec3::ZERO),
+            Keyframe::new(500, Vec3::new(0.0, 0.0, 1.0)),
+        ],
+    )]);
+    let state = ProjectState {
+        playhead_ms: 250.0,
+        selection: vec![1],
+        ..ProjectState::default()
+    };
+
+    write_project(&path, &map, &modchart, &state).unwrap();
+    let project = read_project(&path).unwrap();
+    let _ = fs::remove_dir_all(&folder);
+
+    assert_eq!(project.map.notes, map.notes);
+    assert_eq!(project.map.artists, map.artists);
+    assert_eq!(project.modchart.events(), modchart.events());
+    assert_eq!(project.state.playhead_ms, 250.0);
+    assert_eq!(project.state.selection, vec![1]);
+}
+
+#[test]
+fn snapshots_from_before_versions_still_open() {
+    let folder = folder("first-snapshot");
+    let map = song();
+    write_first_snapshot(
+        &folder.join(format!("100.{EXTENSION}")),
+        &map,
+        r#"{"name":"v1","created":100,"notes":2,"artists":["First","Second"],"save_note_ids":false}"#,
+    );
+
+    let snapshots = list_snapshots(&folder);
+    let restored = read_snapshot(&snapshots[0]);
+    let _ = fs::remove_dir_all(&folder);
+
+    assert_eq!(snapshots.len(), 1);
+    assert_eq!(snapshots[0].name, "v1");
+    let restored = restored.unwrap();
+    assert_eq!(restored.notes, map.notes);
+    assert_eq!(restored.artists, map.artists);
+    assert!(!restored.save_note_ids);
+}
+
+#[test]
+fn snapshots_from_newer_versions_are_refused() {
+    let folder = folder("newer-snapshot");
+    write_first_snapshot(
+        &folder.join(format!("100.{EXTENSION}")),
+        &song(),
+        r#"{"version":99,"name":"future","created":100,"notes":2}"#,
+    );
+
+    let snapshots = list_snapshots(&folder);
+    let restored = read_snapshot(&snapshots[0]);
+    let _ = fs::remove_dir_all(&folder);
+
+    assert!(restored.is_err());
+}
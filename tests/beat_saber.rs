@@ -0,0 +1,42 @@
+// Notes land on Beat Saber's 4x3 grid with the saber picked by side, and times become beats
+
+use common::{note, timing};
+use mm_modchart_maker::maps::{Map, beat_saber::color_notes};
+
+mod common;
+
+#[test]
+fn grid_and_beats() {
+    let map = Map {
+        length: 2000,
+        notes: vec![
+            note(0, 0.0, 0.0),
+            note(500, 2.0, 2.0),
+            note(1000, 1.0, 1.0),
+            note(1500, 1.0, 1.0),
+            note(2000, 0.9, 1.0),
+            note(2000, 0.8, 1.0),
+        ],
+        timing_points: vec![timing(0, 120.0)],
+        ..common::map("vr")
+    };
+
+    let notes: Vec<(f64, u8, u8, u8)> = color_notes(&map)
+        .iter()
+        .map(|n| (n.b, n.x, n.y, n.c))
+        .collect();
+
+    assert_eq!(
+        notes,
+        [
+            // Top left with the left saber, bottom right with the right one
+            (0.0, 0, 2, 0),
+            (1.0, 3, 0, 1),
+            // Middle notes take turns
+            (2.0, 1, 1, 0),
+            (3.0, 2, 1, 1),
+            // Quantum notes on the same lane at once become one
+            (4.0, 1, 1, 0),
+        ]
+    );
+}
@@ -0,0 +1,301 @@
+// Everything written by BinaryWriter has to come back the same from the matching BinaryReader
+// call, or maps change a little every time they are saved
+
+use std::io::Cursor;
+
+use bevy::math::{Vec2, Vec3};
+use mm_modchart_maker::maps::{
+    io::{BinaryReader, BinaryWriter, ReadOptions, WriteOptions},
+    parser::{is_quantum, is_quantum_value},
+};
+use proptest::prelude::*;
+
+type Writer = BinaryWriter<Cursor<Vec<u8>>>;
+type Reader = BinaryReader<Cursor<Vec<u8>>>;
+
+fn written(write: impl FnOnce(&mut Writer) -> std::io::Result<()>) -> Vec<u8> {
+    let mut writer = BinaryWriter::new(Cursor::new(Vec::new()));
+    write(&mut writer).unwrap();
+    writer.into_inner().into_inner()
+}
+
+fn reader(bytes: Vec<u8>) -> Reader {
+    BinaryReader::new(Cursor::new(bytes))
+}
+
+// Reads leave nothing behind, a length that is off would leave bytes or run out of them
+fn assert_consumed(reader: &mut Reader, length: usize) {
+    assert_eq!(reader.stream_position().unwrap(), length as u64);
+}
+
+// What the compact encoding turns a position component into
+fn stored(value: f32, quantum: bool) -> f32 {
+    match quantum {
+        true => value,
+        false => value.round(),
+    }
+}
+
+fn component() -> impl Strategy<Value = f32> {
+    prop_oneof![
+        // Whole cells and values a hair off them, which are stored as whole cells
+        (0..=2u8, -0.004f32..0.004).prop_map(|(cell, off)| cell as f32 + off),
+        -10.0f32..10.0,
+        any::<f32>().prop_filter("finite", |v| v.is_finite()),
+    ]
+}
+
+#[test]
+fn strict_readers_reject_latin1() {
+    let mut bytes = written(|w| w.write_u16(4));
+    bytes.extend_from_slice(b"Caf\xe9");
+    let options = ReadOptions {
+        lossy_strings: false,
+    };
+
+    assert!(
+        BinaryReader::with_options(Cursor::new(bytes), options)
+            .read_string()
+            .is_err()
+    );
+}
+
+// 1.01 is a cell with a coarse epsilon and off the grid with a fine one
+#[test]
+fn the_grid_epsilon_is_the_writers() {
+    let value = Vec2::new(1.01, 2.0);
+    let length = |grid_epsilon| {
+        let mut writer =
+            BinaryWriter::with_options(Cursor::new(Vec::new()), WriteOptions { grid_epsilon });
+        writer.write_vec2(value).unwrap();
+        writer.into_inner().into_inner().len()
+    };
+
+    assert_eq!(length(0.05), 3);
+    assert_eq!(length(0.005), 9);
+}
+
+proptest! {
+    #[test]
+    fn integers_round_trip(a in any::<bool>(), b in any::<u8>(), c in any::<u16>(), d in any::<u32>(), e in any::<u64>()) {
+        let bytes = written(|w| {
+            w.write_bool(a)?;
+            w.write_u8(b)?;
+            w.write_u16(c)?;
+            w.write_u32(d)?;
+            w.write_u64(e)
+        });
+        let length = bytes.len();
+        let mut r = reader(bytes);
+
+        prop_assert_eq!(r.read_bool().unwrap(), a);
+        prop_assert_eq!(r.read_u8().unwrap(), b);
+        prop_assert_eq!(r.read_u16().unwrap(), c);
+        prop_assert_eq!(r.read_u32().unwrap(), d);
+        prop_assert_eq!(r.read_u64().unwrap(), e);
+        assert_consumed(&mut r, length);
+    }
+
+    // Compared bit for bit, so NaN payloads and negative zero survive too
+    #[test]
+    fn floats_round_trip(a in any::<u32>(), b in any::<u64>()) {
+        let (a, b) = (f32::from_bits(a), f64::from_bits(b));
+        let mut r = reader(written(|w| {
+            w.write_f32(a)?;
+            w.write_f64(b)
+        }));
+
+        prop_assert_eq!(r.read_f32().unwrap().to_bits(), a.to_bits());
+        prop_assert_eq!(r.read_f64().unwrap().to_bits(), b.to_bits());
+    }
+
+    // Non-ASCII strings have more bytes than characters, the length has to count bytes
+    #[test]
+    fn strings_round_trip(value in "\\PC{0,64}", long in "\\PC{0,64}") {
+        let bytes = written(|w| {
+            w.write_string(&value)?;
+            w.write_long_string(&long)
+        });
+        prop_assert_eq!(&bytes[..2], &(value.len() as u16).to_le_bytes()[..]);
+        let length = bytes.len();
+        let mut r = reader(bytes);
+
+        prop_assert_eq!(r.read_string().unwrap(), value);
+        prop_assert_eq!(r.read_long_string().unwrap(), long);
+        assert_consumed(&mut r, length);
+    }
+
+    #[test]
+    fn sha1_round_trips(hash in any::<[u8; 20]>()) {
+        let mut r = reader(written(|w| w.write_sha1(&hash)));
+        prop_assert_eq!(r.read_sha1().unwrap(), hash);
+    }
+
+    #[test]
+    fn vec2_round_trips(x in component(), y in component()) {
+        let value = Vec2::new(x, y);
+        let quantum = is_quantum(value);
+        let bytes = written(|w| w.write_vec2(value));
+        prop_assert_eq!(bytes.len(), if quantum { 9 } else { 3 });
+
+        let read = reader(bytes).read_vec2().unwrap();
+        prop_assert_eq!(read, Vec2::new(stored(x, quantum), stored(y, quantum)));
+    }
+
+    #[test]
+    fn vec3_round_trips(x in component(), y in component(), z in component()) {
+        let value = Vec3::new(x, y, z);
+        let quantum = value.to_array().into_iter().any(is_quantum_value);
+        let bytes = written(|w| w.write_vec3(value));
+        prop_assert_eq!(bytes.len(), if quantum { 13 } else { 4 });
+
+        let read = reader(bytes).read_vec3().unwrap();
+        let expected = Vec3::new(stored(x, quantum), stored(y, quantum), stored(z, quantum));
+        prop_assert_eq!(read, expected);
+    }
+
+    // Nothing written by earlier fields leaks into later ones
+    #[test]
+    fn mixed_fields_stay_aligned(name in "\\PC{0,16}", position in (component(), component()), millisecond in any::<u32>()) {
+        let position = Vec2::new(position.0, position.1);
+        let mut r = reader(written(|w| {
+            w.write_string(&name)?;
+            w.write_vec2(position)?;
+            w.write_u32(millisecond)
+        }));
+
+        prop_assert_eq!(r.read_string().unwrap(), name);
+        r.read_vec2().unwrap();
+        prop_assert_eq!(r.read_u32().unwrap(), millisecond);
+    }
+}
+
+#[test]
+fn string_length_limits() {
+    let longest = "a".repeat(u16::MAX as usize);
+    let mut r = reader(written(|w| w.write_string(&longest)));
+    assert_eq!(r.read_string().unwrap(), longest);
+
+    // Would have wrapped around to a length of 0 and left the string in the middle of the file
+    let mut writer = BinaryWriter::new(Cursor::new(Vec::new()));
+    let too_long = "a".repeat(u16::MAX as usize + 1);
+    assert!(writer.write_string(&too_long).is_err());
+    assert!(writer.write_buf(too_long.as_bytes()).is_err());
+
+    // Each of these is 3 bytes but one character, the limit is in bytes
+    let wide = "\u{3042}".repeat(u16::MAX as usize / 3 + 1);
+    assert!(wide.chars().count() < u16::MAX as usize);
+    assert!(writer.write_string(&wide).is_err());
+
+    let mut r = reader(written(|w| w.write_long_string(&too_long)));
+    assert_eq!(r.read_long_string().unwrap(), too_long);
+}
+
+#[test]
+fn empty_strings_round_trip() {
+    let mut r = reader(written(|w| {
+        w.write_string("")?;
+        w.write_long_string("")
+    }));
+    assert_eq!(r.read_string().unwrap(), "");
+    assert_eq!(r.read_long_string().unwrap(), "");
+}
+
+// Just under a cell used to be truncated to the cell before
+#[test]
+fn near_cell_positions_round_to_the_nearest_cell() {
+    let value = Vec2::new(0.999, 1.998);
+    assert!(!is_quantum(value));
+    let read = reader(written(|w| w.write_vec2(value)))
+        .read_vec2()
+        .unwrap();
+    assert_eq!(read, Vec2::new(1.0, 2.0));
+}
+
+// Latin-1 from old community maps, "Café" with a single byte for the é. Lossy strings are the
+// default, the invalid byte is replaced and the rest of the file still reads
+#[test]
+fn latin1_strings_are_replaced() {
+    let mut bytes = written(|w| w.write_u16(4));
+    bytes.extend_from_slice(b"Caf\xe9");
+    bytes.extend(written(|w| w.write_u32(1234)));
+    let mut r = reader(bytes);
+
+    assert_eq!(r.read_string().unwrap(), "Caf\u{fffd}");
+    assert_eq!(r.read_u32().unwrap(), 1234);
+    assert_eq!(r.take_warnings().len(), 1);
+    assert!(r.take_warnings().is_empty());
+}
+
+proptest! {
+    #[test]
+    fn big_endian_round_trips(a in any::<u16>(), b in 0..=0xFF_FFFFu32, c in any::<u32>(), d in any::<u64>(), e in any::<u32>(), f in any::<u64>()) {
+        let (e, f) = (f32::from_bits(e), f64::from_bits(f));
+        let bytes = written(|w| {
+            w.write_u16_be(a)?;
+            w.write_u24_be(b)?;
+            w.write_u32_be(c)?;
+            w.write_u64_be(d)?;
+            w.write_f32_be(e)?;
+            w.write_f64_be(f)
+        });
+        prop_assert_eq!(&bytes[..2], &a.to_be_bytes()[..]);
+        let length = bytes.len();
+        let mut r = reader(bytes);
+
+        prop_assert_eq!(r.read_u16_be().unwrap(), a);
+        prop_assert_eq!(r.read_u24_be().unwrap(), b);
+        prop_assert_eq!(r.read_u32_be().unwrap(), c);
+        prop_assert_eq!(r.read_u64_be().unwrap(), d);
+        prop_assert_eq!(r.read_f32_be().unwrap().to_bits(), e.to_bits());
+        prop_assert_eq!(r.read_f64_be().unwrap().to_bits(), f.to_bits());
+        assert_consumed(&mut r, length);
+    }
+
+    #[test]
+    fn varints_round_trip(value in any::<u64>(), quantity in 0..=0x0FFF_FFFFu32) {
+        let bytes = written(|w| {
+            w.write_varint(value)?;
+            w.write_vlq(quantity)
+        });
+        let length = bytes.len();
+        let mut r = reader(bytes);
+
+        prop_assert_eq!(r.read_varint().unwrap(), value);
+        prop_assert_eq!(r.read_vlq().unwrap(), quantity);
+        assert_consumed(&mut r, length);
+    }
+}
+
+// Examples from the MIDI specification
+#[test]
+fn vlq_matches_midi() {
+    for (value, expected) in [
+        (0x00, &[0x00][..]),
+        (0x7F, &[0x7F]),
+        (0x80, &[0x81, 0x00]),
+        (0x2000, &[0xC0, 0x00]),
+        (0x3FFF, &[0xFF, 0x7F]),
+        (0x10_0000, &[0xC0, 0x80, 0x00]),
+        (0x0FFF_FFFF, &[0xFF, 0xFF, 0xFF, 0x7F]),
+    ] {
+        assert_eq!(written(|w| w.write_vlq(value)), expected);
+    }
+
+    let mut writer = BinaryWriter::new(Cursor::new(Vec::new()));
+    assert!(writer.write_vlq(0x1000_0000).is_err());
+    assert!(writer.write_u24_be(0x100_0000).is_err());
+    assert!(reader(vec![0xFF; 5]).read_vlq().is_err());
+    assert!(reader(vec![0xFF; 11]).read_varint().is_err());
+
+    // Ten bytes hold 70 bits, anything past the 64th makes the varint too large
+    let mut max = vec![0xFF; 9];
+    max.push(0x01);
+    assert_eq!(reader(max).read_varint().unwrap(), u64::MAX);
+    for last in [0x02, 0x7F, 0x81] {
+        let mut bytes = vec![0xFF; 9];
+        bytes.push(last);
+        let error = reader(bytes).read_varint().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,85 @@
+// Bulk deletes remove exactly what the preview counted, and come back in a single undo
+
+use bevy::math::Vec2;
+use common::note;
+use mm_modchart_maker::{
+    editor::{
+        bulk_delete::NoteFilter,
+        commands::{EditCommand, Selection},
+    },
+    maps::objects::Note,
+};
+
+mod common;
+
+fn notes() -> Vec<Note> {
+    vec![
+        note(0, 0.0, 0.0),
+        note(100, 1.0, 1.0),
+        note(200, 1.5, 0.5),
+        note(300, 2.0, 2.0),
+        note(400, 0.0, 2.0),
+        note(500, 1.25, 1.0),
+    ]
+}
+
+#[test]
+fn criteria_combine() {
+    let notes = notes();
+
+    let time = NoteFilter {
+        time: Some((100, 400)),
+        ..Default::default()
+    };
+    assert_eq!(
+        time.matches(&notes).into_iter().collect::<Vec<_>>(),
+        [1, 2, 3, 4]
+    );
+
+    let region = NoteFilter {
+        time: Some((100, 400)),
+        region: Some((Vec2::new(2.0, 2.0), Vec2::new(1.0, 0.0))),
+        ..Default::default()
+    };
+    assert_eq!(
+        region.matches(&notes).into_iter().collect::<Vec<_>>(),
+        [1, 2, 3]
+    );
+
+    let quantum = NoteFilter {
+        quantum_only: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        quantum.matches(&notes).into_iter().collect::<Vec<_>>(),
+        [2, 5]
+    );
+
+    // Counted among the notes the other criteria let through
+    let nth = NoteFilter {
+        time: Some((100, 500)),
+        every_nth: Some(2),
+        ..Default::default()
+    };
+    assert_eq!(nth.matches(&notes).into_iter().collect::<Vec<_>>(), [2, 4]);
+}
+
+#[test]
+fn delete_and_undo() {
+    let original = notes();
+    let mut notes = original.clone();
+    let mut selection = Selection::default();
+
+    let filter = NoteFilter {
+        every_nth: Some(3),
+        ..Default::default()
+    };
+    let change = EditCommand::DeleteMatching { filter }.plan(&notes, &selection, 0);
+
+    change.apply(&mut notes, &mut selection);
+    let left: Vec<u32> = notes.iter().map(|n| n.millisecond).collect();
+    assert_eq!(left, [0, 100, 300, 400]);
+
+    change.revert(&mut notes, &mut selection);
+    assert_eq!(notes, original);
+}
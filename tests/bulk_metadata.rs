@@ -0,0 +1,88 @@
+// Bulk metadata edits are planned before anything is written, and list only the maps they change
+
+use std::path::PathBuf;
+
+use mm_modchart_maker::maps::{
+    Map,
+    metadata::{BulkEdit, Casing, Renumber, plan_bulk_edit},
+};
+
+mod common;
+
+fn map(artists: &[&str], mappers: &[&str], difficulty: u8, difficulty_name: &str) -> Map {
+    Map {
+        artists: artists.iter().map(|a| a.to_string()).collect(),
+        difficulty,
+        difficulty_name: difficulty_name.to_string(),
+        mappers: mappers.iter().map(|m| m.to_string()).collect(),
+        ..common::map("song")
+    }
+}
+
+#[test]
+fn unifies_mapper_spellings() {
+    let a = map(&["the artist"], &["Mapper One"], 3, "Hard");
+    let b = map(&["The Artist"], &["mapper  one", "Other"], 3, "Hard");
+    let c = map(&["The Artist"], &[" Mapper One "], 3, "Hard");
+    let maps = vec![
+        (PathBuf::from("a.sspm"), &a),
+        (PathBuf::from("b.sspm"), &b),
+        (PathBuf::from("c.sspm"), &c),
+    ];
+
+    let edit = BulkEdit {
+        artist_casing: Casing::Title,
+        normalize_mappers: true,
+        difficulty: Renumber::Keep,
+    };
+    let changes = plan_bulk_edit(&maps, &edit);
+
+    let paths: Vec<&PathBuf> = changes.iter().map(|change| &change.path).collect();
+    assert_eq!(
+        paths,
+        vec![
+            &PathBuf::from("a.sspm"),
+            &PathBuf::from("b.sspm"),
+            &PathBuf::from("c.sspm")
+        ]
+    );
+    assert_eq!(changes[0].after.artists, vec!["The Artist"]);
+    assert_eq!(changes[0].after.mappers, vec!["Mapper One"]);
+    assert_eq!(changes[1].after.mappers, vec!["Mapper One", "Other"]);
+    assert_eq!(changes[2].after.mappers, vec!["Mapper One"]);
+    assert_eq!(
+        changes[0].describe(),
+        vec!["Artists: the artist -> The Artist"]
+    );
+}
+
+#[test]
+fn renumbers_difficulties() {
+    let named = map(&[], &[], 0, "logic");
+    let unnamed = map(&[], &[], 5, "Insane");
+    let maps = vec![
+        (PathBuf::from("named.sspm"), &named),
+        (PathBuf::from("unnamed.sspm"), &unnamed),
+    ];
+
+    let from_name = BulkEdit {
+        difficulty: Renumber::FromName,
+        ..Default::default()
+    };
+    let changes = plan_bulk_edit(&maps, &from_name);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].after.difficulty, 4);
+
+    let shift = BulkEdit {
+        difficulty: Renumber::Shift(2),
+        ..Default::default()
+    };
+    let changes = plan_bulk_edit(&maps, &shift);
+    assert_eq!(
+        changes
+            .iter()
+            .map(|change| change.after.difficulty)
+            .collect::<Vec<_>>(),
+        vec![2]
+    );
+}
@@ -0,0 +1,44 @@
+// Maps are tagged by style from their notes so a library can be browsed by it
+
+use bevy::math::Vec2;
+use common::note_at;
+use mm_modchart_maker::{
+    analysis::tags::{ChartStats, ChartTag, chart_tags},
+    maps::objects::Note,
+};
+
+mod common;
+
+fn notes(count: u32, gap_ms: u32, position: impl Fn(u32) -> Vec2) -> Vec<Note> {
+    (0..count)
+        .map(|i| note_at(i * gap_ms, position(i)))
+        .collect()
+}
+
+#[test]
+fn streams_and_jumps_are_told_apart() {
+    let stream = notes(40, 100, |i| Vec2::new((i % 2) as f32, 1.0));
+    assert_eq!(chart_tags(&stream), [ChartTag::StreamHeavy]);
+
+    let jumps = notes(40, 250, |i| Vec2::splat((i % 2) as f32 * 2.0));
+    assert_eq!(chart_tags(&jumps), [ChartTag::JumpHeavy]);
+}
+
+#[test]
+fn off_grid_notes_are_quantum() {
+    let quantum = notes(40, 250, |i| Vec2::new(0.5 + (i % 2) as f32, 1.0));
+    assert!(ChartStats::from_notes(&quantum).quantum_share > 0.9);
+    assert!(chart_tags(&quantum).contains(&ChartTag::Quantum));
+}
+
+#[test]
+fn sparse_maps_are_low_density() {
+    let sparse = notes(20, 1000, |_| Vec2::ONE);
+    assert_eq!(chart_tags(&sparse), [ChartTag::LowDensity]);
+
+    // Stacked notes at one time aren't a stream
+    let chords = notes(3, 0, |i| Vec2::new(i as f32, 0.0));
+    assert_eq!(ChartStats::from_notes(&chords).stream_share, 0.0);
+
+    assert!(chart_tags(&[]).is_empty());
+}
@@ -0,0 +1,89 @@
+// Click tracks put a click on every note time, over the song or on their own
+
+use common::{note, timing};
+use mm_modchart_maker::{
+    jukebox::{
+        click_track::{ClickTrackMode, ClickTrackOptions, beat_times, render_click_track},
+        pcm::Pcm,
+    },
+    maps::Map,
+};
+
+mod common;
+
+fn map(times: &[u32]) -> Map {
+    Map {
+        length: times.last().copied().unwrap_or(0),
+        notes: times
+            .iter()
+            .map(|&millisecond| note(millisecond, 1.0, 1.0))
+            .collect(),
+        timing_points: vec![timing(0, 120.0)],
+        ..common::map("clicks")
+    }
+}
+
+// Loudest sample in the 40ms after `millisecond`
+fn peak(track: &Pcm, millisecond: u32) -> f32 {
+    let start =
+        (millisecond as usize * track.sample_rate as usize / 1000) * track.channels as usize;
+    let length = 40 * track.sample_rate as usize / 1000 * track.channels as usize;
+    track.samples[start..start + length]
+        .iter()
+        .fold(0.0, |peak, sample| peak.max(sample.abs()))
+}
+
+#[test]
+fn isolated_clicks_land_on_notes() {
+    let map = map(&[100, 600, 600]);
+    let options = ClickTrackOptions {
+        mode: ClickTrackMode::Isolated,
+        ..Default::default()
+    };
+
+    // Without audio the track ends a second after the last note
+    let track = render_click_track(&map, None, &options);
+    assert_eq!(track.duration_ms(), 1600);
+    assert!(peak(&track, 100) > 0.5);
+    assert!(peak(&track, 600) > 0.5);
+    assert_eq!(peak(&track, 300), 0.0);
+
+    let with_beats = render_click_track(
+        &map,
+        None,
+        &ClickTrackOptions {
+            metronome: true,
+            ..options
+        },
+    );
+    assert!(peak(&with_beats, 1000) > 0.2);
+    assert_eq!(beat_times(&map, 1600.0), vec![0.0, 500.0, 1000.0, 1500.0]);
+}
+
+#[test]
+fn song_is_turned_down_under_clicks() {
+    let song = Pcm {
+        sample_rate: 8000,
+        channels: 2,
+        samples: vec![0.5; 8000 * 2],
+    };
+    let options = ClickTrackOptions {
+        song_volume: 0.5,
+        ..Default::default()
+    };
+
+    let track = render_click_track(&map(&[200]), Some(&song), &options);
+    assert_eq!(track.samples.len(), song.samples.len());
+    assert_eq!(peak(&track, 500), 0.25);
+    assert!(peak(&track, 200) > 0.5);
+
+    // Clicks alone still match the song's length so they line up with it
+    let isolated = ClickTrackOptions {
+        mode: ClickTrackMode::Isolated,
+        ..options
+    };
+    assert_eq!(
+        render_click_track(&map(&[200]), Some(&song), &isolated).duration_ms(),
+        1000
+    );
+}
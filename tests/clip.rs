@@ -0,0 +1,77 @@
+// Clip frames are encoded as they arrive, in order, and clips are kept to a size that encodes
+
+use std::{env, fs::File, io::BufReader};
+
+use image::{AnimationDecoder, Rgba, RgbaImage, codecs::gif::GifDecoder};
+use mm_modchart_maker::editor::clip::{ClipEncoder, ClipFormat, ClipSettings, MAX_CLIP_PIXELS};
+
+fn small_gif(seconds: f32) -> ClipSettings {
+    ClipSettings {
+        format: ClipFormat::Gif,
+        center_ms: 0,
+        seconds,
+        fps: 10,
+        width: 32,
+    }
+}
+
+fn filled(color: [u8; 3]) -> RgbaImage {
+    let [r, g, b] = color;
+    RgbaImage::from_pixel(32, 18, Rgba([r, g, b, 255]))
+}
+
+#[test]
+fn frames_arriving_out_of_order_are_written_in_order() {
+    let clip = small_gif(0.3);
+    assert_eq!(clip.frame_count(), 3);
+
+    let path = env::temp_dir().join(format!("mm-clip-{}.gif", std::process::id()));
+    let encoder = ClipEncoder::spawn(clip, path.clone());
+    let colors = [[255, 0, 0], [0, 255, 0], [0, 0, 255]];
+    for index in [2, 0, 1] {
+        encoder.frames.send((index, filled(colors[index]))).unwrap();
+    }
+    assert_eq!(encoder.join().unwrap(), path);
+
+    let decoder = GifDecoder::new(BufReader::new(File::open(&path).unwrap())).unwrap();
+    let frames = decoder.into_frames().collect_frames().unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    let firsts: Vec<[u8; 3]> = frames
+        .iter()
+        .map(|frame| {
+            let [r, g, b, _] = frame.buffer().get_pixel(0, 0).0;
+            [r, g, b]
+        })
+        .collect();
+    assert_eq!(firsts, colors);
+    assert_eq!(frames[0].buffer().dimensions(), (32, 18));
+}
+
+#[test]
+fn an_unfinished_recording_is_an_error() {
+    let path = env::temp_dir().join(format!("mm-clip-unfinished-{}.gif", std::process::id()));
+    let encoder = ClipEncoder::spawn(small_gif(0.3), path.clone());
+    encoder.frames.send((0, filled([0, 0, 0]))).unwrap();
+
+    assert!(encoder.join().is_err());
+    assert!(!path.exists());
+}
+
+#[test]
+fn long_clips_are_shortened_at_large_sizes() {
+    let clip = ClipSettings {
+        seconds: 15.0,
+        fps: 60,
+        width: 1280,
+        ..ClipSettings::default()
+    }
+    .limited();
+
+    let pixels = clip.width as u64 * clip.height() as u64 * clip.frame_count() as u64;
+    assert!(clip.seconds < 15.0);
+    assert!(pixels <= MAX_CLIP_PIXELS + MAX_CLIP_PIXELS / 100);
+
+    let small = ClipSettings::default();
+    assert_eq!(small.limited(), small);
+}
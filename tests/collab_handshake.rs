@@ -0,0 +1,123 @@
+// The host never sends its session token, a guest has to bring it before anything is shared
+
+use std::{
+    io::{BufReader, ErrorKind, Write},
+    net::{TcpListener, TcpStream},
+    thread,
+    time::Duration,
+};
+
+use bevy::{asset::AssetPlugin, prelude::*};
+use mm_modchart_maker::{
+    collab::{
+        CollabStatus, Collaboration,
+        protocol::{Message, read_message},
+        receive_messages,
+    },
+    editor::commands::{EditHistory, Selection},
+    maps::{CurrentMap, Map},
+};
+
+mod common;
+
+fn hosting_app() -> (App, u16) {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+        .init_asset::<Map>()
+        .init_resource::<Selection>()
+        .init_resource::<EditHistory>()
+        .add_systems(Update, receive_messages);
+
+    let handle = app
+        .world_mut()
+        .resource_mut::<Assets<Map>>()
+        .add(common::map("song"));
+    app.insert_resource(CurrentMap(handle));
+
+    // A port nothing else is using
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let mut collab = Collaboration::default();
+    collab.host(port, false);
+    app.insert_resource(collab);
+
+    (app, port)
+}
+
+fn status(app: &App) -> CollabStatus {
+    app.world().resource::<Collaboration>().status.clone()
+}
+
+fn update_until(app: &mut App, done: impl Fn(&CollabStatus) -> bool) {
+    for _ in 0..100 {
+        app.update();
+        if done(&status(app)) {
+            return;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    panic!("Still {:?}", status(app));
+}
+
+fn hello(token: &str) -> String {
+    let hello = Message::Hello {
+        peer: 1,
+        token: token.to_string(),
+        map: "Song".to_string(),
+    };
+    serde_json::to_string(&hello).unwrap() + "\n"
+}
+
+#[test]
+fn a_peer_echoing_the_host_is_rejected() {
+    let (mut app, port) = hosting_app();
+    let mut guest = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    update_until(&mut app, |status| {
+        matches!(status, CollabStatus::Verifying { .. })
+    });
+
+    // Nothing comes from the host, so whatever it sent back the guest can only guess
+    guest
+        .set_read_timeout(Some(Duration::from_millis(200)))
+        .unwrap();
+    let mut reader = BufReader::new(guest.try_clone().unwrap());
+    let error = read_message(&mut reader).unwrap_err();
+    assert!(matches!(
+        error.kind(),
+        ErrorKind::WouldBlock | ErrorKind::TimedOut
+    ));
+
+    guest.write_all(hello("").as_bytes()).unwrap();
+    update_until(&mut app, |status| {
+        matches!(status, CollabStatus::Disconnected { .. })
+    });
+}
+
+#[test]
+fn the_right_token_is_answered_with_the_host_notes() {
+    let (mut app, port) = hosting_app();
+    let token = app.world().resource::<Collaboration>().token.clone();
+    assert_eq!(token.len(), 32);
+
+    let mut guest = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    guest.write_all(hello(&token).as_bytes()).unwrap();
+    update_until(&mut app, |status| {
+        matches!(status, CollabStatus::Connected { .. })
+    });
+
+    guest
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let mut reader = BufReader::new(guest);
+    let Some(Message::Hello { token: sent, .. }) = read_message(&mut reader).unwrap() else {
+        panic!("The host should answer with a Hello");
+    };
+    assert!(sent.is_empty());
+    assert!(matches!(
+        read_message(&mut reader).unwrap(),
+        Some(Message::Snapshot { .. })
+    ));
+}
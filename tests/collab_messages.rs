@@ -0,0 +1,45 @@
+// Messages from the other instance are read a line at a time, up to a limit
+
+use std::io::{self, BufReader, Cursor};
+
+use mm_modchart_maker::collab::protocol::{MAX_MESSAGE, Message, read_message};
+
+fn hello(token: &str) -> Message {
+    Message::Hello {
+        peer: 7,
+        token: token.to_string(),
+        map: "Song".to_string(),
+    }
+}
+
+#[test]
+fn messages_are_read_one_per_line() {
+    let mut text = String::new();
+    for token in ["first", "second"] {
+        text += &serde_json::to_string(&hello(token)).unwrap();
+        text.push('\n');
+    }
+    let mut reader = Cursor::new(text);
+
+    assert_eq!(read_message(&mut reader).unwrap(), Some(hello("first")));
+    assert_eq!(read_message(&mut reader).unwrap(), Some(hello("second")));
+    assert_eq!(read_message(&mut reader).unwrap(), None);
+}
+
+#[test]
+fn lines_over_the_limit_are_refused() {
+    // A line that never ends, reading it whole would never finish
+    let mut reader = BufReader::new(io::repeat(b'a'));
+
+    let error = read_message(&mut reader).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn a_line_just_under_the_limit_is_still_parsed() {
+    let line = serde_json::to_string(&hello("token")).unwrap();
+    let padding = " ".repeat(MAX_MESSAGE as usize - line.len() - 1);
+    let mut reader = Cursor::new(format!("{line}{padding}\n"));
+
+    assert_eq!(read_message(&mut reader).unwrap(), Some(hello("token")));
+}
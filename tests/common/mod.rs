@@ -0,0 +1,46 @@
+// Maps and objects shared by the integration tests, so a new Map field only needs a default here.
+// Every test file uses a different part of it
+#![allow(dead_code)]
+
+use bevy::math::Vec2;
+use mm_modchart_maker::maps::{
+    Map,
+    objects::{Note, NoteId, TimingPoint},
+};
+
+// An empty second long map titled after its id
+pub fn map(id: &str) -> Map {
+    let mut title = id.to_string();
+    if let Some(first) = title.get_mut(..1) {
+        first.make_ascii_uppercase();
+    }
+
+    Map {
+        id: id.to_string(),
+        title,
+        length: 1000,
+        ..Map::default()
+    }
+}
+
+pub fn note(millisecond: u32, x: f32, y: f32) -> Note {
+    note_at(millisecond, Vec2::new(x, y))
+}
+
+pub fn note_at(millisecond: u32, position: Vec2) -> Note {
+    Note {
+        id: NoteId::next(),
+        millisecond,
+        position,
+        hitsound: None,
+        color: None,
+    }
+}
+
+pub fn timing(millisecond: u32, bpm: f32) -> TimingPoint {
+    TimingPoint {
+        millisecond,
+        bpm,
+        beats_per_measure: 4,
+    }
+}
@@ -0,0 +1,78 @@
+// Covers come out of processing in the encoding asked for, at the size asked for, and read back
+
+use image::{Rgba, RgbaImage};
+use mm_modchart_maker::maps::cover::{self, CoverEncoding, CoverOptions};
+
+// Sizes that aren't whole 8x8 blocks, so the JPEG encoder's padding has to be cut off again
+fn gradient(width: u32, height: u32) -> RgbaImage {
+    RgbaImage::from_fn(width, height, |x, y| {
+        Rgba([(x * 255 / width) as u8, (y * 255 / height) as u8, 128, 255])
+    })
+}
+
+fn png(image: &RgbaImage) -> Vec<u8> {
+    cover::encode(image, CoverEncoding::Png).unwrap()
+}
+
+#[test]
+fn jpeg_covers_decode_to_the_same_size() {
+    let image = gradient(37, 21);
+
+    let bytes = cover::encode(&image, CoverEncoding::Jpeg { quality: 90 }).unwrap();
+    assert!(bytes.starts_with(&[0xFF, 0xD8]));
+    assert_eq!(cover::dimensions(&bytes), Some((37, 21)));
+
+    let decoded = cover::decode(&bytes).unwrap();
+    assert_eq!(decoded.dimensions(), (37, 21));
+
+    // Lossy, but close to the original everywhere
+    for (original, read) in image.pixels().zip(decoded.pixels()) {
+        for channel in 0..3 {
+            assert!(original.0[channel].abs_diff(read.0[channel]) < 24);
+        }
+        assert_eq!(read.0[3], 255);
+    }
+}
+
+#[test]
+fn processing_uses_the_chosen_encoding() {
+    let original = png(&gradient(300, 200));
+
+    let jpeg = cover::process(&original, &CoverOptions::default()).unwrap();
+    assert!(jpeg.starts_with(&[0xFF, 0xD8]));
+
+    let options = CoverOptions {
+        encoding: CoverEncoding::Png,
+        ..CoverOptions::default()
+    };
+    let png = cover::process(&original, &options).unwrap();
+    assert!(png.starts_with(b"\x89PNG"));
+}
+
+#[test]
+fn covers_are_cropped_square_and_scaled_down() {
+    let options = CoverOptions {
+        crop: true,
+        max_size: 64,
+        encoding: CoverEncoding::Jpeg { quality: 80 },
+    };
+
+    let processed = cover::process(&png(&gradient(300, 200)), &options).unwrap();
+    assert_eq!(cover::dimensions(&processed), Some((64, 64)));
+
+    let uncropped = CoverOptions {
+        crop: false,
+        ..options
+    };
+    let processed = cover::process(&png(&gradient(300, 200)), &uncropped).unwrap();
+    assert_eq!(cover::dimensions(&processed), Some((64, 43)));
+}
+
+#[test]
+fn transparent_covers_keep_their_alpha_as_png() {
+    let mut image = gradient(16, 16);
+    image.put_pixel(3, 4, Rgba([10, 20, 30, 0]));
+
+    let decoded = cover::decode(&png(&image)).unwrap();
+    assert_eq!(decoded, image);
+}
@@ -0,0 +1,46 @@
+// Raw mouse counts are scaled by DPI and sensitivity, smoothing only eases what is drawn
+
+use bevy::math::Vec2;
+use mm_modchart_maker::gameplay::{
+    InputDevice,
+    cursor::{CursorSettings, clamp_to_playfield, smooth},
+};
+
+#[test]
+fn counts_scale_by_dpi() {
+    let cursor = CursorSettings {
+        raw_input: true,
+        dpi: 800.0,
+        sensitivity: 2.0,
+        smoothing_ms: 0.0,
+    };
+
+    // An inch to the right and half an inch down
+    assert_eq!(cursor.scale(Vec2::new(800.0, 400.0)), Vec2::new(2.0, 1.0));
+
+    // The same hand movement on a mouse with twice the DPI
+    let fine = CursorSettings {
+        dpi: 1600.0,
+        ..cursor.clone()
+    };
+    assert_eq!(fine.scale(Vec2::new(1600.0, 800.0)), Vec2::new(2.0, 1.0));
+
+    assert!(cursor.raw(InputDevice::Mouse));
+    assert!(!cursor.raw(InputDevice::Tablet));
+    assert_eq!(
+        clamp_to_playfield(Vec2::new(10.0, -10.0)),
+        Vec2::new(3.0, -1.0)
+    );
+}
+
+#[test]
+fn smoothing_ignores_frame_rate() {
+    let target = Vec2::new(2.0, 0.0);
+    assert_eq!(smooth(Vec2::ZERO, target, 16.0, 0.0), target);
+
+    // One 20ms frame lands where two 10ms frames do, halfway at the half-life
+    let once = smooth(Vec2::ZERO, target, 20.0, 20.0);
+    let twice = smooth(smooth(Vec2::ZERO, target, 10.0, 20.0), target, 10.0, 20.0);
+    assert!(once.distance(twice) < 1e-5);
+    assert!(once.distance(Vec2::new(1.0, 0.0)) < 1e-5);
+}
@@ -0,0 +1,48 @@
+// The player runs as a preview with only its own plugin, the app's settings and theme are optional
+
+use bevy::{
+    asset::AssetPlugin, gizmos::GizmoPlugin, prelude::*, render::render_resource::Shader,
+    state::app::StatesPlugin,
+};
+use common::note;
+use mm_modchart_maker::{
+    maps::Map,
+    player::{
+        PlayerPlugin,
+        api::{LoadMap, Play, PlaybackStatus, Seek},
+    },
+};
+
+mod common;
+
+#[test]
+fn the_player_plugin_works_on_its_own() {
+    // The engine's assets are normally added by DefaultPlugins, which needs a window
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default(), StatesPlugin))
+        .init_asset::<Shader>()
+        .init_asset::<Mesh>()
+        .init_asset::<StandardMaterial>()
+        .add_plugins((GizmoPlugin, PlayerPlugin))
+        .init_asset::<Map>();
+
+    let map = Map {
+        notes: vec![note(500, 1.0, 1.0)],
+        length: 5000,
+        ..common::map("preview")
+    };
+    let handle = app.world_mut().resource_mut::<Assets<Map>>().add(map);
+
+    app.world_mut().send_event(LoadMap::Asset(handle.clone()));
+    app.update();
+    app.world_mut().send_event(Seek(1000.0));
+    app.world_mut().send_event(Play);
+    app.update();
+    app.update();
+
+    let status = app.world().resource::<PlaybackStatus>();
+    assert_eq!(status.map, Some(handle));
+    assert_eq!(status.title, "Preview");
+    assert!(status.playing);
+    assert!(status.millisecond >= 1000.0);
+}
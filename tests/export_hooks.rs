@@ -0,0 +1,52 @@
+// Hook commands are split without a shell and get the exported path in place of the placeholders
+
+use std::path::Path;
+
+use mm_modchart_maker::maps::hooks::{hook_args, split_command};
+
+#[test]
+fn quotes_group_arguments() {
+    assert_eq!(
+        split_command(r#"copy "C:\Games\Sound Space\maps" 'two words'  last"#).unwrap(),
+        vec![r"copy", r"C:\Games\Sound Space\maps", "two words", "last"]
+    );
+    assert!(split_command("upload \"unclosed").is_err());
+}
+
+#[test]
+fn placeholders_or_path_last() {
+    let path = Path::new("/exports/Song [Hard].sspm");
+
+    assert_eq!(
+        hook_args("validate --strict", path).unwrap(),
+        vec!["validate", "--strict", "/exports/Song [Hard].sspm"]
+    );
+    assert_eq!(
+        hook_args("scp {path} server:maps/{name}", path).unwrap(),
+        vec![
+            "scp",
+            "/exports/Song [Hard].sspm",
+            "server:maps/Song [Hard].sspm"
+        ]
+    );
+    assert_eq!(
+        hook_args("open {folder}", path).unwrap(),
+        vec!["open", "/exports"]
+    );
+    assert!(hook_args("  ", path).is_err());
+}
+
+#[test]
+fn placeholders_in_the_path_are_not_expanded() {
+    let path = Path::new("/exports/{folder}/{name}.sspm");
+
+    assert_eq!(
+        hook_args("copy {path} {folder} {name}", path).unwrap(),
+        vec![
+            "copy",
+            "/exports/{folder}/{name}.sspm",
+            "/exports/{folder}",
+            "{name}.sspm"
+        ]
+    );
+}
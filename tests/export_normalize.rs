@@ -0,0 +1,31 @@
+// Exports drop notes placed twice on the same spot, even though each copy has its own id
+
+use common::note;
+use mm_modchart_maker::maps::{Map, export::normalize};
+
+mod common;
+
+#[test]
+fn duplicate_notes_are_removed() {
+    let map = Map {
+        notes: vec![
+            note(500, 1.0, 1.0),
+            note(250, 0.0, 2.0),
+            note(500, 2.0, 0.0),
+            note(500, 1.0, 1.0),
+        ],
+        ..common::map("duplicates")
+    };
+    assert_ne!(map.notes[0].id, map.notes[3].id);
+
+    let notes: Vec<(u32, f32, f32)> = normalize(&map)
+        .notes
+        .iter()
+        .map(|note| (note.millisecond, note.position.x, note.position.y))
+        .collect();
+
+    assert_eq!(
+        notes,
+        vec![(250, 0.0, 2.0), (500, 1.0, 1.0), (500, 2.0, 0.0)]
+    );
+}
@@ -0,0 +1,138 @@
+// Exports over a configured size are warned about, and shrunk before writing when asked to
+
+use std::{env, fs};
+
+use common::note;
+use image::RgbaImage;
+use mm_modchart_maker::maps::{
+    Map, MapFormat,
+    cover::{self, CoverEncoding},
+    export::export_all,
+    io::WriteOptions,
+    limit::{SizeLimit, fit_to_limit},
+    size::SizeReport,
+};
+
+mod common;
+
+// Noise compresses badly, so the cover is most of the file
+fn noisy_cover(size: u32) -> Vec<u8> {
+    let mut seed = 0x2545_f491u32;
+    let image = RgbaImage::from_fn(size, size, |_, _| {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        let [r, g, b, _] = seed.to_le_bytes();
+        image::Rgba([r, g, b, 255])
+    });
+    cover::encode(&image, CoverEncoding::Png).unwrap()
+}
+
+fn map(cover: Vec<u8>) -> Map {
+    Map {
+        cover,
+        notes: vec![note(500, 1.0, 1.0)],
+        ..common::map("limit")
+    }
+}
+
+fn limit(max_mb: f32, shrink: bool) -> SizeLimit {
+    SizeLimit {
+        max_mb,
+        shrink,
+        cover_size: 64,
+        ..SizeLimit::default()
+    }
+}
+
+#[test]
+fn no_limit_skips_the_check() {
+    let mut map = map(noisy_cover(256));
+    let check = fit_to_limit(
+        &mut map,
+        MapFormat::SSPM,
+        &SizeLimit::default(),
+        WriteOptions::default(),
+    )
+    .unwrap();
+    assert!(check.is_none());
+}
+
+#[test]
+fn measured_size_matches_the_written_file() {
+    let map = map(noisy_cover(64));
+    let mut written = Vec::new();
+    MapFormat::SSPM
+        .serialize(
+            &map,
+            std::io::Cursor::new(&mut written),
+            WriteOptions::default(),
+        )
+        .unwrap();
+    assert_eq!(
+        SizeReport::measure(&map, MapFormat::SSPM, WriteOptions::default()).unwrap(),
+        written.len() as u64
+    );
+}
+
+#[test]
+fn oversized_exports_are_only_warned_about_without_shrinking() {
+    let original = map(noisy_cover(512));
+    let mut map = original.clone();
+
+    let check = fit_to_limit(
+        &mut map,
+        MapFormat::SSPM,
+        &limit(0.1, false),
+        WriteOptions::default(),
+    )
+    .unwrap()
+    .unwrap();
+    assert!(check.over());
+    assert_eq!(check.size, check.original);
+    assert!(check.steps.is_empty());
+    assert_eq!(map.cover, original.cover);
+    assert!(check.warnings()[0].contains("over the"));
+}
+
+#[test]
+fn shrinking_the_cover_fits_the_limit() {
+    let mut map = map(noisy_cover(512));
+
+    let check = fit_to_limit(
+        &mut map,
+        MapFormat::SSPM,
+        &limit(0.1, true),
+        WriteOptions::default(),
+    )
+    .unwrap()
+    .unwrap();
+    assert!(!check.over());
+    assert!(check.size < check.original);
+    assert_eq!(check.steps.len(), 1);
+    assert_eq!(cover::dimensions(&map.cover), Some((64, 64)));
+    // The warnings only say what was done
+    assert_eq!(check.warnings(), check.steps);
+}
+
+#[test]
+fn export_writes_the_shrunk_map() {
+    let folder = env::temp_dir().join(format!("mm-size-limit-{}", std::process::id()));
+    let map = map(noisy_cover(512));
+
+    let results = export_all(
+        &map,
+        &folder,
+        "{id}",
+        &limit(0.1, true),
+        WriteOptions::default(),
+    );
+    for export in results.iter() {
+        export.result.as_ref().unwrap();
+        let check = export.limit.as_ref().unwrap();
+        assert!(!check.over(), "{:?} is still over", export.format);
+        assert_eq!(fs::metadata(&export.path).unwrap().len(), check.size);
+    }
+
+    fs::remove_dir_all(folder).unwrap();
+}
@@ -0,0 +1,84 @@
+// Random fills stay inside the song and come out the same for the same seed
+
+use common::timing;
+use mm_modchart_maker::{
+    editor::fill::{FillSettings, random_fill},
+    maps::Map,
+};
+
+mod common;
+
+fn times(map: &Map, settings: &FillSettings) -> Vec<u32> {
+    random_fill(map, settings)
+        .iter()
+        .map(|note| note.millisecond)
+        .collect()
+}
+
+#[test]
+fn fills_stop_at_the_end_of_the_song() {
+    // 120 bpm in quarters is a note every 500ms
+    let map = Map {
+        timing_points: vec![timing(0, 120.0)],
+        length: 1200,
+        ..common::map("song")
+    };
+    let settings = FillSettings {
+        start: 0,
+        end: 5000,
+        divisor: 1,
+        ..FillSettings::default()
+    };
+
+    assert_eq!(times(&map, &settings), vec![0, 500, 1000]);
+}
+
+#[test]
+fn ranges_past_the_song_or_the_end_of_time_place_nothing() {
+    let map = Map {
+        timing_points: vec![timing(0, 120.0)],
+        length: 1000,
+        ..common::map("song")
+    };
+
+    let after = FillSettings {
+        start: 2000,
+        end: 3000,
+        ..FillSettings::default()
+    };
+    assert!(random_fill(&map, &after).is_empty());
+
+    let overflowing = FillSettings {
+        start: u32::MAX - 1,
+        end: u32::MAX,
+        ..FillSettings::default()
+    };
+    assert!(random_fill(&map, &overflowing).is_empty());
+}
+
+#[test]
+fn the_same_seed_places_the_same_notes() {
+    let map = Map {
+        timing_points: vec![timing(0, 150.0)],
+        length: 4000,
+        ..common::map("song")
+    };
+    let settings = FillSettings {
+        seed: 42,
+        end: 4000,
+        ..FillSettings::default()
+    };
+
+    let positions = |settings: &FillSettings| -> Vec<(f32, f32)> {
+        random_fill(&map, settings)
+            .iter()
+            .map(|note| (note.position.x, note.position.y))
+            .collect()
+    };
+
+    assert_eq!(positions(&settings), positions(&settings));
+    for pair in random_fill(&map, &settings).windows(2) {
+        let jump = pair[0].position.distance(pair[1].position);
+        assert!(jump > 0.0 && jump <= settings.max_jump + f32::EPSILON);
+    }
+}
@@ -0,0 +1,95 @@
+// The journal adds up sessions per week and counts retries by the section they were made in
+
+use std::{collections::BTreeMap, env, fs};
+
+use mm_modchart_maker::{
+    maps::{Bookmark, Map},
+    settings::journal::{Journal, JournalEntry, format_duration, section_at},
+};
+
+mod common;
+
+const DAY: u64 = 24 * 60 * 60;
+
+fn map(bookmarks: Vec<Bookmark>) -> Map {
+    Map {
+        length: 120_000,
+        bookmarks,
+        ..common::map("journal")
+    }
+}
+
+fn entry(map_id: &str, started: u64, retries: &[(&str, u32)]) -> JournalEntry {
+    JournalEntry {
+        started,
+        map_id: map_id.to_string(),
+        editing_secs: 600.0,
+        plays: 2,
+        retries: retries
+            .iter()
+            .map(|(section, count)| (section.to_string(), *count))
+            .collect::<BTreeMap<_, _>>(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn sessions_add_up_per_week() {
+    let now = 100 * DAY;
+    let mut journal = Journal::default();
+    journal.push(entry("a", now - 15 * DAY, &[("Drop", 3)]));
+    journal.push(entry("b", now - 2 * DAY, &[("Drop", 1), ("0:00-0:30", 2)]));
+    journal.push(entry("a", now - DAY, &[("Outro", 4)]));
+
+    let weeks = journal.weeks(now);
+    assert_eq!(
+        weeks.iter().map(|(ago, _)| *ago).collect::<Vec<_>>(),
+        vec![0, 2]
+    );
+    assert_eq!(weeks[0].1.editing_secs, 1200.0);
+    assert_eq!(weeks[0].1.plays, 4);
+    assert_eq!(weeks[0].1.total_retries(), 7);
+    assert_eq!(format_duration(weeks[0].1.editing_secs), "20m");
+    assert_eq!(format_duration(3.5 * 3600.0), "3h 30m");
+
+    // Only the map's own sessions count towards its sections
+    assert_eq!(
+        journal.retries_for("a"),
+        vec![("Outro".to_string(), 4), ("Drop".to_string(), 3)]
+    );
+}
+
+#[test]
+fn bookmarks_name_sections() {
+    let bookmark = |millisecond, label: &str| Bookmark {
+        millisecond,
+        label: label.to_string(),
+        color: [255, 255, 255],
+    };
+    let plain = map(Vec::new());
+    assert_eq!(section_at(&plain, 45_000.0), "0:30-1:00");
+
+    let marked = map(vec![bookmark(40_000, "Drop"), bookmark(80_000, " ")]);
+    assert_eq!(section_at(&marked, 10_000.0), "0:00-0:30");
+    assert_eq!(section_at(&marked, 79_999.0), "Drop");
+    assert_eq!(section_at(&marked, 95_000.0), "From 1:20");
+}
+
+#[test]
+fn saving_replaces_the_journal_in_one_step() {
+    let folder = env::temp_dir().join(format!("mm-journal-{}", std::process::id()));
+    fs::create_dir_all(&folder).unwrap();
+    let path = folder.join("journal.json");
+    fs::write(&path, "{\"entries\": []}").unwrap();
+
+    let mut journal = Journal::default();
+    journal.push(entry("a", DAY, &[("Drop", 2)]));
+    journal.save_to(&path).unwrap();
+
+    let saved: Journal = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(saved, journal);
+    // Nothing is left next to it once it has been moved into place
+    assert_eq!(fs::read_dir(&folder).unwrap().count(), 1);
+
+    fs::remove_dir_all(&folder).unwrap();
+}
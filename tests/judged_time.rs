@@ -0,0 +1,18 @@
+// Hits are judged against the song the player hears, not the raw clock
+
+use mm_modchart_maker::{player::playback::PlaybackClock, settings::Settings};
+
+#[test]
+fn offsets_move_the_judged_time() {
+    let mut clock = PlaybackClock::default();
+    clock.seek(1000.0);
+
+    assert_eq!(clock.judged_ms(&Settings::default()), 1000.0);
+
+    let settings = Settings {
+        audio_offset_ms: 40.0,
+        visual_offset_ms: 15.0,
+        ..Settings::default()
+    };
+    assert_eq!(clock.judged_ms(&settings), 1025.0);
+}
@@ -0,0 +1,109 @@
+// Hits, misses and combos trigger feedback that fades out, camera motion follows the comfort guard
+
+use mm_modchart_maker::{
+    gameplay::{
+        feedback::{Feedback, FeedbackSettings},
+        judgement::Judgement,
+    },
+    modchart::comfort::{ComfortSettings, RotationComfort},
+};
+
+fn perfect() -> Judgement {
+    Judgement::Hit {
+        window: 0,
+        offset_ms: 3.0,
+    }
+}
+
+#[test]
+fn only_perfect_hits_punch_and_misses_flash() {
+    let settings = FeedbackSettings::default();
+    let mut feedback = Feedback::default();
+
+    feedback.judge(
+        Judgement::Hit {
+            window: 1,
+            offset_ms: 40.0,
+        },
+        &settings,
+    );
+    assert!(feedback.is_idle());
+
+    feedback.judge(perfect(), &settings);
+    assert_eq!(feedback.punch, 1.0);
+    assert_eq!(feedback.flash, 0.0);
+
+    feedback.judge(Judgement::Miss, &settings);
+    assert_eq!(feedback.flash, 1.0);
+
+    // Both are gone within half a second
+    feedback.tick(0.5);
+    assert!(feedback.is_idle());
+}
+
+#[test]
+fn toggles_and_intensity_turn_effects_off() {
+    let mut feedback = Feedback::default();
+
+    let no_punch = FeedbackSettings {
+        camera_punch: false,
+        ..FeedbackSettings::default()
+    };
+    feedback.judge(perfect(), &no_punch);
+    assert_eq!(feedback.punch, 0.0);
+
+    let off = FeedbackSettings {
+        intensity: 0.0,
+        ..FeedbackSettings::default()
+    };
+    feedback.judge(Judgement::Miss, &off);
+    feedback.track_combo(100, &off);
+    assert!(feedback.is_idle());
+}
+
+#[test]
+fn milestones_show_once_when_crossed() {
+    let settings = FeedbackSettings {
+        milestone_every: 50,
+        ..FeedbackSettings::default()
+    };
+    let mut feedback = Feedback::default();
+
+    feedback.track_combo(49, &settings);
+    assert!(feedback.milestone.is_none());
+
+    // Several hits in one frame still count the milestone they passed
+    feedback.track_combo(52, &settings);
+    assert_eq!(feedback.milestone.map(|(combo, _)| combo), Some(50));
+
+    feedback.tick(2.0);
+    feedback.track_combo(60, &settings);
+    assert!(feedback.milestone.is_none());
+
+    // Breaking the combo starts counting again
+    feedback.track_combo(0, &settings);
+    feedback.track_combo(50, &settings);
+    assert_eq!(feedback.milestone.map(|(combo, _)| combo), Some(50));
+}
+
+#[test]
+fn camera_punch_respects_the_motion_guard() {
+    let settings = FeedbackSettings::default();
+    let mut feedback = Feedback::default();
+    feedback.judge(perfect(), &settings);
+
+    let offset = |rotation| {
+        feedback.camera_offset(
+            &settings,
+            &ComfortSettings {
+                rotation,
+                ..ComfortSettings::default()
+            },
+        )
+    };
+
+    let full = offset(RotationComfort::Full);
+    assert!(full > 0.0);
+    assert_eq!(offset(RotationComfort::Capped), full * 0.5);
+    assert_eq!(offset(RotationComfort::Tilt), 0.0);
+}
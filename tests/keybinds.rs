@@ -0,0 +1,13 @@
+// Every action has a default chord of its own, so none of them is shadowed out of the box
+
+use mm_modchart_maker::editor::shortcuts::{EditorAction, Keybinds};
+
+#[test]
+fn default_bindings_do_not_conflict() {
+    let keybinds = Keybinds::default();
+
+    assert!(keybinds.conflicts().is_empty());
+    for action in EditorAction::ALL {
+        assert_eq!(keybinds.get(action), Some(action.default_chord()));
+    }
+}
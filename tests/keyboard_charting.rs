@@ -0,0 +1,62 @@
+// The numpad is laid out like the grid by default, and deleting by cell only touches the note under the playhead
+
+use bevy::{input::keyboard::KeyCode, math::Vec2};
+use common::note;
+use mm_modchart_maker::editor::{
+    commands::{EditCommand, Selection},
+    keyboard::cell_for_key,
+    shortcuts::{EditorAction, KeyChord, Keybinds},
+};
+
+mod common;
+
+#[test]
+fn numpad_matches_the_grid() {
+    let keybinds = Keybinds::default();
+
+    assert_eq!(
+        cell_for_key(&keybinds, KeyCode::Numpad7),
+        Some(Vec2::new(0.0, 0.0))
+    );
+    assert_eq!(
+        cell_for_key(&keybinds, KeyCode::Numpad5),
+        Some(Vec2::new(1.0, 1.0))
+    );
+    assert_eq!(
+        cell_for_key(&keybinds, KeyCode::Numpad3),
+        Some(Vec2::new(2.0, 2.0))
+    );
+    assert_eq!(cell_for_key(&keybinds, KeyCode::Numpad0), None);
+}
+
+#[test]
+fn cells_follow_their_bindings() {
+    let mut keybinds = Keybinds::default();
+    keybinds
+        .bind(EditorAction::ChartCellTopLeft, KeyChord::new(KeyCode::KeyQ))
+        .unwrap();
+
+    assert_eq!(
+        cell_for_key(&keybinds, KeyCode::KeyQ),
+        Some(Vec2::new(0.0, 0.0))
+    );
+    assert_eq!(cell_for_key(&keybinds, KeyCode::Numpad7), None);
+}
+
+#[test]
+fn delete_at_the_playhead() {
+    let original = vec![note(0, 1.0, 1.0), note(250, 1.0, 1.0), note(250, 2.0, 0.0)];
+    let mut notes = original.clone();
+    let mut selection = Selection::default();
+
+    let change = EditCommand::DeleteAt {
+        position: Vec2::new(1.0, 1.0),
+    }
+    .plan(&notes, &selection, 250);
+
+    change.apply(&mut notes, &mut selection);
+    assert_eq!(notes, [original[0], original[2]]);
+
+    change.revert(&mut notes, &mut selection);
+    assert_eq!(notes, original);
+}
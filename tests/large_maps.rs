@@ -0,0 +1,61 @@
+// Editor views only touch the notes on screen, so meme charts with 500k notes stay responsive
+
+use std::collections::BTreeSet;
+
+use common::note;
+use mm_modchart_maker::{
+    editor::windowing::{marked_columns, notes_between, one_per_column},
+    maps::objects::Note,
+};
+
+mod common;
+
+const NOTES: usize = 500_000;
+
+// A note every millisecond cycling through the grid, about eight minutes of them
+fn notes() -> Vec<Note> {
+    (0..NOTES)
+        .map(|index| note(index as u32, (index % 3) as f32, (index / 3 % 3) as f32))
+        .collect()
+}
+
+#[test]
+fn zoomed_out_views_draw_one_note_per_pixel() {
+    let notes = notes();
+    assert_eq!(notes_between(&notes, 1000.5, 2000.0), 1001..2001);
+    assert_eq!(notes_between(&notes, 600_000.0, 700_000.0), NOTES..NOTES);
+
+    // The whole map on 700 pixels and 9 lanes
+    let columns = 700.0;
+    let column = |i: usize| (notes[i].millisecond as f64 / NOTES as f64 * columns) as i32;
+    let lane = |i: usize| {
+        let position = notes[i].position;
+        position.y as usize * 3 + position.x as usize
+    };
+    let marked = |i: usize| i == 250_010;
+    let drawn = one_per_column(0..NOTES, 9, column, lane, marked);
+    assert_eq!(drawn.len(), 700 * 9);
+
+    // The selected note wins over the first one in its pixel and lane
+    assert!(drawn.contains(&250_010));
+    assert!(!drawn.contains(&250_001));
+    assert!(drawn.contains(&250_002));
+}
+
+#[test]
+fn selection_ticks_are_bounded_by_the_width() {
+    let notes = notes();
+    let everything: BTreeSet<usize> = (0..NOTES).collect();
+    let columns = marked_columns(&notes, &everything, NOTES as f64, 1000, 0);
+    assert_eq!(columns, (0..1000).collect::<Vec<_>>());
+
+    // A few notes are placed directly, a large part of the map is looked up per column
+    let few = BTreeSet::from([0, 999, 1000, 499_999]);
+    assert_eq!(
+        marked_columns(&notes, &few, NOTES as f64, 1000, 0),
+        vec![0, 1, 2, 999]
+    );
+    let second_half: BTreeSet<usize> = (250_000..NOTES).collect();
+    let columns = marked_columns(&notes, &second_half, NOTES as f64, 1000, -250_000);
+    assert_eq!(columns, (0..500).collect::<Vec<_>>());
+}
@@ -0,0 +1,80 @@
+// Legacy text maps are read in either field order and written back in the one the game reads
+
+use std::io::Cursor;
+
+use bevy::math::Vec2;
+use mm_modchart_maker::maps::{
+    Map,
+    legacy::{LegacySerializer, is_legacy_start},
+    library::is_map_file,
+    parser::{MapSerializer, is_quantum},
+};
+
+fn read(text: &str) -> std::io::Result<Map> {
+    LegacySerializer::deserialize(Cursor::new(text.as_bytes().to_vec()))
+}
+
+fn notes(map: &Map) -> Vec<(u32, Vec2)> {
+    map.notes
+        .iter()
+        .map(|note| (note.millisecond, note.position))
+        .collect()
+}
+
+#[test]
+fn both_field_orders() {
+    let expected = [
+        (0, Vec2::new(1.0, 1.0)),
+        (1200, Vec2::new(2.0, 0.0)),
+        (1650, Vec2::new(0.5, 1.75)),
+    ];
+
+    let game = read("123456,1|1|0,2|0|1200,0.5|1.75|1650,").unwrap();
+    assert_eq!(game.id, "123456");
+    assert_eq!(notes(&game), expected);
+
+    let time_first = read("123456,1200|2|0,0|1|1,1650|0.5|1.75").unwrap();
+    assert_eq!(notes(&time_first), expected);
+    assert!(is_quantum(time_first.notes[2].position));
+}
+
+#[test]
+fn written_the_way_it_was_read() {
+    let text = "123456,1|1|0,2|0|1200,0.5|1.75|1650";
+    let map = read(text).unwrap();
+
+    let mut written = Cursor::new(Vec::new());
+    LegacySerializer::serialize(&map, &mut written).unwrap();
+    assert_eq!(String::from_utf8(written.into_inner()).unwrap(), text);
+}
+
+#[test]
+fn other_text_is_refused() {
+    assert!(read("Thanks for downloading").is_err());
+    assert!(read("123456,1|1").is_err());
+    assert!(read("123456,left|1|0").is_err());
+}
+
+#[test]
+fn only_map_text_is_listed() {
+    assert!(is_legacy_start("123456,1|1|0,2|0|1200"));
+    assert!(is_legacy_start("\u{feff}song id, 1 | 1 | 0"));
+    assert!(!is_legacy_start(
+        "Credits\nSong by someone, mapped by someone else"
+    ));
+    assert!(!is_legacy_start("Thanks for downloading, have fun"));
+    assert!(!is_legacy_start("123456"));
+
+    let folder = std::env::temp_dir().join(format!("mm-legacy-{}", std::process::id()));
+    std::fs::create_dir_all(&folder).unwrap();
+    let map = folder.join("song.txt");
+    let readme = folder.join("readme.txt");
+    std::fs::write(&map, "123456,1|1|0").unwrap();
+    std::fs::write(&readme, "Read me, please").unwrap();
+
+    assert!(is_map_file(&map));
+    assert!(!is_map_file(&readme));
+    assert!(is_map_file(&folder.join("song.sspm")));
+
+    std::fs::remove_dir_all(&folder).unwrap();
+}
@@ -0,0 +1,74 @@
+// Timed text imported from LRC files and kept in SSPM maps as custom objects
+
+use std::io::Cursor;
+
+use mm_modchart_maker::maps::{
+    Map,
+    lrc::parse_lrc,
+    objects::{Lyric, LyricStyle, shown_lyrics},
+    parser::{MapSerializer, SSPMSerializer},
+};
+
+mod common;
+
+fn lyric(millisecond: u32, duration_ms: u32, text: &str, style: LyricStyle) -> Lyric {
+    Lyric {
+        millisecond,
+        duration_ms,
+        text: text.to_string(),
+        style,
+    }
+}
+
+#[test]
+fn lrc_lines_last_until_the_next() {
+    let lrc = "[ti:Song]\n\
+               [offset:+100]\n\
+               [00:01.50]First <00:02.00>line\n\
+               [00:04.00][00:10.00]Chorus\n\
+               [00:06.00]\n\
+               [00:08:25]Second";
+    let lyrics = parse_lrc(lrc).unwrap();
+
+    assert_eq!(
+        lyrics,
+        vec![
+            lyric(1400, 2500, "First line", LyricStyle::Subtitle),
+            lyric(3900, 2000, "Chorus", LyricStyle::Subtitle),
+            lyric(8150, 1750, "Second", LyricStyle::Subtitle),
+            lyric(9900, 4000, "Chorus", LyricStyle::Subtitle),
+        ]
+    );
+    assert_eq!(
+        shown_lyrics(&lyrics, 5000.0)
+            .map(|l| &l.text)
+            .collect::<Vec<_>>(),
+        ["Chorus"]
+    );
+    assert_eq!(shown_lyrics(&lyrics, 7000.0).count(), 0);
+
+    assert!(parse_lrc("[ar:Nobody]\nno times here").is_err());
+}
+
+#[test]
+fn lyrics_survive_sspm() {
+    let lyrics = vec![
+        lyric(0, 1500, "Title card", LyricStyle::Title),
+        lyric(2000, 800, "Words, with ünïcode", LyricStyle::Subtitle),
+        lyric(2000, 3000, "Caption", LyricStyle::Caption),
+    ];
+    let map = Map {
+        length: 5000,
+        mappers: vec!["mapper".to_string()],
+        lyrics: lyrics.clone(),
+        ..common::map("lyrics")
+    };
+
+    let mut file = Cursor::new(Vec::new());
+    SSPMSerializer::serialize(&map, &mut file).unwrap();
+    let read = SSPMSerializer::deserialize(Cursor::new(file.into_inner())).unwrap();
+
+    assert_eq!(read.lyrics, lyrics);
+    // Taken out of the raw objects, so they aren't written twice
+    assert!(read.objects.is_empty());
+}
@@ -0,0 +1,26 @@
+// Files that come with a map are checked along with it
+
+use std::{io::Cursor, path::Path};
+
+use mm_modchart_maker::{
+    maps::dependencies::{check_dependency, dependencies},
+    modchart::{
+        Modchart,
+        sidecar::{sidecar_path, write_modchart},
+    },
+};
+
+#[test]
+fn sidecars_are_dependencies() {
+    let map = Path::new("maps/song.sspm");
+    assert_eq!(dependencies(map), [sidecar_path(map)]);
+
+    let mut sidecar = Cursor::new(Vec::new());
+    write_modchart(&Modchart::default(), &mut sidecar).unwrap();
+    let bytes = sidecar.into_inner();
+    assert!(check_dependency(&sidecar_path(map), &bytes).is_ok());
+
+    // Cut off halfway through the header
+    assert!(check_dependency(&sidecar_path(map), &bytes[..3]).is_err());
+    assert!(check_dependency(&sidecar_path(map), b"not a modchart").is_err());
+}
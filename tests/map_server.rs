@@ -0,0 +1,95 @@
+// The map server answers from what it has read, ids are made safe for URLs and kept unique
+
+use std::{env, fs, path::PathBuf};
+
+use common::note;
+use mm_modchart_maker::maps::{Map, metadata::write_map, server::ServedLibrary};
+use serde_json::Value;
+
+mod common;
+
+fn map(id: &str, notes: u32) -> Map {
+    Map {
+        id: id.to_string(),
+        title: "Song".to_string(),
+        length: 2000,
+        difficulty: 3,
+        mappers: vec!["mapper".to_string()],
+        notes: (0..notes)
+            .map(|i| note(i * 100, (i % 3) as f32, 1.0))
+            .collect(),
+        ..common::map("song")
+    }
+}
+
+fn body(library: &ServedLibrary, target: &str) -> (u16, Value) {
+    let response = library.handle("GET", target);
+    (
+        response.status,
+        serde_json::from_slice(&response.body).unwrap(),
+    )
+}
+
+#[test]
+fn lists_and_describes_maps() {
+    let mut library = ServedLibrary::new(Vec::new());
+    library.add(PathBuf::from("a/song.sspm"), None, &map("my song/hard", 12));
+    library.add(PathBuf::from("b/song.sspm"), None, &map("my song/hard", 4));
+
+    let (status, list) = body(&library, "/maps?page=1");
+    assert_eq!(status, 200);
+    let ids: Vec<&str> = list
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|map| map["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, vec!["my_song_hard", "my_song_hard-2"]);
+
+    let (_, summary) = body(&library, "/maps/my_song_hard-2");
+    assert_eq!(summary["notes"], 4);
+    assert_eq!(summary["difficulty_name"], "Hard");
+    assert_eq!(summary["file"], "song.sspm");
+
+    let (_, stats) = body(&library, "/maps/my_song_hard/stats");
+    assert_eq!(stats["peak_notes_per_second"], 10);
+    assert_eq!(stats["sample_ms"], 250);
+    assert!(stats["peak_strain"].as_f64().unwrap() > 0.0);
+}
+
+#[test]
+fn unknown_requests_fail() {
+    let library = ServedLibrary::new(Vec::new());
+
+    assert_eq!(library.handle("GET", "/maps/nothing").status, 404);
+    assert_eq!(library.handle("GET", "/songs").status, 404);
+    assert_eq!(library.handle("POST", "/maps").status, 405);
+}
+
+#[test]
+fn rescans_are_read_apart_from_the_library() {
+    let folder = env::temp_dir().join(format!("mm-server-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(&folder).unwrap();
+
+    write_map(&folder.join("first.sspm"), &map("first", 3)).unwrap();
+    let mut library = ServedLibrary::new(vec![folder.clone()]);
+    library.rescan();
+    assert_eq!(library.len(), 1);
+
+    // The plan is all a rescan needs, maps read meanwhile only show up once merged
+    let plan = library.plan_scan();
+    write_map(&folder.join("second.sspm"), &map("second", 5)).unwrap();
+    let scan = plan.run();
+    assert_eq!(library.len(), 1);
+    library.merge(scan);
+    assert_eq!(library.len(), 2);
+    assert_eq!(body(&library, "/maps/second").1["notes"], 5);
+
+    fs::remove_file(folder.join("first.sspm")).unwrap();
+    library.rescan();
+    assert_eq!(body(&library, "/maps/first").0, 404);
+    assert_eq!(library.len(), 1);
+
+    let _ = fs::remove_dir_all(&folder);
+}
@@ -0,0 +1,96 @@
+// Mashups place the second map after the first, or on top of it, without touching either
+
+use common::{note, timing};
+use mm_modchart_maker::maps::{
+    Map,
+    mashup::{MashupMode, mashup},
+};
+
+mod common;
+
+fn notes(map: &Map) -> Vec<(u32, f32)> {
+    map.notes
+        .iter()
+        .map(|note| (note.millisecond, note.position.x))
+        .collect()
+}
+
+fn first() -> Map {
+    Map {
+        notes: vec![note(100, 0.0, 0.0), note(500, 1.0, 1.0)],
+        timing_points: vec![timing(0, 120.0)],
+        mappers: vec!["A".to_string()],
+        length: 1000,
+        ..common::map("first")
+    }
+}
+
+fn second() -> Map {
+    Map {
+        notes: vec![note(0, 2.0, 2.0), note(500, 1.0, 1.0)],
+        timing_points: vec![timing(0, 150.0)],
+        mappers: vec!["A".to_string(), "B".to_string()],
+        length: 800,
+        ..common::map("second")
+    }
+}
+
+#[test]
+fn appended_maps_start_after_the_gap() {
+    let map = mashup(&first(), &second(), MashupMode::Append { gap_ms: 200 }).unwrap();
+
+    assert_eq!(
+        notes(&map),
+        vec![(100, 0.0), (500, 1.0), (1200, 2.0), (1700, 1.0)]
+    );
+    let timings: Vec<(u32, f32)> = map
+        .timing_points
+        .iter()
+        .map(|timing| (timing.millisecond, timing.bpm))
+        .collect();
+    assert_eq!(timings, vec![(0, 120.0), (1200, 150.0)]);
+    assert_eq!(map.length, 2000);
+    assert_eq!(map.id, "first+second");
+    assert_eq!(map.mappers, vec!["A", "B"]);
+}
+
+#[test]
+fn appending_waits_for_notes_past_the_length() {
+    let mut first = first();
+    first.notes.push(note(1500, 0.0, 0.0));
+
+    let map = mashup(&first, &second(), MashupMode::Append { gap_ms: 0 }).unwrap();
+
+    assert_eq!(
+        notes(&map),
+        vec![
+            (100, 0.0),
+            (500, 1.0),
+            (1500, 0.0),
+            (1500, 2.0),
+            (2000, 1.0)
+        ]
+    );
+    assert_eq!(map.length, 2300);
+}
+
+#[test]
+fn overlaid_notes_on_the_same_spot_are_kept_once() {
+    let map = mashup(&first(), &second(), MashupMode::Overlay).unwrap();
+
+    assert_eq!(notes(&map), vec![(0, 2.0), (100, 0.0), (500, 1.0)]);
+    assert_eq!(map.timing_points.len(), 1);
+    assert_eq!(map.timing_points[0].bpm, 120.0);
+    assert_eq!(map.length, 1000);
+}
+
+#[test]
+fn the_inputs_are_left_alone() {
+    let (first, second) = (first(), second());
+
+    let map = mashup(&first, &second, MashupMode::Overlay).unwrap();
+
+    assert_eq!(first.notes.len(), 2);
+    assert_eq!(second.notes[0].millisecond, 0);
+    assert!(map.notes.iter().all(|note| note.id != second.notes[0].id));
+}
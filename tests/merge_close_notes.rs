@@ -0,0 +1,98 @@
+// Notes a few ms apart from float rounding in other tools are collapsed onto one time
+
+use common::note;
+use mm_modchart_maker::{
+    editor::commands::{EditCommand, Selection, execute},
+    maps::{
+        Map,
+        merge::{close_clusters, merge_on_open, plan_merge},
+        objects::Note,
+    },
+};
+
+mod common;
+
+fn times(notes: &[Note]) -> Vec<u32> {
+    notes.iter().map(|note| note.millisecond).collect()
+}
+
+#[test]
+fn clusters_are_measured_from_their_first_note() {
+    let notes = vec![
+        note(100, 0.0, 0.0),
+        note(101, 0.0, 0.0),
+        note(102, 1.0, 1.0),
+        note(103, 2.0, 2.0),
+        note(200, 1.0, 1.0),
+        note(200, 2.0, 1.0),
+    ];
+
+    // 103 is 3ms from the start of the cluster, a stream isn't chained into one
+    assert_eq!(close_clusters(&notes, 2), vec![0..3]);
+    assert_eq!(close_clusters(&notes, 5), vec![0..4]);
+    // Chords at exactly one time are left alone
+    assert!(close_clusters(&notes, 0).is_empty());
+}
+
+#[test]
+fn duplicates_merge_and_jumps_align() {
+    let notes = vec![
+        note(100, 0.0, 0.0),
+        note(101, 0.0, 0.0),
+        note(101, 2.0, 2.0),
+        note(300, 1.0, 1.0),
+        note(300, 1.0, 1.0),
+    ];
+
+    let (removed, moved, report) = plan_merge(&notes, 2);
+    assert_eq!(removed.into_iter().collect::<Vec<_>>(), [1, 2, 4]);
+    assert_eq!(report.merged, 2);
+    assert_eq!(report.aligned, 1);
+    assert_eq!(moved.len(), 1);
+    assert_eq!(moved[0].millisecond, 100);
+    assert_eq!(moved[0].id, notes[2].id);
+}
+
+#[test]
+fn merging_is_a_single_undo_step() {
+    let notes = vec![
+        note(0, 1.0, 1.0),
+        note(499, 0.0, 0.0),
+        note(500, 0.0, 0.0),
+        note(501, 2.0, 0.0),
+        note(1000, 1.0, 1.0),
+    ];
+    let mut edited = notes.clone();
+    let mut selection = Selection::default();
+
+    let entry = execute(
+        &[EditCommand::MergeClose { epsilon_ms: 2 }],
+        "Merge".to_string(),
+        &mut edited,
+        &mut selection,
+        0,
+    );
+    assert_eq!(times(&edited), [0, 499, 499, 1000]);
+    assert_eq!(edited[1].id, notes[1].id);
+
+    for change in entry.changes.iter().rev() {
+        change.revert(&mut edited, &mut selection);
+    }
+    assert_eq!(edited, notes);
+}
+
+#[test]
+fn opening_merges_only_when_turned_on() {
+    let mut map = Map {
+        notes: vec![note(100, 0.0, 0.0), note(101, 0.0, 0.0)],
+        ..common::map("close")
+    };
+
+    merge_on_open(&mut map, 0);
+    assert_eq!(times(&map.notes), vec![100, 101]);
+    assert!(map.load_warnings.is_empty());
+
+    merge_on_open(&mut map, 2);
+    assert_eq!(times(&map.notes), vec![100]);
+    assert_eq!(map.load_warnings.len(), 1);
+}
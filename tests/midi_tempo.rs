@@ -0,0 +1,129 @@
+// Tempo maps exported by DAWs as MIDI files become timing points
+
+use std::io::Cursor;
+
+use mm_modchart_maker::{
+    editor::commands::{EditHistory, HistoryEntry, Selection, TimingChange},
+    maps::{
+        objects::TimingPoint,
+        tempo::{offset_timing_points, read_midi_tempo_map},
+    },
+};
+
+fn chunk(id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut bytes = id.to_vec();
+    bytes.extend((data.len() as u32).to_be_bytes());
+    bytes.extend(data);
+    bytes
+}
+
+fn midi(division: u16, tracks: &[&[u8]]) -> Vec<u8> {
+    let mut header = vec![0, 1];
+    header.extend((tracks.len() as u16).to_be_bytes());
+    header.extend(division.to_be_bytes());
+
+    let mut file = chunk(b"MThd", &header);
+    for track in tracks {
+        file.extend(chunk(b"MTrk", track));
+    }
+    file
+}
+
+fn point(millisecond: u32, bpm: f32, beats_per_measure: u8) -> TimingPoint {
+    TimingPoint {
+        millisecond,
+        bpm,
+        beats_per_measure,
+    }
+}
+
+#[test]
+fn tempo_changes_become_timing_points() {
+    // 120 BPM in 4/4, then 150 BPM after four beats and 6/8 two beats later at the new tempo.
+    // Deltas over 127 ticks take two bytes
+    let tempo: &[u8] = &[
+        0x00, 0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20, // 500000us
+        0x00, 0xFF, 0x58, 0x04, 0x04, 0x02, 0x18, 0x08, // 4/4
+        0x8F, 0x00, 0xFF, 0x51, 0x03, 0x06, 0x1A, 0x80, // 1920 ticks, 400000us
+        0x87, 0x40, 0xFF, 0x58, 0x04, 0x06, 0x03, 0x18, 0x08, // 960 ticks, 6/8
+        0x00, 0xFF, 0x2F, 0x00,
+    ];
+    // Notes with running status and a sysex message are skipped over
+    let notes: &[u8] = &[
+        0x00, 0xF0, 0x03, 0x7E, 0x09, 0xF7, // sysex
+        0x00, 0x90, 0x3C, 0x64, // note on
+        0x83, 0x60, 0x3C, 0x00, // running status note off after 480 ticks
+        0x00, 0xC0, 0x05, // program change
+        0x00, 0xFF, 0x2F, 0x00,
+    ];
+    let file = midi(480, &[tempo, notes]);
+
+    let points = read_midi_tempo_map(Cursor::new(file)).unwrap();
+    // A bar of 6/8 lasts 3 quarter notes
+    assert_eq!(
+        points,
+        vec![
+            point(0, 120.0, 4),
+            point(2000, 150.0, 4),
+            point(2800, 150.0, 3)
+        ]
+    );
+}
+
+#[test]
+fn offsets_keep_the_starting_tempo() {
+    let points = vec![
+        point(0, 120.0, 4),
+        point(2000, 150.0, 4),
+        point(5000, 90.0, 4),
+    ];
+
+    assert_eq!(
+        offset_timing_points(&points, -3000),
+        vec![point(0, 150.0, 4), point(2000, 90.0, 4)]
+    );
+    assert_eq!(offset_timing_points(&points, 500)[0], point(500, 120.0, 4));
+
+    // Files without a tempo track play at 120 BPM, frame timed ones have no beats at all
+    let empty = midi(96, &[&[0x00, 0xFF, 0x2F, 0x00]]);
+    assert_eq!(
+        read_midi_tempo_map(Cursor::new(empty)).unwrap(),
+        vec![point(0, 120.0, 4)]
+    );
+    let smpte = midi(0xE728, &[]);
+    assert!(read_midi_tempo_map(Cursor::new(smpte)).is_err());
+}
+
+#[test]
+fn events_longer_than_their_track_are_refused() {
+    // A meta event claiming about 256MB in a track of a few bytes
+    let meta: &[u8] = &[0x00, 0xFF, 0x01, 0xFF, 0xFF, 0xFF, 0x7F, 0x00];
+    assert!(read_midi_tempo_map(Cursor::new(midi(480, &[meta]))).is_err());
+
+    let sysex: &[u8] = &[0x00, 0xF0, 0x84, 0x00, 0xF7];
+    assert!(read_midi_tempo_map(Cursor::new(midi(480, &[sysex]))).is_err());
+}
+
+#[test]
+fn imported_timing_undoes_in_one_step() {
+    let before = vec![point(0, 120.0, 4)];
+    let imported = vec![point(0, 150.0, 4), point(2000, 90.0, 3)];
+
+    let mut history = EditHistory::default();
+    history.push(HistoryEntry {
+        label: "Import tempo map".to_string(),
+        changes: Vec::new(),
+        timing: Some(TimingChange {
+            before: before.clone(),
+            after: imported.clone(),
+        }),
+    });
+
+    let (mut notes, mut selection) = (Vec::new(), Selection::default());
+    let mut timing_points = imported.clone();
+
+    history.undo(&mut notes, &mut timing_points, &mut selection);
+    assert_eq!(timing_points, before);
+    history.redo(&mut notes, &mut timing_points, &mut selection);
+    assert_eq!(timing_points, imported);
+}
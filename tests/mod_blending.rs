@@ -0,0 +1,103 @@
+// Overlapping events on a channel stack in the order they start, whatever order they are listed in
+
+use std::io::Cursor;
+
+use bevy::math::Vec3;
+use mm_modchart_maker::modchart::{
+    Keyframe, ModBlend, ModChannel, ModEvent, Modchart, ScreenFrame,
+    random::ModRandom,
+    script::{read_script, write_script},
+    sidecar::{read_modchart, write_modchart},
+};
+
+fn held(channel: ModChannel, start: u32, value: Vec3, blend: ModBlend) -> ModEvent {
+    ModEvent::new(channel, vec![Keyframe::new(start, value)]).with_blend(blend)
+}
+
+fn rotation_at(modchart: &Modchart, millisecond: f64) -> Vec3 {
+    modchart
+        .evaluate(millisecond, &ScreenFrame::default(), &ModRandom::default())
+        .get(ModChannel::CameraRotation)
+}
+
+#[test]
+fn overlapping_events_stack_by_start() {
+    let rotation = ModChannel::CameraRotation;
+    let modchart = Modchart::new(vec![
+        held(rotation, 3000, Vec3::splat(0.5), ModBlend::Max),
+        held(rotation, 2000, Vec3::new(2.0, 1.0, 0.0), ModBlend::Multiply),
+        held(rotation, 1000, Vec3::X, ModBlend::Add),
+        held(rotation, 0, Vec3::Y, ModBlend::Override),
+    ]);
+
+    assert_eq!(rotation_at(&modchart, 500.0), Vec3::Y);
+    assert_eq!(rotation_at(&modchart, 1500.0), Vec3::new(1.0, 1.0, 0.0));
+    assert_eq!(rotation_at(&modchart, 2500.0), Vec3::new(2.0, 1.0, 0.0));
+    assert_eq!(rotation_at(&modchart, 3500.0), Vec3::new(2.0, 1.0, 0.5));
+
+    // A later override hides everything before it
+    let mut overridden = modchart.events().to_vec();
+    overridden.push(held(rotation, 4000, Vec3::Z, ModBlend::Override));
+    let overridden = Modchart::new(overridden);
+    assert_eq!(rotation_at(&overridden, 4500.0), Vec3::Z);
+}
+
+#[test]
+fn ties_keep_list_order() {
+    let scale = ModChannel::PlayfieldScale;
+    let add_first = Modchart::new(vec![
+        held(scale, 0, Vec3::ONE, ModBlend::Add),
+        held(scale, 0, Vec3::splat(3.0), ModBlend::Multiply),
+    ]);
+    let multiply_first = Modchart::new(add_first.events().iter().rev().cloned().collect());
+
+    let frame = ScreenFrame::default();
+    let random = ModRandom::default();
+    // Both start from the channel's default of one
+    assert_eq!(
+        add_first.evaluate(0.0, &frame, &random).get(scale),
+        Vec3::splat(6.0)
+    );
+    assert_eq!(
+        multiply_first.evaluate(0.0, &frame, &random).get(scale),
+        Vec3::splat(4.0)
+    );
+}
+
+#[test]
+fn edited_events_are_ordered_again() {
+    let rotation = ModChannel::CameraRotation;
+    let mut modchart = Modchart::new(vec![
+        held(rotation, 0, Vec3::Y, ModBlend::Override),
+        held(rotation, 1000, Vec3::X, ModBlend::Override),
+    ]);
+    assert_eq!(rotation_at(&modchart, 1500.0), Vec3::X);
+
+    // Moving the first event after the second puts it on top
+    modchart.events_mut()[0] = held(rotation, 1200, Vec3::Y, ModBlend::Override);
+    assert_eq!(rotation_at(&modchart, 1500.0), Vec3::Y);
+
+    modchart
+        .events_mut()
+        .push(held(rotation, 1400, Vec3::Z, ModBlend::Override));
+    assert_eq!(rotation_at(&modchart, 1500.0), Vec3::Z);
+}
+
+#[test]
+fn blends_are_saved() {
+    let modchart = Modchart::new(
+        ModBlend::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, blend)| held(ModChannel::PlayfieldOffset, i as u32 * 100, Vec3::X, *blend))
+            .collect(),
+    );
+
+    let mut sidecar = Cursor::new(Vec::new());
+    write_modchart(&modchart, &mut sidecar).unwrap();
+    sidecar.set_position(0);
+    assert_eq!(read_modchart(sidecar).unwrap().events(), modchart.events());
+
+    let script = write_script(&modchart).unwrap();
+    assert_eq!(read_script(&script).unwrap().events(), modchart.events());
+}
@@ -0,0 +1,65 @@
+// Mod packs carry their modcharts and skins through a zip, and entries that would unpack outside
+// the pack are refused
+
+use std::io::{Cursor, Write};
+
+use bevy::math::Vec3;
+use mm_modchart_maker::modchart::{
+    Keyframe, ModChannel, ModEvent, Modchart,
+    pack::{ModPack, PackManifest, read_pack, write_pack},
+};
+use zip::{ZipWriter, write::SimpleFileOptions};
+
+#[test]
+fn packs_round_trip() {
+    let mut pack = ModPack {
+        manifest: PackManifest {
+            name: "Spin pack".to_string(),
+            author: "someone".to_string(),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    pack.modcharts.insert(
+        "spin".to_string(),
+        Modchart::new(vec![ModEvent::new(
+            ModChannel::PlayfieldRotation,
+            vec![
+                Keyframe::new(0, Vec3::ZERO),
+                Keyframe::new(1000, Vec3::new(0.0, 0.0, 3.1)),
+            ],
+        )]),
+    );
+    pack.skins
+        .insert("neon.ron".to_string(), b"(accent: (255, 0, 200))".to_vec());
+    pack.skins
+        .insert("glow.png".to_string(), vec![0x89, b'P', b'N', b'G']);
+
+    let mut zip = Cursor::new(Vec::new());
+    write_pack(&pack, &mut zip).unwrap();
+    zip.set_position(0);
+    let read = read_pack(zip).unwrap();
+
+    assert_eq!(read.manifest, pack.manifest);
+    assert_eq!(read.modcharts["spin"].events().len(), 1);
+    assert_eq!(
+        read.modcharts["spin"].events()[0].keyframes(),
+        pack.modcharts["spin"].events()[0].keyframes()
+    );
+    assert_eq!(read.skins, pack.skins);
+}
+
+#[test]
+fn refuses_unsafe_entries() {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default();
+    zip.start_file("pack.json", options).unwrap();
+    zip.write_all(br#"{ "name": "Sneaky" }"#).unwrap();
+    zip.start_file("skins/../../../autostart.sh", options)
+        .unwrap();
+    zip.write_all(b"echo hi").unwrap();
+
+    let mut zip = zip.finish().unwrap();
+    zip.set_position(0);
+    assert!(read_pack(zip).is_err());
+}
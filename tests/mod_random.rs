@@ -0,0 +1,74 @@
+// Random mod effects only depend on the seed, not on the order or how often they are sampled
+
+use std::io::Cursor;
+
+use bevy::math::Vec3;
+use mm_modchart_maker::modchart::{
+    Keyframe, ModChannel, ModEvent, Modchart, ScreenFrame,
+    random::{ModRandom, fnv1a},
+    script::{read_script, write_script},
+    sidecar::{read_modchart, write_modchart},
+};
+
+fn glitch() -> Modchart {
+    Modchart::new(vec![
+        ModEvent::new(
+            ModChannel::CameraOffset,
+            (0..20)
+                .map(|i| Keyframe::new(i * 100, Vec3::ZERO))
+                .collect(),
+        )
+        .with_jitter(Vec3::new(0.5, 0.5, 0.0)),
+    ])
+}
+
+#[test]
+fn seeds_replay_exactly() {
+    let modchart = glitch();
+    let frame = ScreenFrame::default();
+    let offset_at = |random: &ModRandom, millisecond: f64| {
+        modchart
+            .evaluate(millisecond, &frame, random)
+            .get(ModChannel::CameraOffset)
+    };
+
+    let run = ModRandom::with_seed(42);
+    let forward: Vec<Vec3> = (0..40).map(|i| offset_at(&run, i as f64 * 50.0)).collect();
+    let backward: Vec<Vec3> = (0..40)
+        .rev()
+        .map(|i| offset_at(&run, i as f64 * 50.0))
+        .collect();
+    assert_eq!(forward, backward.into_iter().rev().collect::<Vec<_>>());
+
+    assert!(forward.iter().any(|offset| *offset != Vec3::ZERO));
+    assert!(
+        forward
+            .iter()
+            .all(|offset| offset.x.abs() <= 0.5 && offset.z == 0.0)
+    );
+
+    let other = ModRandom::with_seed(43);
+    assert_ne!(offset_at(&run, 300.0), offset_at(&other, 300.0));
+}
+
+#[test]
+fn jitter_is_saved() {
+    let modchart = glitch();
+
+    let mut sidecar = Cursor::new(Vec::new());
+    write_modchart(&modchart, &mut sidecar).unwrap();
+    sidecar.set_position(0);
+    assert_eq!(read_modchart(sidecar).unwrap().events(), modchart.events());
+
+    let script = write_script(&modchart).unwrap();
+    assert!(script.contains("jitter"));
+    assert_eq!(read_script(&script).unwrap().events(), modchart.events());
+}
+
+#[test]
+fn fnv_matches_the_reference_values() {
+    // Cache file names are made from it, so it must never change between builds
+    assert_eq!(fnv1a(b""), 0xCBF2_9CE4_8422_2325);
+    assert_eq!(fnv1a(b"a"), 0xAF63_DC4C_8601_EC8C);
+    assert_eq!(fnv1a(b"foobar"), 0x8594_4171_F739_67E8);
+}
@@ -0,0 +1,60 @@
+// Scripts are edited by hand and converted back into sidecars, so a round trip can't lose or
+// reorder anything
+
+use bevy::math::Vec3;
+use mm_modchart_maker::modchart::{
+    CoordinateSpace, Easing, Keyframe, ModChannel, ModEvent, Modchart,
+    script::{read_script, write_script},
+};
+
+#[test]
+fn script_round_trip() {
+    let modchart = Modchart::new(vec![
+        ModEvent::new(
+            ModChannel::CameraOffset,
+            vec![
+                Keyframe::new(0, Vec3::ZERO),
+                Keyframe::new(1500, Vec3::new(0.25, -0.1, 3.0)).with_easing(Easing::QuadOut),
+            ],
+        )
+        .with_space(CoordinateSpace::Screen),
+        ModEvent::new(
+            ModChannel::PlayfieldScale,
+            vec![Keyframe::new(320, Vec3::splat(1.5)).with_easing(Easing::SineInOut)],
+        ),
+    ]);
+
+    let script = write_script(&modchart).unwrap();
+    let read = read_script(&script).unwrap();
+
+    assert_eq!(read.events(), modchart.events());
+}
+
+#[test]
+fn script_defaults_and_sorting() {
+    let script = "(
+        events: [
+            (
+                channel: PlayfieldRotation,
+                keyframes: [
+                    (at: 2000, value: (0.0, 0.0, 1.0)),
+                    (at: 1000, value: (0.0, 0.0, 0.0), easing: CubicIn),
+                ],
+            ),
+        ],
+    )";
+
+    let modchart = read_script(script).unwrap();
+    let event = &modchart.events()[0];
+
+    assert_eq!(event.space, CoordinateSpace::World);
+    assert_eq!(event.start(), Some(1000));
+    assert_eq!(event.keyframes()[0].easing, Easing::CubicIn);
+    assert_eq!(event.keyframes()[1].easing, Easing::Linear);
+}
+
+#[test]
+fn script_rejects_unknown_channels() {
+    let script = "(events: [(channel: Nowhere, keyframes: [])])";
+    assert!(read_script(script).is_err());
+}
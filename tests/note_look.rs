@@ -0,0 +1,53 @@
+// Note size and shape mods, and notes pulsing on their own beats
+
+use bevy::math::Vec3;
+use mm_modchart_maker::{
+    modchart::{Keyframe, ModChannel, ModEvent, Modchart, ScreenFrame, random::ModRandom},
+    player::notes::{NoteLook, beat_pulse},
+    theme::{Theme, shape::NoteShape},
+};
+
+#[test]
+fn pulses_follow_beats() {
+    // Biggest right as a beat passes, back to normal by the next one
+    assert!((beat_pulse(0.2, 1.999) - 1.2).abs() < 1e-2);
+    assert!((beat_pulse(0.2, 2.0) - 1.0).abs() < 1e-6);
+    assert!(beat_pulse(0.2, 1.5) < beat_pulse(0.2, 1.9));
+    assert_eq!(beat_pulse(0.0, 1.7), 1.0);
+}
+
+#[test]
+fn mods_swap_the_theme_shape() {
+    let theme = Theme {
+        note_shape: NoteShape::Panel,
+        ..Theme::default()
+    };
+    let modchart = Modchart::new(vec![
+        ModEvent::new(
+            ModChannel::NoteShape,
+            vec![Keyframe::new(1000, Vec3::new(1.0, 0.0, 0.0))],
+        ),
+        ModEvent::new(
+            ModChannel::NoteScale,
+            vec![
+                Keyframe::new(0, Vec3::ONE),
+                Keyframe::new(1000, Vec3::splat(2.0)),
+            ],
+        ),
+    ]);
+    let look_at = |millisecond: f64| {
+        let state = modchart.evaluate(millisecond, &ScreenFrame::default(), &ModRandom::default());
+        NoteLook::new(&theme, &state)
+    };
+
+    let before = look_at(500.0);
+    assert_eq!(before.shape, NoteShape::Panel);
+    assert_eq!(before.scale, Vec3::splat(1.5));
+
+    let after = look_at(1500.0);
+    assert_eq!(after.shape, NoteShape::Sphere);
+    assert_eq!(after.scale, Vec3::splat(2.0));
+
+    assert_eq!(NoteShape::from_value(2.4), Some(NoteShape::Panel));
+    assert_eq!(NoteShape::from_value(-1.0), None);
+}
@@ -0,0 +1,44 @@
+// Fields of custom objects are read by index, with errors saying which field was wrong
+
+use bevy::math::Vec2;
+use mm_modchart_maker::maps::parser::{ObjectDefinition, ObjectType};
+
+fn object() -> ObjectDefinition {
+    ObjectDefinition {
+        name: "custom_lyric".to_string(),
+        millisecond: 1500,
+        definitions: vec![
+            ObjectType::Vec2(Some(Vec2::new(1.0, 2.0))),
+            ObjectType::LongString(Some("hello".to_string())),
+            ObjectType::U32(Some(7)),
+            ObjectType::F32(None),
+        ],
+    }
+}
+
+#[test]
+fn fields_are_read_by_type() {
+    let object = object();
+
+    assert_eq!(object.get_vec2(0).unwrap(), Vec2::new(1.0, 2.0));
+    assert_eq!(object.get_string(1).unwrap(), "hello");
+    assert_eq!(object.get_u32(2).unwrap(), 7);
+    assert_eq!(
+        object.fields().collect::<Vec<_>>(),
+        [(0, "vec2"), (1, "long string"), (2, "u32"), (3, "f32")]
+    );
+}
+
+#[test]
+fn errors_name_the_field() {
+    let object = object();
+
+    let wrong = object.get_u32(0).unwrap_err().to_string();
+    assert!(wrong.contains("custom_lyric") && wrong.contains("vec2"));
+
+    let missing = object.get_string(9).unwrap_err().to_string();
+    assert!(missing.contains("no field 9"));
+
+    let empty = object.get_f32(3).unwrap_err().to_string();
+    assert!(empty.contains("no value"));
+}
@@ -0,0 +1,60 @@
+// Listing a map only reads its header, the sections after it are never touched
+
+use std::io::Cursor;
+
+use bevy::audio::AudioSource;
+use common::note;
+use mm_modchart_maker::maps::{
+    Map, PartialMap,
+    io::WriteOptions,
+    parser::{MapSerializer, SSPMSerializer},
+};
+
+mod common;
+
+fn map() -> Map {
+    Map {
+        length: 2000,
+        difficulty: 3,
+        difficulty_name: "Insane".to_string(),
+        mappers: vec!["one".to_string(), "two".to_string()],
+        audio: Some(AudioSource {
+            bytes: vec![7u8; 256].into(),
+        }),
+        cover: vec![3u8; 64],
+        notes: (0..4).map(|i| note(500 * i, 1.0, 1.0)).collect(),
+        ..common::map("partial")
+    }
+}
+
+#[test]
+fn meta_matches_full_read() {
+    let mut file = Cursor::new(Vec::new());
+    SSPMSerializer::serialize(&map(), &mut file).unwrap();
+
+    file.set_position(0);
+    let full = SSPMSerializer::deserialize(&mut file).unwrap();
+    file.set_position(0);
+    let meta = SSPMSerializer::deserialize_meta(&mut file).unwrap();
+
+    assert_eq!(meta, PartialMap::from(&full));
+    assert_eq!(meta.note_count, 4);
+    assert_eq!(meta.difficulty_name, "Insane");
+    assert!(meta.has_audio && meta.has_cover);
+}
+
+#[test]
+fn sections_are_not_read() {
+    let mut file = Cursor::new(Vec::new());
+    let sections =
+        SSPMSerializer::serialize_padded(&map(), &mut file, 0, WriteOptions::default()).unwrap();
+
+    // Everything from the first section on is cut off, a full read can't get past it
+    let mut header = file.into_inner();
+    header.truncate(sections.data_start() as usize);
+    assert!(SSPMSerializer::deserialize(Cursor::new(header.clone())).is_err());
+
+    let meta = SSPMSerializer::deserialize_meta(Cursor::new(header)).unwrap();
+    assert_eq!(meta.title, "Partial");
+    assert_eq!(meta.mappers, ["one", "two"]);
+}
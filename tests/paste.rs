@@ -0,0 +1,22 @@
+// Pasted notes keep their spacing from the playhead, without overflowing at the end of time
+
+use common::note;
+use mm_modchart_maker::editor::commands::{EditCommand, Selection};
+
+mod common;
+
+#[test]
+fn notes_past_the_end_of_time_are_left_out() {
+    let mut notes = Vec::new();
+    let mut selection = Selection::default();
+
+    let paste = EditCommand::Paste {
+        notes: vec![note(0, 0.0, 0.0), note(500, 1.0, 1.0), note(1000, 2.0, 2.0)],
+    };
+    paste
+        .plan(&notes, &selection, u32::MAX - 500)
+        .apply(&mut notes, &mut selection);
+
+    let times: Vec<u32> = notes.iter().map(|note| note.millisecond).collect();
+    assert_eq!(times, [u32::MAX - 500, u32::MAX]);
+}
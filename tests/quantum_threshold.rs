@@ -0,0 +1,68 @@
+// How far off a whole cell a note can be before it keeps its exact position in SSPM
+
+use bevy::math::Vec2;
+use common::note_at;
+use mm_modchart_maker::maps::{
+    Map,
+    export::grid_precision_warning,
+    io::DEFAULT_GRID_EPSILON,
+    parser::{is_quantum_value_within, is_quantum_within},
+};
+
+mod common;
+
+fn map(positions: &[Vec2]) -> Map {
+    Map {
+        notes: positions
+            .iter()
+            .enumerate()
+            .map(|(index, &position)| note_at(index as u32 * 100, position))
+            .collect(),
+        ..common::map("grid")
+    }
+}
+
+#[test]
+fn threshold_decides_grid_encoding() {
+    // Exactly at the default threshold keeps the position, however f32 happens to store it
+    for value in [0.005, 0.995, 1.005, 1.995, 2.005] {
+        assert!(
+            is_quantum_value_within(value, DEFAULT_GRID_EPSILON),
+            "{value}"
+        );
+    }
+    assert!(!is_quantum_value_within(0.0049, DEFAULT_GRID_EPSILON));
+    assert!(!is_quantum_value_within(1.0049, DEFAULT_GRID_EPSILON));
+    assert!(!is_quantum_value_within(0.999, DEFAULT_GRID_EPSILON));
+    assert!(!is_quantum_value_within(1.0, DEFAULT_GRID_EPSILON));
+
+    // A stricter threshold keeps smaller offsets, a looser one snaps bigger ones
+    assert!(is_quantum_value_within(0.999, 0.0005));
+    assert!(!is_quantum_within(Vec2::new(0.02, 1.0), 0.05));
+    assert!(is_quantum_within(Vec2::new(0.02, 1.0), 0.01));
+
+    // Off the grid is never snapped, however close to a cell
+    assert!(is_quantum_within(Vec2::new(3.0, 1.0), 0.5));
+}
+
+#[test]
+fn snapped_notes_are_listed() {
+    let positions = [
+        Vec2::new(1.0, 1.0),
+        Vec2::new(0.999, 2.0),
+        Vec2::new(0.5, 0.5),
+        Vec2::new(1.001, 0.0),
+    ];
+    let warning = grid_precision_warning(&map(&positions), DEFAULT_GRID_EPSILON).unwrap();
+    assert!(warning.starts_with("2 notes"));
+    assert!(warning.contains("100ms at (0.999, 2)"));
+    assert!(warning.contains("300ms at (1.001, 0)"));
+
+    assert_eq!(grid_precision_warning(&map(&positions), 0.0), None);
+    let many = map(&[Vec2::splat(0.999); 7]);
+    assert!(
+        grid_precision_warning(&many, DEFAULT_GRID_EPSILON)
+            .unwrap()
+            .ends_with("2 more")
+    );
+}
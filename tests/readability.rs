@@ -0,0 +1,57 @@
+// Notes hidden behind the ones hit just before them make a section unreadable
+
+use bevy::math::Vec2;
+use common::note_at;
+use mm_modchart_maker::{
+    analysis::readability::Readability, maps::objects::Note, theme::approach::ApproachProfile,
+};
+
+mod common;
+
+fn notes(gap_ms: u32, positions: &[Vec2]) -> Vec<Note> {
+    positions
+        .iter()
+        .enumerate()
+        .map(|(i, position)| note_at(1000 + i as u32 * gap_ms, *position))
+        .collect()
+}
+
+#[test]
+fn fast_stacks_are_flagged() {
+    let profile = ApproachProfile::default();
+
+    let spread = notes(100, &[Vec2::ZERO, Vec2::new(2.0, 0.0), Vec2::new(2.0, 2.0)]);
+    let readability = Readability::from_notes(&spread, 2000, &profile);
+    assert_eq!(readability.score(), 100.0);
+    assert!(readability.unreadable().is_empty());
+
+    // The same cell four times in a row, each note sits right behind the one before it
+    let stacked = notes(50, &[Vec2::ONE; 4]);
+    let readability = Readability::from_notes(&stacked, 2000, &profile);
+    assert!(readability.score() < 50.0);
+    let [section] = readability.unreadable() else {
+        panic!("expected one unreadable section");
+    };
+    assert_eq!(section.start_ms, 1000);
+
+    // Slow enough to read every note once the one in front is gone
+    let slow = notes(400, &[Vec2::ONE; 4]);
+    assert!(
+        Readability::from_notes(&slow, 3000, &profile)
+            .unreadable()
+            .is_empty()
+    );
+}
+
+#[test]
+fn late_fade_in_hurts_readability() {
+    let spread = notes(300, &[Vec2::ZERO, Vec2::new(2.0, 0.0), Vec2::new(2.0, 2.0)]);
+    let profile = ApproachProfile {
+        fade_in: 0.98,
+        ..Default::default()
+    };
+
+    let readability = Readability::from_notes(&spread, 2000, &profile);
+    assert!(readability.score() < 100.0);
+    assert!(!readability.unreadable().is_empty());
+}
@@ -0,0 +1,113 @@
+// Rhythm only exports keep every hit and pick lanes from timing alone
+
+use std::{env, fs};
+
+use common::{note, timing};
+use mm_modchart_maker::maps::{
+    Map,
+    objects::TimingPoint,
+    rhythm::{
+        Hit, RhythmFormat, TaikoHit, chart_frets, densities, export_rhythm, hits, taiko_hits,
+    },
+};
+
+mod common;
+
+fn map(times: &[u32]) -> Map {
+    Map {
+        length: times.last().copied().unwrap_or(0),
+        notes: times
+            .iter()
+            .enumerate()
+            .map(|(i, &millisecond)| note(millisecond, i as f32 % 3.0, 1.0))
+            .collect(),
+        timing_points: vec![timing(0, 120.0)],
+        ..common::map("rhythm")
+    }
+}
+
+#[test]
+fn taiko_follows_the_beat() {
+    let map = map(&[0, 0, 250, 500, 1250]);
+    let hits = hits(&map);
+    assert_eq!(
+        hits[0],
+        Hit {
+            millisecond: 0,
+            count: 2
+        }
+    );
+
+    assert_eq!(
+        taiko_hits(&map, &hits),
+        [
+            TaikoHit::Don { big: true },
+            TaikoHit::Kat { big: false },
+            TaikoHit::Don { big: false },
+            TaikoHit::Kat { big: false },
+        ]
+    );
+}
+
+#[test]
+fn frets_stay_in_range() {
+    // A slow start, a fast stream and a chord at the end
+    let mut times: Vec<u32> = (0..4).map(|i| i * 1000).collect();
+    times.extend((0..24).map(|i| 4000 + i * 125));
+    times.extend([7500, 7500, 7500]);
+
+    let map = map(&times);
+    let frets = chart_frets(&hits(&map));
+    assert_eq!(frets.len(), 29);
+
+    for (index, frets) in frets.iter().enumerate() {
+        assert!(frets.iter().all(|fret| *fret < 5), "{index}: {frets:?}");
+        if index < 4 {
+            assert!(frets.iter().all(|fret| *fret < 2), "{index}: {frets:?}");
+        }
+    }
+
+    // After the stream only two frets are in use, so the chord of three is cut down
+    assert_eq!(frets.last(), Some(&vec![0, 1]));
+    // The stream climbs instead of staying on one fret
+    assert!(frets[4..28].iter().any(|frets| frets[0] >= 3));
+}
+
+#[test]
+fn density_counts_the_second_around_each_hit() {
+    let map = map(&[0, 500, 1000, 1500, 2500, 5000]);
+    let hits = hits(&map);
+
+    // Windows near zero are cut short, so their hits count for more
+    assert_eq!(densities(&hits), [3.0, 4000.0 / 1500.0, 2.0, 2.0, 1.0, 0.5]);
+}
+
+#[test]
+fn chart_text_and_meters_are_written_for_clone_hero() {
+    let folder = env::temp_dir().join(format!("mm-rhythm-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+
+    let map = Map {
+        title: "Say \"Hi\"".to_string(),
+        artists: vec!["The \"Band\"".to_string()],
+        timing_points: vec![
+            timing(0, 120.0),
+            TimingPoint {
+                millisecond: 2000,
+                bpm: 120.0,
+                beats_per_measure: 3,
+            },
+        ],
+        ..map(&[0, 500])
+    };
+
+    let export = export_rhythm(&map, RhythmFormat::CloneHero, &folder, "{id}").unwrap();
+    let chart = fs::read_to_string(export.folder.join("notes.chart")).unwrap();
+
+    assert!(chart.contains("  Name = \"Say Hi\"\n"));
+    assert!(chart.contains("  Artist = \"The Band\"\n"));
+    assert!(chart.contains("  0 = TS 4\n  0 = B 120000\n"));
+    assert!(chart.contains("  768 = TS 3\n  768 = B 120000\n"));
+
+    let _ = fs::remove_dir_all(&folder);
+}
@@ -0,0 +1,128 @@
+// Spinning charts can be toned down without changing how they are evaluated while seeking
+
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use mm_modchart_maker::{
+    maps::Map,
+    modchart::{
+        Keyframe, ModChannel, ModEvent, Modchart, ScreenFrame,
+        comfort::{
+            ComfortSettings, RotationComfort, RotationGuard, guard_rotations, spin_at,
+            update_rotation_guard,
+        },
+        random::ModRandom,
+    },
+    settings::Settings,
+};
+
+// Half a turn in a tenth of a second
+fn roll() -> Modchart {
+    Modchart::new(vec![ModEvent::new(
+        ModChannel::PlayfieldRotation,
+        vec![
+            Keyframe::new(0, Vec3::ZERO),
+            Keyframe::new(100, Vec3::new(0.0, 0.0, PI)),
+        ],
+    )])
+}
+
+fn rotation_at(
+    modchart: &Modchart,
+    millisecond: f64,
+    spin: f32,
+    settings: &ComfortSettings,
+    guard: &RotationGuard,
+) -> Vec3 {
+    let random = ModRandom::default();
+    let mut state = modchart.evaluate(millisecond, &ScreenFrame::default(), &random);
+    guard_rotations(&mut state, millisecond, spin, settings, guard);
+    state.get(ModChannel::PlayfieldRotation)
+}
+
+#[test]
+fn turn_speed_is_capped() {
+    let modchart = roll();
+    let frame = ScreenFrame::default();
+    let random = ModRandom::default();
+    let settings = ComfortSettings {
+        rotation: RotationComfort::Capped,
+        max_turn_speed: 90.0,
+    };
+    let guard = RotationGuard::capped(&modchart, &frame, &random, 0.0, 90.0, 5000);
+
+    // A tenth of a second at 90 degrees per second, then it catches up in two seconds
+    let early = rotation_at(&modchart, 100.0, 0.0, &settings, &guard);
+    assert!((early.z - 9f32.to_radians()).abs() < 1e-3);
+    let late = rotation_at(&modchart, 2100.0, 0.0, &settings, &guard);
+    assert!((late.z - PI).abs() < 1e-3);
+
+    let full = ComfortSettings::default();
+    let charted = rotation_at(&modchart, 100.0, 0.0, &full, &RotationGuard::default());
+    assert!((charted.z - PI).abs() < 1e-5);
+}
+
+#[test]
+fn spin_becomes_a_tilt() {
+    let modchart = Modchart::default();
+    let full = ComfortSettings::default();
+    let spun = rotation_at(&modchart, 1500.0, 90.0, &full, &RotationGuard::default());
+    assert_eq!(spun, spin_at(90.0, 1500.0));
+
+    let tilt = ComfortSettings {
+        rotation: RotationComfort::Tilt,
+        ..Default::default()
+    };
+    for millisecond in (0..4000).step_by(250) {
+        let tilted = rotation_at(
+            &modchart,
+            millisecond as f64,
+            90.0,
+            &tilt,
+            &RotationGuard::default(),
+        );
+        assert!(tilted.abs().max_element() <= 0.1 + 1e-6);
+    }
+}
+
+#[test]
+fn capped_rotations_are_worked_out_in_the_background() {
+    let mut settings = Settings::default();
+    settings.comfort.rotation = RotationComfort::Capped;
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .init_resource::<Assets<Map>>()
+        .init_resource::<ScreenFrame>()
+        .init_resource::<ModRandom>()
+        .init_resource::<RotationGuard>()
+        .insert_resource(roll())
+        .insert_resource(settings)
+        .add_systems(Update, update_rotation_guard);
+
+    let ready = |app: &App| {
+        app.world()
+            .resource::<RotationGuard>()
+            .sample(ModChannel::PlayfieldRotation, 100.0)
+    };
+    for _ in 0..500 {
+        app.update();
+        if ready(&app).is_some() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(2));
+    }
+
+    let expected = RotationGuard::capped(
+        &roll(),
+        &ScreenFrame::default(),
+        &ModRandom::default(),
+        0.0,
+        90.0,
+        100,
+    );
+    assert_eq!(
+        ready(&app),
+        expected.sample(ModChannel::PlayfieldRotation, 100.0)
+    );
+}
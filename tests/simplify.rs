@@ -0,0 +1,74 @@
+// Simplified difficulties keep only the notes a beginner can read, close enough to reach
+
+use bevy::math::Vec2;
+use common::{note, timing};
+use mm_modchart_maker::maps::{
+    Map,
+    objects::Note,
+    simplify::{SimplifyOptions, SimplifyReport, simplify},
+};
+
+mod common;
+
+fn map(notes: Vec<Note>) -> Map {
+    Map {
+        difficulty: 3,
+        difficulty_name: "Hard".to_string(),
+        mappers: vec!["mapper".to_string()],
+        notes,
+        timing_points: vec![timing(0, 120.0)],
+        ..common::map("song")
+    }
+}
+
+#[test]
+fn thins_and_shortens() {
+    let map = map(vec![
+        note(0, 0.0, 0.0),
+        note(0, 1.0, 1.0),
+        note(125, 2.0, 0.0),
+        note(250, 2.0, 2.0),
+        note(500, 1.5, 0.5),
+    ]);
+
+    let (simplified, report) = simplify(&map, &SimplifyOptions::default());
+    assert_eq!(
+        report,
+        SimplifyReport {
+            kept: 3,
+            removed: 2,
+            shortened_jumps: 1,
+            snapped: 1,
+        }
+    );
+    assert_eq!(
+        simplified
+            .notes
+            .iter()
+            .map(|n| (n.millisecond, n.position))
+            .collect::<Vec<_>>(),
+        [
+            (0, Vec2::ZERO),
+            (250, Vec2::ONE),
+            (500, Vec2::new(2.0, 1.0)),
+        ]
+    );
+
+    // Kept notes are the same notes, not new ones
+    assert_eq!(simplified.notes[0].id, map.notes[0].id);
+}
+
+#[test]
+fn becomes_a_new_difficulty() {
+    let map = map(vec![note(0, 1.0, 1.0)]);
+    let options = SimplifyOptions {
+        difficulty_name: "Very easy!".to_string(),
+        ..Default::default()
+    };
+
+    let (simplified, _) = simplify(&map, &options);
+    assert_eq!(simplified.id, "song_very_easy");
+    assert_eq!(simplified.difficulty, 2);
+    assert_eq!(simplified.difficulty_name, "Very easy!");
+    assert_eq!(simplified.timing_points, map.timing_points);
+}
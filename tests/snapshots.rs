@@ -0,0 +1,71 @@
+// Snapshots restore the map they were taken of, and diffs follow notes by id
+
+use std::{env, fs};
+
+use common::{note, timing};
+use mm_modchart_maker::maps::{
+    Map,
+    snapshot::{create_snapshot, delete_snapshot, diff_maps, list_snapshots, read_snapshot},
+};
+
+mod common;
+
+#[test]
+fn snapshots_round_trip() {
+    let folder = env::temp_dir().join(format!("mm-snapshots-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+
+    let map = Map {
+        notes: vec![note(100, 0.0, 0.0), note(400, 2.0, 1.0)],
+        timing_points: vec![timing(0, 120.0)],
+        artists: vec!["Someone".to_string()],
+        mappers: vec!["Mapper".to_string()],
+        ..common::map("song")
+    };
+
+    let first = create_snapshot(&map, &folder, "v1 ranked submission").unwrap();
+    let second = create_snapshot(&map, &folder, "pre-remap").unwrap();
+    assert_ne!(first.path, second.path);
+
+    let snapshots = list_snapshots(&folder);
+    let names: Vec<&str> = snapshots.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(snapshots.len(), 2);
+    assert!(names.contains(&"v1 ranked submission") && names.contains(&"pre-remap"));
+    assert!(snapshots.iter().all(|snapshot| snapshot.notes == 2));
+
+    let restored = read_snapshot(&first).unwrap();
+    assert_eq!(restored.notes, map.notes);
+    assert_eq!(restored.timing_points, map.timing_points);
+    assert_eq!(restored.artists, map.artists);
+    assert!(diff_maps(&restored, &map).is_empty());
+
+    delete_snapshot(&first).unwrap();
+    let left = list_snapshots(&folder);
+    let _ = fs::remove_dir_all(&folder);
+
+    assert_eq!(left.len(), 1);
+    assert_eq!(left[0].name, "pre-remap");
+}
+
+#[test]
+fn diffs_count_added_removed_and_moved_notes() {
+    let before = Map {
+        notes: vec![
+            note(100, 0.0, 0.0),
+            note(200, 1.0, 1.0),
+            note(300, 2.0, 2.0),
+        ],
+        ..common::map("song")
+    };
+
+    let mut after = before.clone();
+    after.notes.remove(0);
+    after.notes[0].millisecond = 250;
+    after.notes.push(note(500, 1.0, 0.0));
+    after.title = "Renamed".to_string();
+
+    let diff = diff_maps(&before, &after);
+
+    assert_eq!((diff.added, diff.removed, diff.moved), (1, 1, 1));
+    assert_eq!(diff.changes, vec!["Title: Song -> Renamed".to_string()]);
+}
@@ -0,0 +1,72 @@
+// Streamed audio is swapped for the decoded samples without the song jumping back to the start
+
+use std::sync::Arc;
+
+use bevy::{audio::AudioSource, prelude::*};
+use mm_modchart_maker::{
+    jukebox::{
+        DecodedAudio, STREAM_THRESHOLD, decode_current_audio,
+        output::AudioOutput,
+        pcm::Pcm,
+        song::{Song, SongAudio, SongPlayer, follow_clock},
+    },
+    maps::{CurrentMap, Map},
+    player::{SimulationState, playback::PlaybackClock},
+    settings::Settings,
+};
+
+mod common;
+
+fn playing(app: &mut App) -> Vec<(bool, f64)> {
+    let mut players = app.world_mut().query::<&AudioPlayer<Song>>();
+    let songs = app.world().resource::<Assets<Song>>();
+
+    players
+        .iter(app.world())
+        .filter_map(|player| songs.get(&player.0))
+        .map(|song| {
+            (
+                matches!(song.audio(), SongAudio::Decoded(_)),
+                song.start_ms(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn decoded_audio_continues_from_the_clock() {
+    let map = Map {
+        audio: Some(AudioSource {
+            bytes: vec![0u8; STREAM_THRESHOLD + 1].into(),
+        }),
+        length: 60_000,
+        ..common::map("long")
+    };
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .init_resource::<Assets<Map>>()
+        .init_resource::<Assets<Song>>()
+        .init_resource::<SongPlayer>()
+        .init_resource::<DecodedAudio>()
+        .init_resource::<AudioOutput>()
+        .init_resource::<PlaybackClock>()
+        .insert_resource(Settings::default())
+        .insert_resource(State::new(SimulationState::Running))
+        .add_systems(Update, (decode_current_audio, follow_clock).chain());
+
+    let handle = app.world_mut().resource_mut::<Assets<Map>>().add(map);
+    app.insert_resource(CurrentMap(handle));
+    let offset = app.world().resource::<Settings>().audio_offset_ms as f64;
+
+    app.update();
+    assert_eq!(playing(&mut app), vec![(false, offset.max(0.0))]);
+
+    app.world_mut().resource_mut::<PlaybackClock>().millisecond = 5000.0;
+    app.world_mut().resource_mut::<DecodedAudio>().pcm =
+        Some(Arc::new(Pcm::silence(44_100, 2, 60_000.0)));
+    app.update();
+
+    assert_eq!(playing(&mut app), vec![(true, 5000.0 + offset)]);
+    assert_eq!(app.world().resource::<PlaybackClock>().millisecond, 5000.0);
+}
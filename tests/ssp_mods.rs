@@ -0,0 +1,75 @@
+// Mod objects in maps with the has_mod flag are read into mod events, but never written back
+
+use std::io::Cursor;
+
+use bevy::math::Vec3;
+use mm_modchart_maker::{
+    maps::{
+        Map, MapFormat,
+        io::WriteOptions,
+        parser::{MapSerializer, ObjectDefinition, ObjectType, SSPMSerializer},
+    },
+    modchart::{Keyframe, ModChannel, ModEvent, ssp::take_mod_events},
+};
+
+mod common;
+
+fn object(name: &str, millisecond: u32) -> ObjectDefinition {
+    ObjectDefinition {
+        name: name.to_string(),
+        millisecond,
+        definitions: vec![
+            ObjectType::F32(Some(1.0)),
+            ObjectType::F32(Some(2.0)),
+            ObjectType::F32(Some(3.0)),
+        ],
+    }
+}
+
+#[test]
+fn known_objects_become_events() {
+    let mut objects = vec![
+        object("mm_mod_camera_offset", 500),
+        object("mm_mod_camera_offset", 100),
+        object("someone_elses_object", 200),
+    ];
+
+    let events = take_mod_events(&mut objects);
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].channel, ModChannel::CameraOffset);
+    let times: Vec<u32> = events[0]
+        .keyframes()
+        .iter()
+        .map(|k| k.millisecond)
+        .collect();
+    assert_eq!(times, vec![100, 500]);
+    assert_eq!(events[0].keyframes()[0].value, Vec3::new(1.0, 2.0, 3.0));
+
+    assert_eq!(objects.len(), 1);
+    assert_eq!(objects[0].name, "someone_elses_object");
+}
+
+#[test]
+fn mods_are_left_out_of_the_map_file() {
+    let map = Map {
+        mod_events: vec![ModEvent::new(
+            ModChannel::PlayfieldScale,
+            vec![Keyframe::new(0, Vec3::ONE)],
+        )],
+        ..common::map("mods")
+    };
+
+    let mut file = Cursor::new(Vec::new());
+    SSPMSerializer::serialize(&map, &mut file).unwrap();
+    let read = SSPMSerializer::deserialize(Cursor::new(file.into_inner())).unwrap();
+
+    assert!(read.mod_events.is_empty());
+    assert!(read.objects.is_empty());
+    assert!(
+        MapFormat::SSPM
+            .warnings(&map, WriteOptions::default())
+            .iter()
+            .any(|warning| warning.contains("modchart file"))
+    );
+}
@@ -0,0 +1,81 @@
+// Notes are written byte for byte the way Sound Space Plus stores them, and a map read from a file
+// is written back unchanged
+
+use std::io::Cursor;
+
+use bevy::math::Vec2;
+use common::note;
+use mm_modchart_maker::maps::{
+    Map,
+    parser::{MapSerializer, SSPMSerializer},
+};
+
+mod common;
+
+// Where the object data section's offset is stored in the header, its length follows right after
+const OBJECT_DATA: usize = 112;
+
+fn map() -> Map {
+    Map {
+        notes: vec![
+            note(0, 0.0, 0.0),
+            note(250, 1.0, 2.0),
+            note(500, 2.0, 1.0),
+            note(750, 0.5, 1.25),
+        ],
+        ..common::map("golden")
+    }
+}
+
+fn written(map: &Map) -> Vec<u8> {
+    let mut file = Cursor::new(Vec::new());
+    SSPMSerializer::serialize(map, &mut file).unwrap();
+    file.into_inner()
+}
+
+fn object_data(file: &[u8]) -> &[u8] {
+    let read = |at: usize| u64::from_le_bytes(file[at..at + 8].try_into().unwrap()) as usize;
+    let (offset, length) = (read(OBJECT_DATA), read(OBJECT_DATA + 8));
+    &file[offset..offset + length]
+}
+
+#[test]
+fn notes_are_stored_as_their_cells() {
+    let expected: Vec<u8> = [
+        // Time, definition, quantum flag, then the cells
+        &[0, 0, 0, 0, 0x00, 0x00, 0, 0][..],
+        &[250, 0, 0, 0, 0x00, 0x00, 1, 2],
+        &[244, 1, 0, 0, 0x00, 0x00, 2, 1],
+        // Off the grid, two floats instead
+        &[238, 2, 0, 0, 0x00, 0x01],
+        &0.5f32.to_le_bytes(),
+        &1.25f32.to_le_bytes(),
+    ]
+    .concat();
+
+    assert_eq!(object_data(&written(&map())), &expected[..]);
+}
+
+#[test]
+fn files_are_read_as_the_game_wrote_them() {
+    let read = SSPMSerializer::deserialize(Cursor::new(written(&map()))).unwrap();
+    let positions: Vec<Vec2> = read.notes.iter().map(|note| note.position).collect();
+
+    assert_eq!(
+        positions,
+        vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 2.0),
+            Vec2::new(2.0, 1.0),
+            Vec2::new(0.5, 1.25),
+        ]
+    );
+}
+
+#[test]
+fn saving_a_read_map_changes_nothing() {
+    let file = written(&map());
+    let read = SSPMSerializer::deserialize(Cursor::new(file.clone())).unwrap();
+
+    assert_eq!(written(&read), file);
+}
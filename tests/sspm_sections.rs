@@ -0,0 +1,96 @@
+// Section offsets in the SSPM header are checked before anything is read from them
+
+use std::io::Cursor;
+
+use bevy::audio::AudioSource;
+use common::note;
+use mm_modchart_maker::maps::{
+    Map,
+    parser::{MapSerializer, SSPMSerializer},
+};
+
+mod common;
+
+// Where each section's offset is stored in the header, its length follows right after
+const AUDIO: usize = 64;
+const COVER: usize = 80;
+const OBJECT_DATA: usize = 112;
+
+fn map(audio: bool) -> Map {
+    Map {
+        mappers: vec!["mapper".to_string()],
+        audio: audio.then(|| AudioSource {
+            bytes: vec![7u8; 64].into(),
+        }),
+        cover: vec![3u8; 32],
+        notes: vec![note(500, 1.0, 1.0)],
+        ..common::map("sections")
+    }
+}
+
+fn written(map: &Map) -> Vec<u8> {
+    let mut file = Cursor::new(Vec::new());
+    SSPMSerializer::serialize(map, &mut file).unwrap();
+    file.into_inner()
+}
+
+fn section(file: &[u8], at: usize) -> (u64, u64) {
+    let read = |at: usize| u64::from_le_bytes(file[at..at + 8].try_into().unwrap());
+    (read(at), read(at + 8))
+}
+
+fn set_section(file: &mut [u8], at: usize, (offset, length): (u64, u64)) {
+    file[at..at + 8].copy_from_slice(&offset.to_le_bytes());
+    file[at + 8..at + 16].copy_from_slice(&length.to_le_bytes());
+}
+
+fn error(file: Vec<u8>) -> String {
+    SSPMSerializer::deserialize(Cursor::new(file))
+        .unwrap_err()
+        .to_string()
+}
+
+#[test]
+fn missing_audio_is_not_a_section() {
+    let file = written(&map(false));
+    assert_eq!(section(&file, AUDIO), (0, 0));
+
+    let read = SSPMSerializer::deserialize(Cursor::new(file)).unwrap();
+    assert!(read.audio.is_none());
+    assert_eq!(read.cover, map(false).cover);
+    assert!(read.load_warnings.is_empty());
+}
+
+#[test]
+fn bad_sections_are_named() {
+    let good = written(&map(true));
+    let audio = section(&good, AUDIO);
+    let cover = section(&good, COVER);
+
+    let mut file = good.clone();
+    set_section(&mut file, COVER, (audio.0 + 8, cover.1));
+    let message = error(file);
+    assert!(message.contains("audio section"), "{message}");
+    assert!(message.contains("cover section"), "{message}");
+
+    let mut file = good.clone();
+    set_section(&mut file, AUDIO, (4, audio.1));
+    let message = error(file);
+    assert!(
+        message.contains("audio section at 4 overlaps the header"),
+        "{message}"
+    );
+
+    let mut file = good.clone();
+    set_section(&mut file, OBJECT_DATA, (audio.0, u64::MAX));
+    let message = error(file);
+    assert!(message.contains("object data section"), "{message}");
+    assert!(message.contains("runs past the end"), "{message}");
+
+    // An empty audio section is read as no audio instead of failing
+    let mut file = good;
+    set_section(&mut file, AUDIO, (0, 0));
+    let read = SSPMSerializer::deserialize(Cursor::new(file)).unwrap();
+    assert!(read.audio.is_none());
+    assert_eq!(read.load_warnings.len(), 1);
+}
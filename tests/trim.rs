@@ -0,0 +1,133 @@
+// Trimming keeps what's inside the range, inclusive at both ends, rebased to start at zero
+
+use common::{note, timing};
+use mm_modchart_maker::maps::{
+    Map,
+    objects::{Lyric, LyricStyle, SpeedChange},
+    trim::trim,
+};
+
+mod common;
+
+fn times(map: &Map) -> Vec<u32> {
+    map.notes.iter().map(|note| note.millisecond).collect()
+}
+
+#[test]
+fn empty_ranges_are_rejected() {
+    let map = common::map("song");
+
+    assert!(trim(&map, 500, 500).is_err());
+    assert!(trim(&map, 600, 500).is_err());
+}
+
+#[test]
+fn notes_on_the_bounds_are_kept() {
+    let map = Map {
+        notes: vec![
+            note(999, 0.0, 0.0),
+            note(1000, 0.0, 0.0),
+            note(1500, 1.0, 1.0),
+            note(2000, 2.0, 2.0),
+            note(2001, 2.0, 2.0),
+        ],
+        length: 3000,
+        ..common::map("song")
+    };
+
+    let trimmed = trim(&map, 1000, 2000).unwrap();
+
+    assert_eq!(times(&trimmed), vec![0, 500, 1000]);
+    assert_eq!(trimmed.length, 1000);
+}
+
+#[test]
+fn the_active_timing_point_moves_to_its_first_beat() {
+    // 120 bpm from 100ms puts beats at 100, 600, 1100...
+    let map = Map {
+        timing_points: vec![timing(100, 120.0)],
+        length: 3000,
+        ..common::map("song")
+    };
+
+    let trimmed = trim(&map, 1000, 2000).unwrap();
+
+    assert_eq!(trimmed.timing_points.len(), 1);
+    assert_eq!(trimmed.timing_points[0].millisecond, 100);
+    assert_eq!(trimmed.timing_points[0].bpm, 120.0);
+}
+
+#[test]
+fn the_active_speed_holds_from_zero() {
+    let map = Map {
+        speed_changes: vec![
+            SpeedChange {
+                millisecond: 200,
+                multiplier: 1.5,
+            },
+            SpeedChange {
+                millisecond: 1500,
+                multiplier: 0.5,
+            },
+        ],
+        length: 3000,
+        ..common::map("song")
+    };
+
+    let trimmed = trim(&map, 1000, 2000).unwrap();
+    let speeds: Vec<(u32, f32)> = trimmed
+        .speed_changes
+        .iter()
+        .map(|change| (change.millisecond, change.multiplier))
+        .collect();
+
+    assert_eq!(speeds, vec![(0, 1.5), (500, 0.5)]);
+}
+
+#[test]
+fn lyrics_are_cut_at_the_end() {
+    let map = Map {
+        lyrics: vec![Lyric {
+            millisecond: 1800,
+            duration_ms: 1000,
+            text: "Hold".to_string(),
+            style: LyricStyle::Subtitle,
+        }],
+        length: 3000,
+        ..common::map("song")
+    };
+
+    let trimmed = trim(&map, 1000, 2000).unwrap();
+
+    assert_eq!(trimmed.lyrics[0].millisecond, 800);
+    assert_eq!(trimmed.lyrics[0].duration_ms, 200);
+}
+
+#[test]
+fn ranges_at_the_end_of_time_do_not_overflow() {
+    let map = Map {
+        notes: vec![note(u32::MAX, 0.0, 0.0)],
+        timing_points: vec![timing(0, 120.0)],
+        length: u32::MAX,
+        ..common::map("song")
+    };
+
+    let trimmed = trim(&map, u32::MAX - 1, u32::MAX).unwrap();
+
+    assert_eq!(times(&trimmed), vec![1]);
+    assert_eq!(trimmed.length, 1);
+}
+
+#[test]
+fn the_range_is_named_in_the_difficulty() {
+    let map = Map {
+        difficulty_name: "Hard".to_string(),
+        length: 200_000,
+        ..common::map("song")
+    };
+
+    let trimmed = trim(&map, 65_000, 125_500).unwrap();
+
+    assert_eq!(trimmed.difficulty_name, "Hard (1:05 - 2:05)");
+    assert_eq!(trimmed.id, "song_65000-125500");
+}
@@ -0,0 +1,118 @@
+// Release tags are compared as versions, and the build for this system is picked from the assets
+
+use mm_modchart_maker::settings::{
+    sha256::{self, Sha256},
+    updates::{Release, UpdateSettings, parse_version},
+};
+
+#[test]
+fn tags_compare_as_versions() {
+    assert_eq!(parse_version("v1.2.3"), Some((1, 2, 3)));
+    assert_eq!(parse_version("0.10"), Some((0, 10, 0)));
+    assert_eq!(parse_version("2.0.0-beta.1"), Some((2, 0, 0)));
+    assert_eq!(parse_version("nightly"), None);
+
+    let release = |tag: &str| Release {
+        tag_name: tag.to_string(),
+        name: String::new(),
+        html_url: String::new(),
+        body: String::new(),
+        assets: Vec::new(),
+    };
+    assert!(release("v0.10.0").is_newer_than("0.9.3"));
+    assert!(!release("v0.1.0").is_newer_than("0.1.0"));
+    assert!(!release("latest").is_newer_than("0.1.0"));
+}
+
+#[test]
+fn picks_this_systems_build() {
+    let release: Release = serde_json::from_str(
+        r#"{
+            "tag_name": "v9.0.0",
+            "html_url": "https://example.com/releases/v9.0.0",
+            "assets": [
+                { "name": "mm-modchart-maker-linux.tar.gz.sha256", "browser_download_url": "https://example.com/1" },
+                { "name": "mm-modchart-maker-linux.tar.gz", "browser_download_url": "https://example.com/2", "size": 10 },
+                { "name": "mm-modchart-maker-macos.zip", "browser_download_url": "https://example.com/3" },
+                { "name": "mm-modchart-maker-windows.zip", "browser_download_url": "https://example.com/4" }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let expected = if cfg!(target_os = "windows") {
+        "mm-modchart-maker-windows.zip"
+    } else if cfg!(target_os = "macos") {
+        "mm-modchart-maker-macos.zip"
+    } else {
+        "mm-modchart-maker-linux.tar.gz"
+    };
+    assert_eq!(
+        release
+            .asset_for_platform()
+            .map(|asset| asset.name.as_str()),
+        Some(expected)
+    );
+}
+
+#[test]
+fn not_checked_unless_turned_on() {
+    assert!(!UpdateSettings::default().check_on_startup);
+}
+
+#[test]
+fn downloads_are_hashed_with_sha256() {
+    assert_eq!(
+        sha256::to_hex(&sha256::hash(b"")),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+    assert_eq!(
+        sha256::to_hex(&sha256::hash(b"abc")),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+
+    // Fed in uneven pieces across block boundaries, like a file read in chunks
+    let million = vec![b'a'; 1_000_000];
+    let mut hasher = Sha256::default();
+    for piece in million.chunks(4099) {
+        hasher.update(piece);
+    }
+    assert_eq!(
+        sha256::to_hex(&hasher.finish()),
+        "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+    );
+}
+
+#[test]
+fn checksums_are_found_for_the_build() {
+    let hash = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+    let expected = sha256::hash(b"abc");
+
+    assert_eq!(sha256::parse_checksum(hash, "build.zip"), Some(expected));
+    assert_eq!(
+        sha256::parse_checksum(&format!("{hash} *build.zip\n"), "build.zip"),
+        Some(expected)
+    );
+    let listing = format!("{}  other.zip\n{hash}  build.zip\n", "0".repeat(64));
+    assert_eq!(
+        sha256::parse_checksum(&listing, "build.zip"),
+        Some(expected)
+    );
+    assert_eq!(sha256::parse_checksum(&listing, "missing.zip"), None);
+    assert_eq!(sha256::parse_checksum("not a hash", "build.zip"), None);
+
+    let release: Release = serde_json::from_str(
+        r#"{
+            "tag_name": "v9.0.0",
+            "assets": [
+                { "name": "build.zip", "browser_download_url": "https://example.com/1" },
+                { "name": "BUILD.zip.sha256", "browser_download_url": "https://example.com/2" },
+                { "name": "unsigned.zip", "browser_download_url": "https://example.com/3" }
+            ]
+        }"#,
+    )
+    .unwrap();
+    let checksum = release.checksum_for(&release.assets[0]).unwrap();
+    assert_eq!(checksum.browser_download_url, "https://example.com/2");
+    assert!(release.checksum_for(&release.assets[2]).is_none());
+}